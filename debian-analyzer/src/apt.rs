@@ -0,0 +1,485 @@
+//! Reading apt Packages and Sources indexes.
+//!
+//! Indexes can be read from the local apt lists directory, or downloaded from
+//! a mirror for a specific release. They are loaded into an [`AptIndex`] which
+//! can be used to look up versions and to check whether relations can be
+//! satisfied from the archive.
+use debian_control::lossless::relations::{Entry, Relation, Relations};
+use debian_control::relations::VersionConstraint;
+use debversion::Version;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+/// Default Debian mirror
+pub const DEFAULT_MIRROR: &str = "https://deb.debian.org/debian";
+
+/// Default directory apt stores downloaded lists in
+pub const DEFAULT_LISTS_DIR: &str = "/var/lib/apt/lists";
+
+#[derive(Debug)]
+/// An error that can occur while loading an apt index
+pub enum Error {
+    /// An I/O error occurred while reading an index
+    Io(std::io::Error),
+
+    /// An error occurred while downloading an index
+    Download(String, reqwest::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Download(url, e) => write!(f, "Error downloading {}: {}", url, e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// A binary package in a Packages index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryPackage {
+    /// Name of the package
+    pub name: String,
+
+    /// Version of the package
+    pub version: Version,
+
+    /// Architecture of the package
+    pub architecture: String,
+
+    /// Name of the source package, if different from the binary package
+    pub source: Option<String>,
+
+    /// Virtual packages provided, with their version if any
+    pub provides: Vec<(String, Option<Version>)>,
+
+    /// Contents of the Pre-Depends field, if any
+    pub pre_depends: Option<String>,
+
+    /// Contents of the Depends field, if any
+    pub depends: Option<String>,
+}
+
+/// A source package in a Sources index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcePackage {
+    /// Name of the package
+    pub name: String,
+
+    /// Version of the package
+    pub version: Version,
+
+    /// Names of the binary packages built from this source package
+    pub binaries: Vec<String>,
+}
+
+/// Packages and sources loaded from one or more apt indexes
+#[derive(Debug, Default, Clone)]
+pub struct AptIndex {
+    binaries: HashMap<String, Vec<BinaryPackage>>,
+    sources: HashMap<String, Vec<SourcePackage>>,
+    providers: HashMap<String, Vec<(String, Option<Version>)>>,
+}
+
+/// Open an index file, decompressing it based on its extension.
+fn open_index(path: &Path) -> Result<Box<dyn Read>, std::io::Error> {
+    let f = std::fs::File::open(path)?;
+    Ok(match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => Box::new(flate2::read::GzDecoder::new(f)),
+        Some("xz") => Box::new(xz2::read::XzDecoder::new(f)),
+        _ => Box::new(f),
+    })
+}
+
+fn parse_provides(text: &str) -> Vec<(String, Option<Version>)> {
+    let (relations, _) = Relations::parse_relaxed(text, true);
+    relations
+        .entries()
+        .flat_map(|e| e.relations().collect::<Vec<_>>())
+        .filter_map(|r| {
+            let name = r.try_name()?;
+            let version = match r.version() {
+                Some((VersionConstraint::Equal, v)) => Some(v),
+                _ => None,
+            };
+            Some((name, version))
+        })
+        .collect()
+}
+
+impl AptIndex {
+    /// Create a new, empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the packages from a Packages file.
+    ///
+    /// # Arguments
+    /// * `r` - Reader for the uncompressed contents of the Packages file
+    pub fn add_packages<R: Read>(&mut self, r: R) -> Result<(), Error> {
+        let (deb822, errors) = deb822_lossless::Deb822::read_relaxed(r)?;
+        for error in errors {
+            log::debug!("Error parsing Packages file: {}", error);
+        }
+        for para in deb822.paragraphs() {
+            let (Some(name), Some(version)) = (para.get("Package"), para.get("Version")) else {
+                continue;
+            };
+            let Ok(version) = version.parse::<Version>() else {
+                log::debug!("Invalid version {} for {}", version, name);
+                continue;
+            };
+            let source = para.get("Source").map(|s| {
+                // The source version is appended in parentheses if it differs.
+                s.split_whitespace().next().unwrap_or_default().to_string()
+            });
+            let provides = para
+                .get("Provides")
+                .map(|p| parse_provides(&p))
+                .unwrap_or_default();
+            for (virtual_name, virtual_version) in &provides {
+                self.providers
+                    .entry(virtual_name.clone())
+                    .or_default()
+                    .push((name.clone(), virtual_version.clone()));
+            }
+            self.binaries
+                .entry(name.clone())
+                .or_default()
+                .push(BinaryPackage {
+                    name,
+                    version,
+                    architecture: para.get("Architecture").unwrap_or_default(),
+                    source,
+                    provides,
+                    pre_depends: para.get("Pre-Depends"),
+                    depends: para.get("Depends"),
+                });
+        }
+        Ok(())
+    }
+
+    /// Add the source packages from a Sources file.
+    ///
+    /// # Arguments
+    /// * `r` - Reader for the uncompressed contents of the Sources file
+    pub fn add_sources<R: Read>(&mut self, r: R) -> Result<(), Error> {
+        let (deb822, errors) = deb822_lossless::Deb822::read_relaxed(r)?;
+        for error in errors {
+            log::debug!("Error parsing Sources file: {}", error);
+        }
+        for para in deb822.paragraphs() {
+            let (Some(name), Some(version)) = (para.get("Package"), para.get("Version")) else {
+                continue;
+            };
+            let Ok(version) = version.parse::<Version>() else {
+                log::debug!("Invalid version {} for {}", version, name);
+                continue;
+            };
+            let binaries = para
+                .get("Binary")
+                .map(|b| {
+                    b.split(',')
+                        .map(|b| b.trim().to_string())
+                        .filter(|b| !b.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default();
+            self.sources
+                .entry(name.clone())
+                .or_default()
+                .push(SourcePackage {
+                    name,
+                    version,
+                    binaries,
+                });
+        }
+        Ok(())
+    }
+
+    /// Add the contents of an index file on disk.
+    ///
+    /// Files ending in `.gz` or `.xz` are decompressed. Whether the file is a
+    /// Sources or Packages file is determined from its name.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the index file
+    pub fn add_file(&mut self, path: &Path) -> Result<(), Error> {
+        let r = open_index(path)?;
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if name.contains("Sources") {
+            self.add_sources(r)
+        } else {
+            self.add_packages(r)
+        }
+    }
+
+    /// Load the indexes from an apt lists directory.
+    ///
+    /// # Arguments
+    /// * `dir` - The lists directory, usually [`DEFAULT_LISTS_DIR`]
+    pub fn from_lists_dir(dir: &Path) -> Result<Self, Error> {
+        let mut index = Self::new();
+        let mut paths = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                let Some(name) = p.file_name().and_then(|n| n.to_str()) else {
+                    return false;
+                };
+                let name = name.trim_end_matches(".gz").trim_end_matches(".xz");
+                name.ends_with("_Packages") || name.ends_with("_Sources")
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            log::debug!("Reading {}", path.display());
+            index.add_file(&path)?;
+        }
+        Ok(index)
+    }
+
+    /// Download the indexes for a release from a mirror.
+    ///
+    /// # Arguments
+    /// * `mirror` - Base URL of the mirror, e.g. [`DEFAULT_MIRROR`]
+    /// * `release` - Name of the release, e.g. "sid"
+    /// * `components` - Components to download, e.g. `["main"]`
+    /// * `architecture` - Architecture to download Packages for, or `None` to
+    ///   only download Sources
+    pub fn download(
+        mirror: &str,
+        release: &str,
+        components: &[&str],
+        architecture: Option<&str>,
+    ) -> Result<Self, Error> {
+        let mut index = Self::new();
+        let mirror = mirror.trim_end_matches('/');
+        for component in components {
+            let base = format!("{}/dists/{}/{}", mirror, release, component);
+            let url = format!("{}/source/Sources.xz", base);
+            index.add_sources(xz2::read::XzDecoder::new(fetch(&url)?))?;
+            if let Some(arch) = architecture {
+                let url = format!("{}/binary-{}/Packages.xz", base, arch);
+                index.add_packages(xz2::read::XzDecoder::new(fetch(&url)?))?;
+            }
+        }
+        Ok(index)
+    }
+
+    /// All versions of a binary package in the index.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the binary package
+    pub fn binary_packages(&self, name: &str) -> &[BinaryPackage] {
+        self.binaries.get(name).map_or(&[], |v| v.as_slice())
+    }
+
+    /// All versions of a source package in the index.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the source package
+    pub fn source_packages(&self, name: &str) -> &[SourcePackage] {
+        self.sources.get(name).map_or(&[], |v| v.as_slice())
+    }
+
+    /// The highest version of a binary package in the index.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the binary package
+    pub fn binary_version(&self, name: &str) -> Option<&Version> {
+        self.binary_packages(name).iter().map(|p| &p.version).max()
+    }
+
+    /// The highest version of a source package in the index.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the source package
+    pub fn source_version(&self, name: &str) -> Option<&Version> {
+        self.source_packages(name).iter().map(|p| &p.version).max()
+    }
+
+    /// Names of the binary packages that provide a virtual package.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the virtual package
+    pub fn providers(&self, name: &str) -> Vec<&str> {
+        self.providers
+            .get(name)
+            .map(|p| p.iter().map(|(n, _)| n.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// The binary packages that can satisfy a single relation.
+    ///
+    /// Unversioned virtual packages only satisfy unversioned relations.
+    /// Architecture qualifiers and restrictions are ignored.
+    ///
+    /// # Arguments
+    /// * `relation` - The relation to check
+    pub fn candidates(&self, relation: &Relation) -> Vec<&BinaryPackage> {
+        let Some(name) = relation.try_name() else {
+            return vec![];
+        };
+        let constraint = relation.version();
+        let matches = |version: &Version| match &constraint {
+            None => true,
+            Some((vc, v)) => match vc {
+                VersionConstraint::GreaterThanEqual => version >= v,
+                VersionConstraint::LessThanEqual => version <= v,
+                VersionConstraint::Equal => version == v,
+                VersionConstraint::GreaterThan => version > v,
+                VersionConstraint::LessThan => version < v,
+            },
+        };
+        let mut ret = self
+            .binary_packages(&name)
+            .iter()
+            .filter(|p| matches(&p.version))
+            .collect::<Vec<_>>();
+        let providers = self
+            .providers(&name)
+            .into_iter()
+            .collect::<std::collections::BTreeSet<_>>();
+        for provider in providers {
+            ret.extend(self.binary_packages(provider).iter().filter(|p| {
+                p.provides.iter().any(|(n, version)| {
+                    n == &name
+                        && match version {
+                            Some(version) => matches(version),
+                            None => constraint.is_none(),
+                        }
+                })
+            }));
+        }
+        ret
+    }
+
+    /// Check whether a single relation can be satisfied by the index.
+    ///
+    /// # Arguments
+    /// * `relation` - The relation to check
+    pub fn satisfies(&self, relation: &Relation) -> bool {
+        !self.candidates(relation).is_empty()
+    }
+
+    /// The entries in a set of relations that can not be satisfied by the index.
+    ///
+    /// # Arguments
+    /// * `relations` - The relations to check, e.g. the build dependencies of a package
+    pub fn unsatisfied(&self, relations: &Relations) -> Vec<Entry> {
+        relations
+            .entries()
+            .filter(|e| !e.relations().any(|r| self.satisfies(&r)))
+            .collect()
+    }
+}
+
+fn fetch(url: &str) -> Result<reqwest::blocking::Response, Error> {
+    log::debug!("Downloading {}", url);
+    reqwest::blocking::get(url)
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| Error::Download(url.to_string(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGES: &str = r#"Package: debhelper
+Version: 13.11.4
+Architecture: all
+Provides: debhelper-compat (= 13), debhelper-compat (= 12)
+
+Package: libfoo1
+Source: foo (1.0-1)
+Version: 1.0-1+b1
+Architecture: amd64
+Provides: libfoo
+
+Package: libfoo1
+Source: foo
+Version: 1.1-1
+Architecture: amd64
+"#;
+
+    const SOURCES: &str = r#"Package: foo
+Binary: libfoo1, foo-doc
+Version: 1.1-1
+
+Package: foo
+Binary: libfoo1
+Version: 1.0-1
+"#;
+
+    fn index() -> AptIndex {
+        let mut index = AptIndex::new();
+        index.add_packages(PACKAGES.as_bytes()).unwrap();
+        index.add_sources(SOURCES.as_bytes()).unwrap();
+        index
+    }
+
+    #[test]
+    fn test_versions() {
+        let index = index();
+        assert_eq!(
+            Some(&"1.1-1".parse().unwrap()),
+            index.binary_version("libfoo1")
+        );
+        assert_eq!(Some(&"1.1-1".parse().unwrap()), index.source_version("foo"));
+        assert_eq!(None, index.binary_version("bar"));
+        assert_eq!(
+            Some("foo"),
+            index.binary_packages("libfoo1")[0].source.as_deref()
+        );
+        assert_eq!(
+            vec!["libfoo1", "foo-doc"],
+            index.source_packages("foo")[0].binaries
+        );
+        assert_eq!(vec!["libfoo1"], index.providers("libfoo"));
+    }
+
+    #[test]
+    fn test_unsatisfied() {
+        let index = index();
+        let relations: Relations =
+            "debhelper-compat (= 13), libfoo, libfoo1 (>= 2.0) | bar, libfoo1 (>= 1.1), libfoo (>= 1)"
+                .parse()
+                .unwrap();
+        let unsatisfied = index
+            .unsatisfied(&relations)
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["libfoo1 (>= 2.0) | bar", "libfoo (>= 1)"], unsatisfied);
+    }
+
+    #[test]
+    fn test_from_lists_dir() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(
+            td.path()
+                .join("deb.debian.org_debian_dists_sid_main_binary-amd64_Packages"),
+            PACKAGES,
+        )
+        .unwrap();
+        std::fs::write(td.path().join("lock"), "").unwrap();
+        let index = AptIndex::from_lists_dir(td.path()).unwrap();
+        assert_eq!(
+            Some(&"13.11.4".parse().unwrap()),
+            index.binary_version("debhelper")
+        );
+    }
+}
@@ -321,6 +321,38 @@ impl std::str::FromStr for Expr {
     }
 }
 
+impl Expr {
+    /// Evaluate the expression against the fields of a package.
+    ///
+    /// Version comparisons are not supported, and never match.
+    ///
+    /// # Arguments
+    /// * `field` - Looks up the value of a field, by lowercase name
+    pub fn matches(&self, field: &dyn Fn(&str) -> Option<String>) -> bool {
+        match self {
+            Expr::Bool(b) => *b,
+            Expr::Not(expr) => !expr.matches(field),
+            Expr::Or(exprs) => exprs.iter().any(|e| e.matches(field)),
+            Expr::And(exprs) => exprs.iter().all(|e| e.matches(field)),
+            Expr::FieldRegex(name, pattern) => {
+                let Some(value) = field(name) else {
+                    return false;
+                };
+                match regex::Regex::new(pattern) {
+                    Ok(re) => re.is_match(&value),
+                    Err(e) => {
+                        log::debug!("Invalid regex {}: {}", pattern, e);
+                        false
+                    }
+                }
+            }
+            Expr::FieldString(name, string) => field(name).is_some_and(|v| v.contains(string)),
+            Expr::Source => true,
+            Expr::Comparison(..) | Expr::FieldComparison(..) | Expr::String(..) => false,
+        }
+    }
+}
+
 impl std::fmt::Debug for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -0,0 +1,136 @@
+//! On-disk cache for responses from Debian infrastructure services.
+//!
+//! Values are stored as JSON, one file per key, together with the time they
+//! were fetched. Entries older than the time-to-live are ignored.
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default time-to-live for cached values.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CacheEntry<T> {
+    fetched: u64,
+    value: T,
+}
+
+/// A cache of JSON-serializable values.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+/// The default directory to cache values in.
+///
+/// This is `$XDG_CACHE_HOME/debian-analyzer`, falling back to
+/// `~/.cache/debian-analyzer`.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("debian-analyzer"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl Cache {
+    /// Create a new cache.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory to store cached values in
+    /// * `ttl` - How long cached values remain valid
+    pub fn new(dir: &Path, ttl: Duration) -> Self {
+        Cache {
+            dir: dir.to_path_buf(),
+            ttl,
+        }
+    }
+
+    /// Directory the values are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        let name = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// Look up a value.
+    ///
+    /// # Arguments
+    /// * `key` - Key of the value
+    ///
+    /// # Returns
+    /// The value, or `None` if it is not cached, has expired or can not be read
+    pub fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let text = std::fs::read_to_string(self.path(key)).ok()?;
+        let entry: CacheEntry<T> = match serde_json::from_str(&text) {
+            Ok(entry) => entry,
+            Err(e) => {
+                log::debug!("Ignoring unreadable cache entry for {}: {}", key, e);
+                return None;
+            }
+        };
+        if now().saturating_sub(entry.fetched) > self.ttl.as_secs() {
+            return None;
+        }
+        Some(entry.value)
+    }
+
+    /// Store a value.
+    ///
+    /// # Arguments
+    /// * `key` - Key of the value
+    /// * `value` - The value
+    pub fn put<T: serde::Serialize>(&self, key: &str, value: &T) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let entry = CacheEntry {
+            fetched: now(),
+            value,
+        };
+        std::fs::write(self.path(key), serde_json::to_string(&entry)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_put() {
+        let td = tempfile::tempdir().unwrap();
+        let cache = Cache::new(td.path(), DEFAULT_TTL);
+        assert_eq!(None, cache.get::<Vec<u32>>("popcon/foo"));
+        cache.put("popcon/foo", &vec![1, 2, 3]).unwrap();
+        assert_eq!(Some(vec![1, 2, 3]), cache.get::<Vec<u32>>("popcon/foo"));
+        assert!(td.path().join("popcon_foo.json").exists());
+    }
+
+    #[test]
+    fn test_expired() {
+        let td = tempfile::tempdir().unwrap();
+        let cache = Cache::new(td.path(), Duration::from_secs(60));
+        std::fs::write(
+            td.path().join("key.json"),
+            serde_json::json!({"fetched": now() - 120, "value": 1}).to_string(),
+        )
+        .unwrap();
+        assert_eq!(None, cache.get::<u32>("key"));
+    }
+}
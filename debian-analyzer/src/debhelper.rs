@@ -20,6 +20,20 @@ pub fn maximum_debhelper_compat_version(compat_release: &str) -> Option<u8> {
         })
 }
 
+/// Get the maximum supported debhelper compat version from an apt index.
+///
+/// # Arguments
+/// * `index` - Index of the archive the package will be built against
+///
+/// # Returns
+/// The major version of the newest debhelper in the index, or `None` if it
+/// does not contain debhelper
+pub fn maximum_debhelper_compat_version_from_index(index: &crate::apt::AptIndex) -> Option<u8> {
+    index
+        .binary_version("debhelper")
+        .and_then(|v| v.upstream_version.split('.').next()?.parse().ok())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,4 +48,17 @@ mod tests {
     fn test_unknown_release() {
         assert_eq!(None, maximum_debhelper_compat_version("nonexistent"));
     }
+
+    #[test]
+    fn test_from_index() {
+        let mut index = crate::apt::AptIndex::new();
+        assert_eq!(None, maximum_debhelper_compat_version_from_index(&index));
+        index
+            .add_packages("Package: debhelper\nVersion: 13.11.4\nArchitecture: all\n".as_bytes())
+            .unwrap();
+        assert_eq!(
+            Some(13),
+            maximum_debhelper_compat_version_from_index(&index)
+        );
+    }
 }
@@ -0,0 +1,201 @@
+//! Checking whether build dependencies can be installed from an archive.
+//!
+//! This is a simple resolver over an [`AptIndex`]: it checks that for every
+//! dependency some package is available whose own dependencies can in turn be
+//! satisfied. Conflicts and Breaks are not taken into account, and neither are
+//! architecture qualifiers or build profiles, so a package that is reported as
+//! installable may still fail to install. Anything reported as uninstallable
+//! really is missing from the archive.
+use crate::apt::{AptIndex, BinaryPackage};
+use debian_control::lossless::relations::{Entry, Relation, Relations};
+use std::collections::{HashMap, HashSet};
+
+/// A dependency that can not be satisfied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Binary packages that lead to the unsatisfiable dependency, starting from
+    /// the package that satisfies the top-level relation
+    pub chain: Vec<String>,
+    /// The dependency that can not be satisfied
+    pub unsatisfied: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for package in &self.chain {
+            write!(f, "{} -> ", package)?;
+        }
+        write!(f, "{}", self.unsatisfied)
+    }
+}
+
+struct Checker<'a> {
+    index: &'a AptIndex,
+    done: HashMap<String, Result<(), Problem>>,
+    in_progress: HashSet<String>,
+}
+
+impl<'a> Checker<'a> {
+    fn check_entry(&mut self, entry: &Entry) -> Result<(), Problem> {
+        let mut first = None;
+        for relation in entry.relations() {
+            match self.check_relation(&relation) {
+                Ok(()) => return Ok(()),
+                Err(problem) => {
+                    first.get_or_insert(problem);
+                }
+            }
+        }
+        Err(first.unwrap_or_else(|| Problem {
+            chain: vec![],
+            unsatisfied: entry.to_string(),
+        }))
+    }
+
+    fn check_relation(&mut self, relation: &Relation) -> Result<(), Problem> {
+        let index = self.index;
+        let mut first = None;
+        for candidate in index.candidates(relation) {
+            match self.check_package(candidate) {
+                Ok(()) => return Ok(()),
+                Err(problem) => {
+                    first.get_or_insert(problem);
+                }
+            }
+        }
+        Err(first.unwrap_or_else(|| Problem {
+            chain: vec![],
+            unsatisfied: relation.to_string(),
+        }))
+    }
+
+    fn check_package(&mut self, package: &'a BinaryPackage) -> Result<(), Problem> {
+        let key = format!("{} ({})", package.name, package.version);
+        if let Some(result) = self.done.get(&key) {
+            return result.clone();
+        }
+        // Dependency cycles are fine, as long as the rest of the cycle can be installed.
+        if !self.in_progress.insert(key.clone()) {
+            return Ok(());
+        }
+        let mut result = Ok(());
+        for field in [&package.pre_depends, &package.depends]
+            .into_iter()
+            .flatten()
+        {
+            let (relations, _) = Relations::parse_relaxed(field, true);
+            let problem = relations.entries().find_map(|e| self.check_entry(&e).err());
+            if let Some(mut problem) = problem {
+                problem.chain.insert(0, key.clone());
+                result = Err(problem);
+                break;
+            }
+        }
+        self.in_progress.remove(&key);
+        self.done.insert(key, result.clone());
+        result
+    }
+}
+
+/// Check whether a set of build dependencies can be installed.
+///
+/// # Arguments
+/// * `index` - Index of the archive to install from, e.g. for the compat release
+/// * `relations` - The build dependencies
+///
+/// # Returns
+/// One problem for every top-level entry that can not be installed
+pub fn check_installable(index: &AptIndex, relations: &Relations) -> Vec<Problem> {
+    let mut checker = Checker {
+        index,
+        done: HashMap::new(),
+        in_progress: HashSet::new(),
+    };
+    relations
+        .entries()
+        .filter_map(|e| checker.check_entry(&e).err())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGES: &str = r#"Package: debhelper
+Version: 13.11.4
+Architecture: all
+Depends: perl, dh-autoreconf
+Provides: debhelper-compat (= 13)
+
+Package: perl
+Version: 5.36.0-7
+Architecture: amd64
+Pre-Depends: perl-base (= 5.36.0-7)
+
+Package: perl-base
+Version: 5.36.0-7
+Architecture: amd64
+Depends: perl
+
+Package: dh-autoreconf
+Version: 20
+Architecture: all
+Depends: autoconf | autoconf2.13
+
+Package: libfoo-dev
+Version: 1.0-1
+Architecture: amd64
+Depends: libfoo1 (= 1.0-1)
+
+Package: libfoo1
+Version: 1.0-2
+Architecture: amd64
+"#;
+
+    fn index() -> AptIndex {
+        let mut index = AptIndex::new();
+        index.add_packages(PACKAGES.as_bytes()).unwrap();
+        index
+    }
+
+    #[test]
+    fn test_installable() {
+        let relations: Relations = "perl, libfoo1 (>= 1.0)".parse().unwrap();
+        assert_eq!(
+            Vec::<Problem>::new(),
+            check_installable(&index(), &relations)
+        );
+    }
+
+    #[test]
+    fn test_uninstallable() {
+        let relations: Relations = "debhelper-compat (= 13), libfoo-dev, missing | perl, libbar"
+            .parse()
+            .unwrap();
+        let problems = check_installable(&index(), &relations);
+        assert_eq!(
+            vec![
+                Problem {
+                    chain: vec![
+                        "debhelper (13.11.4)".to_string(),
+                        "dh-autoreconf (20)".to_string()
+                    ],
+                    unsatisfied: "autoconf".to_string(),
+                },
+                Problem {
+                    chain: vec!["libfoo-dev (1.0-1)".to_string()],
+                    unsatisfied: "libfoo1 (= 1.0-1)".to_string(),
+                },
+                Problem {
+                    chain: vec![],
+                    unsatisfied: "libbar".to_string(),
+                },
+            ],
+            problems
+        );
+        assert_eq!(
+            "debhelper (13.11.4) -> dh-autoreconf (20) -> autoconf",
+            problems[0].to_string()
+        );
+    }
+}
@@ -1,8 +1,11 @@
 //! Information about the Debian project and its infrastructure.
 #![deny(missing_docs)]
 
+pub mod apt;
 pub mod benfile;
+pub mod cache;
 pub mod debhelper;
+pub mod installability;
 pub mod key_package_versions;
 pub mod salsa;
 pub mod snapshot;
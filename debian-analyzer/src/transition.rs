@@ -77,6 +77,162 @@ pub fn read_transition<R: std::io::Read>(reader: &mut R) -> Result<Transition, S
     Transition::try_from(benfile)
 }
 
+impl Transition {
+    /// Check whether a package is involved in the transition.
+    ///
+    /// # Arguments
+    /// * `field` - Looks up the value of a field of the package, by lowercase name
+    pub fn affects(&self, field: &dyn Fn(&str) -> Option<String>) -> bool {
+        self.is_affected
+            .as_ref()
+            .is_some_and(|expr| expr.matches(field))
+    }
+}
+
+/// Base URL of the Debian transition tracker.
+pub const DEFAULT_TRACKER_URL: &str = "https://release.debian.org/transitions/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Status of a transition on the transition tracker
+pub enum TransitionStatus {
+    /// The transition has been planned, but not started
+    Planned,
+    /// The transition is in progress
+    Ongoing,
+    /// The transition has finished
+    Finished,
+    /// The transition is tracked permanently
+    Permanent,
+    /// Any other status
+    #[serde(other)]
+    Unknown,
+}
+
+impl TransitionStatus {
+    /// Whether a package in a transition with this status is entangled in it.
+    pub fn is_active(&self) -> bool {
+        matches!(self, TransitionStatus::Planned | TransitionStatus::Ongoing)
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PackageTransitions {
+    name: String,
+    list: Vec<(String, TransitionStatus)>,
+}
+
+/// Parse the list of packages and their transitions exported by the tracker.
+///
+/// # Arguments
+/// * `text` - Contents of the `export/packages.yaml` file
+///
+/// # Returns
+/// A map from source package name to the names and statuses of its transitions
+pub fn parse_packages_export(
+    text: &str,
+) -> Result<std::collections::HashMap<String, Vec<(String, TransitionStatus)>>, serde_yaml::Error> {
+    let packages: Vec<PackageTransitions> = serde_yaml::from_str(text)?;
+    Ok(packages.into_iter().map(|p| (p.name, p.list)).collect())
+}
+
+#[derive(Debug)]
+/// An error that can occur while talking to the transition tracker
+pub enum TrackerError {
+    /// An error occurred while downloading from the tracker
+    Http(reqwest::Error),
+
+    /// The package export could not be parsed
+    InvalidExport(serde_yaml::Error),
+
+    /// A ben file could not be parsed
+    InvalidBenFile(String),
+}
+
+impl std::fmt::Display for TrackerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TrackerError::Http(e) => write!(f, "HTTP error: {}", e),
+            TrackerError::InvalidExport(e) => write!(f, "Invalid package export: {}", e),
+            TrackerError::InvalidBenFile(e) => write!(f, "Invalid ben file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TrackerError {}
+
+impl From<reqwest::Error> for TrackerError {
+    fn from(e: reqwest::Error) -> Self {
+        TrackerError::Http(e)
+    }
+}
+
+/// Client for the transition tracker on release.debian.org
+pub struct TransitionTracker {
+    base_url: String,
+    packages: std::collections::HashMap<String, Vec<(String, TransitionStatus)>>,
+}
+
+impl TransitionTracker {
+    /// Download the list of packages and their transitions from the tracker.
+    ///
+    /// # Arguments
+    /// * `base_url` - Base URL of the tracker, e.g. [`DEFAULT_TRACKER_URL`]
+    pub fn fetch(base_url: &str) -> Result<Self, TrackerError> {
+        let url = format!("{}export/packages.yaml", base_url);
+        let text = reqwest::blocking::get(url)?.error_for_status()?.text()?;
+        let packages = parse_packages_export(&text).map_err(TrackerError::InvalidExport)?;
+        Ok(TransitionTracker {
+            base_url: base_url.to_string(),
+            packages,
+        })
+    }
+
+    /// All transitions a source package is part of, with their status.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the source package
+    pub fn transitions(&self, package: &str) -> &[(String, TransitionStatus)] {
+        self.packages.get(package).map_or(&[], |t| t.as_slice())
+    }
+
+    /// The planned or ongoing transitions a source package is entangled in.
+    ///
+    /// Uploads of these packages should usually be deferred until the
+    /// transitions are done, or coordinated with the release team.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the source package
+    pub fn active_transitions(&self, package: &str) -> Vec<&str> {
+        self.transitions(package)
+            .iter()
+            .filter(|(_, status)| status.is_active())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Download and parse the ben file that defines a transition.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the transition
+    /// * `status` - Status of the transition, which determines where the file lives
+    pub fn transition(
+        &self,
+        name: &str,
+        status: TransitionStatus,
+    ) -> Result<Transition, TrackerError> {
+        let dir = match status {
+            TransitionStatus::Planned => "planned",
+            TransitionStatus::Finished => "finished",
+            TransitionStatus::Permanent => "permanent",
+            TransitionStatus::Ongoing | TransitionStatus::Unknown => "ongoing",
+        };
+        let url = format!("{}config/{}/{}.ben", self.base_url, dir, name);
+        let text = reqwest::blocking::get(url)?.error_for_status()?.text()?;
+        read_transition(&mut text.as_bytes()).map_err(TrackerError::InvalidBenFile)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,4 +286,53 @@ export = false;
         assert_eq!(transition.notes, Some("https://bugs.debian.org/cgi-bin/pkgreport.cgi?users=pkg-gnome-maintainers@lists.alioth.debian.org&tag=libsoup2".to_string()));
         assert_eq!(transition.export, Some(false));
     }
+
+    #[test]
+    fn test_affects() {
+        let transition = read_transition(
+            &mut r#"title = "foo";
+is_affected = .build-depends ~ /libfoo-dev/ & !.build-depends ~ "libfoo2-dev";
+"#
+            .as_bytes(),
+        )
+        .unwrap();
+        assert!(transition.affects(&|f| match f {
+            "build-depends" => Some("debhelper-compat (= 13), libfoo-dev".to_string()),
+            _ => None,
+        }));
+        assert!(!transition.affects(&|f| match f {
+            "build-depends" => Some("libfoo-dev, libfoo2-dev".to_string()),
+            _ => None,
+        }));
+        assert!(!transition.affects(&|_| None));
+    }
+
+    #[test]
+    fn test_parse_packages_export() {
+        let packages = parse_packages_export(
+            r#"---
+- name: foo
+  list:
+  - - auto-libbar
+    - ongoing
+  - - auto-libbaz
+    - finished
+- name: qux
+  list:
+  - - python3.13
+    - something-new
+"#,
+        )
+        .unwrap();
+        let tracker = TransitionTracker {
+            base_url: DEFAULT_TRACKER_URL.to_string(),
+            packages,
+        };
+        assert_eq!(vec!["auto-libbar"], tracker.active_transitions("foo"));
+        assert_eq!(
+            &[("python3.13".to_string(), TransitionStatus::Unknown)],
+            tracker.transitions("qux")
+        );
+        assert!(tracker.transitions("unknown").is_empty());
+    }
 }
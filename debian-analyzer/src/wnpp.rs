@@ -5,13 +5,35 @@ use sqlx::{Error, PgPool, Postgres};
 /// Bug identifier
 pub type BugId = i32;
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
 /// Type of WNPP bug.
 pub enum BugKind {
     /// Request for packaging
     RFP,
     /// Intent to package
     ITP,
+    /// Orphaned package
+    O,
+    /// Request for adoption
+    RFA,
+    /// Request for help
+    RFH,
+    /// Intent to adopt
+    ITA,
+}
+
+impl BugKind {
+    /// Whether this kind of bug is about a package that is not in the archive yet.
+    pub fn is_new_package(&self) -> bool {
+        matches!(self, BugKind::RFP | BugKind::ITP)
+    }
+
+    /// Whether this kind of bug is about a package looking for a new maintainer.
+    pub fn is_orphaning(&self) -> bool {
+        matches!(self, BugKind::O | BugKind::RFA | BugKind::ITA)
+    }
 }
 
 impl std::str::FromStr for BugKind {
@@ -21,6 +43,10 @@ impl std::str::FromStr for BugKind {
         match s {
             "RFP" => Ok(BugKind::RFP),
             "ITP" => Ok(BugKind::ITP),
+            "O" => Ok(BugKind::O),
+            "RFA" => Ok(BugKind::RFA),
+            "RFH" => Ok(BugKind::RFH),
+            "ITA" => Ok(BugKind::ITA),
             _ => Err(format!("Unknown bug kind: {}", s)),
         }
     }
@@ -31,6 +57,10 @@ impl std::fmt::Display for BugKind {
         match self {
             BugKind::RFP => write!(f, "RFP"),
             BugKind::ITP => write!(f, "ITP"),
+            BugKind::O => write!(f, "O"),
+            BugKind::RFA => write!(f, "RFA"),
+            BugKind::RFH => write!(f, "RFH"),
+            BugKind::ITA => write!(f, "ITA"),
         }
     }
 }
@@ -48,6 +78,44 @@ impl sqlx::Decode<'_, Postgres> for BugKind {
     }
 }
 
+/// A bug filed against the wnpp pseudo-package.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WnppBug {
+    /// Bug number
+    pub id: BugId,
+    /// Type of the bug
+    pub kind: BugKind,
+    /// Name of the package the bug is about
+    pub package: String,
+    /// Title of the bug, e.g. "ITP: foo -- does things"
+    pub title: String,
+}
+
+/// Parse the title of a WNPP bug.
+///
+/// # Arguments
+/// * `title` - Title of the bug, e.g. "ITP: foo -- does things"
+///
+/// # Returns
+/// The kind of bug and the name of the package it is about
+pub fn parse_wnpp_title(title: &str) -> Option<(BugKind, String)> {
+    let (kind, rest) = title.split_once(':')?;
+    let kind = kind.trim().parse().ok()?;
+    let package = rest.split(" -- ").next()?.trim();
+    if package.is_empty() || package.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((kind, package.to_string()))
+}
+
+/// Changelog entry for the initial upload of a package, closing its ITP bug.
+///
+/// # Arguments
+/// * `itp` - Number of the ITP bug
+pub fn initial_release_entry(itp: BugId) -> String {
+    format!("Initial release. (Closes: #{})", itp)
+}
+
 /// Read DebBugs data through UDD.
 pub struct DebBugs {
     pool: PgPool,
@@ -105,6 +173,57 @@ impl DebBugs {
         .fetch_all(&self.pool)
         .await
     }
+
+    /// Find open WNPP bugs of particular kinds for a package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the package
+    /// * `kinds` - Kinds of bugs to look for
+    pub async fn find_bugs(&self, package: &str, kinds: &[BugKind]) -> Result<Vec<WnppBug>, Error> {
+        let kinds = kinds.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        let rows = sqlx::query_as::<_, (BugId, BugKind, String, String)>(
+            "select wnpp.id, wnpp.type, wnpp.source, bugs.title from wnpp
+            join bugs on bugs.id = wnpp.id
+            where wnpp.source = $1 and wnpp.type = any($2) order by wnpp.id",
+        )
+        .bind(package)
+        .bind(kinds)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, kind, package, title)| WnppBug {
+                id,
+                kind,
+                package,
+                title,
+            })
+            .collect())
+    }
+
+    /// Find the ITP bug for a package, e.g. to close it in the initial changelog entry.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the package
+    pub async fn find_itp_bug(&self, package: &str) -> Result<Option<WnppBug>, Error> {
+        Ok(self
+            .find_bugs(package, &[BugKind::ITP])
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Find an open O, RFA or ITA bug for a package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the package
+    pub async fn find_orphaning_bug(&self, package: &str) -> Result<Option<WnppBug>, Error> {
+        Ok(self
+            .find_bugs(package, &[BugKind::O, BugKind::RFA, BugKind::ITA])
+            .await?
+            .into_iter()
+            .next())
+    }
 }
 
 /// Find WNPP bugs for a package, trying multiple names.
@@ -143,7 +262,12 @@ mod tests {
     fn test_bug_kind_parsing() {
         assert_eq!("RFP".parse::<BugKind>().unwrap(), BugKind::RFP);
         assert_eq!("ITP".parse::<BugKind>().unwrap(), BugKind::ITP);
+        assert_eq!("O".parse::<BugKind>().unwrap(), BugKind::O);
+        assert_eq!("RFA".parse::<BugKind>().unwrap(), BugKind::RFA);
         assert!("INVALID".parse::<BugKind>().is_err());
+        assert!(BugKind::ITA.is_orphaning());
+        assert!(!BugKind::RFH.is_orphaning());
+        assert!(BugKind::RFP.is_new_package());
     }
 
     #[test]
@@ -151,4 +275,22 @@ mod tests {
         assert_eq!(BugKind::RFP.to_string(), "RFP");
         assert_eq!(BugKind::ITP.to_string(), "ITP");
     }
+
+    #[test]
+    fn test_parse_wnpp_title() {
+        assert_eq!(
+            Some((BugKind::ITP, "foo".to_string())),
+            parse_wnpp_title("ITP: foo -- does things")
+        );
+        assert_eq!(
+            Some((BugKind::O, "bar".to_string())),
+            parse_wnpp_title("O: bar -- something")
+        );
+        assert_eq!(None, parse_wnpp_title("Some other bug"));
+        assert_eq!(None, parse_wnpp_title("ITP: foo bar -- spaces"));
+        assert_eq!(
+            "Initial release. (Closes: #123456)",
+            initial_release_entry(123456)
+        );
+    }
 }
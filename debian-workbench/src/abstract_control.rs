@@ -1,11 +1,77 @@
 //! Abstract interface for editing debian packages, whether backed by real control files or
 //! debcargo files.
+use crate::lintian::StandardsVersion;
 use crate::relations::ensure_relation;
+use crate::rules_requires_root::RulesRequiresRoot;
 use debian_control::lossless::relations::{Entry, Relations};
 use std::path::Path;
 
+/// The kind of files an [`AbstractControlEditor`] edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// debian/control, possibly generated from a template
+    Control,
+
+    /// debian/debcargo.toml, from which debcargo generates debian/control
+    Debcargo,
+
+    /// A backend registered by a third party, identified by its name
+    Other(&'static str),
+}
+
+/// An operation that not every [`AbstractControlEditor`] backend supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Binary package descriptions are stored as written, rather than
+    /// generated from the upstream metadata
+    EditDescriptions,
+
+    /// The Architecture and Multi-Arch fields of binary packages can be set
+    EditArchitecture,
+
+    /// Fields can be wrapped and sorted
+    WrapAndSort,
+}
+
+impl Backend {
+    /// Whether editors for this backend support an operation.
+    pub fn supports(&self, capability: Capability) -> bool {
+        // debcargo generates these fields and the layout of debian/control itself.
+        !matches!(
+            (self, capability),
+            (
+                Backend::Debcargo,
+                Capability::EditDescriptions
+                    | Capability::EditArchitecture
+                    | Capability::WrapAndSort
+            )
+        )
+    }
+}
+
 /// Interface for editing debian packages, whether backed by real control files or debcargo files.
 pub trait AbstractControlEditor {
+    /// Get the kind of files this editor edits.
+    fn backend(&self) -> Backend;
+
+    /// Whether this editor supports an operation.
+    ///
+    /// Unsupported operations are silently ignored by the editor, so
+    /// callers can use this to skip fixes or report them as not applicable.
+    fn supports(&self, capability: Capability) -> bool {
+        self.backend().supports(capability)
+    }
+
+    /// Get the underlying debcargo editor, for backend-specific editing.
+    fn as_debcargo(&self) -> Option<&crate::debcargo::DebcargoEditor> {
+        None
+    }
+
+    /// Get the underlying debcargo editor mutably, for backend-specific editing.
+    fn as_debcargo_mut(&mut self) -> Option<&mut crate::debcargo::DebcargoEditor> {
+        None
+    }
+
     /// Get the source package.
     fn source<'a>(&'a mut self) -> Option<Box<dyn AbstractSource<'a> + 'a>>;
 
@@ -13,10 +79,27 @@ pub trait AbstractControlEditor {
     fn binaries<'a>(&'a mut self) -> Vec<Box<dyn AbstractBinary + 'a>>;
 
     /// Commit the changes.
-    fn commit(&self) -> bool;
+    ///
+    /// # Returns
+    /// Whether any files were changed
+    fn commit(&self) -> Result<bool, crate::error::Error>;
+
+    /// Describe the changes that [`AbstractControlEditor::commit`] would
+    /// make, without writing them.
+    ///
+    /// Use [`crate::editor::unified_diff`] to render them as a diff.
+    fn changes(&self) -> Result<Vec<crate::editor::FileChange>, crate::error::Error>;
 
     /// Wrap and sort the control file.
     fn wrap_and_sort(&mut self);
+
+    /// Get the directory with packaging files that are used as they are.
+    ///
+    /// For debcargo packages this is the overlay directory, which holds
+    /// e.g. the patches that are applied on top of the generated files.
+    fn overlay_dir(&self) -> Option<std::path::PathBuf> {
+        None
+    }
 }
 
 /// An abstract source package.
@@ -38,18 +121,146 @@ pub trait AbstractSource<'a> {
 
     /// Get the VCS URL for the source package.
     fn get_vcs_url(&self, vcs_type: &str) -> Option<String>;
+
+    /// Get the Testsuite field of the source package.
+    fn testsuite(&self) -> Option<String>;
+
+    /// Set the Testsuite field of the source package, or remove it if `None`.
+    fn set_testsuite(&mut self, testsuite: Option<&str>);
+
+    /// Get the Homepage field of the source package.
+    fn homepage(&self) -> Option<String>;
+
+    /// Set the Homepage field of the source package.
+    fn set_homepage(&mut self, homepage: &str);
+
+    /// Get the Rules-Requires-Root field of the source package.
+    fn rules_requires_root(&self) -> Option<String>;
+
+    /// Set the Rules-Requires-Root field of the source package.
+    fn set_rules_requires_root(&mut self, value: &str);
+
+    /// Get the Standards-Version field of the source package.
+    fn standards_version(&self) -> Option<String>;
+
+    /// Set the Standards-Version field of the source package.
+    fn set_standards_version(&mut self, version: &str);
+
+    /// Get the Rules-Requires-Root field of the source package, parsed.
+    ///
+    /// # Returns
+    /// `None` if the field is not set or can not be parsed
+    fn parsed_rules_requires_root(&self) -> Option<RulesRequiresRoot> {
+        self.rules_requires_root()?.parse().ok()
+    }
+
+    /// Set the Rules-Requires-Root field of the source package.
+    fn set_parsed_rules_requires_root(&mut self, value: &RulesRequiresRoot) {
+        self.set_rules_requires_root(&value.to_string());
+    }
+
+    /// Get the Standards-Version field of the source package, parsed.
+    ///
+    /// # Returns
+    /// `None` if the field is not set or can not be parsed
+    fn parsed_standards_version(&self) -> Option<StandardsVersion> {
+        self.standards_version()?.parse().ok()
+    }
+
+    /// Set the Standards-Version field of the source package.
+    fn set_parsed_standards_version(&mut self, version: &StandardsVersion) {
+        self.set_standards_version(&version.to_string());
+    }
 }
 
 /// An abstract binary package.
 pub trait AbstractBinary {
     /// Get the name of the binary package.
     fn name(&self) -> Option<String>;
+
+    /// Get the Architecture field of the binary package.
+    fn architecture(&self) -> Option<String>;
+
+    /// Get the Multi-Arch field of the binary package.
+    fn multi_arch(&self) -> Option<String>;
+
+    /// Set the Multi-Arch field of the binary package, or remove it if `None`.
+    fn set_multi_arch(&mut self, multi_arch: Option<&str>);
+
+    /// Set the Architecture field of the binary package, or remove it if `None`.
+    fn set_architecture(&mut self, architecture: Option<&str>);
+
+    /// Get the Section field of the binary package.
+    fn section(&self) -> Option<String>;
+
+    /// Set the Section field of the binary package, or remove it if `None`.
+    fn set_section(&mut self, section: Option<&str>);
+
+    /// Get the Depends field of the binary package.
+    fn depends(&self) -> Option<String>;
+
+    /// Set the Depends field of the binary package, or remove it if `None`.
+    fn set_depends(&mut self, depends: Option<&str>);
+
+    /// Get the Recommends field of the binary package.
+    fn recommends(&self) -> Option<String>;
+
+    /// Set the Recommends field of the binary package, or remove it if `None`.
+    fn set_recommends(&mut self, recommends: Option<&str>);
+
+    /// Get the Suggests field of the binary package.
+    fn suggests(&self) -> Option<String>;
+
+    /// Set the Suggests field of the binary package, or remove it if `None`.
+    fn set_suggests(&mut self, suggests: Option<&str>);
+
+    /// Get the Description field of the binary package.
+    ///
+    /// The first line is the summary; the lines of the long description
+    /// follow without their leading space, with empty lines as ".".
+    fn description(&self) -> Option<String>;
+
+    /// Set the Description field of the binary package, or remove it if `None`.
+    ///
+    /// The description is in the same form as returned by
+    /// [`AbstractBinary::description`].
+    fn set_description(&mut self, description: Option<&str>);
+}
+
+/// Set or remove a field in a paragraph.
+fn set_field(paragraph: &mut deb822_lossless::Paragraph, name: &str, value: Option<&str>) {
+    if let Some(value) = value {
+        paragraph.set(name, value);
+    } else {
+        paragraph.remove(name);
+    }
+}
+
+/// Split a relations field into its entries.
+fn split_relations(value: &str) -> Vec<&str> {
+    value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
 
 use crate::debcargo::{DebcargoBinary, DebcargoEditor, DebcargoSource};
 use debian_control::{Binary as PlainBinary, Control as PlainControl, Source as PlainSource};
 
 impl AbstractControlEditor for DebcargoEditor {
+    fn backend(&self) -> Backend {
+        Backend::Debcargo
+    }
+
+    fn as_debcargo(&self) -> Option<&DebcargoEditor> {
+        Some(self)
+    }
+
+    fn as_debcargo_mut(&mut self) -> Option<&mut DebcargoEditor> {
+        Some(self)
+    }
+
     fn source<'a>(&'a mut self) -> Option<Box<dyn AbstractSource<'a> + 'a>> {
         Some(Box::new(DebcargoEditor::source(self)) as Box<dyn AbstractSource<'a>>)
     }
@@ -60,17 +271,81 @@ impl AbstractControlEditor for DebcargoEditor {
             .collect()
     }
 
-    fn commit(&self) -> bool {
-        DebcargoEditor::commit(self).unwrap()
+    fn commit(&self) -> Result<bool, crate::error::Error> {
+        DebcargoEditor::commit(self)
+    }
+
+    fn changes(&self) -> Result<Vec<crate::editor::FileChange>, crate::error::Error> {
+        DebcargoEditor::pending_changes(self)
     }
 
     fn wrap_and_sort(&mut self) {}
+
+    fn overlay_dir(&self) -> Option<std::path::PathBuf> {
+        DebcargoEditor::overlay_dir(self)
+    }
 }
 
 impl AbstractBinary for PlainBinary {
     fn name(&self) -> Option<String> {
         self.name()
     }
+
+    fn architecture(&self) -> Option<String> {
+        self.as_deb822().get("Architecture")
+    }
+
+    fn multi_arch(&self) -> Option<String> {
+        self.as_deb822().get("Multi-Arch")
+    }
+
+    fn set_multi_arch(&mut self, multi_arch: Option<&str>) {
+        set_field(self.as_mut_deb822(), "Multi-Arch", multi_arch);
+    }
+
+    fn set_architecture(&mut self, architecture: Option<&str>) {
+        set_field(self.as_mut_deb822(), "Architecture", architecture);
+    }
+
+    fn section(&self) -> Option<String> {
+        self.as_deb822().get("Section")
+    }
+
+    fn set_section(&mut self, section: Option<&str>) {
+        set_field(self.as_mut_deb822(), "Section", section);
+    }
+
+    fn depends(&self) -> Option<String> {
+        self.as_deb822().get("Depends")
+    }
+
+    fn set_depends(&mut self, depends: Option<&str>) {
+        set_field(self.as_mut_deb822(), "Depends", depends);
+    }
+
+    fn recommends(&self) -> Option<String> {
+        self.as_deb822().get("Recommends")
+    }
+
+    fn set_recommends(&mut self, recommends: Option<&str>) {
+        set_field(self.as_mut_deb822(), "Recommends", recommends);
+    }
+
+    fn suggests(&self) -> Option<String> {
+        self.as_deb822().get("Suggests")
+    }
+
+    fn set_suggests(&mut self, suggests: Option<&str>) {
+        set_field(self.as_mut_deb822(), "Suggests", suggests);
+    }
+
+    fn description(&self) -> Option<String> {
+        self.as_deb822().get("Description")
+    }
+
+    fn set_description(&mut self, description: Option<&str>) {
+        set_field(self.as_mut_deb822(), "Description", description);
+    }
 }
 
 impl AbstractSource<'_> for PlainSource {
@@ -104,12 +379,120 @@ impl AbstractSource<'_> for PlainSource {
         let field_name = format!("Vcs-{}", vcs_type);
         self.as_deb822().get(&field_name)
     }
+
+    fn testsuite(&self) -> Option<String> {
+        self.as_deb822().get("Testsuite")
+    }
+
+    fn set_testsuite(&mut self, testsuite: Option<&str>) {
+        if let Some(testsuite) = testsuite {
+            self.as_mut_deb822().set("Testsuite", testsuite);
+        } else {
+            self.as_mut_deb822().remove("Testsuite");
+        }
+    }
+
+    fn homepage(&self) -> Option<String> {
+        self.as_deb822().get("Homepage")
+    }
+
+    fn set_homepage(&mut self, homepage: &str) {
+        self.as_mut_deb822().set("Homepage", homepage);
+    }
+
+    fn rules_requires_root(&self) -> Option<String> {
+        self.as_deb822().get("Rules-Requires-Root")
+    }
+
+    fn set_rules_requires_root(&mut self, value: &str) {
+        self.as_mut_deb822().set("Rules-Requires-Root", value);
+    }
+
+    fn standards_version(&self) -> Option<String> {
+        self.as_deb822().get("Standards-Version")
+    }
+
+    fn set_standards_version(&mut self, version: &str) {
+        self.as_mut_deb822().set("Standards-Version", version);
+    }
 }
 
 impl AbstractBinary for DebcargoBinary<'_> {
     fn name(&self) -> Option<String> {
         Some(self.name().to_string())
     }
+
+    fn architecture(&self) -> Option<String> {
+        DebcargoBinary::architecture(self).map(|a| a.to_string())
+    }
+
+    fn multi_arch(&self) -> Option<String> {
+        DebcargoBinary::multi_arch(self).map(|m| m.to_string())
+    }
+
+    fn set_multi_arch(&mut self, _multi_arch: Option<&str>) {
+        // debcargo determines the Multi-Arch field itself.
+    }
+
+    fn set_architecture(&mut self, _architecture: Option<&str>) {
+        // debcargo determines the Architecture field itself.
+    }
+
+    fn section(&self) -> Option<String> {
+        DebcargoBinary::section(self).map(|s| s.to_string())
+    }
+
+    fn set_section(&mut self, section: Option<&str>) {
+        DebcargoBinary::set_section(self, section);
+    }
+
+    fn depends(&self) -> Option<String> {
+        DebcargoBinary::depends(self).map(|d| d.join(", "))
+    }
+
+    fn set_depends(&mut self, depends: Option<&str>) {
+        DebcargoBinary::set_depends(self, depends.map(split_relations).as_deref());
+    }
+
+    fn recommends(&self) -> Option<String> {
+        DebcargoBinary::recommends(self).map(|r| r.join(", "))
+    }
+
+    fn set_recommends(&mut self, recommends: Option<&str>) {
+        DebcargoBinary::set_recommends(self, recommends.map(split_relations).as_deref());
+    }
+
+    fn suggests(&self) -> Option<String> {
+        DebcargoBinary::suggests(self).map(|s| s.join(", "))
+    }
+
+    fn set_suggests(&mut self, suggests: Option<&str>) {
+        DebcargoBinary::set_suggests(self, suggests.map(split_relations).as_deref());
+    }
+
+    fn description(&self) -> Option<String> {
+        let mut ret = DebcargoBinary::summary(self)?;
+        for line in self.long_description()?.lines() {
+            ret.push('\n');
+            ret.push_str(if line.is_empty() { "." } else { line });
+        }
+        Some(ret)
+    }
+
+    fn set_description(&mut self, description: Option<&str>) {
+        let Some(description) = description else {
+            self.set_summary(None).set_long_description(None);
+            return;
+        };
+        let (summary, long_description) = description.split_once('\n').unwrap_or((description, ""));
+        let long_description = long_description
+            .lines()
+            .map(|l| if l.trim() == "." { "" } else { l.trim_start() })
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.set_summary(Some(summary))
+            .set_long_description(Some(&long_description));
+    }
 }
 
 impl<'a> AbstractSource<'a> for DebcargoSource<'a> {
@@ -118,14 +501,7 @@ impl<'a> AbstractSource<'a> for DebcargoSource<'a> {
     }
 
     fn ensure_build_dep(&mut self, dep: Entry) {
-        // TODO: Check that it's not already there
-        if let Some(build_deps) = self
-            .toml_section_mut()
-            .get_mut("build_depends")
-            .and_then(|v| v.as_array_mut())
-        {
-            build_deps.push(dep.to_string());
-        }
+        (self as &mut crate::debcargo::DebcargoSource).ensure_build_dep(dep);
     }
 
     fn set_maintainer(&mut self, maintainer: &str) {
@@ -148,9 +524,60 @@ impl<'a> AbstractSource<'a> for DebcargoSource<'a> {
             _ => self.get_extra_field(&format!("Vcs-{}", vcs_type)),
         }
     }
+
+    fn testsuite(&self) -> Option<String> {
+        self.get_extra_field("Testsuite")
+    }
+
+    fn set_testsuite(&mut self, testsuite: Option<&str>) {
+        if let Some(testsuite) = testsuite {
+            self.set_extra_field("Testsuite", testsuite);
+        } else {
+            self.remove_extra_field("Testsuite");
+        }
+    }
+
+    fn homepage(&self) -> Option<String> {
+        (self as &crate::debcargo::DebcargoSource)
+            .homepage()
+            .map(|h| h.to_string())
+    }
+
+    fn set_homepage(&mut self, homepage: &str) {
+        (self as &mut crate::debcargo::DebcargoSource).set_homepage(homepage);
+    }
+
+    fn rules_requires_root(&self) -> Option<String> {
+        // debcargo defaults to not requiring root.
+        Some(
+            (self as &crate::debcargo::DebcargoSource)
+                .requires_root()
+                .unwrap_or_else(|| "no".to_string()),
+        )
+    }
+
+    fn set_rules_requires_root(&mut self, value: &str) {
+        (self as &mut crate::debcargo::DebcargoSource).set_requires_root(Some(value));
+    }
+
+    fn standards_version(&self) -> Option<String> {
+        Some(
+            (self as &crate::debcargo::DebcargoSource)
+                .standards_version()
+                .to_string(),
+        )
+    }
+
+    fn set_standards_version(&mut self, version: &str) {
+        (self as &mut crate::debcargo::DebcargoSource).set_standards_version(version);
+    }
 }
 
 impl<E: crate::editor::Editor<PlainControl>> AbstractControlEditor for E {
+    fn backend(&self) -> Backend {
+        Backend::Control
+    }
+
     fn source<'a>(&'a mut self) -> Option<Box<dyn AbstractSource<'a> + 'a>> {
         PlainControl::source(self).map(|s| Box::new(s) as Box<dyn AbstractSource>)
     }
@@ -161,11 +588,14 @@ impl<E: crate::editor::Editor<PlainControl>> AbstractControlEditor for E {
             .collect()
     }
 
-    fn commit(&self) -> bool {
-        !(self as &dyn crate::editor::Editor<PlainControl>)
-            .commit()
-            .unwrap()
-            .is_empty()
+    fn commit(&self) -> Result<bool, crate::error::Error> {
+        Ok(!(self as &dyn crate::editor::Editor<PlainControl>)
+            .commit()?
+            .is_empty())
+    }
+
+    fn changes(&self) -> Result<Vec<crate::editor::FileChange>, crate::error::Error> {
+        Ok((self as &dyn crate::editor::Editor<PlainControl>).pending_changes()?)
     }
 
     fn wrap_and_sort(&mut self) {
@@ -177,24 +607,163 @@ impl<E: crate::editor::Editor<PlainControl>> AbstractControlEditor for E {
     }
 }
 
-/// Open a control file for editing.
-pub fn edit_control<'a>(
-    tree: &dyn breezyshim::workingtree::WorkingTree,
-    subpath: &Path,
-) -> Result<Box<dyn AbstractControlEditor + 'a>, crate::editor::EditorError> {
-    if tree.has_filename(&subpath.join("debian/debcargo.toml")) {
+/// A kind of package whose control information can be edited.
+///
+/// Backends are tried in turn by [`ControlBackendRegistry::open`]; the first
+/// one that detects a package is used to open it. Third parties can add
+/// backends for control files generated from other metadata with
+/// [`register_control_backend`].
+pub trait ControlBackend: Send + Sync {
+    /// Name of the backend, e.g. "debcargo".
+    fn name(&self) -> &str;
+
+    /// Whether the package uses this backend.
+    ///
+    /// # Arguments
+    /// * `base` - Root of the package, containing the debian/ directory
+    fn detect(&self, base: &Path) -> bool;
+
+    /// Open the package for editing.
+    ///
+    /// # Arguments
+    /// * `base` - Root of the package, containing the debian/ directory
+    fn open(&self, base: &Path) -> Result<Box<dyn AbstractControlEditor>, crate::error::Error>;
+}
+
+/// Backend for packages maintained with debcargo.
+pub struct DebcargoBackend;
+
+impl ControlBackend for DebcargoBackend {
+    fn name(&self) -> &str {
+        "debcargo"
+    }
+
+    fn detect(&self, base: &Path) -> bool {
+        base.join("debian/debcargo.toml").exists()
+    }
+
+    fn open(&self, base: &Path) -> Result<Box<dyn AbstractControlEditor>, crate::error::Error> {
         Ok(Box::new(crate::debcargo::DebcargoEditor::from_directory(
-            &tree.abspath(subpath).unwrap(),
+            base,
         )?))
-    } else {
-        let control_path = tree.abspath(&subpath.join(std::path::Path::new("debian/control")));
+    }
+}
+
+/// Backend for debian/control, or a template it is generated from.
+///
+/// This is the fallback, and detects any package.
+pub struct ControlFileBackend;
+
+impl ControlBackend for ControlFileBackend {
+    fn name(&self) -> &str {
+        "control"
+    }
+
+    fn detect(&self, _base: &Path) -> bool {
+        true
+    }
+
+    fn open(&self, base: &Path) -> Result<Box<dyn AbstractControlEditor>, crate::error::Error> {
         Ok(Box::new(crate::control::TemplatedControlEditor::open(
-            control_path.unwrap(),
-        )?) as Box<dyn AbstractControlEditor>)
+            base.join("debian/control"),
+        )?))
     }
 }
 
-#[cfg(test)]
+/// An ordered collection of [`ControlBackend`]s.
+pub struct ControlBackendRegistry {
+    backends: Vec<Box<dyn ControlBackend>>,
+}
+
+impl Default for ControlBackendRegistry {
+    /// Create a registry with the built-in backends.
+    fn default() -> Self {
+        Self {
+            backends: vec![Box::new(DebcargoBackend), Box::new(ControlFileBackend)],
+        }
+    }
+}
+
+impl ControlBackendRegistry {
+    /// Create a registry without any backends.
+    pub fn new() -> Self {
+        Self { backends: vec![] }
+    }
+
+    /// Add a backend, which takes precedence over the backends already added.
+    pub fn register(&mut self, backend: Box<dyn ControlBackend>) {
+        self.backends.insert(0, backend);
+    }
+
+    /// Names of the backends, in the order they are tried.
+    pub fn names(&self) -> Vec<&str> {
+        self.backends.iter().map(|b| b.name()).collect()
+    }
+
+    /// Find the backend for a package.
+    ///
+    /// # Arguments
+    /// * `base` - Root of the package, containing the debian/ directory
+    pub fn detect(&self, base: &Path) -> Option<&dyn ControlBackend> {
+        self.backends
+            .iter()
+            .find(|b| b.detect(base))
+            .map(|b| b.as_ref())
+    }
+
+    /// Open a package for editing, using the first backend that detects it.
+    ///
+    /// # Arguments
+    /// * `base` - Root of the package, containing the debian/ directory
+    pub fn open(&self, base: &Path) -> Result<Box<dyn AbstractControlEditor>, crate::error::Error> {
+        let backend = self.detect(base).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no control backend for {}", base.display()),
+            )
+        })?;
+        log::debug!("Opening {} with {} backend", base.display(), backend.name());
+        backend.open(base)
+    }
+}
+
+fn global_registry() -> &'static std::sync::RwLock<ControlBackendRegistry> {
+    static REGISTRY: std::sync::OnceLock<std::sync::RwLock<ControlBackendRegistry>> =
+        std::sync::OnceLock::new();
+    REGISTRY.get_or_init(|| std::sync::RwLock::new(ControlBackendRegistry::default()))
+}
+
+/// Add a backend to the registry used by [`open_control`] and [`edit_control`].
+///
+/// The backend takes precedence over the built-in backends and those
+/// registered before it.
+pub fn register_control_backend(backend: Box<dyn ControlBackend>) {
+    global_registry().write().unwrap().register(backend);
+}
+
+#[cfg(feature = "vcs")]
+/// Open a control file for editing.
+pub fn edit_control<'a>(
+    tree: &dyn breezyshim::workingtree::WorkingTree,
+    subpath: &Path,
+) -> Result<Box<dyn AbstractControlEditor + 'a>, crate::error::Error> {
+    open_control(&tree.abspath(subpath)?)
+}
+
+/// Open the control file of a package in a plain directory for editing.
+///
+/// The backend is chosen from the built-in backends and those added with
+/// [`register_control_backend`].
+///
+/// # Arguments
+/// * `base` - Root of the package, containing the debian/ directory
+pub fn open_control<'a>(
+    base: &Path,
+) -> Result<Box<dyn AbstractControlEditor + 'a>, crate::error::Error> {
+    global_registry().read().unwrap().open(base)
+}
+
+#[cfg(all(test, feature = "vcs"))]
 mod tests {
     use breezyshim::controldir::{create_standalone_workingtree, ControlDirFormat};
     use breezyshim::prelude::*;
@@ -231,9 +800,15 @@ edition = "2018"
         tree.add(&[(Path::new("debian")), (Path::new("debian/debcargo.toml"))])
             .unwrap();
 
-        let editor = super::edit_control(&tree, Path::new("")).unwrap();
+        let mut editor = super::edit_control(&tree, Path::new("")).unwrap();
+        assert_eq!(editor.backend(), super::Backend::Debcargo);
+        assert!(!editor.supports(super::Capability::EditDescriptions));
+        assert_eq!(
+            editor.as_debcargo_mut().unwrap().source().name().as_deref(),
+            Some("rust-example")
+        );
 
-        editor.commit();
+        editor.commit().unwrap();
     }
 
     #[test]
@@ -260,8 +835,11 @@ Description: Example package
             .unwrap();
 
         let editor = super::edit_control(&tree, Path::new("")).unwrap();
+        assert_eq!(editor.backend(), super::Backend::Control);
+        assert!(editor.supports(super::Capability::EditDescriptions));
+        assert!(editor.as_debcargo().is_none());
 
-        editor.commit();
+        editor.commit().unwrap();
     }
 
     #[test]
@@ -291,7 +869,7 @@ Description: Example package
             debian_control::lossless::relations::Entry::from_str("libssl-dev").unwrap(),
         );
         std::mem::drop(source);
-        editor.commit();
+        editor.commit().unwrap();
 
         let text = tree.get_file_text(Path::new("debian/control")).unwrap();
         assert_eq!(
@@ -335,7 +913,7 @@ Description: Example package
         source.set_vcs_url("Browser", "https://github.com/example/repo");
 
         std::mem::drop(source);
-        editor.commit();
+        editor.commit().unwrap();
 
         let text = tree.get_file_text(Path::new("debian/control")).unwrap();
         assert_eq!(
@@ -388,7 +966,7 @@ version = "0.1.0"
         source.set_vcs_url("Svn", "https://svn.example.com/repo");
 
         std::mem::drop(source);
-        editor.commit();
+        editor.commit().unwrap();
 
         // Read back the debcargo.toml to verify
         let content = std::fs::read_to_string(td.path().join("debian/debcargo.toml")).unwrap();
@@ -501,4 +1079,226 @@ version = "0.1.0"
         // Test getting non-existent VCS URL
         assert_eq!(source.get_vcs_url("Hg"), None);
     }
+
+    #[test]
+    fn test_abstract_binary_plain() {
+        let td = tempfile::tempdir().unwrap();
+        let tree = create_standalone_workingtree(td.path(), &ControlDirFormat::default()).unwrap();
+        tree.mkdir(Path::new("debian")).unwrap();
+        tree.put_file_bytes_non_atomic(
+            Path::new("debian/control"),
+            br#"Source: example
+Maintainer: Alice <alice@example.com>
+
+Package: example
+Architecture: any
+Depends: libc6
+Description: Example package
+ Longer description.
+"#,
+        )
+        .unwrap();
+        tree.add(&[(Path::new("debian")), (Path::new("debian/control"))])
+            .unwrap();
+
+        let mut editor = super::edit_control(&tree, Path::new("")).unwrap();
+        let mut binaries = editor.binaries();
+        let binary = &mut binaries[0];
+        assert_eq!(binary.depends(), Some("libc6".to_string()));
+        assert_eq!(binary.recommends(), None);
+        assert_eq!(
+            binary.description(),
+            Some("Example package\nLonger description.".to_string())
+        );
+
+        binary.set_architecture(Some("all"));
+        binary.set_multi_arch(Some("foreign"));
+        binary.set_section(Some("utils"));
+        binary.set_depends(None);
+        binary.set_suggests(Some("example-doc"));
+        assert_eq!(binary.section(), Some("utils".to_string()));
+        std::mem::drop(binaries);
+        editor.commit().unwrap();
+
+        let content = std::fs::read_to_string(td.path().join("debian/control")).unwrap();
+        assert!(content.contains("Architecture: all\n"));
+        assert!(content.contains("Multi-Arch: foreign\n"));
+        assert!(content.contains("Section: utils\n"));
+        assert!(content.contains("Suggests: example-doc\n"));
+        assert!(!content.contains("Depends:"));
+    }
+
+    #[test]
+    fn test_abstract_binary_debcargo() {
+        let td = tempfile::tempdir().unwrap();
+        let tree = create_standalone_workingtree(td.path(), &ControlDirFormat::default()).unwrap();
+        tree.mkdir(Path::new("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            br#"maintainer = "Alice <alice@example.com>"
+
+[packages.lib]
+depends = ["libfoo-dev"]
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            br#"[package]
+name = "example"
+version = "0.1.0"
+"#,
+        )
+        .unwrap();
+        tree.add(&[(Path::new("debian")), (Path::new("debian/debcargo.toml"))])
+            .unwrap();
+
+        let mut editor = super::edit_control(&tree, Path::new("")).unwrap();
+        let mut binaries = editor.binaries();
+        let binary = binaries
+            .iter_mut()
+            .find(|b| b.name().as_deref() == Some("librust-example-dev"))
+            .unwrap();
+        assert_eq!(binary.depends(), Some("libfoo-dev".to_string()));
+
+        binary.set_depends(Some("libfoo-dev, libbar-dev (>= 1.0)"));
+        binary.set_section(Some("rust"));
+        binary.set_description(Some(
+            "Example crate\nFirst paragraph.\n.\nSecond paragraph.",
+        ));
+        assert_eq!(
+            binary.description(),
+            Some("Example crate\nFirst paragraph.\n.\nSecond paragraph.".to_string())
+        );
+        // debcargo decides the architecture itself
+        binary.set_architecture(Some("all"));
+        std::mem::drop(binaries);
+        editor.commit().unwrap();
+
+        let content = std::fs::read_to_string(td.path().join("debian/debcargo.toml")).unwrap();
+        assert!(content.contains("depends = [\"libfoo-dev\", \"libbar-dev (>= 1.0)\"]\n"));
+        assert!(content.contains("section = \"rust\"\n"));
+        assert!(content.contains("summary = \"Example crate\"\n"));
+        assert!(!content.contains("architecture"));
+    }
+
+    #[test]
+    fn test_abstract_source_typed_fields_debcargo() {
+        use crate::lintian::StandardsVersion;
+        use crate::rules_requires_root::RulesRequiresRoot;
+
+        let td = tempfile::tempdir().unwrap();
+        let tree = create_standalone_workingtree(td.path(), &ControlDirFormat::default()).unwrap();
+        tree.mkdir(Path::new("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            br#"maintainer = "Alice <alice@example.com>"
+
+[source]
+standards-version = "4.6.2"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            br#"[package]
+name = "example"
+version = "0.1.0"
+"#,
+        )
+        .unwrap();
+        tree.add(&[(Path::new("debian")), (Path::new("debian/debcargo.toml"))])
+            .unwrap();
+
+        let mut editor = super::edit_control(&tree, Path::new("")).unwrap();
+        let mut source = editor.source().unwrap();
+        assert_eq!(
+            source.parsed_standards_version(),
+            Some(StandardsVersion::new(4, 6, 2))
+        );
+        assert_eq!(
+            source.parsed_rules_requires_root(),
+            Some(RulesRequiresRoot::No)
+        );
+
+        source.set_parsed_standards_version(&StandardsVersion::new(4, 7, 0));
+        source.set_parsed_rules_requires_root(&RulesRequiresRoot::Keywords(vec![
+            "dpkg/target-subcommand".to_string(),
+        ]));
+        source.set_testsuite(Some("autopkgtest-pkg-rust"));
+        assert_eq!(
+            source.rules_requires_root().as_deref(),
+            Some("dpkg/target-subcommand")
+        );
+        std::mem::drop(source);
+        editor.commit().unwrap();
+
+        let content = std::fs::read_to_string(td.path().join("debian/debcargo.toml")).unwrap();
+        assert_eq!(
+            content,
+            r#"maintainer = "Alice <alice@example.com>"
+
+[source]
+standards-version = "4.7.0"
+requires_root = "dpkg/target-subcommand"
+extra_lines = ["Testsuite: autopkgtest-pkg-rust"]
+"#
+        );
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use super::ControlBackend;
+    use std::path::Path;
+
+    struct ElpaBackend;
+
+    impl ControlBackend for ElpaBackend {
+        fn name(&self) -> &str {
+            "elpa"
+        }
+
+        fn detect(&self, base: &Path) -> bool {
+            base.join("debian/elpa").exists()
+        }
+
+        fn open(
+            &self,
+            base: &Path,
+        ) -> Result<Box<dyn super::AbstractControlEditor>, crate::error::Error> {
+            super::ControlFileBackend.open(base)
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: elpa-foo\n\nPackage: elpa-foo\nArchitecture: all\n",
+        )
+        .unwrap();
+
+        let mut registry = super::ControlBackendRegistry::default();
+        assert_eq!(vec!["debcargo", "control"], registry.names());
+        assert_eq!("control", registry.detect(td.path()).unwrap().name());
+
+        registry.register(Box::new(ElpaBackend));
+        assert_eq!(vec!["elpa", "debcargo", "control"], registry.names());
+        assert_eq!("control", registry.detect(td.path()).unwrap().name());
+        std::fs::write(td.path().join("debian/elpa"), "*.el\n").unwrap();
+        assert_eq!("elpa", registry.detect(td.path()).unwrap().name());
+
+        let mut editor = registry.open(td.path()).unwrap();
+        assert_eq!(
+            Some("elpa-foo".to_string()),
+            editor.source().unwrap().name()
+        );
+
+        assert!(super::ControlBackendRegistry::new()
+            .open(td.path())
+            .is_err());
+    }
 }
@@ -0,0 +1,437 @@
+//! Read-only inspection of a package, summarizing its packaging metadata.
+//!
+//! The result is serializable, so it can be emitted as JSON for dashboards
+//! and other tooling.
+use crate::abstract_control::open_control;
+use crate::vcs::determine_browser_url;
+use std::path::Path;
+
+/// Version control systems that can be declared with a Vcs-* field.
+pub const VCS_TYPES: &[&str] = &[
+    "Git", "Svn", "Bzr", "Hg", "Mtn", "Cvs", "Darcs", "Arch", "Svk",
+];
+
+/// Hosts that used to serve Debian repositories, but have been shut down.
+pub const OBSOLETE_VCS_HOSTS: &[&str] = &[
+    "alioth.debian.org",
+    "anonscm.debian.org",
+    "arch.debian.org",
+    "bzr.debian.org",
+    "cvs.alioth.debian.org",
+    "darcs.debian.org",
+    "git.debian.org",
+    "hg.debian.org",
+    "svn.debian.org",
+];
+
+/// How the debhelper compat level is declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompatMechanism {
+    /// In a debian/compat file
+    CompatFile,
+    /// With a debhelper-compat build dependency
+    BuildDepends,
+    /// With the X-DH-Compat field in debian/control
+    XDhCompat,
+}
+
+/// A problem with the Vcs-* fields of a package.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "issue", rename_all = "kebab-case")]
+pub enum VcsIssue {
+    /// There is no Vcs-* field
+    Missing,
+    /// The repository URL can not be parsed
+    InvalidUrl {
+        /// The URL
+        url: String,
+    },
+    /// The repository is hosted on a site that no longer exists
+    ObsoleteHost {
+        /// The hostname
+        host: String,
+    },
+    /// The repository is accessed over an unencrypted protocol
+    InsecureUrl {
+        /// The URL
+        url: String,
+    },
+    /// There is no Vcs-Browser field
+    MissingBrowser,
+    /// Vcs-Browser does not match the repository URL
+    BrowserMismatch {
+        /// Vcs-Browser as declared
+        actual: String,
+        /// Vcs-Browser as derived from the repository URL
+        expected: String,
+    },
+}
+
+impl std::fmt::Display for VcsIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VcsIssue::Missing => write!(f, "No Vcs-* field"),
+            VcsIssue::InvalidUrl { url } => write!(f, "Invalid repository URL: {}", url),
+            VcsIssue::ObsoleteHost { host } => {
+                write!(f, "Repository is hosted on obsolete host {}", host)
+            }
+            VcsIssue::InsecureUrl { url } => write!(f, "Insecure repository URL: {}", url),
+            VcsIssue::MissingBrowser => write!(f, "No Vcs-Browser field"),
+            VcsIssue::BrowserMismatch { actual, expected } => write!(
+                f,
+                "Vcs-Browser is {}, but repository suggests {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+/// The Vcs-* fields of a package.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct VcsInfo {
+    /// Type of the VCS, e.g. "Git"
+    #[serde(rename = "type")]
+    pub vcs_type: String,
+    /// Value of the Vcs-<type> field
+    pub url: String,
+    /// Value of the Vcs-Browser field
+    pub browser: Option<String>,
+}
+
+/// Status of debian/watch.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum WatchStatus {
+    /// There is no watch file
+    Missing,
+    /// The watch file could not be parsed
+    Invalid {
+        /// The parse error
+        error: String,
+    },
+    /// The watch file has no entries
+    Empty {
+        /// Format version
+        version: u32,
+    },
+    /// The watch file has entries
+    Present {
+        /// Format version
+        version: u32,
+        /// Number of entries
+        entries: usize,
+    },
+}
+
+/// Summary of the packaging metadata of a package.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Analysis {
+    /// Name of the source package
+    pub source: Option<String>,
+    /// Names of the binary packages
+    pub binaries: Vec<String>,
+    /// Debhelper compat level
+    pub debhelper_compat: Option<u8>,
+    /// How the debhelper compat level is declared
+    pub debhelper_compat_mechanism: Option<CompatMechanism>,
+    /// dh sequences enabled with dh-sequence-* build dependencies
+    pub sequences: Vec<String>,
+    /// Standards-Version
+    pub standards_version: Option<String>,
+    /// Vcs-* fields
+    pub vcs: Option<VcsInfo>,
+    /// Problems with the Vcs-* fields
+    pub vcs_issues: Vec<VcsIssue>,
+    /// Status of debian/watch
+    pub watch: WatchStatus,
+    /// Number of patches in the quilt series
+    pub patch_count: usize,
+    /// Rules-Requires-Root
+    pub rules_requires_root: Option<String>,
+}
+
+/// Error analyzing a package.
+#[derive(Debug)]
+pub enum AnalyzeError {
+    /// The control file could not be read
+    Editor(Box<crate::editor::EditorError>),
+    /// I/O error
+    Io(std::io::Error),
+    /// The packaging could not be read
+    Package(Box<crate::error::Error>),
+}
+
+impl std::fmt::Display for AnalyzeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalyzeError::Editor(e) => write!(f, "{}", e),
+            AnalyzeError::Io(e) => write!(f, "{}", e),
+            AnalyzeError::Package(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AnalyzeError {}
+
+impl From<crate::editor::EditorError> for AnalyzeError {
+    fn from(e: crate::editor::EditorError) -> Self {
+        AnalyzeError::Editor(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for AnalyzeError {
+    fn from(e: std::io::Error) -> Self {
+        AnalyzeError::Io(e)
+    }
+}
+
+impl From<crate::error::Error> for AnalyzeError {
+    fn from(e: crate::error::Error) -> Self {
+        AnalyzeError::Package(Box::new(e))
+    }
+}
+
+/// Check the Vcs-* fields of a package for problems.
+///
+/// This does not access the network.
+///
+/// # Arguments
+/// * `vcs` - The Vcs-* fields, if any
+pub fn check_vcs(vcs: Option<&VcsInfo>) -> Vec<VcsIssue> {
+    let vcs = match vcs {
+        Some(vcs) => vcs,
+        None => return vec![VcsIssue::Missing],
+    };
+    let mut issues = vec![];
+    let repo_url = vcs
+        .url
+        .parse::<debian_control::vcs::ParsedVcs>()
+        .map_or(vcs.url.clone(), |p| p.repo_url);
+    match url::Url::parse(&repo_url) {
+        Ok(url) => {
+            if let Some(host) = url.host_str() {
+                if OBSOLETE_VCS_HOSTS.contains(&host) {
+                    issues.push(VcsIssue::ObsoleteHost {
+                        host: host.to_string(),
+                    });
+                }
+            }
+            if ["http", "git", "svn", "bzr"].contains(&url.scheme()) {
+                issues.push(VcsIssue::InsecureUrl {
+                    url: repo_url.clone(),
+                });
+            }
+        }
+        // CVS locations are not URLs
+        Err(_) if vcs.vcs_type == "Cvs" => {}
+        Err(_) => issues.push(VcsIssue::InvalidUrl {
+            url: vcs.url.clone(),
+        }),
+    }
+    match vcs.browser.as_deref() {
+        None => issues.push(VcsIssue::MissingBrowser),
+        Some(actual) => {
+            if let Some(expected) = determine_browser_url(&vcs.vcs_type, &vcs.url, Some(false)) {
+                if actual.trim_end_matches('/') != expected.as_str().trim_end_matches('/') {
+                    issues.push(VcsIssue::BrowserMismatch {
+                        actual: actual.to_string(),
+                        expected: expected.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    issues
+}
+
+/// Determine the status of the watch file of a package.
+///
+/// # Arguments
+/// * `base` - Root of the package
+pub fn watch_status(base: &Path) -> std::io::Result<WatchStatus> {
+    let text = match std::fs::read_to_string(base.join("debian/watch")) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(WatchStatus::Missing),
+        Err(e) => return Err(e),
+    };
+    Ok(match text.parse::<crate::watch::WatchFile>() {
+        Ok(wf) if wf.is_empty() => WatchStatus::Empty {
+            version: wf.version(),
+        },
+        Ok(wf) => WatchStatus::Present {
+            version: wf.version(),
+            entries: wf.entries().count(),
+        },
+        Err(e) => WatchStatus::Invalid {
+            error: e.to_string(),
+        },
+    })
+}
+
+fn compat_mechanism(
+    base: &Path,
+    control: Option<&debian_control::Control>,
+) -> Option<CompatMechanism> {
+    if base.join("debian/compat").exists() {
+        return Some(CompatMechanism::CompatFile);
+    }
+    let source = control?.source()?;
+    if source.as_deb822().get("X-DH-Compat").is_some() {
+        return Some(CompatMechanism::XDhCompat);
+    }
+    source
+        .build_depends()?
+        .entries()
+        .flat_map(|entry| entry.relations().collect::<Vec<_>>())
+        .any(|r| r.try_name().as_deref() == Some("debhelper-compat"))
+        .then_some(CompatMechanism::BuildDepends)
+}
+
+fn patch_count(base: &Path) -> std::io::Result<usize> {
+    let path = base
+        .join(crate::patches::DEFAULT_DEBIAN_PATCHES_DIR)
+        .join("series");
+    match std::fs::File::open(path) {
+        Ok(f) => Ok(patchkit::quilt::Series::read(f)?.patches().count()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+/// Analyze the packaging of a package in a directory.
+///
+/// # Arguments
+/// * `base` - Root of the package, containing the debian/ directory
+pub fn analyze(base: &Path) -> Result<Analysis, AnalyzeError> {
+    let control = match std::fs::File::open(base.join("debian/control")) {
+        Ok(f) => debian_control::Control::read_relaxed(f).ok().map(|r| r.0),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut editor = open_control(base)?;
+    let binaries = editor
+        .binaries()
+        .iter()
+        .filter_map(|b| b.name())
+        .collect::<Vec<_>>();
+    let source = editor.source();
+    let vcs = source.as_ref().and_then(|source| {
+        VCS_TYPES.iter().find_map(|vcs_type| {
+            source.get_vcs_url(vcs_type).map(|url| VcsInfo {
+                vcs_type: vcs_type.to_string(),
+                url,
+                browser: source.get_vcs_url("Browser"),
+            })
+        })
+    });
+
+    Ok(Analysis {
+        source: source.as_ref().and_then(|s| s.name()),
+        binaries,
+        debhelper_compat: crate::debhelper::get_debhelper_compat_level(base)?,
+        debhelper_compat_mechanism: compat_mechanism(base, control.as_ref()),
+        sequences: control
+            .as_ref()
+            .and_then(|c| c.source())
+            .map(|s| crate::debhelper::get_sequences(&s).collect())
+            .unwrap_or_default(),
+        standards_version: source.as_ref().and_then(|s| s.standards_version()),
+        vcs_issues: check_vcs(vcs.as_ref()),
+        vcs,
+        watch: watch_status(base)?,
+        patch_count: patch_count(base)?,
+        rules_requires_root: source.as_ref().and_then(|s| s.rules_requires_root()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_vcs() {
+        assert_eq!(vec![VcsIssue::Missing], check_vcs(None));
+        assert_eq!(
+            Vec::<VcsIssue>::new(),
+            check_vcs(Some(&VcsInfo {
+                vcs_type: "Git".to_string(),
+                url: "https://salsa.debian.org/python-team/packages/foo.git".to_string(),
+                browser: Some("https://salsa.debian.org/python-team/packages/foo".to_string()),
+            }))
+        );
+        assert_eq!(
+            vec![
+                VcsIssue::ObsoleteHost {
+                    host: "anonscm.debian.org".to_string()
+                },
+                VcsIssue::InsecureUrl {
+                    url: "git://anonscm.debian.org/foo/bar.git".to_string()
+                },
+                VcsIssue::MissingBrowser,
+            ],
+            check_vcs(Some(&VcsInfo {
+                vcs_type: "Git".to_string(),
+                url: "git://anonscm.debian.org/foo/bar.git".to_string(),
+                browser: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/patches")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            r#"Source: foo
+Build-Depends: debhelper-compat (= 13), dh-sequence-python3
+Standards-Version: 4.7.0
+Rules-Requires-Root: no
+Vcs-Git: https://salsa.debian.org/debian/foo.git
+Vcs-Browser: https://salsa.debian.org/debian/foo
+
+Package: python3-foo
+Architecture: all
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/patches/series"),
+            "a.patch\nb.patch\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/watch"),
+            "version=4\nhttps://example.com/ foo-(.*)\\.tar\\.gz\n",
+        )
+        .unwrap();
+
+        let analysis = analyze(td.path()).unwrap();
+        assert_eq!(Some("foo".to_string()), analysis.source);
+        assert_eq!(vec!["python3-foo".to_string()], analysis.binaries);
+        assert_eq!(Some(13), analysis.debhelper_compat);
+        assert_eq!(
+            Some(CompatMechanism::BuildDepends),
+            analysis.debhelper_compat_mechanism
+        );
+        assert_eq!(vec!["python3".to_string()], analysis.sequences);
+        assert_eq!(Some("4.7.0".to_string()), analysis.standards_version);
+        assert!(analysis.vcs_issues.is_empty());
+        assert_eq!(
+            WatchStatus::Present {
+                version: 4,
+                entries: 1
+            },
+            analysis.watch
+        );
+        assert_eq!(2, analysis.patch_count);
+        assert_eq!(Some("no".to_string()), analysis.rules_requires_root);
+
+        let json = serde_json::to_value(&analysis).unwrap();
+        assert_eq!("Git", json["vcs"]["type"]);
+        assert_eq!("build-depends", json["debhelper_compat_mechanism"]);
+        assert_eq!("present", json["watch"]["status"]);
+    }
+}
@@ -0,0 +1,479 @@
+//! AppStream metainfo files shipped by upstream, and their installation.
+use crate::editor::{Editor, EditorError, FsEditor};
+use crate::install::{InstallEntry, InstallFile};
+use std::path::{Path, PathBuf};
+
+/// Directory metainfo files should be installed into.
+pub const METAINFO_DIR: &str = "usr/share/metainfo";
+
+/// Directory metainfo files used to be installed into.
+pub const LEGACY_METAINFO_DIR: &str = "usr/share/appdata";
+
+/// Error reading a metainfo file or editing the packaging.
+#[derive(Debug)]
+pub enum Error {
+    /// Error editing the packaging.
+    Editor(Box<EditorError>),
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The file is not well-formed XML.
+    Xml(xmltree::ParseError),
+    /// The root element is not an AppStream component.
+    NotAComponent(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Editor(e) => write!(f, "{}", e),
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Xml(e) => write!(f, "Invalid XML: {}", e),
+            Error::NotAComponent(tag) => write!(f, "Unexpected root element <{}>", tag),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<EditorError> for Error {
+    fn from(e: EditorError) -> Self {
+        Error::Editor(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<xmltree::ParseError> for Error {
+    fn from(e: xmltree::ParseError) -> Self {
+        Error::Xml(e)
+    }
+}
+
+/// An AppStream component, as described by a metainfo file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Component {
+    /// Component ID, e.g. "org.gnome.Maps"
+    pub id: Option<String>,
+    /// Type of the component, e.g. "desktop-application"
+    pub kind: Option<String>,
+    /// Human readable name
+    pub name: Option<String>,
+    /// Short summary
+    pub summary: Option<String>,
+    /// License of the metainfo file itself
+    pub metadata_license: Option<String>,
+    /// License of the project
+    pub project_license: Option<String>,
+    /// Whether the file uses the legacy `<application>` root element
+    pub legacy_format: bool,
+    /// Modaliases listed in `<provides>`
+    pub modaliases: Vec<String>,
+}
+
+fn child_text(element: &xmltree::Element, name: &str) -> Option<String> {
+    // Translated variants carry an xml:lang attribute; use the untranslated one.
+    element
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .find(|c| c.name == name && !c.attributes.keys().any(|k| k.ends_with("lang")))
+        .and_then(|c| c.get_text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+impl std::str::FromStr for Component {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let root = xmltree::Element::parse(text.as_bytes())?;
+        let legacy_format = match root.name.as_str() {
+            "component" => false,
+            "application" => true,
+            other => return Err(Error::NotAComponent(other.to_string())),
+        };
+        let modaliases = root
+            .get_child("provides")
+            .map(|p| {
+                p.children
+                    .iter()
+                    .filter_map(|c| c.as_element())
+                    .filter(|c| c.name == "modalias")
+                    .filter_map(|c| c.get_text().map(|t| t.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Component {
+            id: child_text(&root, "id"),
+            kind: root.attributes.get("type").cloned(),
+            name: child_text(&root, "name"),
+            summary: child_text(&root, "summary"),
+            metadata_license: child_text(&root, "metadata_license"),
+            project_license: child_text(&root, "project_license"),
+            legacy_format,
+            modaliases,
+        })
+    }
+}
+
+/// Read a metainfo file.
+///
+/// # Arguments
+/// * `path` - Path to the metainfo file
+pub fn read_metainfo(path: &Path) -> Result<Component, Error> {
+    std::fs::read_to_string(path)?.parse()
+}
+
+/// A problem with a metainfo file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// There is no component ID
+    MissingId,
+    /// The component ID is not a reverse-DNS style name
+    InvalidId(String),
+    /// There is no name
+    MissingName,
+    /// There is no summary
+    MissingSummary,
+    /// There is no metadata license
+    MissingMetadataLicense,
+    /// The file uses the legacy `<application>` root element
+    LegacyFormat,
+    /// The component ID does not correspond to the package name
+    IdMismatch {
+        /// The component ID
+        id: String,
+        /// Name of the package
+        package: String,
+    },
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::MissingId => write!(f, "missing component ID"),
+            Issue::InvalidId(id) => write!(f, "invalid component ID {}", id),
+            Issue::MissingName => write!(f, "missing name"),
+            Issue::MissingSummary => write!(f, "missing summary"),
+            Issue::MissingMetadataLicense => write!(f, "missing metadata license"),
+            Issue::LegacyFormat => write!(f, "legacy <application> root element"),
+            Issue::IdMismatch { id, package } => {
+                write!(f, "component ID {} does not match package {}", id, package)
+            }
+        }
+    }
+}
+
+impl Issue {
+    /// The lintian tag that corresponds to this issue, if any.
+    pub fn lintian_tag(&self) -> Option<&'static str> {
+        match self {
+            Issue::LegacyFormat => Some("appstream-metadata-legacy-format"),
+            Issue::IdMismatch { .. } => None,
+            _ => Some("appstream-metadata-invalid"),
+        }
+    }
+}
+
+/// Check whether a component ID is a valid reverse-DNS style name.
+pub fn is_valid_id(id: &str) -> bool {
+    let parts = id.split('.').collect::<Vec<_>>();
+    parts.len() >= 2
+        && parts.iter().all(|p| {
+            !p.is_empty()
+                && p.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Check whether a component ID corresponds to the name of a package.
+///
+/// The last part of the ID (ignoring a legacy ".desktop" suffix) has to match
+/// the package name, ignoring case and punctuation.
+///
+/// # Arguments
+/// * `id` - The component ID
+/// * `package` - Name of the source or binary package
+pub fn id_matches_package(id: &str, package: &str) -> bool {
+    let id = id.strip_suffix(".desktop").unwrap_or(id);
+    let Some(last) = id.rsplit('.').next() else {
+        return false;
+    };
+    let last = normalize(last);
+    let package = normalize(package);
+    !last.is_empty() && (last == package || package.ends_with(&last) || last.ends_with(&package))
+}
+
+/// Find problems in a metainfo file.
+///
+/// # Arguments
+/// * `component` - The parsed metainfo file
+/// * `package` - Name of the package the file is installed by, if known
+pub fn validate(component: &Component, package: Option<&str>) -> Vec<Issue> {
+    let mut issues = vec![];
+    if component.legacy_format {
+        issues.push(Issue::LegacyFormat);
+    }
+    match component.id.as_deref() {
+        None => issues.push(Issue::MissingId),
+        Some(id) if !is_valid_id(id.strip_suffix(".desktop").unwrap_or(id)) => {
+            issues.push(Issue::InvalidId(id.to_string()))
+        }
+        Some(id) => {
+            if let Some(package) = package.filter(|p| !id_matches_package(id, p)) {
+                issues.push(Issue::IdMismatch {
+                    id: id.to_string(),
+                    package: package.to_string(),
+                });
+            }
+        }
+    }
+    if component.name.is_none() {
+        issues.push(Issue::MissingName);
+    }
+    if component.summary.is_none() {
+        issues.push(Issue::MissingSummary);
+    }
+    if component.metadata_license.is_none() {
+        issues.push(Issue::MissingMetadataLicense);
+    }
+    issues
+}
+
+/// Check whether a file name is that of a metainfo file or a template for one.
+pub fn is_metainfo_file(name: &str) -> bool {
+    let name = name.strip_suffix(".in").unwrap_or(name);
+    name.ends_with(".metainfo.xml") || name.ends_with(".appdata.xml")
+}
+
+/// Find the metainfo files in an upstream source tree.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// Paths of the metainfo files, relative to `base`
+pub fn find_metainfo_files(base: &Path) -> std::io::Result<Vec<PathBuf>> {
+    fn walk(base: &Path, dir: &Path, ret: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(base.join(dir))? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            let path = dir.join(name);
+            if entry.file_type()?.is_dir() {
+                if dir.as_os_str().is_empty() && ["debian", ".git", ".pc", ".bzr"].contains(&name) {
+                    continue;
+                }
+                walk(base, &path, ret)?;
+            } else if is_metainfo_file(name) {
+                ret.push(path);
+            }
+        }
+        Ok(())
+    }
+    let mut ret = vec![];
+    walk(base, Path::new(""), &mut ret)?;
+    ret.sort();
+    Ok(ret)
+}
+
+fn install_files(base: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut ret = std::fs::read_dir(base.join("debian"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n == "install" || n.ends_with(".install"))
+        })
+        .collect::<Vec<_>>();
+    ret.sort();
+    Ok(ret)
+}
+
+/// Install metainfo files into their current location rather than the legacy one.
+///
+/// This fixes the `appstream-metadata-in-legacy-location` lintian tag.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// The install files that were changed
+pub fn fix_legacy_install_location(base: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut changed = vec![];
+    for path in install_files(base)? {
+        let mut editor = FsEditor::<InstallFile>::new(&path, false, false, false)?;
+        let moves = editor
+            .entries()
+            .filter_map(|e| {
+                let destination = e.destination.as_deref()?.trim_matches('/');
+                let rest = destination.strip_prefix(LEGACY_METAINFO_DIR)?;
+                if !rest.is_empty() && !rest.starts_with('/') {
+                    return None;
+                }
+                let new = format!("{}{}", METAINFO_DIR, rest);
+                Some(
+                    e.sources
+                        .iter()
+                        .map(|s| (s.clone(), new.clone()))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        for (source, destination) in moves {
+            editor.set_destination(&source, Some(&destination));
+        }
+        changed.extend(editor.commit()?);
+    }
+    Ok(changed)
+}
+
+/// Make sure a metainfo file is installed into a binary package.
+///
+/// Nothing is changed if one of the install files already lists the file, or
+/// already installs something into [`METAINFO_DIR`].
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `package` - Binary package that should ship the file
+/// * `metainfo` - Path of the metainfo file in the build tree
+///
+/// # Returns
+/// Whether an entry was added
+pub fn ensure_installed(base: &Path, package: &str, metainfo: &str) -> Result<bool, Error> {
+    for path in install_files(base)? {
+        let install = FsEditor::<InstallFile>::new(&path, false, false, false)?;
+        if install.contains(metainfo)
+            || install.entries().any(|e| {
+                e.destination
+                    .as_deref()
+                    .is_some_and(|d| d.trim_matches('/').starts_with(METAINFO_DIR))
+            })
+        {
+            return Ok(false);
+        }
+    }
+    let mut path = base.join("debian").join(format!("{}.install", package));
+    if !path.exists() && base.join("debian/install").exists() {
+        path = base.join("debian/install");
+    }
+    let mut editor = FsEditor::<InstallFile>::new(&path, false, false, false)?;
+    editor.add(InstallEntry::with_destination(&[metainfo], METAINFO_DIR));
+    editor.commit()?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METAINFO: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<component type="desktop-application">
+  <id>org.example.FooBar</id>
+  <metadata_license>CC0-1.0</metadata_license>
+  <project_license>GPL-3.0-or-later</project_license>
+  <name>Foo Bar</name>
+  <name xml:lang="nl">Foe Baar</name>
+  <summary>Bars all the foos</summary>
+  <provides>
+    <modalias>usb:v1130p0202d*</modalias>
+  </provides>
+</component>
+"#;
+
+    #[test]
+    fn test_parse_and_validate() {
+        let component: Component = METAINFO.parse().unwrap();
+        assert_eq!(Some("org.example.FooBar"), component.id.as_deref());
+        assert_eq!(Some("desktop-application"), component.kind.as_deref());
+        assert_eq!(Some("Foo Bar"), component.name.as_deref());
+        assert_eq!(vec!["usb:v1130p0202d*"], component.modaliases);
+        assert!(validate(&component, Some("foo-bar")).is_empty());
+        assert_eq!(
+            vec![Issue::IdMismatch {
+                id: "org.example.FooBar".to_string(),
+                package: "baz".to_string()
+            }],
+            validate(&component, Some("baz"))
+        );
+
+        let legacy: Component = "<application><id>foo.desktop</id></application>"
+            .parse()
+            .unwrap();
+        let issues = validate(&legacy, None);
+        assert_eq!(
+            vec![
+                Issue::LegacyFormat,
+                Issue::InvalidId("foo.desktop".to_string()),
+                Issue::MissingName,
+                Issue::MissingSummary,
+                Issue::MissingMetadataLicense
+            ],
+            issues
+        );
+        assert_eq!(
+            Some("appstream-metadata-legacy-format"),
+            issues[0].lintian_tag()
+        );
+        assert!(matches!(
+            "<foo/>".parse::<Component>(),
+            Err(Error::NotAComponent(_))
+        ));
+    }
+
+    #[test]
+    fn test_id_matches_package() {
+        assert!(id_matches_package("org.gnome.Maps", "gnome-maps"));
+        assert!(id_matches_package("org.kde.kate.desktop", "kate"));
+        assert!(!id_matches_package("org.kde.kate", "vim"));
+    }
+
+    #[test]
+    fn test_install() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::create_dir_all(td.path().join("data")).unwrap();
+        std::fs::write(td.path().join("data/foo.appdata.xml.in"), METAINFO).unwrap();
+        std::fs::write(td.path().join("debian/foo.metainfo.xml"), METAINFO).unwrap();
+        assert_eq!(
+            vec![PathBuf::from("data/foo.appdata.xml.in")],
+            find_metainfo_files(td.path()).unwrap()
+        );
+
+        std::fs::write(
+            td.path().join("debian/foo.install"),
+            "usr/bin\nfoo.appdata.xml usr/share/appdata\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![td.path().join("debian/foo.install")],
+            fix_legacy_install_location(td.path()).unwrap()
+        );
+        assert_eq!(
+            "usr/bin\nfoo.appdata.xml usr/share/metainfo\n",
+            std::fs::read_to_string(td.path().join("debian/foo.install")).unwrap()
+        );
+        assert!(!ensure_installed(td.path(), "foo", "build/foo.metainfo.xml").unwrap());
+
+        std::fs::write(td.path().join("debian/foo.install"), "usr/bin\n").unwrap();
+        assert!(ensure_installed(td.path(), "foo", "build/foo.metainfo.xml").unwrap());
+        assert_eq!(
+            "usr/bin\nbuild/foo.metainfo.xml usr/share/metainfo\n",
+            std::fs::read_to_string(td.path().join("debian/foo.install")).unwrap()
+        );
+    }
+}
@@ -0,0 +1,1338 @@
+//! Functions for working with autopkgtest definitions in debian/tests/control.
+use deb822_lossless::Paragraph;
+use std::path::Path;
+
+/// Path of the autopkgtest control file, relative to the root of the source tree.
+pub const TESTS_CONTROL_PATH: &str = "debian/tests/control";
+
+/// A single test paragraph from debian/tests/control.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TestDefinition {
+    /// Names of test scripts in debian/tests
+    pub tests: Vec<String>,
+    /// Shell command to run instead of test scripts
+    pub test_command: Option<String>,
+    /// Dependencies of the test, possibly including `@` and `@builddeps@`
+    pub depends: Option<String>,
+    /// Restrictions, e.g. "superficial" or "needs-root"
+    pub restrictions: Vec<String>,
+    /// Features, e.g. "test-name=foo"
+    pub features: Vec<String>,
+    /// Architectures the test is limited to
+    pub architecture: Option<String>,
+}
+
+/// Split a field that may be separated by commas, whitespace or both.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+impl TestDefinition {
+    /// Read a test definition from a deb822 paragraph.
+    pub fn from_paragraph(paragraph: &Paragraph) -> Self {
+        TestDefinition {
+            tests: paragraph
+                .get("Tests")
+                .map(|v| split_list(&v))
+                .unwrap_or_default(),
+            test_command: paragraph.get("Test-Command"),
+            depends: paragraph.get("Depends"),
+            restrictions: paragraph
+                .get("Restrictions")
+                .map(|v| split_list(&v))
+                .unwrap_or_default(),
+            features: paragraph
+                .get("Features")
+                .map(|v| split_list(&v))
+                .unwrap_or_default(),
+            architecture: paragraph.get("Architecture"),
+        }
+    }
+
+    /// Render the test definition as a deb822 paragraph.
+    pub fn to_paragraph(&self) -> Paragraph {
+        let mut paragraph = Paragraph::new();
+        if !self.tests.is_empty() {
+            paragraph.insert("Tests", &self.tests.join(" "));
+        }
+        if let Some(test_command) = self.test_command.as_deref() {
+            paragraph.insert("Test-Command", test_command);
+        }
+        if let Some(depends) = self.depends.as_deref() {
+            paragraph.insert("Depends", depends);
+        }
+        if !self.restrictions.is_empty() {
+            paragraph.insert("Restrictions", &self.restrictions.join(", "));
+        }
+        if !self.features.is_empty() {
+            paragraph.insert("Features", &self.features.join(", "));
+        }
+        if let Some(architecture) = self.architecture.as_deref() {
+            paragraph.insert("Architecture", architecture);
+        }
+        paragraph
+    }
+}
+
+/// Render a list of tests as the contents of debian/tests/control.
+pub fn format_tests(tests: &[TestDefinition]) -> String {
+    tests
+        .iter()
+        .map(|t| t.to_paragraph().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Smoke test that imports a Python module with every supported Python version.
+///
+/// # Arguments
+/// * `package` - Binary package that ships the module
+/// * `module` - Name of the module to import
+pub fn python_import_test(package: &str, module: &str) -> TestDefinition {
+    TestDefinition {
+        test_command: Some(format!(
+            "set -e; for py in $(py3versions -s); do echo \"Testing with $py:\"; $py -c \"import {}\"; done",
+            module
+        )),
+        depends: Some(format!("python3-all, {}", package)),
+        restrictions: vec!["allow-stderr".to_string(), "superficial".to_string()],
+        ..Default::default()
+    }
+}
+
+/// Smoke test that runs a command with `--version`.
+///
+/// # Arguments
+/// * `package` - Binary package that ships the command
+/// * `command` - Name of the command
+pub fn command_version_test(package: &str, command: &str) -> TestDefinition {
+    TestDefinition {
+        test_command: Some(format!("{} --version", command)),
+        depends: Some(package.to_string()),
+        restrictions: vec!["superficial".to_string()],
+        ..Default::default()
+    }
+}
+
+/// Test that builds the package and runs the upstream test suite, like `dh_auto_test`.
+pub fn build_test() -> TestDefinition {
+    TestDefinition {
+        test_command: Some("dh_auto_configure && dh_auto_build && dh_auto_test".to_string()),
+        depends: Some("@, @builddeps@".to_string()),
+        restrictions: vec!["allow-stderr".to_string(), "rw-build-tree".to_string()],
+        ..Default::default()
+    }
+}
+
+/// Find the Python module shipped by a python3-* binary package.
+fn python_module_for_package(base: &Path, package: &str) -> Option<String> {
+    let name = package.strip_prefix("python3-")?;
+    let candidates = [name.replace('-', "_"), name.replace('-', "")];
+    for candidate in &candidates {
+        for dir in [base.to_path_buf(), base.join("src")] {
+            if dir.join(candidate).join("__init__.py").exists()
+                || dir.join(format!("{}.py", candidate)).exists()
+            {
+                return Some(candidate.clone());
+            }
+        }
+    }
+    Some(candidates[0].clone())
+}
+
+/// Find the commands installed in /usr/bin by a binary package.
+fn installed_commands(base: &Path, package: &str, only_package: bool) -> Vec<String> {
+    let mut paths = vec![base.join(format!("debian/{}.install", package))];
+    if only_package {
+        paths.push(base.join("debian/install"));
+    }
+    let mut ret = vec![];
+    for path in paths {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        for line in text.lines() {
+            let source = line.split_whitespace().next().unwrap_or_default();
+            let source = source
+                .trim_start_matches("debian/tmp/")
+                .trim_start_matches('/');
+            if let Some(command) = source.strip_prefix("usr/bin/") {
+                if !command.is_empty()
+                    && !command.contains(['*', '?', '[', '/'])
+                    && !ret.iter().any(|c| c == command)
+                {
+                    ret.push(command.to_string());
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// Suggest minimal autopkgtests for a package, based on the ecosystems it uses.
+///
+/// * python3-* binary packages get a test that imports their module
+/// * commands installed in /usr/bin get a `--version` smoke test
+/// * library development packages get a test that builds and runs the upstream test suite
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+pub fn suggest_tests(base: &Path) -> std::io::Result<Vec<TestDefinition>> {
+    let f = match std::fs::File::open(base.join("debian/control")) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let (control, _) = debian_control::Control::read_relaxed(f)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let binaries = control
+        .binaries()
+        .filter_map(|b| b.name())
+        .collect::<Vec<_>>();
+    let mut ret = vec![];
+    let mut has_library = false;
+    for package in &binaries {
+        if let Some(module) = python_module_for_package(base, package) {
+            ret.push(python_import_test(package, &module));
+        } else if package.starts_with("lib") && package.ends_with("-dev") {
+            has_library = true;
+        }
+        for command in installed_commands(base, package, binaries.len() == 1) {
+            ret.push(command_version_test(package, &command));
+        }
+    }
+    if has_library {
+        ret.push(build_test());
+    }
+    Ok(ret)
+}
+
+/// Add suggested autopkgtests to a package that does not have any yet.
+///
+/// Nothing is done if debian/tests/control exists or the source package uses
+/// an autodep8 `Testsuite: autopkgtest-pkg-*` value.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// The tests that were added
+pub fn add_missing_tests(base: &Path) -> std::io::Result<Vec<TestDefinition>> {
+    let path = base.join(TESTS_CONTROL_PATH);
+    if path.exists() {
+        return Ok(vec![]);
+    }
+    if let Ok(f) = std::fs::File::open(base.join("debian/control")) {
+        if let Ok((control, _)) = debian_control::Control::read_relaxed(f) {
+            let testsuite = control
+                .source()
+                .and_then(|s| s.as_deb822().get("Testsuite"))
+                .unwrap_or_default();
+            if testsuite.contains("autopkgtest-pkg-") {
+                return Ok(vec![]);
+            }
+        }
+    }
+    let tests = suggest_tests(base)?;
+    if !tests.is_empty() {
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, format_tests(&tests))?;
+    }
+    Ok(tests)
+}
+
+/// Binary package name patterns for which autodep8 can generate tests, with
+/// the corresponding Testsuite value.
+pub const AUTODEP8_TESTSUITES: &[(&str, &str, &str)] = &[
+    ("python3-", "", "autopkgtest-pkg-python"),
+    ("lib", "-perl", "autopkgtest-pkg-perl"),
+    ("golang-", "-dev", "autopkgtest-pkg-go"),
+    ("librust-", "-dev", "autopkgtest-pkg-rust"),
+    ("ruby-", "", "autopkgtest-pkg-ruby"),
+    ("node-", "", "autopkgtest-pkg-nodejs"),
+    ("r-cran-", "", "autopkgtest-pkg-r"),
+    ("r-bioc-", "", "autopkgtest-pkg-r"),
+    ("elpa-", "", "autopkgtest-pkg-elpa"),
+    ("octave-", "", "autopkgtest-pkg-octave"),
+];
+
+/// Find the autodep8 Testsuite value that applies to a set of binary packages.
+///
+/// # Arguments
+/// * `binaries` - Names of the binary packages
+///
+/// # Returns
+/// The Testsuite value, if all matching packages agree on one
+pub fn applicable_autodep8_testsuite(binaries: &[String]) -> Option<&'static str> {
+    let mut found = None;
+    for binary in binaries {
+        let testsuite = AUTODEP8_TESTSUITES
+            .iter()
+            .find(|(prefix, suffix, _)| binary.starts_with(prefix) && binary.ends_with(suffix))
+            .map(|(_, _, testsuite)| *testsuite);
+        match (found, testsuite) {
+            (_, None) => {}
+            (None, Some(t)) => found = Some(t),
+            (Some(f), Some(t)) if f != t => return None,
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Compute the correct Testsuite value for a source package.
+///
+/// * `autopkgtest` is implied by dpkg-source when debian/tests/control exists,
+///   so it is dropped from the field
+/// * `autopkgtest` is invalid without debian/tests/control
+/// * an applicable autodep8 value is added if no tests are declared at all
+///
+/// # Arguments
+/// * `current` - Current value of the Testsuite field
+/// * `has_tests_control` - Whether debian/tests/control exists
+/// * `binaries` - Names of the binary packages
+pub fn expected_testsuite(
+    current: Option<&str>,
+    has_tests_control: bool,
+    binaries: &[String],
+) -> Option<String> {
+    let mut values = current
+        .map(split_list)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|v| v != "autopkgtest")
+        .collect::<Vec<_>>();
+    if values.is_empty() && !has_tests_control {
+        if let Some(testsuite) = applicable_autodep8_testsuite(binaries) {
+            values.push(testsuite.to_string());
+        }
+    }
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.join(", "))
+    }
+}
+
+/// Make the Testsuite field consistent with the tests the package declares.
+///
+/// See [`expected_testsuite`] for the rules that are applied.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `editor` - Editor for the package's control file
+///
+/// # Returns
+/// The old and new value of the field, if it was changed
+pub fn sync_testsuite(
+    base: &Path,
+    editor: &mut dyn crate::abstract_control::AbstractControlEditor,
+) -> Option<(Option<String>, Option<String>)> {
+    let binaries = editor
+        .binaries()
+        .iter()
+        .filter_map(|b| b.name())
+        .collect::<Vec<_>>();
+    let has_tests_control = base.join(TESTS_CONTROL_PATH).exists();
+    let mut source = editor.source()?;
+    let current = source.testsuite();
+    let expected = expected_testsuite(current.as_deref(), has_tests_control, &binaries);
+    if current == expected {
+        return None;
+    }
+    source.set_testsuite(expected.as_deref());
+    Some((current, expected))
+}
+
+/// Tests that autodep8 would generate for a Testsuite value.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `testsuite` - autodep8 Testsuite value, e.g. "autopkgtest-pkg-python"
+/// * `binaries` - Names of the binary packages
+///
+/// # Returns
+/// The tests, or `None` if the Testsuite value is not supported
+pub fn autodep8_tests(
+    base: &Path,
+    testsuite: &str,
+    binaries: &[String],
+) -> Option<Vec<TestDefinition>> {
+    let test_name = |name: &str| vec![format!("test-name={}", name)];
+    match testsuite {
+        "autopkgtest-pkg-python" => {
+            let import_names =
+                std::fs::read_to_string(base.join("debian/tests/pkg-python/import-name"))
+                    .ok()
+                    .map(|text| split_list(&text));
+            let mut ret = vec![];
+            for package in binaries.iter().filter(|b| b.starts_with("python3-")) {
+                let modules = match import_names.as_ref() {
+                    Some(names) => names.clone(),
+                    None => vec![package["python3-".len()..].replace('-', "_")],
+                };
+                for module in modules {
+                    ret.push(TestDefinition {
+                        test_command: Some(format!(
+                            "set -e ; for py in $(py3versions -r 2>/dev/null) ; do cd \"$AUTOPKGTEST_TMP\" ; echo \"Testing with $py:\" ; $py -c \"import {}; print({})\" ; done",
+                            module, module
+                        )),
+                        depends: Some(format!("python3-all, {}", package)),
+                        restrictions: vec!["allow-stderr".to_string(), "superficial".to_string()],
+                        features: test_name("autodep8-python3"),
+                        ..Default::default()
+                    });
+                }
+            }
+            Some(ret)
+        }
+        "autopkgtest-pkg-perl" => {
+            let runner =
+                |mode: &str, depends: &str, restrictions: &[&str], name: &str| TestDefinition {
+                    test_command: Some(format!("/usr/share/pkg-perl-autopkgtest/runner {}", mode)),
+                    depends: Some(depends.to_string()),
+                    restrictions: restrictions.iter().map(|r| r.to_string()).collect(),
+                    features: test_name(name),
+                    ..Default::default()
+                };
+            Some(vec![
+                runner(
+                    "build-deps",
+                    "@, @builddeps@, pkg-perl-autopkgtest",
+                    &[],
+                    "autodep8-perl-build-deps",
+                ),
+                runner(
+                    "runtime-deps",
+                    "@, pkg-perl-autopkgtest",
+                    &[],
+                    "autodep8-perl",
+                ),
+                runner(
+                    "runtime-deps-and-recommends",
+                    "@, pkg-perl-autopkgtest",
+                    &["needs-recommends"],
+                    "autodep8-perl-recommends",
+                ),
+            ])
+        }
+        "autopkgtest-pkg-go" => Some(vec![TestDefinition {
+            test_command: Some("/usr/bin/dh_golang_autopkgtest".to_string()),
+            depends: Some("@, @builddeps@, dh-golang".to_string()),
+            restrictions: vec!["allow-stderr".to_string()],
+            features: test_name("dh-golang-autopkgtest"),
+            ..Default::default()
+        }]),
+        _ => None,
+    }
+}
+
+/// Replace autodep8 Testsuite values with the equivalent explicit tests.
+///
+/// The generated tests are appended to debian/tests/control, so that they can
+/// be customized. Testsuite values that are not supported are left alone.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `editor` - Editor for the package's control file
+///
+/// # Returns
+/// The tests that were added
+pub fn expand_autodep8_testsuite(
+    base: &Path,
+    editor: &mut dyn crate::abstract_control::AbstractControlEditor,
+) -> std::io::Result<Vec<TestDefinition>> {
+    let binaries = editor
+        .binaries()
+        .iter()
+        .filter_map(|b| b.name())
+        .collect::<Vec<_>>();
+    let Some(mut source) = editor.source() else {
+        return Ok(vec![]);
+    };
+    let Some(current) = source.testsuite() else {
+        return Ok(vec![]);
+    };
+    let mut remaining = vec![];
+    let mut tests = vec![];
+    for value in split_list(&current) {
+        match autodep8_tests(base, &value, &binaries) {
+            Some(generated) => tests.extend(generated),
+            None => remaining.push(value),
+        }
+    }
+    if remaining.len() == split_list(&current).len() {
+        return Ok(vec![]);
+    }
+
+    let path = base.join(TESTS_CONTROL_PATH);
+    let mut text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    if !tests.is_empty() {
+        if !text.is_empty() {
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push('\n');
+        }
+        text.push_str(&format_tests(&tests));
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, text)?;
+    }
+
+    let remaining = remaining.join(", ");
+    let expected = expected_testsuite(
+        Some(remaining.as_str()).filter(|r| !r.is_empty()),
+        path.exists(),
+        &[],
+    );
+    source.set_testsuite(expected.as_deref());
+    Ok(tests)
+}
+
+/// Versions of the packages available in a release, as listed in its
+/// Packages indexes.
+#[derive(Debug, Clone, Default)]
+pub struct PackageIndex {
+    packages: std::collections::HashMap<String, Vec<debversion::Version>>,
+    provides: std::collections::HashMap<String, Vec<Option<debversion::Version>>>,
+}
+
+impl PackageIndex {
+    /// Create an empty package index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the packages listed in the contents of a Packages file.
+    pub fn read_packages(&mut self, text: &str) {
+        let (deb822, _) = deb822_lossless::Deb822::from_str_relaxed(text);
+        for paragraph in deb822.paragraphs() {
+            let Some(name) = paragraph.get("Package") else {
+                continue;
+            };
+            let Some(version) = paragraph.get("Version").and_then(|v| v.parse().ok()) else {
+                continue;
+            };
+            self.packages.entry(name).or_default().push(version);
+            if let Some(provides) = paragraph.get("Provides") {
+                let (provides, _) =
+                    debian_control::lossless::relations::Relations::parse_relaxed(&provides, true);
+                for relation in provides
+                    .entries()
+                    .flat_map(|e| e.relations().collect::<Vec<_>>())
+                {
+                    let Some(name) = relation.try_name() else {
+                        continue;
+                    };
+                    self.provides
+                        .entry(name)
+                        .or_default()
+                        .push(relation.version().map(|(_, v)| v));
+                }
+            }
+        }
+    }
+
+    /// Load the Packages indexes that apt has downloaded for a release.
+    ///
+    /// # Arguments
+    /// * `lists_dir` - apt lists directory, usually /var/lib/apt/lists
+    /// * `release` - Name of the release, e.g. "unstable" or "bookworm"
+    pub fn from_apt_lists(lists_dir: &Path, release: &str) -> std::io::Result<Self> {
+        let mut index = Self::new();
+        let infix = format!("_dists_{}_", release);
+        for entry in std::fs::read_dir(lists_dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.contains(&infix) && name.ends_with("_Packages") {
+                index.read_packages(&std::fs::read_to_string(entry.path())?);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Check whether a single relation can be satisfied by a package in the
+    /// index, either directly or through a Provides.
+    pub fn satisfies(&self, relation: &debian_control::lossless::relations::Relation) -> bool {
+        use debian_control::relations::VersionConstraint;
+        let Some(name) = relation.try_name() else {
+            return false;
+        };
+        let matches = |actual: &debversion::Version| match relation.version() {
+            None => true,
+            Some((VersionConstraint::GreaterThanEqual, v)) => *actual >= v,
+            Some((VersionConstraint::LessThanEqual, v)) => *actual <= v,
+            Some((VersionConstraint::Equal, v)) => *actual == v,
+            Some((VersionConstraint::GreaterThan, v)) => *actual > v,
+            Some((VersionConstraint::LessThan, v)) => *actual < v,
+        };
+        if self
+            .packages
+            .get(&name)
+            .is_some_and(|versions| versions.iter().any(matches))
+        {
+            return true;
+        }
+        self.provides.get(&name).is_some_and(|provided| {
+            provided.iter().any(|v| match v {
+                Some(v) => matches(v),
+                None => relation.version().is_none(),
+            })
+        })
+    }
+}
+
+/// A test dependency that can not be satisfied from a package index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsatisfiableDependency {
+    /// Name of the test, or its command if it has no name
+    pub test: String,
+    /// The dependency, with alternatives
+    pub dependency: String,
+    /// Whether the dependency comes from the expansion of `@builddeps@`
+    pub from_builddeps: bool,
+}
+
+impl std::fmt::Display for UnsatisfiableDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: unsatisfiable dependency {}",
+            self.test, self.dependency
+        )?;
+        if self.from_builddeps {
+            write!(f, " (from @builddeps@)")?;
+        }
+        Ok(())
+    }
+}
+
+impl TestDefinition {
+    /// Name of the test, for use in messages.
+    fn label(&self) -> String {
+        if !self.tests.is_empty() {
+            return self.tests.join(" ");
+        }
+        if let Some(name) = self
+            .features
+            .iter()
+            .find_map(|f| f.strip_prefix("test-name="))
+        {
+            return name.to_string();
+        }
+        self.test_command.clone().unwrap_or_default()
+    }
+}
+
+/// Find test dependencies in debian/tests/control that can not be satisfied
+/// from a package index.
+///
+/// `@` is expanded to the binary packages built from the source, which are
+/// assumed to be installable. `@builddeps@` is expanded to the build
+/// dependencies of the source package.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `index` - Packages available in the target release
+///
+/// # Returns
+/// The dependencies that can not be satisfied
+pub fn unsatisfiable_test_dependencies(
+    base: &Path,
+    index: &PackageIndex,
+) -> std::io::Result<Vec<UnsatisfiableDependency>> {
+    use debian_control::lossless::relations::{Entry, Relations};
+    let text = match std::fs::read_to_string(base.join(TESTS_CONTROL_PATH)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let f = std::fs::File::open(base.join("debian/control"))?;
+    let (control, _) = debian_control::Control::read_relaxed(f)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let builddeps: Vec<Entry> = control
+        .source()
+        .map(|source| {
+            [
+                source.build_depends(),
+                source.build_depends_indep(),
+                source.build_depends_arch(),
+            ]
+            .into_iter()
+            .flatten()
+            .flat_map(|r| r.entries().collect::<Vec<_>>())
+            .collect()
+        })
+        .unwrap_or_default();
+
+    let mut ret = vec![];
+    let (deb822, _) = deb822_lossless::Deb822::from_str_relaxed(&text);
+    for paragraph in deb822.paragraphs() {
+        let test = TestDefinition::from_paragraph(&paragraph);
+        let Some(depends) = test.depends.as_ref() else {
+            continue;
+        };
+        let mut check = |entry: &Entry, from_builddeps: bool| {
+            if !entry.relations().any(|r| index.satisfies(&r)) {
+                ret.push(UnsatisfiableDependency {
+                    test: test.label(),
+                    dependency: entry.to_string().trim().to_string(),
+                    from_builddeps,
+                });
+            }
+        };
+        for item in depends
+            .split(',')
+            .map(|d| d.trim())
+            .filter(|d| !d.is_empty())
+        {
+            match item {
+                "@" | "@recommends@" => {}
+                "@builddeps@" => {
+                    for entry in &builddeps {
+                        check(entry, true);
+                    }
+                }
+                _ => {
+                    let (relations, _) = Relations::parse_relaxed(item, false);
+                    for entry in relations.entries() {
+                        check(&entry, false);
+                    }
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Whether a test paragraph defines the test with the given name.
+fn paragraph_defines_test(paragraph: &Paragraph, name: &str) -> bool {
+    let test = TestDefinition::from_paragraph(paragraph);
+    test.tests.iter().any(|t| t == name) || test.label() == name
+}
+
+/// Apply a change to the paragraphs in debian/tests/control that define a test.
+///
+/// The file is only written if a paragraph was changed.
+fn edit_test_paragraphs(
+    base: &Path,
+    name: &str,
+    mut f: impl FnMut(&mut Paragraph) -> bool,
+) -> std::io::Result<bool> {
+    let path = base.join(TESTS_CONTROL_PATH);
+    let text = std::fs::read_to_string(&path)?;
+    let (deb822, _) = deb822_lossless::Deb822::from_str_relaxed(&text);
+    let mut changed = false;
+    for mut paragraph in deb822.paragraphs() {
+        if paragraph_defines_test(&paragraph, name) {
+            changed |= f(&mut paragraph);
+        }
+    }
+    if changed {
+        std::fs::write(&path, deb822.to_string())?;
+    }
+    Ok(changed)
+}
+
+/// Add or remove a restriction, e.g. "flaky" or "needs-root", on a test.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `name` - Name of the test, as listed in Tests or the test-name feature
+/// * `restriction` - The restriction to add or remove
+/// * `enabled` - Whether the restriction should be present
+///
+/// # Returns
+/// Whether debian/tests/control was changed
+pub fn set_test_restriction(
+    base: &Path,
+    name: &str,
+    restriction: &str,
+    enabled: bool,
+) -> std::io::Result<bool> {
+    edit_test_paragraphs(base, name, |paragraph| {
+        let mut restrictions = paragraph
+            .get("Restrictions")
+            .map(|v| split_list(&v))
+            .unwrap_or_default();
+        let present = restrictions.iter().any(|r| r == restriction);
+        if present == enabled {
+            return false;
+        }
+        if enabled {
+            restrictions.push(restriction.to_string());
+        } else {
+            restrictions.retain(|r| r != restriction);
+        }
+        if restrictions.is_empty() {
+            paragraph.remove("Restrictions");
+        } else {
+            paragraph.set("Restrictions", &restrictions.join(", "));
+        }
+        true
+    })
+}
+
+/// Limit a test to specific architectures, or remove the limit.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `name` - Name of the test, as listed in Tests or the test-name feature
+/// * `architecture` - Architecture field value, e.g. "amd64 arm64" or "!s390x"
+///
+/// # Returns
+/// Whether debian/tests/control was changed
+pub fn set_test_architecture(
+    base: &Path,
+    name: &str,
+    architecture: Option<&str>,
+) -> std::io::Result<bool> {
+    edit_test_paragraphs(base, name, |paragraph| {
+        if paragraph.get("Architecture").as_deref() == architecture {
+            return false;
+        }
+        match architecture {
+            Some(architecture) => paragraph.set("Architecture", architecture),
+            None => paragraph.remove("Architecture"),
+        }
+        true
+    })
+}
+
+/// A suspicious combination of restrictions on a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestrictionIssue {
+    /// The test is marked flaky, but no bug is referenced to track the flakiness.
+    FlakyWithoutBug(String),
+    /// The test needs internet access, but failures to reach the network
+    /// that are reported on stderr will fail the test.
+    NeedsInternetWithoutAllowStderr(String),
+}
+
+impl std::fmt::Display for RestrictionIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RestrictionIssue::FlakyWithoutBug(test) => {
+                write!(f, "{}: marked flaky without a bug reference", test)
+            }
+            RestrictionIssue::NeedsInternetWithoutAllowStderr(test) => {
+                write!(f, "{}: needs-internet without allow-stderr", test)
+            }
+        }
+    }
+}
+
+/// Whether a test paragraph, including its comments, references a bug.
+fn references_bug(text: &str) -> bool {
+    lazy_regex::regex_is_match!(
+        r"(?i)(#\d{4,}|bugs\.debian\.org|https?://\S+/(issues|bugs?)\b|\bLP: ?#\d+)",
+        text
+    )
+}
+
+/// Find suspicious combinations of restrictions in debian/tests/control.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// The issues that were found
+pub fn check_restrictions(base: &Path) -> std::io::Result<Vec<RestrictionIssue>> {
+    let text = match std::fs::read_to_string(base.join(TESTS_CONTROL_PATH)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let (deb822, _) = deb822_lossless::Deb822::from_str_relaxed(&text);
+    let mut ret = vec![];
+    let mut start = 0;
+    for paragraph in deb822.paragraphs() {
+        // Comments preceding the paragraph are not part of it, so include
+        // everything since the end of the previous paragraph.
+        let end = usize::from(paragraph.text_range().end());
+        let context = &text[start..end];
+        start = end;
+        let test = TestDefinition::from_paragraph(&paragraph);
+        let has = |r: &str| test.restrictions.iter().any(|x| x == r);
+        if has("flaky") && !references_bug(context) {
+            ret.push(RestrictionIssue::FlakyWithoutBug(test.label()));
+        }
+        if has("needs-internet") && !has("allow-stderr") {
+            ret.push(RestrictionIssue::NeedsInternetWithoutAllowStderr(
+                test.label(),
+            ));
+        }
+    }
+    Ok(ret)
+}
+
+/// Virtualisation server that autopkgtest runs tests in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VirtServer {
+    /// Run tests directly on the host, without isolation.
+    Null,
+    /// Run tests in a podman container created from an image.
+    Podman(String),
+    /// Run tests in a QEMU virtual machine created from an image.
+    Qemu(std::path::PathBuf),
+    /// Any other virtualisation server, with its arguments.
+    Other(Vec<String>),
+}
+
+impl VirtServer {
+    /// Arguments to pass to autopkgtest after `--`.
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            VirtServer::Null => vec!["null".to_string()],
+            VirtServer::Podman(image) => vec!["podman".to_string(), image.clone()],
+            VirtServer::Qemu(image) => {
+                vec!["qemu".to_string(), image.to_string_lossy().into_owned()]
+            }
+            VirtServer::Other(args) => args.clone(),
+        }
+    }
+}
+
+/// Outcome of a single test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestOutcome {
+    /// The test passed.
+    Pass,
+    /// The test failed, with the reason given by autopkgtest.
+    Fail(String),
+    /// The test was skipped, with the reason given by autopkgtest.
+    Skip(String),
+    /// The test failed, but is marked as flaky.
+    Flaky(String),
+}
+
+/// Result of a single test from an autopkgtest summary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    /// Name of the test, e.g. "smoke" or "command1"
+    pub name: String,
+    /// Outcome of the test
+    pub outcome: TestOutcome,
+    /// Whether the test is marked as superficial
+    pub superficial: bool,
+}
+
+/// Parse the summary file written by `autopkgtest --summary-file`.
+pub fn parse_summary(text: &str) -> Vec<TestResult> {
+    let mut ret = vec![];
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(status)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let mut reason = parts.collect::<Vec<_>>().join(" ");
+        let superficial = reason == "(superficial)";
+        if superficial {
+            reason.clear();
+        }
+        let outcome = match status {
+            "PASS" => TestOutcome::Pass,
+            "FAIL" => TestOutcome::Fail(reason),
+            "SKIP" => TestOutcome::Skip(reason),
+            "FLAKY" => TestOutcome::Flaky(reason),
+            _ => continue,
+        };
+        ret.push(TestResult {
+            name: name.to_string(),
+            outcome,
+            superficial,
+        });
+    }
+    ret
+}
+
+/// Result of an autopkgtest run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    /// Exit code of autopkgtest
+    pub exit_code: i32,
+    /// Per-test results, in the order they were run
+    pub results: Vec<TestResult>,
+}
+
+impl RunSummary {
+    /// Whether no test failed. Skipped and flaky tests are not failures.
+    pub fn success(&self) -> bool {
+        self.exit_code & !(2 | 8) == 0
+    }
+
+    /// Whether no tests were run at all.
+    pub fn no_tests(&self) -> bool {
+        self.exit_code == 8
+    }
+
+    /// Whether the run failed for a reason other than a failing test, e.g.
+    /// because the testbed could not be set up.
+    pub fn erroneous(&self) -> bool {
+        self.exit_code >= 12
+    }
+
+    /// Tests that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &TestResult> {
+        self.results
+            .iter()
+            .filter(|r| matches!(r.outcome, TestOutcome::Fail(_)))
+    }
+}
+
+/// Run autopkgtest against an unpacked source tree.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `virt_server` - Virtualisation server to run the tests in
+/// * `extra_args` - Additional arguments for autopkgtest, e.g. `--test-name`
+pub fn run_autopkgtest(
+    base: &Path,
+    virt_server: &VirtServer,
+    extra_args: &[&str],
+) -> std::io::Result<RunSummary> {
+    let summary = tempfile::NamedTempFile::new()?;
+    let mut cmd = std::process::Command::new("autopkgtest");
+    cmd.arg("--summary-file")
+        .arg(summary.path())
+        .args(extra_args)
+        .arg(base)
+        .arg("--")
+        .args(virt_server.args());
+    let span = tracing::info_span!("command", command = ?cmd);
+    let status = span.in_scope(|| cmd.status())?;
+    let exit_code = status.code().unwrap_or(-1);
+    let text = std::fs::read_to_string(summary.path())?;
+    Ok(RunSummary {
+        exit_code,
+        results: parse_summary(&text),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let paragraph: Paragraph =
+            "Tests: foo bar\nDepends: @\nRestrictions: needs-root, allow-stderr\n"
+                .parse()
+                .unwrap();
+        let test = TestDefinition::from_paragraph(&paragraph);
+        assert_eq!(vec!["foo", "bar"], test.tests);
+        assert_eq!(vec!["needs-root", "allow-stderr"], test.restrictions);
+        assert_eq!(
+            "Tests: foo bar\nDepends: @\nRestrictions: needs-root, allow-stderr\n",
+            test.to_paragraph().to_string()
+        );
+    }
+
+    #[test]
+    fn test_add_missing_tests() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::create_dir_all(td.path().join("src/foo_bar")).unwrap();
+        std::fs::write(td.path().join("src/foo_bar/__init__.py"), "").unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo-bar\n\nPackage: python3-foo-bar\nArchitecture: all\n\nPackage: foo-bar\nArchitecture: all\n\nPackage: libfoo-dev\nArchitecture: any\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/foo-bar.install"),
+            "usr/bin/foo-bar\nusr/share/foo\n",
+        )
+        .unwrap();
+        let tests = add_missing_tests(td.path()).unwrap();
+        assert_eq!(
+            vec![
+                python_import_test("python3-foo-bar", "foo_bar"),
+                command_version_test("foo-bar", "foo-bar"),
+                build_test(),
+            ],
+            tests
+        );
+        let text = std::fs::read_to_string(td.path().join(TESTS_CONTROL_PATH)).unwrap();
+        assert!(text.contains(
+            "Test-Command: foo-bar --version\nDepends: foo-bar\nRestrictions: superficial\n"
+        ));
+
+        // Nothing is added once tests exist.
+        assert!(add_missing_tests(td.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_autodep8_testsuite() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\nTestsuite: autopkgtest-pkg-python\n\nPackage: python3-foo\nArchitecture: all\n",
+        )
+        .unwrap();
+        assert!(add_missing_tests(td.path()).unwrap().is_empty());
+        assert!(!td.path().join(TESTS_CONTROL_PATH).exists());
+    }
+
+    #[test]
+    fn test_expected_testsuite() {
+        let binaries = vec!["python3-foo".to_string(), "foo-doc".to_string()];
+        assert_eq!(
+            None,
+            expected_testsuite(Some("autopkgtest"), true, &binaries)
+        );
+        assert_eq!(
+            Some("autopkgtest-pkg-python".to_string()),
+            expected_testsuite(Some("autopkgtest"), false, &binaries)
+        );
+        assert_eq!(
+            Some("autopkgtest-pkg-python".to_string()),
+            expected_testsuite(None, false, &binaries)
+        );
+        assert_eq!(
+            Some("autopkgtest-pkg-perl".to_string()),
+            expected_testsuite(Some("autopkgtest-pkg-perl"), true, &binaries)
+        );
+        assert_eq!(None, expected_testsuite(None, false, &["foo".to_string()]));
+        assert_eq!(
+            None,
+            applicable_autodep8_testsuite(&["python3-foo".to_string(), "ruby-foo".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_sync_testsuite() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\nTestsuite: autopkgtest\n\nPackage: python3-foo\nArchitecture: all\n",
+        )
+        .unwrap();
+        let mut editor =
+            crate::control::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+        assert_eq!(
+            Some((
+                Some("autopkgtest".to_string()),
+                Some("autopkgtest-pkg-python".to_string())
+            )),
+            sync_testsuite(td.path(), &mut editor)
+        );
+        crate::abstract_control::AbstractControlEditor::commit(&editor).unwrap();
+        assert_eq!(
+            "Source: foo\nTestsuite: autopkgtest-pkg-python\n\nPackage: python3-foo\nArchitecture: all\n",
+            std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+        );
+        let mut editor =
+            crate::control::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+        assert_eq!(None, sync_testsuite(td.path(), &mut editor));
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        let results = parse_summary(
+            "smoke                PASS\ncommand1             PASS (superficial)\nunit                 FAIL non-zero exit status 1\nroot                 SKIP Test requires machine-level isolation but testbed does not provide that\n",
+        );
+        assert_eq!(
+            vec![
+                TestResult {
+                    name: "smoke".to_string(),
+                    outcome: TestOutcome::Pass,
+                    superficial: false
+                },
+                TestResult {
+                    name: "command1".to_string(),
+                    outcome: TestOutcome::Pass,
+                    superficial: true
+                },
+                TestResult {
+                    name: "unit".to_string(),
+                    outcome: TestOutcome::Fail("non-zero exit status 1".to_string()),
+                    superficial: false
+                },
+                TestResult {
+                    name: "root".to_string(),
+                    outcome: TestOutcome::Skip(
+                        "Test requires machine-level isolation but testbed does not provide that"
+                            .to_string()
+                    ),
+                    superficial: false
+                },
+            ],
+            results
+        );
+        let summary = RunSummary {
+            exit_code: 6,
+            results,
+        };
+        assert!(!summary.success());
+        assert_eq!(1, summary.failures().count());
+        assert!(RunSummary {
+            exit_code: 2,
+            results: vec![]
+        }
+        .success());
+    }
+
+    #[test]
+    fn test_virt_server_args() {
+        assert_eq!(vec!["null"], VirtServer::Null.args());
+        assert_eq!(
+            vec!["podman", "debian:unstable"],
+            VirtServer::Podman("debian:unstable".to_string()).args()
+        );
+    }
+
+    #[test]
+    fn test_expand_autodep8_testsuite() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/tests")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\nTestsuite: autopkgtest-pkg-python\n\nPackage: python3-foo-bar\nArchitecture: all\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join(TESTS_CONTROL_PATH),
+            "Tests: smoke\nDepends: @\n",
+        )
+        .unwrap();
+        let mut editor =
+            crate::control::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+        let tests = expand_autodep8_testsuite(td.path(), &mut editor).unwrap();
+        assert_eq!(1, tests.len());
+        assert_eq!(
+            Some("python3-all, python3-foo-bar"),
+            tests[0].depends.as_deref()
+        );
+        crate::abstract_control::AbstractControlEditor::commit(&editor).unwrap();
+        assert_eq!(
+            "Source: foo\n\nPackage: python3-foo-bar\nArchitecture: all\n",
+            std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+        );
+        let text = std::fs::read_to_string(td.path().join(TESTS_CONTROL_PATH)).unwrap();
+        assert!(
+            text.starts_with("Tests: smoke\nDepends: @\n\nTest-Command: set -e"),
+            "{}",
+            text
+        );
+        assert!(text.contains("import foo_bar; print(foo_bar)"));
+        assert!(text.ends_with("Features: test-name=autodep8-python3\n"));
+    }
+
+    #[test]
+    fn test_autodep8_tests_unsupported() {
+        assert_eq!(
+            None,
+            autodep8_tests(Path::new("."), "autopkgtest-pkg-ruby", &[])
+        );
+        assert_eq!(
+            3,
+            autodep8_tests(Path::new("."), "autopkgtest-pkg-perl", &[])
+                .unwrap()
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_package_index_satisfies() {
+        use debian_control::lossless::relations::Relation;
+        let mut index = PackageIndex::new();
+        index.read_packages(
+            "Package: python3-foo\nVersion: 1.2-1\n\nPackage: mawk\nVersion: 1.3.4-1\nProvides: awk\n",
+        );
+        assert!(index.satisfies(&"python3-foo (>= 1.0)".parse::<Relation>().unwrap()));
+        assert!(!index.satisfies(&"python3-foo (>= 2.0)".parse::<Relation>().unwrap()));
+        assert!(index.satisfies(&"awk".parse::<Relation>().unwrap()));
+        assert!(!index.satisfies(&"awk (>= 1)".parse::<Relation>().unwrap()));
+        assert!(!index.satisfies(&"python3-bar".parse::<Relation>().unwrap()));
+    }
+
+    #[test]
+    fn test_unsatisfiable_test_dependencies() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/tests")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\nBuild-Depends: debhelper-compat (= 13), python3-missing\n\nPackage: python3-foo\nArchitecture: all\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join(TESTS_CONTROL_PATH),
+            "Tests: unit\nDepends: @, @builddeps@, python3-pytest | python3-nose\n\nTest-Command: true\nDepends: python3-gone\nFeatures: test-name=smoke\n",
+        )
+        .unwrap();
+        let mut index = PackageIndex::new();
+        index.read_packages(
+            "Package: debhelper\nVersion: 13.11\nProvides: debhelper-compat (= 13)\n\nPackage: python3-nose\nVersion: 1.3.7-1\n",
+        );
+        let issues = unsatisfiable_test_dependencies(td.path(), &index).unwrap();
+        assert_eq!(
+            vec![
+                UnsatisfiableDependency {
+                    test: "unit".to_string(),
+                    dependency: "python3-missing".to_string(),
+                    from_builddeps: true,
+                },
+                UnsatisfiableDependency {
+                    test: "smoke".to_string(),
+                    dependency: "python3-gone".to_string(),
+                    from_builddeps: false,
+                },
+            ],
+            issues
+        );
+        assert_eq!(
+            "unit: unsatisfiable dependency python3-missing (from @builddeps@)",
+            issues[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_set_test_restriction() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/tests")).unwrap();
+        std::fs::write(
+            td.path().join(TESTS_CONTROL_PATH),
+            "Tests: unit\nDepends: @\n\n# Smoke test\nTest-Command: true\nRestrictions: superficial\nFeatures: test-name=smoke\n",
+        )
+        .unwrap();
+        assert!(set_test_restriction(td.path(), "unit", "needs-root", true).unwrap());
+        assert!(!set_test_restriction(td.path(), "unit", "needs-root", true).unwrap());
+        assert!(set_test_restriction(td.path(), "smoke", "superficial", false).unwrap());
+        assert!(set_test_architecture(td.path(), "smoke", Some("amd64")).unwrap());
+        assert!(!set_test_architecture(td.path(), "missing", None).unwrap());
+        assert_eq!(
+            "Tests: unit\nDepends: @\nRestrictions: needs-root\n\n# Smoke test\nTest-Command: true\nFeatures: test-name=smoke\nArchitecture: amd64\n",
+            std::fs::read_to_string(td.path().join(TESTS_CONTROL_PATH)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_check_restrictions() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/tests")).unwrap();
+        std::fs::write(
+            td.path().join(TESTS_CONTROL_PATH),
+            "Tests: a\nRestrictions: flaky, needs-internet\n\n# Times out on slow builders, see #1012345\nTests: b\nRestrictions: flaky, needs-internet, allow-stderr\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                RestrictionIssue::FlakyWithoutBug("a".to_string()),
+                RestrictionIssue::NeedsInternetWithoutAllowStderr("a".to_string()),
+            ],
+            check_restrictions(td.path()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Running an analysis or fixer over many packages in parallel.
+//!
+//! This is meant for archive-scale runs over a directory of package
+//! checkouts, such as a team monorepo or debcargo-conf. Packages are
+//! processed on a thread pool; an error or panic while processing one
+//! package is recorded in its result and does not affect the others.
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default number of directory levels below the root to look for packages in.
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+/// Whether a directory holds a package.
+fn is_package_dir(path: &Path) -> bool {
+    ["debian/control", "debian/debcargo.toml", "debian/changelog"]
+        .iter()
+        .any(|p| path.join(p).is_file())
+}
+
+/// Find the packages in a directory.
+///
+/// A directory is a package if it has a debian/ directory with a control
+/// file, a changelog or a debcargo.toml file; packages are not searched
+/// for packages nested inside them. Hidden directories are skipped.
+///
+/// # Arguments
+/// * `root` - Directory to search
+/// * `max_depth` - Number of directory levels below `root` to search
+///
+/// # Returns
+/// The package directories, sorted by path
+pub fn find_packages(root: &Path, max_depth: usize) -> std::io::Result<Vec<PathBuf>> {
+    let mut ret = vec![];
+    if is_package_dir(root) {
+        ret.push(root.to_path_buf());
+        return Ok(ret);
+    }
+    let mut todo = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = todo.pop() {
+        if depth >= max_depth {
+            continue;
+        }
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() || entry.file_name().to_string_lossy().starts_with('.')
+            {
+                continue;
+            }
+            let path = entry.path();
+            if is_package_dir(&path) {
+                ret.push(path);
+            } else {
+                todo.push((path, depth + 1));
+            }
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Progress of a batch run, as reported after each package.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// The package that was just processed
+    pub path: &'a Path,
+    /// Whether processing the package succeeded
+    pub succeeded: bool,
+    /// Number of packages processed so far
+    pub completed: usize,
+    /// Total number of packages
+    pub total: usize,
+}
+
+/// The outcome of processing a single package.
+#[derive(Debug)]
+pub enum Outcome<T, E> {
+    /// The package was processed successfully
+    Success(T),
+    /// Processing the package failed
+    Failure(E),
+    /// Processing the package panicked; holds the panic message
+    Panic(String),
+}
+
+impl<T, E> Outcome<T, E> {
+    /// Whether the package was processed successfully.
+    pub fn is_success(&self) -> bool {
+        matches!(self, Outcome::Success(_))
+    }
+}
+
+/// The result of processing a single package.
+#[derive(Debug)]
+pub struct PackageResult<T, E> {
+    /// Path to the package
+    pub path: PathBuf,
+    /// What happened
+    pub outcome: Outcome<T, E>,
+}
+
+/// The results of a batch run.
+#[derive(Debug)]
+pub struct BatchReport<T, E> {
+    /// Results for each package, in the order the packages were given
+    pub results: Vec<PackageResult<T, E>>,
+}
+
+impl<T, E> BatchReport<T, E> {
+    /// The packages that were processed successfully, with their results.
+    pub fn successes(&self) -> impl Iterator<Item = (&Path, &T)> {
+        self.results.iter().filter_map(|r| match &r.outcome {
+            Outcome::Success(value) => Some((r.path.as_path(), value)),
+            _ => None,
+        })
+    }
+
+    /// The packages that failed or panicked.
+    pub fn failures(&self) -> impl Iterator<Item = &PackageResult<T, E>> {
+        self.results.iter().filter(|r| !r.outcome.is_success())
+    }
+
+    /// Number of packages that were processed successfully.
+    pub fn success_count(&self) -> usize {
+        self.successes().count()
+    }
+
+    /// Number of packages that failed or panicked.
+    pub fn failure_count(&self) -> usize {
+        self.failures().count()
+    }
+}
+
+/// Options for a batch run.
+#[derive(Debug, Clone, Default)]
+pub struct BatchOptions {
+    /// Number of threads to use, or `None` for one per CPU
+    pub threads: Option<usize>,
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Process packages in parallel.
+///
+/// # Arguments
+/// * `packages` - Paths to the packages to process
+/// * `options` - Options for the run
+/// * `f` - Function to run for each package
+/// * `progress` - Called after each package has been processed
+pub fn run<T, E, F, P>(
+    packages: &[PathBuf],
+    options: &BatchOptions,
+    f: F,
+    progress: P,
+) -> Result<BatchReport<T, E>, rayon::ThreadPoolBuildError>
+where
+    T: Send,
+    E: Send,
+    F: Fn(&Path) -> Result<T, E> + Sync,
+    P: Fn(&Progress) + Sync,
+{
+    let mut builder = rayon::ThreadPoolBuilder::new();
+    if let Some(threads) = options.threads {
+        builder = builder.num_threads(threads);
+    }
+    let pool = builder.build()?;
+    let completed = AtomicUsize::new(0);
+    let results = pool.install(|| {
+        packages
+            .par_iter()
+            .map(|path| {
+                let outcome =
+                    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(path))) {
+                        Ok(Ok(value)) => Outcome::Success(value),
+                        Ok(Err(e)) => Outcome::Failure(e),
+                        Err(payload) => {
+                            let message = panic_message(payload);
+                            log::warn!("{}: panicked: {}", path.display(), message);
+                            Outcome::Panic(message)
+                        }
+                    };
+                progress(&Progress {
+                    path,
+                    succeeded: outcome.is_success(),
+                    completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                    total: packages.len(),
+                });
+                PackageResult {
+                    path: path.clone(),
+                    outcome,
+                }
+            })
+            .collect()
+    });
+    Ok(BatchReport { results })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_package(path: &Path, file: &str) {
+        std::fs::create_dir_all(path.join("debian")).unwrap();
+        std::fs::write(path.join("debian").join(file), "").unwrap();
+    }
+
+    #[test]
+    fn test_find_packages() {
+        let td = tempfile::tempdir().unwrap();
+        add_package(&td.path().join("foo"), "control");
+        add_package(&td.path().join("src/bar"), "debcargo.toml");
+        add_package(&td.path().join("src/bar/nested"), "control");
+        add_package(&td.path().join(".hidden/baz"), "control");
+        add_package(&td.path().join("a/b/c/deep"), "changelog");
+        std::fs::create_dir_all(td.path().join("empty")).unwrap();
+
+        assert_eq!(
+            vec![td.path().join("foo"), td.path().join("src/bar")],
+            find_packages(td.path(), DEFAULT_MAX_DEPTH).unwrap()
+        );
+        assert_eq!(
+            vec![
+                td.path().join("a/b/c/deep"),
+                td.path().join("foo"),
+                td.path().join("src/bar")
+            ],
+            find_packages(td.path(), 4).unwrap()
+        );
+        assert_eq!(
+            vec![td.path().join("foo")],
+            find_packages(&td.path().join("foo"), DEFAULT_MAX_DEPTH).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let packages = ["ok", "fail", "panic", "ok2"]
+            .iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        let seen = std::sync::Mutex::new(vec![]);
+        let report = run(
+            &packages,
+            &BatchOptions { threads: Some(2) },
+            |path| match path.to_str().unwrap() {
+                "fail" => Err("failed"),
+                "panic" => panic!("boom"),
+                name => Ok(name.len()),
+            },
+            |progress| {
+                assert_eq!(4, progress.total);
+                seen.lock()
+                    .unwrap()
+                    .push((progress.completed, progress.succeeded));
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            packages,
+            report
+                .results
+                .iter()
+                .map(|r| r.path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(Path::new("ok"), &2), (Path::new("ok2"), &3)],
+            report.successes().collect::<Vec<_>>()
+        );
+        assert_eq!(2, report.failure_count());
+        assert!(matches!(
+            report.results[1].outcome,
+            Outcome::Failure("failed")
+        ));
+        assert!(matches!(&report.results[2].outcome, Outcome::Panic(m) if m == "boom"));
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort();
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            seen.iter().map(|(n, _)| *n).collect::<Vec<_>>()
+        );
+        assert_eq!(2, seen.iter().filter(|(_, ok)| *ok).count());
+    }
+}
@@ -0,0 +1,57 @@
+use clap::Parser;
+use debian_workbench::analyze::analyze;
+
+use std::io::Write as _;
+
+#[derive(Parser, Debug)]
+#[command(author, version)]
+struct Args {
+    /// Enable debug output
+    #[arg(long, default_value_t = false)]
+    debug: bool,
+
+    /// directory to run in
+    #[arg(short, long, default_value = std::env::current_dir().unwrap().into_os_string(), value_name = "DIR")]
+    directory: std::path::PathBuf,
+
+    /// Print the report on a single line, for use in scripted pipelines
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    env_logger::builder()
+        .format(|buf, record| writeln!(buf, "{}", record.args()))
+        .filter(
+            None,
+            if args.debug {
+                log::LevelFilter::Debug
+            } else if args.json {
+                log::LevelFilter::Warn
+            } else {
+                log::LevelFilter::Info
+            },
+        )
+        .init();
+
+    let analysis = match analyze(&args.directory) {
+        Ok(analysis) => analysis,
+        Err(e) => {
+            log::error!("Unable to analyze {}: {}", args.directory.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    for issue in &analysis.vcs_issues {
+        log::debug!("{}", issue);
+    }
+
+    let output = if args.json {
+        serde_json::to_string(&analysis)
+    } else {
+        serde_json::to_string_pretty(&analysis)
+    };
+    println!("{}", output.unwrap());
+}
@@ -0,0 +1,200 @@
+use clap::Parser;
+use debian_workbench::debhelper::{bump_debhelper_compat, BumpCompatError};
+use debian_workbench::package_dir::PackageDir;
+use debian_workbench::svp_report::{Failure, Reporter, Success};
+
+use std::io::Write as _;
+
+#[derive(Parser, Debug)]
+#[command(author, version)]
+struct Args {
+    /// Enable debug output
+    #[arg(long, default_value_t = false)]
+    debug: bool,
+
+    /// directory to run in
+    #[arg(short, long, default_value = std::env::current_dir().unwrap().into_os_string(), value_name = "DIR")]
+    directory: std::path::PathBuf,
+
+    /// Compat level to bump to
+    #[arg(long, value_name = "N")]
+    to: Option<u8>,
+
+    /// Oldest release the package should remain buildable on
+    #[arg(long, value_name = "RELEASE")]
+    compat_release: Option<String>,
+
+    /// Do not add a changelog entry
+    #[arg(long, default_value_t = false)]
+    no_changelog: bool,
+
+    /// Write a silver-platter compatible JSON report to this file
+    #[arg(long, value_name = "FILE")]
+    report: Option<std::path::PathBuf>,
+
+    /// Print the report as JSON on standard output, rather than human-readable output
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+/// Log an error, report it and exit.
+fn fail(reporter: &Reporter, code: &str, description: &str) -> ! {
+    log::error!("{}", description);
+    if let Err(e) = reporter.report(Failure::new(code, description)) {
+        log::error!("Unable to write report: {}", e);
+    }
+    std::process::exit(1);
+}
+
+/// Determine the compat level to bump to.
+fn target_level(args: &Args, reporter: &Reporter) -> Option<u8> {
+    let Some(release) = args.compat_release.as_deref() else {
+        if let Some(to) = args.to {
+            return Some(to);
+        }
+        return match debian_workbench::debhelper::highest_stable_compat_level() {
+            Ok(level) => Some(level),
+            Err(e) => {
+                log::error!("Unable to determine the highest stable compat level: {}", e);
+                None
+            }
+        };
+    };
+    let codename = match debian_workbench::release_info::resolve_release_codename(release, None) {
+        Ok(Some(codename)) => codename,
+        Ok(None) => fail(
+            reporter,
+            "unknown-release",
+            &format!("Unknown release: {}", release),
+        ),
+        Err(e) => fail(
+            reporter,
+            "release-info-failed",
+            &format!("Unable to resolve release {}: {}", release, e),
+        ),
+    };
+    let levels = match debian_workbench::debhelper::supported_compat_levels_for_release(&codename) {
+        Ok(Some(levels)) => levels,
+        Ok(None) => fail(
+            reporter,
+            "unknown-compat-level",
+            &format!(
+                "Unable to find the debhelper compat levels for {}",
+                codename
+            ),
+        ),
+        Err(e) => fail(
+            reporter,
+            "compat-levels-failed",
+            &format!(
+                "Unable to determine the debhelper compat levels for {}: {}",
+                codename, e
+            ),
+        ),
+    };
+    let release_level = levels.highest_stable_compat_level;
+    Some(args.to.map_or(release_level, |to| to.min(release_level)))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    env_logger::builder()
+        .format(|buf, record| writeln!(buf, "{}", record.args()))
+        .filter(
+            None,
+            if args.debug {
+                log::LevelFilter::Debug
+            } else if args.json {
+                log::LevelFilter::Warn
+            } else {
+                log::LevelFilter::Info
+            },
+        )
+        .init();
+
+    #[cfg(feature = "vcs")]
+    breezyshim::init();
+
+    let reporter = Reporter::new(args.report.as_deref(), args.json);
+
+    let package = match PackageDir::open(&args.directory) {
+        Ok(package) => package,
+        Err(e) => fail(
+            &reporter,
+            "open-tree-failed",
+            &format!("Unable to open tree at {}: {}", args.directory.display(), e),
+        ),
+    };
+
+    let Some(to) = target_level(&args, &reporter) else {
+        fail(
+            &reporter,
+            "unknown-compat-level",
+            "Unable to determine the compat level to bump to",
+        );
+    };
+
+    let base = package.base()?;
+    let bump = match bump_debhelper_compat(&base, to) {
+        Ok(bump) => bump,
+        Err(BumpCompatError::AlreadyAtLevel(level)) => {
+            let description = format!("Already at compat level {}, nothing to do.", level);
+            log::info!("{}", description);
+            reporter.report(Failure::nothing_to_do(&description))?;
+            return Ok(());
+        }
+        Err(e) => fail(
+            &reporter,
+            "bump-failed",
+            &format!("Unable to bump debhelper compat level: {}", e),
+        ),
+    };
+
+    let changes = bump
+        .changes()
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    if !args.no_changelog {
+        if let Err(e) = package.add_changelog_entry(bump.changes().as_slice()) {
+            fail(
+                &reporter,
+                "changelog-update-failed",
+                &format!("Unable to add changelog entry: {}", e),
+            );
+        }
+    }
+
+    let description = format!(
+        "Bump debhelper compat level from {} to {}.",
+        bump.from, bump.to
+    );
+    let mut success = Success::new(&description, &changes);
+    success.context = Some(serde_json::json!({
+        "from": bump.from,
+        "to": bump.to,
+        "changes": changes,
+        "certainty": bump.certainty(),
+        "follow-ups": bump.follow_ups,
+    }));
+    reporter.report(success)?;
+    if reporter.json() {
+        return Ok(());
+    }
+
+    println!(
+        "Bumped debhelper compat level from {} to {}.",
+        bump.from, bump.to
+    );
+    for change in &changes {
+        println!("  * {}", change);
+    }
+    if !bump.follow_ups.is_empty() {
+        println!("Manual follow-ups:");
+        for follow_up in &bump.follow_ups {
+            println!("  * {}", follow_up);
+        }
+    }
+    Ok(())
+}
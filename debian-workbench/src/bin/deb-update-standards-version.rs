@@ -0,0 +1,180 @@
+use clap::Parser;
+use debian_workbench::lintian::{
+    latest_standards_version_opt, unresolved_checklist_items, StandardsVersion,
+};
+use debian_workbench::package_dir::PackageDir;
+use debian_workbench::svp_report::{Failure, Reporter, Success};
+
+use std::io::Write as _;
+
+#[derive(Parser, Debug)]
+#[command(author, version)]
+struct Args {
+    /// Enable debug output
+    #[arg(long, default_value_t = false)]
+    debug: bool,
+
+    /// directory to run in
+    #[arg(short, long, default_value = std::env::current_dir().unwrap().into_os_string(), value_name = "DIR")]
+    directory: std::path::PathBuf,
+
+    /// Standards version to update to (defaults to the latest policy release)
+    #[arg(long, value_name = "VERSION")]
+    to: Option<StandardsVersion>,
+
+    /// Update even if there are unresolved items on the upgrading checklist
+    #[arg(long, default_value_t = false)]
+    force: bool,
+
+    /// Do not add a changelog entry
+    #[arg(long, default_value_t = false)]
+    no_changelog: bool,
+
+    /// Write a silver-platter compatible JSON report to this file
+    #[arg(long, value_name = "FILE")]
+    report: Option<std::path::PathBuf>,
+
+    /// Print the report as JSON on standard output, rather than human-readable output
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+/// Log an error, report it and exit.
+fn fail(reporter: &Reporter, code: &str, description: &str) -> ! {
+    log::error!("{}", description);
+    if let Err(e) = reporter.report(Failure::new(code, description)) {
+        log::error!("Unable to write report: {}", e);
+    }
+    std::process::exit(1);
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    env_logger::builder()
+        .format(|buf, record| writeln!(buf, "{}", record.args()))
+        .filter(
+            None,
+            if args.debug {
+                log::LevelFilter::Debug
+            } else if args.json {
+                log::LevelFilter::Warn
+            } else {
+                log::LevelFilter::Info
+            },
+        )
+        .init();
+
+    #[cfg(feature = "vcs")]
+    breezyshim::init();
+
+    let reporter = Reporter::new(args.report.as_deref(), args.json);
+
+    let package = match PackageDir::open(&args.directory) {
+        Ok(package) => package,
+        Err(e) => fail(
+            &reporter,
+            "open-tree-failed",
+            &format!("Unable to open tree at {}: {}", args.directory.display(), e),
+        ),
+    };
+
+    let Some(to) = args.to.clone().or_else(latest_standards_version_opt) else {
+        fail(
+            &reporter,
+            "unknown-standards-version",
+            "Unable to determine the latest standards version; specify one with --to",
+        );
+    };
+
+    let mut editor = match package.edit_control() {
+        Ok(editor) => editor,
+        Err(e) => fail(
+            &reporter,
+            "control-open-failed",
+            &format!("Unable to open control file: {}", e),
+        ),
+    };
+    let Some(mut source) = editor.source() else {
+        fail(
+            &reporter,
+            "missing-source-package",
+            "No source package found in control file",
+        );
+    };
+
+    let current = match source
+        .standards_version()
+        .map(|v| v.parse::<StandardsVersion>())
+    {
+        Some(Ok(version)) => Some(version),
+        Some(Err(e)) => fail(
+            &reporter,
+            "invalid-standards-version",
+            &format!("Unable to parse current Standards-Version: {}", e),
+        ),
+        None => None,
+    };
+    if current.as_ref().is_some_and(|current| current >= &to) {
+        let description = format!("Standards-Version is already {}, nothing to do.", to);
+        log::info!("{}", description);
+        reporter.report(Failure::nothing_to_do(&description))?;
+        return Ok(());
+    }
+
+    let base = package.base()?;
+    let unresolved = unresolved_checklist_items(&base, current.as_ref(), &to)?;
+    if !unresolved.is_empty() {
+        for item in &unresolved {
+            log::warn!("Unresolved upgrading checklist item: {}", item);
+        }
+        if !args.force {
+            fail(
+                &reporter,
+                "unresolved-checklist-items",
+                &format!(
+                    "Not updating Standards-Version to {}; resolve the items above or use --force",
+                    to
+                ),
+            );
+        }
+    }
+
+    source.set_standards_version(&to.to_string());
+    std::mem::drop(source);
+    if let Err(e) = editor.commit() {
+        fail(
+            &reporter,
+            "control-update-failed",
+            &format!("Unable to update control file: {}", e),
+        );
+    }
+
+    let entry = format!("Bump Standards-Version to {}.", to);
+    if !args.no_changelog {
+        if let Err(e) = package.add_changelog_entry(&[entry.as_str()]) {
+            fail(
+                &reporter,
+                "changelog-update-failed",
+                &format!("Unable to add changelog entry: {}", e),
+            );
+        }
+    }
+
+    let mut success = Success::new(&entry, std::slice::from_ref(&entry));
+    success.context = Some(serde_json::json!({
+        "old-version": current.as_ref().map(|v| v.to_string()),
+        "new-version": to.to_string(),
+        "unresolved-checklist-items": unresolved.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+    }));
+    reporter.report(success)?;
+    if reporter.json() {
+        return Ok(());
+    }
+
+    match current {
+        Some(current) => println!("Updated Standards-Version from {} to {}.", current, to),
+        None => println!("Set Standards-Version to {}.", to),
+    }
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use clap::Parser;
+use debian_workbench::svp_report::{Failure, Reporter, Success};
+use debian_workbench::vcs_migrate::{
+    migrate_package, parse_package_list, target_url, Layout, MigrateOptions, MigrationState,
+};
+
+use std::io::Write as _;
+
+#[derive(Parser, Debug)]
+#[command(author, version)]
+struct Args {
+    /// Enable debug output
+    #[arg(long, default_value_t = false)]
+    debug: bool,
+
+    /// File listing the packages to migrate, one "PACKAGE URL" pair per line
+    #[arg(value_name = "FILE")]
+    packages: std::path::PathBuf,
+
+    /// URL of the new repositories, with {package} replaced by the package name
+    #[arg(long, value_name = "TEMPLATE")]
+    target: String,
+
+    /// Directory to keep local clones in
+    #[arg(long, default_value = ".", value_name = "DIR")]
+    workdir: std::path::PathBuf,
+
+    /// File to record the progress of the migration in, so that it can be resumed
+    #[arg(long, default_value = "vcs-migrate-state.json", value_name = "FILE")]
+    state: std::path::PathBuf,
+
+    /// Rename branches following DEP-14 (e.g. master to debian/latest)
+    #[arg(long, default_value_t = false)]
+    dep14: bool,
+
+    /// Do not create the repositories at the new location
+    #[arg(long, default_value_t = false)]
+    no_create: bool,
+
+    /// Write a silver-platter compatible JSON report to this file
+    #[arg(long, value_name = "FILE")]
+    report: Option<std::path::PathBuf>,
+
+    /// Print the report as JSON on standard output, rather than human-readable output
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    env_logger::builder()
+        .format(|buf, record| writeln!(buf, "{}", record.args()))
+        .filter(
+            None,
+            if args.debug {
+                log::LevelFilter::Debug
+            } else if args.json {
+                log::LevelFilter::Warn
+            } else {
+                log::LevelFilter::Info
+            },
+        )
+        .init();
+
+    breezyshim::init();
+
+    let reporter = Reporter::new(args.report.as_deref(), args.json);
+
+    let text = std::fs::read_to_string(&args.packages)?;
+    let packages = match parse_package_list(&text) {
+        Ok(packages) => packages,
+        Err(e) => {
+            log::error!("{}: {}", args.packages.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let options = MigrateOptions {
+        workdir: args.workdir.clone(),
+        target_template: args.target.clone(),
+        layout: if args.dep14 {
+            Layout::Dep14
+        } else {
+            Layout::Keep
+        },
+        create: !args.no_create,
+        committer: None,
+    };
+
+    let mut state = MigrationState::load(&args.state)?;
+    let mut migrated = vec![];
+    let mut failed = vec![];
+    for spec in &packages {
+        if state.package_mut(&spec.name).stage.is_done() {
+            log::debug!("{}: already migrated", spec.name);
+            continue;
+        }
+        let mut package_state = state.package_mut(&spec.name).clone();
+        let result = migrate_package(spec, &options, &mut package_state, |s| {
+            let mut saved = state.clone();
+            *saved.package_mut(&spec.name) = s.clone();
+            saved.save(&args.state)
+        });
+        if let Err(e) = &result {
+            log::error!("{}: {}", spec.name, e);
+            package_state.error = Some(e.to_string());
+            failed.push(spec.name.clone());
+        } else {
+            migrated.push(spec.name.clone());
+        }
+        *state.package_mut(&spec.name) = package_state;
+        state.save(&args.state)?;
+    }
+
+    log::info!(
+        "Migrated {} packages, {} failed.",
+        migrated.len(),
+        failed.len()
+    );
+    if !failed.is_empty() {
+        reporter.report(Failure::new(
+            "migration-failed",
+            &format!("Failed to migrate: {}", failed.join(", ")),
+        ))?;
+        std::process::exit(1);
+    }
+    if migrated.is_empty() {
+        reporter.report(Failure::nothing_to_do(
+            "All packages have already been migrated.",
+        ))?;
+    } else {
+        let changes = migrated
+            .iter()
+            .map(|name| match target_url(&args.target, name) {
+                Ok(url) => format!("Migrate {} to {}.", name, url),
+                Err(_) => format!("Migrate {}.", name),
+            })
+            .collect::<Vec<_>>();
+        reporter.report(Success::new(
+            &format!("Migrate {} packages to a new VCS location.", migrated.len()),
+            &changes,
+        ))?;
+    }
+    Ok(())
+}
@@ -5,6 +5,7 @@ use breezyshim::workingtree;
 use clap::Parser;
 use debian_changelog::get_maintainer;
 use debian_workbench::publish::{create_vcs_url, update_official_vcs};
+use debian_workbench::svp_report::{Failure, Reporter, Success};
 
 use debian_workbench::get_committer;
 
@@ -41,6 +42,23 @@ struct Args {
     push: bool,
 
     url: Option<url::Url>,
+
+    /// Write a silver-platter compatible JSON report to this file
+    #[arg(long, value_name = "FILE")]
+    report: Option<std::path::PathBuf>,
+
+    /// Print the report as JSON on standard output, rather than human-readable output
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+/// Log an error, report it and exit.
+fn fail(reporter: &Reporter, code: &str, description: &str) -> ! {
+    log::error!("{}", description);
+    if let Err(e) = reporter.report(Failure::new(code, description)) {
+        log::error!("Unable to write report: {}", e);
+    }
+    std::process::exit(1);
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -52,6 +70,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             None,
             if args.debug {
                 log::LevelFilter::Debug
+            } else if args.json {
+                log::LevelFilter::Warn
             } else {
                 log::LevelFilter::Info
             },
@@ -60,29 +80,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     breezyshim::init();
 
+    let reporter = Reporter::new(args.report.as_deref(), args.json);
+
     let (wt, subpath) = match workingtree::open_containing(&args.directory) {
         Ok((wt, subpath)) => (wt, subpath.display().to_string()),
-        Err(Error::NotBranchError(_msg, _)) => {
-            log::error!("No version control directory found (e.g. a .git directory).");
-            std::process::exit(1);
-        }
-        Err(Error::DependencyNotPresent(name, _reason)) => {
-            log::error!(
+        Err(Error::NotBranchError(_msg, _)) => fail(
+            &reporter,
+            "not-branch-error",
+            "No version control directory found (e.g. a .git directory).",
+        ),
+        Err(Error::DependencyNotPresent(name, _reason)) => fail(
+            &reporter,
+            "dependency-not-present",
+            &format!(
                 "Unable to open tree at {}: missing package {}",
                 args.directory.display(),
                 name
-            );
-            std::process::exit(1);
-        }
-        Err(e) => {
-            log::error!("Unable to open tree at {}: {}", args.directory.display(), e);
-            std::process::exit(1);
-        }
+            ),
+        ),
+        Err(e) => fail(
+            &reporter,
+            "open-tree-failed",
+            &format!("Unable to open tree at {}: {}", args.directory.display(), e),
+        ),
     };
     if args.identity {
-        println!("Committer identity: {}", get_committer(&wt));
+        let committer = get_committer(&wt);
         let (maintainer, email) = get_maintainer().unwrap_or(("".to_string(), "".to_string()));
-        println!("Changelog identity: {} <{}>", maintainer, email);
+        if args.json {
+            println!(
+                "{}",
+                serde_json::json!({
+                    "committer": committer,
+                    "changelog": format!("{} <{}>", maintainer, email),
+                })
+            );
+        } else {
+            println!("Committer identity: {}", committer);
+            println!("Changelog identity: {} <{}>", maintainer, email);
+        }
         std::process::exit(0);
     }
 
@@ -95,10 +131,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some(args.force),
     ) {
         Ok(o) => o,
-        Err(e) => {
-            log::error!("Unable to update official VCS: {}", e);
-            std::process::exit(1);
-        }
+        Err(e) => fail(
+            &reporter,
+            "update-vcs-failed",
+            &format!("Unable to update official VCS: {}", e),
+        ),
     };
 
     let repo_url: url::Url = parsed_vcs.repo_url.parse().unwrap();
@@ -109,18 +146,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Err(Error::UnsupportedForge(_)) => {
                 log::error!("Unable to find a way to create {}", repo_url);
             }
-            Err(Error::ForgeProjectExists(..)) | Err(Error::AlreadyControlDir(..)) => {
-                log::error!("Unable to create {}: already exists", repo_url);
-                std::process::exit(1);
-            }
-            Err(Error::ForgeLoginRequired) => {
-                log::error!("Unable to create {}: login required", repo_url);
-                std::process::exit(1);
-            }
-            Err(e) => {
-                log::error!("Unable to create {}: {}", repo_url, e);
-                std::process::exit(1);
-            }
+            Err(Error::ForgeProjectExists(..)) | Err(Error::AlreadyControlDir(..)) => fail(
+                &reporter,
+                "already-exists",
+                &format!("Unable to create {}: already exists", repo_url),
+            ),
+            Err(Error::ForgeLoginRequired) => fail(
+                &reporter,
+                "forge-login-required",
+                &format!("Unable to create {}: login required", repo_url),
+            ),
+            Err(e) => fail(
+                &reporter,
+                "create-failed",
+                &format!("Unable to create {}: {}", repo_url, e),
+            ),
         }
     }
 
@@ -130,14 +170,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Err(Error::NotBranchError(_, _)) => controldir
             .create_branch(parsed_vcs.branch.as_deref())
             .unwrap(),
-        Err(e) => {
-            log::error!("Unable to open or create branch: {}", e);
-            std::process::exit(1);
-        }
+        Err(e) => fail(
+            &reporter,
+            "branch-open-failed",
+            &format!("Unable to open or create branch: {}", e),
+        ),
     };
 
     wt.branch()
         .push(branch.as_ref(), false, None, None)
         .unwrap();
+
+    let revision = wt.branch().last_revision();
+    let mut success = Success::new(&format!("Published packaging branch to {}.", repo_url), &[]);
+    success.context = Some(serde_json::json!({
+        "repo-url": repo_url.as_str(),
+        "branch": parsed_vcs.branch,
+        "subpath": parsed_vcs.subpath,
+        "revision": revision.to_string(),
+    }));
+    success.target_branch_url = Some(repo_url);
+    success.commit_message = None;
+    reporter.report(success)?;
     Ok(())
 }
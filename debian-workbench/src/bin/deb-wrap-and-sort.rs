@@ -0,0 +1,108 @@
+use clap::Parser;
+use debian_workbench::svp_report::{Failure, Reporter, Success};
+use debian_workbench::wrap_and_sort::{wrap_and_sort_tree, Options, DEFAULT_MAX_LINE_LENGTH};
+
+use std::io::Write as _;
+
+#[derive(Parser, Debug)]
+#[command(author, version)]
+struct Args {
+    /// Enable debug output
+    #[arg(long, default_value_t = false)]
+    debug: bool,
+
+    /// directory to run in
+    #[arg(short, long, default_value = std::env::current_dir().unwrap().into_os_string(), value_name = "DIR")]
+    directory: std::path::PathBuf,
+
+    /// Wrap lists even if they fit on a single line
+    #[arg(short = 'a', long, default_value_t = false)]
+    wrap_always: bool,
+
+    /// Start wrapped lists on a new line, indented by a single space
+    #[arg(short = 's', long, default_value_t = false)]
+    short_indent: bool,
+
+    /// Add a trailing comma to wrapped lists
+    #[arg(short = 't', long, default_value_t = false)]
+    trailing_comma: bool,
+
+    /// Keep the first item of each list in place when sorting
+    #[arg(short = 'k', long, default_value_t = false)]
+    keep_first: bool,
+
+    /// Wrap lists that would exceed this line length
+    #[arg(long, default_value_t = DEFAULT_MAX_LINE_LENGTH, value_name = "N")]
+    max_line_length: usize,
+
+    /// Only list the files that would be changed
+    #[arg(short = 'n', long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Write a silver-platter compatible JSON report to this file
+    #[arg(long, value_name = "FILE")]
+    report: Option<std::path::PathBuf>,
+
+    /// Print the report as JSON on standard output, rather than human-readable output
+    #[arg(long, default_value_t = false)]
+    json: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    env_logger::builder()
+        .format(|buf, record| writeln!(buf, "{}", record.args()))
+        .filter(
+            None,
+            if args.debug {
+                log::LevelFilter::Debug
+            } else if args.json {
+                log::LevelFilter::Warn
+            } else {
+                log::LevelFilter::Info
+            },
+        )
+        .init();
+
+    let options = Options {
+        wrap_always: args.wrap_always,
+        short_indent: args.short_indent,
+        trailing_comma: args.trailing_comma,
+        keep_first: args.keep_first,
+        max_line_length: args.max_line_length,
+    };
+
+    let reporter = Reporter::new(args.report.as_deref(), args.json);
+
+    let changed = match wrap_and_sort_tree(&args.directory, &options, args.dry_run) {
+        Ok(changed) => changed,
+        Err(e) => {
+            let description = format!("Unable to wrap and sort: {}", e);
+            log::error!("{}", description);
+            reporter.report(Failure::new("wrap-and-sort-failed", &description))?;
+            std::process::exit(1);
+        }
+    };
+
+    for path in &changed {
+        if args.dry_run && !args.json {
+            println!("{}", path.display());
+        } else {
+            log::debug!("Formatted {}", path.display());
+        }
+    }
+
+    if changed.is_empty() {
+        reporter.report(Failure::nothing_to_do(
+            "All files are already wrapped and sorted.",
+        ))?;
+    } else {
+        let changes = changed
+            .iter()
+            .map(|p| format!("Wrap and sort {}.", p.display()))
+            .collect::<Vec<_>>();
+        reporter.report(Success::new("Wrap and sort packaging files.", &changes))?;
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
-use breezyshim::workingtree;
 use clap::Parser;
+use debian_workbench::package_dir::PackageDir;
+use debian_workbench::svp_report::{Failure, Reporter};
 use std::io::Write as _;
 
 #[derive(Parser)]
@@ -9,6 +10,10 @@ struct Args {
     #[clap(long)]
     verbose: bool,
 
+    /// Print the result as JSON
+    #[clap(long)]
+    json: bool,
+
     /// The directory to check
     #[clap(default_value = ".")]
     directory: std::path::PathBuf,
@@ -23,23 +28,36 @@ fn main() {
             None,
             if args.verbose {
                 log::LevelFilter::Debug
+            } else if args.json {
+                log::LevelFilter::Warn
             } else {
                 log::LevelFilter::Info
             },
         )
         .init();
 
+    #[cfg(feature = "vcs")]
     breezyshim::init();
 
-    let (wt, subpath) = workingtree::open_containing(&args.directory).unwrap();
-    let debian_path = if debian_workbench::control_files_in_root(&wt, subpath.as_path()) {
-        subpath
-    } else {
-        subpath.join("debian")
+    let package = match PackageDir::open(&args.directory) {
+        Ok(package) => package,
+        Err(e) => {
+            let description = format!("Unable to open tree at {}: {}", args.directory.display(), e);
+            log::error!("{}", description);
+            let reporter = Reporter::new(None, args.json);
+            if let Err(e) = reporter.report(Failure::new("open-tree-failed", &description)) {
+                log::error!("Unable to write report: {}", e);
+            }
+            std::process::exit(1);
+        }
     };
-    let changelog_behaviour =
-        debian_workbench::detect_gbp_dch::guess_update_changelog(&wt, debian_path.as_path(), None);
-    if let Some(changelog_behaviour) = changelog_behaviour {
+    let changelog_behaviour = package.guess_update_changelog();
+    if args.json {
+        println!("{}", serde_json::to_string(&changelog_behaviour).unwrap());
+        if changelog_behaviour.is_none() {
+            std::process::exit(1)
+        }
+    } else if let Some(changelog_behaviour) = changelog_behaviour {
         log::info!("{}", changelog_behaviour.explanation);
         println!("{}", changelog_behaviour.update_changelog);
     } else {
@@ -0,0 +1,298 @@
+//! Client for the wanna-build status pages on buildd.debian.org, which show the
+//! per-architecture build state of source packages.
+use lazy_regex::regex;
+
+/// Base URL of the Debian build daemon status pages.
+pub const DEFAULT_URL: &str = "https://buildd.debian.org/";
+
+/// State of a package in wanna-build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuildState {
+    /// The package has been built and installed in the archive
+    Installed,
+    /// The package has been built, but not yet uploaded
+    Built,
+    /// The package has been built and uploaded
+    Uploaded,
+    /// The package is being built
+    Building,
+    /// The package is waiting to be built
+    NeedsBuild,
+    /// The build failed, and is waiting for a buildd admin to look at it
+    BuildAttempted,
+    /// The build failed
+    Failed,
+    /// The build probably failed
+    MaybeFailed,
+    /// The build probably succeeded
+    MaybeSuccessful,
+    /// The package is waiting for build dependencies to become available
+    DepWait,
+    /// The build dependencies can not be installed
+    BdUninstallable,
+    /// The package is not built on this architecture
+    NotForUs,
+    /// The package is not built on this architecture, according to its Architecture field
+    AutoNotForUs,
+    /// The package is not known on this architecture
+    Absent,
+}
+
+/// Names of the states, as shown on the status pages.
+const STATE_NAMES: &[(&str, BuildState)] = &[
+    ("Installed", BuildState::Installed),
+    ("Built", BuildState::Built),
+    ("Uploaded", BuildState::Uploaded),
+    ("Building", BuildState::Building),
+    ("Needs-Build", BuildState::NeedsBuild),
+    ("Build-Attempted", BuildState::BuildAttempted),
+    ("Failed", BuildState::Failed),
+    ("Maybe-Failed", BuildState::MaybeFailed),
+    ("Maybe-Successful", BuildState::MaybeSuccessful),
+    ("Dep-Wait", BuildState::DepWait),
+    ("BD-Uninstallable", BuildState::BdUninstallable),
+    ("Not-For-Us", BuildState::NotForUs),
+    ("Auto-Not-For-Us", BuildState::AutoNotForUs),
+    ("Absent", BuildState::Absent),
+];
+
+impl std::str::FromStr for BuildState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        STATE_NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, state)| *state)
+            .ok_or_else(|| format!("Unknown build state: {}", s))
+    }
+}
+
+impl std::fmt::Display for BuildState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = STATE_NAMES
+            .iter()
+            .find(|(_, state)| state == self)
+            .map(|(name, _)| *name)
+            .unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+impl BuildState {
+    /// Whether the package failed to build from source.
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            BuildState::BuildAttempted | BuildState::Failed | BuildState::MaybeFailed
+        )
+    }
+
+    /// Whether the package can not be built because of its build dependencies.
+    pub fn is_waiting(&self) -> bool {
+        matches!(self, BuildState::DepWait | BuildState::BdUninstallable)
+    }
+
+    /// Whether the package has been built successfully.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self,
+            BuildState::Installed
+                | BuildState::Built
+                | BuildState::Uploaded
+                | BuildState::MaybeSuccessful
+        )
+    }
+}
+
+/// Build state of a source package on one architecture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildStatus {
+    /// Architecture, e.g. "amd64"
+    pub architecture: String,
+    /// Version of the source package, if shown
+    pub version: Option<String>,
+    /// State of the build
+    pub state: BuildState,
+}
+
+/// Parse the per-architecture build states from a package status page.
+///
+/// # Arguments
+/// * `html` - Contents of the status page
+pub fn parse_status_page(html: &str) -> Vec<BuildStatus> {
+    let mut ret = vec![];
+    for row in html.split("<tr").skip(1) {
+        let row = row.split("</tr>").next().unwrap_or(row);
+        let Some(arch) = regex!(r#"architecture\.php\?a=([A-Za-z0-9_-]+)"#).captures(row) else {
+            continue;
+        };
+        let cells = regex!(r"(?s)<td[^>]*>(.*?)</td>")
+            .captures_iter(row)
+            .map(|c| regex!(r"<[^>]*>").replace_all(&c[1], "").trim().to_string())
+            .collect::<Vec<_>>();
+        let Some(state) = cells.iter().find_map(|c| c.parse::<BuildState>().ok()) else {
+            continue;
+        };
+        let version = cells
+            .iter()
+            .find(|c| regex!(r"^[0-9][A-Za-z0-9.+~:-]*$").is_match(c))
+            .cloned();
+        ret.push(BuildStatus {
+            architecture: arch[1].to_string(),
+            version,
+            state,
+        });
+    }
+    ret
+}
+
+/// The builds that need attention: failures and builds waiting on dependencies.
+///
+/// # Arguments
+/// * `statuses` - Build states of a source package
+pub fn problems(statuses: &[BuildStatus]) -> Vec<&BuildStatus> {
+    statuses
+        .iter()
+        .filter(|s| s.state.is_failure() || s.state.is_waiting())
+        .collect()
+}
+
+/// Error fetching build states.
+#[derive(Debug)]
+pub enum Error {
+    /// Error talking to buildd.debian.org.
+    Http(reqwest::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+/// Client for the buildd status pages.
+pub struct Client {
+    base_url: url::Url,
+    client: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// Create a client for buildd.debian.org.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(DEFAULT_URL.parse().unwrap())
+    }
+
+    /// Create a client for the buildd status pages at the given URL.
+    pub fn with_url(base_url: url::Url) -> Result<Self, Error> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self { base_url, client })
+    }
+
+    /// Fetch the build states of a source package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the source package
+    /// * `suite` - Suite to look at, e.g. "sid"
+    #[tracing::instrument(skip(self))]
+    pub fn status(&self, package: &str, suite: &str) -> Result<Vec<BuildStatus>, Error> {
+        let mut url = self.base_url.join("status/package.php").unwrap();
+        url.query_pairs_mut()
+            .append_pair("p", package)
+            .append_pair("suite", suite);
+        let html = self.client.get(url).send()?.error_for_status()?.text()?;
+        Ok(parse_status_page(&html))
+    }
+
+    /// Fetch the build states of a specific version of a source package.
+    ///
+    /// Architectures where another version is being built are left out.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the source package
+    /// * `version` - Version of the source package
+    /// * `suite` - Suite to look at, e.g. "sid"
+    pub fn status_for_version(
+        &self,
+        package: &str,
+        version: &str,
+        suite: &str,
+    ) -> Result<Vec<BuildStatus>, Error> {
+        Ok(self
+            .status(package, suite)?
+            .into_iter()
+            .filter(|s| s.version.as_deref().is_none_or(|v| v == version))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_state() {
+        assert_eq!(BuildState::DepWait, "Dep-Wait".parse().unwrap());
+        assert_eq!(
+            BuildState::BdUninstallable,
+            "bd-uninstallable".parse().unwrap()
+        );
+        assert_eq!("Maybe-Failed", BuildState::MaybeFailed.to_string());
+        assert!("Sleeping".parse::<BuildState>().is_err());
+        assert!(BuildState::BuildAttempted.is_failure());
+        assert!(BuildState::Installed.is_success());
+    }
+
+    #[test]
+    fn test_parse_status_page() {
+        let html = r#"<table class="data">
+<tr><th>Architecture</th><th>Version</th><th>Status</th></tr>
+<tr><td><a href="architecture.php?a=amd64&amp;suite=sid">amd64</a></td><td>1.0-2</td>
+<td class="status-Installed"><a href="fetch.php?pkg=foo">Installed</a></td></tr>
+<tr><td><a href="architecture.php?a=armel&amp;suite=sid">armel</a></td><td>1.0-2</td>
+<td class="status-Dep-Wait">Dep-Wait</td></tr>
+<tr><td><a href="architecture.php?a=mips64el&amp;suite=sid">mips64el</a></td><td>1.0-1</td>
+<td><b>Build-Attempted</b></td></tr>
+</table>"#;
+        let statuses = parse_status_page(html);
+        assert_eq!(
+            vec![
+                BuildStatus {
+                    architecture: "amd64".to_string(),
+                    version: Some("1.0-2".to_string()),
+                    state: BuildState::Installed,
+                },
+                BuildStatus {
+                    architecture: "armel".to_string(),
+                    version: Some("1.0-2".to_string()),
+                    state: BuildState::DepWait,
+                },
+                BuildStatus {
+                    architecture: "mips64el".to_string(),
+                    version: Some("1.0-1".to_string()),
+                    state: BuildState::BuildAttempted,
+                },
+            ],
+            statuses
+        );
+        assert_eq!(
+            vec!["armel", "mips64el"],
+            problems(&statuses)
+                .iter()
+                .map(|s| s.architecture.as_str())
+                .collect::<Vec<_>>()
+        );
+    }
+}
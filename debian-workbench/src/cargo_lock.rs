@@ -0,0 +1,293 @@
+//! Cargo.lock parsing, to find the dependencies of a crate that are vendored
+//! or packaged in Debian.
+//!
+//! Crates that ship a Cargo.lock and a vendor/ directory embed copies of
+//! their dependencies. Comparing the locked dependencies against the
+//! librust-*-dev packages in the archive tells which of them can be
+//! build-depended on instead, and which are missing from both.
+use crate::debcargo::{debcargo_binary_name, unmangle_debcargo_version};
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+
+/// A package listed in a Cargo.lock file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    /// Name of the crate
+    pub name: String,
+
+    /// Locked version of the crate
+    pub version: semver::Version,
+
+    /// Where the crate comes from, e.g. "registry+https://github.com/rust-lang/crates.io-index";
+    /// `None` for the crates in the workspace itself
+    pub source: Option<String>,
+
+    /// Checksum of the crate, if it comes from a registry
+    pub checksum: Option<String>,
+}
+
+/// The contents of a Cargo.lock file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CargoLock {
+    /// The locked packages, in file order
+    pub packages: Vec<LockedPackage>,
+}
+
+impl std::str::FromStr for CargoLock {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let doc: toml_edit::DocumentMut = text
+            .parse()
+            .map_err(|e: toml_edit::TomlError| e.message().to_string())?;
+        let Some(tables) = doc.get("package") else {
+            return Ok(Self::default());
+        };
+        let tables = tables
+            .as_array_of_tables()
+            .ok_or_else(|| "package is not an array of tables".to_string())?;
+        let mut packages = vec![];
+        for table in tables.iter() {
+            let get = |key: &str| table.get(key).and_then(|v| v.as_str());
+            let name = get("name").ok_or_else(|| "package without name".to_string())?;
+            let version =
+                get("version").ok_or_else(|| format!("package {} without version", name))?;
+            packages.push(LockedPackage {
+                name: name.to_string(),
+                version: version
+                    .parse()
+                    .map_err(|e| format!("invalid version {} of {}: {}", version, name, e))?,
+                source: get("source").map(|s| s.to_string()),
+                checksum: get("checksum").map(|s| s.to_string()),
+            });
+        }
+        Ok(Self { packages })
+    }
+}
+
+impl CargoLock {
+    /// Read a Cargo.lock file.
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        std::fs::read_to_string(path)?
+            .parse()
+            .map_err(|message| Error::Parse {
+                path: path.to_path_buf(),
+                message,
+            })
+    }
+
+    /// The packages that come from outside the workspace.
+    pub fn dependencies(&self) -> impl Iterator<Item = &LockedPackage> {
+        self.packages.iter().filter(|p| p.source.is_some())
+    }
+}
+
+/// Find the Cargo.lock file of a package.
+///
+/// This is the Cargo.lock file next to debian/, or else one in debian/
+/// itself, as some debcargo packages ship.
+///
+/// # Arguments
+/// * `path` - Root of the package
+pub fn find_cargo_lock(path: &Path) -> Option<PathBuf> {
+    [path.join("Cargo.lock"), path.join("debian/Cargo.lock")]
+        .into_iter()
+        .find(|p| p.is_file())
+}
+
+/// What is known about a locked dependency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyReport {
+    /// Name of the crate
+    pub name: String,
+
+    /// Locked version of the crate
+    pub version: semver::Version,
+
+    /// The Debian package that provides a compatible version of the crate,
+    /// or else the package that would
+    pub debian_package: String,
+
+    /// Version of the Debian package, if it is available
+    pub debian_version: Option<debversion::Version>,
+
+    /// Whether the Debian package provides a semver-compatible version of the crate
+    pub packaged: bool,
+
+    /// Whether a copy of the crate is shipped in the vendor directory
+    pub vendored: bool,
+}
+
+impl DependencyReport {
+    /// Whether the vendored copy could be replaced by a build dependency.
+    pub fn is_embedded_copy(&self) -> bool {
+        self.vendored && self.packaged
+    }
+
+    /// Whether the dependency is neither vendored nor packaged.
+    pub fn is_missing(&self) -> bool {
+        !self.vendored && !self.packaged
+    }
+}
+
+/// Whether two crate versions are semver-compatible.
+fn semver_compatible(a: &semver::Version, b: &semver::Version) -> bool {
+    match (a.major, a.minor) {
+        (0, 0) => b.major == 0 && b.minor == 0 && a.patch == b.patch,
+        (0, minor) => b.major == 0 && b.minor == minor,
+        (major, _) => b.major == major,
+    }
+}
+
+/// Check which locked dependencies are vendored or packaged in Debian.
+///
+/// For each dependency, the unsuffixed and semver-suffixed librust-*-dev
+/// packages are looked up, and the first one with a compatible version
+/// is reported.
+///
+/// # Arguments
+/// * `lock` - The Cargo.lock file
+/// * `vendor_dir` - Directory with vendored crates, e.g. "vendor", if any
+/// * `available` - Looks up the version of a binary package in the archive,
+///   e.g. from a [`crate::autopkgtest::PackageIndex`] or UDD
+///
+/// # Returns
+/// A report for each dependency, in Cargo.lock order
+pub fn check_dependencies(
+    lock: &CargoLock,
+    vendor_dir: Option<&Path>,
+    available: impl Fn(&str) -> Option<debversion::Version>,
+) -> Vec<DependencyReport> {
+    lock.dependencies()
+        .map(|dep| {
+            let version = &dep.version;
+            let mut candidates = vec![debcargo_binary_name(&dep.name, "")];
+            if version.major > 0 {
+                candidates.push(debcargo_binary_name(
+                    &dep.name,
+                    &format!("-{}", version.major),
+                ));
+            }
+            candidates.push(debcargo_binary_name(
+                &dep.name,
+                &format!("-{}.{}", version.major, version.minor),
+            ));
+            let found = candidates.iter().find_map(|package| {
+                let debian_version = available(package)?;
+                let crate_version: semver::Version =
+                    unmangle_debcargo_version(&debian_version.upstream_version)
+                        .parse()
+                        .ok()?;
+                semver_compatible(version, &crate_version)
+                    .then(|| (package.clone(), debian_version))
+            });
+            let vendored = vendor_dir.is_some_and(|d| {
+                d.join(&dep.name).is_dir() || d.join(format!("{}-{}", dep.name, version)).is_dir()
+            });
+            let (debian_package, debian_version, packaged) = match found {
+                Some((package, debian_version)) => (package, Some(debian_version), true),
+                None => (candidates[0].clone(), available(&candidates[0]), false),
+            };
+            DependencyReport {
+                name: dep.name.clone(),
+                version: version.clone(),
+                debian_package,
+                debian_version,
+                packaged,
+                vendored,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_LOCK: &str = r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "log",
+ "nom",
+ "rare",
+]
+
+[[package]]
+name = "log"
+version = "0.4.20"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "b5e6163cb8c49088c2c36f57875e58ccd8c87c7427f7fbd50ea6710b2f3f2e8f"
+
+[[package]]
+name = "nom"
+version = "7.1.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "rare"
+version = "1.0.0-beta.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    #[test]
+    fn test_parse() {
+        let lock: CargoLock = CARGO_LOCK.parse().unwrap();
+        assert_eq!(4, lock.packages.len());
+        assert_eq!(None, lock.packages[0].source);
+        assert_eq!(
+            vec!["log", "nom", "rare"],
+            lock.dependencies()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(semver::Version::new(0, 4, 20), lock.packages[1].version);
+        assert!(lock.packages[1].checksum.is_some());
+
+        assert!("[[package]]\nversion = \"1.0\"\n"
+            .parse::<CargoLock>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_dependencies() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("vendor/log")).unwrap();
+        std::fs::create_dir_all(td.path().join("vendor/rare-1.0.0-beta.1")).unwrap();
+        std::fs::write(td.path().join("Cargo.lock"), CARGO_LOCK).unwrap();
+        assert_eq!(
+            Some(td.path().join("Cargo.lock")),
+            find_cargo_lock(td.path())
+        );
+
+        let lock = CargoLock::read(&td.path().join("Cargo.lock")).unwrap();
+        let reports =
+            check_dependencies(
+                &lock,
+                Some(&td.path().join("vendor")),
+                |package| match package {
+                    "librust-log-dev" => Some("0.4.21-1".parse().unwrap()),
+                    "librust-nom-dev" => Some("8.0.0-2".parse().unwrap()),
+                    "librust-nom-7-dev" => Some("7.1.1-1".parse().unwrap()),
+                    _ => None,
+                },
+            );
+
+        assert_eq!("librust-log-dev", reports[0].debian_package);
+        assert!(reports[0].packaged);
+        assert!(reports[0].is_embedded_copy());
+
+        assert_eq!("librust-nom-7-dev", reports[1].debian_package);
+        assert_eq!(Some("7.1.1-1".parse().unwrap()), reports[1].debian_version);
+        assert!(!reports[1].vendored);
+        assert!(!reports[1].is_missing());
+
+        assert_eq!("librust-rare-dev", reports[2].debian_package);
+        assert!(!reports[2].packaged);
+        assert!(reports[2].vendored);
+        assert!(!reports[2].is_missing());
+    }
+}
@@ -1,10 +1,14 @@
 //! Functions for working with debian/changelog files.
 use crate::release_info;
+#[cfg(feature = "vcs")]
 use breezyshim::error::Error;
+#[cfg(feature = "vcs")]
 use breezyshim::prelude::*;
+#[cfg(feature = "vcs")]
 use breezyshim::tree::TreeChange;
 use debian_changelog::ChangeLog;
 
+#[cfg(feature = "vcs")]
 /// Check whether the only change in a tree is to the last changelog entry.
 ///
 /// # Arguments
@@ -105,10 +109,14 @@ pub fn find_last_distribution(cl: &ChangeLog) -> Option<String> {
 /// It's not a simple string comparison to find the same target in
 /// a previous version, as we should consider old series in e.g.
 /// Ubuntu.
-pub fn find_previous_upload(changelog: &ChangeLog) -> Option<debversion::Version> {
-    let current_target = find_last_distribution(changelog)?;
+pub fn find_previous_upload(
+    changelog: &ChangeLog,
+) -> Result<Option<debversion::Version>, crate::error::Error> {
+    let Some(current_target) = find_last_distribution(changelog) else {
+        return Ok(None);
+    };
     // multiple debian pockets with all debian releases
-    let all_debian = crate::release_info::debian_releases()
+    let all_debian = crate::release_info::debian_releases()?
         .iter()
         .flat_map(|r| {
             release_info::DEBIAN_POCKETS
@@ -116,7 +124,7 @@ pub fn find_previous_upload(changelog: &ChangeLog) -> Option<debversion::Version
                 .map(move |t| format!("{}{}", r, t))
         })
         .collect::<Vec<_>>();
-    let all_ubuntu = crate::release_info::ubuntu_releases()
+    let all_ubuntu = crate::release_info::ubuntu_releases()?
         .iter()
         .flat_map(|r| {
             release_info::UBUNTU_POCKETS
@@ -127,7 +135,7 @@ pub fn find_previous_upload(changelog: &ChangeLog) -> Option<debversion::Version
     let match_targets = if all_debian.contains(&current_target) {
         vec![current_target]
     } else if all_ubuntu.contains(&current_target) {
-        let mut match_targets = crate::release_info::ubuntu_releases();
+        let mut match_targets = crate::release_info::ubuntu_releases()?;
         if current_target.contains('-') {
             let distro = current_target.split('-').next().unwrap();
             match_targets.extend(
@@ -145,13 +153,359 @@ pub fn find_previous_upload(changelog: &ChangeLog) -> Option<debversion::Version
     };
     for block in changelog.iter().skip(1) {
         if match_targets.contains(&block.distributions().unwrap()[0]) {
-            return block.version().clone();
+            return Ok(block.version().clone());
         }
     }
 
-    None
+    Ok(None)
+}
+
+/// Suggest the version a new changelog entry should carry.
+///
+/// The version is derived from the last released entry, using
+/// [`crate::versions::next_upload_version`]. If the topmost entry is
+/// UNRELEASED and this is a regular upload, new changes go into that entry
+/// and its version is returned as is, since the maintainer may already have
+/// picked e.g. a new upstream version. For other upload types the version
+/// of an UNRELEASED entry is not trusted and is derived from the last
+/// released entry as well.
+///
+/// # Arguments
+/// * `changelog` - Changelog to inspect
+/// * `upload_type` - Type of upload the new entry is for
+/// * `vendor` - Vendor the upload is for
+///
+/// # Returns
+/// the suggested version, or None if the changelog has no usable entries
+pub fn suggest_next_version(
+    changelog: &ChangeLog,
+    upload_type: &crate::versions::UploadType,
+    vendor: crate::release_info::Vendor,
+) -> Option<debversion::Version> {
+    let first = changelog.iter().next()?;
+    let last_released = changelog
+        .iter()
+        .find(|e| e.is_unreleased() != Some(true))
+        .and_then(|e| e.version());
+    if first.is_unreleased() == Some(true)
+        && (*upload_type == crate::versions::UploadType::Regular || last_released.is_none())
+    {
+        return first.version();
+    }
+    let last_released = last_released?;
+    Some(crate::versions::next_upload_version(
+        &last_released,
+        upload_type,
+        vendor,
+    ))
+}
+
+/// Update the trailer of the topmost UNRELEASED entry to a new identity.
+///
+/// This sets the maintainer and timestamp in the trailer line, leaving the
+/// body of the entry untouched. Unlike
+/// [`debian_changelog::take_uploadership`], no attribution line for the
+/// previous maintainer is added.
+///
+/// # Arguments
+/// * `cl` - Changelog to update
+/// * `maintainer` - New identity; taken from the environment if None
+/// * `timestamp` - New timestamp; the current time if None
+///
+/// # Returns
+/// whether the trailer was updated; false if there is no UNRELEASED entry
+/// at the top of the changelog or no identity could be determined
+pub fn update_trailer_identity(
+    cl: &mut ChangeLog,
+    maintainer: Option<(String, String)>,
+    timestamp: Option<chrono::DateTime<chrono::FixedOffset>>,
+) -> bool {
+    let mut entry = match cl.iter().next() {
+        Some(entry) if entry.is_unreleased() == Some(true) => entry,
+        _ => return false,
+    };
+    let maintainer = match maintainer.or_else(debian_changelog::get_maintainer) {
+        Some(maintainer) => maintainer,
+        None => return false,
+    };
+    entry.set_maintainer(maintainer);
+    entry.set_datetime(timestamp.unwrap_or_else(|| chrono::Utc::now().fixed_offset()));
+    true
+}
+
+/// Return the top-level bullets of a changelog entry, with continuation lines joined.
+fn entry_bullets(entry: &debian_changelog::Entry) -> Vec<String> {
+    let mut bullets: Vec<String> = vec![];
+    for line in entry.change_lines() {
+        let line = line.trim();
+        if let Some(bullet) = line.strip_prefix("* ") {
+            bullets.push(bullet.to_string());
+        } else if line.is_empty() || line.starts_with('[') {
+            continue;
+        } else if let Some(last) = bullets.last_mut() {
+            last.push(' ');
+            last.push_str(line);
+        }
+    }
+    bullets
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+/// Result of cross-checking a changelog entry against the changes in a package.
+pub struct ChangelogCompleteness {
+    /// Files that were changed, but are not mentioned in the changelog entry
+    pub undocumented: Vec<std::path::PathBuf>,
+
+    /// Changelog bullets that only mention files that were not changed
+    pub stale: Vec<String>,
+}
+
+impl ChangelogCompleteness {
+    /// Whether the changelog entry covers all changes, and nothing more.
+    pub fn is_complete(&self) -> bool {
+        self.undocumented.is_empty() && self.stale.is_empty()
+    }
+}
+
+/// Find the paths mentioned in a changelog bullet, e.g. "debian/control" or "d/rules".
+fn mentioned_paths(text: &str) -> Vec<std::path::PathBuf> {
+    lazy_regex::regex!(r"\b(?:debian|d)/[A-Za-z0-9_.+/*-]*[A-Za-z0-9_+*-]")
+        .find_iter(text)
+        .map(|m| {
+            let m = m.as_str();
+            std::path::PathBuf::from(match m.strip_prefix("d/") {
+                Some(rest) => format!("debian/{}", rest),
+                None => m.to_string(),
+            })
+        })
+        .collect()
 }
 
+fn path_mentioned(path: &std::path::Path, mention: &std::path::Path) -> bool {
+    path.starts_with(mention)
+        || crate::install::glob_match(&mention.to_string_lossy(), &path.to_string_lossy())
+}
+
+/// Cross-check a changelog entry against the files that were changed.
+///
+/// # Arguments
+/// * `entry` - The changelog entry describing the changes, usually the topmost one
+/// * `changed_files` - Files that were changed, relative to the package root, e.g. as returned
+///   by [`crate::editor::Editor::commit`]
+///
+/// # Returns
+/// the undocumented changes and stale changelog bullets. Changes to
+/// debian/changelog itself are never reported as undocumented, and bullets
+/// that do not mention any file are never reported as stale.
+pub fn check_changelog_completeness(
+    entry: &debian_changelog::Entry,
+    changed_files: &[std::path::PathBuf],
+) -> ChangelogCompleteness {
+    let bullets = entry_bullets(entry)
+        .into_iter()
+        .map(|b| {
+            let mentions = mentioned_paths(&b);
+            (b, mentions)
+        })
+        .collect::<Vec<_>>();
+
+    let undocumented = changed_files
+        .iter()
+        .filter(|p| p.as_path() != std::path::Path::new("debian/changelog"))
+        .filter(|p| {
+            !bullets
+                .iter()
+                .flat_map(|(_, mentions)| mentions.iter())
+                .any(|m| path_mentioned(p, m))
+        })
+        .cloned()
+        .collect();
+
+    let stale = bullets
+        .into_iter()
+        .filter(|(_, mentions)| {
+            !mentions.is_empty()
+                && !mentions
+                    .iter()
+                    .any(|m| changed_files.iter().any(|p| path_mentioned(p, m)))
+        })
+        .map(|(b, _)| b)
+        .collect();
+
+    ChangelogCompleteness {
+        undocumented,
+        stale,
+    }
+}
+
+/// Parse the "Remaining changes" list of an Ubuntu merge entry.
+///
+/// Ubuntu merges from Debian conventionally start with a line like
+/// "* Merge from Debian unstable. Remaining changes:", followed by a list of
+/// the Ubuntu delta as "-" bullets.
+///
+/// # Arguments
+/// * `entry` - Changelog entry to inspect
+///
+/// # Returns
+/// the remaining changes, with continuation lines joined, or None if the
+/// entry is not a merge entry
+pub fn parse_remaining_changes(entry: &debian_changelog::Entry) -> Option<Vec<String>> {
+    let mut lines = entry.change_lines().map(|l| l.trim().to_string());
+    lines.find(|l| {
+        lazy_regex::regex_is_match!(r"^\* Merge from Debian .*Remaining changes:?$"i, l)
+    })?;
+    let mut ret: Vec<String> = vec![];
+    for line in lines {
+        if line.is_empty() || line.starts_with('*') || line.starts_with('[') {
+            break;
+        }
+        if let Some(item) = line.strip_prefix("- ") {
+            ret.push(item.to_string());
+        } else if let Some(last) = ret.last_mut() {
+            last.push(' ');
+            last.push_str(&line);
+        } else {
+            ret.push(line);
+        }
+    }
+    Some(ret)
+}
+
+/// Find the entries in an Ubuntu changelog that are not in the Debian changelog.
+///
+/// # Arguments
+/// * `ubuntu` - Changelog of the Ubuntu package
+/// * `debian` - Changelog of the Debian package that is being merged
+///
+/// # Returns
+/// the Ubuntu-only entries, newest first
+pub fn ubuntu_only_entries(ubuntu: &ChangeLog, debian: &ChangeLog) -> Vec<debian_changelog::Entry> {
+    let debian_versions = debian
+        .iter()
+        .filter_map(|e| e.version())
+        .collect::<std::collections::HashSet<_>>();
+    ubuntu
+        .iter()
+        .take_while(|e| {
+            e.version()
+                .map(|v| !debian_versions.contains(&v))
+                .unwrap_or(true)
+        })
+        .collect()
+}
+
+/// Generate the skeleton of an Ubuntu merge changelog entry.
+///
+/// The remaining changes are taken from the last merge entry in the Ubuntu
+/// delta (if any), followed by the changes from any later Ubuntu uploads.
+///
+/// # Arguments
+/// * `ubuntu` - Changelog of the Ubuntu package
+/// * `debian` - Changelog of the Debian package that is being merged
+/// * `debian_distribution` - Debian distribution that is merged from, e.g. "unstable"
+///
+/// # Returns
+/// change lines for the merge entry
+pub fn merge_changelog_skeleton(
+    ubuntu: &ChangeLog,
+    debian: &ChangeLog,
+    debian_distribution: &str,
+) -> Vec<String> {
+    let mut remaining = vec![];
+    for entry in ubuntu_only_entries(ubuntu, debian) {
+        if let Some(changes) = parse_remaining_changes(&entry) {
+            remaining.splice(0..0, changes);
+            break;
+        }
+        remaining.splice(0..0, entry_bullets(&entry));
+    }
+    let mut ret = vec![format!(
+        "* Merge from Debian {}. Remaining changes:",
+        debian_distribution
+    )];
+    ret.extend(remaining.into_iter().map(|c| format!("  - {}", c)));
+    ret
+}
+
+/// Add a temporary snapshot entry to a changelog, e.g. for CI builds.
+///
+/// The new entry is based on the version of the topmost entry, with the
+/// snapshot suffix added (see [`crate::versions::add_snapshot_suffix`]). If
+/// `upstream_version` is given, the upstream part of the version is replaced
+/// and the Debian revision is reset. The entry always targets UNRELEASED.
+///
+/// # Arguments
+/// * `cl` - Changelog to add the entry to
+/// * `upstream_version` - New upstream version, e.g. a git snapshot version
+/// * `suffix` - Snapshot suffix, e.g. "jan+unchanged"
+/// * `message` - Change line to add, e.g. "Build for CI."
+/// * `maintainer` - Maintainer for the entry; taken from the environment if None
+/// * `timestamp` - Timestamp for the entry; the current time if None
+///
+/// # Returns
+/// the version of the new entry, or None if the changelog has no entries
+pub fn add_snapshot_entry(
+    cl: &mut ChangeLog,
+    upstream_version: Option<&str>,
+    suffix: &str,
+    message: &str,
+    maintainer: Option<(String, String)>,
+    timestamp: Option<chrono::DateTime<chrono::FixedOffset>>,
+) -> Option<debversion::Version> {
+    let first = cl.iter().next()?;
+    let package = first.package()?;
+    let mut version = first.version()?;
+    if let Some(upstream_version) = upstream_version {
+        if version.upstream_version != upstream_version {
+            version = debversion::Version {
+                epoch: version.epoch,
+                upstream_version: upstream_version.to_string(),
+                debian_revision: version.debian_revision.as_ref().map(|_| "1".to_string()),
+            };
+        }
+    }
+    let version = crate::versions::add_snapshot_suffix(&version, suffix);
+    let mut builder = cl
+        .new_entry()
+        .package(package)
+        .version(version.clone())
+        .distributions(vec!["UNRELEASED".to_string()])
+        .change_line(format!("* {}", message));
+    if let Some(maintainer) = maintainer.or_else(debian_changelog::get_maintainer) {
+        builder = builder.maintainer(maintainer);
+    }
+    builder = builder.datetime(timestamp.unwrap_or_else(|| chrono::Utc::now().fixed_offset()));
+    builder.finish();
+    Some(version)
+}
+
+/// Remove temporary snapshot entries from the top of a changelog.
+///
+/// This is the counterpart of [`add_snapshot_entry`], to be used before
+/// preparing a real release.
+///
+/// # Arguments
+/// * `cl` - Changelog to strip entries from
+/// * `suffix` - Snapshot suffix, e.g. "jan+unchanged"
+///
+/// # Returns
+/// the number of entries that were removed
+pub fn strip_snapshot_entries(cl: &mut ChangeLog, suffix: &str) -> usize {
+    let mut removed = 0;
+    loop {
+        let version = cl.iter().next().and_then(|e| e.version());
+        match version {
+            Some(v) if crate::versions::snapshot_suffix_count(&v, suffix).is_some() => {}
+            _ => break,
+        }
+        cl.pop_first();
+        removed += 1;
+    }
+    removed
+}
+
+#[cfg(feature = "vcs")]
 #[derive(Debug)]
 /// Error type for find_changelog
 pub enum FindChangelogError {
@@ -168,6 +522,7 @@ pub enum FindChangelogError {
     BrzError(breezyshim::error::Error),
 }
 
+#[cfg(feature = "vcs")]
 impl std::fmt::Display for FindChangelogError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -183,14 +538,17 @@ impl std::fmt::Display for FindChangelogError {
     }
 }
 
+#[cfg(feature = "vcs")]
 impl std::error::Error for FindChangelogError {}
 
+#[cfg(feature = "vcs")]
 impl From<breezyshim::error::Error> for FindChangelogError {
     fn from(e: breezyshim::error::Error) -> Self {
         FindChangelogError::BrzError(e)
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Find the changelog in the given tree.
 ///
 /// First looks for 'debian/changelog'. If "merge" is true will also
@@ -271,7 +629,9 @@ pub fn find_changelog(
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "vcs")]
     use breezyshim::workingtree::GenericWorkingTree;
+    #[cfg(feature = "vcs")]
     pub const COMMITTER: &str = "Test User <example@example.com>";
     #[test]
     fn test_find_previous_upload() {
@@ -283,7 +643,7 @@ mod tests {
 "#
         .parse()
         .unwrap();
-        assert_eq!(super::find_previous_upload(&cl), None);
+        assert_eq!(super::find_previous_upload(&cl).unwrap(), None);
 
         let cl = r#"test (1.0-1) unstable; urgency=medium
 
@@ -300,11 +660,300 @@ test (1.0-0) unstable; urgency=medium
         .parse()
         .unwrap();
         assert_eq!(
-            super::find_previous_upload(&cl),
+            super::find_previous_upload(&cl).unwrap(),
             Some("1.0-0".parse().unwrap())
         );
     }
 
+    #[test]
+    fn test_suggest_next_version() {
+        use crate::release_info::Vendor;
+        use crate::versions::UploadType;
+        let cl: ChangeLog = r#"test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            suggest_next_version(&cl, &UploadType::Regular, Vendor::Debian),
+            Some("1.0-2".parse().unwrap())
+        );
+        assert_eq!(
+            suggest_next_version(&cl, &UploadType::Backport("12".to_string()), Vendor::Debian),
+            Some("1.0-1~bpo12+1".parse().unwrap())
+        );
+
+        let cl: ChangeLog = r#"test (1.0-2) UNRELEASED; urgency=medium
+
+  * Some change.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+
+test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            suggest_next_version(&cl, &UploadType::Regular, Vendor::Debian),
+            Some("1.0-2".parse().unwrap())
+        );
+        assert_eq!(
+            suggest_next_version(&cl, &UploadType::NonMaintainer, Vendor::Debian),
+            Some("1.0-1.1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_update_trailer_identity() {
+        let mut cl: ChangeLog = r#"test (1.0-2) UNRELEASED; urgency=medium
+
+  * Some change.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+
+test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert!(update_trailer_identity(
+            &mut cl,
+            Some(("Bot".to_string(), "bot@example.com".to_string())),
+            Some(chrono::DateTime::parse_from_rfc2822("Wed, 01 Jan 2025 00:00:00 +0000").unwrap()),
+        ));
+        assert_eq!(
+            cl.to_string(),
+            r#"test (1.0-2) UNRELEASED; urgency=medium
+
+  * Some change.
+
+ -- Bot <bot@example.com>  Wed, 01 Jan 2025 00:00:00 +0000
+
+test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        );
+
+        let mut cl: ChangeLog = r#"test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert!(!update_trailer_identity(
+            &mut cl,
+            Some(("Bot".to_string(), "bot@example.com".to_string())),
+            None,
+        ));
+    }
+
+    #[test]
+    fn test_check_changelog_completeness() {
+        let entry: debian_changelog::Entry = r#"test (1.0-2) UNRELEASED; urgency=medium
+
+  * debian/control: Bump Standards-Version.
+  * d/rules: Enable hardening,
+    and drop override.
+  * Update debian/watch.
+  * Team upload.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        let result = check_changelog_completeness(
+            &entry,
+            &[
+                "debian/changelog".into(),
+                "debian/control".into(),
+                "debian/rules".into(),
+                "debian/copyright".into(),
+            ],
+        );
+        assert_eq!(
+            result,
+            ChangelogCompleteness {
+                undocumented: vec!["debian/copyright".into()],
+                stale: vec!["Update debian/watch.".to_string()],
+            }
+        );
+        assert!(!result.is_complete());
+
+        let result = check_changelog_completeness(
+            &entry,
+            &[
+                "debian/control".into(),
+                "debian/rules".into(),
+                "debian/watch".into(),
+            ],
+        );
+        assert!(result.is_complete());
+    }
+
+    #[test]
+    fn test_check_changelog_completeness_glob() {
+        let entry: debian_changelog::Entry = r#"test (1.0-2) UNRELEASED; urgency=medium
+
+  * Install manpages from debian/*.manpages.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        let result = check_changelog_completeness(
+            &entry,
+            &["debian/foo.manpages".into(), "debian/control".into()],
+        );
+        assert_eq!(
+            result,
+            ChangelogCompleteness {
+                undocumented: vec!["debian/control".into()],
+                stale: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_remaining_changes() {
+        let entry: debian_changelog::Entry = r#"test (1.0-2ubuntu1) jammy; urgency=medium
+
+  * Merge from Debian unstable. Remaining changes:
+    - debian/control: Drop foo dependency,
+      not in main.
+    - debian/rules: Disable tests.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            parse_remaining_changes(&entry),
+            Some(vec![
+                "debian/control: Drop foo dependency, not in main.".to_string(),
+                "debian/rules: Disable tests.".to_string()
+            ])
+        );
+
+        let entry: debian_changelog::Entry = r#"test (1.0-1ubuntu1) jammy; urgency=medium
+
+  * Disable tests.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(parse_remaining_changes(&entry), None);
+    }
+
+    #[test]
+    fn test_merge_changelog_skeleton() {
+        let debian: ChangeLog = r#"test (1.0-2) unstable; urgency=medium
+
+  * New Debian change.
+
+ -- Test User <test@user.example.com>  Sat, 02 Jan 2021 00:00:00 +0000
+
+test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        let ubuntu: ChangeLog = r#"test (1.0-1ubuntu2) jammy; urgency=medium
+
+  * Build with -O2.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+
+test (1.0-1ubuntu1) jammy; urgency=medium
+
+  * Merge from Debian unstable. Remaining changes:
+    - Disable tests.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+
+test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(ubuntu_only_entries(&ubuntu, &debian).len(), 2);
+        assert_eq!(
+            merge_changelog_skeleton(&ubuntu, &debian, "unstable"),
+            vec![
+                "* Merge from Debian unstable. Remaining changes:",
+                "  - Disable tests.",
+                "  - Build with -O2.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_snapshot_entry() {
+        let mut cl: ChangeLog = r#"test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        let version = add_snapshot_entry(
+            &mut cl,
+            Some("1.2.3+git20250101.abcdef0"),
+            "jan+unchanged",
+            "Build snapshot.",
+            Some(("CI".to_string(), "ci@example.com".to_string())),
+            Some(chrono::DateTime::parse_from_rfc2822("Wed, 01 Jan 2025 00:00:00 +0000").unwrap()),
+        )
+        .unwrap();
+        assert_eq!(
+            version,
+            "1.2.3+git20250101.abcdef0-1~jan+unchanged1"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            cl.to_string(),
+            r#"test (1.2.3+git20250101.abcdef0-1~jan+unchanged1) UNRELEASED; urgency=medium
+
+  * Build snapshot.
+
+ -- CI <ci@example.com>  Wed, 01 Jan 2025 00:00:00 +0000
+
+test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        );
+        assert_eq!(strip_snapshot_entries(&mut cl, "jan+unchanged"), 1);
+        assert_eq!(cl.iter().count(), 1);
+        assert_eq!(strip_snapshot_entries(&mut cl, "jan+unchanged"), 0);
+    }
+
+    #[cfg(feature = "vcs")]
     mod test_only_changes_last_changelog_block {
         use super::*;
         use breezyshim::controldir::{create_standalone_workingtree, ControlDirFormat};
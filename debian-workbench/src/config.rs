@@ -1,5 +1,6 @@
 //! Lintian-brush configuration file.
 use crate::Certainty;
+#[cfg(feature = "vcs")]
 use breezyshim::tree::WorkingTree;
 use configparser::ini::Ini;
 use log::warn;
@@ -9,6 +10,7 @@ const SUPPORTED_KEYS: &[&str] = &[
     "minimum-certainty",
     "allow-reformatting",
     "update-changelog",
+    "update-copyright-years",
 ];
 
 /// Configuration file name
@@ -20,6 +22,7 @@ pub struct Config {
 }
 
 impl Config {
+    #[cfg(feature = "vcs")]
     /// Load configuration from a working tree
     pub fn from_workingtree(
         tree: &dyn WorkingTree,
@@ -66,11 +69,17 @@ impl Config {
     /// Return the compatibility release.
     pub fn compat_release(&self) -> Option<String> {
         self.obj.get("default", "compat-release").and_then(|value| {
-            let codename = crate::release_info::resolve_release_codename(&value, None);
-            if codename.is_none() {
-                warn!("unknown compat release {}, ignoring.", value);
+            match crate::release_info::resolve_release_codename(&value, None) {
+                Ok(Some(codename)) => Some(codename),
+                Ok(None) => {
+                    warn!("unknown compat release {}, ignoring.", value);
+                    None
+                }
+                Err(e) => {
+                    warn!("unable to resolve compat release {}: {}", value, e);
+                    None
+                }
             }
-            codename
         })
     }
 
@@ -109,6 +118,17 @@ impl Config {
             }
         }
     }
+
+    /// Return whether years in debian/copyright should be updated.
+    pub fn update_copyright_years(&self) -> Option<bool> {
+        match self.obj.getbool("default", "update-copyright-years") {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("invalid update-copyright-years value {}, ignoring.", e);
+                None
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -126,7 +146,7 @@ mod tests {
         .unwrap();
         let cfg = Config::load_from_path(&td.path().join("debian/lintian-brush.conf")).unwrap();
 
-        let testing = crate::release_info::resolve_release_codename("testing", None);
+        let testing = crate::release_info::resolve_release_codename("testing", None).unwrap();
 
         assert_eq!(cfg.compat_release(), testing);
     }
@@ -159,6 +179,20 @@ mod tests {
         assert_eq!(cfg.update_changelog(), Some(true));
     }
 
+    #[test]
+    fn test_update_copyright_years() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/lintian-brush.conf"),
+            "update-copyright-years = false\n",
+        )
+        .unwrap();
+        let cfg = Config::load_from_path(&td.path().join("debian/lintian-brush.conf")).unwrap();
+
+        assert_eq!(cfg.update_copyright_years(), Some(false));
+    }
+
     #[test]
     fn test_unknown() {
         let td = tempfile::tempdir().unwrap();
@@ -1,5 +1,5 @@
 //! Tools for working with Debian control files.
-use crate::editor::{Editor, EditorError, FsEditor, GeneratedFile};
+use crate::editor::{Editor, EditorError, FileChange, FsEditor, GeneratedFile};
 use crate::relations::{ensure_relation, is_relation_implied};
 use deb822_lossless::Paragraph;
 use debian_control::lossless::relations::Relations;
@@ -117,9 +117,10 @@ pub fn dh_gnome_clean(path: &std::path::Path) -> Result<(), TemplateExpansionErr
         ));
     }
 
-    let result = std::process::Command::new("dh_gnome_clean")
-        .current_dir(path)
-        .output();
+    let mut cmd = std::process::Command::new("dh_gnome_clean");
+    cmd.current_dir(path);
+    let span = tracing::info_span!("command", command = ?cmd);
+    let result = span.in_scope(|| cmd.output());
 
     match result {
         Ok(output) => {
@@ -161,10 +162,10 @@ pub fn dh_gnome_clean(path: &std::path::Path) -> Result<(), TemplateExpansionErr
 /// - The pg_buildext command is not found
 /// - The command fails to execute
 pub fn pg_buildext_updatecontrol(path: &std::path::Path) -> Result<(), TemplateExpansionError> {
-    let result = std::process::Command::new("pg_buildext")
-        .arg("updatecontrol")
-        .current_dir(path)
-        .output();
+    let mut cmd = std::process::Command::new("pg_buildext");
+    cmd.arg("updatecontrol").current_dir(path);
+    let span = tracing::info_span!("command", command = ?cmd);
+    let result = span.in_scope(|| cmd.output());
 
     match result {
         Ok(output) => {
@@ -215,10 +216,10 @@ fn expand_control_template(
                     break;
                 }
             }
-            let result = std::process::Command::new("./debian/rules")
-                .arg("debian/control")
-                .current_dir(package_root)
-                .output();
+            let mut cmd = std::process::Command::new("./debian/rules");
+            cmd.arg("debian/control").current_dir(package_root);
+            let span = tracing::info_span!("command", command = ?cmd);
+            let result = span.in_scope(|| cmd.output());
 
             match result {
                 Ok(output) => {
@@ -319,7 +320,7 @@ fn update_control_template(
     }
 
     let mut template_editor =
-        FsEditor::<deb822_lossless::Deb822>::new(template_path, true, false).unwrap();
+        FsEditor::<deb822_lossless::Deb822>::new(template_path, true, false, false).unwrap();
 
     let resolve_conflict = match template_type {
         TemplateType::Cdbs => Some(resolve_cdbs_template as ResolveDeb822Conflict),
@@ -342,7 +343,7 @@ fn update_control_template(
         match template_type {
             TemplateType::Cdbs => {
                 let mut editor =
-                    FsEditor::<deb822_lossless::Deb822>::new(path, true, false).unwrap();
+                    FsEditor::<deb822_lossless::Deb822>::new(path, true, false, false).unwrap();
                 apply_changes(&mut editor, changes, None)?;
                 match editor.commit() {
                     Ok(_) => {}
@@ -706,7 +707,7 @@ impl TemplatedControlEditor {
 
         // For deb822-style templates: normalize the template file
         let mut template_editor =
-            FsEditor::<deb822_lossless::Deb822>::new(&template.template_path, true, false)?;
+            FsEditor::<deb822_lossless::Deb822>::new(&template.template_path, true, false, false)?;
         template_editor.normalize_field_spacing();
         template_editor.commit()?;
 
@@ -746,7 +747,7 @@ impl TemplatedControlEditor {
         } else {
             (Template::find(path), false)
         };
-        let primary = FsEditor::<debian_control::Control>::new(path, false, false)?;
+        let primary = FsEditor::<debian_control::Control>::new(path, false, false, false)?;
         Ok(Self {
             path: path.to_path_buf(),
             primary,
@@ -810,7 +811,63 @@ impl TemplatedControlEditor {
         changes
     }
 
+    /// Describe the changes that [`TemplatedControlEditor::commit`] would
+    /// make, without writing them.
+    ///
+    /// If debian/control is generated from a template, this includes the
+    /// change to the template. The regenerated control file is assumed to
+    /// match the edited one.
+    pub fn pending_changes(&self) -> Result<Vec<FileChange>, EditorError> {
+        let template = if self.template_only {
+            self.template
+                .as_ref()
+                .expect("template_only implies template")
+        } else {
+            match self.primary.pending_changes() {
+                Err(EditorError::GeneratedFile(
+                    p,
+                    GeneratedFile {
+                        template_path: None,
+                        template_type,
+                    },
+                )) => {
+                    return Err(EditorError::GeneratedFile(
+                        p,
+                        GeneratedFile {
+                            template_path: None,
+                            template_type,
+                        },
+                    ))
+                }
+                Err(EditorError::GeneratedFile(..)) => self.template.as_ref().ok_or_else(|| {
+                    EditorError::IoError(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "No control file or template found",
+                    ))
+                })?,
+                result => return result,
+            }
+        };
+        let Some(template_change) = template.preview(self.changes()).map_err(|e| {
+            EditorError::TemplateError(template.template_path.clone(), e.to_string())
+        })?
+        else {
+            return Ok(vec![]);
+        };
+        if self.template_only {
+            // The control file was only expanded to read it, and is removed again.
+            return Ok(vec![template_change]);
+        }
+        let control_change = FileChange {
+            path: self.path.clone(),
+            old: self.primary.orig_content().map(|c| c.to_vec()),
+            new: self.primary.updated_content(),
+        };
+        Ok(vec![template_change, control_change])
+    }
+
     /// Commit the changes to the control file and template.
+    #[tracing::instrument(level = "debug", skip(self), fields(path = %self.path.display()))]
     pub fn commit(&self) -> Result<Vec<PathBuf>, EditorError> {
         let mut changed_files: Vec<PathBuf> = vec![];
         if self.template_only {
@@ -944,6 +1001,31 @@ impl Template {
             expand,
         )
     }
+
+    /// Compute the change that [`Template::update`] would make to the template.
+    fn preview(
+        &self,
+        changes: Deb822Changes,
+    ) -> Result<Option<FileChange>, TemplateExpansionError> {
+        if self.template_type == TemplateType::Directory {
+            return Err(TemplateExpansionError::UnknownTemplating(
+                self.path.clone(),
+                Some(self.template_path.clone()),
+            ));
+        }
+        let mut template_editor =
+            FsEditor::<deb822_lossless::Deb822>::new(&self.template_path, true, false, false)
+                .map_err(|e| TemplateExpansionError::Failed(e.to_string()))?;
+        let resolve_conflict = match self.template_type {
+            TemplateType::Cdbs => Some(resolve_cdbs_template as ResolveDeb822Conflict),
+            _ => None,
+        };
+        apply_changes(&mut template_editor, changes, resolve_conflict)?;
+        Ok(template_editor
+            .pending_changes()
+            .map_err(|e| TemplateExpansionError::Failed(e.to_string()))?
+            .pop())
+    }
 }
 
 impl Editor<debian_control::Control> for TemplatedControlEditor {
@@ -966,6 +1048,10 @@ impl Editor<debian_control::Control> for TemplatedControlEditor {
     fn commit(&self) -> Result<Vec<std::path::PathBuf>, EditorError> {
         TemplatedControlEditor::commit(self)
     }
+
+    fn pending_changes(&self) -> Result<Vec<FileChange>, EditorError> {
+        TemplatedControlEditor::pending_changes(self)
+    }
 }
 
 #[cfg(test)]
@@ -2049,6 +2135,69 @@ Uploaders: @lintian-brush-test@
             assert!(!td.path().join("debian/control").exists());
         }
 
+        #[cfg(unix)]
+        #[test]
+        fn test_pending_changes_template_only() {
+            use std::os::unix::fs::PermissionsExt;
+            let td = tempfile::tempdir().unwrap();
+            std::fs::create_dir(td.path().join("debian")).unwrap();
+            std::fs::write(
+                td.path().join("debian/control.in"),
+                r#"Source: blah
+Testsuite: autopkgtest
+Uploaders: @lintian-brush-test@
+
+"#,
+            )
+            .unwrap();
+            std::fs::write(
+                td.path().join("debian/rules"),
+                r#"#!/usr/bin/make -f
+
+debian/control: debian/control.in
+	sed -e 's/@lintian-brush-test@/testvalue/' < $< > $@
+"#,
+            )
+            .unwrap();
+
+            std::fs::set_permissions(
+                td.path().join("debian/rules"),
+                std::fs::Permissions::from_mode(0o755),
+            )
+            .unwrap();
+
+            let editor =
+                super::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+            assert_eq!(editor.pending_changes().unwrap(), vec![]);
+            editor
+                .source()
+                .unwrap()
+                .as_mut_deb822()
+                .set("Testsuite", "autopkgtest8");
+
+            let changes = editor.pending_changes().unwrap();
+            assert_eq!(
+                vec![td.path().join("debian/control.in")],
+                changes.iter().map(|c| c.path.clone()).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                Some(
+                    r#"Source: blah
+Testsuite: autopkgtest8
+Uploaders: @lintian-brush-test@
+
+"#
+                    .as_bytes()
+                    .to_vec()
+                ),
+                changes[0].new
+            );
+            // Nothing is written
+            assert!(std::fs::read_to_string(td.path().join("debian/control.in"))
+                .unwrap()
+                .contains("Testsuite: autopkgtest\n"));
+        }
+
         #[cfg(unix)]
         #[test]
         fn test_update_template_invalid_tokens() {
@@ -0,0 +1,2489 @@
+//! Functions for working with debian/copyright files.
+use debian_copyright::lossless::Copyright;
+use debian_copyright::LicenseExpr;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of bytes of each file that is scanned for license statements.
+pub const SCAN_LIMIT: usize = 32 * 1024;
+
+/// Fingerprints of common license texts, with the DEP-5 name of the license.
+///
+/// BSD-3-clause has to come before BSD-2-clause, since its text also
+/// contains the BSD-2-clause fingerprint.
+const LICENSE_FINGERPRINTS: &[(&str, &str)] = &[
+    (
+        "GPL-2+",
+        r"GNU General Public License[^.]*?version 2[^.]*?or\s*\(at your option\)\s*any later version",
+    ),
+    (
+        "GPL-3+",
+        r"GNU General Public License[^.]*?version 3[^.]*?or\s*\(at your option\)\s*any later version",
+    ),
+    (
+        "LGPL-2.1+",
+        r"GNU Lesser General Public\s+License[^.]*?version 2\.1[^.]*?or\s*\(at your option\)\s*any later version",
+    ),
+    (
+        "LGPL-3+",
+        r"GNU Lesser General Public\s+License[^.]*?version 3[^.]*?or\s*\(at your option\)\s*any later version",
+    ),
+    (
+        "AGPL-3+",
+        r"GNU Affero General Public License[^.]*?version 3[^.]*?or\s*\(at your option\)\s*any later version",
+    ),
+    (
+        "Apache-2.0",
+        r"Licensed under the Apache License,\s*Version 2\.0",
+    ),
+    ("MPL-2.0", r"Mozilla Public\s+License,\s*v\.\s*2\.0"),
+    (
+        "ISC",
+        r"Permission to use, copy, modify, and(?:/or)? distribute this software for any\s+purpose with or without fee is hereby granted",
+    ),
+    (
+        "BSD-3-clause",
+        r"Neither the name of[\s\S]*?may be used to endorse or promote",
+    ),
+    (
+        "BSD-2-clause",
+        r"Redistributions in binary form must reproduce the above copyright",
+    ),
+    (
+        "Expat",
+        r"Permission is hereby granted, free of charge, to any person obtaining a copy",
+    ),
+];
+
+/// Convert an SPDX license identifier to the name commonly used in DEP-5 files.
+///
+/// # Arguments
+/// * `spdx` - SPDX license identifier, e.g. "GPL-2.0-or-later"
+///
+/// # Returns
+/// the DEP-5 name, e.g. "GPL-2+"
+pub fn spdx_to_dep5(spdx: &str) -> String {
+    let spdx = spdx.trim();
+    for family in ["AGPL", "LGPL", "GPL"] {
+        if let Some(rest) = spdx.strip_prefix(family).and_then(|r| r.strip_prefix('-')) {
+            let (version, later) = if let Some(v) = rest.strip_suffix("-or-later") {
+                (v, true)
+            } else if let Some(v) = rest.strip_suffix("-only") {
+                (v, false)
+            } else if let Some(v) = rest.strip_suffix('+') {
+                (v, true)
+            } else {
+                (rest, false)
+            };
+            let version = version.strip_suffix(".0").unwrap_or(version);
+            return format!("{}-{}{}", family, version, if later { "+" } else { "" });
+        }
+    }
+    match spdx {
+        "MIT" => "Expat".to_string(),
+        "BSD-2-Clause" => "BSD-2-clause".to_string(),
+        "BSD-3-Clause" => "BSD-3-clause".to_string(),
+        "BSD-4-Clause" => "BSD-4-clause".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// License and copyright statements found in a source file.
+pub struct LicenseFinding {
+    /// Path of the file, relative to the root of the source tree
+    pub path: PathBuf,
+
+    /// Detected licenses, using DEP-5 names
+    pub licenses: Vec<String>,
+
+    /// Detected copyright statements, e.g. "2019 John Doe"
+    pub copyright: Vec<String>,
+}
+
+/// Detect license and copyright statements in the contents of a file.
+///
+/// SPDX-License-Identifier tags take precedence; if there are none, the text
+/// is matched against fingerprints of common license texts.
+///
+/// # Arguments
+/// * `path` - Path of the file, relative to the root of the source tree
+/// * `text` - Contents of the file
+pub fn scan_text(path: &Path, text: &str) -> LicenseFinding {
+    let mut licenses = vec![];
+    for c in lazy_regex::regex!(r"SPDX-License-Identifier:\s*([^\n*]+?)\s*(?:\*/|-->)?\s*$"m)
+        .captures_iter(text)
+    {
+        let expr = match LicenseExpr::parse_spdx(&c[1]) {
+            Ok(expr) => expr,
+            Err(_) => LicenseExpr::Name(c[1].to_string()),
+        };
+        for name in expr.license_names() {
+            let name = spdx_to_dep5(name);
+            if !licenses.contains(&name) {
+                licenses.push(name);
+            }
+        }
+    }
+    if licenses.is_empty() {
+        // Collapse comment markers and line breaks so that fingerprints can
+        // span lines.
+        let flattened =
+            lazy_regex::regex_replace_all!(r"\s*\n\s*(?:[#*;!/]+|//|--|dnl)?\s*", text, " ");
+        static FINGERPRINT_REGEXES: std::sync::OnceLock<Vec<(&str, regex::Regex)>> =
+            std::sync::OnceLock::new();
+        let fingerprints = FINGERPRINT_REGEXES.get_or_init(|| {
+            LICENSE_FINGERPRINTS
+                .iter()
+                .map(|(name, fingerprint)| {
+                    (
+                        *name,
+                        regex::Regex::new(&format!("(?i){}", fingerprint)).unwrap(),
+                    )
+                })
+                .collect()
+        });
+        for (name, re) in fingerprints {
+            if re.is_match(&flattened) {
+                // BSD-3-clause texts also contain the BSD-2-clause fingerprint
+                if *name == "BSD-2-clause" && licenses.iter().any(|l| l == "BSD-3-clause") {
+                    continue;
+                }
+                licenses.push(name.to_string());
+            }
+        }
+    }
+    let copyright = lazy_regex::regex!(
+        r"(?im)^[^a-z0-9\n]*(?:copyright\s*(?:\(c\)|©)?|\(c\)|©)\s*((?:\d{4}).*?)\s*(?:\*/)?$"
+    )
+    .captures_iter(text)
+    .map(|c| c[1].to_string())
+    .collect();
+    LicenseFinding {
+        path: path.to_path_buf(),
+        licenses,
+        copyright,
+    }
+}
+
+/// Names of directories that are never part of the source.
+const IGNORED_DIRECTORIES: &[&str] = &[".git", ".bzr", ".svn", ".hg", ".pc"];
+
+/// List the files in a source tree.
+///
+/// Version control directories and the quilt `.pc` directory are skipped.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// paths relative to `base`, sorted
+pub fn source_files(base: &Path) -> std::io::Result<Vec<PathBuf>> {
+    fn walk(base: &Path, dir: &Path, ret: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(base.join(dir))? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if IGNORED_DIRECTORIES.contains(&name.to_string_lossy().as_ref()) {
+                continue;
+            }
+            let path = dir.join(&name);
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                walk(base, &path, ret)?;
+            } else if file_type.is_file() {
+                ret.push(path);
+            }
+        }
+        Ok(())
+    }
+    let mut ret = vec![];
+    walk(base, Path::new(""), &mut ret)?;
+    ret.sort();
+    Ok(ret)
+}
+
+/// Scan the files in a source tree for license and copyright statements.
+///
+/// The `debian/` directory, version control directories and binary files are
+/// skipped, and only the first [`SCAN_LIMIT`] bytes of each file are read.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// findings for all files with at least one license or copyright statement,
+/// sorted by path
+pub fn scan_tree(base: &Path) -> std::io::Result<Vec<LicenseFinding>> {
+    use std::io::Read;
+    let mut ret = vec![];
+    for path in source_files(base)? {
+        if path.starts_with("debian") {
+            continue;
+        }
+        let mut buf = vec![];
+        std::fs::File::open(base.join(&path))?
+            .take(SCAN_LIMIT as u64)
+            .read_to_end(&mut buf)?;
+        if buf.contains(&0) {
+            continue;
+        }
+        let finding = scan_text(&path, &String::from_utf8_lossy(&buf));
+        if !finding.licenses.is_empty() || !finding.copyright.is_empty() {
+            ret.push(finding);
+        }
+    }
+    Ok(ret)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A file whose detected license does not match debian/copyright.
+pub struct LicenseMismatch {
+    /// Path of the file, relative to the root of the source tree
+    pub path: PathBuf,
+
+    /// Licenses detected in the file
+    pub detected: Vec<String>,
+
+    /// License declared for the file in debian/copyright, if any
+    pub declared: Option<String>,
+
+    /// The Files pattern in debian/copyright that covers the file, if any
+    pub pattern: Option<String>,
+}
+
+/// Normalize a license name for comparison.
+fn normalize_license_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Compare detected licenses against debian/copyright.
+///
+/// A file is reported if debian/copyright does not cover it, or if any of
+/// the licenses detected in it does not appear in its declared license
+/// expression. Files without detected licenses are not reported.
+///
+/// # Arguments
+/// * `copyright` - The parsed debian/copyright file
+/// * `findings` - Findings from [`scan_tree`] or [`scan_text`]
+///
+/// # Returns
+/// the mismatches, in the order of `findings`
+pub fn reconcile_licenses(
+    copyright: &Copyright,
+    findings: &[LicenseFinding],
+) -> Vec<LicenseMismatch> {
+    let matcher = copyright.matcher().ok();
+    let mut ret = vec![];
+    for finding in findings {
+        if finding.licenses.is_empty() {
+            continue;
+        }
+        let (declared, pattern) = match matcher.as_ref().and_then(|m| m.find_match(&finding.path)) {
+            Some((paragraph, pattern)) => (
+                paragraph
+                    .license()
+                    .and_then(|l| l.name().map(|n| n.to_string())),
+                Some(pattern.pattern().to_string()),
+            ),
+            None => (None, None),
+        };
+        let declared_names: Vec<String> = declared
+            .as_deref()
+            .map(|d| {
+                LicenseExpr::parse(d)
+                    .license_names()
+                    .into_iter()
+                    .map(normalize_license_name)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let matches = finding
+            .licenses
+            .iter()
+            .all(|l| declared_names.contains(&normalize_license_name(l)));
+        if !matches {
+            ret.push(LicenseMismatch {
+                path: finding.path.clone(),
+                detected: finding.licenses.clone(),
+                declared,
+                pattern,
+            });
+        }
+    }
+    ret
+}
+
+/// Check whether the contents of a debian/copyright file use the DEP-5 format.
+pub fn is_dep5(text: &str) -> bool {
+    text.lines()
+        .find(|l| !l.trim().is_empty())
+        .map(|l| l.starts_with("Format:") || l.starts_with("Format-Specification:"))
+        .unwrap_or(false)
+}
+
+#[derive(Debug)]
+/// The result of converting a free-form copyright file to DEP-5.
+pub struct Dep5Conversion {
+    /// The converted copyright file
+    pub copyright: Copyright,
+
+    /// Sections that could not be converted unambiguously and need manual review
+    pub review: Vec<String>,
+}
+
+/// Map the name of a file in /usr/share/common-licenses to a DEP-5 license name.
+fn common_license_name(name: &str, or_later: bool) -> String {
+    let name = match name {
+        "BSD" => "BSD-3-clause",
+        "Apache-2.0" | "Artistic" | "CC0-1.0" | "MPL-2.0" | "MPL-1.1" => return name.to_string(),
+        name => name,
+    };
+    if or_later && (name.starts_with("GPL") || name.starts_with("LGPL") || name.starts_with("GFDL"))
+    {
+        format!("{}+", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Remove the common leading whitespace from each paragraph in a block of lines.
+fn dedent(lines: &[&str]) -> String {
+    let mut paragraphs = vec![];
+    for paragraph in lines.split(|l| l.trim().is_empty()) {
+        if paragraph.is_empty() {
+            continue;
+        }
+        let indent = paragraph
+            .iter()
+            .map(|l| l.len() - l.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        paragraphs.push(
+            paragraph
+                .iter()
+                .map(|l| l[indent..].trim_end())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Extract copyright holders from a block of free-form text.
+fn extract_holders(lines: &[&str]) -> Vec<String> {
+    let statement =
+        lazy_regex::regex!(r"(?i)(?:copyright\s*(?:\(c\)|©)?|\(c\)|©)\s*:?\s*(\d{4}.*)$");
+    let continuation = lazy_regex::regex!(r"^\s+(\d{4}.*)$");
+    let mut holders = vec![];
+    let mut in_statement = false;
+    for line in lines {
+        let captured = if let Some(c) = statement.captures(line) {
+            Some(c[1].to_string())
+        } else if in_statement {
+            continuation.captures(line).map(|c| c[1].to_string())
+        } else {
+            None
+        };
+        in_statement = captured.is_some();
+        if let Some(holder) = captured {
+            let holder = holder
+                .trim()
+                .trim_end_matches(" and")
+                .trim_end_matches([',', '.'])
+                .replacen(", ", " ", 1)
+                .to_string();
+            if !holders.contains(&holder) {
+                holders.push(holder);
+            }
+        }
+    }
+    holders
+}
+
+/// Convert a free-form debian/copyright file to the machine-readable DEP-5 format.
+///
+/// This is a best-effort conversion: the upstream source, upstream authors,
+/// copyright holders and license are extracted with heuristics, and the
+/// license text is moved into a standalone license paragraph. Anything that
+/// can not be determined unambiguously is listed in
+/// [`Dep5Conversion::review`].
+///
+/// # Arguments
+/// * `text` - Contents of the free-form copyright file
+pub fn convert_to_dep5(text: &str) -> Dep5Conversion {
+    let mut review = vec![];
+    let lines: Vec<&str> = text.lines().collect();
+
+    // The packaging section starts at the first paragraph that talks about
+    // the Debian packaging, other than the "debianized by" preamble.
+    let packaging_re =
+        lazy_regex::regex!(r"(?i)debian packaging|packaging is|^\s*Files:\s*debian/");
+    let packaging_start = lines
+        .iter()
+        .position(|l| packaging_re.is_match(l))
+        .unwrap_or(lines.len());
+    let (upstream, packaging) = lines.split_at(packaging_start);
+
+    let mut copyright = Copyright::new();
+    let mut header = copyright.header().unwrap();
+
+    if let Some(c) = lazy_regex::regex!(
+        r"(?is)(?:downloaded|obtained|retrieved|taken|fetched) from:?\s*<?((?:https?|ftp)://[^\s>]+?)>?[.,]?(?:\s|$)"
+    )
+    .captures(text)
+    {
+        header.set_source(&c[1]);
+    } else {
+        review.push("Unable to determine the upstream source".to_string());
+    }
+
+    let authors_re = lazy_regex::regex!(r"(?i)^\s*upstream authors?:\s*(.*)$");
+    if let Some(start) = upstream.iter().position(|l| authors_re.is_match(l)) {
+        let mut authors = vec![];
+        let first = authors_re.captures(upstream[start]).unwrap()[1]
+            .trim()
+            .to_string();
+        if !first.is_empty() {
+            authors.push(first);
+        }
+        for line in &upstream[start + 1..] {
+            if line.trim().is_empty() || !line.starts_with(char::is_whitespace) {
+                break;
+            }
+            authors.push(line.trim().to_string());
+        }
+        if !authors.is_empty() {
+            header.set_upstream_contact(&authors.join("\n"));
+        }
+    }
+
+    // Determine the upstream license
+    let upstream_text = upstream.join("\n");
+    let or_later = lazy_regex::regex_is_match!(r"(?i)any\s+later\s+version", &upstream_text);
+    let mut licenses: Vec<String> = vec![];
+    for c in lazy_regex::regex!(r"/usr/share/common-licenses/([A-Za-z0-9.\-]+?)[.'`]?(?:\s|$|['`])")
+        .captures_iter(&upstream_text)
+    {
+        let name = common_license_name(&c[1], or_later);
+        if !licenses.contains(&name) {
+            licenses.push(name);
+        }
+    }
+    if licenses.is_empty() {
+        licenses = scan_text(Path::new(""), &upstream_text).licenses;
+    }
+    let license_name = match licenses.as_slice() {
+        [] => {
+            review.push("Unable to determine the upstream license".to_string());
+            "UNKNOWN".to_string()
+        }
+        [name] => name.clone(),
+        names => {
+            review.push(format!(
+                "Multiple upstream licenses found ({}); assign them to the right files",
+                names.join(", ")
+            ));
+            names[0].clone()
+        }
+    };
+    if license_name == "GPL" || license_name == "LGPL" {
+        review.push(format!(
+            "The version of the {} license is not specified",
+            license_name
+        ));
+    }
+
+    // Extract the license text
+    let license_heading = lazy_regex::regex!(r"(?i)^\s*licen[cs]e\s*:?\s*(.*)$");
+    let license_text = upstream
+        .iter()
+        .position(|l| license_heading.is_match(l))
+        .map(|start| {
+            let inline = license_heading.captures(upstream[start]).unwrap()[1].to_string();
+            let mut block: Vec<&str> = vec![inline.trim()];
+            block.extend(&upstream[start + 1..]);
+            dedent(&block)
+        })
+        .filter(|t| !t.is_empty());
+    if license_text.is_none() {
+        review.push("Unable to find the upstream license text".to_string());
+    }
+
+    let mut upstream_holders = extract_holders(upstream);
+    if upstream_holders.is_empty() {
+        review.push("Unable to determine the upstream copyright holders".to_string());
+        upstream_holders.push("UNKNOWN".to_string());
+    }
+    let holders: Vec<&str> = upstream_holders.iter().map(|h| h.as_str()).collect();
+    copyright.add_files(
+        &["*"],
+        &holders,
+        &debian_copyright::License::Name(license_name.clone()),
+    );
+
+    // Determine the packaging copyright
+    let mut packaging_holders = extract_holders(packaging);
+    if packaging_holders.is_empty() {
+        if let Some(c) =
+            lazy_regex::regex!(r"(?is)debianized by\s+(.+?)\s+on\s+\S*?\s*\S*?\s*\S*?\s*(\d{4})")
+                .captures(text)
+        {
+            packaging_holders.push(format!("{} {}", &c[2], c[1].trim()));
+        }
+    }
+    if !packaging_holders.is_empty() {
+        let packaging_text = packaging.join("\n");
+        let packaging_licenses = scan_text(Path::new(""), &packaging_text).licenses;
+        let same_as_upstream =
+            lazy_regex::regex_is_match!(r"(?i)see above|same as", &packaging_text);
+        let packaging_license = match packaging_licenses.first() {
+            Some(name) if !same_as_upstream => name.clone(),
+            _ => license_name.clone(),
+        };
+        let holders: Vec<&str> = packaging_holders.iter().map(|h| h.as_str()).collect();
+        copyright.add_files(
+            &["debian/*"],
+            &holders,
+            &debian_copyright::License::Name(packaging_license),
+        );
+    }
+
+    if let Some(license_text) = license_text {
+        copyright.add_license(&debian_copyright::License::Named(
+            license_name,
+            license_text,
+        ));
+    }
+
+    Dep5Conversion { copyright, review }
+}
+
+/// Return the document indices of the Files paragraphs, in order.
+fn files_paragraph_indices(copyright: &Copyright) -> Vec<usize> {
+    copyright
+        .as_deb822()
+        .paragraphs()
+        .enumerate()
+        .filter(|(_, p)| p.contains_key("Files"))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Merge adjacent Files paragraphs with identical copyright, license and comment.
+///
+/// Only paragraphs that are adjacent (ignoring standalone License paragraphs)
+/// are merged, since moving patterns past other Files paragraphs could change
+/// which paragraph applies to a file.
+///
+/// # Returns
+/// a description of each change, suitable for the changelog
+pub fn merge_duplicate_files(copyright: &mut Copyright) -> Vec<String> {
+    let mut changes = vec![];
+    let mut doc = copyright.as_deb822().clone();
+    let indices = files_paragraph_indices(copyright);
+    let mut paragraphs: Vec<_> = copyright.iter_files().collect();
+    let mut removed = vec![];
+    // Index of the paragraph that duplicates are merged into
+    let mut target = 0;
+    for i in 1..paragraphs.len() {
+        let (a, b) = (&paragraphs[target], &paragraphs[i]);
+        if a.copyright() == b.copyright()
+            && a.license() == b.license()
+            && a.comment() == b.comment()
+        {
+            changes.push(format!(
+                "Merge Files paragraphs for {} and {}.",
+                a.files().join(" "),
+                b.files().join(" ")
+            ));
+            for pattern in b.files() {
+                paragraphs[target].add_file(&pattern);
+            }
+            removed.push(indices[i]);
+        } else {
+            target = i;
+        }
+    }
+    for index in removed.into_iter().rev() {
+        doc.remove_paragraph(index);
+    }
+    changes
+}
+
+/// Sort key for a Files pattern; lower keys are more general.
+fn pattern_specificity(pattern: &str) -> usize {
+    if pattern == "*" {
+        0
+    } else {
+        pattern.matches('/').count() + 1
+    }
+}
+
+/// Sort Files paragraphs from general to specific.
+///
+/// DEP-5 applies the last matching paragraph, so more specific paragraphs
+/// have to come after more general ones. The sort is stable, and paragraphs
+/// are ordered by their most general pattern.
+///
+/// # Returns
+/// a description of the change, suitable for the changelog
+pub fn sort_files_paragraphs(copyright: &mut Copyright) -> Vec<String> {
+    let indices = files_paragraph_indices(copyright);
+    let keys: Vec<usize> = copyright
+        .iter_files()
+        .map(|p| {
+            p.files()
+                .iter()
+                .map(|f| pattern_specificity(f))
+                .min()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..keys.len()).collect();
+    order.sort_by_key(|i| keys[*i]);
+    if order.iter().enumerate().all(|(i, o)| i == *o) {
+        return vec![];
+    }
+    let mut doc = copyright.as_deb822().clone();
+    // Move the paragraphs into place one by one, from the first slot onwards
+    let mut current: Vec<usize> = (0..keys.len()).collect();
+    for (slot, wanted) in order.iter().enumerate() {
+        let from = current.iter().position(|c| c == wanted).unwrap();
+        if from != slot {
+            doc.move_paragraph(indices[from], indices[slot]);
+            let item = current.remove(from);
+            current.insert(slot, item);
+        }
+    }
+    vec!["Sort Files paragraphs from general to specific.".to_string()]
+}
+
+/// Normalize a single Files pattern.
+fn normalize_pattern(pattern: &str) -> String {
+    let mut pattern = pattern
+        .trim_start_matches("./")
+        .trim_start_matches('/')
+        .to_string();
+    while pattern.contains("**") {
+        pattern = pattern.replace("**", "*");
+    }
+    if pattern.ends_with('/') {
+        pattern.push('*');
+    }
+    if pattern.is_empty() {
+        pattern.push('*');
+    }
+    pattern
+}
+
+/// Normalize the wildcard patterns in Files paragraphs.
+///
+/// Leading `./` and `/` are removed, `**` is collapsed to `*`, patterns for
+/// directories get a trailing `*` and duplicate patterns are removed.
+///
+/// # Returns
+/// a description of each change, suitable for the changelog
+pub fn normalize_files_patterns(copyright: &mut Copyright) -> Vec<String> {
+    let mut changes = vec![];
+    for mut paragraph in copyright.iter_files() {
+        let files = paragraph.files();
+        let mut normalized: Vec<String> = vec![];
+        for pattern in &files {
+            let new = normalize_pattern(pattern);
+            if &new != pattern {
+                changes.push(format!("Normalize Files pattern {} to {}.", pattern, new));
+            }
+            if normalized.contains(&new) {
+                changes.push(format!("Remove duplicate Files pattern {}.", new));
+            } else {
+                normalized.push(new);
+            }
+        }
+        if normalized != files {
+            paragraph.set_files(&normalized.iter().map(|f| f.as_str()).collect::<Vec<_>>());
+        }
+    }
+    changes
+}
+
+/// Remove Files paragraphs whose patterns do not match any files.
+///
+/// # Arguments
+/// * `copyright` - Copyright file to modify
+/// * `files` - Paths of all files in the source tree, e.g. from [`source_files`]
+///
+/// # Returns
+/// a description of each change, suitable for the changelog
+pub fn remove_unused_files_paragraphs(copyright: &mut Copyright, files: &[PathBuf]) -> Vec<String> {
+    let indices = files_paragraph_indices(copyright);
+    let mut unused = vec![];
+    let mut changes = vec![];
+    for (paragraph, index) in copyright.iter_files().zip(indices) {
+        let patterns = match paragraph.try_compiled_patterns() {
+            Ok(patterns) => patterns,
+            Err(_) => continue,
+        };
+        if !files
+            .iter()
+            .any(|f| patterns.iter().any(|p| p.is_match_path(f)))
+        {
+            changes.push(format!(
+                "Remove Files paragraph for {}, which matches no files.",
+                paragraph.files().join(" ")
+            ));
+            unused.push(index);
+        }
+    }
+    let mut doc = copyright.as_deb822().clone();
+    for index in unused.into_iter().rev() {
+        doc.remove_paragraph(index);
+    }
+    changes
+}
+
+/// Find the files that are not covered by any Files paragraph.
+///
+/// # Arguments
+/// * `copyright` - The parsed debian/copyright file
+/// * `files` - Paths of all files in the source tree, e.g. from [`source_files`]
+///
+/// # Returns
+/// the uncovered files, in the order of `files`
+pub fn find_uncovered_files(copyright: &Copyright, files: &[PathBuf]) -> Vec<PathBuf> {
+    let matcher = match copyright.matcher() {
+        Ok(matcher) => matcher,
+        Err(_) => return vec![],
+    };
+    files
+        .iter()
+        .filter(|f| matcher.find_files(f).is_none())
+        .cloned()
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A suggested Files paragraph for files not covered by debian/copyright.
+pub struct SuggestedFiles {
+    /// File patterns
+    pub patterns: Vec<String>,
+
+    /// Detected copyright holders
+    pub copyright: Vec<String>,
+
+    /// Detected license, if any
+    pub license: Option<String>,
+}
+
+impl SuggestedFiles {
+    /// Add the suggested paragraph to a copyright file.
+    ///
+    /// Holders and licenses that could not be detected are filled in as
+    /// "UNKNOWN", to be reviewed by a human.
+    pub fn add_to(&self, copyright: &mut Copyright) {
+        let patterns: Vec<&str> = self.patterns.iter().map(|p| p.as_str()).collect();
+        let holders: Vec<&str> = if self.copyright.is_empty() {
+            vec!["UNKNOWN"]
+        } else {
+            self.copyright.iter().map(|h| h.as_str()).collect()
+        };
+        copyright.add_files(
+            &patterns,
+            &holders,
+            &debian_copyright::License::Name(
+                self.license
+                    .clone()
+                    .unwrap_or_else(|| "UNKNOWN".to_string()),
+            ),
+        );
+    }
+}
+
+/// Suggest Files paragraphs for files that are not covered by debian/copyright.
+///
+/// Uncovered files are scanned for license and copyright statements and
+/// grouped by what was found. A group that contains every file in a
+/// directory is collapsed to a `<directory>/*` pattern.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `files` - Paths of all files in the source tree, e.g. from [`source_files`]
+/// * `uncovered` - Uncovered files, e.g. from [`find_uncovered_files`]
+pub fn suggest_files_paragraphs(
+    base: &Path,
+    files: &[PathBuf],
+    uncovered: &[PathBuf],
+) -> std::io::Result<Vec<SuggestedFiles>> {
+    use std::io::Read;
+    let mut groups: Vec<(Option<String>, Vec<String>, Vec<PathBuf>)> = vec![];
+    for path in uncovered {
+        let mut buf = vec![];
+        std::fs::File::open(base.join(path))?
+            .take(SCAN_LIMIT as u64)
+            .read_to_end(&mut buf)?;
+        let finding = if buf.contains(&0) {
+            LicenseFinding::default()
+        } else {
+            scan_text(path, &String::from_utf8_lossy(&buf))
+        };
+        let license = if finding.licenses.is_empty() {
+            None
+        } else {
+            Some(finding.licenses.join(" or "))
+        };
+        match groups
+            .iter_mut()
+            .find(|(l, c, _)| l == &license && c == &finding.copyright)
+        {
+            Some((_, _, paths)) => paths.push(path.clone()),
+            None => groups.push((license, finding.copyright, vec![path.clone()])),
+        }
+    }
+    let mut ret = vec![];
+    for (license, copyright, paths) in groups {
+        let mut patterns: Vec<String> = vec![];
+        let mut collapsed: Vec<&Path> = vec![];
+        for path in &paths {
+            // Find the topmost directory whose files all belong to this group
+            let mut dir = None;
+            for ancestor in path.ancestors().skip(1) {
+                if ancestor.as_os_str().is_empty() {
+                    break;
+                }
+                if files
+                    .iter()
+                    .filter(|f| f.starts_with(ancestor))
+                    .all(|f| paths.contains(f))
+                {
+                    dir = Some(ancestor);
+                } else {
+                    break;
+                }
+            }
+            match dir {
+                Some(dir) => {
+                    if !collapsed.contains(&dir) {
+                        collapsed.push(dir);
+                        patterns.push(format!("{}/*", dir.display()));
+                    }
+                }
+                None => patterns.push(path.display().to_string()),
+            }
+        }
+        ret.push(SuggestedFiles {
+            patterns,
+            copyright,
+            license,
+        });
+    }
+    Ok(ret)
+}
+
+/// Extend the year range of a copyright holder line to include a year.
+///
+/// The last year or year range is extended, e.g. "2015, 2017-2019 John Doe"
+/// becomes "2015, 2017-2024 John Doe" for 2024.
+///
+/// # Returns
+/// the updated line, or None if it is up to date or does not start with years
+pub fn update_year_range(line: &str, year: i32) -> Option<String> {
+    let c = lazy_regex::regex_captures!(
+        r"^(\s*(?:\d{4}(?:\s*-\s*\d{4})?\s*,\s*)*)(\d{4})(?:\s*-\s*(\d{4}))?(,?\s.*|,?)$",
+        line
+    );
+    let (_, prefix, first, last, rest) = c?;
+    let first: i32 = first.parse().ok()?;
+    let last: i32 = if last.is_empty() {
+        first
+    } else {
+        last.parse().ok()?
+    };
+    if last >= year || first > year {
+        return None;
+    }
+    Some(format!("{}{}-{}{}", prefix, first, year, rest))
+}
+
+/// Update the years of a paragraph's copyright holder lines.
+///
+/// # Arguments
+/// * `paragraph` - Files paragraph to update
+/// * `year` - Year that should be included
+/// * `holder` - Only update lines that mention this holder; if None, only
+///   paragraphs with a single holder line are updated
+///
+/// # Returns
+/// whether the paragraph was changed
+fn update_paragraph_years(
+    paragraph: &mut debian_copyright::lossless::FilesParagraph,
+    year: i32,
+    holder: Option<&str>,
+) -> bool {
+    let lines = paragraph.copyright();
+    if holder.is_none() && lines.len() != 1 {
+        return false;
+    }
+    let mut changed = false;
+    let updated: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            if holder.map(|h| line.contains(h)).unwrap_or(true) {
+                if let Some(new) = update_year_range(line, year) {
+                    changed = true;
+                    return new;
+                }
+            }
+            line.clone()
+        })
+        .collect();
+    if changed {
+        paragraph.set_copyright(&updated.iter().map(|l| l.as_str()).collect::<Vec<_>>());
+    }
+    changed
+}
+
+/// Return a function that determines the year in which a file was last modified,
+/// based on its modification time.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+pub fn mtime_year(base: &Path) -> impl Fn(&Path) -> Option<i32> + '_ {
+    move |path| {
+        use chrono::Datelike;
+        let modified = std::fs::metadata(base.join(path)).ok()?.modified().ok()?;
+        Some(chrono::DateTime::<chrono::Utc>::from(modified).year())
+    }
+}
+
+/// Update the years in upstream Files paragraphs.
+///
+/// For each paragraph other than `debian/*`, the most recent year in which
+/// one of the files it applies to was modified is determined, and the
+/// holder line is extended to include it. Paragraphs with more than one
+/// holder line are left alone, since it is not clear which holder made the
+/// changes.
+///
+/// Callers should honour [`crate::config::Config::update_copyright_years`].
+///
+/// # Arguments
+/// * `copyright` - Copyright file to modify
+/// * `files` - Paths of all files in the source tree, e.g. from [`source_files`]
+/// * `last_modified` - Returns the year in which a file was last modified,
+///   e.g. [`mtime_year`] or a lookup in the version control history
+///
+/// # Returns
+/// a description of each change, suitable for the changelog
+pub fn update_copyright_years(
+    copyright: &mut Copyright,
+    files: &[PathBuf],
+    last_modified: impl Fn(&Path) -> Option<i32>,
+) -> Vec<String> {
+    let matcher = match copyright.matcher() {
+        Ok(matcher) => matcher,
+        Err(_) => return vec![],
+    };
+    let mut latest: std::collections::HashMap<Vec<String>, i32> = Default::default();
+    for path in files {
+        if path.starts_with("debian") {
+            continue;
+        }
+        if let Some(paragraph) = matcher.find_files(path) {
+            if let Some(year) = last_modified(path) {
+                let entry = latest.entry(paragraph.files()).or_insert(year);
+                *entry = (*entry).max(year);
+            }
+        }
+    }
+    let mut changes = vec![];
+    for mut paragraph in copyright.iter_files() {
+        let patterns = paragraph.files();
+        if let Some(year) = latest.get(&patterns) {
+            if update_paragraph_years(&mut paragraph, *year, None) {
+                changes.push(format!(
+                    "Update copyright years for {}.",
+                    patterns.join(" ")
+                ));
+            }
+        }
+    }
+    changes
+}
+
+/// Update the years in the `debian/*` Files paragraph.
+///
+/// Callers should honour [`crate::config::Config::update_copyright_years`].
+///
+/// # Arguments
+/// * `copyright` - Copyright file to modify
+/// * `year` - Year to include, typically the current year
+/// * `packager` - Only update the line for this packager, e.g. their name
+///
+/// # Returns
+/// a description of the change, suitable for the changelog
+pub fn update_packaging_copyright_year(
+    copyright: &mut Copyright,
+    year: i32,
+    packager: Option<&str>,
+) -> Vec<String> {
+    for mut paragraph in copyright.iter_files() {
+        if paragraph.files().iter().any(|f| f == "debian/*") {
+            if update_paragraph_years(&mut paragraph, year, packager) {
+                return vec![format!(
+                    "Update copyright years for debian/* to include {}.",
+                    year
+                )];
+            }
+            break;
+        }
+    }
+    vec![]
+}
+
+/// License short name keywords defined by the DEP-5 specification.
+pub const DEP5_LICENSE_KEYWORDS: &[&str] = &[
+    "public-domain",
+    "Apache",
+    "Artistic",
+    "BSD-2-clause",
+    "BSD-3-clause",
+    "BSD-4-clause",
+    "ISC",
+    "CC-BY",
+    "CC-BY-SA",
+    "CC-BY-ND",
+    "CC-BY-NC",
+    "CC-BY-NC-SA",
+    "CC-BY-NC-ND",
+    "CC0",
+    "CDDL",
+    "CPL",
+    "EFL",
+    "Expat",
+    "GPL",
+    "LGPL",
+    "GFDL",
+    "GFDL-NIV",
+    "LPPL",
+    "MPL",
+    "Perl",
+    "Python",
+    "QPL",
+    "W3C",
+    "Zlib",
+    "Zope",
+];
+
+/// Commonly used SPDX license identifiers.
+const SPDX_LICENSE_IDS: &[&str] = &[
+    "0BSD",
+    "AGPL-3.0-only",
+    "AGPL-3.0-or-later",
+    "Apache-1.1",
+    "Apache-2.0",
+    "Artistic-1.0",
+    "Artistic-1.0-Perl",
+    "Artistic-2.0",
+    "BSD-1-Clause",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "BSD-4-Clause",
+    "BSL-1.0",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "CC-BY-SA-3.0",
+    "CC-BY-SA-4.0",
+    "CC0-1.0",
+    "CDDL-1.0",
+    "CDDL-1.1",
+    "EPL-1.0",
+    "EPL-2.0",
+    "EUPL-1.2",
+    "GFDL-1.2-only",
+    "GFDL-1.2-or-later",
+    "GFDL-1.3-only",
+    "GFDL-1.3-or-later",
+    "GPL-1.0-only",
+    "GPL-1.0-or-later",
+    "GPL-2.0-only",
+    "GPL-2.0-or-later",
+    "GPL-3.0-only",
+    "GPL-3.0-or-later",
+    "ISC",
+    "LGPL-2.0-only",
+    "LGPL-2.0-or-later",
+    "LGPL-2.1-only",
+    "LGPL-2.1-or-later",
+    "LGPL-3.0-only",
+    "LGPL-3.0-or-later",
+    "LPPL-1.3c",
+    "MIT",
+    "MIT-0",
+    "MPL-1.1",
+    "MPL-2.0",
+    "MS-PL",
+    "OFL-1.1",
+    "OpenSSL",
+    "PSF-2.0",
+    "Python-2.0",
+    "Unicode-DFS-2016",
+    "Unlicense",
+    "W3C",
+    "WTFPL",
+    "X11",
+    "Zlib",
+    "ZPL-2.1",
+];
+
+/// Common misspellings and aliases of license names, with their DEP-5 names.
+const LICENSE_ALIASES: &[(&str, &str)] = &[
+    ("apache 2", "Apache-2.0"),
+    ("apache 2.0", "Apache-2.0"),
+    ("apache-2", "Apache-2.0"),
+    ("apache2", "Apache-2.0"),
+    ("apache2.0", "Apache-2.0"),
+    ("apache license 2.0", "Apache-2.0"),
+    ("bsd-2", "BSD-2-clause"),
+    ("bsd-3", "BSD-3-clause"),
+    ("bsd-4", "BSD-4-clause"),
+    ("bsd2", "BSD-2-clause"),
+    ("bsd3", "BSD-3-clause"),
+    ("mit/x11", "Expat"),
+    ("mit-expat", "Expat"),
+    ("pd", "public-domain"),
+    ("public domain", "public-domain"),
+    ("publicdomain", "public-domain"),
+    ("zlib/libpng", "Zlib"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The result of checking a license short name.
+pub enum LicenseNameCheck {
+    /// The name uses a DEP-5 keyword
+    Dep5,
+
+    /// The name is an SPDX license identifier
+    Spdx,
+
+    /// The name is a known misspelling or alias of the given name
+    Alias(String),
+
+    /// The name is not known; DEP-5 allows custom names, but they need a
+    /// standalone License paragraph
+    Unknown,
+}
+
+/// Find the canonical spelling of a DEP-5 license name, ignoring case.
+fn dep5_canonical_name(name: &str) -> Option<String> {
+    let c = lazy_regex::regex_captures!(r"^(.*?)(-\d+(?:\.\d+)*)?(\+)?$", name)?;
+    let (_, keyword, version, plus) = c;
+    let canonical = DEP5_LICENSE_KEYWORDS
+        .iter()
+        .find(|k| k.eq_ignore_ascii_case(keyword))?;
+    Some(format!("{}{}{}", canonical, version, plus))
+}
+
+/// Check a single license short name.
+///
+/// # Arguments
+/// * `name` - License short name, without `with` exceptions
+pub fn check_license_name(name: &str) -> LicenseNameCheck {
+    let name = name.trim();
+    if let Some(canonical) = dep5_canonical_name(name) {
+        return if canonical == name {
+            LicenseNameCheck::Dep5
+        } else {
+            LicenseNameCheck::Alias(canonical)
+        };
+    }
+    if SPDX_LICENSE_IDS.contains(&name) {
+        return LicenseNameCheck::Spdx;
+    }
+    if let Some(spdx) = SPDX_LICENSE_IDS
+        .iter()
+        .find(|id| id.eq_ignore_ascii_case(name))
+    {
+        return LicenseNameCheck::Alias(spdx.to_string());
+    }
+    let lower = name.to_lowercase();
+    if let Some((_, canonical)) = LICENSE_ALIASES.iter().find(|(alias, _)| *alias == lower) {
+        return LicenseNameCheck::Alias(canonical.to_string());
+    }
+    // GPLv2, GPL v2+, LGPL2.1 or later, etc.
+    if let Some((_, family, version, later)) = lazy_regex::regex_captures!(
+        r"(?i)^((?:a|l)?gpl)\s*-?\s*v?\s*(\d(?:\.\d)?)\s*(\+|\s*or\s*later|-or-later)?$",
+        name
+    ) {
+        let family = family.to_uppercase();
+        let version = version.strip_suffix(".0").unwrap_or(version);
+        return LicenseNameCheck::Alias(format!(
+            "{}-{}{}",
+            family,
+            version,
+            if later.is_empty() { "" } else { "+" }
+        ));
+    }
+    LicenseNameCheck::Unknown
+}
+
+/// Replace aliases in a license expression, preserving its formatting.
+///
+/// # Returns
+/// the fixed expression and the names that were replaced
+fn fix_license_expression(expr: &str) -> (String, Vec<(String, String)>) {
+    let mut ret = expr.to_string();
+    let mut replaced = vec![];
+    let mut ranges = LicenseExpr::name_ranges(expr);
+    ranges.sort_by_key(|(_, range)| std::cmp::Reverse(range.start));
+    for (name, range) in ranges {
+        if let LicenseNameCheck::Alias(canonical) = check_license_name(name) {
+            ret.replace_range(range, &canonical);
+            replaced.push((name.to_string(), canonical));
+        }
+    }
+    replaced.reverse();
+    (ret, replaced)
+}
+
+/// Replace misspelled and aliased license names with their canonical names.
+///
+/// Both the License fields of Files paragraphs and the names of standalone
+/// License paragraphs are fixed.
+///
+/// # Returns
+/// a description of each change, suitable for the changelog
+pub fn fix_license_names(copyright: &mut Copyright) -> Vec<String> {
+    let mut changes = vec![];
+    let mut describe = |replaced: Vec<(String, String)>| {
+        for (old, new) in replaced {
+            let change = format!("Use standard license name {} rather than {}.", new, old);
+            if !changes.contains(&change) {
+                changes.push(change);
+            }
+        }
+    };
+    for mut paragraph in copyright.iter_files() {
+        let license = match paragraph.license() {
+            Some(license) => license,
+            None => continue,
+        };
+        let name = match license.name() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let (fixed, replaced) = fix_license_expression(&name);
+        if replaced.is_empty() {
+            continue;
+        }
+        paragraph.set_license(&match license.text() {
+            Some(text) => debian_copyright::License::Named(fixed, text.to_string()),
+            None => debian_copyright::License::Name(fixed),
+        });
+        describe(replaced);
+    }
+    for mut paragraph in copyright.iter_licenses() {
+        let name = match paragraph.name() {
+            Some(name) => name,
+            None => continue,
+        };
+        let (fixed, replaced) = fix_license_expression(&name);
+        if replaced.is_empty() {
+            continue;
+        }
+        paragraph.set_name(&fixed);
+        describe(replaced);
+    }
+    changes
+}
+
+/// Return the patterns in the Files-Excluded header field.
+pub fn files_excluded(copyright: &Copyright) -> Vec<String> {
+    copyright
+        .header()
+        .and_then(|h| h.files_excluded())
+        .unwrap_or_default()
+}
+
+/// Add a pattern to the Files-Excluded header field.
+///
+/// # Returns
+/// whether the pattern was added; false if it was already present
+pub fn add_files_excluded(copyright: &mut Copyright, pattern: &str) -> bool {
+    let mut header = match copyright.header() {
+        Some(header) => header,
+        None => return false,
+    };
+    let mut patterns = header.files_excluded().unwrap_or_default();
+    if patterns.iter().any(|p| p == pattern) {
+        return false;
+    }
+    patterns.push(pattern.to_string());
+    header.set_files_excluded(&patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>());
+    true
+}
+
+/// Remove a pattern from the Files-Excluded header field.
+///
+/// The field is removed altogether when its last pattern is removed.
+///
+/// # Returns
+/// whether the pattern was present
+pub fn remove_files_excluded(copyright: &mut Copyright, pattern: &str) -> bool {
+    let mut header = match copyright.header() {
+        Some(header) => header,
+        None => return false,
+    };
+    let mut patterns = header.files_excluded().unwrap_or_default();
+    let len = patterns.len();
+    patterns.retain(|p| p != pattern);
+    if patterns.len() == len {
+        return false;
+    }
+    if patterns.is_empty() {
+        header.remove_field("Files-Excluded");
+    } else {
+        header.set_files_excluded(&patterns.iter().map(|p| p.as_str()).collect::<Vec<_>>());
+    }
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An inconsistency between Files-Excluded and the repack options in debian/watch.
+///
+/// Each variant holds the index of the affected watch entry.
+pub enum RepackIssue {
+    /// Files are excluded, but the entry does not set `repacksuffix`
+    MissingRepackSuffix(usize),
+
+    /// Files are excluded, but the entry does not strip the repack suffix
+    /// from the Debian version with `dversionmangle`
+    MissingDversionmangle(usize),
+
+    /// The entry sets `repacksuffix`, but the tarball is never repacked
+    UnusedRepackSuffix(usize),
+}
+
+impl RepackIssue {
+    /// Index of the affected watch entry.
+    pub fn entry(&self) -> usize {
+        match self {
+            RepackIssue::MissingRepackSuffix(i)
+            | RepackIssue::MissingDversionmangle(i)
+            | RepackIssue::UnusedRepackSuffix(i) => *i,
+        }
+    }
+}
+
+impl std::fmt::Display for RepackIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RepackIssue::MissingRepackSuffix(i) => {
+                write!(f, "Watch entry {} does not set repacksuffix", i)
+            }
+            RepackIssue::MissingDversionmangle(i) => write!(
+                f,
+                "Watch entry {} does not strip the repack suffix with dversionmangle",
+                i
+            ),
+            RepackIssue::UnusedRepackSuffix(i) => write!(
+                f,
+                "Watch entry {} sets repacksuffix, but no files are excluded",
+                i
+            ),
+        }
+    }
+}
+
+/// Check whether a dversionmangle rule strips a repack suffix.
+fn dversionmangle_strips(mangle: &str, suffix: &str) -> bool {
+    if mangle == "auto" {
+        return true;
+    }
+    let keyword = suffix.trim_start_matches(['+', '~']);
+    let keyword = keyword.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    !keyword.is_empty() && mangle.contains(keyword)
+}
+
+/// Cross-check Files-Excluded against the repack options in debian/watch.
+///
+/// Only entries for the main tarball are checked, since Files-Excluded does
+/// not apply to component tarballs.
+///
+/// # Arguments
+/// * `copyright` - The parsed debian/copyright file
+/// * `wf` - The parsed debian/watch file
+pub fn check_repack_consistency(
+    copyright: &Copyright,
+    wf: &crate::watch::WatchFile,
+) -> Vec<RepackIssue> {
+    let excluding = !files_excluded(copyright).is_empty();
+    let mut issues = vec![];
+    for (i, entry) in wf.entries().enumerate() {
+        if entry.has_option("component") {
+            continue;
+        }
+        let suffix = entry.option("repacksuffix");
+        if excluding {
+            match &suffix {
+                None => issues.push(RepackIssue::MissingRepackSuffix(i)),
+                Some(suffix) => {
+                    let strips = entry
+                        .option("dversionmangle")
+                        .map(|m| dversionmangle_strips(&m, suffix))
+                        .unwrap_or(false);
+                    if !strips {
+                        issues.push(RepackIssue::MissingDversionmangle(i));
+                    }
+                }
+            }
+        } else if suffix.is_some() && !entry.has_option("repack") {
+            issues.push(RepackIssue::UnusedRepackSuffix(i));
+        }
+    }
+    issues
+}
+
+/// Update the repack options in debian/watch to match Files-Excluded.
+///
+/// If files are excluded, `repacksuffix` and `dversionmangle=auto` are added
+/// where missing. If no files are excluded and the tarball is not otherwise
+/// repacked, an unused `repacksuffix` is removed.
+///
+/// # Arguments
+/// * `copyright` - The parsed debian/copyright file
+/// * `wf` - Watch file to update
+/// * `suffix` - Repack suffix to use when adding one, e.g. "+ds"
+///
+/// # Returns
+/// a description of each change, suitable for the changelog
+pub fn sync_repack_options(
+    copyright: &Copyright,
+    wf: &mut crate::watch::WatchFile,
+    suffix: &str,
+) -> Vec<String> {
+    let issues = check_repack_consistency(copyright, wf);
+    let mut changes = vec![];
+    for (i, entry) in wf.entries_mut().enumerate() {
+        for issue in issues.iter().filter(|issue| issue.entry() == i) {
+            match issue {
+                RepackIssue::MissingRepackSuffix(_) => {
+                    entry.set_option("repacksuffix", Some(suffix));
+                    changes.push(format!("Set repacksuffix={} in debian/watch.", suffix));
+                    let strips = entry
+                        .option("dversionmangle")
+                        .map(|m| dversionmangle_strips(&m, suffix))
+                        .unwrap_or(false);
+                    if !strips && !entry.has_option("dversionmangle") {
+                        entry.set_option("dversionmangle", Some("auto"));
+                        changes.push("Set dversionmangle=auto in debian/watch.".to_string());
+                    }
+                }
+                RepackIssue::MissingDversionmangle(_) => {
+                    if !entry.has_option("dversionmangle") {
+                        entry.set_option("dversionmangle", Some("auto"));
+                        changes.push("Set dversionmangle=auto in debian/watch.".to_string());
+                    }
+                }
+                RepackIssue::UnusedRepackSuffix(_) => {
+                    entry.remove_option("repacksuffix");
+                    changes.push("Remove unused repacksuffix from debian/watch.".to_string());
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// Convert an SPDX license expression to a DEP-5 license expression.
+///
+/// The legacy `/` separator used in older Cargo.toml files is treated as `OR`.
+///
+/// # Arguments
+/// * `spdx` - SPDX license expression, e.g. "MIT OR Apache-2.0"
+pub fn spdx_expression_to_dep5(spdx: &str) -> String {
+    let spdx = spdx.replace('/', " OR ");
+    match LicenseExpr::parse_spdx(&spdx) {
+        Ok(expr) => expr
+            .map_leaves(|leaf| match leaf {
+                LicenseExpr::Name(name) => LicenseExpr::Name(spdx_to_dep5(name)),
+                LicenseExpr::WithException(name, exception) => {
+                    LicenseExpr::WithException(spdx_to_dep5(name), exception.clone())
+                }
+                other => other.clone(),
+            })
+            .to_string(),
+        Err(_) => spdx_to_dep5(&spdx),
+    }
+}
+
+/// License and author information from a project's packaging metadata.
+struct ProjectMetadata {
+    license: Option<String>,
+    authors: Vec<String>,
+}
+
+impl ProjectMetadata {
+    fn from_cargo_toml(text: &str) -> Option<Self> {
+        let doc: toml_edit::DocumentMut = text.parse().ok()?;
+        let package = doc.get("package")?;
+        let license = package
+            .get("license")
+            .and_then(|l| l.as_str())
+            .map(spdx_expression_to_dep5);
+        let authors = package
+            .get("authors")
+            .and_then(|a| a.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(ProjectMetadata { license, authors })
+    }
+
+    fn from_pyproject_toml(text: &str) -> Option<Self> {
+        let doc: toml_edit::DocumentMut = text.parse().ok()?;
+        if let Some(project) = doc.get("project") {
+            let license = project.get("license").and_then(|l| {
+                l.as_str()
+                    .map(spdx_expression_to_dep5)
+                    .or_else(|| l.get("text").and_then(|t| t.as_str()).map(spdx_to_dep5))
+            });
+            let authors = project
+                .get("authors")
+                .and_then(|a| a.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|v| {
+                            let table = v.as_inline_table()?;
+                            let name = table.get("name").and_then(|n| n.as_str());
+                            let email = table.get("email").and_then(|e| e.as_str());
+                            match (name, email) {
+                                (Some(name), Some(email)) => Some(format!("{} <{}>", name, email)),
+                                (Some(name), None) => Some(name.to_string()),
+                                (None, Some(email)) => Some(email.to_string()),
+                                (None, None) => None,
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            return Some(ProjectMetadata { license, authors });
+        }
+        let poetry = doc.get("tool")?.get("poetry")?;
+        let license = poetry
+            .get("license")
+            .and_then(|l| l.as_str())
+            .map(spdx_expression_to_dep5);
+        let authors = poetry
+            .get("authors")
+            .and_then(|a| a.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(ProjectMetadata { license, authors })
+    }
+
+    fn from_package_json(text: &str) -> Option<Self> {
+        let data: serde_json::Value = serde_json::from_str(text).ok()?;
+        let license = data
+            .get("license")
+            .and_then(|l| {
+                l.as_str()
+                    .or_else(|| l.get("type").and_then(|t| t.as_str()))
+            })
+            .map(spdx_expression_to_dep5);
+        let person = |v: &serde_json::Value| -> Option<String> {
+            if let Some(s) = v.as_str() {
+                // "Name <email> (url)"
+                return Some(
+                    lazy_regex::regex_replace!(r"\s*\([^)]*\)\s*$", s, "")
+                        .trim()
+                        .to_string(),
+                );
+            }
+            let name = v.get("name").and_then(|n| n.as_str())?;
+            Some(match v.get("email").and_then(|e| e.as_str()) {
+                Some(email) => format!("{} <{}>", name, email),
+                None => name.to_string(),
+            })
+        };
+        let mut authors: Vec<String> = data.get("author").and_then(person).into_iter().collect();
+        if let Some(contributors) = data.get("contributors").and_then(|c| c.as_array()) {
+            authors.extend(contributors.iter().filter_map(person));
+        }
+        Some(ProjectMetadata { license, authors })
+    }
+
+    fn suggestion(self, pattern: String) -> SuggestedFiles {
+        SuggestedFiles {
+            patterns: vec![pattern],
+            copyright: self.authors,
+            license: self.license,
+        }
+    }
+}
+
+/// Suggest Files paragraphs based on Rust, Python or Node.js packaging metadata.
+///
+/// The license and authors of the project are taken from `Cargo.toml`,
+/// `pyproject.toml` or `package.json` and proposed for `Files: *`.
+/// Dependencies vendored in `vendor/` (Rust) or `node_modules/` (Node.js)
+/// get their own paragraphs.
+///
+/// Since packaging metadata does not list copyright years, the suggested
+/// holders need to be reviewed.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+pub fn suggest_from_ecosystem_metadata(base: &Path) -> Vec<SuggestedFiles> {
+    type Parser = fn(&str) -> Option<ProjectMetadata>;
+    let parsers: &[(&str, Parser, Option<&str>)] = &[
+        (
+            "Cargo.toml",
+            ProjectMetadata::from_cargo_toml,
+            Some("vendor"),
+        ),
+        ("pyproject.toml", ProjectMetadata::from_pyproject_toml, None),
+        (
+            "package.json",
+            ProjectMetadata::from_package_json,
+            Some("node_modules"),
+        ),
+    ];
+    let read = |path: &Path, parser: Parser| -> Option<ProjectMetadata> {
+        parser(&std::fs::read_to_string(path).ok()?)
+    };
+    let mut ret = vec![];
+    for (filename, parser, vendor_dir) in parsers {
+        let metadata = match read(&base.join(filename), *parser) {
+            Some(metadata) => metadata,
+            None => continue,
+        };
+        ret.push(metadata.suggestion("*".to_string()));
+        let vendor_dir = match vendor_dir {
+            Some(vendor_dir) => Path::new(vendor_dir),
+            None => break,
+        };
+        let mut vendored = vec![];
+        let mut dirs: Vec<PathBuf> = std::fs::read_dir(base.join(vendor_dir))
+            .map(|entries| entries.flatten().map(|e| e.path()).collect())
+            .unwrap_or_default();
+        dirs.sort();
+        for dir in dirs {
+            // Scoped npm packages live in node_modules/@scope/name
+            let candidates: Vec<PathBuf> = if dir
+                .file_name()
+                .map(|n| n.to_string_lossy().starts_with('@'))
+                .unwrap_or(false)
+            {
+                let mut scoped: Vec<PathBuf> = std::fs::read_dir(&dir)
+                    .map(|entries| entries.flatten().map(|e| e.path()).collect())
+                    .unwrap_or_default();
+                scoped.sort();
+                scoped
+            } else {
+                vec![dir]
+            };
+            for candidate in candidates {
+                if let Some(metadata) = read(&candidate.join(filename), *parser) {
+                    let relpath = candidate.strip_prefix(base).unwrap_or(&candidate);
+                    vendored.push(metadata.suggestion(format!("{}/*", relpath.display())));
+                }
+            }
+        }
+        ret.extend(vendored);
+        break;
+    }
+    ret
+}
+
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize)]
+/// Summary of how well debian/copyright covers a source tree.
+pub struct CoverageReport {
+    /// Number of files in the source tree
+    pub total_files: usize,
+
+    /// Number of files matched by a paragraph other than `Files: *`
+    pub explicit: usize,
+
+    /// Number of files that are only matched by `Files: *`
+    pub fallback: usize,
+
+    /// Number of files that are not matched by any paragraph
+    pub uncovered: usize,
+
+    /// Number of files per license expression
+    pub licenses: std::collections::BTreeMap<String, usize>,
+
+    /// License names that are used in Files paragraphs, but whose text is
+    /// neither included there nor in a standalone License paragraph
+    pub missing_license_paragraphs: Vec<String>,
+}
+
+impl CoverageReport {
+    fn percentage(&self, count: usize) -> f64 {
+        if self.total_files == 0 {
+            0.0
+        } else {
+            100.0 * count as f64 / self.total_files as f64
+        }
+    }
+
+    /// Percentage of files covered by explicit paragraphs.
+    pub fn explicit_percentage(&self) -> f64 {
+        self.percentage(self.explicit)
+    }
+
+    /// Percentage of files only covered by the `Files: *` fallback.
+    pub fn fallback_percentage(&self) -> f64 {
+        self.percentage(self.fallback)
+    }
+
+    /// Percentage of files not covered at all.
+    pub fn uncovered_percentage(&self) -> f64 {
+        self.percentage(self.uncovered)
+    }
+}
+
+/// Produce a report on how well debian/copyright covers a source tree.
+///
+/// # Arguments
+/// * `copyright` - The parsed debian/copyright file
+/// * `files` - Paths of all files in the source tree, e.g. from [`source_files`]
+pub fn coverage_report(copyright: &Copyright, files: &[PathBuf]) -> CoverageReport {
+    let mut report = CoverageReport {
+        total_files: files.len(),
+        ..Default::default()
+    };
+    let matcher = copyright.matcher().ok();
+    for path in files {
+        match matcher.as_ref().and_then(|m| m.find_match(path)) {
+            Some((paragraph, pattern)) => {
+                if pattern.pattern() == "*" {
+                    report.fallback += 1;
+                } else {
+                    report.explicit += 1;
+                }
+                let license = paragraph
+                    .license()
+                    .and_then(|l| l.name().map(|n| n.to_string()))
+                    .unwrap_or_else(|| "UNKNOWN".to_string());
+                *report.licenses.entry(license).or_default() += 1;
+            }
+            None => report.uncovered += 1,
+        }
+    }
+    for paragraph in copyright.iter_files() {
+        let license = match paragraph.license() {
+            Some(license) => license,
+            None => continue,
+        };
+        let name = match license.name() {
+            Some(name) => name,
+            None => continue,
+        };
+        let names = LicenseExpr::parse(name);
+        let names = names.license_names();
+        // A Files paragraph can only carry the text of a single license
+        if license.text().is_some() && names.len() == 1 {
+            continue;
+        }
+        for name in names {
+            if copyright.find_license_by_name(name).is_none()
+                && !report.missing_license_paragraphs.iter().any(|m| m == name)
+            {
+                report.missing_license_paragraphs.push(name.to_string());
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spdx_to_dep5() {
+        assert_eq!(spdx_to_dep5("GPL-2.0-or-later"), "GPL-2+");
+        assert_eq!(spdx_to_dep5("GPL-3.0-only"), "GPL-3");
+        assert_eq!(spdx_to_dep5("LGPL-2.1+"), "LGPL-2.1+");
+        assert_eq!(spdx_to_dep5("MIT"), "Expat");
+        assert_eq!(spdx_to_dep5("BSD-3-Clause"), "BSD-3-clause");
+        assert_eq!(spdx_to_dep5("Apache-2.0"), "Apache-2.0");
+    }
+
+    #[test]
+    fn test_scan_text_spdx() {
+        let finding = scan_text(
+            Path::new("src/foo.c"),
+            "/* SPDX-License-Identifier: GPL-2.0-or-later OR MIT */\n/* Copyright (C) 2019-2021 John Doe <john@example.com> */\n",
+        );
+        assert_eq!(finding.licenses, vec!["GPL-2+", "Expat"]);
+        assert_eq!(
+            finding.copyright,
+            vec!["2019-2021 John Doe <john@example.com>"]
+        );
+    }
+
+    #[test]
+    fn test_scan_text_fingerprint() {
+        let finding = scan_text(
+            Path::new("foo.py"),
+            r#"# Copyright 2020 Jane Doe
+#
+# This program is free software; you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation; either version 2 of the License, or
+# (at your option) any later version.
+"#,
+        );
+        assert_eq!(finding.licenses, vec!["GPL-2+"]);
+        assert_eq!(finding.copyright, vec!["2020 Jane Doe"]);
+
+        let finding = scan_text(Path::new("README"), "Nothing to see here\n");
+        assert_eq!(
+            finding,
+            LicenseFinding {
+                path: PathBuf::from("README"),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_scan_tree() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("src")).unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("src/lib.rs"),
+            "// SPDX-License-Identifier: Apache-2.0\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("src/data.bin"),
+            b"\0SPDX-License-Identifier: MIT",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/rules"),
+            "# SPDX-License-Identifier: MIT\n",
+        )
+        .unwrap();
+        let findings = scan_tree(td.path()).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].path, Path::new("src/lib.rs"));
+        assert_eq!(findings[0].licenses, vec!["Apache-2.0"]);
+    }
+
+    #[test]
+    fn test_reconcile_licenses() {
+        let copyright: Copyright =
+            r#"Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+
+Files: *
+Copyright: 2020 Jane Doe
+License: GPL-2+ or Expat
+
+Files: vendor/*
+Copyright: 2019 John Doe
+License: Apache-2.0
+"#
+            .parse()
+            .unwrap();
+        let findings = vec![
+            LicenseFinding {
+                path: PathBuf::from("src/foo.c"),
+                licenses: vec!["GPL-2+".to_string()],
+                copyright: vec![],
+            },
+            LicenseFinding {
+                path: PathBuf::from("vendor/bar.c"),
+                licenses: vec!["Expat".to_string()],
+                copyright: vec![],
+            },
+            LicenseFinding {
+                path: PathBuf::from("README"),
+                licenses: vec![],
+                copyright: vec!["2020 Jane Doe".to_string()],
+            },
+        ];
+        assert_eq!(
+            reconcile_licenses(&copyright, &findings),
+            vec![LicenseMismatch {
+                path: PathBuf::from("vendor/bar.c"),
+                detected: vec!["Expat".to_string()],
+                declared: Some("Apache-2.0".to_string()),
+                pattern: Some("vendor/*".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_is_dep5() {
+        assert!(is_dep5(
+            "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/\n"
+        ));
+        assert!(!is_dep5("This package was debianized by John Doe\n"));
+    }
+
+    #[test]
+    fn test_convert_to_dep5() {
+        let text = r#"This package was debianized by John Doe <john@example.com> on
+Mon, 1 Jan 2001 12:00:00 +0000.
+
+It was downloaded from http://example.com/foo/
+
+Upstream Author: Jane Upstream <jane@example.com>
+
+Copyright: 2000-2005 Jane Upstream
+           2003 Someone Else
+
+License:
+
+    This program is free software; you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation; either version 2 of the License, or
+    (at your option) any later version.
+
+On Debian systems, the complete text of the GNU General Public License
+can be found in `/usr/share/common-licenses/GPL-2'.
+
+The Debian packaging is (C) 2001, John Doe <john@example.com> and
+is licensed under the GPL, see above.
+"#;
+        let conversion = convert_to_dep5(text);
+        assert_eq!(conversion.review, Vec::<String>::new());
+        let copyright = conversion.copyright;
+        let header = copyright.header().unwrap();
+        assert_eq!(header.source().as_deref(), Some("http://example.com/foo/"));
+        assert_eq!(
+            header.upstream_contact().as_deref(),
+            Some("Jane Upstream <jane@example.com>")
+        );
+        let files: Vec<_> = copyright.iter_files().collect();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].files(), vec!["*"]);
+        assert_eq!(
+            files[0].copyright(),
+            vec!["2000-2005 Jane Upstream", "2003 Someone Else"]
+        );
+        assert_eq!(files[0].license().unwrap().name(), Some("GPL-2+"));
+        assert_eq!(files[1].files(), vec!["debian/*"]);
+        assert_eq!(
+            files[1].copyright(),
+            vec!["2001 John Doe <john@example.com>"]
+        );
+        assert_eq!(files[1].license().unwrap().name(), Some("GPL-2+"));
+        let license = copyright.find_license_by_name("GPL-2+").unwrap();
+        assert!(license
+            .text()
+            .unwrap()
+            .starts_with("This program is free software;"));
+        // The result is a valid DEP-5 file
+        let reparsed: Copyright = copyright.to_string().parse().unwrap();
+        assert_eq!(reparsed.iter_files().count(), 2);
+    }
+
+    #[test]
+    fn test_convert_to_dep5_ambiguous() {
+        let conversion = convert_to_dep5("Some text without much information.\n");
+        assert_eq!(
+            conversion.review,
+            vec![
+                "Unable to determine the upstream source",
+                "Unable to determine the upstream license",
+                "Unable to find the upstream license text",
+                "Unable to determine the upstream copyright holders",
+            ]
+        );
+    }
+
+    const HEADER: &str =
+        "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/\n";
+
+    #[test]
+    fn test_merge_duplicate_files() {
+        let mut copyright: Copyright = format!(
+            r#"{}
+Files: *
+Copyright: 2020 Jane Doe
+License: GPL-2+
+
+Files: src/foo.c
+Copyright: 2019 John Doe
+License: Expat
+
+Files: src/bar.c
+Copyright: 2019 John Doe
+License: Expat
+
+Files: debian/*
+Copyright: 2020 Jane Doe
+License: GPL-2+
+"#,
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(
+            merge_duplicate_files(&mut copyright),
+            vec!["Merge Files paragraphs for src/foo.c and src/bar.c."]
+        );
+        assert_eq!(merge_duplicate_files(&mut copyright), Vec::<String>::new());
+        let files: Vec<_> = copyright.iter_files().map(|p| p.files()).collect();
+        assert_eq!(
+            files,
+            vec![vec!["*"], vec!["src/foo.c", "src/bar.c"], vec!["debian/*"]]
+        );
+    }
+
+    #[test]
+    fn test_sort_files_paragraphs() {
+        let mut copyright: Copyright = format!(
+            r#"{}
+Files: src/foo/*
+Copyright: 2019 John Doe
+License: Expat
+
+Files: *
+Copyright: 2020 Jane Doe
+License: GPL-2+
+
+Files: src/*
+Copyright: 2018 Joe Doe
+License: Expat
+"#,
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(
+            sort_files_paragraphs(&mut copyright),
+            vec!["Sort Files paragraphs from general to specific."]
+        );
+        assert_eq!(sort_files_paragraphs(&mut copyright), Vec::<String>::new());
+        let files: Vec<_> = copyright.iter_files().map(|p| p.files()).collect();
+        assert_eq!(files, vec![vec!["*"], vec!["src/*"], vec!["src/foo/*"]]);
+        assert!(copyright.to_string().starts_with(HEADER));
+    }
+
+    #[test]
+    fn test_normalize_files_patterns() {
+        let mut copyright: Copyright = format!(
+            "{}\nFiles: ./src/** src/*\nCopyright: 2019 John Doe\nLicense: Expat\n\nFiles: doc/\nCopyright: 2019 John Doe\nLicense: Expat\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(
+            normalize_files_patterns(&mut copyright),
+            vec![
+                "Normalize Files pattern ./src/** to src/*.",
+                "Remove duplicate Files pattern src/*.",
+                "Normalize Files pattern doc/ to doc/*.",
+            ]
+        );
+        let files: Vec<_> = copyright.iter_files().map(|p| p.files()).collect();
+        assert_eq!(files, vec![vec!["src/*"], vec!["doc/*"]]);
+    }
+
+    #[test]
+    fn test_remove_unused_files_paragraphs() {
+        let mut copyright: Copyright = format!(
+            "{}\nFiles: *\nCopyright: 2020 Jane Doe\nLicense: GPL-2+\n\nFiles: old/*\nCopyright: 2019 John Doe\nLicense: Expat\n\nFiles: src/foo.c\nCopyright: 2019 John Doe\nLicense: Expat\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        let files = vec![PathBuf::from("src/foo.c"), PathBuf::from("README")];
+        assert_eq!(
+            remove_unused_files_paragraphs(&mut copyright, &files),
+            vec!["Remove Files paragraph for old/*, which matches no files."]
+        );
+        let files: Vec<_> = copyright.iter_files().map(|p| p.files()).collect();
+        assert_eq!(files, vec![vec!["*"], vec!["src/foo.c"]]);
+    }
+
+    #[test]
+    fn test_uncovered_files() {
+        let td = tempfile::tempdir().unwrap();
+        for (path, content) in [
+            ("src/main.c", "/* Copyright 2020 Jane Doe */\n"),
+            (
+                "vendor/lib/a.c",
+                "// SPDX-License-Identifier: MIT\n// Copyright (c) 2021 Lib Author\n",
+            ),
+            (
+                "vendor/lib/b.c",
+                "// SPDX-License-Identifier: MIT\n// Copyright (c) 2021 Lib Author\n",
+            ),
+            ("extra/x.c", "// SPDX-License-Identifier: Apache-2.0\n"),
+            ("extra/y.c", "// nothing\n"),
+        ] {
+            let path = td.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        let mut copyright: Copyright = format!(
+            "{}\nFiles: src/*\nCopyright: 2020 Jane Doe\nLicense: GPL-2+\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        let files = source_files(td.path()).unwrap();
+        let uncovered = find_uncovered_files(&copyright, &files);
+        assert_eq!(
+            uncovered,
+            vec![
+                PathBuf::from("extra/x.c"),
+                PathBuf::from("extra/y.c"),
+                PathBuf::from("vendor/lib/a.c"),
+                PathBuf::from("vendor/lib/b.c"),
+            ]
+        );
+        let suggestions = suggest_files_paragraphs(td.path(), &files, &uncovered).unwrap();
+        assert_eq!(
+            suggestions,
+            vec![
+                SuggestedFiles {
+                    patterns: vec!["extra/x.c".to_string()],
+                    copyright: vec![],
+                    license: Some("Apache-2.0".to_string()),
+                },
+                SuggestedFiles {
+                    patterns: vec!["extra/y.c".to_string()],
+                    copyright: vec![],
+                    license: None,
+                },
+                SuggestedFiles {
+                    patterns: vec!["vendor/*".to_string()],
+                    copyright: vec!["2021 Lib Author".to_string()],
+                    license: Some("Expat".to_string()),
+                },
+            ]
+        );
+        for suggestion in &suggestions {
+            suggestion.add_to(&mut copyright);
+        }
+        assert_eq!(
+            find_uncovered_files(&copyright, &files),
+            Vec::<PathBuf>::new()
+        );
+    }
+
+    #[test]
+    fn test_update_year_range() {
+        assert_eq!(
+            update_year_range("2019 John Doe", 2024).as_deref(),
+            Some("2019-2024 John Doe")
+        );
+        assert_eq!(
+            update_year_range("2015, 2017-2019 John Doe", 2024).as_deref(),
+            Some("2015, 2017-2024 John Doe")
+        );
+        assert_eq!(
+            update_year_range("2018-2021, John Doe", 2024).as_deref(),
+            Some("2018-2024, John Doe")
+        );
+        assert_eq!(update_year_range("2019-2024 John Doe", 2024), None);
+        assert_eq!(update_year_range("John Doe", 2024), None);
+    }
+
+    #[test]
+    fn test_update_copyright_years() {
+        let mut copyright: Copyright = format!(
+            "{}\nFiles: *\nCopyright: 2019 Jane Doe\nLicense: GPL-2+\n\nFiles: vendor/*\nCopyright: 2018 A\n 2019 B\nLicense: Expat\n\nFiles: debian/*\nCopyright: 2019 Jane Doe\n 2020 Joe Packager\nLicense: GPL-2+\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        let files = vec![
+            PathBuf::from("src/a.c"),
+            PathBuf::from("src/b.c"),
+            PathBuf::from("vendor/c.c"),
+            PathBuf::from("debian/rules"),
+        ];
+        let years: std::collections::HashMap<&str, i32> =
+            [("src/a.c", 2021), ("src/b.c", 2022), ("vendor/c.c", 2023)]
+                .into_iter()
+                .collect();
+        let changes = update_copyright_years(&mut copyright, &files, |p| {
+            years.get(p.to_str().unwrap()).copied()
+        });
+        assert_eq!(changes, vec!["Update copyright years for *."]);
+        assert_eq!(
+            update_packaging_copyright_year(&mut copyright, 2024, Some("Joe Packager")),
+            vec!["Update copyright years for debian/* to include 2024."]
+        );
+        let holders: Vec<_> = copyright.iter_files().map(|p| p.copyright()).collect();
+        assert_eq!(
+            holders,
+            vec![
+                vec!["2019-2022 Jane Doe"],
+                vec!["2018 A", "2019 B"],
+                vec!["2019 Jane Doe", "2020-2024 Joe Packager"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mtime_year() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(td.path().join("a"), "").unwrap();
+        filetime::set_file_mtime(
+            td.path().join("a"),
+            filetime::FileTime::from_unix_time(1_000_000_000, 0),
+        )
+        .unwrap();
+        assert_eq!(mtime_year(td.path())(Path::new("a")), Some(2001));
+        assert_eq!(mtime_year(td.path())(Path::new("b")), None);
+    }
+
+    #[test]
+    fn test_check_license_name() {
+        assert_eq!(check_license_name("GPL-2+"), LicenseNameCheck::Dep5);
+        assert_eq!(check_license_name("BSD-3-clause"), LicenseNameCheck::Dep5);
+        assert_eq!(check_license_name("Apache-2.0"), LicenseNameCheck::Dep5);
+        assert_eq!(check_license_name("MIT"), LicenseNameCheck::Spdx);
+        assert_eq!(
+            check_license_name("expat"),
+            LicenseNameCheck::Alias("Expat".to_string())
+        );
+        assert_eq!(
+            check_license_name("GPLv2"),
+            LicenseNameCheck::Alias("GPL-2".to_string())
+        );
+        assert_eq!(
+            check_license_name("LGPL v2.1 or later"),
+            LicenseNameCheck::Alias("LGPL-2.1+".to_string())
+        );
+        assert_eq!(
+            check_license_name("Apache 2.0"),
+            LicenseNameCheck::Alias("Apache-2.0".to_string())
+        );
+        assert_eq!(
+            check_license_name("public domain"),
+            LicenseNameCheck::Alias("public-domain".to_string())
+        );
+        assert_eq!(check_license_name("Foo-Custom"), LicenseNameCheck::Unknown);
+    }
+
+    #[test]
+    fn test_fix_license_names() {
+        let mut copyright: Copyright = format!(
+            "{}\nFiles: *\nCopyright: 2020 Jane Doe\nLicense: GPLv2+ or Apache 2.0\n\nFiles: src/*\nCopyright: 2020 Jane Doe\nLicense: Expat\n\nLicense: GPLv2+\n On Debian systems, see /usr/share/common-licenses/GPL-2.\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(
+            fix_license_names(&mut copyright),
+            vec![
+                "Use standard license name GPL-2+ rather than GPLv2+.",
+                "Use standard license name Apache-2.0 rather than Apache 2.0.",
+            ]
+        );
+        assert_eq!(fix_license_names(&mut copyright), Vec::<String>::new());
+        let licenses: Vec<_> = copyright
+            .iter_files()
+            .map(|p| p.license().unwrap().name().unwrap().to_string())
+            .collect();
+        assert_eq!(licenses, vec!["GPL-2+ or Apache-2.0", "Expat"]);
+        let license = copyright.find_license_by_name("GPL-2+").unwrap();
+        assert_eq!(
+            license.text(),
+            Some("On Debian systems, see /usr/share/common-licenses/GPL-2.")
+        );
+    }
+
+    #[test]
+    fn test_files_excluded() {
+        let mut copyright: Copyright = format!(
+            "{}Files-Excluded: foo.jar\n\nFiles: *\nCopyright: 2020 Jane Doe\nLicense: GPL-2+\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(files_excluded(&copyright), vec!["foo.jar"]);
+        assert!(add_files_excluded(&mut copyright, "doc/*.pdf"));
+        assert!(!add_files_excluded(&mut copyright, "doc/*.pdf"));
+        assert_eq!(files_excluded(&copyright), vec!["foo.jar", "doc/*.pdf"]);
+        assert!(remove_files_excluded(&mut copyright, "foo.jar"));
+        assert!(remove_files_excluded(&mut copyright, "doc/*.pdf"));
+        assert!(!remove_files_excluded(&mut copyright, "doc/*.pdf"));
+        assert!(!copyright.to_string().contains("Files-Excluded"));
+    }
+
+    #[test]
+    fn test_check_repack_consistency() {
+        let mut copyright: Copyright = format!(
+            "{}Files-Excluded: foo.jar\n\nFiles: *\nCopyright: 2020 Jane Doe\nLicense: GPL-2+\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        let mut wf: crate::watch::WatchFile = r#"version=4
+https://example.com/ foo-(\d\S+)\.tar\.gz
+opts=repacksuffix=+dfsg https://example.com/ foo-(\d\S+)\.tar\.gz
+opts=repacksuffix=+ds,dversionmangle=s/\+ds\d*$// https://example.com/ foo-(\d\S+)\.tar\.gz
+opts=component=bar https://example.com/ bar-(\d\S+)\.tar\.gz
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            check_repack_consistency(&copyright, &wf),
+            vec![
+                RepackIssue::MissingRepackSuffix(0),
+                RepackIssue::MissingDversionmangle(1),
+            ]
+        );
+        assert_eq!(
+            sync_repack_options(&copyright, &mut wf, "+ds"),
+            vec![
+                "Set repacksuffix=+ds in debian/watch.",
+                "Set dversionmangle=auto in debian/watch.",
+                "Set dversionmangle=auto in debian/watch.",
+            ]
+        );
+        assert_eq!(check_repack_consistency(&copyright, &wf), vec![]);
+
+        remove_files_excluded(&mut copyright, "foo.jar");
+        assert_eq!(
+            check_repack_consistency(&copyright, &wf),
+            vec![
+                RepackIssue::UnusedRepackSuffix(0),
+                RepackIssue::UnusedRepackSuffix(1),
+                RepackIssue::UnusedRepackSuffix(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spdx_expression_to_dep5() {
+        assert_eq!(
+            spdx_expression_to_dep5("MIT OR Apache-2.0"),
+            "Expat or Apache-2.0"
+        );
+        assert_eq!(
+            spdx_expression_to_dep5("MIT/Apache-2.0"),
+            "Expat or Apache-2.0"
+        );
+        assert_eq!(
+            spdx_expression_to_dep5("GPL-2.0-or-later WITH Classpath-exception-2.0"),
+            "GPL-2+ with Classpath-exception-2.0"
+        );
+    }
+
+    #[test]
+    fn test_suggest_from_cargo_metadata() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nlicense = \"MIT OR Apache-2.0\"\nauthors = [\"Jane Doe <jane@example.com>\"]\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(td.path().join("vendor/bar")).unwrap();
+        std::fs::write(
+            td.path().join("vendor/bar/Cargo.toml"),
+            "[package]\nname = \"bar\"\nlicense = \"BSD-3-Clause\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            suggest_from_ecosystem_metadata(td.path()),
+            vec![
+                SuggestedFiles {
+                    patterns: vec!["*".to_string()],
+                    copyright: vec!["Jane Doe <jane@example.com>".to_string()],
+                    license: Some("Expat or Apache-2.0".to_string()),
+                },
+                SuggestedFiles {
+                    patterns: vec!["vendor/bar/*".to_string()],
+                    copyright: vec![],
+                    license: Some("BSD-3-clause".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggest_from_python_and_node_metadata() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(
+            td.path().join("pyproject.toml"),
+            "[project]\nname = \"foo\"\nlicense = {text = \"GPL-3.0-or-later\"}\nauthors = [{name = \"Jane Doe\", email = \"jane@example.com\"}]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            suggest_from_ecosystem_metadata(td.path()),
+            vec![SuggestedFiles {
+                patterns: vec!["*".to_string()],
+                copyright: vec!["Jane Doe <jane@example.com>".to_string()],
+                license: Some("GPL-3+".to_string()),
+            }]
+        );
+
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(
+            td.path().join("package.json"),
+            r#"{"name": "foo", "license": "ISC", "author": "Jane Doe <jane@example.com> (https://example.com)"}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(td.path().join("node_modules/@scope/bar")).unwrap();
+        std::fs::write(
+            td.path().join("node_modules/@scope/bar/package.json"),
+            r#"{"name": "@scope/bar", "license": "MIT", "author": {"name": "Joe"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            suggest_from_ecosystem_metadata(td.path()),
+            vec![
+                SuggestedFiles {
+                    patterns: vec!["*".to_string()],
+                    copyright: vec!["Jane Doe <jane@example.com>".to_string()],
+                    license: Some("ISC".to_string()),
+                },
+                SuggestedFiles {
+                    patterns: vec!["node_modules/@scope/bar/*".to_string()],
+                    copyright: vec!["Joe".to_string()],
+                    license: Some("Expat".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let copyright: Copyright = format!(
+            "{}\nFiles: *\nCopyright: 2020 Jane Doe\nLicense: GPL-2+\n\nFiles: vendor/*\nCopyright: 2019 John Doe\nLicense: Expat or Apache-2.0\n\nFiles: doc/*\nCopyright: 2019 John Doe\nLicense: CC0-1.0\n Some text\n\nLicense: Expat\n Permission is hereby granted...\n",
+            HEADER
+        )
+        .parse()
+        .unwrap();
+        let files: Vec<PathBuf> = ["README", "src/a.c", "vendor/b.c", "doc/c.md"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let report = coverage_report(&copyright, &files);
+        assert_eq!(report.total_files, 4);
+        assert_eq!(report.explicit, 2);
+        assert_eq!(report.fallback, 2);
+        assert_eq!(report.uncovered, 0);
+        assert_eq!(report.explicit_percentage(), 50.0);
+        assert_eq!(
+            report.licenses.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("CC0-1.0".to_string(), 1),
+                ("Expat or Apache-2.0".to_string(), 1),
+                ("GPL-2+".to_string(), 2),
+            ]
+        );
+        assert_eq!(
+            report.missing_license_paragraphs,
+            vec!["GPL-2+", "Apache-2.0"]
+        );
+    }
+}
@@ -0,0 +1,742 @@
+//! Client for the crates.io API, used to find new upstream versions of crates and to
+//! work out which dependencies a crate needs packaged.
+use debian_analyzer::cache::Cache;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Base URL of the crates.io API.
+pub const DEFAULT_URL: &str = "https://crates.io/api/v1/";
+
+/// A released version of a crate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateVersion {
+    /// The version number
+    pub num: semver::Version,
+    /// Whether the version has been yanked
+    pub yanked: bool,
+    /// SPDX license expression, if any
+    pub license: Option<String>,
+    /// Features defined by this version, mapping feature names to what they enable
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+    /// Minimum supported Rust version, if any
+    pub rust_version: Option<String>,
+}
+
+/// Metadata about a crate and its versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateInfo {
+    /// Name of the crate
+    pub name: String,
+    /// Short description of the crate
+    pub description: Option<String>,
+    /// Homepage of the crate
+    pub homepage: Option<String>,
+    /// Repository URL of the crate
+    pub repository: Option<String>,
+    /// Versions of the crate, most recent first
+    pub versions: Vec<CrateVersion>,
+}
+
+#[derive(Deserialize)]
+struct CrateData {
+    name: String,
+    description: Option<String>,
+    homepage: Option<String>,
+    repository: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateData,
+    versions: Vec<CrateVersion>,
+}
+
+impl From<CrateResponse> for CrateInfo {
+    fn from(response: CrateResponse) -> Self {
+        CrateInfo {
+            name: response.krate.name,
+            description: response.krate.description,
+            homepage: response.krate.homepage,
+            repository: response.krate.repository,
+            versions: response.versions,
+        }
+    }
+}
+
+impl CrateInfo {
+    /// Look up a specific version.
+    ///
+    /// # Arguments
+    /// * `version` - The version to look up
+    pub fn version(&self, version: &semver::Version) -> Option<&CrateVersion> {
+        self.versions.iter().find(|v| &v.num == version)
+    }
+
+    /// The most recent version that has not been yanked.
+    ///
+    /// # Arguments
+    /// * `include_prerelease` - Whether to consider pre-release versions
+    pub fn latest_version(&self, include_prerelease: bool) -> Option<&CrateVersion> {
+        self.versions
+            .iter()
+            .filter(|v| !v.yanked && (include_prerelease || v.num.pre.is_empty()))
+            .max_by(|a, b| a.num.cmp(&b.num))
+    }
+
+    /// A newer version than the one currently packaged, if there is one.
+    ///
+    /// Pre-release versions are only considered if the current version is a
+    /// pre-release itself.
+    ///
+    /// # Arguments
+    /// * `current` - The version that is currently packaged
+    pub fn new_upstream_version(&self, current: &semver::Version) -> Option<&CrateVersion> {
+        self.latest_version(!current.pre.is_empty())
+            .filter(|v| &v.num > current)
+    }
+}
+
+/// Kind of a dependency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    /// Regular dependency
+    Normal,
+    /// Build script dependency
+    Build,
+    /// Dependency only needed for tests, examples and benchmarks
+    Dev,
+}
+
+/// A dependency of a crate version, as declared in its Cargo.toml.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    /// Name of the crate depended on
+    pub crate_id: String,
+    /// Version requirement
+    pub req: semver::VersionReq,
+    /// Kind of the dependency
+    pub kind: DependencyKind,
+    /// Whether the dependency is optional
+    pub optional: bool,
+    /// Whether the default features of the dependency are enabled
+    pub default_features: bool,
+    /// Features of the dependency that are enabled
+    pub features: Vec<String>,
+    /// Target the dependency is restricted to, if any
+    pub target: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DependenciesResponse {
+    dependencies: Vec<Dependency>,
+}
+
+/// A dependency that is needed for a particular set of features.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedDependency {
+    /// Name of the crate depended on
+    pub name: String,
+    /// Version requirement
+    pub req: semver::VersionReq,
+    /// Kind of the dependency
+    pub kind: DependencyKind,
+    /// Whether the default features of the dependency are needed
+    pub default_features: bool,
+    /// Features of the dependency that are needed
+    pub features: BTreeSet<String>,
+}
+
+/// Work out which features of a crate are enabled, and which of its dependencies
+/// are needed with which features.
+///
+/// Development dependencies are not included.
+///
+/// # Arguments
+/// * `version` - The crate version
+/// * `dependencies` - Dependencies of the crate version
+/// * `features` - Features that are requested
+/// * `default_features` - Whether the default features are requested
+///
+/// # Returns
+/// The enabled features and the needed dependencies
+pub fn resolve_features(
+    version: &CrateVersion,
+    dependencies: &[Dependency],
+    features: &[&str],
+    default_features: bool,
+) -> (BTreeSet<String>, Vec<ResolvedDependency>) {
+    let optional = dependencies
+        .iter()
+        .filter(|d| d.optional)
+        .map(|d| d.crate_id.as_str())
+        .collect::<HashSet<_>>();
+    let mut todo = features.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+    if default_features && version.features.contains_key("default") {
+        todo.push("default".to_string());
+    }
+    let mut enabled = BTreeSet::new();
+    let mut activated = HashSet::new();
+    let mut dep_features: HashMap<String, BTreeSet<String>> = HashMap::new();
+    // Features enabled on a dependency with "dep?/feature", which only apply if
+    // the dependency is activated by something else.
+    let mut weak_features: HashMap<String, BTreeSet<String>> = HashMap::new();
+    while let Some(feature) = todo.pop() {
+        if !enabled.insert(feature.clone()) {
+            continue;
+        }
+        let Some(entries) = version.features.get(&feature) else {
+            // Optional dependencies implicitly define a feature with their name.
+            if optional.contains(feature.as_str()) {
+                activated.insert(feature);
+            }
+            continue;
+        };
+        for entry in entries {
+            if let Some(dep) = entry.strip_prefix("dep:") {
+                activated.insert(dep.to_string());
+            } else if let Some((dep, dep_feature)) = entry.split_once('/') {
+                if let Some(dep) = dep.strip_suffix('?') {
+                    weak_features
+                        .entry(dep.to_string())
+                        .or_default()
+                        .insert(dep_feature.to_string());
+                } else {
+                    if optional.contains(dep) {
+                        activated.insert(dep.to_string());
+                    }
+                    dep_features
+                        .entry(dep.to_string())
+                        .or_default()
+                        .insert(dep_feature.to_string());
+                }
+            } else {
+                todo.push(entry.clone());
+            }
+        }
+    }
+    // Only features that the crate actually defines are reported.
+    enabled.retain(|f| version.features.contains_key(f) || optional.contains(f.as_str()));
+
+    let resolved = dependencies
+        .iter()
+        .filter(|d| d.kind != DependencyKind::Dev)
+        .filter(|d| !d.optional || activated.contains(&d.crate_id))
+        .map(|d| {
+            let mut features = d.features.iter().cloned().collect::<BTreeSet<_>>();
+            for extra in [&dep_features, &weak_features] {
+                if let Some(extra) = extra.get(&d.crate_id) {
+                    features.extend(extra.iter().cloned());
+                }
+            }
+            ResolvedDependency {
+                name: d.crate_id.clone(),
+                req: d.req.clone(),
+                kind: d.kind,
+                default_features: d.default_features,
+                features,
+            }
+        })
+        .collect();
+    (enabled, resolved)
+}
+
+/// Name of the Debian package that debcargo generates for a crate.
+///
+/// # Arguments
+/// * `crate_name` - Name of the crate
+pub fn debian_package_name(crate_name: &str) -> String {
+    crate::debcargo::debcargo_binary_name(crate_name, "")
+}
+
+/// Find the dependencies that are not satisfied by the available crate versions.
+///
+/// # Arguments
+/// * `dependencies` - The dependencies to check
+/// * `available` - Looks up the available version of a crate, e.g. from the
+///   version of its package in the archive
+pub fn dependency_gaps(
+    dependencies: &[ResolvedDependency],
+    available: impl Fn(&str) -> Option<semver::Version>,
+) -> Vec<&ResolvedDependency> {
+    dependencies
+        .iter()
+        .filter(|d| !available(&d.name).is_some_and(|v| d.req.matches(&v)))
+        .collect()
+}
+
+/// Error fetching information from crates.io.
+#[derive(Debug)]
+pub enum Error {
+    /// Error talking to crates.io.
+    Http(reqwest::Error),
+    /// The crate or version does not exist.
+    NotFound,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::NotFound => write!(f, "Crate not found"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+fn store<T: Serialize>(cache: Option<&Cache>, key: &str, value: &T) {
+    if let Some(cache) = cache {
+        if let Err(e) = cache.put(key, value) {
+            log::warn!("Unable to cache {}: {}", key, e);
+        }
+    }
+}
+
+fn default_cache() -> Option<Cache> {
+    debian_analyzer::cache::default_cache_dir()
+        .map(|dir| Cache::new(&dir.join("crates-io"), debian_analyzer::cache::DEFAULT_TTL))
+}
+
+/// Client for the crates.io API.
+pub struct Client {
+    base_url: url::Url,
+    client: reqwest::blocking::Client,
+    cache: Option<Cache>,
+}
+
+impl Client {
+    /// Create a client for crates.io, caching responses in the default cache directory.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(DEFAULT_URL.parse().unwrap(), default_cache())
+    }
+
+    /// Create a client for the crates.io compatible API at the given URL.
+    ///
+    /// # Arguments
+    /// * `base_url` - Base URL of the API
+    /// * `cache` - Cache for responses, if any
+    pub fn with_url(base_url: url::Url, cache: Option<Cache>) -> Result<Self, Error> {
+        // crates.io rejects requests without a user agent.
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self {
+            base_url,
+            client,
+            cache,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let url = self.base_url.join(path).unwrap();
+        let response = self.client.get(url).send()?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::NotFound);
+        }
+        Ok(response.error_for_status()?.json()?)
+    }
+
+    fn cached<T: Serialize + serde::de::DeserializeOwned>(
+        &self,
+        key: &str,
+        fetch: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if let Some(value) = self.cache.as_ref().and_then(|c| c.get(key)) {
+            return Ok(value);
+        }
+        let value = fetch()?;
+        store(self.cache.as_ref(), key, &value);
+        Ok(value)
+    }
+
+    /// Fetch metadata about a crate and all of its versions.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    pub fn crate_info(&self, name: &str) -> Result<CrateInfo, Error> {
+        self.cached(&format!("crates/{}", name), || {
+            Ok(self
+                .get::<CrateResponse>(&format!("crates/{}", name))?
+                .into())
+        })
+    }
+
+    /// Fetch the dependencies of a crate version.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    pub fn dependencies(
+        &self,
+        name: &str,
+        version: &semver::Version,
+    ) -> Result<Vec<Dependency>, Error> {
+        let path = format!("crates/{}/{}/dependencies", name, version);
+        self.cached(&path, || {
+            Ok(self.get::<DependenciesResponse>(&path)?.dependencies)
+        })
+    }
+
+    /// The most recent version of a crate that has not been yanked.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `include_prerelease` - Whether to consider pre-release versions
+    pub fn latest_version(
+        &self,
+        name: &str,
+        include_prerelease: bool,
+    ) -> Result<Option<semver::Version>, Error> {
+        Ok(self
+            .crate_info(name)?
+            .latest_version(include_prerelease)
+            .map(|v| v.num.clone()))
+    }
+
+    /// Check whether a newer version of a crate than the packaged one is available.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `current` - The version that is currently packaged
+    pub fn new_upstream_version(
+        &self,
+        name: &str,
+        current: &semver::Version,
+    ) -> Result<Option<semver::Version>, Error> {
+        Ok(self
+            .crate_info(name)?
+            .new_upstream_version(current)
+            .map(|v| v.num.clone()))
+    }
+
+    /// Check whether a crate version has been yanked.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    pub fn is_yanked(&self, name: &str, version: &semver::Version) -> Result<bool, Error> {
+        self.crate_info(name)?
+            .version(version)
+            .map(|v| v.yanked)
+            .ok_or(Error::NotFound)
+    }
+
+    /// The license of a crate version, as an SPDX expression.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    pub fn license(&self, name: &str, version: &semver::Version) -> Result<Option<String>, Error> {
+        self.crate_info(name)?
+            .version(version)
+            .map(|v| v.license.clone())
+            .ok_or(Error::NotFound)
+    }
+
+    /// The dependencies of a crate version that are needed for a set of features.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    /// * `features` - Features that are requested
+    /// * `default_features` - Whether the default features are requested
+    pub fn resolved_dependencies(
+        &self,
+        name: &str,
+        version: &semver::Version,
+        features: &[&str],
+        default_features: bool,
+    ) -> Result<Vec<ResolvedDependency>, Error> {
+        let info = self.crate_info(name)?;
+        let crate_version = info.version(version).ok_or(Error::NotFound)?;
+        let dependencies = self.dependencies(name, version)?;
+        Ok(resolve_features(crate_version, &dependencies, features, default_features).1)
+    }
+}
+
+/// Asynchronous client for the crates.io API.
+#[cfg(feature = "async")]
+pub struct AsyncClient {
+    base_url: url::Url,
+    client: reqwest::Client,
+    cache: Option<Cache>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncClient {
+    /// Create a client for crates.io, caching responses in the default cache directory.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(DEFAULT_URL.parse().unwrap(), default_cache())
+    }
+
+    /// Create a client for the crates.io compatible API at the given URL.
+    ///
+    /// # Arguments
+    /// * `base_url` - Base URL of the API
+    /// * `cache` - Cache for responses, if any
+    pub fn with_url(base_url: url::Url, cache: Option<Cache>) -> Result<Self, Error> {
+        // crates.io rejects requests without a user agent.
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self {
+            base_url,
+            client,
+            cache,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let url = self.base_url.join(path).unwrap();
+        let response = self.client.get(url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::NotFound);
+        }
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    /// Fetch metadata about a crate and all of its versions.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    pub async fn crate_info(&self, name: &str) -> Result<CrateInfo, Error> {
+        let key = format!("crates/{}", name);
+        if let Some(value) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            return Ok(value);
+        }
+        let value: CrateInfo = self.get::<CrateResponse>(&key).await?.into();
+        store(self.cache.as_ref(), &key, &value);
+        Ok(value)
+    }
+
+    /// Fetch the dependencies of a crate version.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    pub async fn dependencies(
+        &self,
+        name: &str,
+        version: &semver::Version,
+    ) -> Result<Vec<Dependency>, Error> {
+        let path = format!("crates/{}/{}/dependencies", name, version);
+        if let Some(value) = self.cache.as_ref().and_then(|c| c.get(&path)) {
+            return Ok(value);
+        }
+        let value = self.get::<DependenciesResponse>(&path).await?.dependencies;
+        store(self.cache.as_ref(), &path, &value);
+        Ok(value)
+    }
+
+    /// The most recent version of a crate that has not been yanked.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `include_prerelease` - Whether to consider pre-release versions
+    pub async fn latest_version(
+        &self,
+        name: &str,
+        include_prerelease: bool,
+    ) -> Result<Option<semver::Version>, Error> {
+        Ok(self
+            .crate_info(name)
+            .await?
+            .latest_version(include_prerelease)
+            .map(|v| v.num.clone()))
+    }
+
+    /// Check whether a newer version of a crate than the packaged one is available.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `current` - The version that is currently packaged
+    pub async fn new_upstream_version(
+        &self,
+        name: &str,
+        current: &semver::Version,
+    ) -> Result<Option<semver::Version>, Error> {
+        Ok(self
+            .crate_info(name)
+            .await?
+            .new_upstream_version(current)
+            .map(|v| v.num.clone()))
+    }
+
+    /// Check whether a crate version has been yanked.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    pub async fn is_yanked(&self, name: &str, version: &semver::Version) -> Result<bool, Error> {
+        self.crate_info(name)
+            .await?
+            .version(version)
+            .map(|v| v.yanked)
+            .ok_or(Error::NotFound)
+    }
+
+    /// The license of a crate version, as an SPDX expression.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    pub async fn license(
+        &self,
+        name: &str,
+        version: &semver::Version,
+    ) -> Result<Option<String>, Error> {
+        self.crate_info(name)
+            .await?
+            .version(version)
+            .map(|v| v.license.clone())
+            .ok_or(Error::NotFound)
+    }
+
+    /// The dependencies of a crate version that are needed for a set of features.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the crate
+    /// * `version` - Version of the crate
+    /// * `features` - Features that are requested
+    /// * `default_features` - Whether the default features are requested
+    pub async fn resolved_dependencies(
+        &self,
+        name: &str,
+        version: &semver::Version,
+        features: &[&str],
+        default_features: bool,
+    ) -> Result<Vec<ResolvedDependency>, Error> {
+        let info = self.crate_info(name).await?;
+        let crate_version = info.version(version).ok_or(Error::NotFound)?;
+        let dependencies = self.dependencies(name, version).await?;
+        Ok(resolve_features(crate_version, &dependencies, features, default_features).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crate_info() -> CrateInfo {
+        serde_json::from_str::<CrateResponse>(
+            r#"{
+                "crate": {"name": "foo", "description": "A crate", "homepage": null,
+                          "repository": "https://github.com/example/foo", "max_version": "2.0.0-rc.1"},
+                "versions": [
+                    {"num": "2.0.0-rc.1", "yanked": false, "license": "MIT", "features": {}},
+                    {"num": "1.2.0", "yanked": true, "license": "MIT OR Apache-2.0", "features": {}},
+                    {"num": "1.1.0", "yanked": false, "license": "MIT OR Apache-2.0",
+                     "features": {"default": ["std"], "std": []}, "rust_version": "1.70"}
+                ]
+            }"#,
+        )
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn test_versions() {
+        let info = crate_info();
+        assert_eq!(
+            Some("https://github.com/example/foo"),
+            info.repository.as_deref()
+        );
+        let v = |s: &str| semver::Version::parse(s).unwrap();
+        assert_eq!(v("1.1.0"), info.latest_version(false).unwrap().num);
+        assert_eq!(v("2.0.0-rc.1"), info.latest_version(true).unwrap().num);
+        assert_eq!(
+            Some(v("1.1.0")),
+            info.new_upstream_version(&v("1.0.0"))
+                .map(|v| v.num.clone())
+        );
+        assert_eq!(None, info.new_upstream_version(&v("1.1.0")));
+        assert!(info.version(&v("1.2.0")).unwrap().yanked);
+        assert_eq!(
+            Some("1.70"),
+            info.version(&v("1.1.0")).unwrap().rust_version.as_deref()
+        );
+    }
+
+    fn dep(name: &str, req: &str, kind: DependencyKind, optional: bool) -> Dependency {
+        Dependency {
+            crate_id: name.to_string(),
+            req: req.parse().unwrap(),
+            kind,
+            optional,
+            default_features: true,
+            features: vec![],
+            target: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_features() {
+        let version = CrateVersion {
+            num: semver::Version::new(1, 0, 0),
+            yanked: false,
+            license: None,
+            features: maplit::btreemap! {
+                "default".to_string() => vec!["std".to_string()],
+                "std".to_string() => vec!["serde?/std".to_string()],
+                "json".to_string() => vec!["dep:serde_json".to_string(), "serde/derive".to_string()],
+            },
+            rust_version: None,
+        };
+        let deps = vec![
+            dep("log", "^0.4", DependencyKind::Normal, false),
+            dep("serde", "^1", DependencyKind::Normal, true),
+            dep("serde_json", "^1", DependencyKind::Normal, true),
+            dep("regex", "^1", DependencyKind::Normal, true),
+            dep("cc", "^1", DependencyKind::Build, false),
+            dep("tempfile", "^3", DependencyKind::Dev, false),
+        ];
+
+        let (enabled, resolved) = resolve_features(&version, &deps, &[], true);
+        assert_eq!(vec!["default", "std"], enabled.iter().collect::<Vec<_>>());
+        assert_eq!(
+            vec!["log", "cc"],
+            resolved.iter().map(|d| d.name.as_str()).collect::<Vec<_>>()
+        );
+
+        let (enabled, resolved) = resolve_features(&version, &deps, &["json", "regex"], true);
+        assert_eq!(
+            vec!["default", "json", "regex", "std"],
+            enabled.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["log", "serde", "serde_json", "regex", "cc"],
+            resolved.iter().map(|d| d.name.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["derive", "std"],
+            resolved[1].features.iter().collect::<Vec<_>>()
+        );
+
+        let gaps = dependency_gaps(&resolved, |name| match name {
+            "log" => Some(semver::Version::new(0, 4, 20)),
+            "serde" => Some(semver::Version::new(0, 9, 0)),
+            "serde_json" | "regex" | "cc" => Some(semver::Version::new(1, 0, 0)),
+            _ => None,
+        });
+        assert_eq!(
+            vec!["serde"],
+            gaps.iter().map(|d| d.name.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_debian_package_name() {
+        assert_eq!("librust-serde-json-dev", debian_package_name("serde_json"));
+    }
+}
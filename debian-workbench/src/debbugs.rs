@@ -0,0 +1,562 @@
+//! Client for the SOAP interface of the Debian bug tracking system.
+use std::collections::HashMap;
+
+/// URL of the SOAP interface of bugs.debian.org.
+pub const DEFAULT_URL: &str = "https://bugs.debian.org/cgi-bin/soap.cgi";
+
+/// Bug number
+pub type BugId = u32;
+
+/// Status of a bug.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Bug {
+    /// Bug number
+    pub id: BugId,
+    /// Binary or source packages the bug is filed against
+    pub packages: Vec<String>,
+    /// Source packages the bug is filed against
+    pub sources: Vec<String>,
+    /// Subject of the bug
+    pub subject: String,
+    /// Severity, e.g. "serious"
+    pub severity: String,
+    /// Tags, e.g. "patch"
+    pub tags: Vec<String>,
+    /// Versions the bug was found in
+    pub found_versions: Vec<String>,
+    /// Versions the bug was fixed in
+    pub fixed_versions: Vec<String>,
+    /// Who closed the bug, if it is closed
+    pub done: Option<String>,
+    /// Whether the bug has been archived
+    pub archived: bool,
+    /// Where the bug was forwarded to, if anywhere
+    pub forwarded: Option<String>,
+}
+
+impl Bug {
+    /// Whether the bug is filed against a package.
+    ///
+    /// # Arguments
+    /// * `source` - Name of the source package
+    /// * `binaries` - Names of the binary packages built from the source package
+    pub fn belongs_to(&self, source: &str, binaries: &[&str]) -> bool {
+        self.sources.iter().any(|s| s == source)
+            || self.packages.iter().any(|p| {
+                p == source || p == &format!("src:{}", source) || binaries.contains(&p.as_str())
+            })
+    }
+}
+
+/// Error talking to the bug tracker.
+#[derive(Debug)]
+pub enum Error {
+    /// Error talking to the bug tracker.
+    Http(reqwest::Error),
+    /// The response could not be parsed.
+    InvalidResponse(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::InvalidResponse(e) => write!(f, "Invalid response: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+impl From<xmltree::ParseError> for Error {
+    fn from(e: xmltree::ParseError) -> Self {
+        Error::InvalidResponse(e.to_string())
+    }
+}
+
+/// Find the bugs closed by a changelog entry.
+///
+/// This uses the regular expression from Debian policy, section 4.4.
+///
+/// # Arguments
+/// * `text` - Text of the changelog entry
+pub fn closed_bugs(text: &str) -> Vec<BugId> {
+    let mut ret = vec![];
+    for m in lazy_regex::regex!(r"(?i)closes:\s*(?:bug)?\#?\s?\d+(?:,\s*(?:bug)?\#?\s?\d+)*")
+        .find_iter(text)
+    {
+        for n in lazy_regex::regex!(r"\d+").find_iter(m.as_str()) {
+            if let Ok(id) = n.as_str().parse() {
+                if !ret.contains(&id) {
+                    ret.push(id);
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// A problem with a bug closed in the changelog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClosesIssue {
+    /// The bug does not exist
+    UnknownBug(BugId),
+    /// The bug is filed against other packages
+    WrongPackage {
+        /// Bug number
+        bug: BugId,
+        /// Packages the bug is filed against
+        packages: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for ClosesIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClosesIssue::UnknownBug(bug) => write!(f, "Bug #{} does not exist", bug),
+            ClosesIssue::WrongPackage { bug, packages } => {
+                write!(f, "Bug #{} is filed against {}", bug, packages.join(", "))
+            }
+        }
+    }
+}
+
+/// Check that closed bugs exist and belong to a package.
+///
+/// # Arguments
+/// * `source` - Name of the source package
+/// * `binaries` - Names of the binary packages built from the source package
+/// * `closes` - Bugs that are closed
+/// * `bugs` - Status of the closed bugs, as retrieved from the bug tracker
+pub fn check_closes(
+    source: &str,
+    binaries: &[&str],
+    closes: &[BugId],
+    bugs: &[Bug],
+) -> Vec<ClosesIssue> {
+    closes
+        .iter()
+        .filter_map(|id| match bugs.iter().find(|b| b.id == *id) {
+            None => Some(ClosesIssue::UnknownBug(*id)),
+            Some(bug) if !bug.belongs_to(source, binaries) => Some(ClosesIssue::WrongPackage {
+                bug: *id,
+                packages: bug.packages.clone(),
+            }),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn envelope(body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/" xmlns:soapenc="http://schemas.xmlsoap.org/soap/encoding/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" soap:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><soap:Body>{}</soap:Body></soap:Envelope>"#,
+        body
+    )
+}
+
+fn children<'a>(
+    element: &'a xmltree::Element,
+    name: &'a str,
+) -> impl Iterator<Item = &'a xmltree::Element> + 'a {
+    element
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .filter(move |e| e.name == name)
+}
+
+fn child_text(element: &xmltree::Element, name: &str) -> Option<String> {
+    element
+        .get_child(name)
+        .and_then(|e| e.get_text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn child_list(element: &xmltree::Element, name: &str) -> Vec<String> {
+    element
+        .get_child(name)
+        .map(|e| {
+            children(e, "item")
+                .filter_map(|i| i.get_text().map(|t| t.trim().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn split_list(value: Option<String>, separators: &[char]) -> Vec<String> {
+    value
+        .map(|v| {
+            v.split(separators)
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Find the element holding the result of a SOAP call.
+fn response_body(response: &xmltree::Element, method: &str) -> Result<xmltree::Element, Error> {
+    let body = response
+        .get_child("Body")
+        .ok_or_else(|| Error::InvalidResponse("missing SOAP body".to_string()))?;
+    if let Some(fault) = body.get_child("Fault") {
+        return Err(Error::InvalidResponse(
+            child_text(fault, "faultstring").unwrap_or_else(|| "SOAP fault".to_string()),
+        ));
+    }
+    body.get_child(format!("{}Response", method).as_str())
+        .and_then(|r| r.children.iter().find_map(|c| c.as_element()))
+        .cloned()
+        .ok_or_else(|| Error::InvalidResponse(format!("missing {} response", method)))
+}
+
+/// Parse the result of a get_status call.
+fn parse_status(result: &xmltree::Element) -> Vec<Bug> {
+    children(result, "item")
+        .filter_map(|item| {
+            let value = item.get_child("value")?;
+            let id = child_text(item, "key")?.parse().ok()?;
+            Some(Bug {
+                id,
+                packages: split_list(child_text(value, "package"), &[',']),
+                sources: split_list(child_text(value, "source"), &[',']),
+                subject: child_text(value, "subject").unwrap_or_default(),
+                severity: child_text(value, "severity").unwrap_or_default(),
+                tags: split_list(child_text(value, "tags"), &[' ', ',']),
+                found_versions: child_list(value, "found_versions"),
+                fixed_versions: child_list(value, "fixed_versions"),
+                done: child_text(value, "done"),
+                archived: child_text(value, "archived").is_some_and(|a| a != "0"),
+                forwarded: child_text(value, "forwarded"),
+            })
+        })
+        .collect()
+}
+
+/// Parse the result of a get_usertag call.
+fn parse_usertags(result: &xmltree::Element) -> HashMap<String, Vec<BugId>> {
+    result
+        .children
+        .iter()
+        .filter_map(|c| c.as_element())
+        .map(|tag| {
+            let bugs = children(tag, "item")
+                .filter_map(|i| i.get_text().and_then(|t| t.trim().parse().ok()))
+                .collect();
+            (tag.name.clone(), bugs)
+        })
+        .collect()
+}
+
+fn soap_action(method: &str) -> String {
+    format!("\"Debbugs/SOAP#{}\"", method)
+}
+
+fn get_status_body(bugs: &[BugId]) -> String {
+    let items = bugs
+        .iter()
+        .map(|b| format!("<item xsi:type=\"xsd:int\">{}</item>", b))
+        .collect::<String>();
+    format!(
+        "<get_status xmlns=\"urn:Debbugs/SOAP\"><bugs soapenc:arrayType=\"xsd:int[{}]\" xsi:type=\"soapenc:Array\">{}</bugs></get_status>",
+        bugs.len(),
+        items
+    )
+}
+
+fn get_usertag_body(email: &str, tags: &[&str]) -> String {
+    let tags = tags
+        .iter()
+        .map(|t| format!("<tag xsi:type=\"xsd:string\">{}</tag>", escape(t)))
+        .collect::<String>();
+    format!(
+        "<get_usertag xmlns=\"urn:Debbugs/SOAP\"><email xsi:type=\"xsd:string\">{}</email>{}</get_usertag>",
+        escape(email),
+        tags
+    )
+}
+
+/// Client for the Debian bug tracking system.
+pub struct Client {
+    url: url::Url,
+    client: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// Create a client for bugs.debian.org.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(DEFAULT_URL.parse().unwrap())
+    }
+
+    /// Create a client for the debbugs instance with the SOAP interface at the given URL.
+    pub fn with_url(url: url::Url) -> Result<Self, Error> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self { url, client })
+    }
+
+    #[tracing::instrument(skip(self, body))]
+    fn call(&self, method: &str, body: &str) -> Result<xmltree::Element, Error> {
+        let response = self
+            .client
+            .post(self.url.clone())
+            .header("Content-Type", "text/xml; charset=utf-8")
+            .header("SOAPAction", soap_action(method))
+            .body(envelope(body))
+            .send()?
+            .error_for_status()?
+            .bytes()?;
+        response_body(&xmltree::Element::parse(response.as_ref())?, method)
+    }
+
+    /// Fetch the status of bugs.
+    ///
+    /// Bugs that do not exist are omitted from the result.
+    ///
+    /// # Arguments
+    /// * `bugs` - Bug numbers
+    pub fn get_status(&self, bugs: &[BugId]) -> Result<Vec<Bug>, Error> {
+        if bugs.is_empty() {
+            return Ok(vec![]);
+        }
+        let result = self.call("get_status", &get_status_body(bugs))?;
+        Ok(parse_status(&result))
+    }
+
+    /// Fetch the bugs that a user has tagged.
+    ///
+    /// # Arguments
+    /// * `email` - Email address of the user, e.g. "debian-qa@lists.debian.org"
+    /// * `tags` - Tags to fetch; all tags if empty
+    ///
+    /// # Returns
+    /// The bugs, by tag
+    pub fn get_usertag(
+        &self,
+        email: &str,
+        tags: &[&str],
+    ) -> Result<HashMap<String, Vec<BugId>>, Error> {
+        let result = self.call("get_usertag", &get_usertag_body(email, tags))?;
+        Ok(parse_usertags(&result))
+    }
+
+    /// Check that bugs closed in a changelog entry exist and belong to a package.
+    ///
+    /// # Arguments
+    /// * `source` - Name of the source package
+    /// * `binaries` - Names of the binary packages built from the source package
+    /// * `text` - Text of the changelog entry
+    pub fn check_changelog_closes(
+        &self,
+        source: &str,
+        binaries: &[&str],
+        text: &str,
+    ) -> Result<Vec<ClosesIssue>, Error> {
+        let closes = closed_bugs(text);
+        let bugs = self.get_status(&closes)?;
+        Ok(check_closes(source, binaries, &closes, &bugs))
+    }
+}
+
+/// Asynchronous client for the Debian bug tracking system.
+#[cfg(feature = "async")]
+pub struct AsyncClient {
+    url: url::Url,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "async")]
+impl AsyncClient {
+    /// Create a client for bugs.debian.org.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(DEFAULT_URL.parse().unwrap())
+    }
+
+    /// Create a client for the debbugs instance with the SOAP interface at the given URL.
+    pub fn with_url(url: url::Url) -> Result<Self, Error> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self { url, client })
+    }
+
+    #[tracing::instrument(skip(self, body))]
+    async fn call(&self, method: &str, body: &str) -> Result<xmltree::Element, Error> {
+        let response = self
+            .client
+            .post(self.url.clone())
+            .header("Content-Type", "text/xml; charset=utf-8")
+            .header("SOAPAction", soap_action(method))
+            .body(envelope(body))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        response_body(&xmltree::Element::parse(response.as_ref())?, method)
+    }
+
+    /// Fetch the status of bugs.
+    ///
+    /// Bugs that do not exist are omitted from the result.
+    ///
+    /// # Arguments
+    /// * `bugs` - Bug numbers
+    pub async fn get_status(&self, bugs: &[BugId]) -> Result<Vec<Bug>, Error> {
+        if bugs.is_empty() {
+            return Ok(vec![]);
+        }
+        let result = self.call("get_status", &get_status_body(bugs)).await?;
+        Ok(parse_status(&result))
+    }
+
+    /// Fetch the bugs that a user has tagged.
+    ///
+    /// # Arguments
+    /// * `email` - Email address of the user, e.g. "debian-qa@lists.debian.org"
+    /// * `tags` - Tags to fetch; all tags if empty
+    ///
+    /// # Returns
+    /// The bugs, by tag
+    pub async fn get_usertag(
+        &self,
+        email: &str,
+        tags: &[&str],
+    ) -> Result<HashMap<String, Vec<BugId>>, Error> {
+        let result = self
+            .call("get_usertag", &get_usertag_body(email, tags))
+            .await?;
+        Ok(parse_usertags(&result))
+    }
+
+    /// Check that bugs closed in a changelog entry exist and belong to a package.
+    ///
+    /// # Arguments
+    /// * `source` - Name of the source package
+    /// * `binaries` - Names of the binary packages built from the source package
+    /// * `text` - Text of the changelog entry
+    pub async fn check_changelog_closes(
+        &self,
+        source: &str,
+        binaries: &[&str],
+        text: &str,
+    ) -> Result<Vec<ClosesIssue>, Error> {
+        let closes = closed_bugs(text);
+        let bugs = self.get_status(&closes).await?;
+        Ok(check_closes(source, binaries, &closes, &bugs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closed_bugs() {
+        assert_eq!(
+            vec![123456, 234567, 345678],
+            closed_bugs(
+                "  * Fix crash. (Closes: #123456, #234567)\n  * Typo. closes: bug345678\n  * Again (Closes: #123456)\n"
+            )
+        );
+        assert_eq!(Vec::<BugId>::new(), closed_bugs("  * See #123456.\n"));
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let response = xmltree::Element::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/" xmlns:soapenc="http://schemas.xmlsoap.org/soap/encoding/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:apachens="http://xml.apache.org/xml-soap">
+<soap:Body><get_statusResponse xmlns="urn:Debbugs/SOAP"><s-gensym3 xsi:type="apachens:Map">
+<item><key xsi:type="xsd:int">123456</key><value>
+<package xsi:type="xsd:string">python3-foo</package>
+<source xsi:type="xsd:string">foo</source>
+<subject xsi:type="xsd:string">foo: crashes on startup</subject>
+<severity xsi:type="xsd:string">serious</severity>
+<tags xsi:type="xsd:string">patch upstream</tags>
+<found_versions soapenc:arrayType="xsd:string[1]" xsi:type="soapenc:Array"><item xsi:type="xsd:string">foo/1.0-1</item></found_versions>
+<fixed_versions soapenc:arrayType="xsd:anyType[0]" xsi:type="soapenc:Array"/>
+<done xsi:type="xsd:string"></done>
+<archived xsi:type="xsd:int">0</archived>
+<forwarded xsi:type="xsd:string">https://github.com/foo/foo/issues/1</forwarded>
+</value></item>
+</s-gensym3></get_statusResponse></soap:Body></soap:Envelope>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let bugs = parse_status(&response_body(&response, "get_status").unwrap());
+        assert_eq!(
+            vec![Bug {
+                id: 123456,
+                packages: vec!["python3-foo".to_string()],
+                sources: vec!["foo".to_string()],
+                subject: "foo: crashes on startup".to_string(),
+                severity: "serious".to_string(),
+                tags: vec!["patch".to_string(), "upstream".to_string()],
+                found_versions: vec!["foo/1.0-1".to_string()],
+                fixed_versions: vec![],
+                done: None,
+                archived: false,
+                forwarded: Some("https://github.com/foo/foo/issues/1".to_string()),
+            }],
+            bugs
+        );
+        assert_eq!(
+            vec![
+                ClosesIssue::UnknownBug(1),
+                ClosesIssue::WrongPackage {
+                    bug: 123456,
+                    packages: vec!["python3-foo".to_string()]
+                }
+            ],
+            check_closes("bar", &["python3-bar"], &[1, 123456], &bugs)
+        );
+        assert!(check_closes("foo", &[], &[123456], &bugs).is_empty());
+    }
+
+    #[test]
+    fn test_parse_usertags() {
+        let response = xmltree::Element::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+<soap:Body><get_usertagResponse xmlns="urn:Debbugs/SOAP"><s-gensym3>
+<ftbfs><item>1</item><item>2</item></ftbfs>
+<piuparts><item>3</item></piuparts>
+</s-gensym3></get_usertagResponse></soap:Body></soap:Envelope>"#
+                .as_bytes(),
+        )
+        .unwrap();
+        let tags = parse_usertags(&response_body(&response, "get_usertag").unwrap());
+        assert_eq!(Some(&vec![1, 2]), tags.get("ftbfs"));
+        assert_eq!(Some(&vec![3]), tags.get("piuparts"));
+    }
+
+    #[test]
+    fn test_request_bodies() {
+        assert_eq!(
+            "<get_status xmlns=\"urn:Debbugs/SOAP\"><bugs soapenc:arrayType=\"xsd:int[2]\" xsi:type=\"soapenc:Array\"><item xsi:type=\"xsd:int\">1</item><item xsi:type=\"xsd:int\">2</item></bugs></get_status>",
+            get_status_body(&[1, 2])
+        );
+        assert_eq!(
+            "<get_usertag xmlns=\"urn:Debbugs/SOAP\"><email xsi:type=\"xsd:string\">a&amp;b@example.com</email><tag xsi:type=\"xsd:string\">ftbfs</tag></get_usertag>",
+            get_usertag_body("a&b@example.com", &["ftbfs"])
+        );
+    }
+}
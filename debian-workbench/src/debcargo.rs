@@ -2,10 +2,12 @@
 
 // TODO: Reuse the debcargo crate for more of this.
 
+use crate::error::Error;
 use debian_control::fields::MultiArch;
-use std::collections::{HashMap, HashSet};
+use debian_control::lossless::relations::{Entry, Relations};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use toml_edit::{value, DocumentMut, Table};
+use toml_edit::{DocumentMut, Table};
 
 pub use toml_edit;
 
@@ -22,6 +24,16 @@ pub const CURRENT_STANDARDS_VERSION: &str = "4.5.1";
 /// The default priority for Rust packages.
 pub const DEFAULT_PRIORITY: debian_control::Priority = debian_control::Priority::Optional;
 
+/// Read and parse a TOML file.
+fn read_toml(path: &Path) -> Result<DocumentMut, Error> {
+    std::fs::read_to_string(path)?
+        .parse()
+        .map_err(|e: toml_edit::TomlError| Error::Parse {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })
+}
+
 /// A wrapper around a debcargo.toml file.
 pub struct DebcargoEditor {
     /// Path to the debcargo.toml file.
@@ -30,14 +42,18 @@ pub struct DebcargoEditor {
     /// The contents of the debcargo.toml file.
     pub debcargo: DocumentMut,
 
-    /// The contents of the Cargo.toml file.
+    /// The contents of the Cargo.toml file of the crate.
     pub cargo: Option<DocumentMut>,
+
+    /// The contents of the root Cargo.toml file, if the crate is a member of a workspace.
+    workspace: Option<DocumentMut>,
 }
 
 impl From<DocumentMut> for DebcargoEditor {
     fn from(doc: DocumentMut) -> Self {
         Self {
             cargo: None,
+            workspace: None,
             debcargo_toml_path: None,
             debcargo: doc,
         }
@@ -57,6 +73,7 @@ impl DebcargoEditor {
             debcargo_toml_path: None,
             debcargo: DocumentMut::new(),
             cargo: None,
+            workspace: None,
         }
     }
 
@@ -64,51 +81,192 @@ impl DebcargoEditor {
     fn crate_name(&self) -> Option<&str> {
         self.cargo
             .as_ref()
-            .and_then(|c| c["package"]["name"].as_str())
+            .and_then(|c| c.get("package")?.get("name")?.as_str())
     }
 
     /// Return the version of the crate.
     fn crate_version(&self) -> Option<semver::Version> {
         self.cargo
             .as_ref()
-            .and_then(|c| c["package"]["version"].as_str())
-            .map(|s| semver::Version::parse(s).unwrap())
+            .and_then(|c| package_version(c, self.workspace.as_ref()))
+            .and_then(|s| semver::Version::parse(s).ok())
     }
 
     /// Open a debcargo.toml file.
-    pub fn open(path: &Path) -> Result<Self, std::io::Error> {
-        let content = std::fs::read_to_string(path)?;
+    pub fn open(path: &Path) -> Result<Self, Error> {
         Ok(Self {
             debcargo_toml_path: Some(path.to_path_buf()),
             cargo: None,
-            debcargo: content.parse().unwrap(),
+            workspace: None,
+            debcargo: read_toml(path)?,
         })
     }
 
     /// Open a debcargo.toml file in a directory.
-    pub fn from_directory(path: &std::path::Path) -> Result<Self, std::io::Error> {
+    ///
+    /// The Cargo.toml file in the directory must declare the name and a
+    /// valid version of the crate. If it is the root of a workspace
+    /// without a package of its own, the crate is the member in the
+    /// directory named by the `crate_src_path` key in debcargo.toml, or the
+    /// only member of the workspace. The version may be inherited from the
+    /// workspace.
+    pub fn from_directory(path: &std::path::Path) -> Result<Self, Error> {
         let debcargo_toml_path = path.join("debian/debcargo.toml");
-        let debcargo_toml = std::fs::read_to_string(&debcargo_toml_path)?;
-        let cargo_toml = std::fs::read_to_string(path.join("Cargo.toml"))?;
+        let debcargo = read_toml(&debcargo_toml_path)?;
+        let mut cargo_toml_path = path.join("Cargo.toml");
+        let mut cargo = read_toml(&cargo_toml_path)?;
+        let mut workspace = None;
+        if cargo.get("package").is_none() && cargo.get("workspace").is_some() {
+            let member = workspace_member(&debcargo, &cargo).ok_or_else(|| Error::Parse {
+                path: cargo_toml_path.clone(),
+                message: "unable to determine workspace member; set crate_src_path".to_string(),
+            })?;
+            cargo_toml_path = path.join(member).join("Cargo.toml");
+            workspace = Some(std::mem::replace(&mut cargo, read_toml(&cargo_toml_path)?));
+        } else if cargo.get("workspace").is_some() {
+            workspace = Some(cargo.clone());
+        }
+        if cargo
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+            .is_none()
+        {
+            return Err(Error::Parse {
+                path: cargo_toml_path,
+                message: "missing package name".to_string(),
+            });
+        }
+        match package_version(&cargo, workspace.as_ref()) {
+            Some(version) => {
+                if let Err(e) = semver::Version::parse(version) {
+                    return Err(Error::InvalidVersion {
+                        version: version.to_string(),
+                        message: e.to_string(),
+                    });
+                }
+            }
+            None => {
+                return Err(Error::Parse {
+                    path: cargo_toml_path,
+                    message: "missing package version".to_string(),
+                })
+            }
+        }
         Ok(Self {
             debcargo_toml_path: Some(debcargo_toml_path),
-            debcargo: debcargo_toml.parse().unwrap(),
-            cargo: Some(cargo_toml.parse().unwrap()),
+            debcargo,
+            cargo: Some(cargo),
+            workspace,
         })
     }
 
+    /// Return the overlay directory.
+    ///
+    /// Files in the overlay directory are copied on top of the files that
+    /// debcargo generates. The `overlay` key is relative to the directory
+    /// with the debcargo.toml file.
+    ///
+    /// # Returns
+    /// The path of the overlay directory, or `None` if there is no overlay
+    /// or the editor was not opened from a file
+    pub fn overlay_dir(&self) -> Option<PathBuf> {
+        let overlay = self.debcargo.get("overlay")?.as_str()?;
+        Some(self.debcargo_toml_path.as_ref()?.parent()?.join(overlay))
+    }
+
+    /// List the files in the overlay directory.
+    ///
+    /// The debcargo.toml file itself is not included.
+    ///
+    /// # Returns
+    /// The paths of the files relative to the overlay directory, sorted
+    pub fn overlay_files(&self) -> Result<Vec<PathBuf>, Error> {
+        let Some(overlay_dir) = self.overlay_dir() else {
+            return Ok(vec![]);
+        };
+        if !overlay_dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let debcargo_toml_path = self
+            .debcargo_toml_path
+            .as_ref()
+            .and_then(|p| p.canonicalize().ok());
+        let mut ret = vec![];
+        let mut todo = vec![overlay_dir.clone()];
+        while let Some(dir) = todo.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    todo.push(path);
+                } else if path.canonicalize().ok() != debcargo_toml_path {
+                    ret.push(path.strip_prefix(&overlay_dir).unwrap().to_path_buf());
+                }
+            }
+        }
+        ret.sort();
+        Ok(ret)
+    }
+
+    /// Edit a file in the overlay directory.
+    ///
+    /// The file does not have to exist yet; it is created on commit.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the file, relative to the overlay directory
+    /// * `allow_reformatting` - Whether to allow reformatting of the file
+    pub fn edit_overlay_file<P: crate::editor::Marshallable>(
+        &self,
+        path: &Path,
+        allow_reformatting: bool,
+    ) -> Result<crate::editor::FsEditor<P>, crate::editor::EditorError> {
+        let overlay_dir = self.overlay_dir().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "debcargo.toml does not have an overlay directory",
+            )
+        })?;
+        if !path.is_relative() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not a relative path", path.display()),
+            )
+            .into());
+        }
+        crate::editor::FsEditor::new(&overlay_dir.join(path), false, allow_reformatting, false)
+    }
+
     /// Commit changes to the debcargo.toml file.
-    pub fn commit(&self) -> std::io::Result<bool> {
-        let old_contents = std::fs::read_to_string(self.debcargo_toml_path.as_ref().unwrap())?;
+    ///
+    /// Fails if the editor was not opened from a file.
+    #[tracing::instrument(level = "debug", skip(self), fields(path = ?self.debcargo_toml_path))]
+    pub fn commit(&self) -> Result<bool, Error> {
+        let changes = self.pending_changes()?;
+        for change in &changes {
+            std::fs::write(&change.path, change.new.as_deref().unwrap_or_default())?;
+        }
+        Ok(!changes.is_empty())
+    }
+
+    /// Describe the changes that [`DebcargoEditor::commit`] would make,
+    /// without writing them.
+    pub fn pending_changes(&self) -> Result<Vec<crate::editor::FileChange>, Error> {
+        let path = self.debcargo_toml_path.as_ref().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "debcargo.toml editor is not backed by a file",
+            )
+        })?;
+        let old_contents = std::fs::read_to_string(path)?;
         let new_contents = self.debcargo.to_string();
         if old_contents == new_contents {
-            return Ok(false);
+            return Ok(vec![]);
         }
-        std::fs::write(
-            self.debcargo_toml_path.as_ref().unwrap(),
-            new_contents.as_bytes(),
-        )?;
-        Ok(true)
+        Ok(vec![crate::editor::FileChange {
+            path: path.clone(),
+            old: Some(old_contents.into_bytes()),
+            new: Some(new_contents.into_bytes()),
+        }])
     }
 
     /// Return the source package
@@ -117,86 +275,718 @@ impl DebcargoEditor {
     }
 
     fn semver_suffix(&self) -> bool {
-        self.debcargo["source"]
-            .get("semver_suffix")
+        self.debcargo
+            .get("source")
+            .and_then(|s| s.get("semver_suffix"))
             .and_then(|v| v.as_bool())
             .unwrap_or(false)
     }
 
     /// Return an iterator over the binaries in the package.
+    ///
+    /// Besides the lib and bin packages, this includes the packages for
+    /// features that get a package of their own; see
+    /// [`DebcargoEditor::feature_packages`].
+    ///
+    /// Overrides for a binary are stored in its `[packages.<key>]` table,
+    /// e.g. `[packages.lib]` or `[packages."lib+std"]`; tables that do not
+    /// exist yet are only written out once something is set in them.
+    ///
+    /// There are no binaries if the name or version of the crate is not
+    /// known, e.g. when there is no Cargo.toml file.
     pub fn binaries(&mut self) -> impl Iterator<Item = DebcargoBinary<'_>> {
         let semver_suffix = self.semver_suffix();
+        let mut ret = self.binary_names(semver_suffix).unwrap_or_default();
+
+        let context = match (self.crate_name(), self.crate_version()) {
+            (Some(crate_name), Some(crate_version)) => Some(BinaryContext {
+                global_summary: self.global_summary(),
+                global_description: self.global_description(),
+                crate_name: crate_name.to_string(),
+                crate_version,
+                semver_suffix,
+            }),
+            _ => None,
+        };
+        let feature_packages = self.feature_packages();
+
+        let packages = implicit_table(self.debcargo.as_table_mut(), "packages");
+        for key in ret.keys() {
+            implicit_table(packages, key);
+        }
+
+        packages.iter_mut().filter_map(move |(key, item)| {
+            let name = ret.remove(key.get())?;
+            // Features whose package is provided by this one
+            let features = key
+                .get()
+                .strip_prefix("lib+")
+                .map(|f| f.to_string())
+                .into_iter()
+                .chain(
+                    feature_packages
+                        .iter()
+                        .filter(|p| p.provided_by.as_ref() == Some(&name))
+                        .map(|p| p.feature.clone()),
+                )
+                .collect();
+            Some(DebcargoBinary::new(
+                key.get().to_string(),
+                name,
+                item.as_table_mut()?,
+                context.clone()?,
+                features,
+            ))
+        })
+    }
 
-        let mut ret: HashMap<String, String> = HashMap::new();
+    /// Return the name of the source package.
+    fn source_name(&self, semver_suffix: bool) -> Option<String> {
+        let crate_name = self.crate_name()?;
+        if semver_suffix {
+            let crate_version = self.crate_version()?;
+            Some(format!(
+                "rust-{}-{}",
+                debnormalize(crate_name),
+                semver_pair(&crate_version)
+            ))
+        } else {
+            Some(format!("rust-{}", debnormalize(crate_name)))
+        }
+    }
+
+    /// Return the suffix that is appended to the crate name in package names.
+    fn package_suffix(&self, semver_suffix: bool) -> Option<String> {
+        if semver_suffix {
+            Some(format!("-{}", semver_pair(&self.crate_version()?)))
+        } else {
+            Some("".to_string())
+        }
+    }
+
+    /// Return a map from the key of the `[packages.<key>]` table to the binary name.
+    fn binary_names(&self, semver_suffix: bool) -> Option<HashMap<String, String>> {
+        let crate_name = self.crate_name()?;
+        let mut ret = HashMap::new();
         ret.insert(
-            debcargo_binary_name(
-                self.crate_name().unwrap(),
-                &if semver_suffix {
-                    semver_pair(&self.crate_version().unwrap())
-                } else {
-                    "".to_string()
-                },
-            ),
             "lib".to_string(),
+            debcargo_binary_name(crate_name, &self.package_suffix(semver_suffix)?),
         );
 
-        if self.debcargo["bin"].as_bool().unwrap_or(!semver_suffix) {
-            let bin_name = self.debcargo["bin_name"]
-                .as_str()
-                .unwrap_or_else(|| self.crate_name().unwrap());
-            ret.insert(bin_name.to_owned(), "bin".to_string());
+        if self
+            .debcargo
+            .get("bin")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(!semver_suffix)
+        {
+            let bin_name = self
+                .debcargo
+                .get("bin_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or(crate_name);
+            ret.insert("bin".to_string(), bin_name.to_owned());
         }
 
-        let global_summary = self.global_summary();
-        let global_description = self.global_description();
-        let crate_name = self.crate_name().unwrap().to_string();
-        let crate_version = self.crate_version().unwrap();
-        let features = self.features();
+        for package in self.feature_packages_with(semver_suffix) {
+            if package.provided_by.is_none() {
+                ret.insert(format!("lib+{}", package.feature), package.name);
+            }
+        }
+        Some(ret)
+    }
+
+    /// Return the packages that debcargo generates for the features of the crate.
+    ///
+    /// Features include the implicit features for optional dependencies
+    /// and the `default` feature. A feature that enables more than one
+    /// other feature or any dependencies gets a package of its own, named
+    /// `librust-<crate>+<feature>-dev`. Other features are provided by the
+    /// package of the feature they enable, or by the lib package; with
+    /// `collapse_features` set, the lib package provides all features.
+    ///
+    /// # Returns
+    /// The feature packages, sorted by feature
+    pub fn feature_packages(&self) -> Vec<FeaturePackage> {
+        self.feature_packages_with(self.semver_suffix())
+    }
+
+    fn feature_packages_with(&self, semver_suffix: bool) -> Vec<FeaturePackage> {
+        let (Some(cargo), Some(crate_name), Some(suffix)) = (
+            self.cargo.as_ref(),
+            self.crate_name(),
+            self.package_suffix(semver_suffix),
+        ) else {
+            return vec![];
+        };
+        let lib_name = debcargo_binary_name(crate_name, &suffix);
+        let collapse_features = self
+            .debcargo
+            .get("collapse_features")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let features = cargo_features(cargo);
 
-        self.debcargo
-            .as_table_mut()
-            .iter_mut()
-            .filter_map(move |(key, item)| {
-                let kind = ret.remove(&key.to_string())?;
-                Some(DebcargoBinary::new(
-                    kind,
-                    key.to_string(),
-                    item.as_table_mut().unwrap(),
-                    global_summary.clone(),
-                    global_description.clone(),
-                    crate_name.clone(),
-                    crate_version.clone(),
-                    semver_suffix,
-                    features.clone(),
-                ))
+        // Find the package that provides a feature, following features that
+        // only enable a single other feature.
+        let package_name = |feature: &str| {
+            format!(
+                "librust-{}{}+{}-dev",
+                debnormalize(crate_name),
+                suffix,
+                debnormalize(feature)
+            )
+        };
+        let provider = |feature: &str| {
+            let mut feature = feature;
+            let mut seen = HashSet::new();
+            loop {
+                if collapse_features || !seen.insert(feature) {
+                    return lib_name.clone();
+                }
+                let Some((enabled, dependencies)) = features.get(feature) else {
+                    return lib_name.clone();
+                };
+                match enabled.as_slice() {
+                    _ if !dependencies.is_empty() => return package_name(feature),
+                    [] => return lib_name.clone(),
+                    [enabled] => feature = enabled.as_str(),
+                    _ => return package_name(feature),
+                }
+            }
+        };
+
+        features
+            .iter()
+            .map(|(feature, (enabled, dependencies))| {
+                let name = package_name(feature.as_str());
+                let provided_by = provider(feature.as_str());
+                FeaturePackage {
+                    feature: feature.clone(),
+                    provided_by: if provided_by == name {
+                        None
+                    } else {
+                        Some(provided_by)
+                    },
+                    name,
+                    features: enabled.clone(),
+                    dependencies: dependencies.iter().cloned().collect(),
+                }
             })
+            .collect()
+    }
+
+    /// Return the package names for a value of the `semver_suffix` setting.
+    fn package_names(&self, semver_suffix: bool) -> Option<PackageNames> {
+        let mut binaries = self
+            .binary_names(semver_suffix)?
+            .into_values()
+            .collect::<Vec<_>>();
+        binaries.sort();
+        Some(PackageNames {
+            source: self.source_name(semver_suffix)?,
+            binaries,
+        })
     }
 
+    /// Return the description of the crate in Cargo.toml.
+    fn crate_description(&self) -> Option<&str> {
+        self.cargo
+            .as_ref()?
+            .get("package")?
+            .get("description")?
+            .as_str()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Return the summary that the package summaries are based on.
+    ///
+    /// This is the `summary` key, or else the first line of the crate description.
     fn global_summary(&self) -> Option<String> {
         if let Some(summary) = self.debcargo.get("summary").and_then(|v| v.as_str()) {
-            Some(format!("{} - Rust source code", summary))
+            Some(summary.to_string())
         } else {
-            self.cargo.as_ref().and_then(|c| {
-                c["package"]
-                    .get("description")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.split('\n').next().unwrap().to_string())
-            })
+            self.crate_description()
+                .and_then(|d| d.lines().next())
+                .map(|s| s.trim().trim_end_matches('.').to_string())
         }
     }
 
+    /// Return the description that the package descriptions start with.
+    ///
+    /// This is the `description` key, or else the crate description.
     fn global_description(&self) -> Option<String> {
         self.debcargo
             .get("description")
             .and_then(|v| v.as_str())
+            .or_else(|| self.crate_description())
             .map(|description| description.to_owned())
     }
 
-    fn features(&self) -> Option<HashSet<String>> {
-        self.cargo
-            .as_ref()
-            .and_then(|c| c["features"].as_table())
-            .map(|t| t.iter().map(|(k, _)| k.to_string()).collect())
+    /// Set the summary that the package summaries are based on, or remove it if `None`.
+    pub fn set_summary(&mut self, summary: Option<&str>) -> &mut Self {
+        if let Some(summary) = summary {
+            set_preserving(
+                self.debcargo.as_table_mut(),
+                "summary",
+                summary,
+                TOP_LEVEL_KEYS,
+            );
+        } else {
+            self.debcargo.remove("summary");
+        }
+        self
+    }
+
+    /// Set the description that the package descriptions start with, or remove it if `None`.
+    pub fn set_description(&mut self, description: Option<&str>) -> &mut Self {
+        if let Some(description) = description {
+            set_preserving(
+                self.debcargo.as_table_mut(),
+                "description",
+                description,
+                TOP_LEVEL_KEYS,
+            );
+        } else {
+            self.debcargo.remove("description");
+        }
+        self
+    }
+
+    /// Render the debian/control file that debcargo would generate.
+    ///
+    /// Relations on the dependencies of the crate are unversioned, and
+    /// the build dependencies on them are left out, since debcargo
+    /// resolves those against the crate registry.
+    ///
+    /// # Returns
+    /// The control file, or `None` if the crate name or version is unknown
+    pub fn render_control(&mut self) -> Option<debian_control::Control> {
+        let crate_name = self.crate_name()?.to_string();
+        let lib_name = self.binary_names(self.semver_suffix())?.remove("lib")?;
+        let feature_packages = self.feature_packages();
+        let mut control = debian_control::Control::new();
+
+        let source = self.source();
+        let mut paragraph = control.add_source(&source.name()?);
+        let fields = [
+            ("Section", Some(source.section().to_string())),
+            ("Priority", Some(source.priority().to_string())),
+            (
+                "Build-Depends",
+                Some(
+                    ["debhelper-compat (= 13)", "dh-sequence-cargo"]
+                        .iter()
+                        .map(|s| s.to_string())
+                        .chain(source.build_depends().unwrap_or_default())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+            ),
+            (
+                "Build-Depends-Arch",
+                Some(
+                    "cargo:native <!nocheck>, rustc:native <!nocheck>, libstd-rust-dev <!nocheck>"
+                        .to_string(),
+                ),
+            ),
+            ("Maintainer", Some(source.maintainer().to_string())),
+            ("Uploaders", source.uploaders().map(|u| u.join(", "))),
+            (
+                "Standards-Version",
+                Some(source.standards_version().to_string()),
+            ),
+            ("Vcs-Git", source.vcs_git()),
+            ("Vcs-Browser", source.vcs_browser()),
+            ("Homepage", source.homepage().map(|h| h.to_string())),
+            ("X-Cargo-Crate", Some(crate_name.clone())),
+            (
+                "Rules-Requires-Root",
+                Some(source.requires_root().unwrap_or_else(|| "no".to_string())),
+            ),
+        ];
+        for (field, value) in fields {
+            if let Some(value) = value {
+                paragraph.as_mut_deb822().set(field, &value);
+            }
+        }
+        for line in source.extra_lines() {
+            if let Some((field, value)) = line.split_once(':') {
+                paragraph.as_mut_deb822().set(field.trim(), value.trim());
+            }
+        }
+
+        let mut binaries = self.binaries().collect::<Vec<_>>();
+        binaries.sort_by(|a, b| a.name().cmp(b.name()));
+        for binary in binaries {
+            let mut depends = vec!["${misc:Depends}".to_string()];
+            let mut built_using = false;
+            if binary.key == "bin" {
+                depends.push("${shlibs:Depends}".to_string());
+                depends.push("${cargo:Depends}".to_string());
+                built_using = true;
+            } else if let Some(package) = binary
+                .key
+                .strip_prefix("lib+")
+                .and_then(|f| feature_packages.iter().find(|p| p.feature == f))
+            {
+                // Depend on the lib package and the packages of the enabled features
+                let mut requires = vec![&lib_name];
+                for feature in &package.features {
+                    if let Some(required) = feature_packages.iter().find(|p| &p.feature == feature)
+                    {
+                        let required = required.provided_by.as_ref().unwrap_or(&required.name);
+                        if !requires.contains(&required) && required != binary.name() {
+                            requires.push(required);
+                        }
+                    }
+                }
+                depends.extend(
+                    requires
+                        .into_iter()
+                        .map(|r| format!("{} (= ${{binary:Version}})", r)),
+                );
+                depends.extend(
+                    package
+                        .dependencies
+                        .iter()
+                        .map(|d| debcargo_binary_name(d, "")),
+                );
+            }
+            depends.extend(binary.depends().unwrap_or_default());
+            let provides = binary
+                .default_provides()
+                .into_iter()
+                .map(|p| format!("{} (= ${{binary:Version}})", p))
+                .chain(binary.provides().unwrap_or_default())
+                .collect::<Vec<_>>();
+
+            let mut paragraph = control.add_binary(binary.name());
+            let fields = [
+                ("Architecture", binary.architecture().map(|a| a.to_string())),
+                ("Multi-Arch", binary.multi_arch().map(|m| m.to_string())),
+                ("Section", binary.section().map(|s| s.to_string())),
+                ("Depends", Some(depends.join(", "))),
+                ("Recommends", binary.recommends().map(|r| r.join(", "))),
+                ("Suggests", binary.suggests().map(|s| s.join(", "))),
+                (
+                    "Provides",
+                    Some(provides.join(", ")).filter(|p| !p.is_empty()),
+                ),
+                (
+                    "Built-Using",
+                    built_using.then(|| "${cargo:Built-Using}".to_string()),
+                ),
+                (
+                    "Static-Built-Using",
+                    built_using.then(|| "${cargo:Static-Built-Using}".to_string()),
+                ),
+            ];
+            for (field, value) in fields {
+                if let Some(value) = value {
+                    paragraph.as_mut_deb822().set(field, &value);
+                }
+            }
+            if let (Some(summary), Some(long_description)) =
+                (binary.summary(), binary.long_description())
+            {
+                let mut description = summary;
+                for line in long_description.lines() {
+                    description.push('\n');
+                    description.push_str(if line.is_empty() { "." } else { line });
+                }
+                paragraph.set_description(Some(&description));
+            }
+        }
+        Some(control)
+    }
+
+    /// Check the debcargo.toml file against the keys and value types that debcargo accepts.
+    ///
+    /// Spans refer to the debcargo.toml file as it would be written by
+    /// [`DebcargoEditor::commit`], i.e. to the file as read if it has not
+    /// been edited.
+    ///
+    /// # Returns
+    /// The problems found, in file order
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        // Edited documents have no spans, so parse the serialized document again.
+        let doc = match toml_edit::Document::parse(self.debcargo.to_string()) {
+            Ok(doc) => doc,
+            Err(e) => {
+                log::warn!("Unable to reparse debcargo.toml: {}", e);
+                return vec![];
+            }
+        };
+        let mut ret = vec![];
+        check_table(doc.as_table(), &[], TOP_LEVEL_KEYS, &mut ret);
+        if let Some(source) = doc.as_table().get("source").and_then(|s| s.as_table_like()) {
+            check_table(source, &["source"], SOURCE_KEYS, &mut ret);
+        }
+        if let Some(packages) = doc
+            .as_table()
+            .get("packages")
+            .and_then(|p| p.as_table_like())
+        {
+            for (key, item) in packages.iter() {
+                let path = vec!["packages".to_string(), key.to_string()];
+                if key != "lib" && key != "bin" && !key.starts_with("lib+") {
+                    ret.push(Diagnostic {
+                        kind: DiagnosticKind::UnknownKey,
+                        span: packages.get_key_value(key).and_then(|(k, _)| k.span()),
+                        path,
+                    });
+                } else if let Some(package) = item.as_table_like() {
+                    check_table(package, &["packages", key], PACKAGE_KEYS, &mut ret);
+                } else {
+                    ret.push(Diagnostic {
+                        kind: DiagnosticKind::WrongType {
+                            expected: ValueType::Table.name(),
+                            found: item.type_name(),
+                        },
+                        span: item.span(),
+                        path,
+                    });
+                }
+            }
+        }
+        ret.sort_by_key(|d| d.span.as_ref().map(|s| s.start));
+        ret
+    }
+}
+
+/// A package that debcargo generates for a feature of a crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeaturePackage {
+    /// Name of the feature
+    pub feature: String,
+
+    /// Name of the package for the feature
+    pub name: String,
+
+    /// Other features that the feature enables
+    pub features: Vec<String>,
+
+    /// Dependencies that the feature enables, sorted
+    pub dependencies: Vec<String>,
+
+    /// The package that provides the feature, if the feature does not get
+    /// a package of its own
+    pub provided_by: Option<String>,
+}
+
+/// The type of a value in a debcargo.toml file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueType {
+    String,
+    Bool,
+    Array,
+    StringOrArray,
+    StringOrBool,
+    Table,
+}
+
+impl ValueType {
+    fn name(self) -> &'static str {
+        match self {
+            ValueType::String => "string",
+            ValueType::Bool => "boolean",
+            ValueType::Array => "array",
+            ValueType::StringOrArray => "string or array",
+            ValueType::StringOrBool => "string or boolean",
+            ValueType::Table => "table",
+        }
+    }
+
+    fn matches(self, item: &toml_edit::Item) -> bool {
+        match self {
+            ValueType::String => item.is_str(),
+            ValueType::Bool => item.is_bool(),
+            ValueType::Array => item.is_array(),
+            ValueType::StringOrArray => item.is_str() || item.is_array(),
+            ValueType::StringOrBool => item.is_str() || item.is_bool(),
+            ValueType::Table => item.is_table_like(),
+        }
+    }
+}
+
+/// Keys that debcargo accepts at the top level of debcargo.toml.
+const TOP_LEVEL_KEYS: &[(&str, ValueType)] = &[
+    ("overlay", ValueType::String),
+    ("overlay_write_back", ValueType::Bool),
+    ("crate_src_path", ValueType::String),
+    ("excludes", ValueType::Array),
+    ("whitelist", ValueType::Array),
+    ("allow_prerelease_deps", ValueType::Bool),
+    ("summary", ValueType::String),
+    ("description", ValueType::String),
+    ("bin", ValueType::Bool),
+    ("bin_name", ValueType::String),
+    ("semver_suffix", ValueType::Bool),
+    ("collapse_features", ValueType::Bool),
+    ("requires_root", ValueType::String),
+    ("source", ValueType::Table),
+    ("packages", ValueType::Table),
+];
+
+/// Keys that debcargo accepts in the `[source]` table.
+const SOURCE_KEYS: &[(&str, ValueType)] = &[
+    ("standards-version", ValueType::String),
+    ("homepage", ValueType::String),
+    ("vcs_git", ValueType::String),
+    ("vcs_browser", ValueType::String),
+    ("section", ValueType::String),
+    ("priority", ValueType::String),
+    ("maintainer", ValueType::String),
+    ("uploaders", ValueType::Array),
+    ("requires_root", ValueType::StringOrBool),
+    ("semver_suffix", ValueType::Bool),
+    ("build_depends", ValueType::Array),
+    ("build_depends_arch", ValueType::Array),
+    ("build_depends_indep", ValueType::Array),
+    ("build_depends_excludes", ValueType::Array),
+    ("skip_nocheck", ValueType::Bool),
+    ("extra_lines", ValueType::Array),
+];
+
+/// Keys that debcargo accepts in the `[packages.<key>]` tables.
+const PACKAGE_KEYS: &[(&str, ValueType)] = &[
+    ("summary", ValueType::String),
+    ("description", ValueType::String),
+    ("section", ValueType::String),
+    ("depends", ValueType::StringOrArray),
+    ("recommends", ValueType::StringOrArray),
+    ("suggests", ValueType::StringOrArray),
+    ("provides", ValueType::StringOrArray),
+    ("extra_lines", ValueType::Array),
+    ("test_is_broken", ValueType::Bool),
+];
+
+/// Deprecated keys, as the path of the table they are in, the key and its replacement.
+const DEPRECATED_KEYS: &[(&[&str], &str, Option<&str>)] =
+    &[(&["source"], "policy", Some("standards-version"))];
+
+/// Check the keys and value types in a table of a debcargo.toml file.
+fn check_table(
+    table: &dyn toml_edit::TableLike,
+    path: &[&str],
+    schema: &[(&str, ValueType)],
+    ret: &mut Vec<Diagnostic>,
+) {
+    for (key, item) in table.iter() {
+        let key_span = table.get_key_value(key).and_then(|(k, _)| k.span());
+        let key_path = path
+            .iter()
+            .copied()
+            .chain(std::iter::once(key))
+            .map(|k| k.to_string())
+            .collect();
+        if let Some((_, _, replacement)) = DEPRECATED_KEYS
+            .iter()
+            .find(|(p, k, _)| *p == path && *k == key)
+        {
+            ret.push(Diagnostic {
+                kind: DiagnosticKind::Deprecated {
+                    replacement: replacement.map(|r| r.to_string()),
+                },
+                path: key_path,
+                span: key_span,
+            });
+            continue;
+        }
+        match schema.iter().find(|(k, _)| *k == key) {
+            None => ret.push(Diagnostic {
+                kind: DiagnosticKind::UnknownKey,
+                path: key_path,
+                span: key_span,
+            }),
+            Some((_, value_type)) if !value_type.matches(item) => ret.push(Diagnostic {
+                kind: DiagnosticKind::WrongType {
+                    expected: value_type.name(),
+                    found: item.type_name(),
+                },
+                path: key_path,
+                span: item.span(),
+            }),
+            Some(_) => {}
+        }
+    }
+}
+
+/// The kind of problem found in a debcargo.toml file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The key is not known to debcargo
+    UnknownKey,
+    /// The value has the wrong type
+    WrongType {
+        /// Name of the type that debcargo expects
+        expected: &'static str,
+        /// Name of the type of the value
+        found: &'static str,
+    },
+    /// The key is deprecated
+    Deprecated {
+        /// The key to use instead, if any
+        replacement: Option<String>,
+    },
+}
+
+/// A problem found in a debcargo.toml file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// What the problem is
+    pub kind: DiagnosticKind,
+
+    /// Path of the key, e.g. `["source", "section"]`
+    pub path: Vec<String>,
+
+    /// Byte range of the key, or of the value for type errors
+    pub span: Option<std::ops::Range<usize>>,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let path = self.path.join(".");
+        match &self.kind {
+            DiagnosticKind::UnknownKey => write!(f, "Unknown key {}", path),
+            DiagnosticKind::WrongType { expected, found } => {
+                write!(f, "{}: expected {}, found {}", path, expected, found)
+            }
+            DiagnosticKind::Deprecated {
+                replacement: Some(replacement),
+            } => write!(f, "{} is deprecated, use {} instead", path, replacement),
+            DiagnosticKind::Deprecated { replacement: None } => {
+                write!(f, "{} is deprecated", path)
+            }
+        }
+    }
+}
+
+/// The names of the packages built from a debcargo.toml file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageNames {
+    /// Name of the source package
+    pub source: String,
+
+    /// Names of the binary packages, sorted
+    pub binaries: Vec<String>,
+}
+
+/// Package names before and after a change to a debcargo.toml file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageRenames {
+    /// The package names before the change
+    pub old: PackageNames,
+
+    /// The package names after the change
+    pub new: PackageNames,
+}
+
+impl PackageRenames {
+    /// Whether the change renames any packages.
+    pub fn is_empty(&self) -> bool {
+        self.old == self.new
     }
 }
 
@@ -208,15 +998,31 @@ pub struct DebcargoSource<'a> {
 impl DebcargoSource<'_> {
     /// Return the source section of the debcargo.toml file.
     pub fn toml_section_mut(&mut self) -> &mut Table {
-        if !self.main.debcargo.contains_key("source") {
-            self.main.debcargo["source"] = toml_edit::Item::Table(Table::new());
+        let root = self.main.debcargo.as_table_mut();
+        if !root.get("source").is_some_and(|s| s.is_table()) {
+            // An inline table, e.g. as created by IndexMut, becomes a [source] table
+            let source = root
+                .remove("source")
+                .and_then(|s| s.into_table().ok())
+                .unwrap_or_default();
+            root.insert("source", toml_edit::Item::Table(source));
+            // Keep [source] ahead of any [packages.*] tables
+            place_key(root, "source", TOP_LEVEL_KEYS);
+        }
+        match root.get_mut("source") {
+            Some(toml_edit::Item::Table(source)) => source,
+            _ => unreachable!("source was made a table above"),
         }
-        self.main.debcargo["source"].as_table_mut().unwrap()
     }
 
     /// Set the standards version.
     pub fn set_standards_version(&mut self, version: &str) -> &mut Self {
-        self.toml_section_mut()["standards-version"] = value(version);
+        set_preserving(
+            self.toml_section_mut(),
+            "standards-version",
+            version,
+            SOURCE_KEYS,
+        );
         self
     }
 
@@ -232,7 +1038,7 @@ impl DebcargoSource<'_> {
 
     /// Set the homepage.
     pub fn set_homepage(&mut self, homepage: &str) -> &mut Self {
-        self.toml_section_mut()["homepage"] = value(homepage);
+        set_preserving(self.toml_section_mut(), "homepage", homepage, SOURCE_KEYS);
         self
     }
 
@@ -255,7 +1061,7 @@ impl DebcargoSource<'_> {
 
     /// Set the VCS Git URL.
     pub fn set_vcs_git(&mut self, git: &str) -> &mut Self {
-        self.toml_section_mut()["vcs_git"] = value(git);
+        set_preserving(self.toml_section_mut(), "vcs_git", git, SOURCE_KEYS);
         self
     }
 
@@ -295,7 +1101,7 @@ impl DebcargoSource<'_> {
 
     /// Set the VCS browser URL.
     pub fn set_vcs_browser(&mut self, browser: &str) -> &mut Self {
-        self.toml_section_mut()["vcs_browser"] = value(browser);
+        set_preserving(self.toml_section_mut(), "vcs_browser", browser, SOURCE_KEYS);
         self
     }
 
@@ -311,24 +1117,46 @@ impl DebcargoSource<'_> {
 
     /// Set the section.
     pub fn set_section(&mut self, section: &str) -> &mut Self {
-        self.toml_section_mut()["section"] = value(section);
+        set_preserving(self.toml_section_mut(), "section", section, SOURCE_KEYS);
         self
     }
 
     /// Get the name of the package.
     pub fn name(&self) -> Option<String> {
-        let crate_name = self.main.crate_name()?;
-        let semver_suffix = self.main.semver_suffix();
-        if semver_suffix {
-            let crate_version = self.main.crate_version()?;
-            Some(format!(
-                "rust-{}-{}",
-                debnormalize(crate_name),
-                semver_pair(&crate_version)
-            ))
-        } else {
-            Some(format!("rust-{}", debnormalize(crate_name)))
-        }
+        self.main.source_name(self.main.semver_suffix())
+    }
+
+    /// Get whether the package names have a semver suffix.
+    pub fn semver_suffix(&self) -> bool {
+        self.main.semver_suffix()
+    }
+
+    /// Set whether the package names have a semver suffix.
+    ///
+    /// This changes the names of the source and binary packages; use
+    /// [`DebcargoSource::semver_suffix_renames`] to find out how.
+    pub fn set_semver_suffix(&mut self, semver_suffix: bool) -> &mut Self {
+        set_preserving(
+            self.toml_section_mut(),
+            "semver_suffix",
+            semver_suffix,
+            SOURCE_KEYS,
+        );
+        self
+    }
+
+    /// Return the package renames that changing the semver suffix setting implies.
+    ///
+    /// # Arguments
+    /// * `semver_suffix` - The new value of the semver suffix setting
+    ///
+    /// # Returns
+    /// The renames, or `None` if the crate name or version is unknown
+    pub fn semver_suffix_renames(&self, semver_suffix: bool) -> Option<PackageRenames> {
+        Some(PackageRenames {
+            old: self.main.package_names(self.main.semver_suffix())?,
+            new: self.main.package_names(semver_suffix)?,
+        })
     }
 
     /// Get the priority.
@@ -344,23 +1172,56 @@ impl DebcargoSource<'_> {
 
     /// Set the priority.
     pub fn set_priority(&mut self, priority: debian_control::Priority) -> &mut Self {
-        self.toml_section_mut()["priority"] = value(priority.to_string());
+        set_preserving(
+            self.toml_section_mut(),
+            "priority",
+            priority.to_string(),
+            SOURCE_KEYS,
+        );
         self
     }
 
     /// Get whether the package build requires root.
     pub fn rules_requires_root(&self) -> bool {
-        self.main
-            .debcargo
-            .get("source")
-            .and_then(|s| s.get("requires_root"))
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false)
+        self.requires_root().is_some_and(|v| v != "no")
     }
 
     /// Set whether the package build requires root.
     pub fn set_rules_requires_root(&mut self, requires_root: bool) -> &mut Self {
-        self.toml_section_mut()["requires_root"] = value(if requires_root { "yes" } else { "no" });
+        self.set_requires_root(Some(if requires_root {
+            "binary-targets"
+        } else {
+            "no"
+        }))
+    }
+
+    /// Get the value of the Rules-Requires-Root field, if set.
+    ///
+    /// debcargo also accepts a boolean, which is mapped to "binary-targets"
+    /// or "no".
+    pub fn requires_root(&self) -> Option<String> {
+        let value = self.main.debcargo.get("source")?.get("requires_root")?;
+        if let Some(value) = value.as_bool() {
+            Some(if value { "binary-targets" } else { "no" }.to_string())
+        } else {
+            value.as_str().map(|s| s.to_string())
+        }
+    }
+
+    /// Set the value of the Rules-Requires-Root field, or remove it if `None`.
+    pub fn set_requires_root(&mut self, requires_root: Option<&str>) -> &mut Self {
+        if let Some(requires_root) = requires_root {
+            set_preserving(
+                self.toml_section_mut(),
+                "requires_root",
+                requires_root,
+                SOURCE_KEYS,
+            );
+        } else if let Some(source) = self.main.debcargo.get_mut("source") {
+            if let Some(source) = source.as_table_mut() {
+                source.remove("requires_root");
+            }
+        }
         self
     }
 
@@ -376,7 +1237,12 @@ impl DebcargoSource<'_> {
 
     /// Set the maintainer.
     pub fn set_maintainer(&mut self, maintainer: &str) -> &mut Self {
-        self.toml_section_mut()["maintainer"] = value(maintainer);
+        set_preserving(
+            self.toml_section_mut(),
+            "maintainer",
+            maintainer,
+            SOURCE_KEYS,
+        );
         self
     }
 
@@ -401,10 +1267,166 @@ impl DebcargoSource<'_> {
         for u in uploaders {
             array.push(u);
         }
-        self.toml_section_mut()["uploaders"] = value(array);
+        set_preserving(self.toml_section_mut(), "uploaders", array, SOURCE_KEYS);
+        self
+    }
+
+    /// Add an uploader, unless there already is an uploader with the same email address.
+    ///
+    /// # Arguments
+    /// * `uploader` - The uploader, e.g. "Jane Doe <jane@example.com>"
+    ///
+    /// # Returns
+    /// Whether the uploader was added
+    pub fn add_uploader(&mut self, uploader: &str) -> bool {
+        let mut uploaders = self.uploaders().unwrap_or_default();
+        let email = identity_email(uploader);
+        if email.is_some() && uploaders.iter().any(|u| identity_email(u) == email) {
+            return false;
+        }
+        uploaders.push(uploader.to_string());
+        self.set_uploaders(uploaders);
+        true
+    }
+
+    /// Ensure that a person is the maintainer or one of the uploaders.
+    ///
+    /// People are matched by email address; the uploader is added if there
+    /// is no match.
+    ///
+    /// # Arguments
+    /// * `uploader` - The person, e.g. "Jane Doe <jane@example.com>"
+    ///
+    /// # Returns
+    /// Whether the uploader was added
+    pub fn ensure_uploader(&mut self, uploader: &str) -> bool {
+        let email = identity_email(uploader);
+        if email.is_some() && identity_email(self.maintainer()) == email {
+            return false;
+        }
+        self.add_uploader(uploader)
+    }
+
+    /// Remove the uploaders with an email address.
+    ///
+    /// The uploaders key is removed if no uploaders are left.
+    ///
+    /// # Arguments
+    /// * `email` - The email address, or an uploader with the email address
+    ///
+    /// # Returns
+    /// Whether any uploaders were removed
+    pub fn remove_uploader(&mut self, email: &str) -> bool {
+        let Some(uploaders) = self.uploaders() else {
+            return false;
+        };
+        let email = identity_email(email);
+        let old_len = uploaders.len();
+        let uploaders = uploaders
+            .into_iter()
+            .filter(|u| identity_email(u) != email)
+            .collect::<Vec<_>>();
+        if uploaders.len() == old_len {
+            return false;
+        }
+        if uploaders.is_empty() {
+            self.toml_section_mut().remove("uploaders");
+        } else {
+            self.set_uploaders(uploaders);
+        }
+        true
+    }
+
+    /// Get the extra build dependencies.
+    pub fn build_depends(&self) -> Option<Vec<String>> {
+        self.main
+            .debcargo
+            .get("source")
+            .and_then(|s| s.get("build_depends"))
+            .and_then(|x| x.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+    }
+
+    /// Set the extra build dependencies, or remove them if `None`.
+    pub fn set_build_depends(&mut self, build_depends: Option<&[&str]>) -> &mut Self {
+        if let Some(build_depends) = build_depends {
+            set_preserving(
+                self.toml_section_mut(),
+                "build_depends",
+                build_depends.iter().copied().collect::<toml_edit::Array>(),
+                SOURCE_KEYS,
+            );
+        } else {
+            self.toml_section_mut().remove("build_depends");
+        }
         self
     }
 
+    /// Edit the extra build dependencies as relations.
+    ///
+    /// The build_depends array is only rewritten if the relations change.
+    ///
+    /// # Returns
+    /// Whether the build dependencies were changed
+    fn edit_build_depends(&mut self, f: impl FnOnce(&mut Relations)) -> bool {
+        let old = self.build_depends().unwrap_or_default();
+        let mut relations: Relations = match old.join(", ").parse() {
+            Ok(relations) => relations,
+            Err(e) => {
+                log::warn!("Unable to parse build_depends in debcargo.toml: {}", e);
+                return false;
+            }
+        };
+        f(&mut relations);
+        let new = relations
+            .entries()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        if new == old {
+            return false;
+        }
+        self.set_build_depends(Some(&new.iter().map(|s| s.as_str()).collect::<Vec<_>>()));
+        true
+    }
+
+    /// Ensure that a build dependency exists.
+    ///
+    /// Existing build dependencies that are implied by the new one are
+    /// replaced by it; nothing is added if an existing build dependency
+    /// already implies it.
+    ///
+    /// # Arguments
+    /// * `dep` - The build dependency, e.g. "libssl-dev (>= 3.0)"
+    ///
+    /// # Returns
+    /// Whether the build dependencies were changed
+    pub fn ensure_build_dep(&mut self, dep: Entry) -> bool {
+        self.edit_build_depends(|relations| crate::relations::ensure_relation(relations, dep))
+    }
+
+    /// Ensure that there is a build dependency on a minimum version of a package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the package
+    /// * `minimum_version` - Minimum version
+    ///
+    /// # Returns
+    /// Whether the build dependencies were changed
+    pub fn ensure_minimum_build_dep_version(
+        &mut self,
+        package: &str,
+        minimum_version: &debversion::Version,
+    ) -> bool {
+        self.edit_build_depends(|relations| {
+            crate::relations::ensure_minimum_version(relations, package, minimum_version);
+        })
+    }
+
     /// Get the extra_lines field as a vector of strings.
     pub fn extra_lines(&self) -> Vec<String> {
         self.main
@@ -427,7 +1449,7 @@ impl DebcargoSource<'_> {
         for line in lines {
             array.push(line);
         }
-        self.toml_section_mut()["extra_lines"] = value(array);
+        set_preserving(self.toml_section_mut(), "extra_lines", array, SOURCE_KEYS);
         self
     }
 
@@ -531,7 +1553,17 @@ pub struct DebcargoBinary<'a> {
     crate_name: String,
     crate_version: semver::Version,
     semver_suffix: bool,
-    features: Option<HashSet<String>>,
+    features: Vec<String>,
+}
+
+/// Properties of the source package that are shared by all its binaries.
+#[derive(Clone)]
+struct BinaryContext {
+    global_summary: Option<String>,
+    global_description: Option<String>,
+    crate_name: String,
+    crate_version: semver::Version,
+    semver_suffix: bool,
 }
 
 impl<'a> DebcargoBinary<'a> {
@@ -539,23 +1571,19 @@ impl<'a> DebcargoBinary<'a> {
         key: String,
         name: String,
         table: &'a mut Table,
-        global_summary: Option<String>,
-        global_description: Option<String>,
-        crate_name: String,
-        crate_version: semver::Version,
-        semver_suffix: bool,
-        features: Option<HashSet<String>>,
+        context: BinaryContext,
+        features: Vec<String>,
     ) -> Self {
         Self {
             key: key.to_owned(),
             name,
             section: format!("packages.{}", key),
             table,
-            global_summary,
-            global_description,
-            crate_name,
-            crate_version,
-            semver_suffix,
+            global_summary: context.global_summary,
+            global_description: context.global_description,
+            crate_name: context.crate_name,
+            crate_version: context.crate_version,
+            semver_suffix: context.semver_suffix,
             features,
         }
     }
@@ -577,59 +1605,192 @@ impl<'a> DebcargoBinary<'a> {
 
     /// Get the package section.
     pub fn section(&self) -> Option<&str> {
-        self.table["section"].as_str()
+        self.table.get("section").and_then(|v| v.as_str())
+    }
+
+    /// Set the package section, or remove it if `None`.
+    pub fn set_section(&mut self, section: Option<&str>) -> &mut Self {
+        if let Some(section) = section {
+            set_preserving(self.table, "section", section, PACKAGE_KEYS);
+        } else {
+            self.table.remove("section");
+        }
+        self
     }
 
     /// Get the package summary.
-    pub fn summary(&self) -> Option<&str> {
+    ///
+    /// Unless the package overrides it, this is the global summary with a
+    /// suffix that depends on the kind of package, as debcargo generates it.
+    pub fn summary(&self) -> Option<String> {
         if let Some(summary) = self.table.get("summary").and_then(|v| v.as_str()) {
-            Some(summary)
+            return Some(summary.to_string());
+        }
+        let summary = self
+            .global_summary
+            .clone()
+            .unwrap_or_else(|| format!("Rust crate \"{}\"", self.crate_name));
+        match self.key.as_str() {
+            "lib" => Some(format!("{} - Rust source code", summary)),
+            key => match key.strip_prefix("lib+") {
+                Some(feature) => Some(format!("{} - feature \"{}\"", summary, feature)),
+                None => Some(summary),
+            },
+        }
+    }
+
+    /// Set the package summary, or remove the override if `None`.
+    pub fn set_summary(&mut self, summary: Option<&str>) -> &mut Self {
+        if let Some(summary) = summary {
+            set_preserving(self.table, "summary", summary, PACKAGE_KEYS);
         } else {
-            self.global_summary.as_deref()
+            self.table.remove("summary");
         }
+        self
     }
 
     /// Get the package long description.
+    ///
+    /// Unless the package overrides it, this is the global description
+    /// followed by a paragraph about the package, as debcargo generates it,
+    /// wrapped to fit in 80 columns.
     pub fn long_description(&self) -> Option<String> {
         if let Some(description) = self.table.get("description").and_then(|v| v.as_str()) {
-            Some(description.to_string())
-        } else if let Some(description) = self.global_description.as_ref() {
-            Some(description.clone())
-        } else {
-            match self.key.as_str() {
-                "lib" => Some(format!("Source code for Debianized Rust crate \"{}\"", self.crate_name)),
-                "bin" => Some("This package contains the source for the Rust mio crate, packaged by debcargo for use with cargo and dh-cargo.".to_string()),
-                _ => None,
+            return Some(description.to_string());
+        }
+        let blurb = match self.key.as_str() {
+            "lib" => format!(
+                "This package contains the source for the Rust {} crate, packaged by debcargo for use with cargo and dh-cargo.",
+                self.crate_name
+            ),
+            "bin" => format!(
+                "This package contains the following binaries built from the Rust crate \"{}\":\n - {}",
+                self.crate_name, self.name
+            ),
+            key => {
+                let feature = key.strip_prefix("lib+")?;
+                let mut blurb = format!(
+                    "This metapackage enables feature \"{}\" for the Rust {} crate, by pulling in any additional dependencies needed by that feature.",
+                    feature, self.crate_name
+                );
+                let provided = self
+                    .features
+                    .iter()
+                    .filter(|f| *f != feature)
+                    .map(|f| format!("\"{}\"", f))
+                    .collect::<Vec<_>>();
+                if !provided.is_empty() {
+                    blurb.push_str(&format!(
+                        "\n\nAdditionally, this package also provides the {} feature{}.",
+                        provided.join(", "),
+                        if provided.len() == 1 { "" } else { "s" }
+                    ));
+                }
+                blurb
             }
+        };
+        let description = match self.global_description.as_ref() {
+            Some(description) => format!("{}\n\n{}", description, blurb),
+            None => blurb,
+        };
+        Some(wrap_description(&description, DESCRIPTION_WIDTH))
+    }
+
+    /// Set the package long description, or remove the override if `None`.
+    pub fn set_long_description(&mut self, description: Option<&str>) -> &mut Self {
+        if let Some(description) = description {
+            set_preserving(self.table, "description", description, PACKAGE_KEYS);
+        } else {
+            self.table.remove("description");
         }
+        self
     }
 
     /// Return the package description.
     pub fn description(&self) -> Option<String> {
         Some(crate::control::format_description(
-            self.summary()?,
+            &self.summary()?,
             self.long_description()?.lines().collect(),
         ))
     }
 
+    fn relations(&self, key: &str) -> Option<Vec<String>> {
+        match self.table.get(key)? {
+            toml_edit::Item::Value(toml_edit::Value::Array(a)) => Some(
+                a.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .collect(),
+            ),
+            item => item.as_str().map(|s| vec![s.to_string()]),
+        }
+    }
+
+    fn set_relations(&mut self, key: &str, relations: Option<&[&str]>) -> &mut Self {
+        if let Some(relations) = relations {
+            set_preserving(
+                self.table,
+                key,
+                relations.iter().copied().collect::<toml_edit::Array>(),
+                PACKAGE_KEYS,
+            );
+        } else {
+            self.table.remove(key);
+        }
+        self
+    }
+
     /// Get the extra dependencies.
-    pub fn depends(&self) -> Option<&str> {
-        self.table["depends"].as_str()
+    pub fn depends(&self) -> Option<Vec<String>> {
+        self.relations("depends")
+    }
+
+    /// Set the extra dependencies, or remove them if `None`.
+    pub fn set_depends(&mut self, depends: Option<&[&str]>) -> &mut Self {
+        self.set_relations("depends", depends)
     }
 
     /// Get the extra recommends.
-    pub fn recommends(&self) -> Option<&str> {
-        self.table["recommends"].as_str()
+    pub fn recommends(&self) -> Option<Vec<String>> {
+        self.relations("recommends")
+    }
+
+    /// Set the extra recommends, or remove them if `None`.
+    pub fn set_recommends(&mut self, recommends: Option<&[&str]>) -> &mut Self {
+        self.set_relations("recommends", recommends)
     }
 
     /// Get the extra suggests.
-    pub fn suggests(&self) -> Option<&str> {
-        self.table["suggests"].as_str()
+    pub fn suggests(&self) -> Option<Vec<String>> {
+        self.relations("suggests")
+    }
+
+    /// Set the extra suggests, or remove them if `None`.
+    pub fn set_suggests(&mut self, suggests: Option<&[&str]>) -> &mut Self {
+        self.set_relations("suggests", suggests)
+    }
+
+    /// Get the extra provides.
+    ///
+    /// These are in addition to the provides that debcargo generates itself.
+    pub fn provides(&self) -> Option<Vec<String>> {
+        self.relations("provides")
+    }
+
+    /// Set the extra provides, or remove them if `None`.
+    pub fn set_provides(&mut self, provides: Option<&[&str]>) -> &mut Self {
+        self.set_relations("provides", provides)
     }
 
-    #[allow(dead_code)]
-    fn default_provides(&self) -> Option<String> {
-        let mut ret = HashSet::new();
+    /// Return the packages that debcargo makes this package provide.
+    ///
+    /// These are the names of the package with other version suffixes, and
+    /// the packages for the features that do not get a package of their own.
+    fn default_provides(&self) -> Vec<String> {
+        if self.key != "lib" && !self.key.starts_with("lib+") {
+            return vec![];
+        }
+        let mut ret = BTreeSet::new();
         let semver_suffix = self.semver_suffix;
         let semver = &self.crate_version;
 
@@ -644,36 +1805,278 @@ impl<'a> DebcargoBinary<'a> {
             "-{}.{}.{}",
             semver.major, semver.minor, semver.patch
         ));
+        let mut feature_suffixes = self
+            .features
+            .iter()
+            .map(|f| format!("+{}", debnormalize(f)))
+            .collect::<Vec<_>>();
+        if self.key == "lib" {
+            feature_suffixes.push("".to_string());
+        }
         for ver_suffix in suffixes {
-            let mut feature_suffixes = HashSet::new();
-            feature_suffixes.insert("".to_string());
-            feature_suffixes.insert("+default".to_string());
-            feature_suffixes.extend(
-                self.features
-                    .as_ref()
-                    .map(|k| k.iter().map(|k| format!("+{}", k)).collect::<HashSet<_>>())
-                    .unwrap_or_default(),
-            );
-            for feature_suffix in feature_suffixes {
+            for feature_suffix in &feature_suffixes {
                 ret.insert(debcargo_binary_name(
                     &self.crate_name,
-                    &format!("{}{}", ver_suffix, &feature_suffix),
+                    &format!("{}{}", ver_suffix, feature_suffix),
                 ));
             }
         }
         ret.remove(self.name());
-        if ret.is_empty() {
-            None
+        ret.into_iter().collect()
+    }
+}
+
+/// Maximum width of the lines in a long description, excluding the leading space.
+const DESCRIPTION_WIDTH: usize = 79;
+
+/// Wrap the text of a long description.
+///
+/// Lines that start with whitespace or a list marker are kept as they
+/// are; other lines are reflowed per paragraph.
+///
+/// # Arguments
+/// * `text` - The description
+/// * `width` - Maximum width of a line
+fn wrap_description(text: &str, width: usize) -> String {
+    let mut ret = vec![];
+    let mut words: Vec<&str> = vec![];
+    let flush = |words: &mut Vec<&str>, ret: &mut Vec<String>| {
+        let mut line = String::new();
+        for word in words.drain(..) {
+            if !line.is_empty() && line.len() + 1 + word.len() > width {
+                ret.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        if !line.is_empty() {
+            ret.push(line);
+        }
+    };
+    for line in text.lines() {
+        if line.trim().is_empty()
+            || line.starts_with(char::is_whitespace)
+            || line.starts_with(['-', '*'])
+        {
+            flush(&mut words, &mut ret);
+            ret.push(if line.trim().is_empty() { "" } else { line }.to_string());
         } else {
-            Some(format!(
-                "\n{}",
-                &ret.iter()
-                    .map(|s| format!("{} (= ${{binary:Version}})", s))
-                    .collect::<Vec<_>>()
-                    .join(",\n ")
-            ))
+            words.extend(line.split_whitespace());
+        }
+    }
+    flush(&mut words, &mut ret);
+    ret.join("\n")
+}
+
+/// Return the normalized email address of a person, e.g. "Jane Doe <jane@example.com>".
+fn identity_email(identity: &str) -> Option<String> {
+    crate::parseaddr(identity)
+        .and_then(|(_, email)| email)
+        .map(|email| email.to_lowercase())
+}
+
+/// Find the version of the package in a Cargo.toml file.
+///
+/// # Arguments
+/// * `cargo` - The Cargo.toml file of the package
+/// * `workspace` - The root Cargo.toml file of the workspace, for inherited versions
+fn package_version<'a>(
+    cargo: &'a DocumentMut,
+    workspace: Option<&'a DocumentMut>,
+) -> Option<&'a str> {
+    let version = cargo.get("package")?.get("version")?;
+    if version.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+        workspace?
+            .get("workspace")?
+            .get("package")?
+            .get("version")?
+            .as_str()
+    } else {
+        version.as_str()
+    }
+}
+
+/// Find the features of a crate.
+///
+/// # Arguments
+/// * `cargo` - The Cargo.toml file of the crate
+///
+/// # Returns
+/// A map from feature name to the other features and the dependencies it enables
+fn cargo_features(cargo: &DocumentMut) -> BTreeMap<String, (Vec<String>, BTreeSet<String>)> {
+    let mut optional = BTreeSet::new();
+    let target_dependencies = cargo
+        .get("target")
+        .and_then(|t| t.as_table_like())
+        .into_iter()
+        .flat_map(|t| t.iter())
+        .filter_map(|(_, t)| t.get("dependencies"));
+    for dependencies in cargo
+        .get("dependencies")
+        .into_iter()
+        .chain(target_dependencies)
+    {
+        if let Some(dependencies) = dependencies.as_table_like() {
+            optional.extend(
+                dependencies
+                    .iter()
+                    .filter(|(_, d)| d.get("optional").and_then(|o| o.as_bool()) == Some(true))
+                    .map(|(name, _)| name.to_string()),
+            );
         }
     }
+
+    let mut ret = BTreeMap::new();
+    let mut explicit = HashSet::new();
+    let table = cargo.get("features").and_then(|f| f.as_table_like());
+    for (feature, entries) in table.iter().flat_map(|t| t.iter()) {
+        let mut enabled = vec![];
+        let mut dependencies = BTreeSet::new();
+        for entry in entries
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+        {
+            if let Some(dependency) = entry.strip_prefix("dep:") {
+                explicit.insert(dependency.to_string());
+                dependencies.insert(dependency.to_string());
+            } else if let Some((dependency, _)) = entry.split_once('/') {
+                // Weak dependency features do not enable the dependency
+                if !dependency.ends_with('?') {
+                    dependencies.insert(dependency.to_string());
+                }
+            } else if optional.contains(entry) && !table.is_some_and(|t| t.contains_key(entry)) {
+                dependencies.insert(entry.to_string());
+            } else {
+                enabled.push(entry.to_string());
+            }
+        }
+        ret.insert(feature.to_string(), (enabled, dependencies));
+    }
+
+    // Optional dependencies that are not referred to with "dep:" are implicit features
+    for dependency in optional {
+        if !explicit.contains(&dependency) && !ret.contains_key(&dependency) {
+            ret.insert(dependency.clone(), (vec![], BTreeSet::from([dependency])));
+        }
+    }
+    ret
+}
+
+/// Find the directory of the workspace member that a debcargo.toml file is for.
+///
+/// This is the `crate_src_path` set in debcargo.toml, or else the only
+/// member of the workspace.
+fn workspace_member(debcargo: &DocumentMut, cargo: &DocumentMut) -> Option<PathBuf> {
+    if let Some(crate_src_path) = debcargo.get("crate_src_path").and_then(|p| p.as_str()) {
+        return Some(PathBuf::from(crate_src_path));
+    }
+    let members = cargo.get("workspace")?.get("members")?.as_array()?;
+    match members.iter().collect::<Vec<_>>().as_slice() {
+        [member] => member
+            .as_str()
+            .filter(|m| !m.contains('*'))
+            .map(PathBuf::from),
+        _ => None,
+    }
+}
+
+/// Get a table from a parent table, adding an implicit table if it does not exist.
+///
+/// Implicit tables are not written out while they are empty.
+/// Set a value in a table, preserving the formatting of the file.
+///
+/// An existing value keeps the comments and whitespace around it, and an
+/// array that had one element per line keeps that layout. A new key is
+/// placed according to `order`; see [`place_key`].
+fn set_preserving(
+    table: &mut Table,
+    key: &str,
+    new: impl Into<toml_edit::Value>,
+    order: &[(&str, ValueType)],
+) {
+    let mut new = new.into();
+    if let Some(item) = table.get_mut(key) {
+        if let Some(old) = item.as_value() {
+            *new.decor_mut() = old.decor().clone();
+            if let (Some(old), Some(new)) = (old.as_array(), new.as_array_mut()) {
+                copy_array_layout(old, new);
+            }
+        }
+        // Replace the value in place, so that the key keeps its comments
+        *item = toml_edit::Item::Value(new);
+    } else {
+        table.insert(key, toml_edit::Item::Value(new));
+        place_key(table, key, order);
+    }
+}
+
+/// Lay out the elements of an array one per line, if the old array was.
+fn copy_array_layout(old: &toml_edit::Array, new: &mut toml_edit::Array) {
+    let Some(prefix) = old
+        .get(0)
+        .and_then(|first| first.decor().prefix())
+        .and_then(|prefix| prefix.as_str())
+    else {
+        return;
+    };
+    // Leave out any comments before the first element
+    let Some(indent) = prefix.rfind('\n').map(|i| &prefix[i..]) else {
+        return;
+    };
+    let indent = indent.to_string();
+    for value in new.iter_mut() {
+        value.decor_mut().set_prefix(indent.clone());
+    }
+    new.set_trailing_comma(old.trailing_comma());
+    new.set_trailing(old.trailing().as_str().unwrap_or_default().to_string());
+}
+
+/// Move a newly added key to its place in the canonical order.
+///
+/// The canonical order is the order of the keys in `order`, which follows
+/// debcargo's example configuration. The key is placed after the last key
+/// that comes before it in that order, or else before the first key that
+/// comes after it; other keys are left where they are. Keys that are not
+/// in `order` stay at the end.
+fn place_key(table: &mut Table, key: &str, order: &[(&str, ValueType)]) {
+    let rank = |k: &str| order.iter().position(|(o, _)| *o == k);
+    let Some(key_rank) = rank(key) else {
+        return;
+    };
+    let mut keys = table
+        .iter()
+        .map(|(k, _)| k.to_string())
+        .filter(|k| k != key)
+        .collect::<Vec<_>>();
+    let index = match keys
+        .iter()
+        .rposition(|k| rank(k).is_some_and(|r| r < key_rank))
+    {
+        Some(i) => i + 1,
+        None => keys
+            .iter()
+            .position(|k| rank(k).is_some_and(|r| r > key_rank))
+            .unwrap_or(keys.len()),
+    };
+    keys.insert(index, key.to_string());
+    let position = |k: &toml_edit::Key| keys.iter().position(|o| o == k.get());
+    table.sort_values_by(|k1, _, k2, _| position(k1).cmp(&position(k2)));
+}
+
+fn implicit_table<'a>(parent: &'a mut Table, key: &str) -> &'a mut Table {
+    parent
+        .entry(key)
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            toml_edit::Item::Table(table)
+        })
+        .as_table_mut()
+        .expect("expected a table")
 }
 
 fn debnormalize(s: &str) -> String {
@@ -684,13 +2087,54 @@ fn semver_pair(s: &semver::Version) -> String {
     format!("{}.{}", s.major, s.minor)
 }
 
-fn debcargo_binary_name(crate_name: &str, suffix: &str) -> String {
+pub(crate) fn debcargo_binary_name(crate_name: &str, suffix: &str) -> String {
     format!("librust-{}{}-dev", debnormalize(crate_name), suffix)
 }
 
+/// Mangle a crate version into the upstream version that debcargo uses.
+///
+/// The pre-release is separated by a `~` rather than a `-`, so that it
+/// sorts before the release in Debian; build metadata is kept after a `+`.
+///
+/// # Arguments
+/// * `version` - The crate version, e.g. "1.0.0-rc.1+build.5"
+///
+/// # Returns
+/// The upstream version, e.g. "1.0.0~rc.1+build.5"
+pub fn mangle_crate_version(version: &semver::Version) -> String {
+    let mut ret = format!("{}.{}.{}", version.major, version.minor, version.patch);
+    if !version.pre.is_empty() {
+        ret.push('~');
+        ret.push_str(version.pre.as_str());
+    }
+    if !version.build.is_empty() {
+        ret.push('+');
+        ret.push_str(version.build.as_str());
+    }
+    ret
+}
+
 /// Unmangle a debcargo version.
+///
+/// This is the inverse of [`mangle_crate_version`]: the first `~` before any
+/// build metadata starts the pre-release.
+///
+/// # Arguments
+/// * `version` - The upstream version, e.g. "1.0.0~rc.1+build.5"
+///
+/// # Returns
+/// The crate version, e.g. "1.0.0-rc.1+build.5"
 pub fn unmangle_debcargo_version(version: &str) -> String {
-    version.replace("~", "-")
+    let (release, build) = match version.split_once('+') {
+        Some((release, build)) => (release, Some(build)),
+        None => (version, None),
+    };
+    let mut ret = release.replacen('~', "-", 1);
+    if let Some(build) = build {
+        ret.push('+');
+        ret.push_str(build);
+    }
+    ret
 }
 
 #[cfg(test)]
@@ -712,6 +2156,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mangle_crate_version() {
+        for (crate_version, upstream_version) in [
+            ("1.2.3", "1.2.3"),
+            ("1.0.0-alpha", "1.0.0~alpha"),
+            ("1.0.0-rc.1", "1.0.0~rc.1"),
+            ("0.1.0-beta-2", "0.1.0~beta-2"),
+            ("1.0.0+build.5", "1.0.0+build.5"),
+            ("1.0.0-rc.1+20240101", "1.0.0~rc.1+20240101"),
+        ] {
+            let version = crate_version.parse().unwrap();
+            assert_eq!(upstream_version, super::mangle_crate_version(&version));
+            assert_eq!(
+                crate_version,
+                super::unmangle_debcargo_version(upstream_version)
+            );
+        }
+        assert!(
+            format!(
+                "{}-1",
+                super::mangle_crate_version(&"1.0.0-rc.1".parse().unwrap())
+            )
+            .parse::<debversion::Version>()
+            .unwrap()
+                < "1.0.0-1".parse::<debversion::Version>().unwrap()
+        );
+    }
+
     #[test]
     fn test_semver_pair() {
         assert_eq!(super::semver_pair(&"1.2.3".parse().unwrap()), "1.2");
@@ -758,6 +2230,27 @@ mod tests {
         assert_eq!(editor.source().homepage(), Some("https://example.com"));
     }
 
+    #[test]
+    fn test_requires_root() {
+        let mut editor = super::DebcargoEditor::new();
+        assert_eq!(editor.source().requires_root(), None);
+        editor.debcargo["source"]["requires_root"] = toml_edit::value(false);
+        assert_eq!(editor.source().requires_root().as_deref(), Some("no"));
+        assert!(!editor.source().rules_requires_root());
+        editor.source().set_rules_requires_root(true);
+        assert_eq!(
+            editor.source().requires_root().as_deref(),
+            Some("binary-targets")
+        );
+        assert!(editor.source().rules_requires_root());
+        assert_eq!(
+            editor.debcargo.to_string(),
+            "[source]\nrequires_root = \"binary-targets\"\n"
+        );
+        editor.source().set_requires_root(None);
+        assert_eq!(editor.source().requires_root(), None);
+    }
+
     #[test]
     fn test_extra_lines_manipulation() {
         let mut editor = super::DebcargoEditor::new();
@@ -981,4 +2474,644 @@ mod tests {
         // Test getting non-existent VCS type
         assert_eq!(source.get_vcs_url("Hg"), None);
     }
+
+    #[test]
+    fn test_from_directory_errors() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(td.path().join("debian/debcargo.toml"), "overlay = \".\"\n").unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"1.x\"\n",
+        )
+        .unwrap();
+        let e = super::DebcargoEditor::from_directory(td.path())
+            .err()
+            .unwrap();
+        assert!(matches!(e, crate::error::Error::InvalidVersion { .. }));
+        assert!(e.is_package_error());
+
+        std::fs::write(td.path().join("debian/debcargo.toml"), "overlay = ").unwrap();
+        let e = super::DebcargoEditor::from_directory(td.path())
+            .err()
+            .unwrap();
+        assert!(matches!(e, crate::error::Error::Parse { .. }));
+
+        std::fs::remove_file(td.path().join("debian/debcargo.toml")).unwrap();
+        let e = super::DebcargoEditor::from_directory(td.path())
+            .err()
+            .unwrap();
+        assert!(matches!(e, crate::error::Error::Io(_)));
+    }
+
+    #[test]
+    fn test_from_directory() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(td.path().join("debian/debcargo.toml"), "").unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"1.2.3\"\n",
+        )
+        .unwrap();
+        let editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        assert_eq!(Some("foo"), editor.crate_name());
+        assert_eq!(Some(semver::Version::new(1, 2, 3)), editor.crate_version());
+    }
+
+    #[test]
+    fn test_from_directory_workspace() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::create_dir(td.path().join("foo")).unwrap();
+        std::fs::create_dir(td.path().join("foo-macros")).unwrap();
+        std::fs::write(td.path().join("debian/debcargo.toml"), "overlay = \".\"\n").unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"foo\", \"foo-macros\"]\n\n[workspace.package]\nversion = \"1.2.3\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("foo/Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("foo-macros/Cargo.toml"),
+            "[package]\nname = \"foo-macros\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        // Ambiguous without crate_src_path
+        let e = super::DebcargoEditor::from_directory(td.path())
+            .err()
+            .unwrap();
+        assert!(matches!(e, crate::error::Error::Parse { .. }));
+
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            "overlay = \".\"\ncrate_src_path = \"foo\"\n",
+        )
+        .unwrap();
+        let editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        assert_eq!(Some("foo"), editor.crate_name());
+        assert_eq!(Some(semver::Version::new(1, 2, 3)), editor.crate_version());
+
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            "overlay = \".\"\ncrate_src_path = \"foo-macros\"\n",
+        )
+        .unwrap();
+        let editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        assert_eq!(Some("foo-macros"), editor.crate_name());
+        assert_eq!(Some(semver::Version::new(0, 1, 0)), editor.crate_version());
+    }
+
+    #[test]
+    fn test_semver_suffix_renames() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(td.path().join("debian/debcargo.toml"), "overlay = \".\"\n").unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"foo_bar\"\nversion = \"1.2.3\"\n",
+        )
+        .unwrap();
+        let mut editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        let mut source = editor.source();
+        assert!(!source.semver_suffix());
+        assert!(source.semver_suffix_renames(false).unwrap().is_empty());
+        let renames = source.semver_suffix_renames(true).unwrap();
+        assert_eq!(
+            super::PackageRenames {
+                old: super::PackageNames {
+                    source: "rust-foo-bar".to_string(),
+                    binaries: vec!["foo_bar".to_string(), "librust-foo-bar-dev".to_string()],
+                },
+                new: super::PackageNames {
+                    source: "rust-foo-bar-1.2".to_string(),
+                    binaries: vec!["librust-foo-bar-1.2-dev".to_string()],
+                },
+            },
+            renames
+        );
+        source.set_semver_suffix(true);
+        assert!(source.semver_suffix());
+        assert_eq!(Some("rust-foo-bar-1.2".to_string()), source.name());
+        assert_eq!(
+            vec!["librust-foo-bar-1.2-dev"],
+            editor
+                .binaries()
+                .map(|b| b.name().to_string())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_feature_packages() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            "overlay = \".\"\nbin = false\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            r#"[package]
+name = "foo"
+version = "1.2.3"
+
+[dependencies]
+serde = { version = "1", optional = true }
+log = { version = "0.4", optional = true }
+
+[features]
+default = ["std"]
+std = []
+full = ["std", "dep:serde"]
+derive = ["dep:serde", "serde?/derive"]
+"#,
+        )
+        .unwrap();
+        let mut editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        let packages = editor.feature_packages();
+        assert_eq!(
+            vec!["default", "derive", "full", "log", "std"],
+            packages
+                .iter()
+                .map(|p| p.feature.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            super::FeaturePackage {
+                feature: "default".to_string(),
+                name: "librust-foo+default-dev".to_string(),
+                features: vec!["std".to_string()],
+                dependencies: vec![],
+                provided_by: Some("librust-foo-dev".to_string()),
+            },
+            packages[0]
+        );
+        assert_eq!(vec!["serde".to_string()], packages[1].dependencies);
+        assert_eq!(None, packages[1].provided_by);
+        assert_eq!(vec!["std".to_string()], packages[2].features);
+        assert_eq!(vec!["serde".to_string()], packages[2].dependencies);
+        assert_eq!(None, packages[3].provided_by);
+        assert_eq!(Some("librust-foo-dev".to_string()), packages[4].provided_by);
+
+        let mut names = editor
+            .binaries()
+            .map(|b| b.name().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            vec![
+                "librust-foo+derive-dev",
+                "librust-foo+full-dev",
+                "librust-foo+log-dev",
+                "librust-foo-dev"
+            ],
+            names
+        );
+
+        editor.debcargo["collapse_features"] = toml_edit::value(true);
+        assert!(editor
+            .feature_packages()
+            .iter()
+            .all(|p| p.provided_by.as_deref() == Some("librust-foo-dev")));
+        assert_eq!(1, editor.binaries().count());
+    }
+
+    #[test]
+    fn test_validate() {
+        let text = r#"overlay = "."
+bin = "yes"
+unknown = 1
+
+[source]
+policy = "4.6.0"
+section = "rust"
+
+[packages.lib]
+depends = ["libfoo-dev"]
+recommends = true
+
+[packages.doc]
+summary = "Docs"
+"#;
+        let editor = super::DebcargoEditor::from(text.parse::<super::DocumentMut>().unwrap());
+        let diagnostics = editor.validate();
+        assert_eq!(
+            vec![
+                "bin: expected boolean, found string",
+                "Unknown key unknown",
+                "source.policy is deprecated, use standards-version instead",
+                "packages.lib.recommends: expected string or array, found boolean",
+                "Unknown key packages.doc",
+            ],
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("\"yes\"", &text[diagnostics[0].span.clone().unwrap()]);
+        assert_eq!("unknown", &text[diagnostics[1].span.clone().unwrap()]);
+        assert_eq!(
+            super::DiagnosticKind::Deprecated {
+                replacement: Some("standards-version".to_string())
+            },
+            diagnostics[2].kind
+        );
+
+        let editor = super::DebcargoEditor::from(
+            "overlay = \".\"\n[source]\nsection = \"rust\"\n"
+                .parse::<super::DocumentMut>()
+                .unwrap(),
+        );
+        assert_eq!(Vec::<super::Diagnostic>::new(), editor.validate());
+    }
+
+    #[test]
+    fn test_render_control() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            "overlay = \".\"\n\n[packages.bin]\nsuggests = [\"foo-doc\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            r#"[package]
+name = "foo"
+version = "1.2.3"
+description = "Does foo"
+
+[dependencies]
+serde = { version = "1", optional = true }
+
+[features]
+default = ["std"]
+std = []
+derive = ["std", "dep:serde"]
+"#,
+        )
+        .unwrap();
+        let mut editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        let control = editor.render_control().unwrap();
+        let source = control.source().unwrap();
+        assert_eq!(Some("rust-foo".to_string()), source.name());
+        assert_eq!(
+            Some("foo".to_string()),
+            source.as_deb822().get("X-Cargo-Crate")
+        );
+        let binaries = control.binaries().collect::<Vec<_>>();
+        assert_eq!(
+            vec!["foo", "librust-foo+derive-dev", "librust-foo-dev"],
+            binaries
+                .iter()
+                .map(|b| b.name().unwrap())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some("foo-doc".to_string()),
+            binaries[0].as_deb822().get("Suggests")
+        );
+        assert_eq!(
+            Some(
+                "${misc:Depends}, librust-foo-dev (= ${binary:Version}), librust-serde-dev"
+                    .to_string()
+            ),
+            binaries[1].as_deb822().get("Depends")
+        );
+        assert_eq!(
+            Some("librust-foo-1+derive-dev (= ${binary:Version}), librust-foo-1.2+derive-dev (= ${binary:Version}), librust-foo-1.2.3+derive-dev (= ${binary:Version})".to_string()),
+            binaries[1].as_deb822().get("Provides")
+        );
+        let provides = binaries[2].as_deb822().get("Provides").unwrap();
+        assert!(provides.contains("librust-foo+default-dev (= ${binary:Version})"));
+        assert!(provides.contains("librust-foo-1.2.3+std-dev (= ${binary:Version})"));
+        assert!(!provides.contains("derive"));
+    }
+
+    #[test]
+    fn test_ensure_build_dep() {
+        let mut editor =
+            super::DebcargoEditor::from("overlay = \".\"\n".parse::<super::DocumentMut>().unwrap());
+        let mut source = editor.source();
+        assert_eq!(None, source.build_depends());
+        assert!(source.ensure_build_dep("libssl-dev (>= 1.1)".parse().unwrap()));
+        assert!(!source.ensure_build_dep("libssl-dev".parse().unwrap()));
+        assert!(source.ensure_build_dep("pkgconf".parse().unwrap()));
+        assert!(source.ensure_build_dep("libssl-dev (>= 3.0)".parse().unwrap()));
+        assert_eq!(
+            Some(vec![
+                "libssl-dev (>= 3.0)".to_string(),
+                "pkgconf".to_string()
+            ]),
+            source.build_depends()
+        );
+        assert!(source.ensure_minimum_build_dep_version("libssl-dev", &"3.1".parse().unwrap()));
+        assert!(!source.ensure_minimum_build_dep_version("libssl-dev", &"2.0".parse().unwrap()));
+        assert_eq!(
+            "overlay = \".\"\n\n[source]\nbuild_depends = [\"libssl-dev (>= 3.1)\", \"pkgconf\"]\n",
+            editor.debcargo.to_string()
+        );
+    }
+
+    #[test]
+    fn test_overlay() {
+        use crate::editor::Editor;
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/patches")).unwrap();
+        std::fs::write(td.path().join("debian/debcargo.toml"), "overlay = \".\"\n").unwrap();
+        std::fs::write(td.path().join("debian/patches/series"), "fix.patch\n").unwrap();
+        std::fs::write(
+            td.path().join("debian/copyright"),
+            "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/\n",
+        )
+        .unwrap();
+        let editor = super::DebcargoEditor::open(&td.path().join("debian/debcargo.toml")).unwrap();
+        assert_eq!(Some(td.path().join("debian/.")), editor.overlay_dir());
+        assert_eq!(
+            vec![
+                std::path::PathBuf::from("copyright"),
+                std::path::PathBuf::from("patches/series")
+            ],
+            editor.overlay_files().unwrap()
+        );
+
+        let copyright = editor
+            .edit_overlay_file::<deb822_lossless::Deb822>(std::path::Path::new("copyright"), false)
+            .unwrap();
+        copyright
+            .paragraphs()
+            .next()
+            .unwrap()
+            .set("Upstream-Name", "foo");
+        assert_eq!(
+            vec![td.path().join("debian/./copyright")],
+            copyright.commit().unwrap()
+        );
+        assert!(std::fs::read_to_string(td.path().join("debian/copyright"))
+            .unwrap()
+            .contains("Upstream-Name: foo"));
+
+        assert!(editor
+            .edit_overlay_file::<deb822_lossless::Deb822>(
+                std::path::Path::new("/etc/passwd"),
+                false
+            )
+            .is_err());
+        assert!(super::DebcargoEditor::new()
+            .overlay_files()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_uploaders() {
+        let mut editor = super::DebcargoEditor::from(
+            "[source]\nuploaders = [\"Jane Doe <jane@example.com>\"]\n"
+                .parse::<super::DocumentMut>()
+                .unwrap(),
+        );
+        let mut source = editor.source();
+        assert!(!source.add_uploader("J. Doe <Jane@Example.com>"));
+        assert!(source.add_uploader("John Smith <john@example.com>"));
+        assert!(!source.ensure_uploader("john@example.com"));
+        assert!(!source.ensure_uploader("Rust team <pkg-rust-maintainers@alioth-lists.debian.net>"));
+        assert!(source.ensure_uploader("Alice <alice@example.com>"));
+        assert_eq!(
+            Some(vec![
+                "Jane Doe <jane@example.com>".to_string(),
+                "John Smith <john@example.com>".to_string(),
+                "Alice <alice@example.com>".to_string(),
+            ]),
+            source.uploaders()
+        );
+        assert!(source.remove_uploader("jane@example.com"));
+        assert!(!source.remove_uploader("bob@example.com"));
+        assert!(source.remove_uploader("John <john@example.com>"));
+        assert!(source.remove_uploader("alice@example.com"));
+        assert_eq!(None, source.uploaders());
+        assert_eq!("[source]\n", editor.debcargo.to_string());
+    }
+
+    #[test]
+    fn test_descriptions() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(td.path().join("debian/debcargo.toml"), "overlay = \".\"\n").unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            r#"[package]
+name = "foo"
+version = "1.2.3"
+description = "Fast foo parsing."
+
+[dependencies]
+serde = { version = "1", optional = true }
+
+[features]
+derive = ["dep:serde"]
+serde-derive = ["derive"]
+"#,
+        )
+        .unwrap();
+        let mut editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        let mut binaries = editor.binaries().collect::<Vec<_>>();
+        binaries.sort_by(|a, b| a.name().cmp(b.name()));
+        assert_eq!(Some("Fast foo parsing".to_string()), binaries[0].summary());
+        assert_eq!(
+            Some(
+                r#"Fast foo parsing.
+
+This package contains the following binaries built from the Rust crate "foo":
+ - foo"#
+                    .to_string()
+            ),
+            binaries[0].long_description()
+        );
+        assert_eq!(
+            Some("Fast foo parsing - feature \"derive\"".to_string()),
+            binaries[1].summary()
+        );
+        assert_eq!(
+            Some(
+                r#"Fast foo parsing.
+
+This metapackage enables feature "derive" for the Rust foo crate, by pulling in
+any additional dependencies needed by that feature.
+
+Additionally, this package also provides the "serde-derive" feature."#
+                    .to_string()
+            ),
+            binaries[1].long_description()
+        );
+        assert_eq!(
+            Some("Fast foo parsing - Rust source code".to_string()),
+            binaries[2].summary()
+        );
+        assert_eq!(
+            Some(
+                r#"Fast foo parsing.
+
+This package contains the source for the Rust foo crate, packaged by debcargo
+for use with cargo and dh-cargo."#
+                    .to_string()
+            ),
+            binaries[2].long_description()
+        );
+
+        binaries[2]
+            .set_summary(Some("Foo parser"))
+            .set_long_description(Some("Parses foo."));
+        assert_eq!(Some("Foo parser".to_string()), binaries[2].summary());
+        assert_eq!(
+            Some("Foo parser\n Parses foo.\n".to_string()),
+            binaries[2].description()
+        );
+        std::mem::drop(binaries);
+
+        editor.set_summary(Some("Foo library"));
+        let binaries = editor.binaries().collect::<Vec<_>>();
+        assert!(binaries
+            .iter()
+            .filter(|b| b.name() == "foo")
+            .all(|b| b.summary().as_deref() == Some("Foo library")));
+    }
+
+    #[test]
+    fn test_binary_relations() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            "overlay = \".\"\n\n[packages.lib]\ndepends = [\"libfoo-dev\"]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"foo_bar\"\nversion = \"1.2.3\"\n",
+        )
+        .unwrap();
+        let mut editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        let mut binaries = editor.binaries().collect::<Vec<_>>();
+        binaries.sort_by(|a, b| a.name().cmp(b.name()));
+        assert_eq!(
+            vec!["foo_bar", "librust-foo-bar-dev"],
+            binaries.iter().map(|b| b.name()).collect::<Vec<_>>()
+        );
+        assert_eq!(None, binaries[0].depends());
+        let lib = &mut binaries[1];
+        assert_eq!(Some(vec!["libfoo-dev".to_string()]), lib.depends());
+        lib.set_depends(None)
+            .set_recommends(Some(&["libbar-dev"]))
+            .set_provides(Some(&["librust-foo-bar+std-dev"]));
+        assert_eq!(None, lib.suggests());
+        assert_eq!(Some(vec!["libbar-dev".to_string()]), lib.recommends());
+        binaries[0].set_suggests(Some(&["foo-doc", "foo-examples"]));
+        std::mem::drop(binaries);
+        assert!(editor.commit().unwrap());
+        assert_eq!(
+            r#"overlay = "."
+
+[packages.lib]
+recommends = ["libbar-dev"]
+provides = ["librust-foo-bar+std-dev"]
+
+[packages.bin]
+suggests = ["foo-doc", "foo-examples"]
+"#,
+            std::fs::read_to_string(td.path().join("debian/debcargo.toml")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_commit_without_path() {
+        let editor = super::DebcargoEditor::new();
+        assert!(editor.commit().is_err());
+    }
+
+    #[test]
+    fn test_preserve_formatting() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/debcargo.toml"),
+            r#"# Configuration for foo
+overlay = "."
+
+[source]
+# Keep in sync with upstream
+homepage = "https://example.com"  # upstream homepage
+section = "rust"
+uploaders = [
+    "Jane Doe <jane@example.com>",
+]
+
+[packages.lib]
+# Extra dependencies
+depends = ["libfoo-dev"]
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"foo\"\nversion = \"1.2.3\"\n",
+        )
+        .unwrap();
+        let mut editor = super::DebcargoEditor::from_directory(td.path()).unwrap();
+        let mut source = editor.source();
+        source.set_homepage("https://example.org");
+        source.set_vcs_git("https://example.org/foo.git");
+        source.set_standards_version("4.7.0");
+        assert!(source.add_uploader("John Doe <john@example.com>"));
+        source.set_extra_lines(vec!["Testsuite: autopkgtest-pkg-rust".to_string()]);
+        let mut binaries = editor.binaries().collect::<Vec<_>>();
+        let lib = binaries
+            .iter_mut()
+            .find(|b| b.name() == "librust-foo-dev")
+            .unwrap();
+        lib.set_summary(Some("Foo library"));
+        lib.set_depends(Some(&["libfoo-dev (>= 1.0)"]));
+        std::mem::drop(binaries);
+        assert_eq!(
+            r#"# Configuration for foo
+overlay = "."
+
+[source]
+standards-version = "4.7.0"
+# Keep in sync with upstream
+homepage = "https://example.org"  # upstream homepage
+vcs_git = "https://example.org/foo.git"
+section = "rust"
+uploaders = [
+    "Jane Doe <jane@example.com>",
+    "John Doe <john@example.com>",
+]
+extra_lines = ["Testsuite: autopkgtest-pkg-rust"]
+
+[packages.lib]
+summary = "Foo library"
+# Extra dependencies
+depends = ["libfoo-dev (>= 1.0)"]
+"#,
+            editor.debcargo.to_string()
+        );
+
+        // A new [source] table goes before the [packages.*] tables
+        let mut editor = super::DebcargoEditor::from(
+            "overlay = \".\"\n\n[packages.lib]\ndepends = [\"libfoo-dev\"]\n"
+                .parse::<super::DocumentMut>()
+                .unwrap(),
+        );
+        editor.source().set_section("rust");
+        assert_eq!(
+            "overlay = \".\"\n\n[source]\nsection = \"rust\"\n\n[packages.lib]\ndepends = [\"libfoo-dev\"]\n",
+            editor.debcargo.to_string()
+        );
+    }
 }
@@ -0,0 +1,209 @@
+//! Client for the debci API, which exposes the autopkgtest results from ci.debian.net.
+use serde::Deserialize;
+
+/// Base URL of the Debian instance of debci.
+pub const DEFAULT_URL: &str = "https://ci.debian.net/";
+
+/// Outcome of a single debci run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// All tests passed
+    Pass,
+    /// At least one test failed
+    Fail,
+    /// The tests passed, but some were skipped or flaky
+    Neutral,
+    /// The tests could not be run because of a temporary problem
+    Tmpfail,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail => write!(f, "fail"),
+            Status::Neutral => write!(f, "neutral"),
+            Status::Tmpfail => write!(f, "tmpfail"),
+        }
+    }
+}
+
+/// A single autopkgtest run of a source package on debci.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TestRun {
+    /// Identifier of the run
+    pub run_id: u64,
+    /// Version of the source package that was tested
+    pub version: String,
+    /// Date the run finished, as reported by debci
+    pub date: Option<String>,
+    /// Outcome of the run
+    pub status: Status,
+    /// Outcome of the previous run
+    pub previous_status: Option<Status>,
+    /// How long the run took
+    pub duration_seconds: Option<u64>,
+    /// Human readable summary of the outcome
+    pub message: Option<String>,
+    /// What triggered the run, e.g. a migration of another package
+    pub trigger: Option<String>,
+}
+
+/// Error fetching results from debci.
+#[derive(Debug)]
+pub enum Error {
+    /// Error talking to debci.
+    Http(reqwest::Error),
+    /// debci has no results for the package on this suite and architecture.
+    NotFound,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::NotFound => write!(f, "No debci results found"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+/// Directory prefix that debci uses for a source package, e.g. "libf" for "libfoo".
+pub fn package_prefix(package: &str) -> &str {
+    let len = if package.starts_with("lib") && package.len() > 3 {
+        4
+    } else {
+        1
+    };
+    &package[..len.min(package.len())]
+}
+
+/// Whether the tests of a package are currently failing, based on its history.
+///
+/// Temporary failures are ignored, since they say nothing about the package.
+pub fn currently_failing(history: &[TestRun]) -> bool {
+    history
+        .iter()
+        .filter(|r| r.status != Status::Tmpfail)
+        .max_by_key(|r| r.run_id)
+        .is_some_and(|r| r.status == Status::Fail)
+}
+
+/// Client for a debci instance.
+pub struct Client {
+    base_url: url::Url,
+    client: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// Create a client for ci.debian.net.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(DEFAULT_URL.parse().unwrap())
+    }
+
+    /// Create a client for the debci instance at the given URL.
+    pub fn with_url(base_url: url::Url) -> Result<Self, Error> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self { base_url, client })
+    }
+
+    fn package_url(&self, package: &str, suite: &str, arch: &str, file: &str) -> url::Url {
+        self.base_url
+            .join(&format!(
+                "data/packages/{}/{}/{}/{}/{}",
+                suite,
+                arch,
+                package_prefix(package),
+                package,
+                file
+            ))
+            .unwrap()
+    }
+
+    #[tracing::instrument(skip_all, fields(url = %url))]
+    fn get<T: serde::de::DeserializeOwned>(&self, url: url::Url) -> Result<T, Error> {
+        let response = self.client.get(url).send()?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(Error::NotFound);
+        }
+        Ok(response.error_for_status()?.json()?)
+    }
+
+    /// Fetch the most recent run of a source package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the source package
+    /// * `suite` - Suite the tests ran in, e.g. "unstable" or "testing"
+    /// * `arch` - Architecture the tests ran on, e.g. "amd64"
+    pub fn latest(&self, package: &str, suite: &str, arch: &str) -> Result<TestRun, Error> {
+        self.get(self.package_url(package, suite, arch, "latest.json"))
+    }
+
+    /// Fetch all recorded runs of a source package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the source package
+    /// * `suite` - Suite the tests ran in, e.g. "unstable" or "testing"
+    /// * `arch` - Architecture the tests ran on, e.g. "amd64"
+    ///
+    /// # Returns
+    /// The runs, most recent first
+    pub fn history(&self, package: &str, suite: &str, arch: &str) -> Result<Vec<TestRun>, Error> {
+        let mut runs: Vec<TestRun> =
+            self.get(self.package_url(package, suite, arch, "history.json"))?;
+        runs.sort_by_key(|r| std::cmp::Reverse(r.run_id));
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_prefix() {
+        assert_eq!("libf", package_prefix("libfoo"));
+        assert_eq!("l", package_prefix("lintian"));
+        assert_eq!("l", package_prefix("lib"));
+        assert_eq!("s", package_prefix("samba"));
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let runs: Vec<TestRun> = serde_json::from_str(
+            r#"[
+                {"run_id": 12, "package": "foo", "version": "1.0-2", "date": "2024-03-01 10:00:00", "status": "tmpfail", "previous_status": "fail", "duration_seconds": 3, "message": "Tests failed temporarily", "trigger": null},
+                {"run_id": 11, "package": "foo", "version": "1.0-2", "date": "2024-02-28 10:00:00", "status": "fail", "previous_status": "pass", "duration_seconds": 120, "message": "Tests failed", "trigger": "bar/2.0-1"},
+                {"run_id": 10, "package": "foo", "version": "1.0-1", "status": "pass"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(Status::Fail, runs[1].status);
+        assert_eq!(Some("bar/2.0-1"), runs[1].trigger.as_deref());
+        assert_eq!(None, runs[2].date);
+        assert!(currently_failing(&runs));
+        assert!(!currently_failing(&runs[2..]));
+        assert!(!currently_failing(&[]));
+    }
+
+    #[test]
+    fn test_package_url() {
+        let client = Client::new().unwrap();
+        assert_eq!(
+            "https://ci.debian.net/data/packages/unstable/amd64/libf/libfoo/history.json",
+            client
+                .package_url("libfoo", "unstable", "amd64", "history.json")
+                .as_str()
+        );
+    }
+}
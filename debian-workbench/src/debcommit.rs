@@ -19,6 +19,12 @@ pub enum Error {
 
     /// Error from breezyshim.
     BrzError(breezyshim::error::Error),
+
+    /// The distribution of a suite is unknown.
+    UnknownDistribution(String),
+
+    /// Error looking up release information.
+    ReleaseInfo(crate::error::Error),
 }
 
 impl std::fmt::Display for Error {
@@ -27,6 +33,10 @@ impl std::fmt::Display for Error {
             Error::UnreleasedChanges(path) => write!(f, "Unreleased changes in {}", path.display()),
             Error::ChangelogError(e) => write!(f, "{}", e),
             Error::BrzError(e) => write!(f, "{}", e),
+            Error::UnknownDistribution(suite) => {
+                write!(f, "Unknown distribution for suite {}", suite)
+            }
+            Error::ReleaseInfo(e) => write!(f, "{}", e),
         }
     }
 }
@@ -43,9 +53,16 @@ impl From<debian_changelog::Error> for Error {
     }
 }
 
+impl From<crate::error::Error> for Error {
+    fn from(e: crate::error::Error) -> Self {
+        Error::ReleaseInfo(e)
+    }
+}
+
 impl std::error::Error for Error {}
 
 /// Create a commit with a tag for a release.
+#[tracing::instrument(skip(tree))]
 pub fn debcommit_release(
     tree: &dyn PyWorkingTree,
     committer: Option<&str>,
@@ -70,21 +87,25 @@ pub fn debcommit_release(
                 entry.version().unwrap()
             )
         };
-        let vendor = vendor.unwrap_or_else(|| {
-            suite_to_distribution(
-                entry
+        let vendor = match vendor {
+            Some(vendor) => vendor,
+            None => {
+                let suite = entry
                     .distributions()
                     .as_ref()
-                    .and_then(|d| d.first())
-                    .unwrap(),
-            )
-            .unwrap()
-        });
+                    .and_then(|d| d.first().cloned())
+                    .unwrap_or_default();
+                suite_to_distribution(&suite)?.ok_or(Error::UnknownDistribution(suite))?
+            }
+        };
         (message, vendor)
     };
-    let tag_name = if let Ok(tag_name) =
-        breezyshim::debian::tree_debian_tag_name(tree, &tree.branch(), Some(subpath), Some(vendor))
-    {
+    let tag_name = if let Ok(tag_name) = breezyshim::debian::tree_debian_tag_name(
+        tree,
+        &tree.branch(),
+        Some(subpath),
+        Some(vendor.into()),
+    ) {
         tag_name
     } else {
         return Err(Error::UnreleasedChanges(cl_path));
@@ -209,6 +230,7 @@ pub fn changelog_commit_message(
 ///
 /// # Returns
 /// Created revision id
+#[tracing::instrument(skip(tree, reporter))]
 pub fn debcommit(
     tree: &dyn WorkingTree,
     committer: Option<&str>,
@@ -1,4 +1,6 @@
 //! Debhelper utilities.
+use crate::error::Error;
+use crate::Certainty;
 use debversion::Version;
 use std::path::Path;
 
@@ -27,8 +29,11 @@ pub fn read_debhelper_compat_file(path: &Path) -> Result<Option<u8>, std::io::Er
 /// # Returns
 /// The debhelper compat level.
 pub fn get_debhelper_compat_level_from_control(control: &debian_control::Control) -> Option<u8> {
-    let source = control.source()?;
+    compat_level_from_source(&control.source()?)
+}
 
+/// Retrieve the debhelper compat level from the source paragraph of debian/control.
+fn compat_level_from_source(source: &debian_control::lossless::Source) -> Option<u8> {
     if let Some(dh_compat) = source.as_deb822().get("X-DH-Compat") {
         return parse_debhelper_compat(dh_compat.as_str());
     }
@@ -50,73 +55,192 @@ pub fn get_debhelper_compat_level_from_control(control: &debian_control::Control
 ///
 /// # Returns
 /// The debhelper compat level.
-pub fn get_debhelper_compat_level(path: &Path) -> Result<Option<u8>, std::io::Error> {
-    match read_debhelper_compat_file(&path.join("debian/compat")) {
-        Ok(Some(level)) => {
-            return Ok(Some(level));
-        }
-        Err(e) => {
-            return Err(e);
-        }
-        Ok(None) => {}
+pub fn get_debhelper_compat_level(path: &Path) -> Result<Option<u8>, Error> {
+    if let Some(level) = read_debhelper_compat_file(&path.join("debian/compat"))? {
+        return Ok(Some(level));
     }
 
     let p = path.join("debian/control");
 
-    match std::fs::File::open(p) {
+    match std::fs::File::open(&p) {
         Ok(f) => {
-            let control = debian_control::Control::read_relaxed(f).unwrap().0;
+            let control = debian_control::Control::read_relaxed(f)
+                .map_err(|e| Error::Parse {
+                    path: p,
+                    message: e.to_string(),
+                })?
+                .0;
             Ok(get_debhelper_compat_level_from_control(&control))
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
-        Err(e) => Err(e),
+        Err(e) => Err(e.into()),
     }
 }
 
-/// Ask dh_assistant for the supported compat levels.
-///
-/// Cache the result.
-fn get_lintian_compat_levels() -> &'static SupportedCompatLevels {
-    lazy_static::lazy_static! {
-        static ref LINTIAN_COMPAT_LEVELS: SupportedCompatLevels = {
-            // TODO(jelmer): ideally we should be getting these numbers from the compat-release
-            // dh_assistant, rather than what's on the system
-            let output = std::process::Command::new("dh_assistant")
-                .arg("supported-compat-levels")
-                .output()
-                .expect("failed to run dh_assistant")
-                .stdout;
-            serde_json::from_slice(&output).expect("failed to parse dh_assistant output")
-        };
-    };
-    &LINTIAN_COMPAT_LEVELS
-}
-
-#[derive(Debug, serde::Deserialize)]
-#[allow(dead_code)]
-struct SupportedCompatLevels {
+/// The debhelper compat levels supported by a version of debhelper, as
+/// reported by `dh_assistant supported-compat-levels`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct SupportedCompatLevels {
+    /// Highest compat level that is considered stable
     #[serde(rename = "HIGHEST_STABLE_COMPAT_LEVEL")]
-    highest_stable_compat_level: u8,
+    pub highest_stable_compat_level: u8,
+    /// Lowest compat level that is not deprecated
     #[serde(rename = "LOWEST_NON_DEPRECATED_COMPAT_LEVEL")]
-    lowest_non_deprecated_compat_level: u8,
+    pub lowest_non_deprecated_compat_level: u8,
+    /// Lowest compat level that can be declared with a debhelper-compat build dependency
     #[serde(rename = "LOWEST_VIRTUAL_DEBHELPER_COMPAT_LEVEL")]
-    lowest_virtual_debhelper_compat_level: u8,
+    pub lowest_virtual_debhelper_compat_level: u8,
+    /// Highest supported compat level, including experimental ones
     #[serde(rename = "MAX_COMPAT_LEVEL")]
-    max_compat_level: u8,
+    pub max_compat_level: u8,
+    /// Lowest supported compat level
     #[serde(rename = "MIN_COMPAT_LEVEL")]
-    min_compat_level: u8,
+    pub min_compat_level: u8,
+    /// Lowest compat level that is not scheduled for removal
     #[serde(rename = "MIN_COMPAT_LEVEL_NOT_SCHEDULED_FOR_REMOVAL")]
-    min_compat_level_not_scheduled_for_removal: u8,
+    pub min_compat_level_not_scheduled_for_removal: u8,
+}
+
+/// Run dh_assistant to find the supported compat levels.
+///
+/// # Arguments
+/// * `cmd` - Command that runs `dh_assistant supported-compat-levels`,
+///   e.g. in a schroot or chdist for a specific release
+pub fn supported_compat_levels_from_command(
+    mut cmd: std::process::Command,
+) -> Result<SupportedCompatLevels, Error> {
+    let program = cmd.get_program().to_string_lossy().into_owned();
+    let command_error = |message: String| Error::Command {
+        command: program.clone(),
+        message,
+    };
+    let span = tracing::info_span!("command", command = ?cmd);
+    let output = span
+        .in_scope(|| cmd.output())
+        .map_err(|e| command_error(e.to_string()))?;
+    if !output.status.success() {
+        return Err(command_error(format!(
+            "{}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| command_error(format!("unable to parse output: {}", e)))
+}
+
+/// Ask the host's dh_assistant for the supported compat levels.
+///
+/// Cache the result once dh_assistant has been run successfully.
+fn get_lintian_compat_levels() -> Result<&'static SupportedCompatLevels, Error> {
+    static LINTIAN_COMPAT_LEVELS: std::sync::OnceLock<SupportedCompatLevels> =
+        std::sync::OnceLock::new();
+    if let Some(levels) = LINTIAN_COMPAT_LEVELS.get() {
+        return Ok(levels);
+    }
+    let mut cmd = std::process::Command::new("dh_assistant");
+    cmd.arg("supported-compat-levels");
+    let levels = supported_compat_levels_from_command(cmd)?;
+    Ok(LINTIAN_COMPAT_LEVELS.get_or_init(|| levels))
+}
+
+/// Looks up the compat levels supported in a release, returning `None` if
+/// the release is not known.
+pub type ReleaseCompatLevelsLookup =
+    dyn Fn(&str) -> Result<Option<SupportedCompatLevels>, Error> + Send + Sync;
+
+fn release_compat_levels_lookup(
+) -> &'static std::sync::RwLock<Option<Box<ReleaseCompatLevelsLookup>>> {
+    static LOOKUP: std::sync::OnceLock<std::sync::RwLock<Option<Box<ReleaseCompatLevelsLookup>>>> =
+        std::sync::OnceLock::new();
+    LOOKUP.get_or_init(|| std::sync::RwLock::new(None))
+}
+
+/// Set how [`supported_compat_levels_for_release`] looks up the compat
+/// levels supported in a release.
+///
+/// This can be used to ask the dh_assistant of the release itself:
+///
+/// ```rust,no_run
+/// use debian_workbench::debhelper::*;
+/// set_release_compat_levels_lookup(|release| {
+///     let mut cmd = std::process::Command::new("schroot");
+///     cmd.args(["-c", release, "--", "dh_assistant", "supported-compat-levels"]);
+///     supported_compat_levels_from_command(cmd).map(Some)
+/// });
+/// ```
+pub fn set_release_compat_levels_lookup(
+    lookup: impl Fn(&str) -> Result<Option<SupportedCompatLevels>, Error> + Send + Sync + 'static,
+) {
+    *release_compat_levels_lookup().write().unwrap() = Some(Box::new(lookup));
+}
+
+/// Find the debhelper compat levels supported in a release.
+///
+/// The lookup set with [`set_release_compat_levels_lookup`] is used if
+/// there is one. Otherwise, the highest stable compat level is the major
+/// version of debhelper in the release, according to the bundled key
+/// package data (which requires the `debian` feature); the other levels are
+/// those of the host's dh_assistant, capped at that level.
+///
+/// # Arguments
+/// * `release` - Codename of the release, e.g. from
+///   [`crate::config::Config::compat_release`]
+///
+/// # Returns
+/// The supported compat levels, or `None` if the release is not known
+pub fn supported_compat_levels_for_release(
+    release: &str,
+) -> Result<Option<SupportedCompatLevels>, Error> {
+    if let Some(lookup) = release_compat_levels_lookup().read().unwrap().as_ref() {
+        return lookup(release);
+    }
+    #[cfg(feature = "debian")]
+    {
+        let Some(highest) = debian_analyzer::debhelper::maximum_debhelper_compat_version(release)
+        else {
+            return Ok(None);
+        };
+        let host = get_lintian_compat_levels()?;
+        Ok(Some(SupportedCompatLevels {
+            highest_stable_compat_level: highest,
+            lowest_non_deprecated_compat_level: host
+                .lowest_non_deprecated_compat_level
+                .min(highest),
+            lowest_virtual_debhelper_compat_level: host
+                .lowest_virtual_debhelper_compat_level
+                .min(highest),
+            max_compat_level: host.max_compat_level.clamp(highest, highest + 1),
+            min_compat_level: host.min_compat_level.min(highest),
+            min_compat_level_not_scheduled_for_removal: host
+                .min_compat_level_not_scheduled_for_removal
+                .min(highest),
+        }))
+    }
+    #[cfg(not(feature = "debian"))]
+    {
+        log::debug!(
+            "Unable to look up the compat levels for {}: built without the debian feature",
+            release
+        );
+        Ok(None)
+    }
 }
 
 /// Find the lowest non-deprecated debhelper compat level.
-pub fn lowest_non_deprecated_compat_level() -> u8 {
-    get_lintian_compat_levels().lowest_non_deprecated_compat_level
+///
+/// This runs dh_assistant, which needs to be installed. Use
+/// [`supported_compat_levels_for_release`] when targeting a specific release.
+pub fn lowest_non_deprecated_compat_level() -> Result<u8, Error> {
+    Ok(get_lintian_compat_levels()?.lowest_non_deprecated_compat_level)
 }
 
 /// Find the highest stable debhelper compat level.
-pub fn highest_stable_compat_level() -> u8 {
-    get_lintian_compat_levels().highest_stable_compat_level
+///
+/// This runs dh_assistant, which needs to be installed. Use
+/// [`supported_compat_levels_for_release`] when targeting a specific release.
+pub fn highest_stable_compat_level() -> Result<u8, Error> {
+    Ok(get_lintian_compat_levels()?.highest_stable_compat_level)
 }
 
 /// Error type for ensure_minimum_debhelper_version
@@ -151,7 +275,8 @@ impl std::error::Error for EnsureDebhelperError {}
 /// Ensure that the package is at least using a specific version of debhelper.
 ///
 /// This is a dedicated helper, since debhelper can now also be pulled in
-/// with a debhelper-compat dependency.
+/// with a debhelper-compat dependency. To raise the compat level instead,
+/// use [`ensure_minimum_debhelper_compat`].
 ///
 /// # Arguments
 /// * `source` - The source paragraph from debian/control
@@ -274,6 +399,544 @@ pub fn get_sequences(source: &debian_control::lossless::Source) -> impl Iterator
         .into_iter()
 }
 
+/// Apply `f` to the dh invocations in debian/rules, if there is one.
+///
+/// # Returns
+/// Whether debian/rules was changed
+fn edit_rules_dh_invocations(
+    base: &Path,
+    f: impl Fn(&str) -> String,
+) -> Result<bool, std::io::Error> {
+    let rules_path = base.join("debian/rules");
+    let rules = match std::fs::read_to_string(&rules_path) {
+        Ok(rules) => rules,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let updated = edit_invocations(&rules, "dh", f);
+    if updated == rules {
+        return Ok(false);
+    }
+    std::fs::write(&rules_path, updated)?;
+    Ok(true)
+}
+
+/// Ensure that a dh sequence is enabled with a dh-sequence-* build dependency.
+///
+/// The dh-sequence-* build dependency is added, and the sequence is
+/// dropped from the `--with` arguments of dh in debian/rules.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `source` - The source paragraph from debian/control
+/// * `sequence` - Name of the sequence, e.g. "python3"
+///
+/// # Returns
+/// Whether anything was changed
+pub fn ensure_sequence(
+    base: &Path,
+    source: &mut debian_control::lossless::Source,
+    sequence: &str,
+) -> Result<bool, std::io::Error> {
+    let mut rels = source.build_depends().unwrap_or_default();
+    let added =
+        crate::relations::ensure_some_version(&mut rels, &format!("dh-sequence-{}", sequence));
+    if added {
+        source.set_build_depends(&rels);
+    }
+    let dropped =
+        edit_rules_dh_invocations(base, |l| crate::rules::dh_invoke_drop_with(l, sequence))?;
+    Ok(added || dropped)
+}
+
+/// Stop enabling a dh sequence with a dh-sequence-* build dependency.
+///
+/// The dh-sequence-* build dependency is dropped. If `keep_with` is set, the
+/// sequence is enabled with a `--with` argument to dh in debian/rules
+/// instead; the package that provides the sequence then needs to be a
+/// build dependency in its own right. Otherwise, the sequence is also
+/// dropped from any `--with` arguments, so that it is no longer used.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `source` - The source paragraph from debian/control
+/// * `sequence` - Name of the sequence, e.g. "python3"
+/// * `keep_with` - Whether to enable the sequence with `--with` instead
+///
+/// # Returns
+/// Whether anything was changed
+pub fn remove_sequence(
+    base: &Path,
+    source: &mut debian_control::lossless::Source,
+    sequence: &str,
+    keep_with: bool,
+) -> Result<bool, std::io::Error> {
+    let mut rels = source.build_depends().unwrap_or_default();
+    let removed = rels.drop_dependency(&format!("dh-sequence-{}", sequence));
+    if removed {
+        source.set_build_depends(&rels);
+    }
+    let edited = if keep_with {
+        removed
+            && edit_rules_dh_invocations(base, |l| crate::rules::dh_invoke_add_with(l, sequence))?
+    } else {
+        edit_rules_dh_invocations(base, |l| crate::rules::dh_invoke_drop_with(l, sequence))?
+    };
+    Ok(removed || edited)
+}
+
+/// Lowest compat level that can be declared with a debhelper-compat build dependency.
+pub const DEBHELPER_COMPAT_RELATION_LEVEL: u8 = 11;
+
+/// An action taken while bumping the debhelper compat level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatAction {
+    /// Description of the change, suitable for a changelog entry
+    pub description: String,
+    /// How certain it is that the change preserves the behaviour of the package
+    pub certainty: Certainty,
+}
+
+impl CompatAction {
+    fn new(description: String, certainty: Certainty) -> Self {
+        Self {
+            description,
+            certainty,
+        }
+    }
+}
+
+/// The result of bumping the debhelper compat level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatBump {
+    /// Compat level before the bump
+    pub from: u8,
+    /// Compat level after the bump
+    pub to: u8,
+    /// Actions that were taken
+    pub actions: Vec<CompatAction>,
+    /// Things the maintainer should check by hand
+    pub follow_ups: Vec<String>,
+}
+
+impl CompatBump {
+    /// Descriptions of the changes that were made, suitable for a changelog entry.
+    pub fn changes(&self) -> Vec<&str> {
+        self.actions
+            .iter()
+            .map(|a| a.description.as_str())
+            .collect()
+    }
+
+    /// The lowest certainty of the actions that were taken.
+    pub fn certainty(&self) -> Certainty {
+        self.actions
+            .iter()
+            .map(|a| a.certainty)
+            .min()
+            .unwrap_or_default()
+    }
+}
+
+/// Error bumping the debhelper compat level.
+#[derive(Debug)]
+pub enum BumpCompatError {
+    /// The current compat level could not be determined
+    NoCompatLevel,
+    /// The package is already at (or beyond) the requested level
+    AlreadyAtLevel(u8),
+    /// The debhelper build dependency could not be updated
+    Debhelper(EnsureDebhelperError),
+    /// The control file could not be edited
+    Editor(Box<crate::editor::EditorError>),
+    /// I/O error
+    Io(std::io::Error),
+    /// The packaging could not be read
+    Package(Box<Error>),
+}
+
+impl std::fmt::Display for BumpCompatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BumpCompatError::NoCompatLevel => write!(f, "Unable to determine compat level"),
+            BumpCompatError::AlreadyAtLevel(level) => {
+                write!(f, "Already at compat level {}", level)
+            }
+            BumpCompatError::Debhelper(e) => write!(f, "{}", e),
+            BumpCompatError::Editor(e) => write!(f, "{}", e),
+            BumpCompatError::Io(e) => write!(f, "{}", e),
+            BumpCompatError::Package(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for BumpCompatError {}
+
+impl From<EnsureDebhelperError> for BumpCompatError {
+    fn from(e: EnsureDebhelperError) -> Self {
+        BumpCompatError::Debhelper(e)
+    }
+}
+
+impl From<crate::editor::EditorError> for BumpCompatError {
+    fn from(e: crate::editor::EditorError) -> Self {
+        BumpCompatError::Editor(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for BumpCompatError {
+    fn from(e: std::io::Error) -> Self {
+        BumpCompatError::Io(e)
+    }
+}
+
+impl From<Error> for BumpCompatError {
+    fn from(e: Error) -> Self {
+        BumpCompatError::Package(Box::new(e))
+    }
+}
+
+/// Declare a compat level where the package declares its current one.
+///
+/// debian/compat is replaced with a debhelper-compat build dependency if the
+/// level allows it.
+///
+/// # Returns
+/// A description of the migration away from debian/compat, if there was one
+fn declare_compat_level(
+    base: &Path,
+    source: &mut debian_control::lossless::Source,
+    to: u8,
+) -> Result<Option<String>, BumpCompatError> {
+    let to_version: Version = to.to_string().parse().unwrap();
+    let compat_path = base.join("debian/compat");
+    if compat_path.exists() {
+        if to >= DEBHELPER_COMPAT_RELATION_LEVEL {
+            std::fs::remove_file(&compat_path)?;
+            let mut rels = source.build_depends().unwrap_or_default();
+            rels.drop_dependency("debhelper");
+            crate::relations::ensure_exact_version(
+                &mut rels,
+                "debhelper-compat",
+                &to_version,
+                Some(0),
+            );
+            source.set_build_depends(&rels);
+            return Ok(Some(
+                "Replace debian/compat with a dependency on debhelper-compat.".to_string(),
+            ));
+        }
+        std::fs::write(&compat_path, format!("{}\n", to))?;
+        ensure_minimum_debhelper_version(source, &to_version)?;
+    } else if source.as_deb822().get("X-DH-Compat").is_some() {
+        source.as_mut_deb822().set("X-DH-Compat", &to.to_string());
+        ensure_minimum_debhelper_version(source, &to_version)?;
+    } else {
+        let mut rels = source.build_depends().unwrap_or_default();
+        crate::relations::ensure_exact_version(&mut rels, "debhelper-compat", &to_version, None);
+        source.set_build_depends(&rels);
+    }
+    Ok(None)
+}
+
+/// Ensure that the package uses at least a specific debhelper compat level.
+///
+/// Unlike [`ensure_minimum_debhelper_version`], which adds a versioned
+/// debhelper build dependency next to an existing debhelper-compat one,
+/// this raises the compat level itself: the debhelper-compat version,
+/// debian/compat or X-DH-Compat is updated, and debian/compat is replaced
+/// with a debhelper-compat build dependency if the new level allows it.
+///
+/// Unlike [`bump_debhelper_compat`], debian/rules is left alone.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `source` - The source paragraph from debian/control
+/// * `minimum_level` - The minimum compat level
+///
+/// # Returns
+/// The previous compat level if it was raised, or `None` if the package
+/// already uses at least `minimum_level`
+pub fn ensure_minimum_debhelper_compat(
+    base: &Path,
+    source: &mut debian_control::lossless::Source,
+    minimum_level: u8,
+) -> Result<Option<u8>, BumpCompatError> {
+    let current = match read_debhelper_compat_file(&base.join("debian/compat"))? {
+        Some(level) => level,
+        None => compat_level_from_source(source).ok_or(BumpCompatError::NoCompatLevel)?,
+    };
+    if current >= minimum_level {
+        return Ok(None);
+    }
+    declare_compat_level(base, source, minimum_level)?;
+    Ok(Some(current))
+}
+
+/// A change in behaviour of debhelper at a compat level.
+#[derive(Debug, Clone, Copy)]
+enum CompatDelta {
+    /// An argument to a helper that has become its default behaviour
+    DefaultArgument {
+        command: &'static str,
+        argument: &'static str,
+    },
+    /// A dh sequence that is enabled by default, and the package that provided it
+    DefaultAddon {
+        addon: &'static str,
+        package: &'static str,
+    },
+    /// A change that affects packages whose debian/rules match `pattern`,
+    /// and has to be dealt with by hand
+    Review {
+        pattern: &'static str,
+        message: &'static str,
+    },
+}
+
+/// The changes in behaviour between compat levels, by the level that introduced them.
+///
+/// See debhelper-compat-upgrade-checklist(7) for the full list.
+const COMPAT_DELTAS: &[(u8, CompatDelta)] = &[
+    (
+        10,
+        CompatDelta::DefaultArgument {
+            command: "dh",
+            argument: "--parallel",
+        },
+    ),
+    (
+        10,
+        CompatDelta::DefaultArgument {
+            command: "dh_installinit",
+            argument: "--restart-after-upgrade",
+        },
+    ),
+    (
+        10,
+        CompatDelta::DefaultAddon {
+            addon: "autoreconf",
+            package: "dh-autoreconf",
+        },
+    ),
+    (
+        10,
+        CompatDelta::DefaultAddon {
+            addon: "systemd",
+            package: "dh-systemd",
+        },
+    ),
+    (
+        11,
+        CompatDelta::Review {
+            pattern: r"(?m)^override_dh_systemd_(enable|start)",
+            message: "dh_systemd_enable and dh_systemd_start are replaced by dh_installsystemd in compat 11; migrate the override_dh_systemd_* targets.",
+        },
+    ),
+    (
+        11,
+        CompatDelta::Review {
+            pattern: r"(?m)^override_dh_installinit",
+            message: "dh_installinit no longer installs systemd service files in compat 11; check whether the override_dh_installinit target needs to move to dh_installsystemd.",
+        },
+    ),
+    (
+        13,
+        CompatDelta::DefaultArgument {
+            command: "dh_missing",
+            argument: "--fail-missing",
+        },
+    ),
+    (
+        13,
+        CompatDelta::Review {
+            pattern: r"(?m)^override_dh_auto_test",
+            message: "The dh_auto_* helpers set HOME and XDG_* to a directory below debian/.debhelper in compat 13; check that the overridden tests still pass.",
+        },
+    ),
+];
+
+/// Apply `f` to each invocation of `command` in debian/rules, leaving other lines untouched.
+fn edit_invocations(rules: &str, command: &str, f: impl Fn(&str) -> String) -> String {
+    let re = regex::Regex::new(&format!(r"^\t\s*{}(\s|$)", regex::escape(command))).unwrap();
+    rules
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim_end_matches('\n');
+            if re.is_match(trimmed) {
+                f(trimmed) + &line[trimmed.len()..]
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Drop the override targets in debian/rules that now just run the helper they override.
+fn discard_pointless_overrides(rules: &str) -> String {
+    let Ok(mut makefile) = makefile_lossless::Makefile::read_relaxed(rules.as_bytes()) else {
+        return rules.to_string();
+    };
+    if crate::rules::discard_pointless_overrides(&mut makefile) == 0 {
+        return rules.to_string();
+    }
+    makefile.to_string()
+}
+
+/// Bump the debhelper compat level of a package from a known level.
+///
+/// This knows about the changes in behaviour between compat levels: the
+/// compat level is updated where it is declared, replacing debian/compat
+/// with a debhelper-compat build dependency if the new level allows it;
+/// arguments in debian/rules that have become the default are dropped,
+/// along with the build dependencies that provided addons that are now
+/// enabled by default and overrides that have become pointless.
+///
+/// Changes in behaviour that affect the package but can not be dealt with
+/// automatically are reported as follow-ups, and lower the certainty of
+/// the bump itself.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `from` - Current compat level of the package
+/// * `to` - Compat level to bump to
+///
+/// # Returns
+/// The actions taken, and the things the maintainer should check by hand
+pub fn bump_compat_level(base: &Path, from: u8, to: u8) -> Result<CompatBump, BumpCompatError> {
+    if from >= to {
+        return Err(BumpCompatError::AlreadyAtLevel(from));
+    }
+    let mut actions = vec![];
+    let mut follow_ups = vec![];
+
+    let control_path = base.join("debian/control");
+    let editor = crate::control::TemplatedControlEditor::open(&control_path)?;
+    let mut source = editor.source().ok_or(BumpCompatError::NoCompatLevel)?;
+    if let Some(change) = declare_compat_level(base, &mut source, to)? {
+        actions.push(CompatAction::new(change, Certainty::Certain));
+    }
+
+    let rules_path = base.join("debian/rules");
+    let rules = match std::fs::read_to_string(&rules_path) {
+        Ok(rules) => Some(rules),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let applicable = COMPAT_DELTAS
+        .iter()
+        .filter(|(level, _)| from < *level && *level <= to);
+    let mut new_rules = rules.clone();
+    for (level, delta) in applicable {
+        match *delta {
+            CompatDelta::DefaultArgument { command, argument } => {
+                let Some(current) = new_rules.as_ref() else {
+                    continue;
+                };
+                let mut updated = edit_invocations(current, command, |l| {
+                    crate::rules::dh_invoke_drop_argument(l, argument)
+                });
+                if updated == *current {
+                    continue;
+                }
+                if command != "dh" {
+                    updated = discard_pointless_overrides(&updated);
+                }
+                actions.push(CompatAction::new(
+                    if command == "dh" {
+                        format!(
+                            "Drop {}, which is the default in compat {}.",
+                            argument, level
+                        )
+                    } else {
+                        format!(
+                            "Drop {} {}, which is the default in compat {}.",
+                            command, argument, level
+                        )
+                    },
+                    Certainty::Certain,
+                ));
+                new_rules = Some(updated);
+            }
+            CompatDelta::DefaultAddon { addon, package } => {
+                let mut changed = false;
+                if let Some(current) = new_rules.as_ref() {
+                    let updated = edit_invocations(current, "dh", |l| {
+                        crate::rules::dh_invoke_drop_with(l, addon)
+                    });
+                    changed = updated != *current;
+                    new_rules = Some(updated);
+                }
+                let mut rels = source.build_depends().unwrap_or_default();
+                if rels.drop_dependency(package) {
+                    source.set_build_depends(&rels);
+                    changed = true;
+                }
+                if changed {
+                    actions.push(CompatAction::new(
+                        format!(
+                            "Drop use of the {} addon, which is enabled by default in compat {}.",
+                            addon, level
+                        ),
+                        Certainty::Certain,
+                    ));
+                }
+            }
+            CompatDelta::Review { pattern, message } => {
+                if rules
+                    .as_ref()
+                    .is_some_and(|r| regex::Regex::new(pattern).unwrap().is_match(r))
+                {
+                    follow_ups.push(message.to_string());
+                }
+            }
+        }
+    }
+    if new_rules != rules {
+        if let Some(new_rules) = new_rules {
+            std::fs::write(&rules_path, new_rules)?;
+        }
+    }
+    let certainty = if follow_ups.is_empty() {
+        Certainty::Certain
+    } else {
+        Certainty::Possible
+    };
+    actions.insert(
+        0,
+        CompatAction::new(
+            format!("Bump debhelper from old {} to {}.", from, to),
+            certainty,
+        ),
+    );
+    for level in from + 1..=to {
+        follow_ups.push(format!(
+            "Review the changes for compat level {} in debhelper-compat-upgrade-checklist(7).",
+            level
+        ));
+    }
+    editor.commit()?;
+    Ok(CompatBump {
+        from,
+        to,
+        actions,
+        follow_ups,
+    })
+}
+
+/// Bump the debhelper compat level of a package.
+///
+/// The current compat level is read from the package; see
+/// [`bump_compat_level`] for the changes that are made.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `to` - Compat level to bump to
+pub fn bump_debhelper_compat(base: &Path, to: u8) -> Result<CompatBump, BumpCompatError> {
+    let from = get_debhelper_compat_level(base)?.ok_or(BumpCompatError::NoCompatLevel)?;
+    bump_compat_level(base, from, to)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -290,6 +953,44 @@ mod tests {
         assert_eq!(super::parse_debhelper_compat(" # comment"), None);
     }
 
+    const SUPPORTED_COMPAT_LEVELS: &str = r#"{"HIGHEST_STABLE_COMPAT_LEVEL":13,"LOWEST_NON_DEPRECATED_COMPAT_LEVEL":7,"LOWEST_VIRTUAL_DEBHELPER_COMPAT_LEVEL":9,"MAX_COMPAT_LEVEL":14,"MIN_COMPAT_LEVEL":5,"MIN_COMPAT_LEVEL_NOT_SCHEDULED_FOR_REMOVAL":7}"#;
+
+    #[test]
+    fn test_supported_compat_levels_from_command() {
+        let mut cmd = std::process::Command::new("echo");
+        cmd.arg(SUPPORTED_COMPAT_LEVELS);
+        let levels = supported_compat_levels_from_command(cmd).unwrap();
+        assert_eq!(13, levels.highest_stable_compat_level);
+        assert_eq!(7, levels.lowest_non_deprecated_compat_level);
+        assert_eq!(14, levels.max_compat_level);
+
+        let mut cmd = std::process::Command::new("echo");
+        cmd.arg("not json");
+        assert!(matches!(
+            supported_compat_levels_from_command(cmd),
+            Err(Error::Command { command, .. }) if command == "echo"
+        ));
+    }
+
+    #[test]
+    fn test_supported_compat_levels_for_release() {
+        set_release_compat_levels_lookup(|release| {
+            Ok((release == "bookworm").then(|| {
+                serde_json::from_str::<SupportedCompatLevels>(SUPPORTED_COMPAT_LEVELS).unwrap()
+            }))
+        });
+        assert_eq!(
+            Some(13),
+            supported_compat_levels_for_release("bookworm")
+                .unwrap()
+                .map(|l| l.highest_stable_compat_level)
+        );
+        assert_eq!(
+            None,
+            supported_compat_levels_for_release("nonexistent").unwrap()
+        );
+    }
+
     #[test]
     fn test_get_debhelper_compat_level_from_control() {
         let text = "Source: foo
@@ -445,6 +1146,84 @@ Build-Depends: debhelper
         }
     }
 
+    mod ensure_minimum_debhelper_compat_tests {
+        use super::*;
+
+        #[test]
+        fn test_bump_relation() {
+            let td = tempfile::tempdir().unwrap();
+            let text = "Source: foo\nBuild-Depends: debhelper-compat (= 10), libfoo-dev\n";
+            let control = debian_control::Control::read_relaxed(text.as_bytes())
+                .unwrap()
+                .0;
+            let mut source = control.source().unwrap();
+
+            assert_eq!(
+                Some(10),
+                ensure_minimum_debhelper_compat(td.path(), &mut source, 11).unwrap()
+            );
+            assert_eq!(
+                source.build_depends().unwrap().to_string(),
+                "debhelper-compat (= 11), libfoo-dev"
+            );
+
+            assert_eq!(
+                None,
+                ensure_minimum_debhelper_compat(td.path(), &mut source, 10).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_migrate_compat_file() {
+            let td = tempfile::tempdir().unwrap();
+            std::fs::create_dir(td.path().join("debian")).unwrap();
+            std::fs::write(td.path().join("debian/compat"), "9\n").unwrap();
+            let text = "Source: foo\nBuild-Depends: debhelper (>= 9), libfoo-dev\n";
+            let control = debian_control::Control::read_relaxed(text.as_bytes())
+                .unwrap()
+                .0;
+            let mut source = control.source().unwrap();
+
+            assert_eq!(
+                Some(9),
+                ensure_minimum_debhelper_compat(td.path(), &mut source, 10).unwrap()
+            );
+            assert_eq!(
+                "10\n",
+                std::fs::read_to_string(td.path().join("debian/compat")).unwrap()
+            );
+            assert_eq!(
+                source.build_depends().unwrap().to_string(),
+                "debhelper (>= 10), libfoo-dev"
+            );
+
+            assert_eq!(
+                Some(10),
+                ensure_minimum_debhelper_compat(td.path(), &mut source, 13).unwrap()
+            );
+            assert!(!td.path().join("debian/compat").exists());
+            assert_eq!(
+                source.build_depends().unwrap().to_string(),
+                "debhelper-compat (= 13), libfoo-dev"
+            );
+        }
+
+        #[test]
+        fn test_no_compat_level() {
+            let td = tempfile::tempdir().unwrap();
+            let text = "Source: foo\nBuild-Depends: debhelper\n";
+            let control = debian_control::Control::read_relaxed(text.as_bytes())
+                .unwrap()
+                .0;
+            let mut source = control.source().unwrap();
+
+            assert!(matches!(
+                ensure_minimum_debhelper_compat(td.path(), &mut source, 13),
+                Err(BumpCompatError::NoCompatLevel)
+            ));
+        }
+    }
+
     mod get_sequences_tests {
         use super::*;
 
@@ -496,4 +1275,174 @@ Build-Depends: debhelper
             assert_eq!(sequences, Vec::<String>::new());
         }
     }
+
+    mod sequence_tests {
+        use super::*;
+
+        fn setup(control: &str, rules: &str) -> (tempfile::TempDir, debian_control::Control) {
+            let td = tempfile::tempdir().unwrap();
+            std::fs::create_dir_all(td.path().join("debian")).unwrap();
+            std::fs::write(td.path().join("debian/rules"), rules).unwrap();
+            let control = debian_control::Control::read_relaxed(control.as_bytes())
+                .unwrap()
+                .0;
+            (td, control)
+        }
+
+        #[test]
+        fn test_ensure_sequence() {
+            let (td, control) = setup(
+                "Source: foo\nBuild-Depends: debhelper-compat (= 13), dh-python\n",
+                "%:\n\tdh $@ --with python3\n",
+            );
+            let mut source = control.source().unwrap();
+            assert!(ensure_sequence(td.path(), &mut source, "python3").unwrap());
+            assert_eq!(
+                "Source: foo\nBuild-Depends: debhelper-compat (= 13), dh-python, dh-sequence-python3\n",
+                control.to_string()
+            );
+            assert_eq!(
+                "%:\n\tdh $@\n",
+                std::fs::read_to_string(td.path().join("debian/rules")).unwrap()
+            );
+            assert!(!ensure_sequence(td.path(), &mut source, "python3").unwrap());
+        }
+
+        #[test]
+        fn test_remove_sequence() {
+            let (td, control) = setup(
+                "Source: foo\nBuild-Depends: debhelper-compat (= 13), dh-sequence-python3\n",
+                "%:\n\tdh $@\n",
+            );
+            let mut source = control.source().unwrap();
+            assert!(remove_sequence(td.path(), &mut source, "python3", true).unwrap());
+            assert_eq!(
+                "Source: foo\nBuild-Depends: debhelper-compat (= 13)\n",
+                control.to_string()
+            );
+            assert_eq!(
+                "%:\n\tdh $@ --with=python3\n",
+                std::fs::read_to_string(td.path().join("debian/rules")).unwrap()
+            );
+            assert!(remove_sequence(td.path(), &mut source, "python3", false).unwrap());
+            assert_eq!(
+                "%:\n\tdh $@\n",
+                std::fs::read_to_string(td.path().join("debian/rules")).unwrap()
+            );
+            assert!(!remove_sequence(td.path(), &mut source, "python3", false).unwrap());
+        }
+    }
+
+    mod bump_debhelper_compat_tests {
+        use super::*;
+
+        #[test]
+        fn test_compat_file() {
+            let td = tempfile::tempdir().unwrap();
+            std::fs::create_dir_all(td.path().join("debian")).unwrap();
+            std::fs::write(td.path().join("debian/compat"), "9\n").unwrap();
+            std::fs::write(
+                td.path().join("debian/control"),
+                "Source: foo\nBuild-Depends: debhelper (>= 9), dh-autoreconf, libfoo-dev\n\nPackage: foo\nArchitecture: any\n",
+            )
+            .unwrap();
+            std::fs::write(
+                td.path().join("debian/rules"),
+                "#!/usr/bin/make -f\n\n%:\n\tdh $@ --parallel --with autoreconf\n\noverride_dh_systemd_start:\n\tdh_systemd_start --no-start\n",
+            )
+            .unwrap();
+            let bump = bump_debhelper_compat(td.path(), 13).unwrap();
+            assert_eq!(9, bump.from);
+            assert_eq!(
+                vec![
+                    "Bump debhelper from old 9 to 13.",
+                    "Replace debian/compat with a dependency on debhelper-compat.",
+                    "Drop --parallel, which is the default in compat 10.",
+                    "Drop use of the autoreconf addon, which is enabled by default in compat 10.",
+                ],
+                bump.changes()
+            );
+            assert_eq!(Certainty::Possible, bump.certainty());
+            assert_eq!(5, bump.follow_ups.len());
+            assert!(bump.follow_ups[0].contains("dh_installsystemd"));
+            assert!(!td.path().join("debian/compat").exists());
+            assert_eq!(
+                "Source: foo\nBuild-Depends: debhelper-compat (= 13), libfoo-dev\n\nPackage: foo\nArchitecture: any\n",
+                std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+            );
+            assert_eq!(
+                "#!/usr/bin/make -f\n\n%:\n\tdh $@\n\noverride_dh_systemd_start:\n\tdh_systemd_start --no-start\n",
+                std::fs::read_to_string(td.path().join("debian/rules")).unwrap()
+            );
+            assert!(matches!(
+                bump_debhelper_compat(td.path(), 13),
+                Err(BumpCompatError::AlreadyAtLevel(13))
+            ));
+        }
+
+        #[test]
+        fn test_debhelper_compat() {
+            let td = tempfile::tempdir().unwrap();
+            std::fs::create_dir_all(td.path().join("debian")).unwrap();
+            std::fs::write(
+                td.path().join("debian/control"),
+                "Source: foo\nBuild-Depends: debhelper-compat (= 12)\n\nPackage: foo\nArchitecture: any\n",
+            )
+            .unwrap();
+            let bump = bump_debhelper_compat(td.path(), 13).unwrap();
+            assert_eq!(vec!["Bump debhelper from old 12 to 13."], bump.changes());
+            assert_eq!(Certainty::Certain, bump.certainty());
+            assert_eq!(
+                "Source: foo\nBuild-Depends: debhelper-compat (= 13)\n\nPackage: foo\nArchitecture: any\n",
+                std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+            );
+        }
+
+        #[test]
+        fn test_bump_compat_level_helper_arguments() {
+            let td = tempfile::tempdir().unwrap();
+            std::fs::create_dir_all(td.path().join("debian")).unwrap();
+            std::fs::write(
+                td.path().join("debian/control"),
+                "Source: foo\nX-DH-Compat: 9\nBuild-Depends: debhelper (>= 9)\n\nPackage: foo\nArchitecture: any\n",
+            )
+            .unwrap();
+            std::fs::write(
+                td.path().join("debian/rules"),
+                "#!/usr/bin/make -f\n\n%:\n\tdh $@\n\noverride_dh_installinit:\n\tdh_installinit --restart-after-upgrade\n\noverride_dh_missing:\n\tdh_missing --fail-missing\n",
+            )
+            .unwrap();
+            let bump = bump_compat_level(td.path(), 9, 13).unwrap();
+            assert_eq!(
+                vec![
+                    CompatAction::new(
+                        "Bump debhelper from old 9 to 13.".to_string(),
+                        Certainty::Possible
+                    ),
+                    CompatAction::new(
+                        "Drop dh_installinit --restart-after-upgrade, which is the default in compat 10.".to_string(),
+                        Certainty::Certain
+                    ),
+                    CompatAction::new(
+                        "Drop dh_missing --fail-missing, which is the default in compat 13.".to_string(),
+                        Certainty::Certain
+                    ),
+                ],
+                bump.actions
+            );
+            assert!(bump.follow_ups[0].contains("override_dh_installinit"));
+            let rules = std::fs::read_to_string(td.path().join("debian/rules")).unwrap();
+            assert!(rules.contains("\tdh $@\n"));
+            assert!(!rules.contains("override_dh_installinit"));
+            assert!(!rules.contains("override_dh_missing"));
+            assert!(std::fs::read_to_string(td.path().join("debian/control"))
+                .unwrap()
+                .contains("X-DH-Compat: 13\n"));
+
+            assert!(matches!(
+                bump_compat_level(td.path(), 13, 12),
+                Err(BumpCompatError::AlreadyAtLevel(13))
+            ));
+        }
+    }
 }
@@ -1,8 +1,6 @@
 //! Detect whether the changelog should be updated.
-use breezyshim::error::Error;
-use breezyshim::graph::Graph;
-use breezyshim::prelude::*;
-use breezyshim::revisionid::RevisionId;
+#[cfg(feature = "vcs")]
+use breezyshim::{error::Error, graph::Graph, prelude::*, revisionid::RevisionId};
 use debian_changelog::{ChangeLog, Entry as ChangeLogEntry};
 use lazy_regex::regex;
 
@@ -40,11 +38,13 @@ impl From<&ChangelogBehaviour> for (bool, String) {
 }
 
 // Number of revisions to search back
+#[cfg(feature = "vcs")]
 const DEFAULT_BACKLOG: usize = 50;
 
 // TODO(jelmer): Check that what's added in the changelog is actually based on
 // what was in the commit messages?
 
+#[cfg(feature = "vcs")]
 fn gbp_conf_has_dch_section(tree: &dyn Tree, debian_path: &std::path::Path) -> bool {
     let gbp_conf_path = debian_path.join("gbp.conf");
     let gbp_conf_text = match tree.get_file_text(gbp_conf_path.as_path()) {
@@ -63,8 +63,12 @@ fn gbp_conf_has_dch_section(tree: &dyn Tree, debian_path: &std::path::Path) -> b
         }
     };
 
+    gbp_conf_text_has_dch_section(&gbp_conf_path, gbp_conf_text.as_slice())
+}
+
+fn gbp_conf_text_has_dch_section(gbp_conf_path: &std::path::Path, text: &[u8]) -> bool {
     let mut parser = configparser::ini::Ini::new();
-    if let Err(e) = parser.read(String::from_utf8_lossy(gbp_conf_text.as_slice()).to_string()) {
+    if let Err(e) = parser.read(String::from_utf8_lossy(text).to_string()) {
         // Same logic for malformed INI: assume no `[dch]` section
         // rather than panic.
         log::warn!("Failed to parse {}: {}", gbp_conf_path.display(), e);
@@ -73,6 +77,30 @@ fn gbp_conf_has_dch_section(tree: &dyn Tree, debian_path: &std::path::Path) -> b
     parser.sections().contains(&"dch".to_string())
 }
 
+/// Guess from the contents of the changelog whether it should be updated.
+fn guess_update_changelog_from_changelog(cl: &ChangeLog) -> Option<ChangelogBehaviour> {
+    if debian_changelog::is_unreleased_inaugural(cl) {
+        return Some(ChangelogBehaviour {
+            update_changelog: false,
+            explanation: "assuming changelog does not need to be updated since it is the inaugural unreleased entry".to_string()
+        });
+    }
+    if let Some(first_entry) = cl.iter().next() {
+        for line in first_entry.change_lines() {
+            if line.contains("generated at release time") {
+                return Some(ChangelogBehaviour {
+                    update_changelog: false,
+                    explanation:
+                        "last changelog entry warns changelog is generated at release time"
+                            .to_string(),
+                });
+            }
+        }
+    }
+    None
+}
+
+#[cfg(feature = "vcs")]
 /// Guess whether the changelog should be updated.
 ///
 /// # Arguments
@@ -125,25 +153,8 @@ pub fn guess_update_changelog(
             }
         }
     }
-    if let Some(ref cl) = cl {
-        if debian_changelog::is_unreleased_inaugural(cl) {
-            return Some(ChangelogBehaviour {
-                update_changelog: false,
-                explanation: "assuming changelog does not need to be updated since it is the inaugural unreleased entry".to_string()
-            });
-        }
-        if let Some(first_entry) = cl.iter().next() {
-            for line in first_entry.change_lines() {
-                if line.contains("generated at release time") {
-                    return Some(ChangelogBehaviour {
-                        update_changelog: false,
-                        explanation:
-                            "last changelog entry warns changelog is generated at release time"
-                                .to_string(),
-                    });
-                }
-            }
-        }
+    if let Some(ret) = cl.as_ref().and_then(guess_update_changelog_from_changelog) {
+        return Some(ret);
     }
     if let Some(ret) = guess_update_changelog_from_tree(tree, debian_path, cl) {
         Some(ret)
@@ -152,6 +163,7 @@ pub fn guess_update_changelog(
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Guess whether the changelog should be updated by looking at tree contents
 pub fn guess_update_changelog_from_tree(
     tree: &dyn Tree,
@@ -180,6 +192,58 @@ pub fn guess_update_changelog_from_tree(
     None
 }
 
+/// Guess whether the changelog should be updated, for a package outside version control.
+///
+/// Only the contents of the package are considered, since there is no history to analyze.
+///
+/// # Arguments
+/// * `base` - Root of the package, containing the debian/ directory
+pub fn guess_update_changelog_from_directory(base: &std::path::Path) -> Option<ChangelogBehaviour> {
+    let changelog_path = base.join("debian/changelog");
+    let cl = match std::fs::File::open(&changelog_path) {
+        Ok(f) => match ChangeLog::read(f) {
+            Ok(cl) => Some(cl),
+            Err(e) => {
+                log::warn!("Failed to parse {}: {:?}", changelog_path.display(), e);
+                return None;
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            log::warn!(
+                "Unexpected error reading {}: {:?}",
+                changelog_path.display(),
+                e
+            );
+            return None;
+        }
+    };
+    if let Some(ret) = cl.as_ref().and_then(guess_update_changelog_from_changelog) {
+        return Some(ret);
+    }
+    let gbp_conf_path = base.join("debian/gbp.conf");
+    if let Ok(text) = std::fs::read(&gbp_conf_path) {
+        if gbp_conf_text_has_dch_section(&gbp_conf_path, &text) {
+            return Some(ChangelogBehaviour {
+                update_changelog: false,
+                explanation: "Assuming changelog does not need to be updated, since there is a [dch] section in gbp.conf.".to_string()
+            });
+        }
+    }
+    if cl
+        .as_ref()
+        .and_then(|cl| cl.iter().next())
+        .is_some_and(|entry| all_sha_prefixed(&entry))
+    {
+        return Some(ChangelogBehaviour {
+            update_changelog: false,
+            explanation: "Assuming changelog does not need to be updated, since all entries in last changelog entry are prefixed by git shas.".to_string()
+        });
+    }
+    None
+}
+
+#[cfg(feature = "vcs")]
 fn greedy_revisions(graph: &Graph, revid: &RevisionId, length: usize) -> (Vec<RevisionId>, bool) {
     let mut ret = vec![];
     let mut it = match graph.iter_lefthand_ancestry(revid, None) {
@@ -206,6 +270,7 @@ fn greedy_revisions(graph: &Graph, revid: &RevisionId, length: usize) -> (Vec<Re
     (ret, false)
 }
 
+#[cfg(feature = "vcs")]
 #[derive(Debug, Default)]
 struct ChangelogStats {
     mixed: usize,
@@ -215,6 +280,7 @@ struct ChangelogStats {
     unreleased_references: usize,
 }
 
+#[cfg(feature = "vcs")]
 fn changelog_stats(
     branch: &dyn Branch,
     history: usize,
@@ -299,6 +365,7 @@ fn changelog_stats(
     ret
 }
 
+#[cfg(feature = "vcs")]
 /// Guess whether the changelog should be updated manually.
 ///
 /// # Arguments
@@ -384,9 +451,13 @@ pub fn all_sha_prefixed(cb: &ChangeLogEntry) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "vcs")]
     use breezyshim::controldir::{create_standalone_workingtree, ControlDirFormat};
+    #[cfg(feature = "vcs")]
     use std::path::Path;
+    #[cfg(feature = "vcs")]
     pub const COMMITTER: &str = "Test User <test@example.com>";
+    #[cfg(feature = "vcs")]
     fn make_changelog(entries: Vec<String>) -> String {
         format!(
             r###"lintian-brush (0.1) UNRELEASED; urgency=medium
@@ -402,6 +473,7 @@ mod tests {
         )
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_no_gbp_conf() {
         let td = tempfile::tempdir().unwrap();
@@ -415,6 +487,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_custom_path() {
         let td = tempfile::tempdir().unwrap();
@@ -442,6 +515,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_gbp_conf_dch() {
         let td = tempfile::tempdir().unwrap();
@@ -464,6 +538,7 @@ pristine-tar = False
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_changelog_sha_prefixed() {
         let td = tempfile::tempdir().unwrap();
@@ -496,6 +571,7 @@ pristine-tar = False
         );
     }
 
+    #[cfg(feature = "vcs")]
     /// A debian/changelog the parser can't make sense of must not crash
     /// the whole codemod — return None so the caller treats it as
     /// "couldn't guess". Reproduces the panic seen on real worker
@@ -519,6 +595,7 @@ pristine-tar = False
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_empty() {
         let td = tempfile::tempdir().unwrap();
@@ -532,6 +609,7 @@ pristine-tar = False
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_update_with_change() {
         let td = tempfile::tempdir().unwrap();
@@ -578,6 +656,7 @@ pristine-tar = False
         }), guess_update_changelog(&tree, Path::new("debian"), None));
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_changelog_updated_separately() {
         let td = tempfile::tempdir().unwrap();
@@ -647,6 +726,7 @@ pristine-tar = False
         }), guess_update_changelog(&tree, Path::new("debian"), None));
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_has_dch_in_messages() {
         let td = tempfile::tempdir().unwrap();
@@ -664,6 +744,7 @@ pristine-tar = False
         }), guess_update_changelog(&tree, Path::new("debian"), None));
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_inaugural_unreleased() {
         let td = tempfile::tempdir().unwrap();
@@ -687,6 +768,7 @@ pristine-tar = False
         }), guess_update_changelog(&tree, Path::new("debian"), None));
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_last_entry_warns_generated() {
         let td = tempfile::tempdir().unwrap();
@@ -720,6 +802,7 @@ blah (0.20.1) unstable; urgency=medium
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_never_unreleased() {
         let td = tempfile::tempdir().unwrap();
@@ -786,4 +869,20 @@ blah (0.20.1) unstable; urgency=medium
             explanation: "Assuming changelog does not need to be updated, since it never uses UNRELEASED entries".to_string()
         }), guess_update_changelog(&tree, Path::new("debian"), None));
     }
+
+    #[test]
+    fn test_from_directory() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        assert_eq!(None, guess_update_changelog_from_directory(td.path()));
+        std::fs::write(
+            td.path().join("debian/gbp.conf"),
+            "[DEFAULT]\npristine-tar = true\n\n[dch]\nfull = True\n",
+        )
+        .unwrap();
+        assert_eq!(Some(ChangelogBehaviour {
+            update_changelog: false,
+            explanation: "Assuming changelog does not need to be updated, since there is a [dch] section in gbp.conf.".to_string()
+        }), guess_update_changelog_from_directory(td.path()));
+    }
 }
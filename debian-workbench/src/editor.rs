@@ -1,5 +1,7 @@
 //! Editing files
+#[cfg(feature = "vcs")]
 use breezyshim::error::Error as BrzError;
+#[cfg(feature = "vcs")]
 use breezyshim::tree::MutableTree;
 use std::borrow::Cow;
 use std::io::BufRead;
@@ -76,28 +78,35 @@ impl std::error::Error for FormattingUnpreservable {}
 impl FormattingUnpreservable {
     /// Get a unified diff of the original and rewritten contents.
     pub fn diff(&self) -> Vec<String> {
-        let original_lines = std::str::from_utf8(self.original_contents.as_deref().unwrap_or(b""))
-            .unwrap()
-            .split_inclusive('\n')
-            .collect::<Vec<_>>();
-        let rewritten_lines =
-            std::str::from_utf8(self.rewritten_contents.as_deref().unwrap_or(b""))
-                .unwrap()
-                .split_inclusive('\n')
-                .collect::<Vec<_>>();
-
-        difflib::unified_diff(
-            original_lines.as_slice(),
-            rewritten_lines.as_slice(),
-            "original",
-            "rewritten",
-            "",
-            "",
-            3,
+        round_trip_diff(
+            self.original_contents.as_deref(),
+            self.rewritten_contents.as_deref(),
         )
     }
 }
 
+/// Get a unified diff of the original contents of a file and the contents
+/// after a round-trip through our parser/serializer.
+fn round_trip_diff(
+    original_contents: Option<&[u8]>,
+    rewritten_contents: Option<&[u8]>,
+) -> Vec<String> {
+    let original = String::from_utf8_lossy(original_contents.unwrap_or(b""));
+    let rewritten = String::from_utf8_lossy(rewritten_contents.unwrap_or(b""));
+    let original_lines = original.split_inclusive('\n').collect::<Vec<_>>();
+    let rewritten_lines = rewritten.split_inclusive('\n').collect::<Vec<_>>();
+
+    difflib::unified_diff(
+        original_lines.as_slice(),
+        rewritten_lines.as_slice(),
+        "original",
+        "rewritten",
+        "",
+        "",
+        3,
+    )
+}
+
 /// Check that formatting can be preserved.
 ///
 /// # Arguments
@@ -159,6 +168,7 @@ fn check_template_exists(path: &std::path::Path) -> Option<(PathBuf, Option<Temp
     None
 }
 
+#[cfg(feature = "vcs")]
 fn tree_check_template_exists(
     tree: &dyn MutableTree,
     path: &std::path::Path,
@@ -204,6 +214,7 @@ pub fn check_generated_file(path: &std::path::Path) -> Result<(), GeneratedFile>
     Ok(())
 }
 
+#[cfg(feature = "vcs")]
 /// Check if a file is generated from another file.
 ///
 /// # Arguments
@@ -248,10 +259,12 @@ pub enum EditorError {
     /// I/O error
     IoError(std::io::Error),
 
+    #[cfg(feature = "vcs")]
     /// Breezy error
     BrzError(BrzError),
 }
 
+#[cfg(feature = "vcs")]
 impl From<BrzError> for EditorError {
     fn from(e: BrzError) -> Self {
         EditorError::BrzError(e)
@@ -268,6 +281,7 @@ impl std::fmt::Display for EditorError {
                 write!(f, "Unable to preserve formatting in {}", p.display())
             }
             EditorError::IoError(e) => write!(f, "I/O error: {}", e),
+            #[cfg(feature = "vcs")]
             EditorError::BrzError(e) => write!(f, "Breezy error: {}", e),
             EditorError::TemplateError(p, e) => {
                 write!(f, "Error in template {}: {}", p.display(), e)
@@ -422,6 +436,7 @@ pub fn edit_formatted_file(
     Ok(changed)
 }
 
+#[cfg(feature = "vcs")]
 /// Edit a formatted file in a tree.
 ///
 /// # Arguments
@@ -484,6 +499,80 @@ pub trait Marshallable {
     fn to_bytes(&self) -> Option<Vec<u8>>;
 }
 
+/// A change that committing an editor would make to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    /// Path to the file
+    pub path: PathBuf,
+
+    /// Contents before the change, or `None` if the file is created
+    pub old: Option<Vec<u8>>,
+
+    /// Contents after the change, or `None` if the file is removed
+    pub new: Option<Vec<u8>>,
+}
+
+impl FileChange {
+    /// Get a unified diff of the change, with `a/` and `b/` prefixes.
+    pub fn diff(&self) -> String {
+        String::from_utf8_lossy(&crate::patches::unified_diff_file(
+            &self.path,
+            self.old.as_deref(),
+            self.new.as_deref(),
+        ))
+        .into_owned()
+    }
+}
+
+/// Get a unified diff of a set of changes, e.g. for `--diff` output.
+pub fn unified_diff(changes: &[FileChange]) -> String {
+    changes.iter().map(|c| c.diff()).collect()
+}
+
+/// Compute the change that [`edit_formatted_file`] would make, without
+/// writing it.
+///
+/// # Arguments
+/// * `path` - Path to the file
+/// * `original_contents` - The original contents of the file
+/// * `rewritten_contents` - The contents rewritten with our parser/serializer
+/// * `updated_contents` - Updated contents rewritten with our parser/serializer after changes were
+///   made
+/// * `allow_generated` - Do not raise `GeneratedFile` when encountering a generated file
+/// * `allow_reformatting` - Whether to allow reformatting of the file
+/// * `check_generated` - Check whether the file is generated
+fn formatted_file_change(
+    path: &std::path::Path,
+    original_contents: Option<&[u8]>,
+    rewritten_contents: Option<&[u8]>,
+    updated_contents: Option<&[u8]>,
+    allow_generated: bool,
+    allow_reformatting: bool,
+    check_generated: impl FnOnce() -> Result<(), GeneratedFile>,
+) -> Result<Option<FileChange>, EditorError> {
+    if original_contents == updated_contents {
+        return Ok(None);
+    }
+    let (updated_contents, changed) = reformat_file(
+        original_contents,
+        rewritten_contents,
+        updated_contents,
+        allow_reformatting,
+    )
+    .map_err(|e| EditorError::FormattingUnpreservable(path.to_path_buf(), e))?;
+    if !changed {
+        return Ok(None);
+    }
+    if !allow_generated {
+        check_generated().map_err(|e| EditorError::GeneratedFile(path.to_path_buf(), e))?;
+    }
+    Ok(Some(FileChange {
+        path: path.to_path_buf(),
+        old: original_contents.map(|c| c.to_vec()),
+        new: updated_contents.map(|c| c.into_owned()),
+    }))
+}
+
 /// An editor for a file
 pub trait Editor<P: Marshallable>:
     std::ops::Deref<Target = P> + std::ops::DerefMut<Target = P>
@@ -502,16 +591,50 @@ pub trait Editor<P: Marshallable>:
         self.updated_content().as_deref() != self.rewritten_content()
     }
 
+    /// Whether the parser can represent everything in the original file.
+    ///
+    /// If this is false, writing out the parsed file would change parts of
+    /// it that were not edited. [`Editor::commit`] then only writes the
+    /// edited regions, and fails with [`EditorError::FormattingUnpreservable`]
+    /// if that is not possible, unless reformatting is allowed or the editor
+    /// was created with `force` set.
+    fn is_lossless(&self) -> bool {
+        self.orig_content() == self.rewritten_content()
+    }
+
+    /// Describe what the parser could not represent in the original file.
+    ///
+    /// # Returns
+    /// A unified diff between the original file and the file as written
+    /// out by the parser; empty if the editor is lossless
+    fn lost_information(&self) -> Vec<String> {
+        if self.is_lossless() {
+            return vec![];
+        }
+        round_trip_diff(self.orig_content(), self.rewritten_content())
+    }
+
     /// Check if the file is generated
     fn is_generated(&self) -> bool;
 
     /// Commit the changes
     ///
+    /// Parts of the file that were not edited are written back byte for
+    /// byte, even if the parser could not represent them.
+    ///
     /// # Returns
     /// A list of paths that were changed
     fn commit(&self) -> Result<Vec<std::path::PathBuf>, EditorError>;
+
+    /// Describe the changes that [`Editor::commit`] would make, without
+    /// writing them.
+    ///
+    /// # Returns
+    /// The changes, with the same paths as [`Editor::commit`] would return
+    fn pending_changes(&self) -> Result<Vec<FileChange>, EditorError>;
 }
 
+#[cfg(feature = "vcs")]
 /// Allow calling .edit_file("debian/control") on a tree
 pub trait MutableTreeEdit {
     /// Edit a file in a tree
@@ -523,6 +646,7 @@ pub trait MutableTreeEdit {
     ) -> Result<TreeEditor<'_, P>, EditorError>;
 }
 
+#[cfg(feature = "vcs")]
 impl<T: MutableTree> MutableTreeEdit for T {
     fn edit_file<P: Marshallable>(
         &self,
@@ -530,10 +654,11 @@ impl<T: MutableTree> MutableTreeEdit for T {
         allow_generated: bool,
         allow_reformatting: bool,
     ) -> Result<TreeEditor<'_, P>, EditorError> {
-        TreeEditor::new(self, path, allow_generated, allow_reformatting)
+        TreeEditor::new(self, path, allow_generated, allow_reformatting, false)
     }
 }
 
+#[cfg(feature = "vcs")]
 /// An editor for a file in a breezy tree
 pub struct TreeEditor<'a, P: Marshallable> {
     tree: &'a dyn MutableTree,
@@ -542,9 +667,11 @@ pub struct TreeEditor<'a, P: Marshallable> {
     rewritten_content: Option<Vec<u8>>,
     allow_generated: bool,
     allow_reformatting: bool,
+    force: bool,
     parsed: Option<P>,
 }
 
+#[cfg(feature = "vcs")]
 impl<P: Marshallable> std::ops::Deref for TreeEditor<'_, P> {
     type Target = P;
 
@@ -553,12 +680,14 @@ impl<P: Marshallable> std::ops::Deref for TreeEditor<'_, P> {
     }
 }
 
+#[cfg(feature = "vcs")]
 impl<P: Marshallable> std::ops::DerefMut for TreeEditor<'_, P> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.parsed.as_mut().unwrap()
     }
 }
 
+#[cfg(feature = "vcs")]
 impl<'a, P: Marshallable> TreeEditor<'a, P> {
     /// Create a new editor, with preferences being read from the environment
     pub fn from_env(
@@ -571,7 +700,7 @@ impl<'a, P: Marshallable> TreeEditor<'a, P> {
             std::env::var("REFORMATTING").unwrap_or("disallow".to_string()) == "allow"
         });
 
-        Self::new(tree, path, allow_generated, allow_reformatting)
+        Self::new(tree, path, allow_generated, allow_reformatting, false)
     }
 
     /// Read the file contents and parse them
@@ -590,11 +719,15 @@ impl<'a, P: Marshallable> TreeEditor<'a, P> {
     }
 
     /// Create a new editor
+    ///
+    /// If `force` is set, changes are written out even if the formatting of
+    /// the unedited parts of the file can not be preserved.
     pub fn new(
         tree: &'a dyn MutableTree,
         path: &std::path::Path,
         allow_generated: bool,
         allow_reformatting: bool,
+        force: bool,
     ) -> Result<Self, EditorError> {
         assert!(path.is_relative());
         let mut ret = Self {
@@ -604,6 +737,7 @@ impl<'a, P: Marshallable> TreeEditor<'a, P> {
             rewritten_content: None,
             allow_generated,
             allow_reformatting,
+            force,
             parsed: None,
         };
         ret.read()?;
@@ -611,6 +745,7 @@ impl<'a, P: Marshallable> TreeEditor<'a, P> {
     }
 }
 
+#[cfg(feature = "vcs")]
 impl<P: Marshallable> Editor<P> for TreeEditor<'_, P> {
     fn orig_content(&self) -> Option<&[u8]> {
         self.orig_content.as_deref()
@@ -624,6 +759,7 @@ impl<P: Marshallable> Editor<P> for TreeEditor<'_, P> {
         self.rewritten_content.as_deref()
     }
 
+    #[tracing::instrument(level = "debug", skip(self), fields(path = %self.path.display()))]
     fn commit(&self) -> Result<Vec<std::path::PathBuf>, EditorError> {
         let updated_content = self.updated_content();
 
@@ -634,7 +770,7 @@ impl<P: Marshallable> Editor<P> for TreeEditor<'_, P> {
             self.rewritten_content.as_deref(),
             updated_content.as_deref(),
             self.allow_generated,
-            self.allow_reformatting,
+            self.allow_reformatting || self.force,
         )?;
         if changed {
             Ok(vec![self.path.clone()])
@@ -643,6 +779,21 @@ impl<P: Marshallable> Editor<P> for TreeEditor<'_, P> {
         }
     }
 
+    fn pending_changes(&self) -> Result<Vec<FileChange>, EditorError> {
+        let updated_content = self.updated_content();
+        Ok(formatted_file_change(
+            &self.path,
+            self.orig_content.as_deref(),
+            self.rewritten_content.as_deref(),
+            updated_content.as_deref(),
+            self.allow_generated,
+            self.allow_reformatting || self.force,
+            || tree_check_generated_file(self.tree, &self.path),
+        )?
+        .into_iter()
+        .collect())
+    }
+
     fn is_generated(&self) -> bool {
         tree_check_generated_file(self.tree, &self.path).is_ok() || {
             let mut buf =
@@ -659,6 +810,7 @@ pub struct FsEditor<P: Marshallable> {
     rewritten_content: Option<Vec<u8>>,
     allow_generated: bool,
     allow_reformatting: bool,
+    force: bool,
     parsed: Option<P>,
 }
 
@@ -687,7 +839,7 @@ impl<P: Marshallable> FsEditor<P> {
             std::env::var("REFORMATTING").unwrap_or("disallow".to_string()) == "allow"
         });
 
-        Self::new(path, allow_generated, allow_reformatting)
+        Self::new(path, allow_generated, allow_reformatting, false)
     }
 
     /// Read the file contents and parse them
@@ -706,10 +858,14 @@ impl<P: Marshallable> FsEditor<P> {
     }
 
     /// Create a new editor
+    ///
+    /// If `force` is set, changes are written out even if the formatting of
+    /// the unedited parts of the file can not be preserved.
     pub fn new(
         path: &std::path::Path,
         allow_generated: bool,
         allow_reformatting: bool,
+        force: bool,
     ) -> Result<Self, EditorError> {
         let mut ret = Self {
             path: path.to_path_buf(),
@@ -717,6 +873,7 @@ impl<P: Marshallable> FsEditor<P> {
             rewritten_content: None,
             allow_generated,
             allow_reformatting,
+            force,
             parsed: None,
         };
         ret.read()?;
@@ -745,6 +902,7 @@ impl<P: Marshallable> Editor<P> for FsEditor<P> {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self), fields(path = %self.path.display()))]
     fn commit(&self) -> Result<Vec<std::path::PathBuf>, EditorError> {
         let updated_content = self.updated_content();
 
@@ -754,7 +912,7 @@ impl<P: Marshallable> Editor<P> for FsEditor<P> {
             self.rewritten_content.as_deref(),
             updated_content.as_deref(),
             self.allow_generated,
-            self.allow_reformatting,
+            self.allow_reformatting || self.force,
         )?;
         if changed {
             Ok(vec![self.path.clone()])
@@ -762,6 +920,21 @@ impl<P: Marshallable> Editor<P> for FsEditor<P> {
             Ok(vec![])
         }
     }
+
+    fn pending_changes(&self) -> Result<Vec<FileChange>, EditorError> {
+        let updated_content = self.updated_content();
+        Ok(formatted_file_change(
+            &self.path,
+            self.orig_content.as_deref(),
+            self.rewritten_content.as_deref(),
+            updated_content.as_deref(),
+            self.allow_generated,
+            self.allow_reformatting || self.force,
+            || check_generated_file(&self.path),
+        )?
+        .into_iter()
+        .collect())
+    }
 }
 
 impl Marshallable for debian_control::Control {
@@ -873,7 +1046,7 @@ impl Marshallable for crate::maintscripts::Maintscript {
         if self.is_empty() {
             None
         } else {
-            Some(self.to_string().into_bytes())
+            Some(format!("{}\n", self).into_bytes())
         }
     }
 }
@@ -1093,7 +1266,8 @@ mod tests {
     fn test_edit_create_file() {
         let td = tempfile::tempdir().unwrap();
 
-        let mut editor = FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false).unwrap();
+        let mut editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false, false).unwrap();
         assert!(!editor.has_changed());
         editor.inc_data();
         assert_eq!(editor.get_data(), Some(1));
@@ -1104,11 +1278,50 @@ mod tests {
         assert_eq!("1", std::fs::read_to_string(td.path().join("a")).unwrap());
     }
 
+    #[test]
+    fn test_lossless() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(td.path().join("a"), "1").unwrap();
+
+        let editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false, false).unwrap();
+        assert!(editor.is_lossless());
+        assert!(editor.lost_information().is_empty());
+    }
+
+    #[test]
+    fn test_lossy_refuses_destructive_write() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(td.path().join("a"), "01").unwrap();
+
+        let mut editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false, false).unwrap();
+        assert!(!editor.is_lossless());
+        let lost = editor.lost_information();
+        assert_eq!(
+            vec!["--- original\t\n", "+++ rewritten\t\n", "@@ -1 +1 @@\n"],
+            lost[..3]
+        );
+        editor.inc_data();
+        assert!(matches!(
+            editor.commit().unwrap_err(),
+            EditorError::FormattingUnpreservable(..)
+        ));
+        assert_eq!("01", std::fs::read_to_string(td.path().join("a")).unwrap());
+
+        let mut editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, true, false).unwrap();
+        editor.inc_data();
+        assert_eq!(editor.commit().unwrap(), vec![td.path().join("a")]);
+        assert_eq!("2", std::fs::read_to_string(td.path().join("a")).unwrap());
+    }
+
     #[test]
     fn test_edit_create_no_changes() {
         let td = tempfile::tempdir().unwrap();
 
-        let editor = FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false).unwrap();
+        let editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false, false).unwrap();
         assert!(!editor.has_changed());
         assert_eq!(editor.commit().unwrap(), Vec::<std::path::PathBuf>::new());
         assert_eq!(editor.get_data(), None);
@@ -1120,7 +1333,8 @@ mod tests {
         let td = tempfile::tempdir().unwrap();
         std::fs::write(td.path().join("a"), "1").unwrap();
 
-        let mut editor = FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false).unwrap();
+        let mut editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false, false).unwrap();
         assert!(!editor.has_changed());
         editor.inc_data();
         assert_eq!(editor.get_data(), Some(2));
@@ -1136,7 +1350,8 @@ mod tests {
         let td = tempfile::tempdir().unwrap();
         std::fs::write(td.path().join("a"), "1").unwrap();
 
-        let mut editor = FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false).unwrap();
+        let mut editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false, false).unwrap();
         assert!(!editor.has_changed());
         editor.unset_data();
         assert_eq!(editor.get_data(), None);
@@ -1147,6 +1362,31 @@ mod tests {
         assert!(!td.path().join("a").exists());
     }
 
+    #[test]
+    fn test_pending_changes() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(td.path().join("a"), "1").unwrap();
+
+        let mut editor =
+            FsEditor::<TestMarshall>::new(&td.path().join("a"), false, false, false).unwrap();
+        assert_eq!(editor.pending_changes().unwrap(), vec![]);
+        editor.inc_data();
+        let changes = editor.pending_changes().unwrap();
+        assert_eq!(
+            changes,
+            vec![FileChange {
+                path: td.path().join("a"),
+                old: Some(b"1".to_vec()),
+                new: Some(b"2".to_vec()),
+            }]
+        );
+        let diff = unified_diff(&changes);
+        assert!(diff.contains("\n-1\n"));
+        assert!(diff.contains("\n+2\n"));
+        assert_eq!("1", std::fs::read_to_string(td.path().join("a")).unwrap());
+    }
+
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_tree_editor_edit() {
         use breezyshim::controldir::{create_standalone_workingtree, ControlDirFormat};
@@ -1171,6 +1411,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_tree_edit_control() {
         use breezyshim::controldir::{create_standalone_workingtree, ControlDirFormat};
@@ -1227,6 +1468,7 @@ Multi-Arch: foreign
             &td.path().join("debian/control"),
             false,
             false,
+            false,
         )
         .unwrap();
         editor.source.homepage = Some("https://example.com".parse().unwrap());
@@ -0,0 +1,154 @@
+//! Errors shared by the APIs of this crate.
+//!
+//! Errors are categorised by their cause: problems with the package being
+//! processed, such as an unparseable file, can be reported back to the
+//! package maintainer, while problems with the environment, such as a
+//! missing tool, need to be fixed by whoever is running the code.
+#[cfg(feature = "vcs")]
+use breezyshim::error::Error as BrzError;
+use std::path::PathBuf;
+
+/// The cause of an [`Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// The package being processed is malformed or unsupported.
+    Package,
+
+    /// The environment is broken, e.g. a tool or data file is missing.
+    Environment,
+}
+
+/// Error returned by the APIs of this crate.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// I/O error
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// A file in the package could not be parsed.
+    #[error("Unable to parse {}: {message}", path.display())]
+    Parse {
+        /// Path of the file
+        path: PathBuf,
+
+        /// Description of the problem
+        message: String,
+    },
+
+    /// A version in the package is invalid.
+    #[error("Invalid version {version:?}: {message}")]
+    InvalidVersion {
+        /// The version as found in the package
+        version: String,
+
+        /// Description of the problem
+        message: String,
+    },
+
+    /// The distro-info data could not be loaded.
+    #[error("Unable to load distro-info data: {0}")]
+    DistroInfo(String),
+
+    /// An external command failed.
+    #[error("Unable to run {command}: {message}")]
+    Command {
+        /// Name of the command
+        command: String,
+
+        /// Description of the problem
+        message: String,
+    },
+
+    /// No maintainer identity is configured, e.g. in a build chroot.
+    #[error("Unable to determine maintainer identity; set DEBFULLNAME and DEBEMAIL")]
+    NoMaintainer,
+
+    /// Error editing a file.
+    #[error(transparent)]
+    Editor(Box<crate::editor::EditorError>),
+
+    /// Breezy error
+    #[cfg(feature = "vcs")]
+    #[error("Breezy error: {0}")]
+    Brz(Box<BrzError>),
+}
+
+// The editor and Breezy errors are boxed to keep `Result<_, Error>` small.
+impl From<crate::editor::EditorError> for Error {
+    fn from(e: crate::editor::EditorError) -> Self {
+        Error::Editor(Box::new(e))
+    }
+}
+
+#[cfg(feature = "vcs")]
+impl From<BrzError> for Error {
+    fn from(e: BrzError) -> Self {
+        Error::Brz(Box::new(e))
+    }
+}
+
+impl Error {
+    /// The cause of this error.
+    pub fn category(&self) -> ErrorCategory {
+        use crate::editor::EditorError;
+        match self {
+            Error::Editor(e) => match e.as_ref() {
+                EditorError::GeneratedFile(..)
+                | EditorError::TemplateError(..)
+                | EditorError::FormattingUnpreservable(..) => ErrorCategory::Package,
+                EditorError::IoError(_) => ErrorCategory::Environment,
+                #[cfg(feature = "vcs")]
+                EditorError::BrzError(_) => ErrorCategory::Environment,
+            },
+            Error::Parse { .. } | Error::InvalidVersion { .. } => ErrorCategory::Package,
+            Error::Io(_) | Error::DistroInfo(_) | Error::Command { .. } | Error::NoMaintainer => {
+                ErrorCategory::Environment
+            }
+            #[cfg(feature = "vcs")]
+            Error::Brz(_) => ErrorCategory::Environment,
+        }
+    }
+
+    /// Whether this error is caused by the package being processed.
+    pub fn is_package_error(&self) -> bool {
+        self.category() == ErrorCategory::Package
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category() {
+        assert_eq!(
+            ErrorCategory::Package,
+            Error::Parse {
+                path: PathBuf::from("debian/debcargo.toml"),
+                message: "expected `=`".to_string(),
+            }
+            .category()
+        );
+        assert_eq!(
+            ErrorCategory::Environment,
+            Error::Command {
+                command: "dh_assistant".to_string(),
+                message: "not found".to_string(),
+            }
+            .category()
+        );
+        assert!(!Error::DistroInfo("missing".to_string()).is_package_error());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "Unable to parse debian/debcargo.toml: expected `=`",
+            Error::Parse {
+                path: PathBuf::from("debian/debcargo.toml"),
+                message: "expected `=`".to_string(),
+            }
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,445 @@
+//! Lossless editing of git-buildpackage configuration files.
+use std::path::{Path, PathBuf};
+
+/// Locations of the git-buildpackage configuration in a source tree, in order of preference.
+pub const GBP_CONF_PATHS: &[&str] = &["debian/gbp.conf", ".gbp.conf"];
+
+/// Name of the section with settings that apply to all commands
+pub const DEFAULT_SECTION: &str = "DEFAULT";
+
+/// Find the git-buildpackage configuration file of a source tree.
+///
+/// # Returns
+/// The first of [`GBP_CONF_PATHS`] that exists, or debian/gbp.conf if none does
+pub fn find_gbp_conf(base: &Path) -> PathBuf {
+    GBP_CONF_PATHS
+        .iter()
+        .map(|p| base.join(p))
+        .find(|p| p.exists())
+        .unwrap_or_else(|| base.join(GBP_CONF_PATHS[0]))
+}
+
+/// Normalize an option name; gbp treats dashes and underscores alike.
+fn normalize_key(key: &str) -> String {
+    key.trim().to_lowercase().replace('_', "-")
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "yes" | "true" | "on" => Some(true),
+        "0" | "no" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A line in a gbp.conf file
+enum Line {
+    /// A comment or blank line, kept verbatim
+    Verbatim(String),
+    /// An option, with the original text (including continuation lines) if
+    /// it has not been changed
+    Option {
+        key: String,
+        value: String,
+        text: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A section of a gbp.conf file
+struct Section {
+    /// Name of the section; None for lines before the first section header
+    name: Option<String>,
+    /// The section header, as it appeared in the file
+    header: Option<String>,
+    lines: Vec<Line>,
+}
+
+/// A git-buildpackage configuration file (debian/gbp.conf), preserving
+/// comments and formatting.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GbpConf {
+    sections: Vec<Section>,
+}
+
+impl GbpConf {
+    /// Create a new, empty configuration
+    pub fn new() -> Self {
+        GbpConf { sections: vec![] }
+    }
+
+    /// Check if the configuration has no options
+    pub fn is_empty(&self) -> bool {
+        self.sections
+            .iter()
+            .all(|s| s.lines.iter().all(|l| matches!(l, Line::Verbatim(_))))
+    }
+
+    /// Names of the sections in the file
+    pub fn sections(&self) -> Vec<&str> {
+        self.sections
+            .iter()
+            .filter_map(|s| s.name.as_deref())
+            .collect()
+    }
+
+    fn section(&self, name: &str) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|s| s.name.as_deref() == Some(name))
+    }
+
+    /// Get the value of an option in a section.
+    ///
+    /// # Arguments
+    /// * `section` - Name of the section, e.g. "DEFAULT" or "buildpackage"
+    /// * `key` - Name of the option
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        let key = normalize_key(key);
+        self.section(section)?
+            .lines
+            .iter()
+            .rev()
+            .find_map(|l| match l {
+                Line::Option { key: k, value, .. } if normalize_key(k) == key => {
+                    Some(value.as_str())
+                }
+                _ => None,
+            })
+    }
+
+    /// Get the value of an option as it applies to a gbp command.
+    ///
+    /// The command's own section takes precedence over its legacy
+    /// "git-" prefixed name and over the DEFAULT section.
+    ///
+    /// # Arguments
+    /// * `command` - Name of the command, e.g. "buildpackage" or "pq"
+    /// * `key` - Name of the option
+    pub fn get_for_command(&self, command: &str, key: &str) -> Option<&str> {
+        self.get(command, key)
+            .or_else(|| self.get(&format!("git-{}", command), key))
+            .or_else(|| self.get(DEFAULT_SECTION, key))
+    }
+
+    /// Set the value of an option, adding the section if necessary.
+    ///
+    /// # Returns
+    /// Whether the file was changed
+    pub fn set(&mut self, section: &str, key: &str, value: &str) -> bool {
+        let normalized = normalize_key(key);
+        if self.get(section, key) == Some(value) {
+            return false;
+        }
+        let index = match self
+            .sections
+            .iter()
+            .position(|s| s.name.as_deref() == Some(section))
+        {
+            Some(index) => index,
+            None => {
+                if let Some(last) = self.sections.last_mut() {
+                    if !matches!(last.lines.last(), Some(Line::Verbatim(l)) if l.trim().is_empty())
+                    {
+                        last.lines.push(Line::Verbatim(String::new()));
+                    }
+                }
+                self.sections.push(Section {
+                    name: Some(section.to_string()),
+                    header: None,
+                    lines: vec![],
+                });
+                self.sections.len() - 1
+            }
+        };
+        let lines = &mut self.sections[index].lines;
+        for line in lines.iter_mut().rev() {
+            if let Line::Option {
+                key: k,
+                value: v,
+                text,
+            } = line
+            {
+                if normalize_key(k) == normalized {
+                    *v = value.to_string();
+                    *text = None;
+                    return true;
+                }
+            }
+        }
+        // Insert after the last option, so trailing blank lines and
+        // comments stay between this section and the next.
+        let position = lines
+            .iter()
+            .rposition(|l| matches!(l, Line::Option { .. }))
+            .map_or(0, |i| i + 1);
+        lines.insert(
+            position,
+            Line::Option {
+                key: key.to_string(),
+                value: value.to_string(),
+                text: None,
+            },
+        );
+        true
+    }
+
+    /// Remove an option from a section.
+    ///
+    /// # Returns
+    /// Whether the option was present
+    pub fn remove(&mut self, section: &str, key: &str) -> bool {
+        let key = normalize_key(key);
+        let Some(section) = self
+            .sections
+            .iter_mut()
+            .find(|s| s.name.as_deref() == Some(section))
+        else {
+            return false;
+        };
+        let len = section.lines.len();
+        section
+            .lines
+            .retain(|l| !matches!(l, Line::Option { key: k, .. } if normalize_key(k) == key));
+        section.lines.len() != len
+    }
+
+    /// The branch that contains the packaging
+    pub fn debian_branch(&self) -> Option<&str> {
+        self.get(DEFAULT_SECTION, "debian-branch")
+    }
+
+    /// Set the branch that contains the packaging
+    pub fn set_debian_branch(&mut self, branch: &str) -> bool {
+        self.set(DEFAULT_SECTION, "debian-branch", branch)
+    }
+
+    /// The branch that contains the upstream sources
+    pub fn upstream_branch(&self) -> Option<&str> {
+        self.get(DEFAULT_SECTION, "upstream-branch")
+    }
+
+    /// Set the branch that contains the upstream sources
+    pub fn set_upstream_branch(&mut self, branch: &str) -> bool {
+        self.set(DEFAULT_SECTION, "upstream-branch", branch)
+    }
+
+    /// Whether upstream tarballs are stored with pristine-tar
+    pub fn pristine_tar(&self) -> Option<bool> {
+        self.get(DEFAULT_SECTION, "pristine-tar")
+            .and_then(parse_bool)
+    }
+
+    /// Set whether upstream tarballs are stored with pristine-tar
+    pub fn set_pristine_tar(&mut self, enabled: bool) -> bool {
+        self.set(
+            DEFAULT_SECTION,
+            "pristine-tar",
+            if enabled { "True" } else { "False" },
+        )
+    }
+
+    /// Whether `gbp pq` numbers the patches it exports
+    pub fn patch_numbers(&self) -> Option<bool> {
+        self.get_for_command("pq", "patch-numbers")
+            .and_then(parse_bool)
+    }
+
+    /// Set whether `gbp pq` numbers the patches it exports
+    pub fn set_patch_numbers(&mut self, enabled: bool) -> bool {
+        let section = if self.get("pq", "patch-numbers").is_some() {
+            "pq"
+        } else {
+            DEFAULT_SECTION
+        };
+        self.set(
+            section,
+            "patch-numbers",
+            if enabled { "True" } else { "False" },
+        )
+    }
+}
+
+impl std::fmt::Display for GbpConf {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for section in &self.sections {
+            match (&section.header, &section.name) {
+                (Some(header), _) => writeln!(f, "{}", header)?,
+                (None, Some(name)) => writeln!(f, "[{}]", name)?,
+                (None, None) => {}
+            }
+            for line in &section.lines {
+                match line {
+                    Line::Verbatim(text)
+                    | Line::Option {
+                        text: Some(text), ..
+                    } => writeln!(f, "{}", text)?,
+                    Line::Option {
+                        key,
+                        value,
+                        text: None,
+                    } => writeln!(f, "{} = {}", key, value)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for GbpConf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sections = vec![Section {
+            name: None,
+            header: None,
+            lines: vec![],
+        }];
+        for l in s.lines() {
+            let trimmed = l.trim();
+            let section = sections.last_mut().unwrap();
+            if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with(';') {
+                section.lines.push(Line::Verbatim(l.to_string()));
+            } else if l.starts_with(char::is_whitespace) {
+                // Continuation of the previous value
+                match section.lines.last_mut() {
+                    Some(Line::Option {
+                        value,
+                        text: Some(text),
+                        ..
+                    }) => {
+                        value.push('\n');
+                        value.push_str(trimmed);
+                        text.push('\n');
+                        text.push_str(l);
+                    }
+                    _ => return Err(format!("Unexpected continuation line: {}", l)),
+                }
+            } else if let Some(name) = trimmed.strip_prefix('[').and_then(|n| n.strip_suffix(']')) {
+                sections.push(Section {
+                    name: Some(name.trim().to_string()),
+                    header: Some(l.to_string()),
+                    lines: vec![],
+                });
+            } else if let Some((key, value)) = l.split_once(['=', ':']) {
+                section.lines.push(Line::Option {
+                    key: key.trim().to_string(),
+                    value: value.trim().to_string(),
+                    text: Some(l.to_string()),
+                });
+            } else {
+                return Err(format!("Invalid line: {}", l));
+            }
+        }
+        Ok(GbpConf { sections })
+    }
+}
+
+impl crate::editor::Marshallable for GbpConf {
+    fn from_bytes(content: &[u8]) -> Self {
+        std::str::from_utf8(content).unwrap().parse().unwrap()
+    }
+
+    fn empty() -> Self {
+        GbpConf::new()
+    }
+
+    fn to_bytes(&self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.to_string().into_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let text = "# Configuration\n[DEFAULT]\ndebian-branch = debian/latest\nupstream_branch: upstream\npristine-tar = True\n\n[pq]\npatch-numbers = False\n\n[dch]\nmulti-main = True\ngit-log = --no-merges\n  --first-parent\n";
+        let conf: GbpConf = text.parse().unwrap();
+        assert_eq!(text, conf.to_string());
+        assert_eq!(vec!["DEFAULT", "pq", "dch"], conf.sections());
+        assert_eq!(Some("debian/latest"), conf.debian_branch());
+        assert_eq!(Some("upstream"), conf.upstream_branch());
+        assert_eq!(Some(true), conf.pristine_tar());
+        assert_eq!(Some(false), conf.patch_numbers());
+        assert_eq!(
+            Some("--no-merges\n--first-parent"),
+            conf.get_for_command("dch", "git-log")
+        );
+        assert_eq!(
+            Some("debian/latest"),
+            conf.get_for_command("buildpackage", "debian-branch")
+        );
+        assert!("[DEFAULT]\nnonsense\n".parse::<GbpConf>().is_err());
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut conf: GbpConf = "[DEFAULT]\n# The packaging branch\ndebian-branch = master\n\n[pq]\npatch-numbers = False\n"
+            .parse()
+            .unwrap();
+        assert!(conf.set_debian_branch("debian/latest"));
+        assert!(!conf.set_debian_branch("debian/latest"));
+        assert!(conf.set_upstream_branch("upstream/latest"));
+        assert!(conf.set_patch_numbers(true));
+        assert!(conf.set("buildpackage", "sign-tags", "True"));
+        assert_eq!(
+            "[DEFAULT]\n# The packaging branch\ndebian-branch = debian/latest\nupstream-branch = upstream/latest\n\n[pq]\npatch-numbers = True\n\n[buildpackage]\nsign-tags = True\n",
+            conf.to_string()
+        );
+        assert!(conf.remove("pq", "patch_numbers"));
+        assert!(!conf.remove("pq", "patch_numbers"));
+        assert_eq!(None, conf.patch_numbers());
+    }
+
+    #[test]
+    fn test_new() {
+        let mut conf = GbpConf::new();
+        assert!(conf.is_empty());
+        assert!(conf.set_pristine_tar(true));
+        assert_eq!("[DEFAULT]\npristine-tar = True\n", conf.to_string());
+    }
+
+    #[test]
+    fn test_find_gbp_conf() {
+        let td = tempfile::tempdir().unwrap();
+        assert_eq!(td.path().join("debian/gbp.conf"), find_gbp_conf(td.path()));
+        std::fs::write(td.path().join(".gbp.conf"), "[DEFAULT]\n").unwrap();
+        assert_eq!(td.path().join(".gbp.conf"), find_gbp_conf(td.path()));
+    }
+
+    #[test]
+    fn test_edit_lossy() {
+        use crate::editor::{Editor, EditorError, FsEditor};
+        let td = tempfile::tempdir().unwrap();
+        let path = td.path().join("gbp.conf");
+        // Line endings are normalized, so this file does not round-trip
+        std::fs::write(&path, "[DEFAULT]\r\npristine-tar = True\r\n").unwrap();
+
+        let mut editor = FsEditor::<GbpConf>::new(&path, false, false, false).unwrap();
+        assert!(!editor.is_lossless());
+        assert!(editor.set_pristine_tar(false));
+        assert!(matches!(
+            editor.commit().unwrap_err(),
+            EditorError::FormattingUnpreservable(..)
+        ));
+        assert_eq!(
+            "[DEFAULT]\r\npristine-tar = True\r\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+
+        let mut editor = FsEditor::<GbpConf>::new(&path, false, false, true).unwrap();
+        assert!(editor.set_pristine_tar(false));
+        assert_eq!(vec![path.clone()], editor.commit().unwrap());
+        assert_eq!(
+            "[DEFAULT]\npristine-tar = False\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+    }
+}
@@ -0,0 +1,721 @@
+//! Editing of dh_install files (debian/*.install).
+use std::path::{Path, PathBuf};
+
+/// Interpreter line used by executable install files that are run through dh-exec.
+pub const DH_EXEC_SHEBANG: &str = "#!/usr/bin/dh-exec";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An entry in an install file.
+pub struct InstallEntry {
+    /// Architecture restriction, as supported by dh-exec, e.g. "linux-any"
+    pub architectures: Option<String>,
+    /// Paths or globs of the files to install
+    pub sources: Vec<String>,
+    /// Directory to install the files into, relative to the package root
+    pub destination: Option<String>,
+    /// Whether this is a dh-exec rename (`source => destination`), in which
+    /// case the destination is the new path of the file
+    pub rename: bool,
+}
+
+impl InstallEntry {
+    /// Create an entry that installs files into their default location.
+    pub fn new(sources: &[&str]) -> Self {
+        InstallEntry {
+            architectures: None,
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+            destination: None,
+            rename: false,
+        }
+    }
+
+    /// Create an entry that installs files into a specific directory.
+    pub fn with_destination(sources: &[&str], destination: &str) -> Self {
+        InstallEntry {
+            destination: Some(destination.to_string()),
+            ..Self::new(sources)
+        }
+    }
+
+    /// Create a dh-exec entry that installs a file under a different name.
+    pub fn rename(source: &str, destination: &str) -> Self {
+        InstallEntry {
+            rename: true,
+            ..Self::with_destination(&[source], destination)
+        }
+    }
+}
+
+impl std::fmt::Display for InstallEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(architectures) = self.architectures.as_ref() {
+            write!(f, "[{}] ", architectures)?;
+        }
+        write!(f, "{}", self.sources.join(" "))?;
+        match (self.destination.as_ref(), self.rename) {
+            (Some(destination), true) => write!(f, " => {}", destination),
+            (Some(destination), false) => write!(f, " {}", destination),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+impl std::str::FromStr for InstallEntry {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut line = line.trim();
+        let mut architectures = None;
+        if let Some(rest) = line.strip_prefix('[') {
+            let (archs, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| format!("Unterminated architecture restriction: {}", line))?;
+            architectures = Some(archs.trim().to_string());
+            line = rest.trim_start();
+        }
+        if let Some((source, destination)) = line.split_once("=>") {
+            return Ok(InstallEntry {
+                architectures,
+                sources: vec![source.trim().to_string()],
+                destination: Some(destination.trim().to_string()),
+                rename: true,
+            });
+        }
+        let mut parts = line
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        if parts.is_empty() {
+            return Err("Empty install entry".to_string());
+        }
+        let destination = if parts.len() > 1 { parts.pop() } else { None };
+        Ok(InstallEntry {
+            architectures,
+            sources: parts,
+            destination,
+            rename: false,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A line in an install file
+enum Line {
+    /// A comment, blank line or interpreter line, kept verbatim
+    Verbatim(String),
+    /// An entry, with its original text if it has not been changed
+    Entry(InstallEntry, Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// A dh_install file
+pub struct InstallFile {
+    lines: Vec<Line>,
+}
+
+impl InstallFile {
+    /// Create a new, empty install file
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if the install file has no entries
+    pub fn is_empty(&self) -> bool {
+        !self.lines.iter().any(|l| matches!(l, Line::Entry(..)))
+    }
+
+    /// Check whether the file is run through dh-exec.
+    pub fn is_dh_exec(&self) -> bool {
+        matches!(self.lines.first(), Some(Line::Verbatim(l)) if l.starts_with("#!") && l.contains("dh-exec"))
+    }
+
+    /// Iterate over the entries in the install file
+    pub fn entries(&self) -> impl Iterator<Item = &InstallEntry> + '_ {
+        self.lines.iter().filter_map(|l| match l {
+            Line::Entry(e, _) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Check whether a path is installed by one of the entries.
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries().any(|e| e.sources.iter().any(|s| s == path))
+    }
+
+    /// Add an entry, unless an identical one is already present.
+    ///
+    /// Adding a rename turns the file into a dh-exec script; the caller is
+    /// responsible for making the file executable.
+    ///
+    /// # Returns
+    /// Whether the entry was added
+    pub fn add(&mut self, entry: InstallEntry) -> bool {
+        if self.entries().any(|e| *e == entry) {
+            return false;
+        }
+        if (entry.rename || entry.architectures.is_some()) && !self.is_dh_exec() {
+            self.lines
+                .insert(0, Line::Verbatim(DH_EXEC_SHEBANG.to_string()));
+        }
+        self.lines.push(Line::Entry(entry, None));
+        true
+    }
+
+    /// Stop installing a path.
+    ///
+    /// The path is dropped from the entries that list it; entries that are left
+    /// without sources are removed.
+    ///
+    /// # Returns
+    /// Whether any entry was changed
+    pub fn remove(&mut self, path: &str) -> bool {
+        let mut changed = false;
+        self.lines.retain_mut(|line| {
+            let Line::Entry(entry, text) = line else {
+                return true;
+            };
+            if !entry.sources.iter().any(|s| s == path) {
+                return true;
+            }
+            changed = true;
+            entry.sources.retain(|s| s != path);
+            *text = None;
+            !entry.sources.is_empty()
+        });
+        changed
+    }
+
+    /// Change the destination of the entries that install a path.
+    ///
+    /// # Returns
+    /// Whether any entry was changed
+    pub fn set_destination(&mut self, path: &str, destination: Option<&str>) -> bool {
+        let mut changed = false;
+        for line in self.lines.iter_mut() {
+            if let Line::Entry(entry, text) = line {
+                if entry.sources.iter().any(|s| s == path)
+                    && entry.destination.as_deref() != destination
+                {
+                    entry.destination = destination.map(|d| d.to_string());
+                    *text = None;
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+impl std::fmt::Display for InstallFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Verbatim(text) | Line::Entry(_, Some(text)) => writeln!(f, "{}", text)?,
+                Line::Entry(entry, None) => writeln!(f, "{}", entry)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for InstallFile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s
+            .lines()
+            .map(|l| {
+                if l.starts_with('#') || l.trim().is_empty() {
+                    Ok(Line::Verbatim(l.to_string()))
+                } else {
+                    Ok(Line::Entry(l.parse()?, Some(l.to_string())))
+                }
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(InstallFile { lines })
+    }
+}
+
+impl crate::editor::Marshallable for InstallFile {
+    fn from_bytes(content: &[u8]) -> Self {
+        std::str::from_utf8(content).unwrap().parse().unwrap()
+    }
+
+    fn empty() -> Self {
+        InstallFile::new()
+    }
+
+    fn to_bytes(&self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.to_string().into_bytes())
+        }
+    }
+}
+
+/// Suffixes of the debhelper files that are specific to a binary package.
+pub const PACKAGE_FILE_SUFFIXES: &[&str] = &[
+    "bash-completion",
+    "config",
+    "cron.d",
+    "cron.daily",
+    "cron.hourly",
+    "cron.monthly",
+    "cron.weekly",
+    "default",
+    "dirs",
+    "doc-base",
+    "docs",
+    "examples",
+    "info",
+    "init",
+    "install",
+    "links",
+    "lintian-overrides",
+    "logrotate",
+    "maintscript",
+    "manpages",
+    "menu",
+    "mime",
+    "postinst",
+    "postrm",
+    "preinst",
+    "prerm",
+    "service",
+    "shlibs",
+    "socket",
+    "symbols",
+    "templates",
+    "timer",
+    "tmpfiles",
+    "triggers",
+    "udev",
+];
+
+/// Rename the debhelper files of a binary package, e.g. after the package
+/// itself has been renamed.
+///
+/// Architecture-specific variants such as `foo.symbols.amd64` are renamed too.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `old` - Old name of the binary package
+/// * `new` - New name of the binary package
+///
+/// # Returns
+/// The old and new paths of the renamed files, relative to `base`
+pub fn rename_package_files(
+    base: &Path,
+    old: &str,
+    new: &str,
+) -> std::io::Result<Vec<(PathBuf, PathBuf)>> {
+    let debian = Path::new("debian");
+    let mut ret = vec![];
+    let prefix = format!("{}.", old);
+    let mut names = std::fs::read_dir(base.join(debian))?
+        .map(|e| e.map(|e| e.file_name().to_string_lossy().into_owned()))
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    for name in names {
+        let Some(suffix) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        let known = PACKAGE_FILE_SUFFIXES.iter().any(|s| {
+            suffix == *s
+                || suffix
+                    .strip_prefix(s)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        });
+        if !known {
+            continue;
+        }
+        let new_name = format!("{}.{}", new, suffix);
+        if base.join(debian).join(&new_name).exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("debian/{} already exists", new_name),
+            ));
+        }
+        std::fs::rename(
+            base.join(debian).join(&name),
+            base.join(debian).join(&new_name),
+        )?;
+        ret.push((debian.join(name), debian.join(new_name)));
+    }
+    Ok(ret)
+}
+
+/// Match a path against a shell glob, as used in install files.
+///
+/// `*` and `?` do not match `/`.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[char], path: &[char]) -> bool {
+        match pattern.first() {
+            None => path.is_empty(),
+            Some('*') => (0..=path.len())
+                .take_while(|&i| i == 0 || path[i - 1] != '/')
+                .any(|i| matches(&pattern[1..], &path[i..])),
+            Some('?') => !path.is_empty() && path[0] != '/' && matches(&pattern[1..], &path[1..]),
+            Some('[') => {
+                let Some(end) = pattern.iter().skip(1).position(|&c| c == ']') else {
+                    return path.first() == Some(&'[') && matches(&pattern[1..], &path[1..]);
+                };
+                let class = &pattern[1..end + 1];
+                let (negate, class) = match class.first() {
+                    Some('!') | Some('^') => (true, &class[1..]),
+                    _ => (false, class),
+                };
+                let Some(&c) = path.first() else {
+                    return false;
+                };
+                let mut found = false;
+                let mut i = 0;
+                while i < class.len() {
+                    if i + 2 < class.len() && class[i + 1] == '-' {
+                        found |= class[i] <= c && c <= class[i + 2];
+                        i += 3;
+                    } else {
+                        found |= class[i] == c;
+                        i += 1;
+                    }
+                }
+                found != negate && matches(&pattern[end + 2..], &path[1..])
+            }
+            Some(&p) => path.first() == Some(&p) && matches(&pattern[1..], &path[1..]),
+        }
+    }
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let path = path.chars().collect::<Vec<_>>();
+    matches(&pattern, &path)
+}
+
+/// Check whether an install file pattern covers a path in debian/tmp, either
+/// directly or by naming one of its parent directories.
+fn pattern_covers(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_start_matches("debian/tmp/").trim_matches('/');
+    let mut prefix = path;
+    loop {
+        if glob_match(pattern, prefix) {
+            return true;
+        }
+        match prefix.rfind('/') {
+            Some(i) => prefix = &prefix[..i],
+            None => return false,
+        }
+    }
+}
+
+/// A file that was installed into debian/tmp, but is not in any package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingFile {
+    /// Path of the file, relative to debian/tmp
+    pub path: String,
+    /// Binary package whose install file should probably list it
+    pub package: Option<String>,
+}
+
+/// List the files installed into a directory such as debian/tmp.
+///
+/// # Returns
+/// Paths of the files and symlinks, relative to `dir` and sorted
+pub fn installed_files(dir: &Path) -> std::io::Result<Vec<String>> {
+    fn walk(dir: &Path, prefix: &str, ret: &mut Vec<String>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if entry.file_type()?.is_dir() {
+                walk(&entry.path(), &format!("{}/", name), ret)?;
+            } else {
+                ret.push(name);
+            }
+        }
+        Ok(())
+    }
+    let mut ret = vec![];
+    walk(dir, "", &mut ret)?;
+    ret.sort();
+    Ok(ret)
+}
+
+/// Suggest the binary package that an unpackaged file belongs in.
+///
+/// Well-known locations are matched to packages by their naming convention
+/// (`lib*-dev`, `lib*`, `python3-*`, `*-doc`); other files go to the package
+/// that already installs the closest directory.
+fn suggest_package(
+    path: &str,
+    binaries: &[String],
+    install_files: &[(String, InstallFile)],
+) -> Option<String> {
+    let find = |pred: &dyn Fn(&str) -> bool| binaries.iter().find(|b| pred(b)).cloned();
+    let file_name = path.rsplit('/').next().unwrap();
+    let suggestion = if path.starts_with("usr/include/")
+        || path.contains("/pkgconfig/")
+        || file_name.ends_with(".a")
+        || file_name.ends_with(".so")
+        || path.contains("/cmake/")
+    {
+        find(&|b| b.ends_with("-dev"))
+    } else if file_name.starts_with("lib") && file_name.contains(".so.") {
+        find(&|b| b.starts_with("lib") && !b.ends_with("-dev") && !b.ends_with("-doc"))
+    } else if path.contains("/dist-packages/") {
+        find(&|b| b.starts_with("python3-"))
+    } else if path.starts_with("usr/share/doc/") || path.starts_with("usr/share/gtk-doc/") {
+        find(&|b| b.ends_with("-doc"))
+    } else if path.starts_with("usr/bin/") || path.starts_with("usr/sbin/") {
+        find(&|b| !b.starts_with("lib") && !b.starts_with("python3-") && !b.ends_with("-doc"))
+    } else {
+        None
+    };
+    if suggestion.is_some() {
+        return suggestion;
+    }
+    // Fall back to the package installing the most specific parent directory.
+    install_files
+        .iter()
+        .flat_map(|(package, install)| {
+            install
+                .entries()
+                .flat_map(|e| e.sources.iter())
+                .map(move |source| (package, source))
+        })
+        .filter_map(|(package, source)| {
+            let source = source.trim_start_matches("debian/tmp/").trim_matches('/');
+            let dir = source.rsplit_once('/').map(|(d, _)| d).unwrap_or("");
+            let common = path
+                .split('/')
+                .zip(dir.split('/'))
+                .take_while(|(a, b)| a == b || glob_match(b, a))
+                .count();
+            (common > 0).then_some((common, package))
+        })
+        .max_by_key(|(common, _)| *common)
+        .map(|(_, package)| package.clone())
+        .or_else(|| (binaries.len() == 1).then(|| binaries[0].clone()))
+}
+
+/// Find installed files that are not in any binary package.
+///
+/// # Arguments
+/// * `installed` - Paths of the installed files, relative to debian/tmp
+/// * `binaries` - Names of the binary packages
+/// * `install_files` - Install files, with the package they belong to
+/// * `not_installed` - Contents of debian/not-installed, if any
+///
+/// # Returns
+/// The files that are not in any package, with suggested packages
+pub fn find_missing_files(
+    installed: &[String],
+    binaries: &[String],
+    install_files: &[(String, InstallFile)],
+    not_installed: Option<&InstallFile>,
+) -> Vec<MissingFile> {
+    let covered = |path: &str| {
+        install_files
+            .iter()
+            .map(|(_, f)| f)
+            .chain(not_installed)
+            .flat_map(|f| f.entries())
+            .flat_map(|e| e.sources.iter())
+            .any(|pattern| pattern_covers(pattern, path))
+    };
+    installed
+        .iter()
+        .filter(|path| !covered(path))
+        .map(|path| MissingFile {
+            path: path.clone(),
+            package: suggest_package(path, binaries, install_files),
+        })
+        .collect()
+}
+
+/// Find the files in debian/tmp of a built source tree that are not in any
+/// binary package, as reported by dh_missing.
+///
+/// # Arguments
+/// * `base` - Root of the source tree, after the package has been built
+///
+/// # Returns
+/// The files that are not in any package, with suggested packages
+pub fn analyze_dh_missing(base: &Path) -> std::io::Result<Vec<MissingFile>> {
+    let f = std::fs::File::open(base.join("debian/control"))?;
+    let (control, _) = debian_control::Control::read_relaxed(f)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let binaries = control
+        .binaries()
+        .filter_map(|b| b.name())
+        .collect::<Vec<_>>();
+    let read = |path: PathBuf| -> std::io::Result<Option<InstallFile>> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => text
+                .parse()
+                .map(Some)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
+    let mut install_files = vec![];
+    for (i, package) in binaries.iter().enumerate() {
+        let mut install = read(base.join(format!("debian/{}.install", package)))?;
+        if install.is_none() && i == 0 {
+            // debhelper uses debian/install for the first package.
+            install = read(base.join("debian/install"))?;
+        }
+        if let Some(install) = install {
+            install_files.push((package.clone(), install));
+        }
+    }
+    let not_installed = read(base.join("debian/not-installed"))?;
+    let installed = installed_files(&base.join("debian/tmp"))?;
+    Ok(find_missing_files(
+        &installed,
+        &binaries,
+        &install_files,
+        not_installed.as_ref(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entries() {
+        let text = "#!/usr/bin/dh-exec\n# Binaries\nusr/bin/foo\nusr/lib/*/libfoo.so.*  usr/lib\n[linux-any] etc/foo.conf => etc/foo/foo.conf\n";
+        let install: InstallFile = text.parse().unwrap();
+        assert!(install.is_dh_exec());
+        assert_eq!(text, install.to_string());
+        assert_eq!(
+            vec![
+                &InstallEntry::new(&["usr/bin/foo"]),
+                &InstallEntry::with_destination(&["usr/lib/*/libfoo.so.*"], "usr/lib"),
+                &InstallEntry {
+                    architectures: Some("linux-any".to_string()),
+                    ..InstallEntry::rename("etc/foo.conf", "etc/foo/foo.conf")
+                },
+            ],
+            install.entries().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut install: InstallFile = "# Binaries\nbin/foo   bin/bar usr/lib/foo\nusr/share/foo\n"
+            .parse()
+            .unwrap();
+        assert!(!install.add(InstallEntry::new(&["usr/share/foo"])));
+        assert!(install.remove("bin/bar"));
+        assert!(!install.remove("usr/bin/missing"));
+        assert!(install.set_destination("usr/share/foo", Some("usr/share/foo-data")));
+        assert!(install.add(InstallEntry::rename("foo.1", "usr/share/man/man1/bar.1")));
+        assert_eq!(
+            "#!/usr/bin/dh-exec\n# Binaries\nbin/foo usr/lib/foo\nusr/share/foo usr/share/foo-data\nfoo.1 => usr/share/man/man1/bar.1\n",
+            install.to_string()
+        );
+        assert!(install.remove("bin/foo"));
+        assert!(!install.contains("bin/foo"));
+    }
+
+    #[test]
+    fn test_rename_package_files() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        for name in [
+            "foo.install",
+            "foo.symbols.amd64",
+            "foo-doc.docs",
+            "foo.unknown",
+        ] {
+            std::fs::write(td.path().join("debian").join(name), "").unwrap();
+        }
+        assert_eq!(
+            vec![
+                (
+                    PathBuf::from("debian/foo.install"),
+                    PathBuf::from("debian/bar.install")
+                ),
+                (
+                    PathBuf::from("debian/foo.symbols.amd64"),
+                    PathBuf::from("debian/bar.symbols.amd64")
+                ),
+            ],
+            rename_package_files(td.path(), "foo", "bar").unwrap()
+        );
+        assert!(td.path().join("debian/foo-doc.docs").exists());
+        assert!(td.path().join("debian/foo.unknown").exists());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(
+            "usr/lib/*/libfoo.so.*",
+            "usr/lib/x86_64-linux-gnu/libfoo.so.1"
+        ));
+        assert!(!glob_match(
+            "usr/lib/*.so",
+            "usr/lib/x86_64-linux-gnu/libfoo.so"
+        ));
+        assert!(glob_match(
+            "usr/share/man/man[18]/foo.?",
+            "usr/share/man/man8/foo.8"
+        ));
+        assert!(!glob_match(
+            "usr/share/man/man[!8]/foo.?",
+            "usr/share/man/man8/foo.8"
+        ));
+        assert!(pattern_covers("debian/tmp/usr/share/", "usr/share/foo/bar"));
+    }
+
+    #[test]
+    fn test_analyze_dh_missing() {
+        let td = tempfile::tempdir().unwrap();
+        let tmp = td.path().join("debian/tmp");
+        for path in [
+            "usr/bin/foo",
+            "usr/include/foo.h",
+            "usr/lib/x86_64-linux-gnu/libfoo.so.1.0",
+            "usr/lib/x86_64-linux-gnu/libfoo.so",
+            "usr/lib/x86_64-linux-gnu/libfoo.la",
+            "usr/share/foo/data/extra.dat",
+            "usr/share/foo/data.dat",
+        ] {
+            std::fs::create_dir_all(tmp.join(path).parent().unwrap()).unwrap();
+            std::fs::write(tmp.join(path), "").unwrap();
+        }
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\n\nPackage: foo\nArchitecture: any\n\nPackage: libfoo1\nArchitecture: any\n\nPackage: libfoo-dev\nArchitecture: any\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/foo.install"),
+            "usr/bin\nusr/share/foo/*.dat\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/libfoo1.install"),
+            "usr/lib/*/libfoo.so.*\n",
+        )
+        .unwrap();
+        std::fs::write(td.path().join("debian/not-installed"), "usr/lib/*/*.la\n").unwrap();
+        assert_eq!(
+            vec![
+                MissingFile {
+                    path: "usr/include/foo.h".to_string(),
+                    package: Some("libfoo-dev".to_string()),
+                },
+                MissingFile {
+                    path: "usr/lib/x86_64-linux-gnu/libfoo.so".to_string(),
+                    package: Some("libfoo-dev".to_string()),
+                },
+                MissingFile {
+                    path: "usr/share/foo/data/extra.dat".to_string(),
+                    package: Some("foo".to_string()),
+                },
+            ],
+            analyze_dh_missing(td.path()).unwrap()
+        );
+    }
+}
@@ -1,36 +1,79 @@
 //! Library for manipulating Debian packages.
 #![deny(missing_docs)]
-use breezyshim::branch::Branch;
-use breezyshim::dirty_tracker::DirtyTreeTracker;
-use breezyshim::error::Error;
-use breezyshim::tree::{PyTree, Tree, TreeChange, WorkingTree};
-use breezyshim::workingtree::PyWorkingTree;
-use breezyshim::workspace::reset_tree_with_dirty_tracker;
+#[cfg(feature = "vcs")]
+use breezyshim::{
+    branch::Branch,
+    dirty_tracker::DirtyTreeTracker,
+    error::Error,
+    tree::{PyTree, Tree, TreeChange, WorkingTree},
+    workingtree::PyWorkingTree,
+    workspace::reset_tree_with_dirty_tracker,
+};
 
 pub mod abstract_control;
+pub mod analyze;
+#[cfg(feature = "appstream")]
+pub mod appstream;
+pub mod autopkgtest;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "buildd")]
+pub mod buildd;
+pub mod cargo_lock;
 pub mod changelog;
 pub mod config;
 pub mod control;
+pub mod copyright;
+#[cfg(feature = "crates-io")]
+pub mod crates_io;
+#[cfg(feature = "debbugs")]
+pub mod debbugs;
 pub mod debcargo;
+#[cfg(feature = "debci")]
+pub mod debci;
+#[cfg(feature = "vcs")]
 pub mod debcommit;
 pub mod debhelper;
 pub mod detect_gbp_dch;
 pub mod editor;
+pub mod error;
+pub mod gbp;
+pub mod install;
 pub mod lintian;
+pub mod listfile;
 pub mod maintscripts;
+pub mod multiarch;
+#[cfg(feature = "new-queue")]
+pub mod new_queue;
+pub mod package_dir;
 pub mod patches;
+#[cfg(feature = "vcs")]
 pub mod publish;
+#[cfg(feature = "python")]
+mod python;
+pub mod readme_source;
 pub mod relations;
 pub mod release_info;
 pub mod rules;
+pub mod rules_requires_root;
+pub mod svp_report;
+pub mod triggers;
+pub mod upstream_metadata;
+#[cfg(feature = "upstream-registry")]
+pub mod upstream_registry;
 pub mod vcs;
+#[cfg(feature = "vcs")]
+pub mod vcs_migrate;
 pub mod vendor;
 pub mod versions;
+pub mod watch;
+pub mod wrap_and_sort;
 
 // TODO(jelmer): Import this from ognibuild
 /// Default builder
 pub const DEFAULT_BUILDER: &str = "sbuild --no-clean-source";
 
+#[cfg(feature = "vcs")]
 #[derive(Debug)]
 /// Error applying a change
 pub enum ApplyError<R, E> {
@@ -42,12 +85,14 @@ pub enum ApplyError<R, E> {
     NoChanges(R),
 }
 
+#[cfg(feature = "vcs")]
 impl<R, E> From<Error> for ApplyError<R, E> {
     fn from(e: Error) -> Self {
         ApplyError::BrzError(e)
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Apply a change in a clean tree.
 ///
 /// This will either run a callback in a tree, or if the callback fails,
@@ -170,6 +215,7 @@ impl std::fmt::Display for ChangelogError {
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Add an entry to a changelog.
 ///
 /// # Arguments
@@ -180,21 +226,69 @@ pub fn add_changelog_entry<T: WorkingTree>(
     working_tree: &T,
     changelog_path: &std::path::Path,
     entry: &[&str],
-) -> Result<(), crate::editor::EditorError> {
+) -> Result<(), crate::error::Error> {
     use crate::editor::{Editor, MutableTreeEdit};
     let mut cl =
         working_tree.edit_file::<debian_changelog::ChangeLog>(changelog_path, false, true)?;
 
+    auto_add_change(
+        &mut cl,
+        changelog_path,
+        entry,
+        debian_changelog::get_maintainer(),
+    )?;
+
+    cl.commit()?;
+
+    Ok(())
+}
+
+/// Add an entry to a changelog in a plain directory.
+///
+/// # Arguments
+/// * `changelog_path` - Path to the changelog
+/// * `entry` - Changelog entry
+pub fn add_changelog_entry_path(
+    changelog_path: &std::path::Path,
+    entry: &[&str],
+) -> Result<(), crate::error::Error> {
+    use crate::editor::Editor;
+    let mut cl = crate::editor::FsEditor::<debian_changelog::ChangeLog>::new(
+        changelog_path,
+        false,
+        true,
+        false,
+    )?;
+
+    auto_add_change(
+        &mut cl,
+        changelog_path,
+        entry,
+        debian_changelog::get_maintainer(),
+    )?;
+
+    cl.commit()?;
+
+    Ok(())
+}
+
+fn auto_add_change(
+    cl: &mut debian_changelog::ChangeLog,
+    changelog_path: &std::path::Path,
+    entry: &[&str],
+    maintainer: Option<(String, String)>,
+) -> Result<(), crate::error::Error> {
+    let maintainer = maintainer.ok_or(crate::error::Error::NoMaintainer)?;
     cl.try_auto_add_change(
         entry,
-        debian_changelog::get_maintainer().unwrap(),
+        maintainer,
         Some(chrono::Utc::now().fixed_offset()),
         None,
     )
-    .unwrap();
-
-    cl.commit()?;
-
+    .map_err(|e| crate::error::Error::Parse {
+        path: changelog_path.to_path_buf(),
+        message: e.to_string(),
+    })?;
     Ok(())
 }
 
@@ -278,6 +372,7 @@ pub fn min_certainty(certainties: &[Certainty]) -> Option<Certainty> {
     certainties.iter().min().cloned()
 }
 
+#[cfg(feature = "vcs")]
 /// Get the committer string for a tree
 pub fn get_committer(working_tree: &dyn PyWorkingTree) -> String {
     if let Some(committer) = breezyshim::git::get_committer(working_tree) {
@@ -293,6 +388,7 @@ pub fn get_committer(working_tree: &dyn PyWorkingTree) -> String {
         .unwrap_or_default()
 }
 
+#[cfg(feature = "vcs")]
 /// Check whether there are any control files present in a tree.
 ///
 /// # Arguments
@@ -319,11 +415,13 @@ pub fn control_file_present(tree: &dyn Tree, subpath: &std::path::Path) -> bool
     false
 }
 
+#[cfg(feature = "vcs")]
 /// Check whether the package in a tree uses debcargo.
 pub fn is_debcargo_package(tree: &dyn Tree, subpath: &std::path::Path) -> bool {
     tree.has_filename(subpath.join("debian/debcargo.toml").as_path())
 }
 
+#[cfg(feature = "vcs")]
 /// Check whether the package in a tree has control files in the root, rather than in debian/.
 pub fn control_files_in_root(tree: &dyn Tree, subpath: &std::path::Path) -> bool {
     let debian_path = subpath.join("debian");
@@ -371,7 +469,8 @@ pub fn gbp_dch(path: &std::path::Path) -> Result<(), std::io::Error> {
     let mut cmd = std::process::Command::new("gbp");
     cmd.arg("dch").arg("--ignore-branch");
     cmd.current_dir(path);
-    let status = cmd.status()?;
+    let span = tracing::info_span!("command", command = ?cmd);
+    let status = span.in_scope(|| cmd.status())?;
     if !status.success() {
         return Err(std::io::Error::other(format!("gbp dch failed: {}", status)));
     }
@@ -381,6 +480,7 @@ pub fn gbp_dch(path: &std::path::Path) -> Result<(), std::io::Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "vcs")]
     use serial_test::serial;
 
     #[test]
@@ -392,6 +492,29 @@ mod tests {
         assert_eq!(parseaddr("foo").unwrap(), (None, Some("foo".to_string())));
     }
 
+    #[test]
+    fn test_auto_add_change_no_maintainer() {
+        let mut cl: debian_changelog::ChangeLog = r#"test (1.0-1) unstable; urgency=medium
+
+  * Initial release.
+
+ -- Test User <test@user.example.com>  Fri, 01 Jan 2021 00:00:00 +0000
+"#
+        .parse()
+        .unwrap();
+        assert!(matches!(
+            auto_add_change(
+                &mut cl,
+                std::path::Path::new("debian/changelog"),
+                &["Some change."],
+                None
+            ),
+            Err(crate::error::Error::NoMaintainer)
+        ));
+        assert_eq!(cl.iter().count(), 1);
+    }
+
+    #[cfg(feature = "vcs")]
     #[serial]
     #[test]
     fn test_git_env() {
@@ -421,6 +544,7 @@ mod tests {
         assert_eq!("Some Git Committer <committer@example.com>", committer);
     }
 
+    #[cfg(feature = "vcs")]
     #[serial]
     #[test]
     fn test_git_config() {
@@ -461,6 +585,7 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "vcs")]
     #[test]
     fn test_apply_or_revert_dirty_tracker_adds_new_files() {
         // With a DirtyTreeTracker active, apply_or_revert must add newly
@@ -234,6 +234,126 @@ pub fn latest_standards_version_opt() -> Option<StandardsVersion> {
         .map(|release| release.version)
 }
 
+/// An item of the policy upgrading checklist that can be checked automatically.
+#[derive(Debug)]
+pub struct ChecklistItem {
+    /// The policy release that introduced the item
+    pub version: &'static str,
+    /// Description of the item
+    pub description: &'static str,
+    /// Returns true if the source tree does not yet comply with the item
+    check: fn(&std::path::Path) -> std::io::Result<bool>,
+}
+
+impl ChecklistItem {
+    /// Check whether the item is unresolved in a source tree.
+    ///
+    /// # Arguments
+    /// * `base` - Root of the source tree
+    pub fn is_unresolved(&self, base: &std::path::Path) -> std::io::Result<bool> {
+        (self.check)(base)
+    }
+}
+
+impl std::fmt::Display for ChecklistItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({})", self.description, self.version)
+    }
+}
+
+fn read_optional(path: &std::path::Path) -> std::io::Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn uses_priority_extra(base: &std::path::Path) -> std::io::Result<bool> {
+    Ok(read_optional(&base.join("debian/control"))?
+        .is_some_and(|text| lazy_regex::regex_is_match!(r"(?mi)^Priority:\s*extra\s*$", &text)))
+}
+
+fn has_get_orig_source(base: &std::path::Path) -> std::io::Result<bool> {
+    Ok(read_optional(&base.join("debian/rules"))?
+        .is_some_and(|text| lazy_regex::regex_is_match!(r"(?m)^get-orig-source\s*:", &text)))
+}
+
+fn has_init_script_without_unit(base: &std::path::Path) -> std::io::Result<bool> {
+    let debian = base.join("debian");
+    if !debian.is_dir() {
+        return Ok(false);
+    }
+    let mut names = vec![];
+    for entry in std::fs::read_dir(&debian)? {
+        names.push(entry?.file_name().to_string_lossy().into_owned());
+    }
+    Ok(names.iter().any(|name| {
+        let Some(stem) = name.strip_suffix("init") else {
+            return false;
+        };
+        let stem = if stem.is_empty() {
+            stem
+        } else if let Some(stem) = stem.strip_suffix('.') {
+            stem
+        } else {
+            return false;
+        };
+        let unit = if stem.is_empty() {
+            "service".to_string()
+        } else {
+            format!("{}.service", stem)
+        };
+        !names.contains(&unit)
+    }))
+}
+
+/// Items of the policy upgrading checklist that can be checked automatically.
+pub const UPGRADE_CHECKLIST: &[ChecklistItem] = &[
+    ChecklistItem {
+        version: "4.0.1",
+        description: "Priority \"extra\" is deprecated; use \"optional\" instead",
+        check: uses_priority_extra,
+    },
+    ChecklistItem {
+        version: "4.1.4",
+        description: "The get-orig-source target is no longer documented; use debian/watch",
+        check: has_get_orig_source,
+    },
+    ChecklistItem {
+        version: "4.7.0",
+        description: "Init scripts must be accompanied by a systemd unit",
+        check: has_init_script_without_unit,
+    },
+];
+
+/// Find checklist items that are unresolved when upgrading between two standards versions.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `from` - Current standards version; all items are considered if `None`
+/// * `to` - Standards version to upgrade to
+///
+/// # Returns
+/// The items introduced after `from` and up to `to` that the tree does not yet comply with
+pub fn unresolved_checklist_items(
+    base: &std::path::Path,
+    from: Option<&StandardsVersion>,
+    to: &StandardsVersion,
+) -> std::io::Result<Vec<&'static ChecklistItem>> {
+    let mut ret = vec![];
+    for item in UPGRADE_CHECKLIST {
+        let version: StandardsVersion = item.version.parse().unwrap();
+        if from.is_some_and(|from| &version <= from) || &version > to {
+            continue;
+        }
+        if item.is_unresolved(base)? {
+            ret.push(item);
+        }
+    }
+    Ok(ret)
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::Datelike;
@@ -370,4 +490,34 @@ mod tests {
         // Should be at least 4.0.0 (Debian policy versions)
         assert!(latest >= "4.0.0".parse::<super::StandardsVersion>().unwrap());
     }
+
+    #[test]
+    fn test_unresolved_checklist_items() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\nPriority: extra\n\nPackage: foo\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/rules"),
+            "#!/usr/bin/make -f\n\n%:\n\tdh $@\n\nget-orig-source:\n\tuscan\n",
+        )
+        .unwrap();
+        std::fs::write(td.path().join("debian/foo.init"), "#!/bin/sh\n").unwrap();
+        let to: super::StandardsVersion = "4.7.0".parse().unwrap();
+        let versions = |from: Option<&str>| {
+            let from = from.map(|f| f.parse::<super::StandardsVersion>().unwrap());
+            super::unresolved_checklist_items(td.path(), from.as_ref(), &to)
+                .unwrap()
+                .iter()
+                .map(|i| i.version)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["4.0.1", "4.1.4", "4.7.0"], versions(None));
+        assert_eq!(vec!["4.7.0"], versions(Some("4.6.2")));
+        std::fs::write(td.path().join("debian/foo.service"), "[Unit]\n").unwrap();
+        assert!(versions(Some("4.6.2")).is_empty());
+    }
 }
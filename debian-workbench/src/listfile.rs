@@ -0,0 +1,438 @@
+//! Editing of simple debhelper list files: debian/*.docs, *.examples, *.manpages, *.links and
+//! debian/clean.
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A line in a list file
+enum Line {
+    /// A comment or blank line, kept verbatim
+    Verbatim(String),
+    /// Items on a line, with the original text if they have not been changed
+    Items(Vec<String>, Option<String>),
+}
+
+/// A debhelper file listing entries of `COLUMNS` whitespace-separated items.
+///
+/// Several entries may appear on a single line.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ListFile<const COLUMNS: usize> {
+    lines: Vec<Line>,
+}
+
+/// A dh_installdocs file (debian/*.docs)
+pub type DocsFile = ListFile<1>;
+
+/// A dh_installexamples file (debian/*.examples)
+pub type ExamplesFile = ListFile<1>;
+
+/// A dh_installman file (debian/*.manpages)
+pub type ManpagesFile = ListFile<1>;
+
+/// A dh_link file (debian/*.links), listing pairs of link target and link name
+pub type LinksFile = ListFile<2>;
+
+/// A dh_clean file (debian/clean), listing files to remove when cleaning
+pub type CleanFile = ListFile<1>;
+
+impl<const COLUMNS: usize> ListFile<COLUMNS> {
+    /// Create a new, empty list file
+    pub fn new() -> Self {
+        ListFile { lines: vec![] }
+    }
+
+    /// Check if the file has no entries
+    pub fn is_empty(&self) -> bool {
+        !self.lines.iter().any(|l| matches!(l, Line::Items(..)))
+    }
+
+    /// Iterate over the entries in the file
+    pub fn entries(&self) -> impl Iterator<Item = &[String]> + '_ {
+        self.lines.iter().flat_map(|l| match l {
+            Line::Items(items, _) => items.chunks(COLUMNS).collect::<Vec<_>>(),
+            Line::Verbatim(_) => vec![],
+        })
+    }
+
+    /// Check whether an entry is present.
+    pub fn contains(&self, entry: &[&str]) -> bool {
+        self.entries().any(|e| e == entry)
+    }
+
+    /// Add an entry on a line of its own, unless it is already present.
+    ///
+    /// # Returns
+    /// Whether the entry was added
+    pub fn add(&mut self, entry: &[&str]) -> bool {
+        assert_eq!(entry.len(), COLUMNS);
+        if self.contains(entry) {
+            return false;
+        }
+        self.lines.push(Line::Items(
+            entry.iter().map(|s| s.to_string()).collect(),
+            None,
+        ));
+        true
+    }
+
+    /// Apply `keep` to each entry, dropping those for which it returns false.
+    fn retain_entries(&mut self, mut keep: impl FnMut(&[String]) -> bool) -> usize {
+        let mut removed = 0;
+        self.lines.retain_mut(|line| {
+            let Line::Items(items, text) = line else {
+                return true;
+            };
+            let kept = items
+                .chunks(COLUMNS)
+                .filter(|e| keep(e))
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+            if kept.len() != items.len() {
+                removed += (items.len() - kept.len()) / COLUMNS;
+                *items = kept;
+                *text = None;
+            }
+            !items.is_empty()
+        });
+        removed
+    }
+
+    /// Remove the entries whose first item is `path`.
+    ///
+    /// # Returns
+    /// Whether any entry was removed
+    pub fn remove(&mut self, path: &str) -> bool {
+        self.retain_entries(|e| e[0] != path) > 0
+    }
+
+    /// Remove entries that are listed more than once, keeping the first.
+    ///
+    /// # Returns
+    /// Number of entries that were removed
+    pub fn dedup(&mut self) -> usize {
+        let mut seen = std::collections::HashSet::new();
+        self.retain_entries(|e| seen.insert(e.to_vec()))
+    }
+
+    /// Sort the entries, putting each on a line of its own.
+    ///
+    /// Comments are moved to the top of the file. Since this changes the
+    /// layout of the file, nothing is done unless reformatting is allowed.
+    ///
+    /// # Arguments
+    /// * `allow_reformatting` - Whether the file may be reformatted
+    ///
+    /// # Returns
+    /// Whether the file was changed
+    pub fn sort(&mut self, allow_reformatting: bool) -> bool {
+        let mut entries = self.entries().map(|e| e.to_vec()).collect::<Vec<_>>();
+        entries.sort();
+        let mut lines = self
+            .lines
+            .iter()
+            .filter(|l| matches!(l, Line::Verbatim(t) if !t.trim().is_empty()))
+            .cloned()
+            .collect::<Vec<_>>();
+        lines.extend(entries.into_iter().map(|e| Line::Items(e, None)));
+        let sorted = ListFile::<COLUMNS> { lines };
+        if sorted.to_string() == self.to_string() || !allow_reformatting {
+            return false;
+        }
+        *self = sorted;
+        true
+    }
+
+    /// Find entries that refer to files that do not exist in the upstream tree.
+    ///
+    /// Only the first item of each entry is checked. Entries containing
+    /// wildcards or substitutions are assumed to exist.
+    ///
+    /// # Arguments
+    /// * `upstream_dir` - Root of the upstream source
+    pub fn missing_paths(&self, upstream_dir: &Path) -> Vec<String> {
+        self.entries()
+            .map(|e| &e[0])
+            .filter(|p| !p.contains(['*', '?', '[', '$']))
+            .filter(|p| !upstream_dir.join(p.trim_start_matches('/')).exists())
+            .cloned()
+            .collect()
+    }
+}
+
+impl<const COLUMNS: usize> std::fmt::Display for ListFile<COLUMNS> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Verbatim(text) | Line::Items(_, Some(text)) => writeln!(f, "{}", text)?,
+                Line::Items(items, None) => writeln!(f, "{}", items.join(" "))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const COLUMNS: usize> std::str::FromStr for ListFile<COLUMNS> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s
+            .lines()
+            .map(|l| {
+                if l.starts_with('#') || l.trim().is_empty() {
+                    return Ok(Line::Verbatim(l.to_string()));
+                }
+                let items = l
+                    .split_whitespace()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>();
+                if items.len() % COLUMNS != 0 {
+                    return Err(format!("Expected {} items per entry: {}", COLUMNS, l));
+                }
+                Ok(Line::Items(items, Some(l.to_string())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ListFile { lines })
+    }
+}
+
+impl<const COLUMNS: usize> crate::editor::Marshallable for ListFile<COLUMNS> {
+    fn from_bytes(content: &[u8]) -> Self {
+        std::str::from_utf8(content).unwrap().parse().unwrap()
+    }
+
+    fn empty() -> Self {
+        ListFile::new()
+    }
+
+    fn to_bytes(&self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.to_string().into_bytes())
+        }
+    }
+}
+
+/// Directories that are never part of the build output.
+const IGNORED_DIRECTORIES: &[&str] = &[".git", ".bzr", ".hg", ".svn", ".pc"];
+
+/// List the files in a source tree, skipping version control and quilt state.
+///
+/// # Returns
+/// Paths relative to `base`, sorted
+pub fn tree_files(base: &Path) -> std::io::Result<Vec<String>> {
+    fn walk(dir: &Path, prefix: &str, ret: &mut Vec<String>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let name = format!("{}{}", prefix, file_name);
+            if entry.file_type()?.is_dir() {
+                if !IGNORED_DIRECTORIES.contains(&file_name.as_str()) {
+                    walk(&entry.path(), &format!("{}/", name), ret)?;
+                }
+            } else {
+                ret.push(name);
+            }
+        }
+        Ok(())
+    }
+    let mut ret = vec![];
+    walk(base, "", &mut ret)?;
+    ret.sort();
+    Ok(ret)
+}
+
+/// Whether dh_clean removes a file by itself.
+fn cleaned_by_debhelper(path: &str) -> bool {
+    let Some(name) = path.strip_prefix("debian/") else {
+        return false;
+    };
+    // Package build directories, debian/tmp and debian/.debhelper
+    name.contains('/')
+        || ["files", "debhelper-build-stamp"].contains(&name)
+        || name.starts_with("autoreconf.")
+        || [".substvars", ".debhelper.log", ".debhelper"]
+            .iter()
+            .any(|s| name.ends_with(s))
+}
+
+/// Find the files created by a build that are not removed by debhelper.
+///
+/// # Arguments
+/// * `before` - Files in the tree before the build, e.g. from [`tree_files`]
+/// * `after` - Files in the tree after the build
+pub fn build_generated_files(before: &[String], after: &[String]) -> Vec<String> {
+    let before = before.iter().collect::<std::collections::HashSet<_>>();
+    after
+        .iter()
+        .filter(|p| !before.contains(p) && !cleaned_by_debhelper(p))
+        .cloned()
+        .collect()
+}
+
+/// Add patterns to debian/clean for files generated by the build.
+///
+/// Files already matched by a pattern in the file are skipped. When at least
+/// three generated files share a directory and extension, and no file with
+/// that extension existed in the directory before the build, a single
+/// wildcard pattern is added for them.
+///
+/// # Arguments
+/// * `clean` - The debian/clean file
+/// * `generated` - Files generated by the build, e.g. from [`build_generated_files`]
+/// * `before` - Files in the tree before the build
+///
+/// # Returns
+/// The patterns that were added
+pub fn add_clean_patterns(
+    clean: &mut CleanFile,
+    generated: &[String],
+    before: &[String],
+) -> Vec<String> {
+    let is_cleaned = |clean: &CleanFile, path: &str| {
+        clean
+            .entries()
+            .any(|e| crate::install::glob_match(e[0].trim_start_matches("./"), path))
+    };
+    let split = |path: &str| -> Option<(String, String)> {
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let (_, ext) = name.rsplit_once('.').filter(|(stem, _)| !stem.is_empty())?;
+        Some((dir.to_string(), ext.to_string()))
+    };
+    let mut groups = std::collections::BTreeMap::<(String, String), usize>::new();
+    for path in generated {
+        if let Some(key) = split(path) {
+            *groups.entry(key).or_default() += 1;
+        }
+    }
+    let mut added = vec![];
+    for path in generated {
+        if is_cleaned(clean, path) {
+            continue;
+        }
+        let pattern = match split(path) {
+            Some((dir, ext))
+                if groups[&(dir.clone(), ext.clone())] >= 3
+                    && !before
+                        .iter()
+                        .any(|p| split(p) == Some((dir.clone(), ext.clone()))) =>
+            {
+                if dir.is_empty() {
+                    format!("*.{}", ext)
+                } else {
+                    format!("{}/*.{}", dir, ext)
+                }
+            }
+            _ => path.clone(),
+        };
+        if clean.add(&[&pattern]) {
+            added.push(pattern);
+        }
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_docs() {
+        let text = "# Upstream docs\nREADME.md  NEWS\nREADME.md\nTODO\n";
+        let mut docs: DocsFile = text.parse().unwrap();
+        assert_eq!(text, docs.to_string());
+        assert_eq!(
+            vec!["README.md", "NEWS", "README.md", "TODO"],
+            docs.entries().map(|e| e[0].as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(1, docs.dedup());
+        assert!(!docs.add(&["NEWS"]));
+        assert!(docs.add(&["AUTHORS"]));
+        assert!(docs.remove("TODO"));
+        assert_eq!(
+            "# Upstream docs\nREADME.md  NEWS\nAUTHORS\n",
+            docs.to_string()
+        );
+        assert!(!docs.sort(false));
+        assert!(docs.sort(true));
+        assert_eq!(
+            "# Upstream docs\nAUTHORS\nNEWS\nREADME.md\n",
+            docs.to_string()
+        );
+        assert!(!docs.sort(true));
+    }
+
+    #[test]
+    fn test_links() {
+        let mut links: LinksFile = "usr/share/foo/a usr/bin/a usr/share/foo/b usr/bin/b\n"
+            .parse()
+            .unwrap();
+        assert!(links.contains(&["usr/share/foo/b", "usr/bin/b"]));
+        assert!(links.remove("usr/share/foo/a"));
+        assert_eq!("usr/share/foo/b usr/bin/b\n", links.to_string());
+        assert!("usr/share/foo/a\n".parse::<LinksFile>().is_err());
+    }
+
+    #[test]
+    fn test_missing_paths() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(td.path().join("README"), "").unwrap();
+        let manpages: ManpagesFile = "README\ndoc/foo.1\ndoc/*.8\n".parse().unwrap();
+        assert_eq!(vec!["doc/foo.1"], manpages.missing_paths(td.path()));
+    }
+
+    #[test]
+    fn test_add_clean_patterns() {
+        let td = tempfile::tempdir().unwrap();
+        for path in [
+            "configure.ac",
+            "po/de.po",
+            "po/fr.po",
+            "po/nl.po",
+            ".git/HEAD",
+        ] {
+            std::fs::create_dir_all(td.path().join(path).parent().unwrap()).unwrap();
+            std::fs::write(td.path().join(path), "").unwrap();
+        }
+        let before = tree_files(td.path()).unwrap();
+        assert_eq!(
+            vec!["configure.ac", "po/de.po", "po/fr.po", "po/nl.po"],
+            before
+        );
+        let mut after = before.clone();
+        after.extend(
+            [
+                "config.log",
+                "po/de.gmo",
+                "po/fr.gmo",
+                "po/nl.gmo",
+                "doc/foo.1",
+                "debian/files",
+                "debian/foo.substvars",
+                "debian/foo/usr/bin/foo",
+            ]
+            .map(String::from),
+        );
+        let generated = build_generated_files(&before, &after);
+        assert_eq!(
+            vec![
+                "config.log",
+                "po/de.gmo",
+                "po/fr.gmo",
+                "po/nl.gmo",
+                "doc/foo.1"
+            ],
+            generated
+        );
+        let mut clean: CleanFile = "# Generated by the build\nconfig.*\n".parse().unwrap();
+        assert_eq!(
+            vec!["po/*.gmo", "doc/foo.1"],
+            add_clean_patterns(&mut clean, &generated, &before)
+        );
+        assert_eq!(
+            "# Generated by the build\nconfig.*\npo/*.gmo\ndoc/foo.1\n",
+            clean.to_string()
+        );
+        assert!(add_clean_patterns(&mut clean, &generated, &before).is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 //! Maintscript file parsing and generation
 use debversion::Version;
+use std::path::Path;
 
 #[derive(Debug, PartialEq, Eq)]
 /// An error that occurred while parsing a maintscript file
@@ -378,6 +379,18 @@ impl Maintscript {
             }
         }
     }
+
+    /// Append an entry, unless an identical entry is already present.
+    ///
+    /// # Returns
+    /// Whether the entry was added
+    pub fn add(&mut self, entry: Entry) -> bool {
+        if self.entries().contains(&&entry) {
+            return false;
+        }
+        self.lines.push(Line::Entry(entry));
+        true
+    }
 }
 
 impl std::fmt::Display for Maintscript {
@@ -412,6 +425,209 @@ impl std::str::FromStr for Maintscript {
     }
 }
 
+/// The debhelper version that introduced support for debian/*.maintscript files.
+pub const MAINTSCRIPT_DEBHELPER_VERSION: &str = "8.1.0";
+
+/// Paths in a built binary package that matter for maintscript entries.
+///
+/// All paths are absolute, as they appear on the installed system.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageContents {
+    /// Conffiles shipped by the package
+    pub conffiles: Vec<String>,
+    /// Symlinks shipped by the package, with their targets
+    pub symlinks: Vec<(String, String)>,
+    /// Directories shipped by the package
+    pub directories: Vec<String>,
+}
+
+impl PackageContents {
+    /// Read the contents of a built package tree, e.g. debian/<package>.
+    ///
+    /// Conffiles are read from DEBIAN/conffiles, if present.
+    pub fn from_dir(dir: &Path) -> std::io::Result<Self> {
+        let mut ret = PackageContents::default();
+        match std::fs::read_to_string(dir.join("DEBIAN/conffiles")) {
+            Ok(text) => {
+                ret.conffiles = text
+                    .lines()
+                    .map(|l| l.trim())
+                    .filter(|l| !l.is_empty())
+                    .map(|l| l.trim_start_matches("remove-on-upgrade ").to_string())
+                    .collect();
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        let mut todo = vec![dir.to_path_buf()];
+        while let Some(current) = todo.pop() {
+            for entry in std::fs::read_dir(&current)? {
+                let entry = entry?;
+                let path = entry.path();
+                let relpath = path.strip_prefix(dir).unwrap();
+                if relpath.starts_with("DEBIAN") {
+                    continue;
+                }
+                let name = format!("/{}", relpath.display());
+                let file_type = entry.file_type()?;
+                if file_type.is_symlink() {
+                    let target = std::fs::read_link(&path)?;
+                    ret.symlinks.push((name, target.display().to_string()));
+                } else if file_type.is_dir() {
+                    ret.directories.push(name);
+                    todo.push(path);
+                }
+            }
+        }
+        ret.symlinks.sort();
+        ret.directories.sort();
+        Ok(ret)
+    }
+}
+
+/// The prior version to use in maintscript entries for a change made in `version`.
+///
+/// This is `version` with a tilde appended, so that the entry also applies
+/// to upgrades from backports and other versions lower than `version`.
+pub fn prior_version_for(version: &Version) -> Version {
+    format!("{}~", version).parse().unwrap()
+}
+
+/// Generate maintscript entries for the changes between two versions of a package.
+///
+/// Conffiles that disappeared are moved if a conffile with the same file name
+/// appeared, and removed otherwise. Symlinks that became directories and
+/// directories that became symlinks are converted.
+///
+/// # Arguments
+/// * `old` - Contents of the previous version of the package
+/// * `new` - Contents of the new version of the package
+/// * `prior_version` - Version to record in the entries, see [`prior_version_for`]
+/// * `package` - Name of the package, if it should be recorded in the entries
+pub fn entries_for_changes(
+    old: &PackageContents,
+    new: &PackageContents,
+    prior_version: &Version,
+    package: Option<&str>,
+) -> Vec<Entry> {
+    let file_name = |p: &str| p.rsplit('/').next().unwrap_or(p).to_string();
+    let added = new
+        .conffiles
+        .iter()
+        .filter(|c| !old.conffiles.contains(c))
+        .collect::<Vec<_>>();
+    let mut ret = vec![];
+    for conffile in old.conffiles.iter().filter(|c| !new.conffiles.contains(c)) {
+        let candidates = added
+            .iter()
+            .filter(|a| file_name(a) == file_name(conffile))
+            .collect::<Vec<_>>();
+        ret.push(match candidates.as_slice() {
+            [new_conffile] => Entry::MoveConffile {
+                old_conffile: conffile.clone(),
+                new_conffile: new_conffile.to_string(),
+                prior_version: Some(prior_version.clone()),
+                package: package.map(|p| p.to_string()),
+            },
+            _ => Entry::RemoveConffile {
+                conffile: conffile.clone(),
+                prior_version: Some(prior_version.clone()),
+                package: package.map(|p| p.to_string()),
+            },
+        });
+    }
+    for (pathname, old_target) in &old.symlinks {
+        if new.directories.contains(pathname) {
+            ret.push(Entry::SymlinkToDir {
+                pathname: pathname.clone(),
+                old_target: old_target.clone(),
+                prior_version: Some(prior_version.clone()),
+                package: package.map(|p| p.to_string()),
+            });
+        }
+    }
+    for (pathname, new_target) in &new.symlinks {
+        if old.directories.contains(pathname) {
+            ret.push(Entry::DirToSymlink {
+                pathname: pathname.clone(),
+                new_target: new_target.clone(),
+                prior_version: Some(prior_version.clone()),
+                package: package.map(|p| p.to_string()),
+            });
+        }
+    }
+    ret
+}
+
+/// Error adding entries to a maintscript file.
+#[derive(Debug)]
+pub enum AddEntriesError {
+    /// The maintscript file could not be edited
+    Editor(Box<crate::editor::EditorError>),
+    /// The debhelper dependency could not be updated
+    Debhelper(crate::debhelper::EnsureDebhelperError),
+}
+
+impl std::fmt::Display for AddEntriesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AddEntriesError::Editor(e) => write!(f, "{}", e),
+            AddEntriesError::Debhelper(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AddEntriesError {}
+
+impl From<crate::editor::EditorError> for AddEntriesError {
+    fn from(e: crate::editor::EditorError) -> Self {
+        AddEntriesError::Editor(Box::new(e))
+    }
+}
+
+impl From<crate::debhelper::EnsureDebhelperError> for AddEntriesError {
+    fn from(e: crate::debhelper::EnsureDebhelperError) -> Self {
+        AddEntriesError::Debhelper(e)
+    }
+}
+
+/// Add entries to debian/<package>.maintscript.
+///
+/// The Build-Depends of the source package are updated to require a
+/// debhelper version that supports maintscript files, if entries were added.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `package` - Name of the binary package
+/// * `entries` - Entries to add; entries that are already present are skipped
+/// * `source` - Source paragraph of debian/control
+///
+/// # Returns
+/// Whether any entries were added
+pub fn add_entries(
+    base: &Path,
+    package: &str,
+    entries: Vec<Entry>,
+    source: &mut debian_control::lossless::Source,
+) -> Result<bool, AddEntriesError> {
+    use crate::editor::Editor;
+    let path = base.join("debian").join(format!("{}.maintscript", package));
+    let mut editor = crate::editor::FsEditor::<Maintscript>::new(&path, false, false, false)?;
+    let mut changed = false;
+    for entry in entries {
+        changed |= editor.add(entry);
+    }
+    if !changed {
+        return Ok(false);
+    }
+    crate::debhelper::ensure_minimum_debhelper_version(
+        source,
+        &MAINTSCRIPT_DEBHELPER_VERSION.parse().unwrap(),
+    )?;
+    editor.commit()?;
+    Ok(true)
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -474,4 +690,88 @@ dir_to_symlink /etc/foo /etc/bar 1.2.3-4";
         parsed.remove(0);
         assert_eq!(parsed.to_string(), "rm_conffile /etc/bar.conf 1.2.3-4");
     }
+
+    #[test]
+    fn test_entries_for_changes() {
+        let old = super::PackageContents {
+            conffiles: vec![
+                "/etc/foo/foo.conf".to_string(),
+                "/etc/foo/obsolete.conf".to_string(),
+            ],
+            symlinks: vec![("/usr/share/doc/foo".to_string(), "bar".to_string())],
+            directories: vec!["/usr/share/foo".to_string()],
+        };
+        let new = super::PackageContents {
+            conffiles: vec!["/etc/foo.d/foo.conf".to_string()],
+            symlinks: vec![("/usr/share/foo".to_string(), "/usr/share/bar".to_string())],
+            directories: vec!["/usr/share/doc/foo".to_string()],
+        };
+        let prior_version = super::prior_version_for(&"1.0-2".parse().unwrap());
+        assert_eq!("1.0-2~", prior_version.to_string());
+        let entries = super::entries_for_changes(&old, &new, &prior_version, None);
+        assert_eq!(
+            vec![
+                "mv_conffile /etc/foo/foo.conf /etc/foo.d/foo.conf 1.0-2~",
+                "rm_conffile /etc/foo/obsolete.conf 1.0-2~",
+                "symlink_to_dir /usr/share/doc/foo bar 1.0-2~",
+                "dir_to_symlink /usr/share/foo /usr/share/bar 1.0-2~",
+            ],
+            entries.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_package_contents_from_dir() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("DEBIAN")).unwrap();
+        std::fs::create_dir_all(td.path().join("etc/foo")).unwrap();
+        std::fs::write(td.path().join("etc/foo/foo.conf"), "").unwrap();
+        std::fs::write(
+            td.path().join("DEBIAN/conffiles"),
+            "/etc/foo/foo.conf\nremove-on-upgrade /etc/foo/old.conf\n",
+        )
+        .unwrap();
+        std::os::unix::fs::symlink("foo", td.path().join("etc/bar")).unwrap();
+        let contents = super::PackageContents::from_dir(td.path()).unwrap();
+        assert_eq!(
+            vec!["/etc/foo/foo.conf", "/etc/foo/old.conf"],
+            contents.conffiles
+        );
+        assert_eq!(
+            vec![("/etc/bar".to_string(), "foo".to_string())],
+            contents.symlinks
+        );
+        assert_eq!(vec!["/etc", "/etc/foo"], contents.directories);
+    }
+
+    #[test]
+    fn test_add_entries() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/foo.maintscript"),
+            "rm_conffile /etc/foo.conf 1.0-1~\n",
+        )
+        .unwrap();
+        let control = debian_control::Control::read_relaxed(
+            "Source: foo\nBuild-Depends: debhelper (>= 7)\n".as_bytes(),
+        )
+        .unwrap()
+        .0;
+        let mut source = control.source().unwrap();
+        let entries = vec![
+            "rm_conffile /etc/foo.conf 1.0-1~".parse().unwrap(),
+            "rm_conffile /etc/bar.conf 1.0-2~".parse().unwrap(),
+        ];
+        assert!(super::add_entries(td.path(), "foo", entries.clone(), &mut source).unwrap());
+        assert_eq!(
+            "rm_conffile /etc/foo.conf 1.0-1~\nrm_conffile /etc/bar.conf 1.0-2~\n",
+            std::fs::read_to_string(td.path().join("debian/foo.maintscript")).unwrap()
+        );
+        assert_eq!(
+            "debhelper (>= 8.1.0)",
+            source.build_depends().unwrap().to_string()
+        );
+        assert!(!super::add_entries(td.path(), "foo", entries, &mut source).unwrap());
+    }
 }
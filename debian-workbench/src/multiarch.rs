@@ -0,0 +1,230 @@
+//! Suggestions for the Multi-Arch field of binary packages.
+use serde::Deserialize;
+use std::path::Path;
+
+/// A hint published by the Debian multiarch hinter.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MultiArchHint {
+    /// Name of the binary package
+    pub binary: String,
+    /// Name of the source package
+    pub source: Option<String>,
+    /// Version of the source package the hint was generated for
+    pub version: Option<String>,
+    /// Human readable description of the hint
+    pub description: String,
+    /// Link to the documentation of the hint
+    pub link: String,
+    /// Severity of the hint, e.g. "high" or "low"
+    pub severity: Option<String>,
+}
+
+impl MultiArchHint {
+    /// The Multi-Arch value the hint suggests, if it suggests one.
+    pub fn multi_arch(&self) -> Option<&'static str> {
+        match self.link.rsplit_once('#').map(|(_, anchor)| anchor) {
+            Some("ma-foreign") | Some("ma-foreign-library") => Some("foreign"),
+            Some("ma-same") => Some("same"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MultiArchHints {
+    hints: Vec<MultiArchHint>,
+}
+
+/// Parse the hints published by the multiarch hinter, in their JSON form.
+pub fn parse_hints(text: &str) -> serde_json::Result<Vec<MultiArchHint>> {
+    Ok(serde_json::from_str::<MultiArchHints>(text)?.hints)
+}
+
+/// Whether a path is specific to the architecture it was built for.
+fn is_arch_qualified(path: &str) -> bool {
+    lazy_regex::regex_is_match!(
+        r"^usr/(lib|include)/[a-z0-9_]+-[a-z0-9_]+-[a-z0-9_]+/",
+        path
+    )
+}
+
+/// Suggest a Multi-Arch value for a binary package.
+///
+/// # Arguments
+/// * `name` - Name of the binary package
+/// * `architecture` - Architecture field of the binary package
+/// * `contents` - Paths of the files in the package, relative to its root,
+///   if it has been built
+///
+/// # Returns
+/// The suggested value, or None if no value can be suggested
+pub fn suggest_multi_arch(
+    name: &str,
+    architecture: Option<&str>,
+    contents: Option<&[String]>,
+) -> Option<&'static str> {
+    let has_programs = contents.is_some_and(|c| {
+        c.iter().any(|p| {
+            p.starts_with("usr/bin/") || p.starts_with("usr/sbin/") || p.starts_with("bin/")
+        })
+    });
+    if architecture == Some("all") {
+        // Documentation and data packages can satisfy dependencies from any
+        // architecture.
+        let is_data =
+            name.ends_with("-doc") || name.ends_with("-data") || name.ends_with("-common");
+        return (is_data && !has_programs).then_some("foreign");
+    }
+    let coinstallable = contents.is_none_or(|c| {
+        c.iter().all(|p| {
+            is_arch_qualified(p)
+                || p.starts_with("usr/share/doc/")
+                || p.starts_with("usr/share/lintian/")
+                || (name.ends_with("-dev") && p.starts_with("usr/include/"))
+        })
+    });
+    let is_library = lazy_regex::regex_is_match!(r"^lib.+\d(-[a-z0-9]+)?$", name)
+        && !name.ends_with("-dev")
+        && !name.ends_with("-bin");
+    if (is_library || (name.starts_with("lib") && name.ends_with("-dev")))
+        && coinstallable
+        && !has_programs
+    {
+        return Some("same");
+    }
+    None
+}
+
+/// Set the Multi-Arch field of binary packages.
+///
+/// Hints from the multiarch hinter take precedence over suggestions based on
+/// the package name and contents. Suggestions only apply to packages that do
+/// not have a Multi-Arch field yet.
+///
+/// # Arguments
+/// * `base` - Root of the source tree; package contents are read from
+///   debian/<package> if it has been built
+/// * `editor` - Editor for the package's control file
+/// * `hints` - Hints from the multiarch hinter
+///
+/// # Returns
+/// Names of the binary packages that were changed, with the new value
+pub fn apply_multi_arch(
+    base: &Path,
+    editor: &mut dyn crate::abstract_control::AbstractControlEditor,
+    hints: &[MultiArchHint],
+) -> std::io::Result<Vec<(String, String)>> {
+    let mut ret = vec![];
+    for mut binary in editor.binaries() {
+        let Some(name) = binary.name() else {
+            continue;
+        };
+        let current = binary.multi_arch();
+        let value = match hints
+            .iter()
+            .find(|h| h.binary == name)
+            .and_then(|h| h.multi_arch())
+        {
+            Some(value) => Some(value),
+            None if current.is_none() => {
+                let dir = base.join("debian").join(&name);
+                let contents = if dir.is_dir() {
+                    Some(crate::install::installed_files(&dir)?)
+                } else {
+                    None
+                };
+                suggest_multi_arch(&name, binary.architecture().as_deref(), contents.as_deref())
+            }
+            None => None,
+        };
+        if let Some(value) = value {
+            if current.as_deref() != Some(value) {
+                binary.set_multi_arch(Some(value));
+                ret.push((name, value.to_string()));
+            }
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_multi_arch() {
+        assert_eq!(
+            Some("foreign"),
+            suggest_multi_arch("foo-doc", Some("all"), None)
+        );
+        assert_eq!(None, suggest_multi_arch("foo", Some("all"), None));
+        assert_eq!(
+            Some("same"),
+            suggest_multi_arch("libfoo1", Some("any"), None)
+        );
+        assert_eq!(
+            Some("same"),
+            suggest_multi_arch("libfoo-2.0-0t64", Some("any"), None)
+        );
+        assert_eq!(
+            Some("same"),
+            suggest_multi_arch(
+                "libfoo-dev",
+                Some("any"),
+                Some(&[
+                    "usr/include/foo.h".to_string(),
+                    "usr/lib/x86_64-linux-gnu/libfoo.so".to_string(),
+                ])
+            )
+        );
+        assert_eq!(
+            None,
+            suggest_multi_arch(
+                "libfoo-dev",
+                Some("any"),
+                Some(&[
+                    "usr/bin/foo-config".to_string(),
+                    "usr/lib/x86_64-linux-gnu/libfoo.so".to_string(),
+                ])
+            )
+        );
+        assert_eq!(
+            None,
+            suggest_multi_arch(
+                "libfoo1",
+                Some("any"),
+                Some(&["usr/lib/libfoo.so.1".to_string()])
+            )
+        );
+    }
+
+    #[test]
+    fn test_apply_multi_arch() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\n\nPackage: foo\nArchitecture: any\nMulti-Arch: same\n\nPackage: libfoo1\nArchitecture: any\n\nPackage: foo-doc\nArchitecture: all\nMulti-Arch: foreign\n",
+        )
+        .unwrap();
+        let hints = parse_hints(
+            r#"{"format": "multiarch-hints-1.0", "hints": [{"binary": "foo", "source": "foo", "version": "1.0-1", "description": "foo could be marked Multi-Arch: foreign", "link": "https://wiki.debian.org/MultiArch/Hints#ma-foreign", "severity": "low"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(Some("foreign"), hints[0].multi_arch());
+        let mut editor =
+            crate::control::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+        assert_eq!(
+            vec![
+                ("foo".to_string(), "foreign".to_string()),
+                ("libfoo1".to_string(), "same".to_string()),
+            ],
+            apply_multi_arch(td.path(), &mut editor, &hints).unwrap()
+        );
+        crate::abstract_control::AbstractControlEditor::commit(&editor).unwrap();
+        assert_eq!(
+            "Source: foo\n\nPackage: foo\nArchitecture: any\nMulti-Arch: foreign\n\nPackage: libfoo1\nArchitecture: any\nMulti-Arch: same\n\nPackage: foo-doc\nArchitecture: all\nMulti-Arch: foreign\n",
+            std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+        );
+    }
+}
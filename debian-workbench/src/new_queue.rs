@@ -0,0 +1,400 @@
+//! Client for the ftp-master NEW queue, which holds uploads of source packages
+//! and binary package names that have not been in the archive before.
+//!
+//! Before renaming a binary package or uploading a new source package, it is
+//! worth checking whether the name is already waiting in NEW, or was recently
+//! rejected from it.
+use deb822_lossless::{Deb822, Paragraph};
+use std::str::FromStr;
+
+/// Base URL of the Debian ftp-master site.
+pub const DEFAULT_URL: &str = "https://ftp-master.debian.org/";
+
+/// An upload that is waiting in the NEW queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upload {
+    /// Name of the source package
+    pub source: String,
+    /// Versions of the source package in the queue
+    pub versions: Vec<String>,
+    /// Names of the binary packages built by the upload
+    pub binaries: Vec<String>,
+    /// Architectures included in the upload
+    pub architectures: Vec<String>,
+    /// Distribution the upload targets, e.g. "unstable"
+    pub distribution: Option<String>,
+    /// Queue the upload is in, e.g. "new" or "byhand"
+    pub queue: Option<String>,
+    /// How long the upload has been waiting, as shown by ftp-master
+    pub age: Option<String>,
+    /// Person who uploaded the package
+    pub changed_by: Option<String>,
+    /// Sponsor of the upload, if any
+    pub sponsored_by: Option<String>,
+    /// Bugs closed by the upload
+    pub closes: Vec<String>,
+}
+
+fn split_list(value: Option<String>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+impl Upload {
+    fn from_paragraph(para: &Paragraph) -> Option<Self> {
+        Some(Self {
+            source: para.get("Source")?,
+            versions: split_list(para.get("Version")),
+            binaries: split_list(para.get("Binary")),
+            architectures: split_list(para.get("Architectures")),
+            distribution: para.get("Distribution"),
+            queue: para.get("Queue"),
+            age: para.get("Age"),
+            changed_by: para.get("Changed-By"),
+            sponsored_by: para.get("Sponsored-By"),
+            closes: split_list(para.get("Closes"))
+                .into_iter()
+                .map(|b| b.trim_start_matches('#').to_string())
+                .collect(),
+        })
+    }
+}
+
+/// Contents of the NEW queue.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewQueue {
+    /// Uploads in the queue
+    pub uploads: Vec<Upload>,
+}
+
+impl FromStr for NewQueue {
+    type Err = deb822_lossless::ParseError;
+
+    /// Parse the deb822 listing of the NEW queue, as published at new.822.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let data = Deb822::from_str(text)?;
+        Ok(Self {
+            uploads: data
+                .paragraphs()
+                .filter_map(|p| Upload::from_paragraph(&p))
+                .collect(),
+        })
+    }
+}
+
+impl NewQueue {
+    /// Find the uploads of a source package.
+    ///
+    /// # Arguments
+    /// * `source` - Name of the source package
+    pub fn source(&self, source: &str) -> Vec<&Upload> {
+        self.uploads.iter().filter(|u| u.source == source).collect()
+    }
+
+    /// Find the upload that introduces a binary package.
+    ///
+    /// # Arguments
+    /// * `binary` - Name of the binary package
+    pub fn binary(&self, binary: &str) -> Option<&Upload> {
+        self.uploads
+            .iter()
+            .find(|u| u.binaries.iter().any(|b| b == binary))
+    }
+
+    /// Check whether a specific version of a source package is waiting in NEW.
+    ///
+    /// # Arguments
+    /// * `source` - Name of the source package
+    /// * `version` - Version of the source package
+    pub fn contains(&self, source: &str, version: &str) -> bool {
+        self.source(source)
+            .iter()
+            .any(|u| u.versions.iter().any(|v| v == version))
+    }
+
+    /// Find the binary package names that are already claimed by uploads of
+    /// other source packages in NEW.
+    ///
+    /// # Arguments
+    /// * `source` - Name of the source package that will build the binaries
+    /// * `binaries` - Names of the binary packages
+    ///
+    /// # Returns
+    /// Pairs of binary package name and the upload that claims it
+    pub fn conflicts<'a>(&'a self, source: &str, binaries: &[&str]) -> Vec<(String, &'a Upload)> {
+        binaries
+            .iter()
+            .filter_map(|b| {
+                self.uploads
+                    .iter()
+                    .find(|u| u.source != source && u.binaries.iter().any(|n| n == b))
+                    .map(|u| (b.to_string(), u))
+            })
+            .collect()
+    }
+}
+
+/// What the ftp-masters decided about an upload in NEW.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// The upload was accepted into the archive
+    Accept,
+    /// The upload was rejected
+    Reject,
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "ACCEPT" | "ACCEPTED" => Ok(Action::Accept),
+            "REJECT" | "REJECTED" => Ok(Action::Reject),
+            _ => Err(format!("Unknown action: {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Accept => write!(f, "ACCEPT"),
+            Action::Reject => write!(f, "REJECT"),
+        }
+    }
+}
+
+/// A decision about an upload that was in NEW.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    /// Name of the source package
+    pub source: String,
+    /// Version of the source package
+    pub version: String,
+    /// What was decided
+    pub action: Action,
+    /// When the decision was made, as reported by the server
+    pub date: Option<String>,
+    /// Reason given for a rejection
+    pub reason: Option<String>,
+}
+
+/// Parse a deb822 listing of recent NEW queue decisions.
+///
+/// Every paragraph has `Source`, `Version` and `Action` fields, and optionally
+/// `Date` and `Reason`. Paragraphs missing any of the required fields, or with
+/// an unknown action, are skipped.
+///
+/// # Arguments
+/// * `text` - The listing
+pub fn parse_decisions(text: &str) -> Result<Vec<Decision>, deb822_lossless::ParseError> {
+    let data = Deb822::from_str(text)?;
+    Ok(data
+        .paragraphs()
+        .filter_map(|p| {
+            Some(Decision {
+                source: p.get("Source")?,
+                version: p.get("Version")?,
+                action: p.get("Action")?.parse().ok()?,
+                date: p.get("Date"),
+                reason: p.get("Reason"),
+            })
+        })
+        .collect())
+}
+
+/// Find the most recent decision about a source package.
+///
+/// Decisions are assumed to be listed oldest first.
+///
+/// # Arguments
+/// * `decisions` - Recent decisions
+/// * `source` - Name of the source package
+pub fn latest_decision<'a>(decisions: &'a [Decision], source: &str) -> Option<&'a Decision> {
+    decisions.iter().rev().find(|d| d.source == source)
+}
+
+/// Error fetching NEW queue data.
+#[derive(Debug)]
+pub enum Error {
+    /// Error talking to ftp-master.
+    Http(reqwest::Error),
+    /// The data could not be parsed.
+    Parse(deb822_lossless::ParseError),
+    /// No URL was configured for the decision listing.
+    NoDecisionsUrl,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            Error::Parse(e) => write!(f, "Parse error: {}", e),
+            Error::NoDecisionsUrl => write!(f, "No URL configured for NEW queue decisions"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+impl From<deb822_lossless::ParseError> for Error {
+    fn from(e: deb822_lossless::ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
+
+/// Client for the NEW queue on ftp-master.
+pub struct Client {
+    base_url: url::Url,
+    decisions_url: Option<url::Url>,
+    client: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// Create a client for ftp-master.debian.org.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(DEFAULT_URL.parse().unwrap())
+    }
+
+    /// Create a client for the ftp-master site at the given URL.
+    pub fn with_url(base_url: url::Url) -> Result<Self, Error> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self {
+            base_url,
+            decisions_url: None,
+            client,
+        })
+    }
+
+    /// Set the URL of the deb822 listing of recent decisions.
+    ///
+    /// See [`parse_decisions`] for the expected format.
+    pub fn with_decisions_url(mut self, url: url::Url) -> Self {
+        self.decisions_url = Some(url);
+        self
+    }
+
+    #[tracing::instrument(skip_all, fields(url = %url))]
+    fn fetch(&self, url: url::Url) -> Result<String, Error> {
+        Ok(self.client.get(url).send()?.error_for_status()?.text()?)
+    }
+
+    /// Fetch the contents of the NEW queue.
+    pub fn queue(&self) -> Result<NewQueue, Error> {
+        let text = self.fetch(self.base_url.join("new.822").unwrap())?;
+        Ok(text.parse()?)
+    }
+
+    /// Fetch the recent decisions about uploads in NEW.
+    pub fn recent_decisions(&self) -> Result<Vec<Decision>, Error> {
+        let url = self.decisions_url.clone().ok_or(Error::NoDecisionsUrl)?;
+        Ok(parse_decisions(&self.fetch(url)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NEW_822: &str = r#"Source: rust-foo
+Binary: librust-foo-dev, librust-foo+std-dev
+Version: 0.2.0-1 0.2.1-1
+Architectures: source, all
+Age: 3 days
+Queue: new
+Changed-By: Jane Doe <jane@example.com>
+Distribution: unstable
+Closes: #1000000
+
+Source: bar
+Binary: bar libbar2
+Version: 2.0-1
+Architectures: source amd64
+Age: 2 weeks
+Queue: new
+Sponsored-By: John Smith <john@example.com>
+Distribution: experimental
+"#;
+
+    #[test]
+    fn test_parse_queue() {
+        let queue: NewQueue = NEW_822.parse().unwrap();
+        assert_eq!(2, queue.uploads.len());
+        let foo = &queue.uploads[0];
+        assert_eq!("rust-foo", foo.source);
+        assert_eq!(vec!["0.2.0-1", "0.2.1-1"], foo.versions);
+        assert_eq!(vec!["librust-foo-dev", "librust-foo+std-dev"], foo.binaries);
+        assert_eq!(vec!["source", "all"], foo.architectures);
+        assert_eq!(vec!["1000000"], foo.closes);
+        assert_eq!(Some("unstable"), foo.distribution.as_deref());
+        assert_eq!(vec!["bar", "libbar2"], queue.uploads[1].binaries);
+        assert_eq!(
+            Some("John Smith <john@example.com>"),
+            queue.uploads[1].sponsored_by.as_deref()
+        );
+    }
+
+    #[test]
+    fn test_lookups() {
+        let queue: NewQueue = NEW_822.parse().unwrap();
+        assert_eq!(1, queue.source("bar").len());
+        assert!(queue.source("baz").is_empty());
+        assert_eq!("bar", queue.binary("libbar2").unwrap().source);
+        assert!(queue.binary("libbar1").is_none());
+        assert!(queue.contains("rust-foo", "0.2.1-1"));
+        assert!(!queue.contains("rust-foo", "0.3.0-1"));
+        let conflicts = queue.conflicts("newbar", &["libbar2", "libbar3"]);
+        assert_eq!(1, conflicts.len());
+        assert_eq!("libbar2", conflicts[0].0);
+        assert_eq!("bar", conflicts[0].1.source);
+        assert!(queue.conflicts("bar", &["libbar2"]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_decisions() {
+        let decisions = parse_decisions(
+            r#"Source: bar
+Version: 1.0-1
+Action: REJECT
+Date: 2024-01-01
+Reason: Missing copyright information
+
+Source: baz
+Version: 1.0-1
+Action: ACCEPT
+
+Source: bar
+Version: 1.0-2
+Action: accepted
+
+Source: qux
+Version: 1.0-1
+Action: BYHAND
+"#,
+        )
+        .unwrap();
+        assert_eq!(3, decisions.len());
+        assert_eq!(Action::Reject, decisions[0].action);
+        assert_eq!(
+            Some("Missing copyright information"),
+            decisions[0].reason.as_deref()
+        );
+        let latest = latest_decision(&decisions, "bar").unwrap();
+        assert_eq!("1.0-2", latest.version);
+        assert_eq!(Action::Accept, latest.action);
+        assert!(latest_decision(&decisions, "qux").is_none());
+        assert_eq!("REJECT", Action::Reject.to_string());
+    }
+}
@@ -0,0 +1,201 @@
+//! Access to a package that may or may not be under version control.
+//!
+//! Packages in a working tree are edited through the tree, so that changes
+//! can be committed. Packages in a plain directory (e.g. an unpacked source
+//! package in a build chroot) are edited directly on disk.
+use crate::abstract_control::AbstractControlEditor;
+#[cfg(feature = "vcs")]
+use breezyshim::{error::Error as BrzError, prelude::*, workingtree::GenericWorkingTree};
+use std::path::{Path, PathBuf};
+
+/// Error accessing a package.
+#[derive(Debug)]
+pub enum PackageDirError {
+    /// Error from breezy
+    #[cfg(feature = "vcs")]
+    Brz(Box<BrzError>),
+    /// Error editing a file
+    Editor(Box<crate::editor::EditorError>),
+    /// Error opening the control file
+    Control(Box<crate::error::Error>),
+}
+
+impl std::fmt::Display for PackageDirError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(feature = "vcs")]
+            PackageDirError::Brz(e) => write!(f, "{}", e),
+            PackageDirError::Editor(e) => write!(f, "{}", e),
+            PackageDirError::Control(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for PackageDirError {}
+
+#[cfg(feature = "vcs")]
+impl From<BrzError> for PackageDirError {
+    fn from(e: BrzError) -> Self {
+        PackageDirError::Brz(Box::new(e))
+    }
+}
+
+impl From<crate::editor::EditorError> for PackageDirError {
+    fn from(e: crate::editor::EditorError) -> Self {
+        PackageDirError::Editor(Box::new(e))
+    }
+}
+
+impl From<crate::error::Error> for PackageDirError {
+    fn from(e: crate::error::Error) -> Self {
+        PackageDirError::Control(Box::new(e))
+    }
+}
+
+/// A package, in a working tree or in a plain directory.
+pub enum PackageDir {
+    /// A package in a working tree
+    #[cfg(feature = "vcs")]
+    Tree {
+        /// The working tree
+        tree: GenericWorkingTree,
+        /// Path of the package in the tree
+        subpath: PathBuf,
+    },
+    /// A package in a directory that is not under version control
+    Plain(PathBuf),
+}
+
+impl PackageDir {
+    /// Open the package in a directory.
+    ///
+    /// Falls back to treating the directory as a plain directory if it is
+    /// not in a working tree.
+    ///
+    /// # Arguments
+    /// * `path` - Root of the package
+    #[cfg(feature = "vcs")]
+    pub fn open(path: &Path) -> Result<Self, PackageDirError> {
+        match breezyshim::workingtree::open_containing(path) {
+            Ok((tree, subpath)) => Ok(PackageDir::Tree { tree, subpath }),
+            Err(BrzError::NotBranchError(..)) => {
+                log::debug!(
+                    "{} is not under version control, editing files directly",
+                    path.display()
+                );
+                Ok(PackageDir::Plain(path.to_path_buf()))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Open the package in a directory.
+    ///
+    /// Without the `vcs` feature, the directory is always treated as a
+    /// plain directory.
+    ///
+    /// # Arguments
+    /// * `path` - Root of the package
+    #[cfg(not(feature = "vcs"))]
+    pub fn open(path: &Path) -> Result<Self, PackageDirError> {
+        Ok(PackageDir::Plain(path.to_path_buf()))
+    }
+
+    /// The working tree and path of the package in it, if any.
+    #[cfg(feature = "vcs")]
+    pub fn tree(&self) -> Option<(&GenericWorkingTree, &Path)> {
+        match self {
+            PackageDir::Tree { tree, subpath } => Some((tree, subpath.as_path())),
+            PackageDir::Plain(_) => None,
+        }
+    }
+
+    /// Whether the package is in a plain directory.
+    pub fn is_plain(&self) -> bool {
+        matches!(self, PackageDir::Plain(_))
+    }
+
+    /// Absolute path to the root of the package.
+    pub fn base(&self) -> Result<PathBuf, PackageDirError> {
+        match self {
+            #[cfg(feature = "vcs")]
+            PackageDir::Tree { tree, subpath } => Ok(tree.abspath(subpath)?),
+            PackageDir::Plain(path) => Ok(path.clone()),
+        }
+    }
+
+    /// Open the control file for editing.
+    pub fn edit_control<'a>(&self) -> Result<Box<dyn AbstractControlEditor + 'a>, PackageDirError> {
+        Ok(match self {
+            #[cfg(feature = "vcs")]
+            PackageDir::Tree { tree, subpath } => {
+                crate::abstract_control::edit_control(tree, subpath)?
+            }
+            PackageDir::Plain(path) => crate::abstract_control::open_control(path)?,
+        })
+    }
+
+    /// Add an entry to debian/changelog.
+    ///
+    /// # Arguments
+    /// * `entry` - Changelog entry
+    pub fn add_changelog_entry(&self, entry: &[&str]) -> Result<(), PackageDirError> {
+        match self {
+            #[cfg(feature = "vcs")]
+            PackageDir::Tree { tree, subpath } => {
+                crate::add_changelog_entry(tree, &subpath.join("debian/changelog"), entry)?
+            }
+            PackageDir::Plain(path) => {
+                crate::add_changelog_entry_path(&path.join("debian/changelog"), entry)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Guess whether debian/changelog should be updated when making changes.
+    pub fn guess_update_changelog(&self) -> Option<crate::detect_gbp_dch::ChangelogBehaviour> {
+        match self {
+            #[cfg(feature = "vcs")]
+            PackageDir::Tree { tree, subpath } => {
+                let debian_path = if crate::control_files_in_root(tree, subpath) {
+                    subpath.clone()
+                } else {
+                    subpath.join("debian")
+                };
+                crate::detect_gbp_dch::guess_update_changelog(tree, &debian_path, None)
+            }
+            PackageDir::Plain(path) => {
+                crate::detect_gbp_dch::guess_update_changelog_from_directory(path)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain() {
+        let td = tempfile::tempdir().unwrap();
+        let package = PackageDir::Plain(td.path().to_path_buf());
+        std::fs::create_dir(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\nStandards-Version: 4.6.0\n\nPackage: foo\nArchitecture: all\n",
+        )
+        .unwrap();
+        assert!(package.is_plain());
+        #[cfg(feature = "vcs")]
+        assert!(package.tree().is_none());
+        assert_eq!(td.path(), package.base().unwrap());
+
+        let mut editor = package.edit_control().unwrap();
+        editor.source().unwrap().set_standards_version("4.7.0");
+        assert!(editor.commit().unwrap());
+        assert_eq!(
+            "Source: foo\nStandards-Version: 4.7.0\n\nPackage: foo\nArchitecture: all\n",
+            std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+        );
+    }
+}
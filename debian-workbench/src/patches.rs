@@ -1,15 +1,20 @@
 //! Functions for working with patches in a Debian package.
-use breezyshim::delta::filter_excluded;
-use breezyshim::error::Error as BrzError;
-use breezyshim::patches::AppliedPatches;
-use breezyshim::prelude::*;
-use breezyshim::tree::{PyTree, Tree};
-use breezyshim::workingtree::{PyWorkingTree, WorkingTree};
-use breezyshim::workspace::reset_tree_with_dirty_tracker;
-use breezyshim::RevisionId;
+#[cfg(feature = "vcs")]
+use breezyshim::{
+    delta::filter_excluded,
+    error::Error as BrzError,
+    patches::AppliedPatches,
+    prelude::*,
+    tree::{PyTree, Tree},
+    workingtree::{PyWorkingTree, WorkingTree},
+    workspace::reset_tree_with_dirty_tracker,
+    RevisionId,
+};
+#[cfg(feature = "vcs")]
 use debian_changelog::ChangeLog;
 use patchkit::quilt::QuiltPatch;
 use patchkit::unified::UnifiedPatch;
+#[cfg(feature = "vcs")]
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
@@ -17,6 +22,7 @@ use std::path::{Path, PathBuf};
 /// Default name of the patches directory.
 pub const DEFAULT_DEBIAN_PATCHES_DIR: &str = "debian/patches";
 
+#[cfg(feature = "vcs")]
 /// Find the name of the patches directory.
 ///
 /// This will always return a path, even if the patches directory does not yet exist.
@@ -33,7 +39,7 @@ pub fn tree_patches_directory(tree: &dyn PyTree, subpath: &Path) -> PathBuf {
     find_patches_directory(tree, subpath).unwrap_or(DEFAULT_DEBIAN_PATCHES_DIR.into())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "vcs"))]
 mod tree_patches_directory_tests {
     use super::*;
 
@@ -123,6 +129,7 @@ fn test_rules_find_patches_directory() {
     );
 }
 
+#[cfg(feature = "vcs")]
 /// Find the patches directory for a package
 pub fn find_patches_directory(tree: &dyn PyTree, subpath: &Path) -> Option<PathBuf> {
     let rules_path = subpath.join("debian/rules");
@@ -160,6 +167,7 @@ pub fn find_patches_directory(tree: &dyn PyTree, subpath: &Path) -> Option<PathB
     None
 }
 
+#[cfg(feature = "vcs")]
 /// Find the base revision to apply patches to.
 ///
 /// * `tree` - Tree to find the patch base for
@@ -193,7 +201,7 @@ pub fn find_patch_base(tree: &dyn WorkingTree) -> Option<RevisionId> {
     possible_tags.iter().find_map(|tag| tags.get(tag).cloned())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "vcs"))]
 mod find_patch_base_tests {
     const COMMITTER: &str = "Test Suite <test@suite.example.com>";
     use super::*;
@@ -252,6 +260,7 @@ mod find_patch_base_tests {
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Find the branch that is used to track patches.
 ///
 /// * `tree` - Tree for which to find patches branch
@@ -291,7 +300,7 @@ pub fn find_patches_branch(tree: &dyn WorkingTree) -> Option<Box<dyn Branch>> {
     None
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "vcs"))]
 mod find_patches_branch_tests {
     use super::*;
     use breezyshim::workingtree::{GenericWorkingTree, WorkingTree};
@@ -374,6 +383,7 @@ mod find_patches_branch_tests {
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Add a new patch.
 ///
 /// # Arguments
@@ -443,6 +453,7 @@ pub fn add_patch(
     Ok((specific_files, patchname))
 }
 
+#[cfg(feature = "vcs")]
 /// Move upstream changes to patch.
 ///
 /// # Arguments
@@ -491,7 +502,7 @@ where
     Ok((specific_files, patchname))
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "vcs"))]
 mod move_upstream_changes_to_patch_tests {
     use super::*;
     use breezyshim::controldir::ControlDirFormat;
@@ -558,6 +569,7 @@ Last-Update: 2020-01-01
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Read quilt patches from a directory.
 pub fn read_quilt_patches<'a>(
     tree: &'a dyn Tree,
@@ -591,7 +603,7 @@ pub fn read_quilt_patches<'a>(
     ret.into_iter().flat_map(|p| p.parse().unwrap())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "vcs"))]
 mod read_quilt_patches_tests {
     const COMMITTER: &str = "Test Suite <test@suite.example.com>";
     use super::*;
@@ -688,6 +700,7 @@ mod read_quilt_patches_tests {
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Get the upstream tree with patches applied.
 pub fn upstream_with_applied_patches(
     tree: breezyshim::workingtree::GenericWorkingTree,
@@ -709,7 +722,7 @@ pub fn upstream_with_applied_patches(
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "vcs"))]
 mod upstream_with_applied_patches_tests {
     const COMMITTER: &str = "Test Suite <test@suite.example.com>";
     use super::*;
@@ -799,6 +812,7 @@ mod upstream_with_applied_patches_tests {
     }
 }
 
+#[cfg(feature = "vcs")]
 /// Check if a Debian tree has changes vs upstream tree.
 pub fn tree_non_patches_changes(
     tree: breezyshim::workingtree::GenericWorkingTree,
@@ -835,7 +849,7 @@ pub fn tree_non_patches_changes(
         .collect())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "vcs"))]
 mod tree_non_patches_changes_tests {
     const COMMITTER: &str = "Test Suite <test@suite.example.com>";
     use super::*;
@@ -936,3 +950,2583 @@ mod tree_non_patches_changes_tests {
         std::mem::drop(td);
     }
 }
+
+/// Name of the directory quilt uses to track applied patches.
+pub const QUILT_PC_DIR: &str = ".pc";
+
+/// Error applying or unapplying a quilt series.
+#[derive(Debug)]
+pub enum QuiltError {
+    /// I/O error while reading patches or touching the tree.
+    Io(std::io::Error),
+    /// A patch in the series could not be parsed.
+    InvalidPatch {
+        /// Name of the patch
+        patch: String,
+        /// Description of the problem
+        reason: String,
+    },
+    /// A patch in the series does not apply.
+    PatchDoesNotApply(String),
+}
+
+impl std::fmt::Display for QuiltError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuiltError::Io(e) => write!(f, "I/O error: {}", e),
+            QuiltError::InvalidPatch { patch, reason } => {
+                write!(f, "Invalid patch {}: {}", patch, reason)
+            }
+            QuiltError::PatchDoesNotApply(patch) => write!(f, "Patch {} does not apply", patch),
+        }
+    }
+}
+
+impl std::error::Error for QuiltError {}
+
+impl From<std::io::Error> for QuiltError {
+    fn from(e: std::io::Error) -> Self {
+        QuiltError::Io(e)
+    }
+}
+
+/// Outcome of applying a single patch from a quilt series.
+#[derive(Debug, Clone)]
+pub struct PatchApplication {
+    /// Name of the patch, as listed in the series file.
+    pub name: String,
+
+    /// Per-file results, in patch order.
+    pub files: Vec<patchkit::apply_tree::FileReport>,
+}
+
+impl PatchApplication {
+    /// Whether every hunk of the patch applied, possibly with offset or fuzz.
+    pub fn applied(&self) -> bool {
+        self.files.iter().all(|f| f.applied)
+    }
+
+    /// Largest fuzz that was needed for any hunk.
+    pub fn fuzz(&self) -> usize {
+        self.files
+            .iter()
+            .flat_map(|f| f.hunks.iter())
+            .map(|h| h.fuzz)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Hunks that could not be applied, as (file, 1-based hunk number) pairs.
+    pub fn conflicts(&self) -> Vec<(PathBuf, usize)> {
+        self.files
+            .iter()
+            .flat_map(|f| {
+                f.hunks
+                    .iter()
+                    .filter(|h| !h.applied())
+                    .map(|h| (f.path.clone(), h.index + 1))
+            })
+            .collect()
+    }
+}
+
+fn read_series_file(
+    base: &Path,
+    patches_directory: &Path,
+) -> std::io::Result<patchkit::quilt::Series> {
+    match std::fs::File::open(base.join(patches_directory).join("series")) {
+        Ok(f) => patchkit::quilt::Series::read(f),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(patchkit::quilt::Series::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Strip level for a patch, from its series options (e.g. `-p0`).
+fn series_strip_level(options: &[String]) -> u32 {
+    options
+        .iter()
+        .find_map(|o| o.strip_prefix("-p").and_then(|p| p.parse().ok()))
+        .unwrap_or(1)
+}
+
+/// Files in the tree touched by a patch, after stripping.
+fn patch_targets(
+    name: &str,
+    patches: &[UnifiedPatch],
+    strip: u32,
+) -> Result<Vec<PathBuf>, QuiltError> {
+    let mut ret = vec![];
+    for patch in patches {
+        for raw in [&patch.orig_name, &patch.mod_name] {
+            let raw = String::from_utf8_lossy(raw);
+            let raw = raw.split('\t').next().unwrap_or_default();
+            if raw == "/dev/null" {
+                continue;
+            }
+            let path = patchkit::strip_prefix(Path::new(raw), strip as usize);
+            if path.as_os_str().is_empty()
+                || path
+                    .components()
+                    .any(|c| !matches!(c, std::path::Component::Normal(_)))
+            {
+                return Err(QuiltError::InvalidPatch {
+                    patch: name.to_string(),
+                    reason: format!("unsafe path {}", raw),
+                });
+            }
+            if !ret.iter().any(|p: &PathBuf| p == path) {
+                ret.push(path.to_path_buf());
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Restore the files backed up for a patch in `.pc/<patch>` and remove the backup.
+///
+/// Following quilt, an empty backup file means the file did not exist before the patch.
+fn restore_backup(base: &Path, backup_dir: &Path) -> std::io::Result<()> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                walk(&entry.path(), out)?;
+            } else {
+                out.push(entry.path());
+            }
+        }
+        Ok(())
+    }
+    if !backup_dir.is_dir() {
+        return Ok(());
+    }
+    let mut backups = vec![];
+    walk(backup_dir, &mut backups)?;
+    for backup in backups {
+        let target = base.join(backup.strip_prefix(backup_dir).unwrap());
+        if std::fs::metadata(&backup)?.len() == 0 {
+            match std::fs::remove_file(&target) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        } else {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&backup, &target)?;
+        }
+    }
+    std::fs::remove_dir_all(backup_dir)
+}
+
+/// List the patches that quilt has recorded as applied in a tree.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// Names of applied patches, in application order
+pub fn applied_quilt_patches(base: &Path) -> std::io::Result<Vec<String>> {
+    match std::fs::read_to_string(base.join(QUILT_PC_DIR).join("applied-patches")) {
+        Ok(text) => Ok(text
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .map(|l| l.to_string())
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_applied_patches(base: &Path, applied: &[String]) -> std::io::Result<()> {
+    let mut text = String::new();
+    for name in applied {
+        text.push_str(name);
+        text.push('\n');
+    }
+    std::fs::write(base.join(QUILT_PC_DIR).join("applied-patches"), text)
+}
+
+/// Apply all unapplied patches in a quilt series to a tree on disk.
+///
+/// Progress is tracked in `.pc` the same way quilt does, so the result can be
+/// inspected or unapplied with quilt itself. Application stops at the first
+/// patch that does not apply; that patch is reported but any partial changes
+/// it made are reverted and it is not recorded as applied.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+/// * `fuzz` - Maximum number of context lines that may be ignored per hunk
+///
+/// # Returns
+/// One result per patch that was attempted, in series order
+pub fn quilt_push_all(
+    base: &Path,
+    patches_directory: &Path,
+    fuzz: usize,
+) -> Result<Vec<PatchApplication>, QuiltError> {
+    let series = read_series_file(base, patches_directory)?;
+    let mut applied = applied_quilt_patches(base)?;
+    let pc_dir = base.join(QUILT_PC_DIR);
+    let mut ret = vec![];
+    for entry in series.iter() {
+        let (name, options) = match entry {
+            patchkit::quilt::SeriesEntry::Patch { name, options } => (name, options),
+            patchkit::quilt::SeriesEntry::Comment(_) => continue,
+        };
+        if applied.contains(name) {
+            continue;
+        }
+        if !pc_dir.exists() {
+            std::fs::create_dir_all(&pc_dir)?;
+            std::fs::write(pc_dir.join(".version"), "2\n")?;
+            std::fs::write(
+                pc_dir.join(".quilt_patches"),
+                format!("{}\n", patches_directory.display()),
+            )?;
+            std::fs::write(pc_dir.join(".quilt_series"), "series\n")?;
+        }
+        let quilt_patch = QuiltPatch {
+            name: name.to_string(),
+            patch: std::fs::read(base.join(patches_directory).join(name))?,
+            options: options.to_vec(),
+        };
+        let patches = quilt_patch.parse().map_err(|e| QuiltError::InvalidPatch {
+            patch: name.to_string(),
+            reason: e.to_string(),
+        })?;
+        let strip = series_strip_level(options);
+
+        let backup_dir = pc_dir.join(name);
+        for target in patch_targets(name, &patches, strip)? {
+            let backup = backup_dir.join(&target);
+            std::fs::create_dir_all(backup.parent().unwrap())?;
+            match std::fs::copy(base.join(&target), &backup) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    std::fs::write(&backup, b"")?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let options = patchkit::apply_tree::ApplyToTreeOptions {
+            apply: patchkit::apply::ApplyOptions::with_fuzz(fuzz),
+            strip,
+            backup_suffix: None,
+            ..Default::default()
+        };
+        let report = patchkit::apply_tree::apply_to_tree(base, &patches, &options, None).map_err(
+            |e| match e {
+                patchkit::apply_tree::Error::Io(e) => QuiltError::Io(e),
+                patchkit::apply_tree::Error::Malformed(reason) => QuiltError::InvalidPatch {
+                    patch: name.to_string(),
+                    reason,
+                },
+            },
+        )?;
+        let result = PatchApplication {
+            name: name.to_string(),
+            files: report.files,
+        };
+        if !result.applied() {
+            restore_backup(base, &backup_dir)?;
+            ret.push(result);
+            break;
+        }
+        applied.push(name.to_string());
+        write_applied_patches(base, &applied)?;
+        ret.push(result);
+    }
+    Ok(ret)
+}
+
+/// Unapply all patches that quilt has recorded as applied in a tree.
+///
+/// Files are restored from the backups in `.pc`, after which `.pc` is removed.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// Names of the patches that were unapplied, in the order they were unapplied
+pub fn quilt_pop_all(base: &Path) -> Result<Vec<String>, QuiltError> {
+    let mut applied = applied_quilt_patches(base)?;
+    let pc_dir = base.join(QUILT_PC_DIR);
+    let mut ret = vec![];
+    while let Some(name) = applied.pop() {
+        restore_backup(base, &pc_dir.join(&name))?;
+        write_applied_patches(base, &applied)?;
+        ret.push(name);
+    }
+    if pc_dir.exists() {
+        std::fs::remove_dir_all(&pc_dir)?;
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod quilt_push_all_tests {
+    use super::*;
+
+    fn setup(patches: &[(&str, &str)]) -> tempfile::TempDir {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/patches")).unwrap();
+        std::fs::write(td.path().join("a"), "line 1\nline 2\nline 3\n").unwrap();
+        let mut series = String::new();
+        for (name, contents) in patches {
+            std::fs::write(td.path().join("debian/patches").join(name), contents).unwrap();
+            series.push_str(name);
+            series.push('\n');
+        }
+        std::fs::write(td.path().join("debian/patches/series"), series).unwrap();
+        td
+    }
+
+    const MODIFY: &str = "\
+--- a/a
++++ b/a
+@@ -1,3 +1,3 @@
+ line 1
+-line 2
++new line 2
+ line 3
+";
+
+    const CREATE: &str = "\
+--- /dev/null
++++ b/b
+@@ -0,0 +1 @@
++new file
+";
+
+    #[test]
+    fn test_push_and_pop() {
+        let td = setup(&[("modify.patch", MODIFY), ("create.patch", CREATE)]);
+        let results = quilt_push_all(td.path(), Path::new("debian/patches"), 0).unwrap();
+        assert_eq!(2, results.len());
+        assert!(results.iter().all(|r| r.applied() && r.fuzz() == 0));
+        assert_eq!(
+            "line 1\nnew line 2\nline 3\n",
+            std::fs::read_to_string(td.path().join("a")).unwrap()
+        );
+        assert!(td.path().join("b").exists());
+        assert_eq!(
+            vec!["modify.patch", "create.patch"],
+            applied_quilt_patches(td.path()).unwrap()
+        );
+
+        // Pushing again is a no-op.
+        assert!(quilt_push_all(td.path(), Path::new("debian/patches"), 0)
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(
+            vec!["create.patch", "modify.patch"],
+            quilt_pop_all(td.path()).unwrap()
+        );
+        assert_eq!(
+            "line 1\nline 2\nline 3\n",
+            std::fs::read_to_string(td.path().join("a")).unwrap()
+        );
+        assert!(!td.path().join("b").exists());
+        assert!(!td.path().join(".pc").exists());
+    }
+
+    #[test]
+    fn test_conflict() {
+        let conflicting = MODIFY
+            .replace(" line 3\n", " line 4\n")
+            .replace("-line 2", "-line 9");
+        let td = setup(&[("create.patch", CREATE), ("bad.patch", &conflicting)]);
+        let results = quilt_push_all(td.path(), Path::new("debian/patches"), 0).unwrap();
+        assert_eq!(2, results.len());
+        assert!(results[0].applied());
+        assert!(!results[1].applied());
+        assert_eq!(vec![(PathBuf::from("a"), 1)], results[1].conflicts());
+        assert_eq!(
+            vec!["create.patch"],
+            applied_quilt_patches(td.path()).unwrap()
+        );
+        assert_eq!(
+            "line 1\nline 2\nline 3\n",
+            std::fs::read_to_string(td.path().join("a")).unwrap()
+        );
+        assert!(!td.path().join(".pc/bad.patch").exists());
+    }
+
+    #[test]
+    fn test_fuzz() {
+        let fuzzy = MODIFY.replace(" line 1\n", " line 0\n");
+        let td = setup(&[("fuzzy.patch", &fuzzy)]);
+        let results = quilt_push_all(td.path(), Path::new("debian/patches"), 2).unwrap();
+        assert!(results[0].applied());
+        assert_eq!(1, results[0].fuzz());
+    }
+
+    #[test]
+    fn test_unsafe_path() {
+        let td = setup(&[("evil.patch", &MODIFY.replace("b/a", "b/../a"))]);
+        assert!(matches!(
+            quilt_push_all(td.path(), Path::new("debian/patches"), 0),
+            Err(QuiltError::InvalidPatch { .. })
+        ));
+    }
+}
+
+/// How much of a patch is already present in an upstream tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamMergeStatus {
+    /// All changes in the patch are present upstream.
+    Merged,
+    /// Some, but not all, changes in the patch are present upstream.
+    PartiallyMerged,
+    /// None of the changes in the patch are present upstream.
+    NotMerged,
+}
+
+/// Determine whether the changes in a patch are already present in a tree.
+///
+/// A hunk counts as merged if its reverse applies exactly to the file
+/// contents, while the hunk itself does not.
+///
+/// # Arguments
+/// * `patches` - Parsed file patches that make up a quilt patch
+/// * `strip` - Number of leading path components to strip from patch file names
+/// * `get_file` - Callback returning the contents of a file in the tree, or
+///   `None` if it does not exist
+pub fn patch_merge_status(
+    patches: &[UnifiedPatch],
+    strip: usize,
+    get_file: impl Fn(&Path) -> Option<Vec<u8>>,
+) -> UpstreamMergeStatus {
+    let options = patchkit::apply::ApplyOptions::default();
+    let (mut merged, mut total) = (0, 0);
+    for patch in patches {
+        let name = |raw: &[u8]| -> Option<PathBuf> {
+            let raw = String::from_utf8_lossy(raw);
+            let raw = raw.split('\t').next().unwrap_or_default().to_string();
+            if raw == "/dev/null" {
+                None
+            } else {
+                Some(patchkit::strip_prefix(Path::new(&raw), strip).to_path_buf())
+            }
+        };
+        match (name(&patch.orig_name), name(&patch.mod_name)) {
+            (Some(orig), None) => {
+                // The patch removes the file.
+                total += 1;
+                if get_file(&orig).is_none() {
+                    merged += 1;
+                }
+            }
+            (_, Some(path)) => {
+                let contents = get_file(&path);
+                for hunk in &patch.hunks {
+                    total += 1;
+                    let Some(contents) = contents.as_ref() else {
+                        continue;
+                    };
+                    let reverse = patchkit::apply::dry_run(
+                        contents,
+                        std::slice::from_ref(&hunk.reverse()),
+                        &options,
+                    );
+                    let forward =
+                        patchkit::apply::dry_run(contents, std::slice::from_ref(hunk), &options);
+                    if reverse.hunks.iter().all(|h| h.applied())
+                        && !forward.hunks.iter().all(|h| h.applied())
+                    {
+                        merged += 1;
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    if total == 0 || merged == 0 {
+        UpstreamMergeStatus::NotMerged
+    } else if merged == total {
+        UpstreamMergeStatus::Merged
+    } else {
+        UpstreamMergeStatus::PartiallyMerged
+    }
+}
+
+#[cfg(feature = "vcs")]
+/// Find which patches in a quilt series are already present in an upstream tree.
+///
+/// This is useful after merging a new upstream version, to find patches that
+/// can be dropped or marked as applied upstream.
+///
+/// # Arguments
+/// * `tree` - Tree containing the packaging
+/// * `patches_directory` - Path to the patches directory in `tree`
+/// * `upstream_tree` - Tree with the new upstream sources
+///
+/// # Returns
+/// The merge status of each patch, in series order
+pub fn find_patches_merged_upstream(
+    tree: &dyn Tree,
+    patches_directory: &Path,
+    upstream_tree: &dyn Tree,
+) -> Result<Vec<(String, UpstreamMergeStatus)>, String> {
+    let series_path = patches_directory.join("series");
+    let series = match tree.get_file(series_path.as_path()) {
+        Ok(f) => patchkit::quilt::Series::read(f)
+            .map_err(|e| format!("Failed to read {}: {}", series_path.display(), e))?,
+        Err(BrzError::NoSuchFile(..)) => patchkit::quilt::Series::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", series_path.display(), e)),
+    };
+    let mut ret = vec![];
+    for entry in series.iter() {
+        let (name, options) = match entry {
+            patchkit::quilt::SeriesEntry::Patch { name, options } => (name, options),
+            patchkit::quilt::SeriesEntry::Comment(_) => continue,
+        };
+        let patch = QuiltPatch {
+            name: name.to_string(),
+            patch: tree
+                .get_file_text(&patches_directory.join(name))
+                .map_err(|e| format!("Failed to read patch {}: {}", name, e))?,
+            options: options.to_vec(),
+        };
+        let patches = patch
+            .parse()
+            .map_err(|e| format!("Failed to parse patch {}: {}", name, e))?;
+        let status = patch_merge_status(&patches, series_strip_level(options) as usize, |path| {
+            upstream_tree.get_file_text(path).ok()
+        });
+        ret.push((name.to_string(), status));
+    }
+    Ok(ret)
+}
+
+/// Split a patch into its DEP-3 header and the remainder.
+///
+/// An empty header is returned if the patch does not have one yet, in which
+/// case the remainder starts with a `---` separator.
+fn split_patch_header(
+    contents: &str,
+) -> Result<(dep3::lossless::PatchHeader, String), deb822_lossless::ParseError> {
+    let end = dep3::lossless::header_end(contents);
+    if contents[..end].trim().is_empty() {
+        let body = &contents[end..];
+        let body = if body.starts_with("---\n") {
+            body.to_string()
+        } else {
+            format!("---\n{}", body)
+        };
+        Ok((dep3::lossless::PatchHeader::new(), body))
+    } else {
+        let (header, end) = dep3::lossless::PatchHeader::parse_relaxed(contents)?;
+        Ok((header, contents[end..].to_string()))
+    }
+}
+
+/// Set the Applied-Upstream field in the DEP-3 header of a patch.
+///
+/// A header is added if the patch does not have one yet.
+///
+/// # Arguments
+/// * `contents` - Contents of the patch file
+/// * `applied_upstream` - Where the patch was applied upstream
+///
+/// # Returns
+/// The updated patch contents
+pub fn set_patch_applied_upstream(
+    contents: &str,
+    applied_upstream: dep3::AppliedUpstream,
+) -> Result<String, deb822_lossless::ParseError> {
+    let (mut header, body) = split_patch_header(contents)?;
+    // PatchHeader::set_applied_upstream appends rather than replaces an existing field.
+    header
+        .as_deb822_mut()
+        .set("Applied-Upstream", applied_upstream.to_string().as_str());
+    Ok(format!("{}{}", header, body))
+}
+
+#[cfg(feature = "vcs")]
+/// Mark a patch in a tree as applied upstream.
+///
+/// # Arguments
+/// * `tree` - Tree to edit
+/// * `patches_directory` - Path to the patches directory in `tree`
+/// * `name` - Name of the patch, as listed in the series file
+/// * `applied_upstream` - Where the patch was applied upstream
+///
+/// # Returns
+/// Path of the patch file that was modified
+pub fn mark_patch_applied_upstream(
+    tree: &dyn PyWorkingTree,
+    patches_directory: &Path,
+    name: &str,
+    applied_upstream: dep3::AppliedUpstream,
+) -> Result<PathBuf, String> {
+    let path = patches_directory.join(name);
+    let contents = tree
+        .get_file_text(&path)
+        .map_err(|e| format!("Failed to read patch {}: {}", name, e))?;
+    let contents =
+        set_patch_applied_upstream(&String::from_utf8_lossy(&contents), applied_upstream)
+            .map_err(|e| format!("Failed to parse header of {}: {}", name, e))?;
+    tree.put_file_bytes_non_atomic(&path, contents.as_bytes())
+        .map_err(|e| format!("Failed to write patch: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(feature = "vcs")]
+/// Remove a patch from the series and from the tree.
+///
+/// # Arguments
+/// * `tree` - Tree to edit
+/// * `patches_directory` - Path to the patches directory in `tree`
+/// * `name` - Name of the patch, as listed in the series file
+///
+/// # Returns
+/// Paths that were changed
+pub fn remove_patch(
+    tree: &dyn PyWorkingTree,
+    patches_directory: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let series_path = patches_directory.join("series");
+    let mut series = match tree.get_file(&series_path) {
+        Ok(f) => patchkit::quilt::Series::read(f)
+            .map_err(|e| format!("Failed to read {}: {}", series_path.display(), e))?,
+        Err(e) => {
+            return Err(format!("Failed to read {}: {}", series_path.display(), e));
+        }
+    };
+    if !series.contains(name) {
+        return Err(format!("Patch {} is not in the series", name));
+    }
+    series.remove(name);
+    let mut series_bytes = Vec::new();
+    series
+        .write(&mut series_bytes)
+        .map_err(|e| format!("Failed to write series: {}", e))?;
+    tree.put_file_bytes_non_atomic(&series_path, series_bytes.as_slice())
+        .map_err(|e| format!("Failed to write series: {}", e))?;
+
+    let path = patches_directory.join(name);
+    if tree.has_filename(&path) {
+        let abspath = tree
+            .abspath(&path)
+            .map_err(|e| format!("Failed to find patch: {}", e))?;
+        tree.remove(&[path.as_path()])
+            .map_err(|e| format!("Failed to remove patch: {}", e))?;
+        if abspath.exists() {
+            std::fs::remove_file(&abspath).map_err(|e| format!("Failed to remove patch: {}", e))?;
+        }
+    }
+    Ok(vec![series_path, path])
+}
+
+#[cfg(test)]
+mod patch_merge_status_tests {
+    use super::*;
+
+    fn parse(patch: &str) -> Vec<UnifiedPatch> {
+        QuiltPatch {
+            name: "test".to_string(),
+            patch: patch.as_bytes().to_vec(),
+            options: vec![],
+        }
+        .parse()
+        .unwrap()
+    }
+
+    const PATCH: &str = "\
+--- a/a
++++ b/a
+@@ -1,3 +1,3 @@
+ line 1
+-line 2
++new line 2
+ line 3
+@@ -5,3 +5,3 @@
+ line 5
+-line 6
++new line 6
+ line 7
+";
+
+    fn status(contents: &str) -> UpstreamMergeStatus {
+        patch_merge_status(&parse(PATCH), 1, |p| {
+            (p == Path::new("a")).then(|| contents.as_bytes().to_vec())
+        })
+    }
+
+    #[test]
+    fn test_merged() {
+        assert_eq!(
+            UpstreamMergeStatus::Merged,
+            status("line 1\nnew line 2\nline 3\nline 4\nline 5\nnew line 6\nline 7\n")
+        );
+    }
+
+    #[test]
+    fn test_partially_merged() {
+        assert_eq!(
+            UpstreamMergeStatus::PartiallyMerged,
+            status("line 1\nnew line 2\nline 3\nline 4\nline 5\nline 6\nline 7\n")
+        );
+    }
+
+    #[test]
+    fn test_not_merged() {
+        assert_eq!(
+            UpstreamMergeStatus::NotMerged,
+            status("line 1\nline 2\nline 3\nline 4\nline 5\nline 6\nline 7\n")
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        assert_eq!(
+            UpstreamMergeStatus::NotMerged,
+            patch_merge_status(&parse(PATCH), 1, |_| None)
+        );
+    }
+
+    #[test]
+    fn test_set_applied_upstream() {
+        assert_eq!(
+            "Description: Fix things\nApplied-Upstream: commit:abcdef\n---\n--- a/a\n+++ b/a\n",
+            set_patch_applied_upstream(
+                "Description: Fix things\n---\n--- a/a\n+++ b/a\n",
+                dep3::AppliedUpstream::Commit("abcdef".into())
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "Applied-Upstream: 1.2\n---\n--- a/a\n+++ b/a\n",
+            set_patch_applied_upstream(
+                "--- a/a\n+++ b/a\n",
+                dep3::AppliedUpstream::Other("1.2".into())
+            )
+            .unwrap()
+        );
+    }
+}
+
+/// Derive a patch file name from a patch description.
+///
+/// # Arguments
+/// * `description` - Description or commit message; only the first line is used
+///
+/// # Returns
+/// Lowercase name without suffix, e.g. "fix-build-with-gcc-14"
+pub fn patch_name_from_description(description: &str) -> String {
+    const MAX_LENGTH: usize = 50;
+    let first_line = description.lines().next().unwrap_or_default();
+    let mut name = String::new();
+    for word in first_line
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        if !name.is_empty() && name.len() + word.len() + 1 > MAX_LENGTH {
+            break;
+        }
+        if !name.is_empty() {
+            name.push('-');
+        }
+        name.push_str(&word.to_ascii_lowercase());
+    }
+    name.truncate(MAX_LENGTH);
+    if name.is_empty() {
+        "patch".to_string()
+    } else {
+        name
+    }
+}
+
+/// Build a DEP-3 header from a commit message.
+///
+/// The first line of the message becomes the description, the remaining
+/// paragraphs the long description.
+pub fn dep3_header_from_message(
+    message: &str,
+    author: Option<&str>,
+    last_update: chrono::NaiveDate,
+) -> dep3::lossless::PatchHeader {
+    let mut header = dep3::lossless::PatchHeader::new();
+    let message = message.trim();
+    let (summary, body) = message.split_once('\n').unwrap_or((message, ""));
+    header.set_description(summary.trim());
+    let body = body.trim();
+    if !body.is_empty() {
+        header.set_long_description(body);
+        // Continuation lines are conventionally indented by a single space.
+        let _ = header
+            .as_deb822_mut()
+            .change_field_indent("Description", &deb822_lossless::IndentPattern::Fixed(1));
+    }
+    if let Some(author) = author.filter(|a| !a.is_empty()) {
+        header.set_author(author);
+    } else {
+        header.set_origin(None, dep3::Origin::Other("other".into()));
+    }
+    header.set_last_update(last_update);
+    header
+}
+
+/// Remove the parts of a unified diff that touch files under a directory.
+///
+/// # Arguments
+/// * `diff` - Diff with `a/` and `b/` prefixes
+/// * `exclude` - Directory to leave out, e.g. `debian`
+///
+/// # Returns
+/// The remaining diff, and the paths it touches
+pub fn filter_diff_excluding(diff: &[u8], exclude: &Path) -> (Vec<u8>, Vec<PathBuf>) {
+    let mut ret = vec![];
+    let mut paths = vec![];
+    let lines = diff.split_inclusive(|&b| b == b'\n').map(|l| l.to_vec());
+    for patch in patchkit::unified::parse_patches(lines) {
+        let Ok(patchkit::unified::PlainOrBinaryPatch::Plain(patch)) = patch else {
+            continue;
+        };
+        let names = [&patch.orig_name, &patch.mod_name]
+            .into_iter()
+            .map(|n| {
+                String::from_utf8_lossy(n)
+                    .split('\t')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .filter(|n| n != "/dev/null")
+            .map(|n| patchkit::strip_prefix(Path::new(&n), 1).to_path_buf())
+            .collect::<Vec<_>>();
+        if names.iter().any(|n| n.starts_with(exclude)) {
+            continue;
+        }
+        for name in names {
+            if !paths.contains(&name) {
+                paths.push(name);
+            }
+        }
+        ret.extend(patch.as_bytes());
+    }
+    (ret, paths)
+}
+
+#[cfg(feature = "vcs")]
+/// Create a patch from the changes to upstream files in a committed revision.
+///
+/// Changes to the packaging itself are left out. The DEP-3 header is generated
+/// from the revision: the committer becomes the author and the commit message
+/// the description.
+///
+/// # Arguments
+/// * `tree` - Tree to add the patch to
+/// * `subpath` - Path to the package in `tree`
+/// * `revid` - Revision to create a patch from
+/// * `name` - Patch name without suffix; derived from the commit message if not set
+///
+/// # Returns
+/// The paths that were changed and the name of the patch (including suffix)
+pub fn create_patch_from_revision(
+    tree: &dyn PyWorkingTree,
+    subpath: &Path,
+    revid: &RevisionId,
+    name: Option<&str>,
+) -> Result<(Vec<PathBuf>, String), String> {
+    let repository = tree.branch().repository();
+    let revision = repository
+        .get_revision(revid)
+        .map_err(|e| format!("Failed to read revision {}: {}", revid, e))?;
+    let new_tree = repository
+        .revision_tree(revid)
+        .map_err(|e| format!("Failed to read revision {}: {}", revid, e))?;
+    let old_tree = repository
+        .revision_tree(revision.parent_ids.first().unwrap_or(&RevisionId::null()))
+        .map_err(|e| format!("Failed to read parent of {}: {}", revid, e))?;
+    let mut diff = Vec::new();
+    breezyshim::diff::show_diff_trees(&old_tree, &new_tree, &mut diff, Some("a/"), Some("b/"))
+        .map_err(|e| format!("Failed to generate diff: {}", e))?;
+    let (diff, paths) = filter_diff_excluding(&diff, &subpath.join("debian"));
+    if paths.is_empty() {
+        return Err(format!("Revision {} does not change upstream files", revid));
+    }
+    let header = dep3_header_from_message(
+        &revision.message,
+        Some(revision.committer.as_str()),
+        revision.datetime().date_naive(),
+    );
+    let name = name.map_or_else(
+        || patch_name_from_description(&revision.message),
+        |n| n.to_string(),
+    );
+    let patches_directory = subpath.join(tree_patches_directory(tree, subpath));
+    add_patch(tree, &patches_directory, &name, &diff, Some(header))
+}
+
+#[cfg(feature = "vcs")]
+/// Create a patch from the uncommitted changes to upstream files in a tree.
+///
+/// The upstream changes are reverted in the tree and moved to a new patch that
+/// is appended to the series. Uncommitted changes to the packaging are left alone.
+/// The author is taken from the committer identity configured for the tree.
+///
+/// # Arguments
+/// * `tree` - Tree with uncommitted changes
+/// * `subpath` - Path to the package in `tree`
+/// * `description` - Description of the change
+/// * `name` - Patch name without suffix; derived from the description if not set
+/// * `timestamp` - Date to record as last update; defaults to today
+///
+/// # Returns
+/// The paths that were changed and the name of the patch (including suffix)
+pub fn create_patch_from_uncommitted_changes(
+    tree: &dyn PyWorkingTree,
+    subpath: &Path,
+    description: &str,
+    name: Option<&str>,
+    timestamp: Option<chrono::NaiveDate>,
+) -> Result<(Vec<PathBuf>, String), String> {
+    let basis_tree = tree
+        .basis_tree()
+        .map_err(|e| format!("Failed to read basis tree: {}", e))?;
+    let mut diff = Vec::new();
+    breezyshim::diff::show_diff_trees(&basis_tree, tree, &mut diff, Some("a/"), Some("b/"))
+        .map_err(|e| format!("Failed to generate diff: {}", e))?;
+    let (diff, paths) = filter_diff_excluding(&diff, &subpath.join("debian"));
+    if paths.is_empty() {
+        return Err("No uncommitted changes to upstream files".to_string());
+    }
+
+    let added = paths
+        .iter()
+        .filter(|p| !basis_tree.has_filename(p))
+        .cloned()
+        .collect::<Vec<_>>();
+    tree.revert(Some(
+        paths
+            .iter()
+            .map(|p| p.as_path())
+            .collect::<Vec<_>>()
+            .as_slice(),
+    ))
+    .map_err(|e| format!("Failed to revert upstream changes: {}", e))?;
+    for path in added {
+        let abspath = tree.basedir().join(path);
+        if abspath.is_file() {
+            std::fs::remove_file(&abspath)
+                .map_err(|e| format!("Failed to remove {}: {}", abspath.display(), e))?;
+        }
+    }
+
+    let committer = crate::get_committer(tree);
+    let header = dep3_header_from_message(
+        description,
+        Some(committer.as_str()),
+        timestamp.unwrap_or_else(|| chrono::Utc::now().naive_utc().date()),
+    );
+    let name = name.map_or_else(
+        || patch_name_from_description(description),
+        |n| n.to_string(),
+    );
+    let patches_directory = subpath.join(tree_patches_directory(tree, subpath));
+    add_patch(tree, &patches_directory, &name, &diff, Some(header))
+}
+
+#[cfg(test)]
+mod create_patch_tests {
+    use super::*;
+
+    #[test]
+    fn test_patch_name_from_description() {
+        assert_eq!(
+            "fix-build-with-gcc-14",
+            patch_name_from_description("Fix build with GCC 14.\n\nMore details.")
+        );
+        assert_eq!("patch", patch_name_from_description("...\n"));
+        let name = patch_name_from_description(
+            "Use the system copy of the library rather than the bundled one in third_party",
+        );
+        assert_eq!("use-the-system-copy-of-the-library-rather-than-the", name);
+    }
+
+    #[test]
+    fn test_dep3_header_from_message() {
+        let header = dep3_header_from_message(
+            "Fix build with GCC 14\n\nAdd a missing include.\n",
+            Some("Jane Doe <jane@example.com>"),
+            chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+        );
+        assert_eq!(
+            "Description: Fix build with GCC 14\n Add a missing include.\nAuthor: Jane Doe <jane@example.com>\nLast-Update: 2024-05-01\n",
+            header.to_string()
+        );
+    }
+
+    #[test]
+    fn test_filter_diff_excluding() {
+        let diff = b"\
+--- a/debian/changelog
++++ b/debian/changelog
+@@ -1 +1 @@
+-old
++new
+--- a/src/main.c
++++ b/src/main.c
+@@ -1 +1 @@
+-old
++new
+";
+        let (filtered, paths) = filter_diff_excluding(diff, Path::new("debian"));
+        assert_eq!(vec![PathBuf::from("src/main.c")], paths);
+        assert_eq!(
+            "--- a/src/main.c\n+++ b/src/main.c\n@@ -1 +1 @@\n-old\n+new\n",
+            String::from_utf8(filtered).unwrap()
+        );
+    }
+}
+
+/// Generate a unified diff for a single file, in the format used by quilt.
+///
+/// # Arguments
+/// * `path` - Path of the file, relative to the root of the source tree
+/// * `old` - Old contents, or `None` if the file is being created
+/// * `new` - New contents, or `None` if the file is being removed
+///
+/// # Returns
+/// The diff, with `a/` and `b/` prefixes; empty if the contents are identical
+pub fn unified_diff_file(path: &Path, old: Option<&[u8]>, new: Option<&[u8]>) -> Vec<u8> {
+    let old_label = if old.is_some() {
+        format!("a/{}", path.display())
+    } else {
+        "/dev/null".to_string()
+    };
+    let new_label = if new.is_some() {
+        format!("b/{}", path.display())
+    } else {
+        "/dev/null".to_string()
+    };
+    unified_diff_labelled(&old_label, &new_label, old, new)
+}
+
+fn unified_diff_labelled(
+    old_label: &str,
+    new_label: &str,
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+) -> Vec<u8> {
+    let old_text = String::from_utf8_lossy(old.unwrap_or_default());
+    let new_text = String::from_utf8_lossy(new.unwrap_or_default());
+    let old_lines = old_text.split_inclusive('\n').collect::<Vec<_>>();
+    let new_lines = new_text.split_inclusive('\n').collect::<Vec<_>>();
+    let mut ret = String::new();
+    for line in difflib::unified_diff(
+        old_lines.as_slice(),
+        new_lines.as_slice(),
+        old_label,
+        new_label,
+        "",
+        "",
+        3,
+    ) {
+        if let Some(label) = line.strip_suffix("\t\n") {
+            ret.push_str(label);
+            ret.push('\n');
+        } else if line.ends_with('\n') {
+            ret.push_str(&line);
+        } else {
+            ret.push_str(&line);
+            ret.push_str("\n\\ No newline at end of file\n");
+        }
+    }
+    ret.into_bytes()
+}
+
+/// Directories that never contain upstream sources.
+const NON_UPSTREAM_DIRECTORIES: &[&str] = &[".git", ".bzr", ".hg", ".svn", QUILT_PC_DIR, "debian"];
+
+fn upstream_files(base: &Path) -> std::io::Result<Vec<PathBuf>> {
+    fn walk(base: &Path, dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(base.join(dir))? {
+            let entry = entry?;
+            let path = dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                if dir.as_os_str().is_empty()
+                    && NON_UPSTREAM_DIRECTORIES
+                        .contains(&entry.file_name().to_string_lossy().as_ref())
+                {
+                    continue;
+                }
+                walk(base, &path, out)?;
+            } else {
+                out.push(path);
+            }
+        }
+        Ok(())
+    }
+    let mut ret = vec![];
+    if base.is_dir() {
+        walk(base, Path::new(""), &mut ret)?;
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Generate a diff of the upstream sources between two directories.
+///
+/// The packaging (`debian/`), quilt state and VCS metadata are ignored.
+///
+/// # Arguments
+/// * `old_base` - Directory with the original sources
+/// * `new_base` - Directory with the modified sources
+pub fn diff_upstream_directories(old_base: &Path, new_base: &Path) -> std::io::Result<Vec<u8>> {
+    let old_files = upstream_files(old_base)?;
+    let new_files = upstream_files(new_base)?;
+    let mut all = old_files.iter().chain(new_files.iter()).collect::<Vec<_>>();
+    all.sort();
+    all.dedup();
+    let mut ret = vec![];
+    for path in all {
+        let read = |base: &Path, files: &[PathBuf]| -> std::io::Result<Option<Vec<u8>>> {
+            if files.contains(path) {
+                Ok(Some(std::fs::read(base.join(path))?))
+            } else {
+                Ok(None)
+            }
+        };
+        let old = read(old_base, &old_files)?;
+        let new = read(new_base, &new_files)?;
+        if old != new {
+            ret.extend(unified_diff_file(path, old.as_deref(), new.as_deref()));
+        }
+    }
+    Ok(ret)
+}
+
+/// How changes to upstream sources are represented in a packaging branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchWorkflow {
+    /// Quilt series in `debian/patches`, with unmodified upstream sources.
+    Unapplied,
+    /// Quilt series in `debian/patches`, with the patches applied to the
+    /// upstream sources in the branch, as done by gbp pq and dgit.
+    Applied,
+    /// Changes committed directly to the upstream sources, with
+    /// `single-debian-patch` set in `debian/source/options`.
+    SingleDebianPatch,
+}
+
+const SOURCE_OPTIONS_PATH: &str = "debian/source/options";
+
+fn has_source_option(base: &Path, option: &str) -> std::io::Result<bool> {
+    match std::fs::read_to_string(base.join(SOURCE_OPTIONS_PATH)) {
+        Ok(text) => Ok(text.lines().any(|l| l.trim() == option)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn set_source_option(base: &Path, option: &str, enabled: bool) -> std::io::Result<()> {
+    let path = base.join(SOURCE_OPTIONS_PATH);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut lines = text
+        .lines()
+        .filter(|l| l.trim() != option)
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>();
+    if enabled {
+        lines.push(option.to_string());
+    }
+    if lines.iter().all(|l| l.trim().is_empty()) {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(&path, lines.join("\n") + "\n")
+}
+
+/// Read and parse all patches in a series, in order.
+fn read_series_patches(
+    base: &Path,
+    patches_directory: &Path,
+) -> Result<Vec<(String, Vec<UnifiedPatch>, u32)>, QuiltError> {
+    let series = read_series_file(base, patches_directory)?;
+    let mut ret = vec![];
+    for name in series.patches() {
+        let options = series
+            .iter()
+            .find_map(|e| match e {
+                patchkit::quilt::SeriesEntry::Patch { name: n, options } if n == name => {
+                    Some(options.to_vec())
+                }
+                _ => None,
+            })
+            .unwrap_or_default();
+        let quilt_patch = QuiltPatch {
+            name: name.to_string(),
+            patch: std::fs::read(base.join(patches_directory).join(name))?,
+            options: options.clone(),
+        };
+        let patches = quilt_patch.parse().map_err(|e| QuiltError::InvalidPatch {
+            patch: name.to_string(),
+            reason: e.to_string(),
+        })?;
+        ret.push((name.to_string(), patches, series_strip_level(&options)));
+    }
+    Ok(ret)
+}
+
+/// Detect how changes to upstream sources are represented in a tree.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+pub fn detect_patch_workflow(
+    base: &Path,
+    patches_directory: &Path,
+) -> Result<PatchWorkflow, QuiltError> {
+    if has_source_option(base, "single-debian-patch")? {
+        return Ok(PatchWorkflow::SingleDebianPatch);
+    }
+    let patches = read_series_patches(base, patches_directory)?;
+    if patches.is_empty() {
+        return Ok(PatchWorkflow::Unapplied);
+    }
+    let all_applied = patches.iter().all(|(_, patches, strip)| {
+        patch_merge_status(patches, *strip as usize, |p| {
+            std::fs::read(base.join(p)).ok()
+        }) == UpstreamMergeStatus::Merged
+    });
+    Ok(if all_applied {
+        PatchWorkflow::Applied
+    } else {
+        PatchWorkflow::Unapplied
+    })
+}
+
+/// Apply the quilt series to the upstream sources, without tracking state in `.pc`.
+///
+/// This converts a tree from [`PatchWorkflow::Unapplied`] to [`PatchWorkflow::Applied`].
+/// If a patch does not apply, the tree is left with no patches applied.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+pub fn apply_patches_in_place(base: &Path, patches_directory: &Path) -> Result<(), QuiltError> {
+    let results = quilt_push_all(base, patches_directory, 0)?;
+    if let Some(failed) = results.iter().find(|r| !r.applied()) {
+        quilt_pop_all(base)?;
+        return Err(QuiltError::PatchDoesNotApply(failed.name.clone()));
+    }
+    let pc_dir = base.join(QUILT_PC_DIR);
+    if pc_dir.exists() {
+        std::fs::remove_dir_all(pc_dir)?;
+    }
+    Ok(())
+}
+
+/// Remove the changes from the quilt series from the upstream sources.
+///
+/// This converts a tree from [`PatchWorkflow::Applied`] to [`PatchWorkflow::Unapplied`].
+/// Patches are reversed in the opposite order of the series. If a patch can
+/// not be reversed, the tree is left unchanged.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+pub fn unapply_patches_in_place(base: &Path, patches_directory: &Path) -> Result<(), QuiltError> {
+    let backup = tempfile::tempdir()?;
+    for (name, patches, strip) in read_series_patches(base, patches_directory)?
+        .into_iter()
+        .rev()
+    {
+        for target in patch_targets(&name, &patches, strip)? {
+            let dest = backup.path().join(&target);
+            if dest.exists() {
+                continue;
+            }
+            std::fs::create_dir_all(dest.parent().unwrap())?;
+            match std::fs::copy(base.join(&target), &dest) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    std::fs::write(&dest, b"")?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let options = patchkit::apply_tree::ApplyToTreeOptions {
+            apply: patchkit::apply::ApplyOptions::default(),
+            strip,
+            reverse: true,
+            backup_suffix: None,
+            ..Default::default()
+        };
+        let applied = match patchkit::apply_tree::apply_to_tree(base, &patches, &options, None) {
+            Ok(report) => report.applied(),
+            Err(patchkit::apply_tree::Error::Io(e)) => return Err(e.into()),
+            Err(patchkit::apply_tree::Error::Malformed(reason)) => {
+                return Err(QuiltError::InvalidPatch {
+                    patch: name,
+                    reason,
+                })
+            }
+        };
+        if !applied {
+            restore_backup(base, backup.path())?;
+            return Err(QuiltError::PatchDoesNotApply(name));
+        }
+    }
+    Ok(())
+}
+
+/// Convert a tree that uses a quilt series to the single-debian-patch workflow.
+///
+/// The patches are applied to the upstream sources (if they are not already),
+/// the series is removed and `single-debian-patch` is added to
+/// `debian/source/options`.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+pub fn convert_to_single_debian_patch(
+    base: &Path,
+    patches_directory: &Path,
+) -> Result<(), QuiltError> {
+    match detect_patch_workflow(base, patches_directory)? {
+        PatchWorkflow::SingleDebianPatch => return Ok(()),
+        PatchWorkflow::Unapplied => apply_patches_in_place(base, patches_directory)?,
+        PatchWorkflow::Applied => {}
+    }
+    let directory = base.join(patches_directory);
+    let series = read_series_file(base, patches_directory)?;
+    for name in series.patches() {
+        let path = directory.join(name);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    if directory.join("series").exists() {
+        std::fs::remove_file(directory.join("series"))?;
+    }
+    if directory.is_dir() && std::fs::read_dir(&directory)?.next().is_none() {
+        std::fs::remove_dir(&directory)?;
+    }
+    set_source_option(base, "single-debian-patch", true)?;
+    Ok(())
+}
+
+/// Convert a tree that uses the single-debian-patch workflow to a quilt series.
+///
+/// All changes to the upstream sources are moved into a single patch that
+/// becomes the series, and `single-debian-patch` is removed from
+/// `debian/source/options`.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `upstream` - Directory with the pristine upstream sources
+/// * `patches_directory` - Patches directory, relative to `base`
+/// * `name` - Name of the patch to create, including suffix
+/// * `workflow` - Either [`PatchWorkflow::Unapplied`] to revert the changes to
+///   the upstream sources, or [`PatchWorkflow::Applied`] to keep them
+pub fn convert_from_single_debian_patch(
+    base: &Path,
+    upstream: &Path,
+    patches_directory: &Path,
+    name: &str,
+    workflow: PatchWorkflow,
+) -> Result<(), QuiltError> {
+    assert_ne!(workflow, PatchWorkflow::SingleDebianPatch);
+    let diff = diff_upstream_directories(upstream, base)?;
+    if !diff.is_empty() {
+        let directory = base.join(patches_directory);
+        std::fs::create_dir_all(&directory)?;
+        let mut header = dep3::lossless::PatchHeader::new();
+        header.set_description("Changes to upstream sources");
+        header.set_forwarded(dep3::Forwarded::NotNeeded);
+        let mut contents = header.to_string().into_bytes();
+        contents.extend_from_slice(b"---\n");
+        contents.extend(diff);
+        std::fs::write(directory.join(name), contents)?;
+        let mut series = read_series_file(base, patches_directory)?;
+        if !series.contains(name) {
+            series.append(name, None);
+        }
+        let mut series_bytes = vec![];
+        series.write(&mut series_bytes)?;
+        std::fs::write(directory.join("series"), series_bytes)?;
+        if workflow == PatchWorkflow::Unapplied {
+            unapply_patches_in_place(base, patches_directory)?;
+        }
+    }
+    set_source_option(base, "single-debian-patch", false)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod patch_workflow_tests {
+    use super::*;
+
+    const PATCH: &str = "\
+--- a/a
++++ b/a
+@@ -1,3 +1,3 @@
+ line 1
+-line 2
++new line 2
+ line 3
+";
+
+    fn setup() -> tempfile::TempDir {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/patches")).unwrap();
+        std::fs::write(td.path().join("a"), "line 1\nline 2\nline 3\n").unwrap();
+        std::fs::write(td.path().join("debian/patches/fix.patch"), PATCH).unwrap();
+        std::fs::write(td.path().join("debian/patches/series"), "fix.patch\n").unwrap();
+        td
+    }
+
+    #[test]
+    fn test_unified_diff_file() {
+        assert_eq!(
+            "--- a/a\n+++ b/a\n@@ -1,3 +1,3 @@\n line 1\n-line 2\n+new line 2\n line 3\n",
+            String::from_utf8(unified_diff_file(
+                Path::new("a"),
+                Some(b"line 1\nline 2\nline 3\n"),
+                Some(b"line 1\nnew line 2\nline 3\n")
+            ))
+            .unwrap()
+        );
+        assert_eq!(
+            "--- /dev/null\n+++ b/b\n@@ -0,0 +1 @@\n+foo\n\\ No newline at end of file\n",
+            String::from_utf8(unified_diff_file(Path::new("b"), None, Some(b"foo"))).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_applied_round_trip() {
+        let td = setup();
+        let patches = Path::new("debian/patches");
+        assert_eq!(
+            PatchWorkflow::Unapplied,
+            detect_patch_workflow(td.path(), patches).unwrap()
+        );
+        apply_patches_in_place(td.path(), patches).unwrap();
+        assert!(!td.path().join(".pc").exists());
+        assert_eq!(
+            PatchWorkflow::Applied,
+            detect_patch_workflow(td.path(), patches).unwrap()
+        );
+        unapply_patches_in_place(td.path(), patches).unwrap();
+        assert_eq!(
+            "line 1\nline 2\nline 3\n",
+            std::fs::read_to_string(td.path().join("a")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_single_debian_patch_round_trip() {
+        let td = setup();
+        let upstream = tempfile::tempdir().unwrap();
+        std::fs::write(upstream.path().join("a"), "line 1\nline 2\nline 3\n").unwrap();
+        let patches = Path::new("debian/patches");
+
+        convert_to_single_debian_patch(td.path(), patches).unwrap();
+        assert!(!td.path().join("debian/patches").exists());
+        assert_eq!(
+            "single-debian-patch\n",
+            std::fs::read_to_string(td.path().join("debian/source/options")).unwrap()
+        );
+        assert_eq!(
+            "line 1\nnew line 2\nline 3\n",
+            std::fs::read_to_string(td.path().join("a")).unwrap()
+        );
+
+        convert_from_single_debian_patch(
+            td.path(),
+            upstream.path(),
+            patches,
+            "debian-changes.patch",
+            PatchWorkflow::Unapplied,
+        )
+        .unwrap();
+        assert!(!td.path().join("debian/source/options").exists());
+        assert_eq!(
+            "debian-changes.patch\n",
+            std::fs::read_to_string(td.path().join("debian/patches/series")).unwrap()
+        );
+        assert_eq!(
+            "line 1\nline 2\nline 3\n",
+            std::fs::read_to_string(td.path().join("a")).unwrap()
+        );
+        let patch =
+            std::fs::read_to_string(td.path().join("debian/patches/debian-changes.patch")).unwrap();
+        assert!(patch.ends_with(PATCH), "{:?}", patch);
+    }
+}
+
+/// Outcome of refreshing a single patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefreshStatus {
+    /// The patch applies exactly and was left alone.
+    Unchanged,
+    /// The patch applied with offset or fuzz and was regenerated.
+    Refreshed,
+    /// The patch no longer applies; lists (file, 1-based hunk number) pairs that failed.
+    Failed(Vec<(PathBuf, usize)>),
+}
+
+/// Offset of the first file diff in a patch, i.e. the end of its header.
+fn patch_body_start(contents: &str) -> usize {
+    let mut offset = 0;
+    let mut lines = contents.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        if line.starts_with("diff ")
+            || line.starts_with("Index: ")
+            || (line.starts_with("--- ") && lines.peek().is_some_and(|l| l.starts_with("+++ ")))
+        {
+            return offset;
+        }
+        offset += line.len();
+    }
+    contents.len()
+}
+
+/// Refresh all patches in a quilt series against the current upstream sources.
+///
+/// Each patch is applied in memory on top of the previous ones. Patches that
+/// only apply with offset or fuzz are regenerated with updated context, keeping
+/// their header. Patches that no longer apply are reported and left alone.
+/// The tree is expected to have the patches unapplied.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+/// * `fuzz` - Maximum number of context lines that may be ignored per hunk
+///
+/// # Returns
+/// The outcome for each patch, in series order
+pub fn refresh(
+    base: &Path,
+    patches_directory: &Path,
+    fuzz: usize,
+) -> Result<Vec<(String, RefreshStatus)>, QuiltError> {
+    let mut files: std::collections::HashMap<PathBuf, Option<Vec<u8>>> =
+        std::collections::HashMap::new();
+    let options = patchkit::apply::ApplyOptions::with_fuzz(fuzz);
+    let mut ret = vec![];
+    for (name, patches, strip) in read_series_patches(base, patches_directory)? {
+        let patch_path = base.join(patches_directory).join(&name);
+        let contents = String::from_utf8_lossy(&std::fs::read(&patch_path)?).into_owned();
+        let mut updated = std::collections::HashMap::new();
+        let mut body = Vec::new();
+        let mut conflicts = vec![];
+        let mut needs_refresh = false;
+        for patch in &patches {
+            let label = |raw: &[u8]| {
+                String::from_utf8_lossy(raw)
+                    .split('\t')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let (orig_label, mod_label) = (label(&patch.orig_name), label(&patch.mod_name));
+            let stripped = |l: &str| {
+                (l != "/dev/null")
+                    .then(|| patchkit::strip_prefix(Path::new(l), strip as usize).to_path_buf())
+            };
+            let (orig, modified) = (stripped(&orig_label), stripped(&mod_label));
+            let Some(path) = modified.clone().or(orig.clone()) else {
+                continue;
+            };
+            let current = |p: &PathBuf| -> Option<Vec<u8>> {
+                updated
+                    .get(p)
+                    .or_else(|| files.get(p))
+                    .cloned()
+                    .unwrap_or_else(|| std::fs::read(base.join(p)).ok())
+            };
+            let old = match orig.as_ref() {
+                Some(orig) => match current(orig) {
+                    Some(old) => Some(old),
+                    None => {
+                        conflicts.extend((1..=patch.hunks.len()).map(|i| (path.clone(), i)));
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            let result = patchkit::apply::apply_fuzzy(
+                old.as_deref().unwrap_or_default(),
+                &patch.hunks,
+                &options,
+            );
+            if result
+                .hunks
+                .iter()
+                .any(|h| h.applied() && (h.offset != 0 || h.fuzz != 0))
+            {
+                needs_refresh = true;
+            }
+            conflicts.extend(result.rejected().map(|h| (path.clone(), h.index + 1)));
+            let Some(patched) = result.patched else {
+                continue;
+            };
+            let new = modified.is_some().then_some(patched);
+            body.extend(unified_diff_labelled(
+                &orig_label,
+                &mod_label,
+                old.as_deref(),
+                new.as_deref(),
+            ));
+            if let Some(orig) = orig.filter(|o| Some(o) != modified.as_ref()) {
+                updated.insert(orig, None);
+            }
+            updated.insert(path, new);
+        }
+        let status = if !conflicts.is_empty() {
+            RefreshStatus::Failed(conflicts)
+        } else if needs_refresh
+            && !contents.contains("GIT binary patch")
+            && !contents.contains("\nBinary files ")
+        {
+            let mut new_contents = contents.as_bytes()[..patch_body_start(&contents)].to_vec();
+            new_contents.extend(body);
+            std::fs::write(&patch_path, new_contents)?;
+            RefreshStatus::Refreshed
+        } else {
+            RefreshStatus::Unchanged
+        };
+        if !matches!(status, RefreshStatus::Failed(_)) {
+            files.extend(updated);
+        }
+        ret.push((name, status));
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod refresh_tests {
+    use super::*;
+
+    const PATCH: &str = "\
+Description: Change line 2
+---
+--- a/a
++++ b/a
+@@ -1,3 +1,3 @@
+ line 1
+-line 2
++new line 2
+ line 3
+";
+
+    const FOLLOWUP: &str = "\
+--- a/a
++++ b/a
+@@ -2,2 +2,2 @@
+-new line 2
++newer line 2
+ line 3
+";
+
+    fn setup(upstream: &str) -> tempfile::TempDir {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/patches")).unwrap();
+        std::fs::write(td.path().join("a"), upstream).unwrap();
+        std::fs::write(td.path().join("debian/patches/change.patch"), PATCH).unwrap();
+        std::fs::write(td.path().join("debian/patches/followup.patch"), FOLLOWUP).unwrap();
+        std::fs::write(
+            td.path().join("debian/patches/series"),
+            "change.patch\nfollowup.patch\n",
+        )
+        .unwrap();
+        td
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let td = setup("line 1\nline 2\nline 3\n");
+        assert_eq!(
+            vec![
+                ("change.patch".to_string(), RefreshStatus::Unchanged),
+                ("followup.patch".to_string(), RefreshStatus::Unchanged)
+            ],
+            refresh(td.path(), Path::new("debian/patches"), 0).unwrap()
+        );
+        assert_eq!(
+            PATCH,
+            std::fs::read_to_string(td.path().join("debian/patches/change.patch")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_offset() {
+        let td = setup("line 0\nline 1\nline 2\nline 3\n");
+        assert_eq!(
+            vec![
+                ("change.patch".to_string(), RefreshStatus::Refreshed),
+                ("followup.patch".to_string(), RefreshStatus::Refreshed)
+            ],
+            refresh(td.path(), Path::new("debian/patches"), 0).unwrap()
+        );
+        assert_eq!(
+            "Description: Change line 2\n---\n--- a/a\n+++ b/a\n@@ -1,4 +1,4 @@\n line 0\n line 1\n-line 2\n+new line 2\n line 3\n",
+            std::fs::read_to_string(td.path().join("debian/patches/change.patch")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_failed() {
+        let td = setup("line 1\nline two\nline 3\n");
+        let results = refresh(td.path(), Path::new("debian/patches"), 0).unwrap();
+        assert_eq!(
+            RefreshStatus::Failed(vec![(PathBuf::from("a"), 1)]),
+            results[0].1
+        );
+        assert_eq!(
+            PATCH,
+            std::fs::read_to_string(td.path().join("debian/patches/change.patch")).unwrap()
+        );
+    }
+}
+
+/// Problem with the consistency between a quilt series file and the patch files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeriesIssue {
+    /// A patch file exists in the patches directory but is not listed in any series.
+    UnlistedPatch(String),
+    /// The series lists a patch whose file does not exist.
+    MissingPatch(String),
+}
+
+impl SeriesIssue {
+    /// The lintian tag corresponding to this issue, if any.
+    pub fn lintian_tag(&self) -> Option<&'static str> {
+        match self {
+            SeriesIssue::UnlistedPatch(_) => Some("patch-file-present-but-not-mentioned-in-series"),
+            SeriesIssue::MissingPatch(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SeriesIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeriesIssue::UnlistedPatch(name) => {
+                write!(f, "Patch {} is not listed in the series", name)
+            }
+            SeriesIssue::MissingPatch(name) => {
+                write!(f, "Series lists {}, which does not exist", name)
+            }
+        }
+    }
+}
+
+/// Whether a file in the patches directory is something other than a patch.
+fn is_patches_directory_metadata(name: &str) -> bool {
+    let basename = name.rsplit('/').next().unwrap_or(name);
+    basename == "series"
+        || basename == "00list"
+        || basename.ends_with(".series")
+        || basename.starts_with("series.")
+        || basename.starts_with("README")
+        || basename.starts_with('.')
+        || basename.ends_with('~')
+        || basename.ends_with(".orig")
+        || basename.ends_with(".rej")
+}
+
+/// Check that the quilt series and the patch files in a patches directory agree.
+///
+/// Patches listed in vendor-specific series files (e.g. `ubuntu.series`) are
+/// considered listed.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+pub fn check_series(base: &Path, patches_directory: &Path) -> std::io::Result<Vec<SeriesIssue>> {
+    fn walk(dir: &Path, prefix: &Path, out: &mut Vec<String>) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = prefix.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                if !entry.file_name().to_string_lossy().starts_with('.') {
+                    walk(&entry.path(), &name, out)?;
+                }
+            } else {
+                out.push(name.to_string_lossy().into_owned());
+            }
+        }
+        Ok(())
+    }
+    let directory = base.join(patches_directory);
+    if !directory.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    walk(&directory, Path::new(""), &mut files)?;
+    files.sort();
+
+    let series = read_series_file(base, patches_directory)?;
+    let mut listed = series.patches().map(|p| p.to_string()).collect::<Vec<_>>();
+    for vendor_series in files.iter().filter(|f| f.ends_with(".series")) {
+        let f = std::fs::File::open(directory.join(vendor_series))?;
+        listed.extend(
+            patchkit::quilt::Series::read(f)?
+                .patches()
+                .map(|p| p.to_string()),
+        );
+    }
+
+    let mut issues = vec![];
+    for name in series.patches() {
+        if !directory.join(name).is_file() {
+            issues.push(SeriesIssue::MissingPatch(name.to_string()));
+        }
+    }
+    for name in files {
+        if !is_patches_directory_metadata(&name) && !listed.contains(&name) {
+            issues.push(SeriesIssue::UnlistedPatch(name));
+        }
+    }
+    Ok(issues)
+}
+
+/// Fix inconsistencies between the quilt series and the patch files.
+///
+/// Series entries for missing patches are removed. Unlisted patch files are
+/// either appended to the series or deleted.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+/// * `issues` - Issues to fix, as returned by [`check_series`]
+/// * `add_unlisted` - Whether to add unlisted patches to the series rather than delete them
+///
+/// # Returns
+/// A description of each change that was made
+pub fn fix_series(
+    base: &Path,
+    patches_directory: &Path,
+    issues: &[SeriesIssue],
+    add_unlisted: bool,
+) -> std::io::Result<Vec<String>> {
+    let directory = base.join(patches_directory);
+    let mut series = read_series_file(base, patches_directory)?;
+    let mut series_changed = false;
+    let mut ret = vec![];
+    for issue in issues {
+        match issue {
+            SeriesIssue::MissingPatch(name) => {
+                series.remove(name);
+                series_changed = true;
+                ret.push(format!("Remove missing patch {} from series.", name));
+            }
+            SeriesIssue::UnlistedPatch(name) if add_unlisted => {
+                series.append(name, None);
+                series_changed = true;
+                ret.push(format!("Add unlisted patch {} to series.", name));
+            }
+            SeriesIssue::UnlistedPatch(name) => {
+                std::fs::remove_file(directory.join(name))?;
+                ret.push(format!("Remove unused patch {}.", name));
+            }
+        }
+    }
+    if series_changed {
+        let mut series_bytes = vec![];
+        series.write(&mut series_bytes)?;
+        std::fs::write(directory.join("series"), series_bytes)?;
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod check_series_tests {
+    use super::*;
+
+    fn setup() -> tempfile::TempDir {
+        let td = tempfile::tempdir().unwrap();
+        let patches = td.path().join("debian/patches");
+        std::fs::create_dir_all(patches.join("upstream")).unwrap();
+        std::fs::write(patches.join("series"), "listed.patch\nmissing.patch\n").unwrap();
+        std::fs::write(patches.join("ubuntu.series"), "vendor.patch\n").unwrap();
+        std::fs::write(patches.join("listed.patch"), "").unwrap();
+        std::fs::write(patches.join("vendor.patch"), "").unwrap();
+        std::fs::write(patches.join("upstream/unlisted.diff"), "").unwrap();
+        std::fs::write(patches.join("README"), "").unwrap();
+        td
+    }
+
+    #[test]
+    fn test_check_series() {
+        let td = setup();
+        let issues = check_series(td.path(), Path::new("debian/patches")).unwrap();
+        assert_eq!(
+            vec![
+                SeriesIssue::MissingPatch("missing.patch".to_string()),
+                SeriesIssue::UnlistedPatch("upstream/unlisted.diff".to_string()),
+            ],
+            issues
+        );
+        assert_eq!(
+            Some("patch-file-present-but-not-mentioned-in-series"),
+            issues[1].lintian_tag()
+        );
+    }
+
+    #[test]
+    fn test_fix_series_add() {
+        let td = setup();
+        let patches = Path::new("debian/patches");
+        let issues = check_series(td.path(), patches).unwrap();
+        assert_eq!(
+            vec![
+                "Remove missing patch missing.patch from series.",
+                "Add unlisted patch upstream/unlisted.diff to series.",
+            ],
+            fix_series(td.path(), patches, &issues, true).unwrap()
+        );
+        assert_eq!(
+            "listed.patch\nupstream/unlisted.diff\n",
+            std::fs::read_to_string(td.path().join("debian/patches/series")).unwrap()
+        );
+        assert!(check_series(td.path(), patches).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fix_series_remove() {
+        let td = setup();
+        let patches = Path::new("debian/patches");
+        let issues = check_series(td.path(), patches).unwrap();
+        fix_series(td.path(), patches, &issues, false).unwrap();
+        assert!(!td
+            .path()
+            .join("debian/patches/upstream/unlisted.diff")
+            .exists());
+        assert!(check_series(td.path(), patches).unwrap().is_empty());
+    }
+}
+
+/// Find the patches in a quilt series that still need to be forwarded upstream.
+///
+/// A patch needs forwarding if its Forwarded field is missing or `no`, unless
+/// it originates upstream or has already been applied upstream.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+pub fn patches_needing_forwarding(
+    base: &Path,
+    patches_directory: &Path,
+) -> Result<Vec<String>, QuiltError> {
+    let series = read_series_file(base, patches_directory)?;
+    let mut ret = vec![];
+    for name in series.patches() {
+        let contents = std::fs::read(base.join(patches_directory).join(name))?;
+        let (header, _) = split_patch_header(&String::from_utf8_lossy(&contents)).map_err(|e| {
+            QuiltError::InvalidPatch {
+                patch: name.to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+        if matches!(
+            header.origin(),
+            Some((
+                Some(dep3::OriginCategory::Upstream | dep3::OriginCategory::Backport),
+                _
+            ))
+        ) || header.applied_upstream().is_some()
+        {
+            continue;
+        }
+        if matches!(header.forwarded(), None | Some(dep3::Forwarded::No)) {
+            ret.push(name.to_string());
+        }
+    }
+    Ok(ret)
+}
+
+/// Set the Forwarded field in the DEP-3 header of a patch.
+///
+/// A header is added if the patch does not have one yet.
+///
+/// # Arguments
+/// * `contents` - Contents of the patch file
+/// * `forwarded` - New value of the Forwarded field
+///
+/// # Returns
+/// The updated patch contents
+pub fn set_patch_forwarded(
+    contents: &str,
+    forwarded: dep3::Forwarded,
+) -> Result<String, deb822_lossless::ParseError> {
+    let (mut header, body) = split_patch_header(contents)?;
+    // PatchHeader::set_forwarded appends rather than replaces an existing field.
+    header
+        .as_deb822_mut()
+        .set("Forwarded", forwarded.to_string().as_str());
+    Ok(format!("{}{}", header, body))
+}
+
+/// Issue to file upstream for a patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardRequest {
+    /// Title of the issue
+    pub title: String,
+    /// Body of the issue, in Markdown
+    pub body: String,
+}
+
+impl ForwardRequest {
+    /// Build an upstream issue for a patch from its DEP-3 header and diff.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the patch
+    /// * `contents` - Contents of the patch file
+    pub fn for_patch(name: &str, contents: &str) -> Result<Self, deb822_lossless::ParseError> {
+        let (header, body) = split_patch_header(contents)?;
+        let title = header.description().unwrap_or_else(|| {
+            name.trim_end_matches(".patch")
+                .trim_end_matches(".diff")
+                .replace(['-', '_'], " ")
+        });
+        let mut text = String::new();
+        if let Some(long_description) = header.long_description() {
+            text.push_str(long_description.trim());
+            text.push_str("\n\n");
+        }
+        text.push_str("This patch is carried in the Debian package.\n\n");
+        text.push_str("```diff\n");
+        text.push_str(body.strip_prefix("---\n").unwrap_or(&body));
+        if !body.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str("```\n");
+        Ok(ForwardRequest { title, body: text })
+    }
+}
+
+/// Error forwarding a patch upstream.
+#[derive(Debug)]
+pub enum ForwardError {
+    /// The upstream repository is not hosted on a supported forge.
+    UnsupportedForge(String),
+    /// No upstream repository is known.
+    NoRepository,
+    /// Error talking to the forge.
+    Http(reqwest::Error),
+    /// The forge rejected the request.
+    Api {
+        /// HTTP status code
+        status: u16,
+        /// Message returned by the forge
+        message: String,
+    },
+    /// Error reading or updating the patch.
+    Quilt(QuiltError),
+}
+
+impl std::fmt::Display for ForwardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForwardError::UnsupportedForge(url) => {
+                write!(f, "Unsupported upstream repository: {}", url)
+            }
+            ForwardError::NoRepository => write!(f, "No upstream repository known"),
+            ForwardError::Http(e) => write!(f, "HTTP error: {}", e),
+            ForwardError::Api { status, message } => {
+                write!(f, "Forge returned {}: {}", status, message)
+            }
+            ForwardError::Quilt(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ForwardError {}
+
+impl From<reqwest::Error> for ForwardError {
+    fn from(e: reqwest::Error) -> Self {
+        ForwardError::Http(e)
+    }
+}
+
+impl From<QuiltError> for ForwardError {
+    fn from(e: QuiltError) -> Self {
+        ForwardError::Quilt(e)
+    }
+}
+
+impl From<std::io::Error> for ForwardError {
+    fn from(e: std::io::Error) -> Self {
+        ForwardError::Quilt(QuiltError::Io(e))
+    }
+}
+
+/// Read the upstream repository URL from the DEP-12 metadata in `debian/upstream/metadata`.
+fn upstream_repository(base: &Path) -> std::io::Result<Option<String>> {
+    let text = match std::fs::read_to_string(base.join("debian/upstream/metadata")) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(text.lines().find_map(|line| {
+        line.strip_prefix("Repository:")
+            .map(|v| v.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+            .filter(|v| !v.is_empty())
+    }))
+}
+
+/// Open an issue on an upstream forge.
+///
+/// # Arguments
+/// * `source` - Upstream repository; only GitHub and GitLab are supported
+/// * `token` - API token for the forge
+/// * `request` - Issue to open
+///
+/// # Returns
+/// URL of the newly created issue
+#[tracing::instrument(skip(token, request), fields(title = %request.title))]
+pub fn open_upstream_issue(
+    source: &crate::watch::UpstreamSource,
+    token: &str,
+    request: &ForwardRequest,
+) -> Result<url::Url, ForwardError> {
+    use crate::watch::UpstreamSource;
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+    let (request, url_field) = match source {
+        UpstreamSource::GitHub { owner, repo } => (
+            client
+                .post(format!(
+                    "https://api.github.com/repos/{}/{}/issues",
+                    owner, repo
+                ))
+                .bearer_auth(token)
+                .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+                .json(&serde_json::json!({"title": request.title, "body": request.body})),
+            "html_url",
+        ),
+        UpstreamSource::GitLab { host, path } => (
+            client
+                .post(format!(
+                    "https://{}/api/v4/projects/{}/issues",
+                    host,
+                    path.replace('/', "%2F")
+                ))
+                .header("PRIVATE-TOKEN", token)
+                .json(&serde_json::json!({"title": request.title, "description": request.body})),
+            "web_url",
+        ),
+        other => return Err(ForwardError::UnsupportedForge(format!("{:?}", other))),
+    };
+    let response = request.send()?;
+    let status = response.status();
+    let data = response.json::<serde_json::Value>()?;
+    if !status.is_success() {
+        return Err(ForwardError::Api {
+            status: status.as_u16(),
+            message: data["message"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+    data[url_field]
+        .as_str()
+        .and_then(|u| u.parse().ok())
+        .ok_or_else(|| ForwardError::Api {
+            status: status.as_u16(),
+            message: format!("response did not include {}", url_field),
+        })
+}
+
+/// Forward a patch upstream and record the result in its DEP-3 header.
+///
+/// The upstream repository is taken from the `Repository` field in
+/// `debian/upstream/metadata`. An issue containing the patch is opened on the
+/// forge, and the Forwarded field of the patch is set to its URL.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+/// * `name` - Name of the patch, as listed in the series file
+/// * `token` - API token for the forge
+///
+/// # Returns
+/// URL of the issue that was opened
+pub fn forward_patch(
+    base: &Path,
+    patches_directory: &Path,
+    name: &str,
+    token: &str,
+) -> Result<url::Url, ForwardError> {
+    let repository = upstream_repository(base)?.ok_or(ForwardError::NoRepository)?;
+    let source = crate::watch::UpstreamSource::from_repository_url(&repository)
+        .ok_or_else(|| ForwardError::UnsupportedForge(repository.clone()))?;
+    let path = base.join(patches_directory).join(name);
+    let contents = String::from_utf8_lossy(&std::fs::read(&path)?).into_owned();
+    let invalid = |e: deb822_lossless::ParseError| {
+        ForwardError::Quilt(QuiltError::InvalidPatch {
+            patch: name.to_string(),
+            reason: e.to_string(),
+        })
+    };
+    let request = ForwardRequest::for_patch(name, &contents).map_err(invalid)?;
+    let url = open_upstream_issue(&source, token, &request)?;
+    let contents = set_patch_forwarded(&contents, dep3::Forwarded::Yes(url.to_string().into()))
+        .map_err(invalid)?;
+    std::fs::write(&path, contents)?;
+    Ok(url)
+}
+
+#[cfg(test)]
+mod forward_tests {
+    use super::*;
+
+    #[test]
+    fn test_patches_needing_forwarding() {
+        let td = tempfile::tempdir().unwrap();
+        let patches = td.path().join("debian/patches");
+        std::fs::create_dir_all(&patches).unwrap();
+        std::fs::write(
+            patches.join("series"),
+            "missing.patch\nno.patch\nyes.patch\nupstream.patch\nnot-needed.patch\n",
+        )
+        .unwrap();
+        std::fs::write(patches.join("missing.patch"), "--- a/a\n+++ b/a\n").unwrap();
+        std::fs::write(patches.join("no.patch"), "Forwarded: no\n---\n").unwrap();
+        std::fs::write(
+            patches.join("yes.patch"),
+            "Forwarded: https://example.com/1\n---\n",
+        )
+        .unwrap();
+        std::fs::write(
+            patches.join("upstream.patch"),
+            "Origin: upstream, https://example.com/commit/1\n---\n",
+        )
+        .unwrap();
+        std::fs::write(
+            patches.join("not-needed.patch"),
+            "Forwarded: not-needed\n---\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec!["missing.patch", "no.patch"],
+            patches_needing_forwarding(td.path(), Path::new("debian/patches")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_set_patch_forwarded() {
+        assert_eq!(
+            "Description: Fix\nForwarded: https://example.com/1\n---\n--- a/a\n",
+            set_patch_forwarded(
+                "Description: Fix\nForwarded: no\n---\n--- a/a\n",
+                dep3::Forwarded::Yes("https://example.com/1".into())
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_forward_request() {
+        let request = ForwardRequest::for_patch(
+            "fix-build.patch",
+            "Description: Fix the build\n More detail.\n---\n--- a/a\n+++ b/a\n",
+        )
+        .unwrap();
+        assert_eq!("Fix the build", request.title);
+        assert_eq!(
+            "More detail.\n\nThis patch is carried in the Debian package.\n\n```diff\n--- a/a\n+++ b/a\n```\n",
+            request.body
+        );
+        let request = ForwardRequest::for_patch("fix-build.patch", "--- a/a\n+++ b/a\n").unwrap();
+        assert_eq!("fix build", request.title);
+    }
+
+    #[test]
+    fn test_upstream_repository() {
+        let td = tempfile::tempdir().unwrap();
+        assert_eq!(None, upstream_repository(td.path()).unwrap());
+        std::fs::create_dir_all(td.path().join("debian/upstream")).unwrap();
+        std::fs::write(
+            td.path().join("debian/upstream/metadata"),
+            "---\nName: foo\nRepository: https://github.com/example/foo.git\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Some("https://github.com/example/foo.git".to_string()),
+            upstream_repository(td.path()).unwrap()
+        );
+    }
+}
+
+/// Naming scheme for patch files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchNamingScheme {
+    /// `NNNN-description.patch`, numbered in series order, as used by gbp pq.
+    Numbered,
+    /// `description.patch`, without a number.
+    Plain,
+}
+
+/// Strip the number prefix and extension from a patch name.
+fn patch_name_stem(basename: &str) -> &str {
+    let stem = [".patch", ".diff", ".dpatch"]
+        .iter()
+        .find_map(|ext| basename.strip_suffix(ext))
+        .unwrap_or(basename);
+    let digits = stem.len() - stem.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    match stem[digits..].strip_prefix(['-', '_']) {
+        Some(rest) if digits > 0 && !rest.is_empty() => rest,
+        _ => stem,
+    }
+}
+
+/// Compute normalized names for the patches in a series.
+///
+/// Patches in subdirectories keep their directory.
+///
+/// # Arguments
+/// * `names` - Patch names, in series order
+/// * `scheme` - Naming scheme to apply
+///
+/// # Returns
+/// (old name, new name) pairs for the patches that need to be renamed
+pub fn normalized_patch_names(names: &[&str], scheme: PatchNamingScheme) -> Vec<(String, String)> {
+    let mut taken = std::collections::HashSet::new();
+    let mut ret = vec![];
+    for (i, name) in names.iter().enumerate() {
+        let (dir, basename) = match name.rsplit_once('/') {
+            Some((dir, basename)) => (Some(dir), basename),
+            None => (None, *name),
+        };
+        let slug = patch_name_from_description(patch_name_stem(basename));
+        let mut candidate = slug.clone();
+        let mut counter = 1;
+        let new_name = loop {
+            let basename = match scheme {
+                PatchNamingScheme::Numbered => format!("{:04}-{}.patch", i + 1, candidate),
+                PatchNamingScheme::Plain => format!("{}.patch", candidate),
+            };
+            let new_name = match dir {
+                Some(dir) => format!("{}/{}", dir, basename),
+                None => basename,
+            };
+            if taken.insert(new_name.clone()) {
+                break new_name;
+            }
+            counter += 1;
+            candidate = format!("{}-{}", slug, counter);
+        };
+        if new_name != *name {
+            ret.push((name.to_string(), new_name));
+        }
+    }
+    ret
+}
+
+/// Replace whole-word occurrences of patch names in a text.
+fn replace_patch_references(text: &str, renames: &[(String, String)]) -> String {
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/');
+    let mut ret = String::new();
+    let mut rest = text;
+    'outer: while !rest.is_empty() {
+        let preceded_by_name = ret.chars().last().is_some_and(is_name_char);
+        if !preceded_by_name {
+            for (old, new) in renames {
+                if let Some(after) = rest.strip_prefix(old.as_str()) {
+                    // A trailing full stop ends a sentence rather than the name.
+                    let next = after.chars().next();
+                    let boundary = match next {
+                        None => true,
+                        Some('.') => !after[1..].starts_with(|c: char| c.is_alphanumeric()),
+                        Some(c) => !is_name_char(c),
+                    };
+                    if boundary {
+                        ret.push_str(new);
+                        rest = after;
+                        continue 'outer;
+                    }
+                }
+            }
+        }
+        let c = rest.chars().next().unwrap();
+        ret.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    ret
+}
+
+/// Rename the patches in a quilt series to a consistent naming scheme.
+///
+/// The series file keeps its order, options and comments. References to the
+/// old names in `debian/README.source` are updated.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `patches_directory` - Patches directory, relative to `base`
+/// * `scheme` - Naming scheme to apply
+///
+/// # Returns
+/// (old name, new name) pairs for the patches that were renamed
+pub fn normalize_patch_names(
+    base: &Path,
+    patches_directory: &Path,
+    scheme: PatchNamingScheme,
+) -> Result<Vec<(String, String)>, QuiltError> {
+    let directory = base.join(patches_directory);
+    let series = read_series_file(base, patches_directory)?;
+    let names = series.patches().collect::<Vec<_>>();
+    let renames = normalized_patch_names(&names, scheme);
+    if renames.is_empty() {
+        return Ok(renames);
+    }
+
+    // Rename in two steps, so that new names may coincide with old ones.
+    for (i, (old, _)) in renames.iter().enumerate() {
+        std::fs::rename(
+            directory.join(old),
+            directory.join(format!("{}.renaming-{}", old, i)),
+        )?;
+    }
+    for (i, (old, new)) in renames.iter().enumerate() {
+        std::fs::rename(
+            directory.join(format!("{}.renaming-{}", old, i)),
+            directory.join(new),
+        )?;
+    }
+
+    let series_path = directory.join("series");
+    let text = std::fs::read_to_string(&series_path)?;
+    let mut new_text = String::new();
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let name = trimmed.split_whitespace().next().unwrap_or_default();
+        match renames.iter().find(|(old, _)| old == name) {
+            Some((old, new)) if !trimmed.starts_with('#') => {
+                let indent = &line[..line.len() - trimmed.len()];
+                new_text.push_str(indent);
+                new_text.push_str(new);
+                new_text.push_str(&trimmed[old.len()..]);
+            }
+            _ => new_text.push_str(line),
+        }
+    }
+    std::fs::write(&series_path, new_text)?;
+
+    let readme_path = base.join("debian/README.source");
+    match std::fs::read_to_string(&readme_path) {
+        Ok(text) => {
+            let new_text = replace_patch_references(&text, &renames);
+            if new_text != text {
+                std::fs::write(&readme_path, new_text)?;
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(renames)
+}
+
+#[cfg(test)]
+mod normalize_patch_names_tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_patch_names() {
+        assert_eq!(
+            vec![
+                (
+                    "01_Fix_Build.diff".to_string(),
+                    "0001-fix-build.patch".to_string()
+                ),
+                (
+                    "debian/Use system zlib.patch".to_string(),
+                    "debian/0002-use-system-zlib.patch".to_string()
+                ),
+            ],
+            normalized_patch_names(
+                &[
+                    "01_Fix_Build.diff",
+                    "debian/Use system zlib.patch",
+                    "0003-typo.patch"
+                ],
+                PatchNamingScheme::Numbered
+            )
+        );
+        assert_eq!(
+            vec![
+                ("0001-fix.patch".to_string(), "fix.patch".to_string()),
+                ("fix.diff".to_string(), "fix-2.patch".to_string()),
+            ],
+            normalized_patch_names(&["0001-fix.patch", "fix.diff"], PatchNamingScheme::Plain)
+        );
+    }
+
+    #[test]
+    fn test_replace_patch_references() {
+        let renames = vec![("fix.diff".to_string(), "0001-fix.patch".to_string())];
+        assert_eq!(
+            "See 0001-fix.patch. Not other-fix.diff or fix.diff.orig.\n",
+            replace_patch_references(
+                "See fix.diff. Not other-fix.diff or fix.diff.orig.\n",
+                &renames
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_patch_names() {
+        let td = tempfile::tempdir().unwrap();
+        let patches = td.path().join("debian/patches");
+        std::fs::create_dir_all(&patches).unwrap();
+        std::fs::write(
+            patches.join("series"),
+            "# Build fixes\n02_second.diff -p1\nfirst.patch\n",
+        )
+        .unwrap();
+        std::fs::write(patches.join("02_second.diff"), "second").unwrap();
+        std::fs::write(patches.join("first.patch"), "first").unwrap();
+        std::fs::write(
+            td.path().join("debian/README.source"),
+            "The patch 02_second.diff is needed.\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                (
+                    "02_second.diff".to_string(),
+                    "0001-second.patch".to_string()
+                ),
+                ("first.patch".to_string(), "0002-first.patch".to_string()),
+            ],
+            normalize_patch_names(
+                td.path(),
+                Path::new("debian/patches"),
+                PatchNamingScheme::Numbered
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            "# Build fixes\n0001-second.patch -p1\n0002-first.patch\n",
+            std::fs::read_to_string(patches.join("series")).unwrap()
+        );
+        assert_eq!(
+            "second",
+            std::fs::read_to_string(patches.join("0001-second.patch")).unwrap()
+        );
+        assert!(!patches.join("first.patch").exists());
+        assert_eq!(
+            "The patch 0001-second.patch is needed.\n",
+            std::fs::read_to_string(td.path().join("debian/README.source")).unwrap()
+        );
+    }
+}
@@ -0,0 +1,483 @@
+//! Python bindings.
+//!
+//! The editors and helpers of this crate are exposed as the
+//! `debian_analyzer` Python module, so that Python code such as
+//! lintian-brush can move onto them one piece at a time. Build the module
+//! with `maturin build --features python`.
+//!
+//! Errors caused by the package are raised as `PackageError`, errors caused
+//! by the environment as `BrokenEnvironment`; see [`crate::error::ErrorCategory`].
+use crate::abstract_control::{AbstractControlEditor, AbstractSource};
+use crate::editor::{Editor, FsEditor};
+use crate::error::ErrorCategory;
+use crate::release_info::Vendor;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+create_exception!(
+    debian_analyzer,
+    PackageError,
+    PyException,
+    "The package is malformed or unsupported."
+);
+create_exception!(
+    debian_analyzer,
+    BrokenEnvironment,
+    PyException,
+    "The environment is broken, e.g. a tool or data file is missing."
+);
+
+impl From<crate::error::Error> for PyErr {
+    fn from(e: crate::error::Error) -> Self {
+        match e.category() {
+            ErrorCategory::Package => PackageError::new_err(e.to_string()),
+            ErrorCategory::Environment => BrokenEnvironment::new_err(e.to_string()),
+        }
+    }
+}
+
+fn editor_error(e: crate::editor::EditorError) -> PyErr {
+    crate::error::Error::from(e).into()
+}
+
+fn parse_version(version: &str) -> PyResult<debversion::Version> {
+    version
+        .parse()
+        .map_err(|e| PyValueError::new_err(format!("Invalid version {}: {}", version, e)))
+}
+
+fn parse_vendor(vendor: &str) -> PyResult<Vendor> {
+    match vendor.to_lowercase().as_str() {
+        "debian" => Ok(Vendor::Debian),
+        "ubuntu" => Ok(Vendor::Ubuntu),
+        "kali" => Ok(Vendor::Kali),
+        _ => Err(PyValueError::new_err(format!("Unknown vendor {}", vendor))),
+    }
+}
+
+/// Editor for the control file of a package, backed by debian/control or
+/// debian/debcargo.toml.
+#[pyclass(unsendable, module = "debian_analyzer")]
+struct ControlEditor {
+    editor: Box<dyn AbstractControlEditor>,
+}
+
+impl ControlEditor {
+    fn source(&mut self) -> PyResult<Box<dyn AbstractSource<'_> + '_>> {
+        self.editor
+            .source()
+            .ok_or_else(|| PackageError::new_err("No source package in control file"))
+    }
+}
+
+#[pymethods]
+impl ControlEditor {
+    /// Open the control file of the package in a directory.
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        Ok(Self {
+            editor: crate::abstract_control::open_control(&path)?,
+        })
+    }
+
+    /// Name of the source package.
+    fn source_name(&mut self) -> PyResult<Option<String>> {
+        Ok(self.source()?.name())
+    }
+
+    /// Names of the binary packages.
+    fn binary_names(&mut self) -> Vec<String> {
+        self.editor
+            .binaries()
+            .iter()
+            .filter_map(|b| b.name())
+            .collect()
+    }
+
+    /// Get the Standards-Version field.
+    fn get_standards_version(&mut self) -> PyResult<Option<String>> {
+        Ok(self.source()?.standards_version())
+    }
+
+    /// Set the Standards-Version field.
+    fn set_standards_version(&mut self, version: &str) -> PyResult<()> {
+        self.source()?.set_standards_version(version);
+        Ok(())
+    }
+
+    /// Get the Homepage field.
+    fn get_homepage(&mut self) -> PyResult<Option<String>> {
+        Ok(self.source()?.homepage())
+    }
+
+    /// Set the Homepage field.
+    fn set_homepage(&mut self, homepage: &str) -> PyResult<()> {
+        self.source()?.set_homepage(homepage);
+        Ok(())
+    }
+
+    /// Set the Maintainer field.
+    fn set_maintainer(&mut self, maintainer: &str) -> PyResult<()> {
+        self.source()?.set_maintainer(maintainer);
+        Ok(())
+    }
+
+    /// Set the Uploaders field.
+    fn set_uploaders(&mut self, uploaders: Vec<String>) -> PyResult<()> {
+        let uploaders = uploaders.iter().map(|u| u.as_str()).collect::<Vec<_>>();
+        self.source()?.set_uploaders(&uploaders);
+        Ok(())
+    }
+
+    /// Get a Vcs-* field, e.g. "Git" or "Browser".
+    fn get_vcs_url(&mut self, vcs_type: &str) -> PyResult<Option<String>> {
+        Ok(self.source()?.get_vcs_url(vcs_type))
+    }
+
+    /// Set a Vcs-* field, e.g. "Git" or "Browser".
+    fn set_vcs_url(&mut self, vcs_type: &str, url: &str) -> PyResult<()> {
+        self.source()?.set_vcs_url(vcs_type, url);
+        Ok(())
+    }
+
+    /// Get the Rules-Requires-Root field.
+    fn get_rules_requires_root(&mut self) -> PyResult<Option<String>> {
+        Ok(self.source()?.rules_requires_root())
+    }
+
+    /// Set the Rules-Requires-Root field.
+    fn set_rules_requires_root(&mut self, value: &str) -> PyResult<()> {
+        self.source()?.set_rules_requires_root(value);
+        Ok(())
+    }
+
+    /// Get the Testsuite field.
+    fn get_testsuite(&mut self) -> PyResult<Option<String>> {
+        Ok(self.source()?.testsuite())
+    }
+
+    /// Set the Testsuite field, or remove it if `None`.
+    #[pyo3(signature = (testsuite))]
+    fn set_testsuite(&mut self, testsuite: Option<&str>) -> PyResult<()> {
+        self.source()?.set_testsuite(testsuite);
+        Ok(())
+    }
+
+    /// Make sure a build dependency is present, e.g. "debhelper-compat (= 13)".
+    fn ensure_build_dep(&mut self, dep: &str) -> PyResult<()> {
+        let entry = debian_control::lossless::relations::Entry::from_str(dep)
+            .map_err(|e| PyValueError::new_err(format!("Invalid relation {}: {}", dep, e)))?;
+        self.source()?.ensure_build_dep(entry);
+        Ok(())
+    }
+
+    /// Get the Multi-Arch field of a binary package.
+    fn get_multi_arch(&mut self, binary: &str) -> PyResult<Option<String>> {
+        self.editor
+            .binaries()
+            .iter()
+            .find(|b| b.name().as_deref() == Some(binary))
+            .map(|b| b.multi_arch())
+            .ok_or_else(|| PyKeyError::new_err(binary.to_string()))
+    }
+
+    /// Set the Multi-Arch field of a binary package, or remove it if `None`.
+    #[pyo3(signature = (binary, multi_arch))]
+    fn set_multi_arch(&mut self, binary: &str, multi_arch: Option<&str>) -> PyResult<()> {
+        let mut binaries = self.editor.binaries();
+        let binary = binaries
+            .iter_mut()
+            .find(|b| b.name().as_deref() == Some(binary))
+            .ok_or_else(|| PyKeyError::new_err(binary.to_string()))?;
+        binary.set_multi_arch(multi_arch);
+        Ok(())
+    }
+
+    /// Wrap and sort the control file.
+    fn wrap_and_sort(&mut self) {
+        self.editor.wrap_and_sort();
+    }
+
+    /// Write the changes to disk.
+    ///
+    /// Returns whether any files were changed.
+    fn commit(&self) -> PyResult<bool> {
+        Ok(self.editor.commit()?)
+    }
+}
+
+/// Editor for a debian/rules file.
+#[pyclass(unsendable, module = "debian_analyzer")]
+struct RulesEditor {
+    editor: FsEditor<makefile_lossless::Makefile>,
+}
+
+#[pymethods]
+impl RulesEditor {
+    /// Open a debian/rules file.
+    #[new]
+    #[pyo3(signature = (path, allow_reformatting=false))]
+    fn new(path: PathBuf, allow_reformatting: bool) -> PyResult<Self> {
+        Ok(Self {
+            editor: FsEditor::new(&path, false, allow_reformatting, false).map_err(editor_error)?,
+        })
+    }
+
+    /// Targets of all rules.
+    fn targets(&self) -> Vec<String> {
+        self.editor
+            .rules()
+            .flat_map(|r| r.targets().collect::<Vec<_>>())
+            .collect()
+    }
+
+    /// Remove override rules that only run the command they override.
+    ///
+    /// Returns the number of rules that were removed.
+    fn discard_pointless_overrides(&mut self) -> usize {
+        crate::rules::discard_pointless_overrides(&mut self.editor)
+    }
+
+    /// Write the changes to disk.
+    ///
+    /// Returns the paths of the files that were changed.
+    fn commit(&self) -> PyResult<Vec<PathBuf>> {
+        self.editor.commit().map_err(editor_error)
+    }
+
+    fn __str__(&self) -> String {
+        self.editor.to_string()
+    }
+}
+
+/// The result of bumping the debhelper compat level.
+#[pyclass(get_all, frozen, module = "debian_analyzer")]
+struct CompatBump {
+    from_level: u8,
+    to_level: u8,
+    changes: Vec<String>,
+    certainty: String,
+    follow_ups: Vec<String>,
+}
+
+/// Get the debhelper compat level of the package in a directory.
+#[pyfunction]
+fn get_debhelper_compat_level(path: PathBuf) -> PyResult<Option<u8>> {
+    Ok(crate::debhelper::get_debhelper_compat_level(&path)?)
+}
+
+/// Read a debian/compat file.
+#[pyfunction]
+fn read_debhelper_compat_file(path: PathBuf) -> PyResult<Option<u8>> {
+    Ok(crate::debhelper::read_debhelper_compat_file(&path)?)
+}
+
+/// Find the lowest non-deprecated debhelper compat level.
+#[pyfunction]
+fn lowest_non_deprecated_compat_level() -> PyResult<u8> {
+    Ok(crate::debhelper::lowest_non_deprecated_compat_level()?)
+}
+
+/// Find the highest stable debhelper compat level.
+#[pyfunction]
+fn highest_stable_compat_level() -> PyResult<u8> {
+    Ok(crate::debhelper::highest_stable_compat_level()?)
+}
+
+/// Bump the debhelper compat level of the package in a directory.
+#[pyfunction]
+fn bump_debhelper_compat(path: PathBuf, to: u8) -> PyResult<CompatBump> {
+    let bump = crate::debhelper::bump_debhelper_compat(&path, to)
+        .map_err(|e| PackageError::new_err(e.to_string()))?;
+    Ok(CompatBump {
+        from_level: bump.from,
+        to_level: bump.to,
+        changes: bump.changes().into_iter().map(String::from).collect(),
+        certainty: bump.certainty().to_string(),
+        follow_ups: bump.follow_ups,
+    })
+}
+
+/// Add a `--with` argument to a dh invocation.
+#[pyfunction]
+fn dh_invoke_add_with(line: &str, with_argument: &str) -> String {
+    crate::rules::dh_invoke_add_with(line, with_argument)
+}
+
+/// Get the `--with` arguments of a dh invocation.
+#[pyfunction]
+fn dh_invoke_get_with(line: &str) -> Vec<String> {
+    crate::rules::dh_invoke_get_with(line)
+}
+
+/// Drop a `--with` argument from a dh invocation.
+#[pyfunction]
+fn dh_invoke_drop_with(line: &str, with_argument: &str) -> String {
+    crate::rules::dh_invoke_drop_with(line, with_argument)
+}
+
+/// Drop an argument from a dh invocation.
+#[pyfunction]
+fn dh_invoke_drop_argument(line: &str, argument: &str) -> String {
+    crate::rules::dh_invoke_drop_argument(line, argument)
+}
+
+/// Replace an argument of a dh invocation.
+#[pyfunction]
+fn dh_invoke_replace_argument(line: &str, old: &str, new: &str) -> String {
+    crate::rules::dh_invoke_replace_argument(line, old, new)
+}
+
+/// Check whether a debian/rules file uses CDBS.
+#[pyfunction]
+fn check_cdbs(path: PathBuf) -> bool {
+    crate::rules::check_cdbs(&path)
+}
+
+/// List all Debian releases.
+#[pyfunction]
+fn debian_releases() -> PyResult<Vec<String>> {
+    Ok(crate::release_info::debian_releases()?)
+}
+
+/// List all Ubuntu releases.
+#[pyfunction]
+fn ubuntu_releases() -> PyResult<Vec<String>> {
+    Ok(crate::release_info::ubuntu_releases()?)
+}
+
+/// Infer the distribution ("debian", "ubuntu" or "kali") from a suite.
+#[pyfunction]
+fn suite_to_distribution(suite: &str) -> PyResult<Option<String>> {
+    Ok(crate::release_info::suite_to_distribution(suite)?
+        .map(|vendor| format!("{:?}", vendor).to_lowercase()))
+}
+
+/// Find aliases for a release, e.g. "stable".
+#[pyfunction]
+fn release_aliases(name: &str) -> PyResult<Vec<String>> {
+    Ok(crate::release_info::release_aliases(name, None)?)
+}
+
+/// Resolve a release codename or alias to a series name.
+#[pyfunction]
+fn resolve_release_codename(name: &str) -> PyResult<Option<String>> {
+    Ok(crate::release_info::resolve_release_codename(name, None)?)
+}
+
+/// Convert an upstream version to a Debian upstream version.
+#[pyfunction]
+fn debianize_upstream_version(version: &str) -> String {
+    crate::versions::debianize_upstream_version(version)
+}
+
+/// Check whether an upstream version matches a release version.
+#[pyfunction]
+fn matches_release(upstream_version: &str, release_version: &str) -> bool {
+    crate::versions::matches_release(upstream_version, release_version)
+}
+
+/// Add a snapshot suffix, e.g. "~jan+lint", to a version.
+#[pyfunction]
+fn add_snapshot_suffix(version: &str, suffix: &str) -> PyResult<String> {
+    Ok(crate::versions::add_snapshot_suffix(&parse_version(version)?, suffix).to_string())
+}
+
+/// Strip a snapshot suffix from a version.
+#[pyfunction]
+fn strip_snapshot_suffix(version: &str, suffix: &str) -> PyResult<String> {
+    Ok(crate::versions::strip_snapshot_suffix(&parse_version(version)?, suffix).to_string())
+}
+
+/// The version for a non-maintainer upload of a version.
+#[pyfunction]
+fn nmu_version(version: &str) -> PyResult<String> {
+    Ok(crate::versions::nmu_version(&parse_version(version)?).to_string())
+}
+
+/// The version for a backport of a version to a release, e.g. "12".
+#[pyfunction]
+fn backport_version(version: &str, release_version: &str) -> PyResult<String> {
+    Ok(crate::versions::backport_version(&parse_version(version)?, release_version).to_string())
+}
+
+/// The version for an Ubuntu upload of a version.
+#[pyfunction]
+fn ubuntu_version(version: &str) -> PyResult<String> {
+    Ok(crate::versions::ubuntu_version(&parse_version(version)?).to_string())
+}
+
+/// The version a new upload should carry.
+///
+/// `upload_type` is "regular", "nmu" or "backport"; backports also need
+/// the version of the target release.
+#[pyfunction]
+#[pyo3(signature = (version, upload_type="regular", vendor="debian", release_version=None))]
+fn next_upload_version(
+    version: &str,
+    upload_type: &str,
+    vendor: &str,
+    release_version: Option<String>,
+) -> PyResult<String> {
+    use crate::versions::UploadType;
+    let upload_type = match (upload_type, release_version) {
+        ("regular", _) => UploadType::Regular,
+        ("nmu", _) => UploadType::NonMaintainer,
+        ("backport", Some(release_version)) => UploadType::Backport(release_version),
+        ("backport", None) => {
+            return Err(PyValueError::new_err(
+                "release_version is required for backports",
+            ))
+        }
+        (other, _) => {
+            return Err(PyValueError::new_err(format!(
+                "Unknown upload type {}",
+                other
+            )))
+        }
+    };
+    Ok(crate::versions::next_upload_version(
+        &parse_version(version)?,
+        &upload_type,
+        parse_vendor(vendor)?,
+    )
+    .to_string())
+}
+
+/// Editors and helpers for Debian packaging.
+#[pymodule]
+fn debian_analyzer(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("PackageError", m.py().get_type::<PackageError>())?;
+    m.add("BrokenEnvironment", m.py().get_type::<BrokenEnvironment>())?;
+    m.add_class::<ControlEditor>()?;
+    m.add_class::<RulesEditor>()?;
+    m.add_class::<CompatBump>()?;
+    m.add_function(wrap_pyfunction!(get_debhelper_compat_level, m)?)?;
+    m.add_function(wrap_pyfunction!(read_debhelper_compat_file, m)?)?;
+    m.add_function(wrap_pyfunction!(lowest_non_deprecated_compat_level, m)?)?;
+    m.add_function(wrap_pyfunction!(highest_stable_compat_level, m)?)?;
+    m.add_function(wrap_pyfunction!(bump_debhelper_compat, m)?)?;
+    m.add_function(wrap_pyfunction!(dh_invoke_add_with, m)?)?;
+    m.add_function(wrap_pyfunction!(dh_invoke_get_with, m)?)?;
+    m.add_function(wrap_pyfunction!(dh_invoke_drop_with, m)?)?;
+    m.add_function(wrap_pyfunction!(dh_invoke_drop_argument, m)?)?;
+    m.add_function(wrap_pyfunction!(dh_invoke_replace_argument, m)?)?;
+    m.add_function(wrap_pyfunction!(check_cdbs, m)?)?;
+    m.add_function(wrap_pyfunction!(debian_releases, m)?)?;
+    m.add_function(wrap_pyfunction!(ubuntu_releases, m)?)?;
+    m.add_function(wrap_pyfunction!(suite_to_distribution, m)?)?;
+    m.add_function(wrap_pyfunction!(release_aliases, m)?)?;
+    m.add_function(wrap_pyfunction!(resolve_release_codename, m)?)?;
+    m.add_function(wrap_pyfunction!(debianize_upstream_version, m)?)?;
+    m.add_function(wrap_pyfunction!(matches_release, m)?)?;
+    m.add_function(wrap_pyfunction!(add_snapshot_suffix, m)?)?;
+    m.add_function(wrap_pyfunction!(strip_snapshot_suffix, m)?)?;
+    m.add_function(wrap_pyfunction!(nmu_version, m)?)?;
+    m.add_function(wrap_pyfunction!(backport_version, m)?)?;
+    m.add_function(wrap_pyfunction!(ubuntu_version, m)?)?;
+    m.add_function(wrap_pyfunction!(next_upload_version, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,346 @@
+//! Maintenance of managed sections in debian/README.source.
+//!
+//! Sections describing a packaging workflow are delimited by marker
+//! comments, so that they can be updated or removed when the workflow
+//! changes without touching text written by the maintainer.
+use std::path::Path;
+
+/// Path of the README.source file, relative to the root of the source tree.
+pub const README_SOURCE_PATH: &str = "debian/README.source";
+
+/// A packaging workflow that is documented in README.source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Workflow {
+    /// Changes to the upstream source are kept as quilt patches
+    Quilt,
+    /// The upstream tarball is repacked to drop files listed in Files-Excluded
+    Repack,
+    /// Patches are maintained with `gbp pq`
+    GbpPq,
+}
+
+impl Workflow {
+    /// All known workflows, in the order their sections are added.
+    pub const ALL: &'static [Workflow] = &[Workflow::Quilt, Workflow::Repack, Workflow::GbpPq];
+
+    /// Identifier of the workflow, as used in the section markers.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Workflow::Quilt => "quilt",
+            Workflow::Repack => "repack",
+            Workflow::GbpPq => "gbp-pq",
+        }
+    }
+
+    /// The text of the section describing the workflow.
+    pub fn text(&self) -> &'static str {
+        match self {
+            Workflow::Quilt => {
+                "This package uses quilt to manage all modifications to the upstream
+source. Changes are stored in the source package as diffs in
+debian/patches and applied during the build.
+
+See /usr/share/doc/quilt/README.source for a detailed explanation.
+"
+            }
+            Workflow::Repack => {
+                "The upstream tarball is repacked to remove the files listed in the
+Files-Excluded field of debian/copyright. Run uscan to download and
+repack a new upstream release.
+"
+            }
+            Workflow::GbpPq => {
+                "Patches are maintained with gbp pq. Run \"gbp pq import\" to turn
+debian/patches into a patch queue branch, and \"gbp pq export\" to
+regenerate debian/patches from it.
+"
+            }
+        }
+    }
+
+    fn begin_marker(&self) -> String {
+        format!("<!-- BEGIN {} -->", self.id())
+    }
+
+    fn end_marker(&self) -> String {
+        format!("<!-- END {} -->", self.id())
+    }
+}
+
+/// The contents of a README.source file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReadmeSource {
+    text: String,
+}
+
+impl ReadmeSource {
+    /// Create a new, empty README.source
+    pub fn new() -> Self {
+        ReadmeSource {
+            text: String::new(),
+        }
+    }
+
+    /// Check if the file has no content
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    /// Find the byte range of the section for a workflow, including its markers.
+    fn section_range(&self, workflow: Workflow) -> Option<std::ops::Range<usize>> {
+        let begin = self.text.find(&workflow.begin_marker())?;
+        let end_marker = workflow.end_marker();
+        let end = begin + self.text[begin..].find(&end_marker)? + end_marker.len();
+        let end = if self.text[end..].starts_with('\n') {
+            end + 1
+        } else {
+            end
+        };
+        Some(begin..end)
+    }
+
+    /// Workflows that have a managed section in the file.
+    pub fn workflows(&self) -> Vec<Workflow> {
+        Workflow::ALL
+            .iter()
+            .copied()
+            .filter(|w| self.section_range(*w).is_some())
+            .collect()
+    }
+
+    /// Get the text of the managed section for a workflow, without its markers.
+    pub fn section(&self, workflow: Workflow) -> Option<&str> {
+        let range = self.section_range(workflow)?;
+        let section = &self.text[range];
+        let start = section.find('\n')? + 1;
+        let end = section.rfind(&workflow.end_marker())?;
+        Some(&section[start.min(end)..end])
+    }
+
+    /// Add or update the managed section for a workflow.
+    ///
+    /// # Returns
+    /// Whether the file was changed
+    pub fn set_section(&mut self, workflow: Workflow, content: &str) -> bool {
+        let mut content = content.to_string();
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        let section = format!(
+            "{}\n{}{}\n",
+            workflow.begin_marker(),
+            content,
+            workflow.end_marker()
+        );
+        match self.section_range(workflow) {
+            Some(range) => {
+                if self.text[range.clone()] == section {
+                    return false;
+                }
+                self.text.replace_range(range, &section);
+            }
+            None => {
+                if !self.text.is_empty() {
+                    if !self.text.ends_with('\n') {
+                        self.text.push('\n');
+                    }
+                    if !self.text.ends_with("\n\n") {
+                        self.text.push('\n');
+                    }
+                }
+                self.text.push_str(&section);
+            }
+        }
+        true
+    }
+
+    /// Remove the managed section for a workflow, along with a blank line preceding it.
+    ///
+    /// # Returns
+    /// Whether the section was present
+    pub fn remove_section(&mut self, workflow: Workflow) -> bool {
+        let Some(mut range) = self.section_range(workflow) else {
+            return false;
+        };
+        if self.text[..range.start].ends_with("\n\n") {
+            range.start -= 1;
+        } else if range.start == 0 && self.text[range.end..].starts_with('\n') {
+            range.end += 1;
+        }
+        self.text.replace_range(range, "");
+        true
+    }
+
+    /// Make the managed sections match the workflows in use.
+    ///
+    /// Sections for workflows that are in use are added with their default
+    /// text if missing; existing sections are left as they are. Sections
+    /// for workflows that are no longer in use are removed.
+    ///
+    /// # Returns
+    /// Whether the file was changed
+    pub fn sync(&mut self, workflows: &[Workflow]) -> bool {
+        let mut changed = false;
+        for workflow in Workflow::ALL {
+            if workflows.contains(workflow) {
+                if self.section_range(*workflow).is_none() {
+                    changed |= self.set_section(*workflow, workflow.text());
+                }
+            } else {
+                changed |= self.remove_section(*workflow);
+            }
+        }
+        changed
+    }
+
+    /// Read the README.source of a source tree; returns an empty file if it does not exist.
+    pub fn read(base: &Path) -> std::io::Result<Self> {
+        match std::fs::read_to_string(base.join(README_SOURCE_PATH)) {
+            Ok(text) => Ok(ReadmeSource { text }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ReadmeSource::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the README.source of a source tree, removing it if it is empty.
+    pub fn write(&self, base: &Path) -> std::io::Result<()> {
+        let path = base.join(README_SOURCE_PATH);
+        if self.is_empty() {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            }
+        } else {
+            std::fs::write(path, &self.text)
+        }
+    }
+}
+
+impl std::fmt::Display for ReadmeSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl std::str::FromStr for ReadmeSource {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(ReadmeSource {
+            text: s.to_string(),
+        })
+    }
+}
+
+/// Detect which of the documented workflows a source tree uses.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+pub fn detect_workflows(base: &Path) -> std::io::Result<Vec<Workflow>> {
+    let read = |path: &str| match std::fs::read_to_string(base.join(path)) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    };
+    let mut ret = vec![];
+    let is_quilt = read("debian/source/format")?.is_some_and(|f| f.trim() == "3.0 (quilt)");
+    let has_patches = read("debian/patches/series")?.is_some_and(|s| {
+        s.lines()
+            .any(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+    });
+    if is_quilt && has_patches {
+        ret.push(Workflow::Quilt);
+    }
+    if let Some(text) = read("debian/copyright")? {
+        if let Ok(copyright) = text.parse::<debian_copyright::lossless::Copyright>() {
+            if !crate::copyright::files_excluded(&copyright).is_empty() {
+                ret.push(Workflow::Repack);
+            }
+        }
+    }
+    let gbp_conf = crate::gbp::find_gbp_conf(base);
+    if let Ok(text) = std::fs::read_to_string(gbp_conf) {
+        if let Ok(conf) = text.parse::<crate::gbp::GbpConf>() {
+            if conf.sections().contains(&"pq") {
+                ret.push(Workflow::GbpPq);
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Update the managed sections of debian/README.source to match the workflows in use.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// Whether README.source was changed
+pub fn update_readme_source(base: &Path) -> std::io::Result<bool> {
+    let workflows = detect_workflows(base)?;
+    let mut readme = ReadmeSource::read(base)?;
+    if !readme.sync(&workflows) {
+        return Ok(false);
+    }
+    readme.write(base)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections() {
+        let mut readme: ReadmeSource = "Some notes by the maintainer.\n".parse().unwrap();
+        assert!(readme.set_section(Workflow::Quilt, "Uses quilt."));
+        assert!(!readme.set_section(Workflow::Quilt, "Uses quilt.\n"));
+        assert!(readme.set_section(Workflow::GbpPq, "Uses gbp pq.\n"));
+        assert_eq!(
+            "Some notes by the maintainer.\n\n<!-- BEGIN quilt -->\nUses quilt.\n<!-- END quilt -->\n\n<!-- BEGIN gbp-pq -->\nUses gbp pq.\n<!-- END gbp-pq -->\n",
+            readme.to_string()
+        );
+        assert_eq!(Some("Uses quilt.\n"), readme.section(Workflow::Quilt));
+        assert_eq!(vec![Workflow::Quilt, Workflow::GbpPq], readme.workflows());
+        assert!(readme.set_section(Workflow::Quilt, "Still uses quilt.\n"));
+        assert!(readme.remove_section(Workflow::Quilt));
+        assert!(!readme.remove_section(Workflow::Quilt));
+        assert_eq!(
+            "Some notes by the maintainer.\n\n<!-- BEGIN gbp-pq -->\nUses gbp pq.\n<!-- END gbp-pq -->\n",
+            readme.to_string()
+        );
+        assert!(readme.remove_section(Workflow::GbpPq));
+        assert_eq!("Some notes by the maintainer.\n", readme.to_string());
+    }
+
+    #[test]
+    fn test_update_readme_source() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/source")).unwrap();
+        std::fs::create_dir_all(td.path().join("debian/patches")).unwrap();
+        std::fs::write(td.path().join("debian/source/format"), "3.0 (quilt)\n").unwrap();
+        std::fs::write(td.path().join("debian/patches/series"), "fix-build.patch\n").unwrap();
+        std::fs::write(
+            td.path().join("debian/gbp.conf"),
+            "[pq]\npatch-numbers = False\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![Workflow::Quilt, Workflow::GbpPq],
+            detect_workflows(td.path()).unwrap()
+        );
+        assert!(update_readme_source(td.path()).unwrap());
+        assert!(!update_readme_source(td.path()).unwrap());
+        let readme = ReadmeSource::read(td.path()).unwrap();
+        assert_eq!(
+            Some(Workflow::Quilt.text()),
+            readme.section(Workflow::Quilt)
+        );
+
+        // Dropping the workflows removes the sections, and with them the file.
+        std::fs::write(td.path().join("debian/patches/series"), "").unwrap();
+        std::fs::remove_file(td.path().join("debian/gbp.conf")).unwrap();
+        assert!(update_readme_source(td.path()).unwrap());
+        assert!(!td.path().join(README_SOURCE_PATH).exists());
+    }
+}
@@ -1,33 +1,61 @@
 //! Debian and Ubuntu release information.
 
-pub use breezyshim::debian::Vendor;
+use crate::error::Error;
 use chrono::{NaiveDate, Utc};
 use distro_info::DistroInfo;
 
+/// A distribution that packages are uploaded to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Vendor {
+    /// Debian
+    Debian,
+    /// Ubuntu
+    Ubuntu,
+    /// Kali Linux
+    Kali,
+}
+
+#[cfg(feature = "vcs")]
+impl From<Vendor> for breezyshim::debian::Vendor {
+    fn from(vendor: Vendor) -> Self {
+        match vendor {
+            Vendor::Debian => breezyshim::debian::Vendor::Debian,
+            Vendor::Ubuntu => breezyshim::debian::Vendor::Ubuntu,
+            Vendor::Kali => breezyshim::debian::Vendor::Kali,
+        }
+    }
+}
+
 /// Pocket names for Debian.
 pub const DEBIAN_POCKETS: &[&str] = &["", "-security", "-proposed-updates", "-backports"];
 
 /// Pocket names for Ubuntu.
 pub const UBUNTU_POCKETS: &[&str] = &["", "-proposed", "-updates", "-security", "-backports"];
 
+fn debian_distro_info() -> Result<distro_info::DebianDistroInfo, Error> {
+    distro_info::DebianDistroInfo::new().map_err(|e| Error::DistroInfo(e.to_string()))
+}
+
+fn ubuntu_distro_info() -> Result<distro_info::UbuntuDistroInfo, Error> {
+    distro_info::UbuntuDistroInfo::new().map_err(|e| Error::DistroInfo(e.to_string()))
+}
+
 /// List of all Debian releases.
-pub fn debian_releases() -> Vec<String> {
-    let debian = distro_info::DebianDistroInfo::new().unwrap();
-    debian
+pub fn debian_releases() -> Result<Vec<String>, Error> {
+    Ok(debian_distro_info()?
         .all_at(Utc::now().naive_utc().date())
         .into_iter()
         .map(|r| r.series().to_string())
-        .collect()
+        .collect())
 }
 
 /// List of all Ubuntu releases.
-pub fn ubuntu_releases() -> Vec<String> {
-    let ubuntu = distro_info::UbuntuDistroInfo::new().unwrap();
-    ubuntu
+pub fn ubuntu_releases() -> Result<Vec<String>, Error> {
+    Ok(ubuntu_distro_info()?
         .all_at(Utc::now().naive_utc().date())
         .into_iter()
         .map(|r| r.series().to_string())
-        .collect()
+        .collect())
 }
 
 /// Infer the distribution from a suite.
@@ -38,49 +66,48 @@ pub fn ubuntu_releases() -> Vec<String> {
 ///
 /// # Arguments
 /// * `suite`: the string containing the suite
-pub fn suite_to_distribution(suite: &str) -> Option<Vendor> {
-    let all_debian = debian_releases()
+pub fn suite_to_distribution(suite: &str) -> Result<Option<Vendor>, Error> {
+    let all_debian = debian_releases()?
         .iter()
         .flat_map(|r| DEBIAN_POCKETS.iter().map(move |t| format!("{}{}", r, t)))
         .collect::<Vec<_>>();
-    let all_ubuntu = ubuntu_releases()
+    let all_ubuntu = ubuntu_releases()?
         .iter()
         .flat_map(|r| UBUNTU_POCKETS.iter().map(move |t| format!("{}{}", r, t)))
         .collect::<Vec<_>>();
     if all_debian.contains(&suite.to_string()) {
-        return Some(Vendor::Debian);
+        return Ok(Some(Vendor::Debian));
     }
     if all_ubuntu.contains(&suite.to_string()) {
-        return Some(Vendor::Ubuntu);
+        return Ok(Some(Vendor::Ubuntu));
     }
 
     if suite == "kali" || suite.starts_with("kali-") {
-        return Some(Vendor::Kali);
+        return Ok(Some(Vendor::Kali));
     }
 
-    None
+    Ok(None)
 }
 
 /// Find aliases for a particular release.
-pub fn release_aliases(name: &str, date: Option<NaiveDate>) -> Vec<String> {
+pub fn release_aliases(name: &str, date: Option<NaiveDate>) -> Result<Vec<String>, Error> {
     let mut ret = vec![];
-    let debian_info = distro_info::DebianDistroInfo::new().unwrap();
+    let debian_info = debian_distro_info()?;
     let all_released = debian_info.released(date.unwrap_or(Utc::now().naive_utc().date()));
-    if all_released[0].series() == name {
-        ret.push("stable".to_string());
-    }
-    if all_released[1].series() == name {
-        ret.push("oldstable".to_string());
-    }
-    if all_released[2].series() == name {
-        ret.push("oldoldstable".to_string());
+    for (release, alias) in all_released
+        .iter()
+        .zip(["stable", "oldstable", "oldoldstable"])
+    {
+        if release.series() == name {
+            ret.push(alias.to_string());
+        }
     }
 
     if name == "sid" {
         ret.push("unstable".to_string());
     }
 
-    let ubuntu_info = distro_info::UbuntuDistroInfo::new().unwrap();
+    let ubuntu_info = ubuntu_distro_info()?;
 
     let all_released = ubuntu_info.released(date.unwrap_or(Utc::now().naive_utc().date()));
     for series in all_released.iter() {
@@ -89,11 +116,17 @@ pub fn release_aliases(name: &str, date: Option<NaiveDate>) -> Vec<String> {
         }
     }
 
-    ret
+    Ok(ret)
 }
 
 /// Resolve a release codename or series name to a series name.
-pub fn resolve_release_codename(name: &str, date: Option<NaiveDate>) -> Option<String> {
+///
+/// # Returns
+/// The series name, or `None` if the release is unknown
+pub fn resolve_release_codename(
+    name: &str,
+    date: Option<NaiveDate>,
+) -> Result<Option<String>, Error> {
     let date = date.unwrap_or(Utc::now().naive_utc().date());
     let (distro, mut name) = if let Some((distro, name)) = name.split_once('/') {
         (Some(distro), name)
@@ -102,14 +135,14 @@ pub fn resolve_release_codename(name: &str, date: Option<NaiveDate>) -> Option<S
     };
     let active = |x: &Option<NaiveDate>| x.map(|x| x > date).unwrap_or(false);
     if distro.is_none() || distro == Some("debian") {
-        let debian = distro_info::DebianDistroInfo::new().unwrap();
+        let debian = debian_distro_info()?;
         if name == "lts" {
             let lts = debian
                 .all_at(date)
                 .into_iter()
                 .filter(|r| active(r.eol_lts()))
                 .min_by_key(|r| r.created());
-            return lts.map(|r| r.series().to_string());
+            return Ok(lts.map(|r| r.series().to_string()));
         }
         if name == "elts" {
             let elts = debian
@@ -117,7 +150,7 @@ pub fn resolve_release_codename(name: &str, date: Option<NaiveDate>) -> Option<S
                 .into_iter()
                 .filter(|r| active(r.eol_elts()))
                 .min_by_key(|r| r.created());
-            return elts.map(|r| r.series().to_string());
+            return Ok(elts.map(|r| r.series().to_string()));
         }
         let mut all_released = debian
             .all_at(date)
@@ -127,13 +160,13 @@ pub fn resolve_release_codename(name: &str, date: Option<NaiveDate>) -> Option<S
         all_released.sort_by_key(|r| r.created());
         all_released.reverse();
         if name == "stable" {
-            return Some(all_released[0].series().to_string());
+            return Ok(all_released.first().map(|r| r.series().to_string()));
         }
         if name == "oldstable" {
-            return Some(all_released[1].series().to_string());
+            return Ok(all_released.get(1).map(|r| r.series().to_string()));
         }
         if name == "oldoldstable" {
-            return Some(all_released[2].series().to_string());
+            return Ok(all_released.get(2).map(|r| r.series().to_string()));
         }
         if name == "unstable" {
             name = "sid";
@@ -145,7 +178,7 @@ pub fn resolve_release_codename(name: &str, date: Option<NaiveDate>) -> Option<S
                 .filter(|r| r.release().is_none())
                 .collect::<Vec<_>>();
             all_unreleased.sort_by_key(|r| r.created());
-            return Some(all_unreleased.last().unwrap().series().to_string());
+            return Ok(all_unreleased.last().map(|r| r.series().to_string()));
         }
 
         let all = debian.all_at(date);
@@ -153,36 +186,36 @@ pub fn resolve_release_codename(name: &str, date: Option<NaiveDate>) -> Option<S
             .iter()
             .find(|r| r.codename() == name || r.series() == name)
         {
-            return Some(series.series().to_string());
+            return Ok(Some(series.series().to_string()));
         }
     }
     if distro.is_none() || distro == Some("ubuntu") {
-        let ubuntu = distro_info::UbuntuDistroInfo::new().unwrap();
+        let ubuntu = ubuntu_distro_info()?;
         if name == "esm" {
-            return ubuntu
+            return Ok(ubuntu
                 .all_at(date)
                 .into_iter()
                 .filter(|r| active(r.eol_esm()))
                 .min_by_key(|r| r.created())
-                .map(|r| r.series().to_string());
+                .map(|r| r.series().to_string()));
         }
         if name == "lts" {
-            return ubuntu
+            return Ok(ubuntu
                 .all_at(date)
                 .into_iter()
                 .filter(|r| r.is_lts() && r.supported_at(date))
                 .min_by_key(|r| r.created())
-                .map(|r| r.series().to_string());
+                .map(|r| r.series().to_string()));
         }
         let all = ubuntu.all_at(date);
         if let Some(series) = all
             .iter()
             .find(|r| r.codename() == name || r.series() == name)
         {
-            return Some(series.series().to_string());
+            return Ok(Some(series.series().to_string()));
         }
     }
-    None
+    Ok(None)
 }
 
 #[cfg(test)]
@@ -191,12 +224,25 @@ mod tests {
 
     #[test]
     fn test_debian() {
-        assert_eq!("sid", resolve_release_codename("debian/sid", None).unwrap());
-        assert_eq!("sid", resolve_release_codename("sid", None).unwrap());
-        assert_eq!("sid", resolve_release_codename("unstable", None).unwrap());
+        assert_eq!(
+            "sid",
+            resolve_release_codename("debian/sid", None)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            "sid",
+            resolve_release_codename("sid", None).unwrap().unwrap()
+        );
+        assert_eq!(
+            "sid",
+            resolve_release_codename("unstable", None).unwrap().unwrap()
+        );
         assert_eq!(
             "experimental",
-            resolve_release_codename("experimental", None).unwrap()
+            resolve_release_codename("experimental", None)
+                .unwrap()
+                .unwrap()
         );
     }
 
@@ -204,42 +250,73 @@ mod tests {
     fn test_ubuntu() {
         assert_eq!(
             "trusty",
-            resolve_release_codename("ubuntu/trusty", None).unwrap()
+            resolve_release_codename("ubuntu/trusty", None)
+                .unwrap()
+                .unwrap()
         );
-        assert_eq!("trusty", resolve_release_codename("trusty", None).unwrap());
-        assert!(resolve_release_codename("ubuntu/lts", None).is_some());
+        assert_eq!(
+            "trusty",
+            resolve_release_codename("trusty", None).unwrap().unwrap()
+        );
+        assert!(resolve_release_codename("ubuntu/lts", None)
+            .unwrap()
+            .is_some());
     }
 
     #[test]
     fn test_resolve_debian() {
-        assert_eq!("sid", resolve_release_codename("sid", None).unwrap());
-        assert_eq!("buster", resolve_release_codename("buster", None).unwrap());
-        assert_eq!("sid", resolve_release_codename("unstable", None).unwrap());
         assert_eq!(
             "sid",
-            resolve_release_codename("debian/unstable", None).unwrap()
+            resolve_release_codename("sid", None).unwrap().unwrap()
+        );
+        assert_eq!(
+            "buster",
+            resolve_release_codename("buster", None).unwrap().unwrap()
         );
-        assert!(resolve_release_codename("oldstable", None).is_some());
-        assert!(resolve_release_codename("oldoldstable", None).is_some());
+        assert_eq!(
+            "sid",
+            resolve_release_codename("unstable", None).unwrap().unwrap()
+        );
+        assert_eq!(
+            "sid",
+            resolve_release_codename("debian/unstable", None)
+                .unwrap()
+                .unwrap()
+        );
+        assert!(resolve_release_codename("oldstable", None)
+            .unwrap()
+            .is_some());
+        assert!(resolve_release_codename("oldoldstable", None)
+            .unwrap()
+            .is_some());
     }
 
     #[test]
     fn test_resolve_unknown() {
-        assert!(resolve_release_codename("blah", None).is_none());
+        assert!(resolve_release_codename("blah", None).unwrap().is_none());
     }
 
     #[test]
     fn test_resolve_ubuntu() {
-        assert_eq!("trusty", resolve_release_codename("trusty", None).unwrap());
         assert_eq!(
             "trusty",
-            resolve_release_codename("ubuntu/trusty", None).unwrap()
+            resolve_release_codename("trusty", None).unwrap().unwrap()
+        );
+        assert_eq!(
+            "trusty",
+            resolve_release_codename("ubuntu/trusty", None)
+                .unwrap()
+                .unwrap()
         );
-        assert!(resolve_release_codename("ubuntu/lts", None).is_some())
+        assert!(resolve_release_codename("ubuntu/lts", None)
+            .unwrap()
+            .is_some())
     }
 
     #[test]
     fn test_resolve_ubuntu_esm() {
-        assert!(resolve_release_codename("ubuntu/esm", None).is_some())
+        assert!(resolve_release_codename("ubuntu/esm", None)
+            .unwrap()
+            .is_some())
     }
 }
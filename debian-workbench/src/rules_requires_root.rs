@@ -0,0 +1,356 @@
+//! Decide whether a package can declare `Rules-Requires-Root: no`.
+use std::path::Path;
+
+/// Lowest debhelper compat level for which the field is set automatically.
+///
+/// Packages on older compat levels have usually not been touched in a long
+/// time, so they are left alone.
+pub const MINIMUM_COMPAT_LEVEL: u8 = 10;
+
+/// Value of the Rules-Requires-Root field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulesRequiresRoot {
+    /// The build does not need root at all
+    No,
+    /// The binary targets need to be run as (fake)root
+    BinaryTargets,
+    /// The build needs root only for the operations named by these keywords,
+    /// e.g. "dpkg/target-subcommand"
+    Keywords(Vec<String>),
+}
+
+impl std::str::FromStr for RulesRequiresRoot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "no" => Ok(RulesRequiresRoot::No),
+            "binary-targets" => Ok(RulesRequiresRoot::BinaryTargets),
+            "" => Err("empty Rules-Requires-Root field".to_string()),
+            s => {
+                let keywords = s
+                    .split_whitespace()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<_>>();
+                if let Some(k) = keywords.iter().find(|k| !k.contains('/')) {
+                    return Err(format!("invalid Rules-Requires-Root keyword {}", k));
+                }
+                Ok(RulesRequiresRoot::Keywords(keywords))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RulesRequiresRoot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RulesRequiresRoot::No => write!(f, "no"),
+            RulesRequiresRoot::BinaryTargets => write!(f, "binary-targets"),
+            RulesRequiresRoot::Keywords(keywords) => write!(f, "{}", keywords.join(" ")),
+        }
+    }
+}
+
+/// Evidence that building the package does not require root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Evidence {
+    /// The package is maintained with debcargo, which does not need root
+    Debcargo,
+    /// debian/rules uses the dh sequencer at this compat level
+    DhSequencer(u8),
+    /// debian/rules does not change the ownership of files
+    NoOwnershipChanges,
+    /// These maintainer scripts set file ownership when the package is installed
+    OwnershipInMaintainerScripts(Vec<String>),
+}
+
+impl std::fmt::Display for Evidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Evidence::Debcargo => write!(f, "package is built by debcargo"),
+            Evidence::DhSequencer(compat) => {
+                write!(f, "debian/rules uses dh with compat level {}", compat)
+            }
+            Evidence::NoOwnershipChanges => {
+                write!(f, "debian/rules does not change file ownership")
+            }
+            Evidence::OwnershipInMaintainerScripts(scripts) => write!(
+                f,
+                "file ownership is set at install time by {}",
+                scripts.join(", ")
+            ),
+        }
+    }
+}
+
+/// Reason why `Rules-Requires-Root: no` may not be safe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Blocker {
+    /// debian/rules uses cdbs
+    Cdbs,
+    /// debian/rules does not use the dh sequencer
+    NoDhSequencer,
+    /// The debhelper compat level is unknown or too old
+    OldCompatLevel(Option<u8>),
+    /// A command in debian/rules changes the ownership of files
+    OwnershipChange(String),
+}
+
+impl std::fmt::Display for Blocker {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Blocker::Cdbs => write!(f, "debian/rules uses cdbs"),
+            Blocker::NoDhSequencer => write!(f, "debian/rules does not use dh"),
+            Blocker::OldCompatLevel(Some(compat)) => {
+                write!(f, "debhelper compat level {} is too old", compat)
+            }
+            Blocker::OldCompatLevel(None) => write!(f, "debhelper compat level is unknown"),
+            Blocker::OwnershipChange(command) => {
+                write!(f, "debian/rules changes file ownership: {}", command)
+            }
+        }
+    }
+}
+
+/// Result of analyzing whether a package needs root to build.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Analysis {
+    /// Evidence that the build does not need root
+    pub evidence: Vec<Evidence>,
+    /// Reasons the build may need root
+    pub blockers: Vec<Blocker>,
+}
+
+impl Analysis {
+    /// Whether `Rules-Requires-Root: no` can safely be set.
+    pub fn is_safe(&self) -> bool {
+        self.blockers.is_empty() && !self.evidence.is_empty()
+    }
+
+    /// Describe the evidence, e.g. for use in a changelog entry.
+    pub fn explanation(&self) -> String {
+        self.evidence
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Find commands in debian/rules that change the ownership of files.
+///
+/// # Arguments
+/// * `makefile` - The parsed debian/rules file
+pub fn ownership_changes(makefile: &makefile_lossless::Makefile) -> Vec<String> {
+    makefile
+        .rules()
+        .flat_map(|r| r.recipes().collect::<Vec<_>>())
+        .filter(|command| {
+            lazy_regex::regex_is_match!(
+                r"(^|[\s;&|(])(chown|chgrp)\s|\binstall\b.*\s(-[og]\s|--owner|--group)|--owner=",
+                command
+            )
+        })
+        .map(|command| command.trim().to_string())
+        .collect()
+}
+
+/// Whether debian/rules hands all targets to the dh sequencer.
+fn uses_dh_sequencer(makefile: &makefile_lossless::Makefile) -> bool {
+    makefile.rules().any(|r| {
+        r.targets().any(|t| t == "%")
+            && r.recipes()
+                .any(|c| lazy_regex::regex_is_match!(r"^\s*dh\s+\$[@(]", &c))
+    })
+}
+
+/// Find maintainer scripts that set file ownership at install time.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// Names of the maintainer scripts, relative to the debian directory
+pub fn maintainer_scripts_setting_ownership(base: &Path) -> std::io::Result<Vec<String>> {
+    let mut ret = vec![];
+    let debian = base.join("debian");
+    if !debian.is_dir() {
+        return Ok(ret);
+    }
+    for entry in std::fs::read_dir(&debian)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if !(name == "postinst" || name.ends_with(".postinst")) {
+            continue;
+        }
+        let text = std::fs::read_to_string(debian.join(&name))?;
+        if lazy_regex::regex_is_match!(r"(?m)^[^#]*\b(chown|chgrp|dpkg-statoverride)\b", &text) {
+            ret.push(name);
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Analyze whether building a package requires root.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+pub fn analyze(base: &Path) -> Result<Analysis, crate::error::Error> {
+    let mut analysis = Analysis::default();
+    if base.join("debian/debcargo.toml").exists() {
+        analysis.evidence.push(Evidence::Debcargo);
+        return Ok(analysis);
+    }
+    let rules_path = base.join("debian/rules");
+    if crate::rules::check_cdbs(&rules_path) {
+        analysis.blockers.push(Blocker::Cdbs);
+        return Ok(analysis);
+    }
+    let makefile = match std::fs::File::open(&rules_path) {
+        Ok(f) => makefile_lossless::Makefile::read_relaxed(f).map_err(|e| {
+            crate::error::Error::Parse {
+                path: rules_path.clone(),
+                message: e.to_string(),
+            }
+        })?,
+        Err(e) => return Err(e.into()),
+    };
+    if uses_dh_sequencer(&makefile) {
+        match crate::debhelper::get_debhelper_compat_level(base)? {
+            Some(compat) if compat >= MINIMUM_COMPAT_LEVEL => {
+                analysis.evidence.push(Evidence::DhSequencer(compat))
+            }
+            compat => analysis.blockers.push(Blocker::OldCompatLevel(compat)),
+        }
+    } else {
+        analysis.blockers.push(Blocker::NoDhSequencer);
+    }
+    let changes = ownership_changes(&makefile);
+    if changes.is_empty() {
+        analysis.evidence.push(Evidence::NoOwnershipChanges);
+    }
+    analysis
+        .blockers
+        .extend(changes.into_iter().map(Blocker::OwnershipChange));
+    let scripts = maintainer_scripts_setting_ownership(base)?;
+    if !scripts.is_empty() {
+        analysis
+            .evidence
+            .push(Evidence::OwnershipInMaintainerScripts(scripts));
+    }
+    Ok(analysis)
+}
+
+/// Set `Rules-Requires-Root: no` if it is safe to do so.
+///
+/// Nothing is changed if the source package already has a
+/// Rules-Requires-Root field.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `editor` - Editor for the package's control file
+///
+/// # Returns
+/// The analysis, if the field was not yet set; the field has been set if
+/// [`Analysis::is_safe`] returns true
+pub fn set_rules_requires_root_no(
+    base: &Path,
+    editor: &mut dyn crate::abstract_control::AbstractControlEditor,
+) -> Result<Option<Analysis>, crate::error::Error> {
+    let Some(mut source) = editor.source() else {
+        return Ok(None);
+    };
+    // debcargo always reports a value, since it defaults to not requiring root.
+    if source.rules_requires_root().is_some() {
+        return Ok(None);
+    }
+    let analysis = analyze(base)?;
+    if analysis.is_safe() {
+        source.set_rules_requires_root("no");
+    }
+    Ok(Some(analysis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_requires_root_value() {
+        assert_eq!(Ok(RulesRequiresRoot::No), "no".parse());
+        assert_eq!(
+            Ok(RulesRequiresRoot::BinaryTargets),
+            "binary-targets".parse()
+        );
+        let keywords: RulesRequiresRoot = "dpkg/target-subcommand  foo/bar".parse().unwrap();
+        assert_eq!("dpkg/target-subcommand foo/bar", keywords.to_string());
+        assert!("yes".parse::<RulesRequiresRoot>().is_err());
+        assert!("".parse::<RulesRequiresRoot>().is_err());
+    }
+
+    fn setup(rules: &str, control: &str) -> tempfile::TempDir {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(td.path().join("debian/rules"), rules).unwrap();
+        std::fs::write(td.path().join("debian/control"), control).unwrap();
+        td
+    }
+
+    const CONTROL: &str =
+        "Source: foo\nBuild-Depends: debhelper-compat (= 13)\n\nPackage: foo\nArchitecture: any\n";
+
+    #[test]
+    fn test_set_rules_requires_root_no() {
+        let td = setup("#!/usr/bin/make -f\n\n%:\n\tdh $@\n", CONTROL);
+        std::fs::write(
+            td.path().join("debian/foo.postinst"),
+            "#!/bin/sh\nchown foo:foo /var/lib/foo\n",
+        )
+        .unwrap();
+        let mut editor =
+            crate::control::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+        let analysis = set_rules_requires_root_no(td.path(), &mut editor)
+            .unwrap()
+            .unwrap();
+        assert!(analysis.is_safe());
+        assert_eq!(
+            "debian/rules uses dh with compat level 13; debian/rules does not change file ownership; file ownership is set at install time by foo.postinst",
+            analysis.explanation()
+        );
+        crate::abstract_control::AbstractControlEditor::commit(&editor).unwrap();
+        assert_eq!(
+            "Source: foo\nBuild-Depends: debhelper-compat (= 13)\nRules-Requires-Root: no\n\nPackage: foo\nArchitecture: any\n",
+            std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+        );
+        let mut editor =
+            crate::control::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+        assert_eq!(
+            None,
+            set_rules_requires_root_no(td.path(), &mut editor).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_blockers() {
+        let td = setup(
+            "#!/usr/bin/make -f\n\n%:\n\tdh $@\n\noverride_dh_fixperms:\n\tdh_fixperms\n\tchown games:games debian/foo/var/games/foo\n",
+            "Source: foo\nBuild-Depends: debhelper (>= 9)\n\nPackage: foo\nArchitecture: any\n",
+        );
+        std::fs::write(td.path().join("debian/compat"), "9\n").unwrap();
+        let analysis = analyze(td.path()).unwrap();
+        assert!(!analysis.is_safe());
+        assert_eq!(
+            vec![
+                Blocker::OldCompatLevel(Some(9)),
+                Blocker::OwnershipChange("chown games:games debian/foo/var/games/foo".to_string()),
+            ],
+            analysis.blockers
+        );
+
+        let td = setup(
+            "#!/usr/bin/make -f\ninclude /usr/share/cdbs/1/rules/debhelper.mk\n",
+            CONTROL,
+        );
+        assert_eq!(vec![Blocker::Cdbs], analyze(td.path()).unwrap().blockers);
+    }
+}
@@ -0,0 +1,285 @@
+//! Silver-platter compatible result reports.
+//!
+//! Binaries can write a JSON report describing their outcome, either to a
+//! file given on the command line or, when running as a silver-platter
+//! worker, to the file named by `SVP_RESULT`. With `--json`, the report is
+//! also printed on standard output, for use in scripted pipelines.
+use crate::detect_gbp_dch::ChangelogBehaviour;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable set by silver-platter to the supported API version.
+pub const SVP_API_ENV: &str = "SVP_API";
+
+/// Environment variable set by silver-platter to the path the result should be written to.
+pub const SVP_RESULT_ENV: &str = "SVP_RESULT";
+
+/// Result code reported when there were no changes to make.
+pub const NOTHING_TO_DO: &str = "nothing-to-do";
+
+/// Debian-specific context of a report.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize)]
+pub struct DebianContext {
+    /// How debian/changelog is updated in the branch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<ChangelogBehaviour>,
+}
+
+/// A report of changes that were made successfully.
+#[derive(Debug, Clone, PartialEq, Default, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Success {
+    /// Versions of the software involved
+    pub versions: HashMap<String, String>,
+    /// Value of the changes, used by silver-platter to prioritize them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<i32>,
+    /// Description of the changes that were made
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Context describing the changes, e.g. for use in merge proposal descriptions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Value>,
+    /// Debian-specific context
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debian: Option<DebianContext>,
+    /// URL of the branch the changes were made for or pushed to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_branch_url: Option<url::Url>,
+    /// Commit message to use, or that was used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+}
+
+/// A report of a failure, or of there being nothing to do.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize)]
+pub struct Failure {
+    /// Machine-readable code for the failure
+    pub result_code: String,
+    /// Versions of the software involved
+    pub versions: HashMap<String, String>,
+    /// Human-readable description of the failure
+    pub description: String,
+    /// Whether the failure is transient, and retrying may help
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transient: Option<bool>,
+}
+
+/// A result report.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(untagged)]
+pub enum Report {
+    /// The changes were made successfully
+    Success(Success),
+    /// The changes could not be made
+    Failure(Failure),
+}
+
+/// Versions to include in reports.
+pub fn versions() -> HashMap<String, String> {
+    maplit::hashmap! {
+        "debian-workbench".to_string() => env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+impl Success {
+    /// Create a success report describing a set of changes.
+    ///
+    /// # Arguments
+    /// * `description` - Description of the changes
+    /// * `changes` - The individual changes, included in the context
+    pub fn new(description: &str, changes: &[String]) -> Self {
+        Success {
+            versions: versions(),
+            description: Some(description.to_string()),
+            context: Some(serde_json::json!({ "changes": changes })),
+            commit_message: Some(description.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+impl Failure {
+    /// Create a failure report.
+    ///
+    /// # Arguments
+    /// * `result_code` - Machine-readable code for the failure
+    /// * `description` - Human-readable description
+    pub fn new(result_code: &str, description: &str) -> Self {
+        Failure {
+            result_code: result_code.to_string(),
+            versions: versions(),
+            description: description.to_string(),
+            transient: None,
+        }
+    }
+
+    /// Create a report for there being nothing to do.
+    pub fn nothing_to_do(description: &str) -> Self {
+        Self::new(NOTHING_TO_DO, description)
+    }
+}
+
+impl From<Success> for Report {
+    fn from(s: Success) -> Self {
+        Report::Success(s)
+    }
+}
+
+impl From<Failure> for Report {
+    fn from(f: Failure) -> Self {
+        Report::Failure(f)
+    }
+}
+
+/// Check whether we are running as a silver-platter worker.
+pub fn running_under_svp() -> bool {
+    std::env::var(SVP_API_ENV).is_ok_and(|v| v == "1")
+}
+
+/// Determine where the report should be written.
+///
+/// # Arguments
+/// * `explicit` - Path given on the command line, which takes precedence
+///
+/// # Returns
+/// The path, or `None` if no report was requested
+pub fn report_path(explicit: Option<&Path>) -> Option<PathBuf> {
+    if let Some(path) = explicit {
+        return Some(path.to_path_buf());
+    }
+    if !running_under_svp() {
+        return None;
+    }
+    std::env::var_os(SVP_RESULT_ENV).map(PathBuf::from)
+}
+
+impl Report {
+    /// Write the report as JSON.
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let f = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(f, self)?;
+        Ok(())
+    }
+
+    /// Render the report as JSON on a single line.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// Write a report, if one was requested.
+///
+/// # Arguments
+/// * `explicit` - Path given on the command line, if any
+/// * `report` - The report to write
+pub fn write_report(explicit: Option<&Path>, report: impl Into<Report>) -> std::io::Result<()> {
+    match report_path(explicit) {
+        Some(path) => report.into().write(&path),
+        None => Ok(()),
+    }
+}
+
+/// Destination for the reports of a binary.
+#[derive(Debug, Clone, Default)]
+pub struct Reporter {
+    path: Option<PathBuf>,
+    json: bool,
+}
+
+impl Reporter {
+    /// Create a new reporter.
+    ///
+    /// # Arguments
+    /// * `explicit` - Path given on the command line, if any
+    /// * `json` - Whether to print reports on standard output
+    pub fn new(explicit: Option<&Path>, json: bool) -> Self {
+        Reporter {
+            path: report_path(explicit),
+            json,
+        }
+    }
+
+    /// Whether reports are printed on standard output, in place of human-readable output.
+    pub fn json(&self) -> bool {
+        self.json
+    }
+
+    /// Write a report to the report file and standard output, as requested.
+    ///
+    /// # Arguments
+    /// * `report` - The report to write
+    pub fn report(&self, report: impl Into<Report>) -> std::io::Result<()> {
+        let report = report.into();
+        if let Some(path) = self.path.as_deref() {
+            report.write(path)?;
+        }
+        if self.json {
+            println!("{}", report.to_json());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let mut success = Success::new(
+            "Bump Standards-Version to 4.7.0.",
+            &["Bump Standards-Version to 4.7.0.".to_string()],
+        );
+        success.versions = HashMap::new();
+        success.target_branch_url = Some("https://salsa.debian.org/foo/bar".parse().unwrap());
+        assert_eq!(
+            serde_json::json!({
+                "versions": {},
+                "description": "Bump Standards-Version to 4.7.0.",
+                "context": {"changes": ["Bump Standards-Version to 4.7.0."]},
+                "target-branch-url": "https://salsa.debian.org/foo/bar",
+                "commit-message": "Bump Standards-Version to 4.7.0.",
+            }),
+            serde_json::to_value(Report::from(success)).unwrap()
+        );
+        let mut failure = Failure::nothing_to_do("Already up to date");
+        failure.versions = HashMap::new();
+        assert_eq!(
+            serde_json::json!({
+                "result_code": "nothing-to-do",
+                "versions": {},
+                "description": "Already up to date",
+            }),
+            serde_json::to_value(Report::from(failure)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_report() {
+        let td = tempfile::tempdir().unwrap();
+        let path = td.path().join("result.json");
+        write_report(
+            Some(&path),
+            Failure::new("unknown-release", "Unknown release"),
+        )
+        .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!("unknown-release", written["result_code"]);
+    }
+
+    #[test]
+    fn test_reporter() {
+        let td = tempfile::tempdir().unwrap();
+        let path = td.path().join("result.json");
+        let reporter = Reporter::new(Some(&path), false);
+        assert!(!reporter.json());
+        reporter
+            .report(Failure::nothing_to_do("Nothing to do"))
+            .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!("nothing-to-do", written["result_code"]);
+    }
+}
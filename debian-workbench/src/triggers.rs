@@ -0,0 +1,420 @@
+//! Parsing, validation and editing of debian/*.triggers files.
+
+/// A directive in a triggers file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Directive {
+    /// Interest in a trigger, awaiting it implicitly
+    Interest,
+    /// Interest in a trigger, awaiting it
+    InterestAwait,
+    /// Interest in a trigger, without awaiting it
+    InterestNoawait,
+    /// Activation of a trigger, awaiting it implicitly
+    Activate,
+    /// Activation of a trigger, awaiting it
+    ActivateAwait,
+    /// Activation of a trigger, without awaiting it
+    ActivateNoawait,
+}
+
+impl Directive {
+    /// Whether this directive declares interest in a trigger, rather than activating it.
+    pub fn is_interest(&self) -> bool {
+        matches!(
+            self,
+            Directive::Interest | Directive::InterestAwait | Directive::InterestNoawait
+        )
+    }
+
+    /// Whether the triggering package awaits processing of the trigger.
+    pub fn awaits(&self) -> bool {
+        !matches!(
+            self,
+            Directive::InterestNoawait | Directive::ActivateNoawait
+        )
+    }
+
+    /// Whether the directive relies on the implicit default of awaiting the trigger.
+    pub fn is_implicit_await(&self) -> bool {
+        matches!(self, Directive::Interest | Directive::Activate)
+    }
+
+    /// The equivalent directive with the given await behaviour, spelled out explicitly.
+    pub fn with_await(&self, awaits: bool) -> Directive {
+        match (self.is_interest(), awaits) {
+            (true, true) => Directive::InterestAwait,
+            (true, false) => Directive::InterestNoawait,
+            (false, true) => Directive::ActivateAwait,
+            (false, false) => Directive::ActivateNoawait,
+        }
+    }
+}
+
+impl std::fmt::Display for Directive {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Directive::Interest => "interest",
+            Directive::InterestAwait => "interest-await",
+            Directive::InterestNoawait => "interest-noawait",
+            Directive::Activate => "activate",
+            Directive::ActivateAwait => "activate-await",
+            Directive::ActivateNoawait => "activate-noawait",
+        })
+    }
+}
+
+impl std::str::FromStr for Directive {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "interest" => Ok(Directive::Interest),
+            "interest-await" => Ok(Directive::InterestAwait),
+            "interest-noawait" => Ok(Directive::InterestNoawait),
+            "activate" => Ok(Directive::Activate),
+            "activate-await" => Ok(Directive::ActivateAwait),
+            "activate-noawait" => Ok(Directive::ActivateNoawait),
+            _ => Err(ParseError::UnknownDirective(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+/// An error that occurred while parsing a triggers file
+pub enum ParseError {
+    /// An unknown directive
+    UnknownDirective(String),
+    /// A directive without a trigger name
+    MissingTriggerName(String),
+    /// Unexpected text after the trigger name
+    TrailingData(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownDirective(directive) => {
+                write!(f, "Unknown trigger directive: {}", directive)
+            }
+            ParseError::MissingTriggerName(line) => {
+                write!(f, "Missing trigger name: {}", line)
+            }
+            ParseError::TrailingData(line) => {
+                write!(f, "Unexpected data after trigger name: {}", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A single trigger directive and the name of the trigger it applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Trigger {
+    /// The directive
+    pub directive: Directive,
+    /// Name of the trigger; file triggers are absolute paths
+    pub name: String,
+}
+
+impl Trigger {
+    /// Create a new trigger directive
+    pub fn new(directive: Directive, name: &str) -> Self {
+        Trigger {
+            directive,
+            name: name.to_string(),
+        }
+    }
+
+    /// Whether this is a file trigger, rather than an explicit trigger.
+    pub fn is_file_trigger(&self) -> bool {
+        self.name.starts_with('/')
+    }
+}
+
+impl std::fmt::Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.directive, self.name)
+    }
+}
+
+impl std::str::FromStr for Trigger {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let directive = parts
+            .next()
+            .ok_or_else(|| ParseError::MissingTriggerName(s.to_string()))?
+            .parse()?;
+        let name = parts
+            .next()
+            .ok_or_else(|| ParseError::MissingTriggerName(s.to_string()))?;
+        if parts.next().is_some() {
+            return Err(ParseError::TrailingData(s.to_string()));
+        }
+        Ok(Trigger::new(directive, name))
+    }
+}
+
+/// A problem with a triggers file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerIssue {
+    /// The directive does not say whether the trigger is awaited
+    ImplicitAwait(Trigger),
+    /// The same trigger is listed more than once
+    Duplicate(Trigger),
+    /// The trigger name is not valid
+    InvalidName(String),
+}
+
+impl std::fmt::Display for TriggerIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TriggerIssue::ImplicitAwait(trigger) => {
+                write!(f, "{} implicitly awaits the trigger", trigger)
+            }
+            TriggerIssue::Duplicate(trigger) => write!(f, "{} is listed more than once", trigger),
+            TriggerIssue::InvalidName(name) => write!(f, "Invalid trigger name: {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A line in a triggers file
+enum Line {
+    /// A comment or blank line, kept verbatim
+    Verbatim(String),
+    /// A trigger, with the original text if it has not been changed
+    Trigger(Trigger, Option<String>),
+}
+
+/// A triggers file (debian/*.triggers)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TriggersFile {
+    lines: Vec<Line>,
+}
+
+impl TriggersFile {
+    /// Create a new, empty triggers file
+    pub fn new() -> Self {
+        TriggersFile { lines: vec![] }
+    }
+
+    /// Check if the file has no triggers
+    pub fn is_empty(&self) -> bool {
+        !self.lines.iter().any(|l| matches!(l, Line::Trigger(..)))
+    }
+
+    /// Iterate over the triggers in the file
+    pub fn triggers(&self) -> impl Iterator<Item = &Trigger> + '_ {
+        self.lines.iter().filter_map(|l| match l {
+            Line::Trigger(trigger, _) => Some(trigger),
+            Line::Verbatim(_) => None,
+        })
+    }
+
+    /// Check whether a trigger is present.
+    pub fn contains(&self, trigger: &Trigger) -> bool {
+        self.triggers().any(|t| t == trigger)
+    }
+
+    /// Add a trigger, unless it is already present.
+    ///
+    /// # Returns
+    /// Whether the trigger was added
+    pub fn add(&mut self, trigger: Trigger) -> bool {
+        if self.contains(&trigger) {
+            return false;
+        }
+        self.lines.push(Line::Trigger(trigger, None));
+        true
+    }
+
+    /// Remove all directives for the trigger with the given name.
+    ///
+    /// # Returns
+    /// Whether any directive was removed
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.lines.len();
+        self.lines
+            .retain(|l| !matches!(l, Line::Trigger(t, _) if t.name == name));
+        self.lines.len() != len
+    }
+
+    /// Change the directives of a trigger, leaving other triggers untouched.
+    fn update(&mut self, mut f: impl FnMut(&Trigger) -> Option<Directive>) -> usize {
+        let mut changed = 0;
+        for line in self.lines.iter_mut() {
+            let Line::Trigger(trigger, text) = line else {
+                continue;
+            };
+            if let Some(directive) = f(trigger) {
+                if directive != trigger.directive {
+                    trigger.directive = directive;
+                    *text = None;
+                    changed += 1;
+                }
+            }
+        }
+        changed
+    }
+
+    /// Set whether the trigger with the given name is awaited.
+    ///
+    /// # Returns
+    /// Number of directives that were changed
+    pub fn set_await(&mut self, name: &str, awaits: bool) -> usize {
+        self.update(|t| (t.name == name).then(|| t.directive.with_await(awaits)))
+    }
+
+    /// Spell out the await behaviour of directives that rely on the default.
+    ///
+    /// This does not change the behaviour of the triggers.
+    ///
+    /// # Returns
+    /// Number of directives that were changed
+    pub fn make_await_explicit(&mut self) -> usize {
+        self.update(|t| {
+            t.directive
+                .is_implicit_await()
+                .then(|| t.directive.with_await(true))
+        })
+    }
+
+    /// Check the file for problems.
+    pub fn validate(&self) -> Vec<TriggerIssue> {
+        let mut ret = vec![];
+        let mut seen = std::collections::HashSet::new();
+        for trigger in self.triggers() {
+            let valid_name = trigger.name.chars().all(|c| c.is_ascii_graphic())
+                && (trigger.is_file_trigger() || !trigger.name.contains('/'));
+            if !valid_name {
+                ret.push(TriggerIssue::InvalidName(trigger.name.clone()));
+            }
+            if trigger.directive.is_implicit_await() {
+                ret.push(TriggerIssue::ImplicitAwait(trigger.clone()));
+            }
+            if !seen.insert(trigger) {
+                ret.push(TriggerIssue::Duplicate(trigger.clone()));
+            }
+        }
+        ret
+    }
+}
+
+impl std::fmt::Display for TriggersFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Verbatim(text) | Line::Trigger(_, Some(text)) => writeln!(f, "{}", text)?,
+                Line::Trigger(trigger, None) => writeln!(f, "{}", trigger)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for TriggersFile {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s
+            .lines()
+            .map(|l| {
+                if l.trim_start().starts_with('#') || l.trim().is_empty() {
+                    Ok(Line::Verbatim(l.to_string()))
+                } else {
+                    Ok(Line::Trigger(l.parse()?, Some(l.to_string())))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TriggersFile { lines })
+    }
+}
+
+impl crate::editor::Marshallable for TriggersFile {
+    fn from_bytes(content: &[u8]) -> Self {
+        std::str::from_utf8(content).unwrap().parse().unwrap()
+    }
+
+    fn empty() -> Self {
+        TriggersFile::new()
+    }
+
+    fn to_bytes(&self) -> Option<Vec<u8>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.to_string().into_bytes())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "# Rebuild the cache\ninterest-noawait /usr/share/foo\nactivate  ldconfig\n";
+        let triggers: TriggersFile = text.parse().unwrap();
+        assert_eq!(text, triggers.to_string());
+        assert_eq!(
+            vec![
+                Trigger::new(Directive::InterestNoawait, "/usr/share/foo"),
+                Trigger::new(Directive::Activate, "ldconfig"),
+            ],
+            triggers.triggers().cloned().collect::<Vec<_>>()
+        );
+        assert!(triggers.triggers().next().unwrap().is_file_trigger());
+        assert_eq!(
+            Err(ParseError::UnknownDirective("activate-later".to_string())),
+            "activate-later foo\n".parse::<TriggersFile>()
+        );
+        assert_eq!(
+            Err(ParseError::MissingTriggerName("interest".to_string())),
+            "interest\n".parse::<TriggersFile>()
+        );
+        assert_eq!(
+            Err(ParseError::TrailingData("interest foo bar".to_string())),
+            "interest foo bar\n".parse::<TriggersFile>()
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let triggers: TriggersFile =
+            "interest foo\nactivate-noawait foo/bar\nactivate-noawait foo/bar\n"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            vec![
+                TriggerIssue::ImplicitAwait(Trigger::new(Directive::Interest, "foo")),
+                TriggerIssue::InvalidName("foo/bar".to_string()),
+                TriggerIssue::InvalidName("foo/bar".to_string()),
+                TriggerIssue::Duplicate(Trigger::new(Directive::ActivateNoawait, "foo/bar")),
+            ],
+            triggers.validate()
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut triggers: TriggersFile = "# comment\nactivate ldconfig\ninterest /usr/share/foo\n"
+            .parse()
+            .unwrap();
+        assert_eq!(1, triggers.set_await("ldconfig", false));
+        assert_eq!(0, triggers.set_await("ldconfig", false));
+        assert_eq!(1, triggers.make_await_explicit());
+        assert!(triggers.add(Trigger::new(Directive::ActivateNoawait, "update-foo")));
+        assert!(!triggers.add(Trigger::new(Directive::ActivateNoawait, "update-foo")));
+        assert_eq!(
+            "# comment\nactivate-noawait ldconfig\ninterest-await /usr/share/foo\nactivate-noawait update-foo\n",
+            triggers.to_string()
+        );
+        assert!(triggers.remove("ldconfig"));
+        assert!(!triggers.remove("ldconfig"));
+        assert!(triggers.validate().is_empty());
+    }
+}
@@ -0,0 +1,1678 @@
+//! Functions for working with DEP-12 upstream metadata in debian/upstream/metadata.
+use crate::watch::UpstreamSource;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Path of the upstream metadata file, relative to the root of the source tree.
+pub const UPSTREAM_METADATA_PATH: &str = "debian/upstream/metadata";
+
+/// A top-level field, or a run of lines that do not belong to one (comments,
+/// document markers, blank lines).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Block {
+    key: Option<String>,
+    text: String,
+}
+
+/// DEP-12 upstream metadata.
+///
+/// Only top-level scalar fields can be read and changed; everything else,
+/// including comments and nested values, is preserved as is.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UpstreamMetadata {
+    blocks: Vec<Block>,
+}
+
+/// Strip YAML quotes from a scalar value.
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.len() >= 2 {
+        if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            return inner.replace("''", "'");
+        }
+        if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            return inner.replace("\\\"", "\"");
+        }
+    }
+    // Strip trailing comments
+    match value.find(" #") {
+        Some(i) => value[..i].trim_end().to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// Quote a scalar value if YAML would not read it back as the same string.
+fn quote(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.trim() != value
+        || value.starts_with(|c| "'\"[]{}&*!|>%@`#,?:-".contains(c))
+        || value.contains(": ")
+        || value.contains(" #");
+    if needs_quotes {
+        format!("'{}'", value.replace('\'', "''"))
+    } else {
+        value.to_string()
+    }
+}
+
+impl FromStr for UpstreamMetadata {
+    type Err = std::convert::Infallible;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut blocks: Vec<Block> = vec![];
+        for line in text.split_inclusive('\n') {
+            let continuation = line.starts_with([' ', '\t'])
+                || (line.starts_with('-') && !line.starts_with("---"));
+            if continuation {
+                if let Some(last) = blocks.last_mut().filter(|b| b.key.is_some()) {
+                    last.text.push_str(line);
+                    continue;
+                }
+            }
+            let key = if line.starts_with('#') || continuation {
+                None
+            } else {
+                line.split_once(':')
+                    .map(|(k, _)| k.trim().to_string())
+                    .filter(|k| !k.is_empty())
+            };
+            blocks.push(Block {
+                key,
+                text: line.to_string(),
+            });
+        }
+        Ok(UpstreamMetadata { blocks })
+    }
+}
+
+impl std::fmt::Display for UpstreamMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for block in &self.blocks {
+            f.write_str(&block.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl UpstreamMetadata {
+    /// Read the upstream metadata of a source tree.
+    ///
+    /// # Arguments
+    /// * `base` - Root of the source tree
+    ///
+    /// # Returns
+    /// The metadata, which is empty if the file does not exist
+    pub fn read(base: &Path) -> std::io::Result<Self> {
+        match std::fs::read_to_string(base.join(UPSTREAM_METADATA_PATH)) {
+            Ok(text) => Ok(text.parse().unwrap()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the upstream metadata to a source tree.
+    ///
+    /// # Arguments
+    /// * `base` - Root of the source tree
+    pub fn write(&self, base: &Path) -> std::io::Result<()> {
+        let path = base.join(UPSTREAM_METADATA_PATH);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, self.to_string())
+    }
+
+    /// Names of the top-level fields, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.blocks.iter().filter_map(|b| b.key.as_deref())
+    }
+
+    /// Check whether a top-level field is present.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.keys().any(|k| k == key)
+    }
+
+    /// Get the value of a top-level scalar field.
+    ///
+    /// # Returns
+    /// The value, or None if the field is missing or is not a scalar
+    pub fn get(&self, key: &str) -> Option<String> {
+        let block = self.blocks.iter().find(|b| b.key.as_deref() == Some(key))?;
+        let first_line = block.text.lines().next()?;
+        let (_, value) = first_line.split_once(':')?;
+        Some(unquote(value)).filter(|v| !v.is_empty())
+    }
+
+    /// Set a top-level field to a scalar value, adding it if it is missing.
+    pub fn set(&mut self, key: &str, value: &str) {
+        let text = format!("{}: {}\n", key, quote(value));
+        if let Some(block) = self
+            .blocks
+            .iter_mut()
+            .find(|b| b.key.as_deref() == Some(key))
+        {
+            block.text = text;
+            return;
+        }
+        if let Some(last) = self.blocks.last_mut() {
+            if !last.text.ends_with('\n') {
+                last.text.push('\n');
+            }
+        }
+        self.blocks.push(Block {
+            key: Some(key.to_string()),
+            text,
+        });
+    }
+
+    /// Remove a top-level field.
+    ///
+    /// # Returns
+    /// Whether the field was present
+    pub fn remove(&mut self, key: &str) -> bool {
+        let len = self.blocks.len();
+        self.blocks.retain(|b| b.key.as_deref() != Some(key));
+        self.blocks.len() != len
+    }
+
+    /// Rename a top-level field, preserving its value.
+    ///
+    /// # Returns
+    /// Whether the field was present
+    pub fn rename(&mut self, old: &str, new: &str) -> bool {
+        let Some(block) = self
+            .blocks
+            .iter_mut()
+            .find(|b| b.key.as_deref() == Some(old))
+        else {
+            return false;
+        };
+        let (_, rest) = block.text.split_once(':').unwrap();
+        block.text = format!("{}:{}", new, rest);
+        block.key = Some(new.to_string());
+        true
+    }
+}
+
+/// Top-level fields defined by DEP-12.
+pub const DEP12_FIELDS: &[&str] = &[
+    "Archive",
+    "ASCL-Id",
+    "Bug-Database",
+    "Bug-Submit",
+    "Cite-As",
+    "Changelog",
+    "CPE",
+    "Documentation",
+    "Donation",
+    "FAQ",
+    "Funding",
+    "Gallery",
+    "Other-References",
+    "Reference",
+    "Registration",
+    "Registry",
+    "Repository",
+    "Repository-Browse",
+    "Screenshots",
+    "Security-Contact",
+    "Webservice",
+];
+
+/// Fields that were once part of DEP-12, but have since been dropped.
+pub const OBSOLETE_DEP12_FIELDS: &[&str] = &["Contact", "Name"];
+
+/// Common misspellings of DEP-12 fields that are not caught by comparing
+/// spelling alone.
+const MISSPELLINGS: &[(&str, &str)] = &[
+    ("Bug-Tracker", "Bug-Database"),
+    ("Bugs", "Bug-Database"),
+    ("Issues", "Bug-Database"),
+    ("Repo", "Repository"),
+    ("Source", "Repository"),
+    ("Repository-Web", "Repository-Browse"),
+    ("Docs", "Documentation"),
+    ("Changes", "Changelog"),
+    ("Security", "Security-Contact"),
+];
+
+/// A problem with the name of a field in the upstream metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataKeyIssue {
+    /// The field looks like a misspelling of a DEP-12 field.
+    Misspelled {
+        /// Name of the field
+        key: String,
+        /// The DEP-12 field that was probably intended
+        suggestion: String,
+    },
+    /// The field is no longer part of DEP-12.
+    Obsolete(String),
+    /// The field is not part of DEP-12.
+    Unknown(String),
+}
+
+impl std::fmt::Display for MetadataKeyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataKeyIssue::Misspelled { key, suggestion } => {
+                write!(f, "Unknown field {}, did you mean {}?", key, suggestion)
+            }
+            MetadataKeyIssue::Obsolete(key) => write!(f, "Obsolete field {}", key),
+            MetadataKeyIssue::Unknown(key) => write!(f, "Unknown field {}", key),
+        }
+    }
+}
+
+/// Number of single character edits needed to turn one string into another.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Find the DEP-12 field that an unknown field name was probably meant to be.
+fn suggest_field(key: &str) -> Option<&'static str> {
+    if let Some((_, field)) = MISSPELLINGS
+        .iter()
+        .find(|(m, _)| m.eq_ignore_ascii_case(key))
+    {
+        return Some(field);
+    }
+    let normalize = |s: &str| s.to_ascii_lowercase().replace(['-', '_'], "");
+    let key = normalize(key);
+    DEP12_FIELDS
+        .iter()
+        .map(|field| (edit_distance(&key, &normalize(field)), *field))
+        .filter(|(distance, field)| *distance <= 2.min(field.len() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, field)| field)
+}
+
+/// Check the field names in upstream metadata against the DEP-12 vocabulary.
+///
+/// Fields starting with "X-" are assumed to be deliberate extensions.
+///
+/// # Arguments
+/// * `metadata` - Metadata to check
+///
+/// # Returns
+/// The issues that were found
+pub fn check_keys(metadata: &UpstreamMetadata) -> Vec<MetadataKeyIssue> {
+    metadata
+        .keys()
+        .filter(|key| !DEP12_FIELDS.contains(key) && !key.starts_with("X-"))
+        .map(|key| {
+            if OBSOLETE_DEP12_FIELDS.contains(&key) {
+                MetadataKeyIssue::Obsolete(key.to_string())
+            } else if let Some(suggestion) = suggest_field(key) {
+                MetadataKeyIssue::Misspelled {
+                    key: key.to_string(),
+                    suggestion: suggestion.to_string(),
+                }
+            } else {
+                MetadataKeyIssue::Unknown(key.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Rename misspelled fields to the DEP-12 fields they were meant to be.
+///
+/// Fields are not renamed if the intended field is already present.
+///
+/// # Arguments
+/// * `metadata` - Metadata to fix
+///
+/// # Returns
+/// The old and new names of the fields that were renamed
+pub fn fix_keys(metadata: &mut UpstreamMetadata) -> Vec<(String, String)> {
+    let mut ret = vec![];
+    for issue in check_keys(metadata) {
+        if let MetadataKeyIssue::Misspelled { key, suggestion } = issue {
+            if !metadata.contains_key(&suggestion) && metadata.rename(&key, &suggestion) {
+                ret.push((key, suggestion));
+            }
+        }
+    }
+    ret
+}
+
+/// Where a URL referring to the upstream project was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlLocation {
+    /// The Homepage field in debian/control
+    Homepage,
+    /// The Repository field in the upstream metadata
+    Repository,
+    /// The Repository-Browse field in the upstream metadata
+    RepositoryBrowse,
+    /// An entry in debian/watch
+    Watch,
+    /// The Vcs-Git field in debian/control
+    VcsGit,
+    /// The Vcs-Browser field in debian/control
+    VcsBrowser,
+}
+
+impl std::fmt::Display for UrlLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlLocation::Homepage => write!(f, "Homepage"),
+            UrlLocation::Repository => write!(f, "Repository"),
+            UrlLocation::RepositoryBrowse => write!(f, "Repository-Browse"),
+            UrlLocation::Watch => write!(f, "debian/watch"),
+            UrlLocation::VcsGit => write!(f, "Vcs-Git"),
+            UrlLocation::VcsBrowser => write!(f, "Vcs-Browser"),
+        }
+    }
+}
+
+/// A URL and where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedUrl {
+    /// Where the URL was found
+    pub location: UrlLocation,
+    /// The URL
+    pub url: String,
+}
+
+impl LocatedUrl {
+    /// The forge repository the URL refers to, ignoring case.
+    fn forge_source(&self) -> Option<UpstreamSource> {
+        UpstreamSource::from_repository_url(&self.url.to_lowercase())
+    }
+}
+
+/// A contradiction between the URLs that describe the upstream project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsistencyIssue {
+    /// Two URLs refer to different repositories on a forge.
+    Contradiction(LocatedUrl, LocatedUrl),
+    /// An upstream URL refers to the packaging repository.
+    PackagingRepository(LocatedUrl),
+}
+
+impl std::fmt::Display for ConsistencyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsistencyIssue::Contradiction(a, b) => write!(
+                f,
+                "{} ({}) and {} ({}) refer to different repositories",
+                a.location, a.url, b.location, b.url
+            ),
+            ConsistencyIssue::PackagingRepository(u) => write!(
+                f,
+                "{} ({}) refers to the packaging repository",
+                u.location, u.url
+            ),
+        }
+    }
+}
+
+/// Collect the URLs referring to the upstream project.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `editor` - Editor for the package's control file
+///
+/// # Returns
+/// The URLs, with the upstream metadata first
+pub fn upstream_urls(
+    base: &Path,
+    editor: &mut dyn crate::abstract_control::AbstractControlEditor,
+) -> std::io::Result<Vec<LocatedUrl>> {
+    let metadata = UpstreamMetadata::read(base)?;
+    let mut ret = vec![];
+    let mut push = |location, url: Option<String>| {
+        if let Some(url) = url {
+            ret.push(LocatedUrl { location, url });
+        }
+    };
+    push(UrlLocation::Repository, metadata.get("Repository"));
+    push(
+        UrlLocation::RepositoryBrowse,
+        metadata.get("Repository-Browse"),
+    );
+    if let Some(source) = editor.source() {
+        push(UrlLocation::Homepage, source.homepage());
+        push(UrlLocation::VcsGit, source.get_vcs_url("Git"));
+        push(UrlLocation::VcsBrowser, source.get_vcs_url("Browser"));
+    }
+    match std::fs::read_to_string(base.join("debian/watch")) {
+        Ok(text) => {
+            if let Ok(wf) = text.parse::<crate::watch::WatchFile>() {
+                for entry in wf.entries() {
+                    push(UrlLocation::Watch, entry.url());
+                }
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    Ok(ret)
+}
+
+/// Find contradictions between the URLs referring to the upstream project.
+///
+/// Only URLs on a known forge are compared; a Homepage pointing at a project
+/// website is not a contradiction.
+///
+/// # Arguments
+/// * `urls` - URLs, as returned by [`upstream_urls`]
+///
+/// # Returns
+/// The issues that were found
+pub fn check_consistency(urls: &[LocatedUrl]) -> Vec<ConsistencyIssue> {
+    let is_packaging =
+        |u: &&LocatedUrl| matches!(u.location, UrlLocation::VcsGit | UrlLocation::VcsBrowser);
+    let packaging = urls
+        .iter()
+        .filter(is_packaging)
+        .filter_map(|u| u.forge_source())
+        .collect::<Vec<_>>();
+    let mut ret = vec![];
+    let mut canonical: Option<(&LocatedUrl, UpstreamSource)> = None;
+    for url in urls.iter().filter(|u| !is_packaging(u)) {
+        let Some(source) = url.forge_source() else {
+            continue;
+        };
+        if packaging.contains(&source) {
+            ret.push(ConsistencyIssue::PackagingRepository(url.clone()));
+            continue;
+        }
+        match canonical.as_ref() {
+            None => canonical = Some((url, source)),
+            Some((first, first_source)) if *first_source != source => {
+                ret.push(ConsistencyIssue::Contradiction(
+                    (*first).clone(),
+                    url.clone(),
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    ret
+}
+
+/// Rewrite a URL on a forge to refer to another repository.
+///
+/// # Arguments
+/// * `url` - URL of, or within, a repository on a forge
+/// * `new` - The repository the URL should refer to
+///
+/// # Returns
+/// The rewritten URL, or None if the URL is not on a forge or already refers
+/// to `new`
+pub fn rewrite_repository_url(url: &str, new: &UpstreamSource) -> Option<String> {
+    let new_web_url = new.web_url()?;
+    let old_web_url = UpstreamSource::from_repository_url(url)?.web_url()?;
+    if old_web_url.eq_ignore_ascii_case(&new_web_url) {
+        return None;
+    }
+    let prefix = url.get(..old_web_url.len())?;
+    if !prefix.eq_ignore_ascii_case(&old_web_url) {
+        return None;
+    }
+    Some(format!("{}{}", new_web_url, &url[old_web_url.len()..]))
+}
+
+/// Point all references to the upstream repository at the canonical one.
+///
+/// The Repository and Repository-Browse fields are set, and the Homepage field
+/// and watch entries are updated if they refer to a different repository on a
+/// forge. The control file is not committed.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `editor` - Editor for the package's control file
+/// * `canonical` - The canonical upstream repository
+///
+/// # Returns
+/// The locations that were changed
+pub fn propagate_upstream_repository(
+    base: &Path,
+    editor: &mut dyn crate::abstract_control::AbstractControlEditor,
+    canonical: &UpstreamSource,
+) -> std::io::Result<Vec<UrlLocation>> {
+    let Some(web_url) = canonical.web_url() else {
+        return Ok(vec![]);
+    };
+    let differs = |url: &str| {
+        UpstreamSource::from_repository_url(&url.to_lowercase())
+            .is_some_and(|s| s.web_url().map(|u| u.to_lowercase()) != Some(web_url.to_lowercase()))
+    };
+    let mut ret = vec![];
+
+    let mut metadata = UpstreamMetadata::read(base)?;
+    let repository = format!("{}.git", web_url);
+    for (key, location, value) in [
+        ("Repository", UrlLocation::Repository, &repository),
+        ("Repository-Browse", UrlLocation::RepositoryBrowse, &web_url),
+    ] {
+        if metadata.get(key).is_none_or(|v| differs(&v)) {
+            metadata.set(key, value);
+            ret.push(location);
+        }
+    }
+    if !ret.is_empty() {
+        metadata.write(base)?;
+    }
+
+    if let Some(mut source) = editor.source() {
+        if source.homepage().is_some_and(|h| differs(&h)) {
+            source.set_homepage(&web_url);
+            ret.push(UrlLocation::Homepage);
+        }
+    }
+
+    let watch_path = base.join("debian/watch");
+    if let Ok(text) = std::fs::read_to_string(&watch_path) {
+        if let Ok(mut wf) = text.parse::<crate::watch::WatchFile>() {
+            let mut changed = false;
+            for entry in wf.entries_mut() {
+                let Some(url) = entry.url() else {
+                    continue;
+                };
+                if let Some(new_url) = rewrite_repository_url(&url, canonical) {
+                    entry.set_url(&new_url);
+                    changed = true;
+                }
+            }
+            if changed {
+                std::fs::write(&watch_path, wf.to_string())?;
+                ret.push(UrlLocation::Watch);
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Map a project URL label, as used in pyproject.toml and setup.cfg, to a
+/// DEP-12 field.
+fn field_for_url_label(label: &str) -> Option<&'static str> {
+    let label = label
+        .to_lowercase()
+        .replace(|c: char| !c.is_ascii_alphanumeric(), "");
+    match label.as_str() {
+        "source" | "sourcecode" | "repository" | "code" | "github" | "gitlab" | "git" => {
+            Some("Repository")
+        }
+        "bugtracker" | "issues" | "issuetracker" | "bugreports" | "tracker" | "bugs" => {
+            Some("Bug-Database")
+        }
+        "documentation" | "docs" => Some("Documentation"),
+        "changelog" | "changes" | "releasenotes" | "history" | "news" => Some("Changelog"),
+        "funding" | "donate" | "sponsor" => Some("Donation"),
+        _ => None,
+    }
+}
+
+/// Turn the repository URLs used by package managers into plain URLs.
+fn normalize_repository_url(url: &str) -> String {
+    let url = url.trim();
+    let url = url.strip_prefix("git+").unwrap_or(url);
+    if let Some(path) = url.strip_prefix("github:") {
+        return format!("https://github.com/{}", path);
+    }
+    if let Some(path) = url.strip_prefix("gitlab:") {
+        return format!("https://gitlab.com/{}", path);
+    }
+    if !url.contains(':') && url.matches('/').count() == 1 {
+        // npm shorthand for GitHub repositories
+        return format!("https://github.com/{}", url);
+    }
+    match url.strip_prefix("git://") {
+        Some(rest) if rest.starts_with("github.com/") => format!("https://{}", rest),
+        _ => url.to_string(),
+    }
+}
+
+/// Parser extracting DEP-12 fields from the contents of a manifest.
+type ManifestParser = fn(&str) -> Vec<(&'static str, String)>;
+
+/// DEP-12 fields from the package section of a Cargo.toml file.
+fn cargo_manifest_metadata(text: &str) -> Vec<(&'static str, String)> {
+    let Ok(doc) = text.parse::<toml_edit::DocumentMut>() else {
+        return vec![];
+    };
+    let mut ret = vec![];
+    for (key, field) in [
+        ("repository", "Repository"),
+        ("documentation", "Documentation"),
+    ] {
+        if let Some(value) = doc
+            .get("package")
+            .and_then(|p| p.get(key))
+            .and_then(|v| v.as_str())
+        {
+            ret.push((field, value.to_string()));
+        }
+    }
+    ret
+}
+
+/// DEP-12 fields from the project URLs in a pyproject.toml file.
+fn pyproject_metadata(text: &str) -> Vec<(&'static str, String)> {
+    let Ok(doc) = text.parse::<toml_edit::DocumentMut>() else {
+        return vec![];
+    };
+    let mut ret = vec![];
+    if let Some(urls) = doc
+        .get("project")
+        .and_then(|p| p.get("urls"))
+        .and_then(|u| u.as_table_like())
+    {
+        for (label, value) in urls.iter() {
+            if let (Some(field), Some(value)) = (field_for_url_label(label), value.as_str()) {
+                ret.push((field, value.to_string()));
+            }
+        }
+    }
+    if let Some(poetry) = doc.get("tool").and_then(|t| t.get("poetry")) {
+        for (key, field) in [
+            ("repository", "Repository"),
+            ("documentation", "Documentation"),
+        ] {
+            if let Some(value) = poetry.get(key).and_then(|v| v.as_str()) {
+                ret.push((field, value.to_string()));
+            }
+        }
+    }
+    ret
+}
+
+/// DEP-12 fields from the project_urls option in a setup.cfg file.
+fn setup_cfg_metadata(text: &str) -> Vec<(&'static str, String)> {
+    let mut ret = vec![];
+    let mut section = "";
+    let mut in_project_urls = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            section = &trimmed[1..trimmed.len() - 1];
+            in_project_urls = false;
+            continue;
+        }
+        if section != "metadata" || trimmed.is_empty() || trimmed.starts_with(['#', ';']) {
+            continue;
+        }
+        if !line.starts_with([' ', '\t']) {
+            let key = line.split(['=', ':']).next().unwrap().trim();
+            in_project_urls = key == "project_urls";
+            continue;
+        }
+        if in_project_urls {
+            if let Some((label, url)) = trimmed.split_once('=') {
+                if let Some(field) = field_for_url_label(label) {
+                    ret.push((field, url.trim().to_string()));
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// DEP-12 fields from a package.json file.
+fn package_json_metadata(text: &str) -> Vec<(&'static str, String)> {
+    let Ok(data) = serde_json::from_str::<serde_json::Value>(text) else {
+        return vec![];
+    };
+    // Both fields can either be a string or an object with a url.
+    let url = |v: &serde_json::Value| {
+        v.as_str()
+            .or_else(|| v["url"].as_str())
+            .map(|s| s.to_string())
+    };
+    let mut ret = vec![];
+    if let Some(repository) = url(&data["repository"]) {
+        ret.push(("Repository", repository));
+    }
+    if let Some(bugs) = url(&data["bugs"]) {
+        ret.push(("Bug-Database", bugs));
+    }
+    if let Some(funding) = url(&data["funding"]) {
+        ret.push(("Donation", funding));
+    }
+    ret
+}
+
+/// Derive DEP-12 fields from the package manager manifests in an upstream
+/// source tree.
+///
+/// Cargo.toml, pyproject.toml, setup.cfg and package.json are supported.
+/// If several manifests provide the same field, the first one wins.
+///
+/// # Arguments
+/// * `upstream_dir` - Root of the upstream source
+///
+/// # Returns
+/// Field names and values
+pub fn metadata_from_manifests(
+    upstream_dir: &Path,
+) -> std::io::Result<Vec<(&'static str, String)>> {
+    let parsers: [(&str, ManifestParser); 4] = [
+        ("Cargo.toml", cargo_manifest_metadata),
+        ("pyproject.toml", pyproject_metadata),
+        ("setup.cfg", setup_cfg_metadata),
+        ("package.json", package_json_metadata),
+    ];
+    let mut ret: Vec<(&'static str, String)> = vec![];
+    for (name, parser) in parsers {
+        let text = match std::fs::read_to_string(upstream_dir.join(name)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for (field, value) in parser(&text) {
+            let value = if field == "Repository" {
+                normalize_repository_url(&value)
+            } else {
+                value
+            };
+            if !value.is_empty() && !ret.iter().any(|(f, _)| *f == field) {
+                ret.push((field, value));
+            }
+        }
+    }
+    if let Some(browse) = ret
+        .iter()
+        .find(|(f, _)| *f == "Repository")
+        .and_then(|(_, url)| UpstreamSource::from_repository_url(url))
+        .and_then(|s| s.web_url())
+    {
+        ret.push(("Repository-Browse", browse));
+    }
+    Ok(ret)
+}
+
+/// Add upstream metadata derived from the package manager manifests in the
+/// source tree.
+///
+/// Fields that are already present are left alone.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// Names of the fields that were added
+pub fn import_from_manifests(base: &Path) -> std::io::Result<Vec<String>> {
+    let fields = metadata_from_manifests(base)?;
+    let mut metadata = UpstreamMetadata::read(base)?;
+    let added = populate(&mut metadata, &fields);
+    if !added.is_empty() {
+        metadata.write(base)?;
+    }
+    Ok(added)
+}
+
+/// Information about an upstream repository, as reported by its forge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRepository {
+    /// URL of the web view of the repository
+    pub web_url: String,
+    /// Name of the default branch
+    pub default_branch: Option<String>,
+    /// Whether the issue tracker of the forge is enabled
+    pub has_issues: bool,
+    /// Whether the repository has been archived
+    pub archived: bool,
+    /// Names of the files in the root of the repository
+    pub files: Vec<String>,
+}
+
+impl ForgeRepository {
+    /// Interpret the responses of a forge API.
+    ///
+    /// # Arguments
+    /// * `source` - The upstream source the responses are for
+    /// * `repository` - Response describing the repository
+    /// * `files` - Response listing the root directory of the repository
+    ///
+    /// # Returns
+    /// The repository, or None if the forge is not supported or the response
+    /// is incomplete
+    pub fn from_api_response(
+        source: &UpstreamSource,
+        repository: &serde_json::Value,
+        files: &serde_json::Value,
+    ) -> Option<Self> {
+        let (url_field, issues_field) = match source {
+            UpstreamSource::GitHub { .. } | UpstreamSource::Codeberg { .. } => {
+                ("html_url", "has_issues")
+            }
+            UpstreamSource::GitLab { .. } => ("web_url", "issues_enabled"),
+            _ => return None,
+        };
+        Some(ForgeRepository {
+            web_url: repository[url_field].as_str()?.to_string(),
+            default_branch: repository["default_branch"].as_str().map(|s| s.to_string()),
+            has_issues: repository[issues_field].as_bool().unwrap_or(false),
+            archived: repository["archived"].as_bool().unwrap_or(false),
+            files: files
+                .as_array()
+                .map(|files| {
+                    files
+                        .iter()
+                        .filter_map(|f| f["name"].as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Name of the changelog file in the root of the repository, if any.
+    fn changelog_file(&self) -> Option<&str> {
+        self.files.iter().map(|f| f.as_str()).find(|f| {
+            let stem = f.split('.').next().unwrap().to_ascii_uppercase();
+            ["CHANGELOG", "CHANGES", "NEWS", "HISTORY"].contains(&stem.as_str())
+        })
+    }
+}
+
+/// DEP-12 fields that can be derived from a forge repository.
+///
+/// # Arguments
+/// * `source` - The upstream source the repository is hosted on
+/// * `repository` - Information about the repository
+///
+/// # Returns
+/// Field names and values
+pub fn forge_metadata(
+    source: &UpstreamSource,
+    repository: &ForgeRepository,
+) -> Vec<(&'static str, String)> {
+    let web_url = repository.web_url.trim_end_matches('/');
+    let (issues, blob) = match source {
+        UpstreamSource::GitLab { .. } => ("-/issues", "-/blob"),
+        UpstreamSource::Codeberg { .. } => ("issues", "src/branch"),
+        _ => ("issues", "blob"),
+    };
+    let mut ret = vec![
+        ("Repository", format!("{}.git", web_url)),
+        ("Repository-Browse", web_url.to_string()),
+    ];
+    if repository.has_issues {
+        ret.push(("Bug-Database", format!("{}/{}", web_url, issues)));
+        ret.push(("Bug-Submit", format!("{}/{}/new", web_url, issues)));
+    }
+    if let Some(changelog) = repository.changelog_file() {
+        ret.push((
+            "Changelog",
+            format!(
+                "{}/{}/{}/{}",
+                web_url,
+                blob,
+                repository.default_branch.as_deref().unwrap_or("HEAD"),
+                changelog
+            ),
+        ));
+    }
+    ret
+}
+
+/// Add fields that are missing from the upstream metadata.
+///
+/// Fields that are already present are left alone.
+///
+/// # Arguments
+/// * `metadata` - Metadata to update
+/// * `fields` - Field names and values to add
+///
+/// # Returns
+/// Names of the fields that were added
+pub fn populate(metadata: &mut UpstreamMetadata, fields: &[(&str, String)]) -> Vec<String> {
+    let mut ret = vec![];
+    for (key, value) in fields {
+        if !metadata.contains_key(key) {
+            metadata.set(key, value);
+            ret.push(key.to_string());
+        }
+    }
+    ret
+}
+
+/// Determine whether a repository has moved, based on what its forge reports.
+///
+/// # Arguments
+/// * `source` - The repository that was queried
+/// * `repository` - Information the forge returned for it
+///
+/// # Returns
+/// The new location of the repository, if it has moved
+pub fn moved_repository(
+    source: &UpstreamSource,
+    repository: &ForgeRepository,
+) -> Option<UpstreamSource> {
+    let new = UpstreamSource::from_repository_url(&repository.web_url)?;
+    let same = new
+        .web_url()
+        .zip(source.web_url())
+        .is_some_and(|(a, b)| a.eq_ignore_ascii_case(&b));
+    if same {
+        None
+    } else {
+        Some(new)
+    }
+}
+
+/// An upstream URL that is stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleUrl {
+    /// The URL and where it was found
+    pub url: LocatedUrl,
+    /// The URL it should be replaced with, if the project has moved
+    pub new_url: Option<String>,
+    /// Whether the repository has been archived
+    pub archived: bool,
+}
+
+impl std::fmt::Display for StaleUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.url.location, self.url.url)?;
+        if let Some(new_url) = self.new_url.as_ref() {
+            write!(f, " has moved to {}", new_url)?;
+            if self.archived {
+                write!(f, ", which is archived")?;
+            }
+        } else if self.archived {
+            write!(f, " is archived")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error querying a forge for repository information.
+#[cfg(feature = "forge-api")]
+#[derive(Debug)]
+pub enum ForgeApiError {
+    /// The repository is not hosted on a supported forge.
+    UnsupportedForge(String),
+    /// Error talking to the forge.
+    Http(reqwest::Error),
+    /// Error reading or writing the cache or source tree.
+    Io(std::io::Error),
+}
+
+#[cfg(feature = "forge-api")]
+impl std::fmt::Display for ForgeApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForgeApiError::UnsupportedForge(url) => write!(f, "Unsupported forge: {}", url),
+            ForgeApiError::Http(e) => write!(f, "HTTP error: {}", e),
+            ForgeApiError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "forge-api")]
+impl std::error::Error for ForgeApiError {}
+
+#[cfg(feature = "forge-api")]
+impl From<reqwest::Error> for ForgeApiError {
+    fn from(e: reqwest::Error) -> Self {
+        ForgeApiError::Http(e)
+    }
+}
+
+#[cfg(feature = "forge-api")]
+impl From<std::io::Error> for ForgeApiError {
+    fn from(e: std::io::Error) -> Self {
+        ForgeApiError::Io(e)
+    }
+}
+
+/// Path to cache the response for a forge API URL in.
+#[cfg(feature = "forge-api")]
+fn forge_cache_path(cache_dir: Option<&Path>, url: &str) -> Option<std::path::PathBuf> {
+    cache_dir.map(|dir| {
+        let name = url
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        dir.join(format!("{}.json", name))
+    })
+}
+
+/// The API URLs for a repository and for the listing of its top-level files.
+#[cfg(feature = "forge-api")]
+fn forge_api_urls(source: &UpstreamSource) -> Result<(String, String), ForgeApiError> {
+    let api_url = match source {
+        UpstreamSource::GitHub { owner, repo } => {
+            format!("https://api.github.com/repos/{}/{}", owner, repo)
+        }
+        UpstreamSource::GitLab { host, path } => format!(
+            "https://{}/api/v4/projects/{}",
+            host,
+            path.replace('/', "%2F")
+        ),
+        UpstreamSource::Codeberg { owner, repo } => {
+            format!("https://codeberg.org/api/v1/repos/{}/{}", owner, repo)
+        }
+        other => return Err(ForgeApiError::UnsupportedForge(format!("{:?}", other))),
+    };
+    let files_url = match source {
+        UpstreamSource::GitLab { .. } => format!("{}/repository/tree", api_url),
+        _ => format!("{}/contents", api_url),
+    };
+    Ok((api_url, files_url))
+}
+
+/// Client for the APIs of GitHub, GitLab and Codeberg, with an optional
+/// on-disk cache of responses.
+#[cfg(feature = "forge-api")]
+pub struct ForgeClient {
+    client: reqwest::blocking::Client,
+    cache_dir: Option<std::path::PathBuf>,
+}
+
+#[cfg(feature = "forge-api")]
+impl ForgeClient {
+    /// Create a new client.
+    ///
+    /// # Arguments
+    /// * `cache_dir` - Directory to cache responses in, if any
+    pub fn new(cache_dir: Option<&Path>) -> Result<Self, ForgeApiError> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self {
+            client,
+            cache_dir: cache_dir.map(|p| p.to_path_buf()),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn get_json(&self, url: &str) -> Result<serde_json::Value, ForgeApiError> {
+        let cache_path = forge_cache_path(self.cache_dir.as_deref(), url);
+        if let Some(cache_path) = cache_path.as_ref() {
+            if let Ok(text) = std::fs::read_to_string(cache_path) {
+                if let Ok(value) = serde_json::from_str(&text) {
+                    return Ok(value);
+                }
+            }
+        }
+        let value: serde_json::Value = self.client.get(url).send()?.error_for_status()?.json()?;
+        if let Some(cache_path) = cache_path {
+            std::fs::create_dir_all(cache_path.parent().unwrap())?;
+            std::fs::write(cache_path, value.to_string())?;
+        }
+        Ok(value)
+    }
+
+    /// Fetch information about a repository from its forge.
+    pub fn repository(&self, source: &UpstreamSource) -> Result<ForgeRepository, ForgeApiError> {
+        let (api_url, files_url) = forge_api_urls(source)?;
+        let repository = self.get_json(&api_url)?;
+        let files = self.get_json(&files_url)?;
+        ForgeRepository::from_api_response(source, &repository, &files)
+            .ok_or(ForgeApiError::UnsupportedForge(api_url))
+    }
+}
+
+#[cfg(feature = "forge-api")]
+impl ForgeClient {
+    /// Follow the redirects for a URL.
+    ///
+    /// # Returns
+    /// The URL that the redirects end at
+    #[tracing::instrument(skip(self))]
+    pub fn resolve_redirects(&self, url: &str) -> Result<String, ForgeApiError> {
+        let response = self.client.get(url).send()?;
+        Ok(response.url().to_string())
+    }
+}
+
+/// Asynchronous client for the APIs of GitHub, GitLab and Codeberg, with an
+/// optional on-disk cache of responses.
+#[cfg(all(feature = "forge-api", feature = "async"))]
+pub struct AsyncForgeClient {
+    client: reqwest::Client,
+    cache_dir: Option<std::path::PathBuf>,
+}
+
+#[cfg(all(feature = "forge-api", feature = "async"))]
+impl AsyncForgeClient {
+    /// Create a new client.
+    ///
+    /// # Arguments
+    /// * `cache_dir` - Directory to cache responses in, if any
+    pub fn new(cache_dir: Option<&Path>) -> Result<Self, ForgeApiError> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+        Ok(Self {
+            client,
+            cache_dir: cache_dir.map(|p| p.to_path_buf()),
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_json(&self, url: &str) -> Result<serde_json::Value, ForgeApiError> {
+        let cache_path = forge_cache_path(self.cache_dir.as_deref(), url);
+        if let Some(cache_path) = cache_path.as_ref() {
+            if let Ok(text) = tokio::fs::read_to_string(cache_path).await {
+                if let Ok(value) = serde_json::from_str(&text) {
+                    return Ok(value);
+                }
+            }
+        }
+        let value: serde_json::Value = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(cache_path) = cache_path {
+            tokio::fs::create_dir_all(cache_path.parent().unwrap()).await?;
+            tokio::fs::write(cache_path, value.to_string()).await?;
+        }
+        Ok(value)
+    }
+
+    /// Fetch information about a repository from its forge.
+    pub async fn repository(
+        &self,
+        source: &UpstreamSource,
+    ) -> Result<ForgeRepository, ForgeApiError> {
+        let (api_url, files_url) = forge_api_urls(source)?;
+        let repository = self.get_json(&api_url).await?;
+        let files = self.get_json(&files_url).await?;
+        ForgeRepository::from_api_response(source, &repository, &files)
+            .ok_or(ForgeApiError::UnsupportedForge(api_url))
+    }
+
+    /// Follow the redirects for a URL.
+    ///
+    /// # Returns
+    /// The URL that the redirects end at
+    #[tracing::instrument(skip(self))]
+    pub async fn resolve_redirects(&self, url: &str) -> Result<String, ForgeApiError> {
+        let response = self.client.get(url).send().await?;
+        Ok(response.url().to_string())
+    }
+}
+
+/// Find upstream URLs that refer to repositories that have moved or been
+/// archived.
+///
+/// Repositories on a known forge are looked up through the forge API; other
+/// Homepage URLs are checked for redirects.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `editor` - Editor for the package's control file
+/// * `client` - Client to query forges with
+///
+/// # Returns
+/// The stale URLs, with proposed replacements
+#[cfg(feature = "forge-api")]
+pub fn find_stale_urls(
+    base: &Path,
+    editor: &mut dyn crate::abstract_control::AbstractControlEditor,
+    client: &ForgeClient,
+) -> Result<Vec<StaleUrl>, ForgeApiError> {
+    let mut ret = vec![];
+    let mut repositories: Vec<(UpstreamSource, ForgeRepository)> = vec![];
+    for url in upstream_urls(base, editor)? {
+        if matches!(url.location, UrlLocation::VcsGit | UrlLocation::VcsBrowser) {
+            continue;
+        }
+        let Some(source) = UpstreamSource::from_repository_url(&url.url) else {
+            if url.location == UrlLocation::Homepage {
+                let resolved = client.resolve_redirects(&url.url)?;
+                if resolved.trim_end_matches('/') != url.url.trim_end_matches('/') {
+                    ret.push(StaleUrl {
+                        url,
+                        new_url: Some(resolved),
+                        archived: false,
+                    });
+                }
+            }
+            continue;
+        };
+        let repository = match repositories.iter().find(|(s, _)| *s == source) {
+            Some((_, repository)) => repository.clone(),
+            None => {
+                let repository = client.repository(&source)?;
+                repositories.push((source.clone(), repository.clone()));
+                repository
+            }
+        };
+        let new_url = moved_repository(&source, &repository)
+            .and_then(|new| rewrite_repository_url(&url.url, &new));
+        if new_url.is_some() || repository.archived {
+            ret.push(StaleUrl {
+                url,
+                new_url,
+                archived: repository.archived,
+            });
+        }
+    }
+    Ok(ret)
+}
+
+/// Fill in missing upstream metadata fields from the forge hosting upstream.
+///
+/// The repository is taken from the `Repository` field, or else from the
+/// `Homepage` field in debian/control.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `client` - Client to query the forge with
+///
+/// # Returns
+/// Names of the fields that were added
+#[cfg(feature = "forge-api")]
+pub fn populate_from_forge(
+    base: &Path,
+    client: &ForgeClient,
+) -> Result<Vec<String>, ForgeApiError> {
+    let mut metadata = UpstreamMetadata::read(base)?;
+    let url = match metadata.get("Repository") {
+        Some(url) => url,
+        None => {
+            let f = std::fs::File::open(base.join("debian/control"))?;
+            let (control, _) = debian_control::Control::read_relaxed(f)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            control
+                .source()
+                .and_then(|s| s.homepage())
+                .map(|u| u.to_string())
+                .ok_or_else(|| ForgeApiError::UnsupportedForge("no repository".to_string()))?
+        }
+    };
+    let source = UpstreamSource::from_repository_url(&url)
+        .ok_or_else(|| ForgeApiError::UnsupportedForge(url.clone()))?;
+    let repository = client.repository(&source)?;
+    let added = populate(&mut metadata, &forge_metadata(&source, &repository));
+    if !added.is_empty() {
+        metadata.write(base)?;
+    }
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "---\n# Upstream metadata\nName: foo\nReference:\n  - Author: Jane\n    Title: 'Foo: a bar'\nRepository: \"https://github.com/foo/foo.git\"\n";
+        let metadata: UpstreamMetadata = text.parse().unwrap();
+        assert_eq!(text, metadata.to_string());
+        assert_eq!(
+            vec!["Name", "Reference", "Repository"],
+            metadata.keys().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("foo".to_string()), metadata.get("Name"));
+        assert_eq!(None, metadata.get("Reference"));
+        assert_eq!(
+            Some("https://github.com/foo/foo.git".to_string()),
+            metadata.get("Repository")
+        );
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut metadata: UpstreamMetadata = "Name: foo\nArchive: CPAN".parse().unwrap();
+        metadata.set("Name", "foo: the bar");
+        metadata.set("Bug-Database", "https://example.com/bugs");
+        assert!(metadata.rename("Archive", "Registry"));
+        assert!(!metadata.remove("Missing"));
+        assert_eq!(
+            "Name: 'foo: the bar'\nRegistry: CPAN\nBug-Database: https://example.com/bugs\n",
+            metadata.to_string()
+        );
+        assert_eq!(Some("foo: the bar".to_string()), metadata.get("Name"));
+    }
+
+    #[test]
+    fn test_forge_metadata() {
+        let source = UpstreamSource::GitLab {
+            host: "gitlab.com".to_string(),
+            path: "foo/bar".to_string(),
+        };
+        let repository = ForgeRepository::from_api_response(
+            &source,
+            &serde_json::json!({
+                "web_url": "https://gitlab.com/foo/bar",
+                "default_branch": "main",
+                "issues_enabled": true,
+            }),
+            &serde_json::json!([{"name": "README.md"}, {"name": "NEWS.md"}]),
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                ("Repository", "https://gitlab.com/foo/bar.git".to_string()),
+                (
+                    "Repository-Browse",
+                    "https://gitlab.com/foo/bar".to_string()
+                ),
+                (
+                    "Bug-Database",
+                    "https://gitlab.com/foo/bar/-/issues".to_string()
+                ),
+                (
+                    "Bug-Submit",
+                    "https://gitlab.com/foo/bar/-/issues/new".to_string()
+                ),
+                (
+                    "Changelog",
+                    "https://gitlab.com/foo/bar/-/blob/main/NEWS.md".to_string()
+                ),
+            ],
+            forge_metadata(&source, &repository)
+        );
+    }
+
+    #[test]
+    fn test_populate() {
+        let mut metadata: UpstreamMetadata =
+            "Repository: https://github.com/foo/bar\n".parse().unwrap();
+        let added = populate(
+            &mut metadata,
+            &[
+                ("Repository", "https://github.com/foo/bar.git".to_string()),
+                (
+                    "Bug-Database",
+                    "https://github.com/foo/bar/issues".to_string(),
+                ),
+            ],
+        );
+        assert_eq!(vec!["Bug-Database"], added);
+        assert_eq!(
+            "Repository: https://github.com/foo/bar\nBug-Database: https://github.com/foo/bar/issues\n",
+            metadata.to_string()
+        );
+    }
+
+    #[test]
+    fn test_check_keys() {
+        let metadata: UpstreamMetadata =
+            "Bugs-Database: https://example.com/bugs\nrepository-browser: https://example.com/foo\nName: foo\nX-Custom: 1\nFrobnicate: yes\nBug-Tracker: https://example.com/bugs\n"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            vec![
+                MetadataKeyIssue::Misspelled {
+                    key: "Bugs-Database".to_string(),
+                    suggestion: "Bug-Database".to_string()
+                },
+                MetadataKeyIssue::Misspelled {
+                    key: "repository-browser".to_string(),
+                    suggestion: "Repository-Browse".to_string()
+                },
+                MetadataKeyIssue::Obsolete("Name".to_string()),
+                MetadataKeyIssue::Unknown("Frobnicate".to_string()),
+                MetadataKeyIssue::Misspelled {
+                    key: "Bug-Tracker".to_string(),
+                    suggestion: "Bug-Database".to_string()
+                },
+            ],
+            check_keys(&metadata)
+        );
+    }
+
+    #[test]
+    fn test_fix_keys() {
+        let mut metadata: UpstreamMetadata =
+            "Bugs-Database: https://example.com/bugs # tracker\nBug-Tracker: https://example.com/other\nRepository-Browser: https://example.com/foo\n"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            vec![
+                ("Bugs-Database".to_string(), "Bug-Database".to_string()),
+                (
+                    "Repository-Browser".to_string(),
+                    "Repository-Browse".to_string()
+                ),
+            ],
+            fix_keys(&mut metadata)
+        );
+        assert_eq!(
+            "Bug-Database: https://example.com/bugs # tracker\nBug-Tracker: https://example.com/other\nRepository-Browse: https://example.com/foo\n",
+            metadata.to_string()
+        );
+    }
+
+    #[test]
+    fn test_check_consistency() {
+        let url = |location, url: &str| LocatedUrl {
+            location,
+            url: url.to_string(),
+        };
+        let urls = vec![
+            url(UrlLocation::Repository, "https://github.com/foo/bar.git"),
+            url(UrlLocation::Homepage, "https://github.com/oldfork/bar"),
+            url(UrlLocation::Watch, "https://GitHub.com/Foo/bar/tags"),
+            url(
+                UrlLocation::RepositoryBrowse,
+                "https://salsa.debian.org/debian/bar",
+            ),
+            url(
+                UrlLocation::VcsGit,
+                "https://salsa.debian.org/debian/bar.git",
+            ),
+        ];
+        assert_eq!(
+            vec![
+                ConsistencyIssue::Contradiction(urls[0].clone(), urls[1].clone()),
+                ConsistencyIssue::PackagingRepository(urls[3].clone()),
+            ],
+            check_consistency(&urls)
+        );
+        assert!(
+            check_consistency(&[url(UrlLocation::Homepage, "https://example.com/")]).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_propagate_upstream_repository() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian/upstream")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: bar\nHomepage: https://github.com/oldfork/bar\n\nPackage: bar\nArchitecture: all\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("debian/watch"),
+            "version=4\nhttps://github.com/oldfork/bar/tags .*/v?(\\d\\S+)\\.tar\\.gz\n",
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join(UPSTREAM_METADATA_PATH),
+            "Repository: https://github.com/foo/bar.git\n",
+        )
+        .unwrap();
+        let mut editor =
+            crate::control::TemplatedControlEditor::open(td.path().join("debian/control")).unwrap();
+        let urls = upstream_urls(td.path(), &mut editor).unwrap();
+        assert_eq!(2, check_consistency(&urls).len());
+        let canonical = UpstreamSource::GitHub {
+            owner: "foo".to_string(),
+            repo: "bar".to_string(),
+        };
+        assert_eq!(
+            vec![
+                UrlLocation::RepositoryBrowse,
+                UrlLocation::Homepage,
+                UrlLocation::Watch
+            ],
+            propagate_upstream_repository(td.path(), &mut editor, &canonical).unwrap()
+        );
+        crate::abstract_control::AbstractControlEditor::commit(&editor).unwrap();
+        assert!(std::fs::read_to_string(td.path().join("debian/control"))
+            .unwrap()
+            .contains("Homepage: https://github.com/foo/bar\n"));
+        assert!(std::fs::read_to_string(td.path().join("debian/watch"))
+            .unwrap()
+            .contains("https://github.com/foo/bar/tags"));
+        let urls = upstream_urls(td.path(), &mut editor).unwrap();
+        assert!(check_consistency(&urls).is_empty());
+    }
+
+    #[test]
+    fn test_manifest_parsers() {
+        assert_eq!(
+            vec![("Repository", "https://github.com/foo/bar".to_string())],
+            cargo_manifest_metadata(
+                "[package]\nname = \"bar\"\nrepository = \"https://github.com/foo/bar\"\n"
+            )
+        );
+        assert_eq!(
+            vec![
+                ("Bug-Database", "https://example.com/issues".to_string()),
+                ("Changelog", "https://example.com/news".to_string()),
+            ],
+            pyproject_metadata(
+                "[project]\nname = \"bar\"\n\n[project.urls]\nHomepage = \"https://example.com/\"\n\"Bug Tracker\" = \"https://example.com/issues\"\nChangelog = \"https://example.com/news\"\n"
+            )
+        );
+        assert_eq!(
+            vec![("Documentation", "https://bar.readthedocs.io/".to_string())],
+            setup_cfg_metadata(
+                "[metadata]\nname = bar\nproject_urls =\n    Documentation = https://bar.readthedocs.io/\n    Homepage = https://example.com/\nlicense = MIT\n\n[options]\n    Docs = https://example.com/\n"
+            )
+        );
+        assert_eq!(
+            vec![
+                (
+                    "Repository",
+                    "git+https://github.com/foo/bar.git".to_string()
+                ),
+                (
+                    "Bug-Database",
+                    "https://github.com/foo/bar/issues".to_string()
+                ),
+            ],
+            package_json_metadata(
+                r#"{"name": "bar", "repository": {"type": "git", "url": "git+https://github.com/foo/bar.git"}, "bugs": "https://github.com/foo/bar/issues"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_repository_url() {
+        assert_eq!(
+            "https://github.com/foo/bar.git",
+            normalize_repository_url("git+https://github.com/foo/bar.git")
+        );
+        assert_eq!(
+            "https://github.com/foo/bar",
+            normalize_repository_url("foo/bar")
+        );
+        assert_eq!(
+            "https://gitlab.com/foo/bar",
+            normalize_repository_url("gitlab:foo/bar")
+        );
+        assert_eq!(
+            "https://github.com/foo/bar.git",
+            normalize_repository_url("git://github.com/foo/bar.git")
+        );
+    }
+
+    #[test]
+    fn test_import_from_manifests() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::write(
+            td.path().join("package.json"),
+            r#"{"repository": "github:foo/bar", "bugs": {"url": "https://github.com/foo/bar/issues"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            td.path().join("Cargo.toml"),
+            "[package]\nname = \"bar\"\ndocumentation = \"https://docs.rs/bar\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                "Documentation",
+                "Repository",
+                "Bug-Database",
+                "Repository-Browse"
+            ],
+            import_from_manifests(td.path()).unwrap()
+        );
+        assert_eq!(
+            "Documentation: https://docs.rs/bar\nRepository: https://github.com/foo/bar\nBug-Database: https://github.com/foo/bar/issues\nRepository-Browse: https://github.com/foo/bar\n",
+            std::fs::read_to_string(td.path().join(UPSTREAM_METADATA_PATH)).unwrap()
+        );
+        assert!(import_from_manifests(td.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_moved_repository() {
+        let source = UpstreamSource::GitHub {
+            owner: "foo".to_string(),
+            repo: "bar".to_string(),
+        };
+        let repository = |web_url: &str| ForgeRepository {
+            web_url: web_url.to_string(),
+            default_branch: None,
+            has_issues: true,
+            archived: false,
+            files: vec![],
+        };
+        assert_eq!(
+            None,
+            moved_repository(&source, &repository("https://github.com/Foo/bar"))
+        );
+        let new =
+            moved_repository(&source, &repository("https://github.com/bar-project/bar")).unwrap();
+        assert_eq!(
+            Some("https://github.com/bar-project/bar/releases".to_string()),
+            rewrite_repository_url("https://GitHub.com/foo/bar/releases", &new)
+        );
+        assert_eq!(
+            None,
+            rewrite_repository_url("https://example.com/bar", &new)
+        );
+        assert_eq!(
+            "Homepage (https://github.com/foo/bar) has moved to https://github.com/bar-project/bar, which is archived",
+            StaleUrl {
+                url: LocatedUrl {
+                    location: UrlLocation::Homepage,
+                    url: "https://github.com/foo/bar".to_string(),
+                },
+                new_url: Some("https://github.com/bar-project/bar".to_string()),
+                archived: true,
+            }
+            .to_string()
+        );
+    }
+
+    #[cfg(feature = "forge-api")]
+    #[test]
+    fn test_forge_api_urls() {
+        assert_eq!(
+            (
+                "https://gitlab.com/api/v4/projects/foo%2Fbar".to_string(),
+                "https://gitlab.com/api/v4/projects/foo%2Fbar/repository/tree".to_string()
+            ),
+            forge_api_urls(&UpstreamSource::GitLab {
+                host: "gitlab.com".to_string(),
+                path: "foo/bar".to_string(),
+            })
+            .unwrap()
+        );
+        assert!(forge_api_urls(&UpstreamSource::PyPI("foo".to_string())).is_err());
+        assert_eq!(
+            Some(Path::new("/cache/https___api_github_com_repos_a_b.json").to_path_buf()),
+            forge_cache_path(
+                Some(Path::new("/cache")),
+                "https://api.github.com/repos/a/b"
+            )
+        );
+    }
+}
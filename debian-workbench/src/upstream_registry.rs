@@ -0,0 +1,503 @@
+//! Version and metadata lookups in language-specific package registries.
+//!
+//! PyPI, npm, RubyGems and (with the `crates-io` feature) crates.io are all
+//! available through the [`UpstreamRegistry`] trait, so callers can look up
+//! new upstream versions or fill in metadata without caring about the ecosystem.
+use crate::watch::UpstreamSource;
+
+/// Metadata about the latest release of a package in a registry.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PackageMetadata {
+    /// Name of the package
+    pub name: String,
+    /// Latest version of the package
+    pub version: String,
+    /// Short description of the package
+    pub summary: Option<String>,
+    /// Homepage of the package
+    pub homepage: Option<String>,
+    /// URL of the source code repository
+    pub repository: Option<String>,
+    /// License of the package, as reported by the registry
+    pub license: Option<String>,
+}
+
+/// Error talking to a registry.
+#[derive(Debug)]
+pub enum Error {
+    /// Error talking to the registry.
+    Http(reqwest::Error),
+    /// Error talking to crates.io.
+    #[cfg(feature = "crates-io")]
+    CratesIo(crate::crates_io::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "HTTP error: {}", e),
+            #[cfg(feature = "crates-io")]
+            Error::CratesIo(e) => write!(f, "crates.io error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+#[cfg(feature = "crates-io")]
+impl From<crate::crates_io::Error> for Error {
+    fn from(e: crate::crates_io::Error) -> Self {
+        Error::CratesIo(e)
+    }
+}
+
+/// A registry that packages can be looked up in.
+pub trait UpstreamRegistry {
+    /// Human readable name of the registry.
+    fn name(&self) -> &'static str;
+
+    /// Look up metadata about the latest release of a package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the package in the registry
+    ///
+    /// # Returns
+    /// The metadata, or `None` if the package does not exist
+    fn metadata(&self, package: &str) -> Result<Option<PackageMetadata>, Error>;
+
+    /// Look up the latest version of a package.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the package in the registry
+    ///
+    /// # Returns
+    /// The version, or `None` if the package does not exist
+    fn latest_version(&self, package: &str) -> Result<Option<String>, Error> {
+        Ok(self.metadata(package)?.map(|m| m.version))
+    }
+}
+
+fn http_client() -> Result<reqwest::blocking::Client, Error> {
+    Ok(reqwest::blocking::Client::builder()
+        .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+        .build()?)
+}
+
+#[tracing::instrument(skip(client))]
+fn get_json(
+    client: &reqwest::blocking::Client,
+    url: &str,
+) -> Result<Option<serde_json::Value>, Error> {
+    let response = client.get(url).send()?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(response.error_for_status()?.json()?))
+}
+
+fn pypi_url(base_url: &str, package: &str) -> String {
+    format!("{}{}/json", base_url, package)
+}
+
+fn npm_url(base_url: &str, package: &str) -> String {
+    // Scoped packages have their slash escaped.
+    format!("{}{}", base_url, package.replace('/', "%2f"))
+}
+
+fn rubygems_url(base_url: &str, package: &str) -> String {
+    format!("{}gems/{}.json", base_url, package)
+}
+
+fn string(value: &serde_json::Value) -> Option<String> {
+    value
+        .as_str()
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+}
+
+/// The Python Package Index.
+pub struct PyPI {
+    base_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl PyPI {
+    /// Base URL of the PyPI JSON API.
+    pub const DEFAULT_URL: &'static str = "https://pypi.org/pypi/";
+
+    /// Create a client for pypi.org.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(Self::DEFAULT_URL)
+    }
+
+    /// Create a client for the PyPI compatible API at the given URL.
+    pub fn with_url(base_url: &str) -> Result<Self, Error> {
+        Ok(Self {
+            base_url: base_url.to_string(),
+            client: http_client()?,
+        })
+    }
+}
+
+/// Extract package metadata from a PyPI JSON API response.
+pub fn parse_pypi(value: &serde_json::Value) -> Option<PackageMetadata> {
+    let info = &value["info"];
+    let project_urls = &info["project_urls"];
+    let repository = ["Source", "Source Code", "Repository", "Code"]
+        .iter()
+        .find_map(|k| string(&project_urls[k]));
+    Some(PackageMetadata {
+        name: string(&info["name"])?,
+        version: string(&info["version"])?,
+        summary: string(&info["summary"]),
+        homepage: string(&info["home_page"]).or_else(|| string(&project_urls["Homepage"])),
+        repository,
+        license: string(&info["license_expression"]).or_else(|| string(&info["license"])),
+    })
+}
+
+impl UpstreamRegistry for PyPI {
+    fn name(&self) -> &'static str {
+        "PyPI"
+    }
+
+    fn metadata(&self, package: &str) -> Result<Option<PackageMetadata>, Error> {
+        let url = pypi_url(&self.base_url, package);
+        Ok(get_json(&self.client, &url)?.and_then(|v| parse_pypi(&v)))
+    }
+}
+
+/// The npm registry.
+pub struct Npm {
+    base_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl Npm {
+    /// Base URL of the npm registry.
+    pub const DEFAULT_URL: &'static str = "https://registry.npmjs.org/";
+
+    /// Create a client for registry.npmjs.org.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(Self::DEFAULT_URL)
+    }
+
+    /// Create a client for the npm compatible registry at the given URL.
+    pub fn with_url(base_url: &str) -> Result<Self, Error> {
+        Ok(Self {
+            base_url: base_url.to_string(),
+            client: http_client()?,
+        })
+    }
+}
+
+/// Extract package metadata from an npm registry response.
+pub fn parse_npm(value: &serde_json::Value) -> Option<PackageMetadata> {
+    let version = string(&value["dist-tags"]["latest"])?;
+    let release = &value["versions"][&version];
+    let field = |name: &str| string(&release[name]).or_else(|| string(&value[name]));
+    let repository = match &release["repository"] {
+        serde_json::Value::Object(o) => o.get("url").and_then(string),
+        other => string(other),
+    };
+    Some(PackageMetadata {
+        name: string(&value["name"])?,
+        version,
+        summary: field("description"),
+        homepage: field("homepage"),
+        repository,
+        license: field("license"),
+    })
+}
+
+impl UpstreamRegistry for Npm {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn metadata(&self, package: &str) -> Result<Option<PackageMetadata>, Error> {
+        let url = npm_url(&self.base_url, package);
+        Ok(get_json(&self.client, &url)?.and_then(|v| parse_npm(&v)))
+    }
+}
+
+/// The RubyGems registry.
+pub struct RubyGems {
+    base_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl RubyGems {
+    /// Base URL of the RubyGems API.
+    pub const DEFAULT_URL: &'static str = "https://rubygems.org/api/v1/";
+
+    /// Create a client for rubygems.org.
+    pub fn new() -> Result<Self, Error> {
+        Self::with_url(Self::DEFAULT_URL)
+    }
+
+    /// Create a client for the RubyGems compatible API at the given URL.
+    pub fn with_url(base_url: &str) -> Result<Self, Error> {
+        Ok(Self {
+            base_url: base_url.to_string(),
+            client: http_client()?,
+        })
+    }
+}
+
+/// Extract package metadata from a RubyGems API response.
+pub fn parse_rubygems(value: &serde_json::Value) -> Option<PackageMetadata> {
+    let licenses = value["licenses"]
+        .as_array()
+        .map(|l| l.iter().filter_map(string).collect::<Vec<_>>())
+        .filter(|l| !l.is_empty());
+    Some(PackageMetadata {
+        name: string(&value["name"])?,
+        version: string(&value["version"])?,
+        summary: string(&value["info"]),
+        homepage: string(&value["homepage_uri"]),
+        repository: string(&value["source_code_uri"]),
+        license: licenses.map(|l| l.join(" or ")),
+    })
+}
+
+impl UpstreamRegistry for RubyGems {
+    fn name(&self) -> &'static str {
+        "RubyGems"
+    }
+
+    fn metadata(&self, package: &str) -> Result<Option<PackageMetadata>, Error> {
+        let url = rubygems_url(&self.base_url, package);
+        Ok(get_json(&self.client, &url)?.and_then(|v| parse_rubygems(&v)))
+    }
+}
+
+#[cfg(feature = "crates-io")]
+impl UpstreamRegistry for crate::crates_io::Client {
+    fn name(&self) -> &'static str {
+        "crates.io"
+    }
+
+    fn metadata(&self, package: &str) -> Result<Option<PackageMetadata>, Error> {
+        let info = match self.crate_info(package) {
+            Ok(info) => info,
+            Err(crate::crates_io::Error::NotFound) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(crate_metadata(&info))
+    }
+}
+
+#[cfg(feature = "crates-io")]
+fn crate_metadata(info: &crate::crates_io::CrateInfo) -> Option<PackageMetadata> {
+    let latest = info.latest_version(false)?;
+    Some(PackageMetadata {
+        name: info.name.clone(),
+        version: latest.num.to_string(),
+        summary: info.description.clone(),
+        homepage: info.homepage.clone(),
+        repository: info.repository.clone(),
+        license: latest.license.clone(),
+    })
+}
+
+/// A registry, along with the name of a package in it.
+pub type RegistryPackage<'a> = (Box<dyn UpstreamRegistry>, &'a str);
+
+/// Find the registry for an upstream source, along with the name of the package in it.
+///
+/// # Arguments
+/// * `source` - The upstream source
+///
+/// # Returns
+/// The registry and package name, or `None` if the source is not a registry
+/// (e.g. a forge) or its registry is not supported
+pub fn registry_for_source(source: &UpstreamSource) -> Result<Option<RegistryPackage<'_>>, Error> {
+    Ok(match source {
+        UpstreamSource::PyPI(name) => Some((Box::new(PyPI::new()?), name)),
+        UpstreamSource::Npm(name) => Some((Box::new(Npm::new()?), name)),
+        UpstreamSource::RubyGem(name) => Some((Box::new(RubyGems::new()?), name)),
+        #[cfg(feature = "crates-io")]
+        UpstreamSource::Crate(name) => Some((Box::new(crate::crates_io::Client::new()?), name)),
+        _ => None,
+    })
+}
+
+/// Asynchronous client for all supported registries.
+#[cfg(feature = "async")]
+pub struct AsyncClient {
+    client: reqwest::Client,
+    #[cfg(feature = "crates-io")]
+    crates_io: crate::crates_io::AsyncClient,
+}
+
+#[cfg(feature = "async")]
+impl AsyncClient {
+    /// Create a client for the public registries.
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .user_agent(concat!("debian-workbench/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+            #[cfg(feature = "crates-io")]
+            crates_io: crate::crates_io::AsyncClient::new()?,
+        })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_json(&self, url: &str) -> Result<Option<serde_json::Value>, Error> {
+        let response = self.client.get(url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+
+    /// Look up metadata about the latest release of an upstream package.
+    ///
+    /// # Arguments
+    /// * `source` - The upstream source
+    ///
+    /// # Returns
+    /// The metadata, or `None` if the package does not exist, the source is
+    /// not a registry or its registry is not supported
+    pub async fn metadata(
+        &self,
+        source: &UpstreamSource,
+    ) -> Result<Option<PackageMetadata>, Error> {
+        Ok(match source {
+            UpstreamSource::PyPI(name) => self
+                .get_json(&pypi_url(PyPI::DEFAULT_URL, name))
+                .await?
+                .and_then(|v| parse_pypi(&v)),
+            UpstreamSource::Npm(name) => self
+                .get_json(&npm_url(Npm::DEFAULT_URL, name))
+                .await?
+                .and_then(|v| parse_npm(&v)),
+            UpstreamSource::RubyGem(name) => self
+                .get_json(&rubygems_url(RubyGems::DEFAULT_URL, name))
+                .await?
+                .and_then(|v| parse_rubygems(&v)),
+            #[cfg(feature = "crates-io")]
+            UpstreamSource::Crate(name) => match self.crates_io.crate_info(name).await {
+                Ok(info) => crate_metadata(&info),
+                Err(crate::crates_io::Error::NotFound) => None,
+                Err(e) => return Err(e.into()),
+            },
+            _ => None,
+        })
+    }
+
+    /// Look up the latest version of an upstream package.
+    ///
+    /// # Arguments
+    /// * `source` - The upstream source
+    ///
+    /// # Returns
+    /// The version, or `None` if the package does not exist, the source is
+    /// not a registry or its registry is not supported
+    pub async fn latest_version(&self, source: &UpstreamSource) -> Result<Option<String>, Error> {
+        Ok(self.metadata(source).await?.map(|m| m.version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pypi() {
+        let value = serde_json::json!({
+            "info": {
+                "name": "dulwich",
+                "version": "0.22.1",
+                "summary": "Python Git Library",
+                "home_page": "",
+                "license": "Apache-2.0 or GPL-2.0-or-later",
+                "project_urls": {
+                    "Homepage": "https://www.dulwich.io/",
+                    "Repository": "https://github.com/jelmer/dulwich"
+                }
+            }
+        });
+        assert_eq!(
+            Some(PackageMetadata {
+                name: "dulwich".to_string(),
+                version: "0.22.1".to_string(),
+                summary: Some("Python Git Library".to_string()),
+                homepage: Some("https://www.dulwich.io/".to_string()),
+                repository: Some("https://github.com/jelmer/dulwich".to_string()),
+                license: Some("Apache-2.0 or GPL-2.0-or-later".to_string()),
+            }),
+            parse_pypi(&value)
+        );
+        assert_eq!(
+            None,
+            parse_pypi(&serde_json::json!({"message": "Not Found"}))
+        );
+    }
+
+    #[test]
+    fn test_parse_npm() {
+        let value = serde_json::json!({
+            "name": "@foo/bar",
+            "description": "Old description",
+            "dist-tags": {"latest": "2.0.0"},
+            "versions": {
+                "1.0.0": {"description": "Ancient"},
+                "2.0.0": {
+                    "description": "Bars for foo",
+                    "license": "MIT",
+                    "repository": {"type": "git", "url": "git+https://github.com/foo/bar.git"}
+                }
+            }
+        });
+        let metadata = parse_npm(&value).unwrap();
+        assert_eq!("2.0.0", metadata.version);
+        assert_eq!(Some("Bars for foo"), metadata.summary.as_deref());
+        assert_eq!(
+            Some("git+https://github.com/foo/bar.git"),
+            metadata.repository.as_deref()
+        );
+        assert_eq!(Some("MIT"), metadata.license.as_deref());
+        assert_eq!(None, metadata.homepage);
+    }
+
+    #[test]
+    fn test_parse_rubygems() {
+        let value = serde_json::json!({
+            "name": "rake",
+            "version": "13.2.1",
+            "info": "Rake is a Make-like program implemented in Ruby.",
+            "licenses": ["MIT"],
+            "homepage_uri": "https://github.com/ruby/rake",
+            "source_code_uri": null
+        });
+        let metadata = parse_rubygems(&value).unwrap();
+        assert_eq!("13.2.1", metadata.version);
+        assert_eq!(Some("MIT"), metadata.license.as_deref());
+        assert_eq!(None, metadata.repository);
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            "https://pypi.org/pypi/dulwich/json",
+            pypi_url(PyPI::DEFAULT_URL, "dulwich")
+        );
+        assert_eq!(
+            "https://registry.npmjs.org/@foo%2fbar",
+            npm_url(Npm::DEFAULT_URL, "@foo/bar")
+        );
+        assert_eq!(
+            "https://rubygems.org/api/v1/gems/rake.json",
+            rubygems_url(RubyGems::DEFAULT_URL, "rake")
+        );
+    }
+}
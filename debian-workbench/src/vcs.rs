@@ -24,6 +24,7 @@ pub fn is_gitlab_site(hostname: &str, net_access: Option<bool>) -> bool {
 }
 
 /// Check if a particular host is a GitLab instance.
+#[tracing::instrument]
 pub fn probe_gitlab_host(hostname: &str) -> bool {
     use reqwest::header::HeaderMap;
     let url = format!("https://{}/api/v4/version", hostname);
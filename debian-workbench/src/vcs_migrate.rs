@@ -0,0 +1,429 @@
+//! Batch migration of packaging repositories to a new VCS location.
+//!
+//! Each package goes through a fixed sequence of stages. The stage that
+//! was reached is recorded in a state file, so that an interrupted or
+//! partially failed migration can be resumed.
+use breezyshim::branch::Branch;
+use breezyshim::controldir::ControlDir;
+use breezyshim::error::Error as BrzError;
+use breezyshim::prelude::*;
+use debian_control::vcs::ParsedVcs;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// Placeholder in target URL templates that is replaced by the package name.
+pub const PACKAGE_PLACEHOLDER: &str = "{package}";
+
+/// Stage of the migration of a single package.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage {
+    /// Nothing has been done yet
+    #[default]
+    Pending,
+    /// The packaging branch has been cloned locally
+    Cloned,
+    /// The repository at the new location has been created
+    RepositoryCreated,
+    /// All branches have been pushed to the new location
+    BranchesPushed,
+    /// The Vcs-* fields have been updated and pushed
+    VcsUpdated,
+}
+
+impl Stage {
+    /// The stage that follows this one, if any.
+    pub fn next(&self) -> Option<Stage> {
+        match self {
+            Stage::Pending => Some(Stage::Cloned),
+            Stage::Cloned => Some(Stage::RepositoryCreated),
+            Stage::RepositoryCreated => Some(Stage::BranchesPushed),
+            Stage::BranchesPushed => Some(Stage::VcsUpdated),
+            Stage::VcsUpdated => None,
+        }
+    }
+
+    /// Whether the migration is complete.
+    pub fn is_done(&self) -> bool {
+        self.next().is_none()
+    }
+}
+
+/// Migration state of a single package.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct PackageState {
+    /// The last stage that was completed
+    pub stage: Stage,
+    /// The error that stopped the migration, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Migration state of a set of packages, as stored in the state file.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct MigrationState {
+    /// State of each package, by name
+    pub packages: BTreeMap<String, PackageState>,
+}
+
+impl MigrationState {
+    /// Load the state from a file; returns an empty state if it does not exist.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        match std::fs::read(path) {
+            Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Save the state to a file, replacing it atomically.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let dir = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let mut f = tempfile::NamedTempFile::new_in(dir)?;
+        serde_json::to_writer_pretty(&mut f, self)?;
+        f.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    /// Get the state of a package, adding it if it is not known yet.
+    pub fn package_mut(&mut self, name: &str) -> &mut PackageState {
+        self.packages.entry(name.to_string()).or_default()
+    }
+}
+
+/// A package to migrate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSpec {
+    /// Name of the source package
+    pub name: String,
+    /// Current location of the packaging repository
+    pub url: Url,
+}
+
+/// Parse a list of packages to migrate.
+///
+/// Each line holds a package name and the URL of its current repository,
+/// separated by whitespace. Empty lines and lines starting with `#` are
+/// ignored.
+pub fn parse_package_list(text: &str) -> Result<Vec<PackageSpec>, String> {
+    let mut ret = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(url), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("line {}: expected package name and URL", i + 1));
+        };
+        let url = url
+            .parse()
+            .map_err(|e| format!("line {}: invalid URL {}: {}", i + 1, url, e))?;
+        ret.push(PackageSpec {
+            name: name.to_string(),
+            url,
+        });
+    }
+    Ok(ret)
+}
+
+/// Expand a target URL template for a package.
+///
+/// # Arguments
+/// * `template` - URL template, e.g. `https://salsa.debian.org/debian/{package}.git`
+/// * `package` - Name of the source package
+pub fn target_url(template: &str, package: &str) -> Result<Url, url::ParseError> {
+    template.replace(PACKAGE_PLACEHOLDER, package).parse()
+}
+
+/// Branch layout to use at the new location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// Keep the existing branch names
+    #[default]
+    Keep,
+    /// Rename branches following DEP-14
+    Dep14,
+}
+
+/// The DEP-14 name for a commonly used branch name.
+pub fn dep14_branch_name(name: &str) -> Option<&'static str> {
+    match name {
+        "master" | "main" | "debian" | "debian/master" | "debian/main" | "debian/sid"
+        | "debian/unstable" => Some("debian/latest"),
+        "upstream" | "upstream/master" | "upstream/main" => Some("upstream/latest"),
+        _ => None,
+    }
+}
+
+impl Layout {
+    /// The name a branch should have at the new location.
+    pub fn branch_name(&self, name: &str) -> String {
+        match self {
+            Layout::Keep => name.to_string(),
+            Layout::Dep14 => dep14_branch_name(name).unwrap_or(name).to_string(),
+        }
+    }
+}
+
+/// Options for a migration.
+#[derive(Debug, Clone)]
+pub struct MigrateOptions {
+    /// Directory in which local clones are kept
+    pub workdir: PathBuf,
+    /// Template for the new repository URL, see [`target_url`]
+    pub target_template: String,
+    /// Branch layout to use at the new location
+    pub layout: Layout,
+    /// Whether to create the repository at the new location
+    pub create: bool,
+    /// Committer to use for the Vcs-* update, or `None` for the default
+    pub committer: Option<String>,
+}
+
+/// Error migrating a package.
+#[derive(Debug)]
+pub enum MigrateError {
+    /// A VCS operation failed
+    Brz(Box<BrzError>),
+    /// The control file could not be edited
+    Editor(Box<crate::editor::EditorError>),
+    /// The target URL is invalid
+    InvalidUrl(url::ParseError),
+    /// I/O error
+    Io(std::io::Error),
+    /// The control file has no source paragraph
+    MissingSource(PathBuf),
+}
+
+impl std::fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrateError::Brz(e) => write!(f, "{}", e),
+            MigrateError::Editor(e) => write!(f, "{}", e),
+            MigrateError::InvalidUrl(e) => write!(f, "Invalid target URL: {}", e),
+            MigrateError::Io(e) => write!(f, "{}", e),
+            MigrateError::MissingSource(p) => {
+                write!(f, "No source paragraph in {}", p.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrateError {}
+
+impl From<BrzError> for MigrateError {
+    fn from(e: BrzError) -> Self {
+        MigrateError::Brz(Box::new(e))
+    }
+}
+
+impl From<crate::editor::EditorError> for MigrateError {
+    fn from(e: crate::editor::EditorError) -> Self {
+        MigrateError::Editor(Box::new(e))
+    }
+}
+
+impl From<url::ParseError> for MigrateError {
+    fn from(e: url::ParseError) -> Self {
+        MigrateError::InvalidUrl(e)
+    }
+}
+
+impl From<std::io::Error> for MigrateError {
+    fn from(e: std::io::Error) -> Self {
+        MigrateError::Io(e)
+    }
+}
+
+/// Open a branch in a control directory, creating it if it does not exist.
+fn open_or_create_branch<C: ControlDir + ?Sized>(
+    controldir: &C,
+    name: Option<&str>,
+) -> Result<Box<C::Branch>, MigrateError> {
+    match controldir.open_branch(name) {
+        Ok(branch) => Ok(branch),
+        Err(BrzError::NotBranchError(..)) => Ok(controldir.create_branch(name)?),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Push all branches of the old repository to the new one, renaming them as needed.
+fn push_branches(spec: &PackageSpec, target: &Url, layout: Layout) -> Result<(), MigrateError> {
+    let source = breezyshim::controldir::open(&spec.url, None)?;
+    let target = breezyshim::controldir::open(target, None)?;
+    for name in source.branch_names()? {
+        let branch = source.open_branch((!name.is_empty()).then_some(name.as_str()))?;
+        let new_name = layout.branch_name(&name);
+        log::debug!("Pushing {} branch {:?} as {:?}", spec.name, name, new_name);
+        let target_branch =
+            open_or_create_branch(target.as_ref(), (!new_name.is_empty()).then_some(&new_name))?;
+        branch.push(target_branch.as_ref(), false, None, None)?;
+    }
+    Ok(())
+}
+
+/// Point the Vcs-* fields at the new location, and push the change there.
+fn update_vcs(local: &Path, target: &Url, options: &MigrateOptions) -> Result<(), MigrateError> {
+    let wt = breezyshim::workingtree::open(local)?;
+    let branch_name = wt.branch().name().filter(|n| !n.is_empty());
+    let new_name = branch_name
+        .as_deref()
+        .map(|n| options.layout.branch_name(n));
+
+    let control_path = local.join("debian/control");
+    let editor = crate::control::TemplatedControlEditor::open(&control_path)?;
+    let mut source = editor
+        .source()
+        .ok_or(MigrateError::MissingSource(control_path))?;
+    let vcs_url = ParsedVcs {
+        repo_url: target.to_string(),
+        branch: new_name.clone(),
+        subpath: None,
+    };
+    crate::publish::update_control_for_vcs_url(
+        &mut source,
+        wt.branch().vcs_type(),
+        &vcs_url.to_string(),
+    );
+    editor.commit()?;
+
+    let committer = options
+        .committer
+        .clone()
+        .unwrap_or_else(|| crate::get_committer(&wt));
+    match wt
+        .build_commit()
+        .message(&format!("Update Vcs-* headers for move to {}.", target))
+        .allow_pointless(false)
+        .committer(committer.as_str())
+        .commit()
+    {
+        Ok(_) | Err(BrzError::PointlessCommit) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let target = breezyshim::controldir::open(target, None)?;
+    let target_branch = open_or_create_branch(target.as_ref(), new_name.as_deref())?;
+    wt.branch()
+        .push(target_branch.as_ref(), false, None, None)?;
+    Ok(())
+}
+
+/// Migrate a package, resuming from the stage recorded in its state.
+///
+/// # Arguments
+/// * `spec` - The package to migrate
+/// * `options` - Migration options
+/// * `state` - State of the package, updated as stages complete
+/// * `checkpoint` - Called after each completed stage, e.g. to save the state
+pub fn migrate_package(
+    spec: &PackageSpec,
+    options: &MigrateOptions,
+    state: &mut PackageState,
+    mut checkpoint: impl FnMut(&PackageState) -> std::io::Result<()>,
+) -> Result<(), MigrateError> {
+    let target = target_url(&options.target_template, &spec.name)?;
+    let local = options.workdir.join(&spec.name);
+    state.error = None;
+    while let Some(next) = state.stage.next() {
+        log::info!("{}: {:?}", spec.name, next);
+        match next {
+            Stage::Pending => unreachable!(),
+            Stage::Cloned => {
+                if !local.exists() {
+                    let local_url = Url::from_directory_path(std::path::absolute(&local)?)
+                        .map_err(|_| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidInput,
+                                format!("Invalid local path: {}", local.display()),
+                            )
+                        })?;
+                    breezyshim::controldir::open(&spec.url, None)?.sprout(
+                        local_url,
+                        None,
+                        Some(true),
+                        None,
+                        None,
+                    )?;
+                }
+            }
+            Stage::RepositoryCreated => {
+                if options.create {
+                    crate::publish::create_vcs_url(&target, None)?;
+                }
+            }
+            Stage::BranchesPushed => push_branches(spec, &target, options.layout)?,
+            Stage::VcsUpdated => update_vcs(&local, &target, options)?,
+        }
+        state.stage = next;
+        checkpoint(state)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_list() {
+        assert_eq!(
+            vec![PackageSpec {
+                name: "foo".to_string(),
+                url: "https://example.com/foo.git".parse().unwrap(),
+            }],
+            parse_package_list("# Packages\n\nfoo https://example.com/foo.git\n").unwrap()
+        );
+        assert!(parse_package_list("foo\n").is_err());
+        assert!(parse_package_list("foo not-a-url\n").is_err());
+    }
+
+    #[test]
+    fn test_branch_names() {
+        assert_eq!(
+            "https://salsa.debian.org/debian/foo.git",
+            target_url("https://salsa.debian.org/debian/{package}.git", "foo")
+                .unwrap()
+                .as_str()
+        );
+        assert_eq!("debian/latest", Layout::Dep14.branch_name("master"));
+        assert_eq!("upstream/latest", Layout::Dep14.branch_name("upstream"));
+        assert_eq!("pristine-tar", Layout::Dep14.branch_name("pristine-tar"));
+        assert_eq!("master", Layout::Keep.branch_name("master"));
+    }
+
+    #[test]
+    fn test_state() {
+        let td = tempfile::tempdir().unwrap();
+        let path = td.path().join("state.json");
+        let mut state = MigrationState::load(&path).unwrap();
+        assert!(state.packages.is_empty());
+        state.package_mut("foo").stage = Stage::BranchesPushed;
+        state.package_mut("bar").error = Some("push failed".to_string());
+        state.save(&path).unwrap();
+        let state = MigrationState::load(&path).unwrap();
+        assert_eq!(Stage::BranchesPushed, state.packages["foo"].stage);
+        assert_eq!(Stage::Pending, state.packages["bar"].stage);
+        assert!(!state.packages["foo"].stage.is_done());
+        assert!(Stage::VcsUpdated.is_done());
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"branches-pushed\""));
+    }
+}
@@ -77,6 +77,225 @@ pub fn matches_release(upstream_version: &str, release_version: &str) -> bool {
     false
 }
 
+/// Build the upstream version for a git snapshot of an upstream release.
+///
+/// # Arguments
+/// * `upstream_version` - Upstream version the snapshot is based on
+/// * `date` - Date of the snapshot
+/// * `sha` - Git revision of the snapshot; only the first 7 characters are used
+///
+/// # Returns
+/// upstream version string, e.g. "1.2.3+git20250101.abcdef0"
+pub fn git_snapshot_upstream_version(
+    upstream_version: &str,
+    date: chrono::NaiveDate,
+    sha: &str,
+) -> String {
+    debversion::upstream::upstream_version_add_revision(
+        upstream_version,
+        debversion::upstream::VcsSnapshot::Git {
+            date: Some(date),
+            sha: Some(sha.to_string()),
+            snapshot: None,
+        },
+        None,
+    )
+}
+
+/// Return the snapshot count of a version with the given snapshot suffix.
+///
+/// # Arguments
+/// * `version` - Version to check
+/// * `suffix` - Snapshot suffix, e.g. "jan+unchanged"
+///
+/// # Returns
+/// the snapshot count, or None if the version does not carry the suffix
+pub fn snapshot_suffix_count(version: &debversion::Version, suffix: &str) -> Option<usize> {
+    let s = version
+        .debian_revision
+        .as_deref()
+        .unwrap_or(version.upstream_version.as_str());
+    let (_, rest) = s.rsplit_once(&format!("~{}", suffix))?;
+    rest.parse().ok()
+}
+
+/// Add (or increment) a snapshot suffix on a version.
+///
+/// The suffix is appended to the Debian revision (or to the upstream version
+/// for native packages) with a tilde, so that the snapshot sorts before the
+/// version it is based on. If the version already carries the suffix, the
+/// trailing count is incremented instead.
+///
+/// # Arguments
+/// * `version` - Version to add the suffix to
+/// * `suffix` - Snapshot suffix, e.g. "jan+unchanged"
+///
+/// # Returns
+/// the new version, e.g. "1.0-1~jan+unchanged1"
+pub fn add_snapshot_suffix(version: &debversion::Version, suffix: &str) -> debversion::Version {
+    let count = snapshot_suffix_count(version, suffix);
+    let base = strip_snapshot_suffix(version, suffix);
+    let append = |s: &str| format!("{}~{}{}", s, suffix, count.map_or(1, |c| c + 1));
+    let mut ret = base.clone();
+    if let Some(debian_revision) = base.debian_revision.as_deref() {
+        ret.debian_revision = Some(append(debian_revision));
+    } else {
+        ret.upstream_version = append(&base.upstream_version);
+    }
+    ret
+}
+
+/// Strip a snapshot suffix from a version.
+///
+/// # Arguments
+/// * `version` - Version to strip the suffix from
+/// * `suffix` - Snapshot suffix, e.g. "jan+unchanged"
+///
+/// # Returns
+/// the version without the suffix; the version itself if it had no suffix
+pub fn strip_snapshot_suffix(version: &debversion::Version, suffix: &str) -> debversion::Version {
+    let strip = |s: &str| -> Option<String> {
+        let (base, rest) = s.rsplit_once(&format!("~{}", suffix))?;
+        if rest.chars().all(|c| c.is_ascii_digit()) && !rest.is_empty() {
+            Some(base.to_string())
+        } else {
+            None
+        }
+    };
+    let mut ret = version.clone();
+    if let Some(debian_revision) = version.debian_revision.as_deref() {
+        if let Some(base) = strip(debian_revision) {
+            ret.debian_revision = Some(base);
+        }
+    } else if let Some(base) = strip(&version.upstream_version) {
+        ret.upstream_version = base;
+    }
+    ret
+}
+
+/// Increment a counter in a version, e.g. the "1" in "1.0-1.1".
+///
+/// # Returns
+/// the incremented counter, or None if it is not a number or would overflow
+fn next_count(count: &str) -> Option<u32> {
+    count.parse::<u32>().ok().and_then(|c| c.checked_add(1))
+}
+
+/// Return the version for a non-maintainer upload of a version.
+///
+/// Follows the conventions from the Developer's Reference: "1.0-1" becomes
+/// "1.0-1.1", and native packages get a "+nmu1" suffix.
+pub fn nmu_version(version: &debversion::Version) -> debversion::Version {
+    let mut ret = version.clone();
+    if let Some(debian_revision) = version.debian_revision.as_deref() {
+        ret.debian_revision = Some(
+            match lazy_regex::regex_captures!(r"^(.*\.)([0-9]+)$", debian_revision)
+                .and_then(|(_, base, count)| Some((base, next_count(count)?)))
+            {
+                Some((base, count)) => format!("{}{}", base, count),
+                None => format!("{}.1", debian_revision),
+            },
+        );
+    } else if let Some(count) = version.nmu_count().and_then(|c| c.checked_add(1)) {
+        let (base, _) = version.upstream_version.rsplit_once("+nmu").unwrap();
+        ret.upstream_version = format!("{}+nmu{}", base, count);
+    } else {
+        ret.upstream_version = format!("{}+nmu1", version.upstream_version);
+    }
+    ret
+}
+
+/// Return the version for a backport of a version.
+///
+/// # Arguments
+/// * `version` - Version that is being backported
+/// * `release_version` - Version number of the target release, e.g. "12"
+///
+/// # Returns
+/// the backport version, e.g. "1.0-1~bpo12+1"
+pub fn backport_version(
+    version: &debversion::Version,
+    release_version: &str,
+) -> debversion::Version {
+    let suffix = format!("~bpo{}+", release_version);
+    let mut ret = version.clone();
+    let field = if let Some(debian_revision) = ret.debian_revision.as_mut() {
+        debian_revision
+    } else {
+        &mut ret.upstream_version
+    };
+    *field = match field.rsplit_once(suffix.as_str()) {
+        Some((base, count)) => match next_count(count) {
+            Some(next) => format!("{}{}{}", base, suffix, next),
+            // Another backport suffix would sort lower; extend the counter instead
+            None if !count.is_empty() && count.chars().all(|c| c.is_ascii_digit()) => {
+                format!("{}.1", field)
+            }
+            None => format!("{}{}1", field, suffix),
+        },
+        None => format!("{}{}1", field, suffix),
+    };
+    ret
+}
+
+/// Return the version for an Ubuntu upload of a version.
+///
+/// "1.0-1" becomes "1.0-1ubuntu1"; versions that already carry an Ubuntu
+/// suffix have it incremented.
+pub fn ubuntu_version(version: &debversion::Version) -> debversion::Version {
+    let mut ret = version.clone();
+    let field = if let Some(debian_revision) = ret.debian_revision.as_mut() {
+        debian_revision
+    } else {
+        &mut ret.upstream_version
+    };
+    *field = match lazy_regex::regex_captures!(r"^(.*ubuntu)([0-9]+)$", field)
+        .and_then(|(_, base, count)| Some((base, next_count(count)?)))
+    {
+        Some((base, count)) => format!("{}{}", base, count),
+        None => format!("{}ubuntu1", field),
+    };
+    ret
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Type of upload a new changelog entry is for.
+pub enum UploadType {
+    /// Regular upload by the maintainer
+    Regular,
+
+    /// Non-maintainer upload
+    NonMaintainer,
+
+    /// Backport to the release with the given version number (e.g. "12")
+    Backport(String),
+}
+
+/// Return the version a new upload of a package should carry.
+///
+/// # Arguments
+/// * `version` - Version of the last upload
+/// * `upload_type` - Type of upload
+/// * `vendor` - Vendor the upload is for
+pub fn next_upload_version(
+    version: &debversion::Version,
+    upload_type: &UploadType,
+    vendor: crate::release_info::Vendor,
+) -> debversion::Version {
+    match upload_type {
+        UploadType::NonMaintainer => nmu_version(version),
+        UploadType::Backport(release_version) => backport_version(version, release_version),
+        UploadType::Regular if vendor == crate::release_info::Vendor::Ubuntu => {
+            ubuntu_version(version)
+        }
+        UploadType::Regular => {
+            let mut ret = version.clone();
+            ret.increment_debian();
+            ret
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,4 +315,112 @@ mod tests {
         assert!(!matches_release("1.0", "1.1"));
         assert!(!matches_release("1.0+ds1", "1.1"));
     }
+
+    #[test]
+    fn test_git_snapshot_upstream_version() {
+        assert_eq!(
+            git_snapshot_upstream_version(
+                "1.2.3",
+                chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                "abcdef0123456789"
+            ),
+            "1.2.3+git20250101.abcdef0"
+        );
+    }
+
+    #[test]
+    fn test_add_snapshot_suffix() {
+        let v: debversion::Version = "1.0-1".parse().unwrap();
+        let v = add_snapshot_suffix(&v, "jan+unchanged");
+        assert_eq!(v.to_string(), "1.0-1~jan+unchanged1");
+        assert_eq!(snapshot_suffix_count(&v, "jan+unchanged"), Some(1));
+        let v = add_snapshot_suffix(&v, "jan+unchanged");
+        assert_eq!(v.to_string(), "1.0-1~jan+unchanged2");
+        assert!(v < "1.0-1".parse().unwrap());
+
+        let native: debversion::Version = "1.0".parse().unwrap();
+        assert_eq!(
+            add_snapshot_suffix(&native, "jan+unchanged").to_string(),
+            "1.0~jan+unchanged1"
+        );
+    }
+
+    #[test]
+    fn test_strip_snapshot_suffix() {
+        let v: debversion::Version = "1.0-1~jan+unchanged3".parse().unwrap();
+        assert_eq!(
+            strip_snapshot_suffix(&v, "jan+unchanged").to_string(),
+            "1.0-1"
+        );
+        let v: debversion::Version = "1.0-1".parse().unwrap();
+        assert_eq!(strip_snapshot_suffix(&v, "jan+unchanged"), v);
+        assert_eq!(snapshot_suffix_count(&v, "jan+unchanged"), None);
+    }
+
+    #[test]
+    fn test_nmu_version() {
+        let v = |s: &str| s.parse::<debversion::Version>().unwrap();
+        assert_eq!(nmu_version(&v("1.0-1")), v("1.0-1.1"));
+        assert_eq!(nmu_version(&v("1.0-1.1")), v("1.0-1.2"));
+        assert_eq!(nmu_version(&v("1.0")), v("1.0+nmu1"));
+        assert_eq!(nmu_version(&v("1.0+nmu1")), v("1.0+nmu2"));
+        assert_eq!(
+            nmu_version(&v("1.0-1.99999999999")),
+            v("1.0-1.99999999999.1")
+        );
+        assert_eq!(nmu_version(&v("1.0-1.4294967295")), v("1.0-1.4294967295.1"));
+        assert_eq!(
+            nmu_version(&v("1.0+nmu2147483647")),
+            v("1.0+nmu2147483647+nmu1")
+        );
+    }
+
+    #[test]
+    fn test_backport_version() {
+        let v = |s: &str| s.parse::<debversion::Version>().unwrap();
+        assert_eq!(backport_version(&v("1.0-1"), "12"), v("1.0-1~bpo12+1"));
+        assert_eq!(
+            backport_version(&v("1.0-1~bpo12+1"), "12"),
+            v("1.0-1~bpo12+2")
+        );
+        assert_eq!(backport_version(&v("1.0"), "12"), v("1.0~bpo12+1"));
+        assert_eq!(
+            backport_version(&v("1.0-1~bpo12+4294967295"), "12"),
+            v("1.0-1~bpo12+4294967295.1")
+        );
+        assert!(backport_version(&v("1.0-1~bpo12+4294967295"), "12") > v("1.0-1~bpo12+4294967295"));
+    }
+
+    #[test]
+    fn test_ubuntu_version() {
+        let v = |s: &str| s.parse::<debversion::Version>().unwrap();
+        assert_eq!(ubuntu_version(&v("1.0-1")), v("1.0-1ubuntu1"));
+        assert_eq!(ubuntu_version(&v("1.0-1ubuntu1")), v("1.0-1ubuntu2"));
+        assert_eq!(
+            ubuntu_version(&v("1.0-1ubuntu99999999999")),
+            v("1.0-1ubuntu99999999999ubuntu1")
+        );
+        assert_eq!(
+            ubuntu_version(&v("1.0-1ubuntu4294967295")),
+            v("1.0-1ubuntu4294967295ubuntu1")
+        );
+    }
+
+    #[test]
+    fn test_next_upload_version() {
+        use crate::release_info::Vendor;
+        let v = |s: &str| s.parse::<debversion::Version>().unwrap();
+        assert_eq!(
+            next_upload_version(&v("1.0-1"), &UploadType::Regular, Vendor::Debian),
+            v("1.0-2")
+        );
+        assert_eq!(
+            next_upload_version(&v("1.0-1"), &UploadType::Regular, Vendor::Ubuntu),
+            v("1.0-1ubuntu1")
+        );
+        assert_eq!(
+            next_upload_version(&v("1.0-1"), &UploadType::NonMaintainer, Vendor::Debian),
+            v("1.0-1.1")
+        );
+    }
 }
@@ -0,0 +1,2855 @@
+//! Functions for working with debian/watch files.
+//!
+//! Both the traditional line-based format (versions 3 and 4) and the
+//! deb822-based format (version 5) are supported. Parsing is lossless:
+//! comments, blank lines, whitespace and line continuations are preserved
+//! for everything that is not explicitly modified.
+use deb822_lossless::{Deb822, Paragraph};
+
+/// Default format version for new watch files.
+pub const DEFAULT_VERSION: u32 = 4;
+
+/// Pattern matching version tags in `mode=git` entries.
+pub const GIT_TAG_PATTERN: &str = r"refs/tags/v?(\d[\-+\.:\~\da-zA-Z]*)";
+
+/// Default `pretty=` format for `mode=git` entries that track a branch head.
+pub const DEFAULT_GIT_PRETTY: &str = "0.0~git%cd.%h";
+
+/// Default `pretty=` format for `mode=svn` entries that track a branch head.
+pub const DEFAULT_SVN_PRETTY: &str = "0.0~svn%s";
+
+/// Regex that the `@ANY_VERSION@` macro expands to.
+pub const ANY_VERSION: &str = r"[-_]?[Vv]?(\d[\-+\.:\~\da-zA-Z]*)";
+
+/// Regex that the `@ARCHIVE_EXT@` macro expands to.
+pub const ARCHIVE_EXT: &str = r"(?i)(?:\.(?:tar\.xz|tar\.bz2|tar\.gz|tar\.zstd?|zip|tgz|tbz|txz))";
+
+/// Regex that the `@SIGNATURE_EXT@` macro expands to.
+pub const SIGNATURE_EXT: &str = r"(?i)(?:\.(?:tar\.xz|tar\.bz2|tar\.gz|tar\.zstd?|zip|tgz|tbz|txz))(?:\.(?:asc|pgp|gpg|sig|sign))";
+
+/// Regex that the `@DEB_EXT@` macro expands to.
+pub const DEB_EXT: &str = r"[\+~](debian|dfsg|ds|deb)(\.)?(\d+)?$";
+
+/// Expand the macros that uscan supports in patterns.
+///
+/// # Arguments
+/// * `pattern` - Pattern that may contain macros such as `@ANY_VERSION@`
+/// * `package` - Name of the source package, used for `@PACKAGE@`; if None,
+///   `@PACKAGE@` is left as is
+///
+/// # Returns
+/// the effective regular expression
+pub fn expand_macros(pattern: &str, package: Option<&str>) -> String {
+    let mut ret = pattern
+        .replace("@ANY_VERSION@", ANY_VERSION)
+        .replace("@ARCHIVE_EXT@", ARCHIVE_EXT)
+        .replace("@SIGNATURE_EXT@", SIGNATURE_EXT)
+        .replace("@DEB_EXT@", DEB_EXT);
+    if let Some(package) = package {
+        ret = ret.replace("@PACKAGE@", &regex::escape(package));
+    }
+    ret
+}
+
+/// Options that only make sense when scraping HTTP index pages.
+const HTTP_ONLY_OPTIONS: &[&str] = &[
+    "downloadurlmangle",
+    "filenamemangle",
+    "pagemangle",
+    "pgpsigurlmangle",
+    "searchmode",
+    "user-agent",
+];
+
+/// Field in version 5 watch files that holds the URL.
+const SOURCE_FIELD: &str = "Source";
+
+/// Field in version 5 watch files that holds the matching pattern.
+const MATCHING_PATTERN_FIELD: &str = "Matching-Pattern";
+
+/// Field in version 5 watch files that holds the version policy.
+const VERSION_POLICY_FIELD: &str = "Version-Policy";
+
+/// Field in version 5 watch files that holds the script to run.
+const SCRIPT_FIELD: &str = "Script";
+
+/// Field names used in version 5 watch files for options whose name is not
+/// simply the capitalized option name.
+const OPTION_FIELD_NAMES: &[(&str, &str)] = &[
+    ("dirversionmangle", "Dirversion-Mangle"),
+    ("downloadurlmangle", "Downloadurl-Mangle"),
+    ("dversionmangle", "Dversion-Mangle"),
+    ("filenamemangle", "Filename-Mangle"),
+    ("gitexport", "Git-Export"),
+    ("gitmode", "Git-Mode"),
+    ("oversionmangle", "Oversion-Mangle"),
+    ("pagemangle", "Page-Mangle"),
+    ("pgpmode", "Pgp-Mode"),
+    ("pgpsigurlmangle", "Pgpsigurl-Mangle"),
+    ("repacksuffix", "Repack-Suffix"),
+    ("searchmode", "Search-Mode"),
+    ("unzipopt", "Unzip-Opt"),
+    ("uversionmangle", "Uversion-Mangle"),
+    ("versionmangle", "Version-Mangle"),
+];
+
+/// Normalize a field or option name for comparison, e.g. "Pgp-Mode" -> "pgpmode".
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '-' && *c != '_')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Return the version 5 field name for an option.
+fn option_field_name(option: &str) -> String {
+    let normalized = normalize_name(option);
+    if let Some((_, field)) = OPTION_FIELD_NAMES.iter().find(|(o, _)| *o == normalized) {
+        return field.to_string();
+    }
+    option
+        .split('-')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Return the option name for a version 5 field name.
+fn field_option_name(field: &str) -> String {
+    let normalized = normalize_name(field);
+    if let Some((option, _)) = OPTION_FIELD_NAMES.iter().find(|(o, _)| *o == normalized) {
+        return option.to_string();
+    }
+    field.to_lowercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An error that occurred while parsing a watch file
+pub enum ParseError {
+    /// The version line could not be parsed
+    InvalidVersion(String),
+
+    /// The watch file uses a format version that is not supported
+    UnsupportedVersion(u32),
+
+    /// An entry is missing its URL
+    MissingUrl(usize),
+
+    /// The deb822 structure of a version 5 watch file is invalid
+    InvalidDeb822(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidVersion(line) => write!(f, "Invalid version line: {}", line),
+            ParseError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported watch file version: {}", version)
+            }
+            ParseError::MissingUrl(lineno) => write!(f, "Missing URL on line {}", lineno),
+            ParseError::InvalidDeb822(e) => write!(f, "Invalid deb822 watch file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An error that occurred while upgrading the format of a watch file
+pub enum UpgradeError {
+    /// The target version is older than the current version
+    Downgrade {
+        /// Current format version
+        from: u32,
+        /// Requested format version
+        to: u32,
+    },
+
+    /// Upgrading from or to this version is not supported
+    UnsupportedVersion(u32),
+
+    /// The watch file has parts that could not be parsed
+    Unparsed(ParseError),
+}
+
+impl std::fmt::Display for UpgradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            UpgradeError::Downgrade { from, to } => {
+                write!(
+                    f,
+                    "Can not downgrade watch file from version {} to {}",
+                    from, to
+                )
+            }
+            UpgradeError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported watch file version: {}", version)
+            }
+            UpgradeError::Unparsed(e) => write!(f, "Unable to upgrade unparsed watch file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for UpgradeError {}
+
+/// Length of the whitespace (including line continuations) at the start of `s`.
+fn whitespace_len(s: &str) -> usize {
+    let mut i = 0;
+    let bytes = s.as_bytes();
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' => i += 1,
+            b'\\' if bytes.get(i + 1) == Some(&b'\n') => i += 2,
+            b'\\' if bytes.get(i + 1) == Some(&b'\r') && bytes.get(i + 2) == Some(&b'\n') => i += 3,
+            _ => break,
+        }
+    }
+    i
+}
+
+/// Length of the whitespace (including line continuations) at the end of `s`.
+fn trailing_whitespace_len(s: &str) -> usize {
+    let mut start = s.len();
+    loop {
+        let rest = &s[..start];
+        if rest.ends_with(' ') || rest.ends_with('\t') {
+            start -= 1;
+        } else if rest.ends_with("\\\n") {
+            start -= 2;
+        } else {
+            break;
+        }
+    }
+    s.len() - start
+}
+
+/// Remove line continuations from a token.
+fn join_continuations(s: &str) -> String {
+    lazy_regex::regex_replace_all!(r"\\\r?\n[ \t]*", s, "").into_owned()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The parsed `opts=` token of a line-based entry.
+struct OptsToken {
+    /// Text before the first option, e.g. `opts="`
+    prefix: String,
+
+    /// The options, each with the separator that precedes it
+    items: Vec<(String, String)>,
+
+    /// Text after the last option, e.g. `"`
+    suffix: String,
+}
+
+impl OptsToken {
+    fn parse(token: &str) -> Self {
+        let (prefix_len, quoted) = if token.starts_with("opts=\"") {
+            (6, true)
+        } else {
+            (5, false)
+        };
+        let inner_end = if quoted && token.len() > prefix_len && token.ends_with('"') {
+            token.len() - 1
+        } else {
+            token.len()
+        };
+        let inner = &token[prefix_len..inner_end];
+        let mut segments = vec![];
+        let mut start = 0;
+        let mut prev = None;
+        for (i, c) in inner.char_indices() {
+            if c == ',' && prev != Some('\\') {
+                segments.push((start, i));
+                start = i + 1;
+            }
+            prev = Some(c);
+        }
+        segments.push((start, inner.len()));
+
+        let mut items = vec![];
+        let mut pending = String::new();
+        for (i, (start, end)) in segments.iter().enumerate() {
+            let segment = &inner[*start..*end];
+            let lead = whitespace_len(segment);
+            let trail = trailing_whitespace_len(&segment[lead..]);
+            let item = &segment[lead..segment.len() - trail];
+            if i > 0 {
+                pending.push(',');
+            }
+            pending.push_str(&segment[..lead]);
+            if item.is_empty() {
+                pending.push_str(&segment[lead..]);
+                continue;
+            }
+            items.push((std::mem::take(&mut pending), item.to_string()));
+            pending.push_str(&segment[segment.len() - trail..]);
+        }
+        let mut suffix = pending;
+        suffix.push_str(&token[inner_end..]);
+        OptsToken {
+            prefix: token[..prefix_len].to_string(),
+            items,
+            suffix,
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut ret = self.prefix.clone();
+        for (sep, item) in &self.items {
+            ret.push_str(sep);
+            ret.push_str(item);
+        }
+        ret.push_str(&self.suffix);
+        ret
+    }
+
+    fn options(&self) -> Vec<(String, Option<String>)> {
+        self.items
+            .iter()
+            .map(|(_, item)| {
+                let item = join_continuations(item);
+                match item.split_once('=') {
+                    Some((k, v)) => (k.trim().to_string(), Some(v.trim().to_string())),
+                    None => (item.trim().to_string(), None),
+                }
+            })
+            .collect()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        let name = normalize_name(name);
+        self.options()
+            .iter()
+            .position(|(k, _)| normalize_name(k) == name)
+    }
+
+    fn set(&mut self, name: &str, value: Option<&str>) {
+        let item = match value {
+            Some(value) => format!("{}={}", name, value),
+            None => name.to_string(),
+        };
+        if let Some(idx) = self.position(name) {
+            self.items[idx].1 = item;
+        } else {
+            let sep = if self.items.is_empty() {
+                String::new()
+            } else {
+                ",".to_string()
+            };
+            self.items.push((sep, item));
+        }
+        if !self.prefix.ends_with('"') && item_needs_quoting(&self.render()) {
+            self.prefix.push('"');
+            self.suffix.push('"');
+        }
+    }
+
+    fn remove(&mut self, name: &str) -> bool {
+        if let Some(idx) = self.position(name) {
+            let (sep, _) = self.items.remove(idx);
+            if idx == 0 {
+                if let Some(next) = self.items.first_mut() {
+                    next.0 = sep;
+                }
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn item_needs_quoting(token: &str) -> bool {
+    token.contains([' ', '\t'])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An entry in a line-based (version 3 or 4) watch file.
+pub struct LineEntry {
+    /// Tokens, each with the whitespace that precedes it
+    tokens: Vec<(String, String)>,
+
+    /// Whitespace and newline after the last token
+    trailing: String,
+}
+
+impl LineEntry {
+    fn parse(text: &str) -> Self {
+        let mut tokens = vec![];
+        let mut pos = 0;
+        let body_end = text.len() - text.len().min(trailing_newline_len(text));
+        let body = &text[..body_end];
+        while pos < body.len() {
+            let ws = whitespace_len(&body[pos..]);
+            if pos + ws >= body.len() {
+                break;
+            }
+            let sep = body[pos..pos + ws].to_string();
+            pos += ws;
+            let start = pos;
+            let mut in_quotes = false;
+            while pos < body.len() {
+                let rest = &body[pos..];
+                let c = rest.chars().next().unwrap();
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                } else if !in_quotes && whitespace_len(rest) > 0 {
+                    break;
+                }
+                pos += c.len_utf8();
+            }
+            tokens.push((sep, body[start..pos].to_string()));
+        }
+        LineEntry {
+            tokens,
+            trailing: text[pos..].to_string(),
+        }
+    }
+
+    fn has_opts(&self) -> bool {
+        self.tokens
+            .first()
+            .map(|(_, t)| t.starts_with("opts="))
+            .unwrap_or(false)
+    }
+
+    fn field_offset(&self) -> usize {
+        if self.has_opts() {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Whether the URL and matching pattern are combined in a single token.
+    fn is_combined(&self) -> bool {
+        let url = match self.tokens.get(self.field_offset()) {
+            Some((_, url)) => url,
+            None => return false,
+        };
+        url.rsplit_once('/')
+            .map(|(_, last)| last.contains('('))
+            .unwrap_or(false)
+    }
+
+    fn field(&self, idx: usize) -> Option<String> {
+        let combined = self.is_combined();
+        let offset = self.field_offset();
+        let url = &self.tokens.get(offset)?.1;
+        match (idx, combined) {
+            (0, true) => Some(url.rsplit_once('/').unwrap().0.to_string()),
+            (1, true) => Some(url.rsplit_once('/').unwrap().1.to_string()),
+            (i, true) => self.tokens.get(offset + i - 1).map(|(_, t)| t.clone()),
+            (i, false) => self.tokens.get(offset + i).map(|(_, t)| t.clone()),
+        }
+    }
+
+    fn set_field(&mut self, idx: usize, value: &str) {
+        let combined = self.is_combined();
+        let offset = self.field_offset();
+        let token_idx = match (idx, combined) {
+            (0, true) => {
+                let url = &mut self.tokens[offset].1;
+                let (_, pattern) = url.rsplit_once('/').unwrap();
+                *url = format!("{}/{}", value.trim_end_matches('/'), pattern);
+                return;
+            }
+            (1, true) => {
+                let url = &mut self.tokens[offset].1;
+                let (base, _) = url.rsplit_once('/').unwrap();
+                *url = format!("{}/{}", base, value);
+                return;
+            }
+            (i, true) => offset + i - 1,
+            (i, false) => offset + i,
+        };
+        while self.tokens.len() < token_idx {
+            // Fill in missing intermediate fields with their defaults
+            let default = match self.tokens.len() - offset + usize::from(combined) {
+                2 => "debian",
+                _ => "",
+            };
+            self.tokens.push((" ".to_string(), default.to_string()));
+        }
+        if let Some(token) = self.tokens.get_mut(token_idx) {
+            token.1 = value.to_string();
+        } else {
+            let sep = if self.tokens.is_empty() { "" } else { " " };
+            self.tokens.push((sep.to_string(), value.to_string()));
+        }
+    }
+
+    fn opts(&self) -> Option<OptsToken> {
+        if self.has_opts() {
+            Some(OptsToken::parse(&self.tokens[0].1))
+        } else {
+            None
+        }
+    }
+
+    fn set_opts(&mut self, opts: Option<OptsToken>) {
+        match (opts, self.has_opts()) {
+            (Some(opts), _) if opts.items.is_empty() => self.set_opts(None),
+            (Some(opts), true) => self.tokens[0].1 = opts.render(),
+            (Some(opts), false) => {
+                let lead = self
+                    .tokens
+                    .first_mut()
+                    .map(|t| std::mem::replace(&mut t.0, " ".to_string()))
+                    .unwrap_or_default();
+                self.tokens.insert(0, (lead, opts.render()));
+            }
+            (None, true) => {
+                let (lead, _) = self.tokens.remove(0);
+                if let Some(first) = self.tokens.first_mut() {
+                    first.0 = lead;
+                }
+            }
+            (None, false) => {}
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut ret = String::new();
+        for (sep, token) in &self.tokens {
+            ret.push_str(sep);
+            ret.push_str(token);
+        }
+        ret.push_str(&self.trailing);
+        ret
+    }
+}
+
+fn trailing_newline_len(text: &str) -> usize {
+    if text.ends_with("\r\n") {
+        2
+    } else if text.ends_with('\n') {
+        1
+    } else {
+        0
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An entry in a watch file.
+pub enum Entry {
+    /// Entry in a line-based (version 3 or 4) watch file
+    Line(LineEntry),
+
+    /// Entry in a deb822-based (version 5) watch file
+    Paragraph(Paragraph),
+}
+
+impl Entry {
+    fn get_field(paragraph: &Paragraph, name: &str) -> Option<String> {
+        let normalized = normalize_name(name);
+        paragraph
+            .keys()
+            .find(|k| normalize_name(k) == normalized)
+            .and_then(|k| paragraph.get(&k))
+    }
+
+    fn set_field(paragraph: &mut Paragraph, name: &str, value: &str) {
+        let normalized = normalize_name(name);
+        let key = paragraph
+            .keys()
+            .find(|k| normalize_name(k) == normalized)
+            .unwrap_or_else(|| name.to_string());
+        paragraph.set(&key, value);
+    }
+
+    fn remove_field(paragraph: &mut Paragraph, name: &str) -> bool {
+        let normalized = normalize_name(name);
+        let key = paragraph.keys().find(|k| normalize_name(k) == normalized);
+        if let Some(key) = key {
+            paragraph.remove(&key);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Return the URL of the entry.
+    pub fn url(&self) -> Option<String> {
+        match self {
+            Entry::Line(e) => e.field(0),
+            Entry::Paragraph(p) => Self::get_field(p, SOURCE_FIELD),
+        }
+    }
+
+    /// Set the URL of the entry.
+    pub fn set_url(&mut self, url: &str) {
+        match self {
+            Entry::Line(e) => e.set_field(0, url),
+            Entry::Paragraph(p) => Self::set_field(p, SOURCE_FIELD, url),
+        }
+    }
+
+    /// Return the pattern that upstream files are matched against.
+    pub fn matching_pattern(&self) -> Option<String> {
+        match self {
+            Entry::Line(e) => e.field(1),
+            Entry::Paragraph(p) => Self::get_field(p, MATCHING_PATTERN_FIELD),
+        }
+    }
+
+    /// Return the pattern that upstream files are matched against, with
+    /// uscan macros expanded.
+    ///
+    /// # Arguments
+    /// * `package` - Name of the source package, used for `@PACKAGE@`
+    pub fn effective_matching_pattern(&self, package: Option<&str>) -> Option<String> {
+        self.matching_pattern()
+            .map(|pattern| expand_macros(&pattern, package))
+    }
+
+    /// Set the pattern that upstream files are matched against.
+    pub fn set_matching_pattern(&mut self, pattern: &str) {
+        match self {
+            Entry::Line(e) => e.set_field(1, pattern),
+            Entry::Paragraph(p) => Self::set_field(p, MATCHING_PATTERN_FIELD, pattern),
+        }
+    }
+
+    /// Return the version policy of the entry, e.g. "debian" or "same".
+    pub fn version_policy(&self) -> Option<String> {
+        match self {
+            Entry::Line(e) => e.field(2),
+            Entry::Paragraph(p) => Self::get_field(p, VERSION_POLICY_FIELD),
+        }
+    }
+
+    /// Set the version policy of the entry.
+    pub fn set_version_policy(&mut self, policy: &str) {
+        match self {
+            Entry::Line(e) => e.set_field(2, policy),
+            Entry::Paragraph(p) => Self::set_field(p, VERSION_POLICY_FIELD, policy),
+        }
+    }
+
+    /// Return the script to run after a download, e.g. "uupdate".
+    pub fn script(&self) -> Option<String> {
+        match self {
+            Entry::Line(e) => e.field(3),
+            Entry::Paragraph(p) => Self::get_field(p, SCRIPT_FIELD),
+        }
+    }
+
+    /// Set the script to run after a download.
+    pub fn set_script(&mut self, script: &str) {
+        match self {
+            Entry::Line(e) => e.set_field(3, script),
+            Entry::Paragraph(p) => Self::set_field(p, SCRIPT_FIELD, script),
+        }
+    }
+
+    /// Return the mode of the entry, e.g. "git"; "lwp" if none is set.
+    pub fn mode(&self) -> String {
+        self.option("mode").unwrap_or_else(|| "lwp".to_string())
+    }
+
+    /// Return the options of the entry, in order.
+    ///
+    /// Options without a value (e.g. "repack") have a value of None.
+    pub fn options(&self) -> Vec<(String, Option<String>)> {
+        match self {
+            Entry::Line(e) => e.opts().map(|o| o.options()).unwrap_or_default(),
+            Entry::Paragraph(p) => p
+                .items()
+                .filter(|(k, _)| {
+                    ![
+                        SOURCE_FIELD,
+                        MATCHING_PATTERN_FIELD,
+                        VERSION_POLICY_FIELD,
+                        SCRIPT_FIELD,
+                    ]
+                    .iter()
+                    .any(|f| normalize_name(f) == normalize_name(k))
+                })
+                .map(|(k, v)| {
+                    let value = if v == "yes" { None } else { Some(v) };
+                    (field_option_name(&k), value)
+                })
+                .collect(),
+        }
+    }
+
+    /// Check whether an option is set.
+    pub fn has_option(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        self.options()
+            .iter()
+            .any(|(k, _)| normalize_name(k) == name)
+    }
+
+    /// Return the value of an option.
+    ///
+    /// Returns None if the option is not set or has no value.
+    pub fn option(&self, name: &str) -> Option<String> {
+        let name = normalize_name(name);
+        self.options()
+            .into_iter()
+            .find(|(k, _)| normalize_name(k) == name)
+            .and_then(|(_, v)| v)
+    }
+
+    /// Set an option, replacing any existing value.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the option, e.g. "pgpmode"
+    /// * `value` - Value of the option; None for flags such as "repack"
+    pub fn set_option(&mut self, name: &str, value: Option<&str>) {
+        match self {
+            Entry::Line(e) => {
+                let mut opts = e.opts().unwrap_or_else(|| OptsToken {
+                    prefix: "opts=".to_string(),
+                    items: vec![],
+                    suffix: String::new(),
+                });
+                opts.set(name, value);
+                e.set_opts(Some(opts));
+            }
+            Entry::Paragraph(p) => {
+                Self::set_field(p, &option_field_name(name), value.unwrap_or("yes"))
+            }
+        }
+    }
+
+    /// Remove an option.
+    ///
+    /// # Returns
+    /// whether the option was present
+    pub fn remove_option(&mut self, name: &str) -> bool {
+        match self {
+            Entry::Line(e) => {
+                let mut opts = match e.opts() {
+                    Some(opts) => opts,
+                    None => return false,
+                };
+                let removed = opts.remove(name);
+                if removed {
+                    e.set_opts(Some(opts));
+                }
+                removed
+            }
+            Entry::Paragraph(p) => Self::remove_field(p, &option_field_name(name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    /// Blank line, comment or other text that is preserved as is
+    Other(String),
+
+    /// The version line
+    Version(String),
+
+    /// An entry
+    Entry(Entry),
+
+    /// Text that could not be parsed, preserved as is
+    Invalid(String, ParseError),
+}
+
+#[derive(Debug, Clone)]
+enum Body {
+    Lines(Vec<Line>),
+    Deb822 { doc: Deb822, entries: Vec<Entry> },
+}
+
+#[derive(Debug, Clone)]
+/// A debian/watch file.
+pub struct WatchFile {
+    version: u32,
+    body: Body,
+}
+
+impl Default for WatchFile {
+    fn default() -> Self {
+        Self::new(DEFAULT_VERSION)
+    }
+}
+
+impl WatchFile {
+    /// Create a new, empty watch file.
+    ///
+    /// # Arguments
+    /// * `version` - Format version of the watch file
+    pub fn new(version: u32) -> Self {
+        let body = if version >= 5 {
+            let mut doc = Deb822::new();
+            let mut header = doc.add_paragraph();
+            header.set("Version", &version.to_string());
+            Body::Deb822 {
+                doc,
+                entries: vec![],
+            }
+        } else {
+            Body::Lines(vec![Line::Version(format!("version={}\n", version))])
+        };
+        WatchFile { version, body }
+    }
+
+    /// Return the format version of the watch file.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Iterate over the entries in the watch file.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> + '_ {
+        let ret: Box<dyn Iterator<Item = &Entry>> = match &self.body {
+            Body::Lines(lines) => Box::new(lines.iter().filter_map(|l| match l {
+                Line::Entry(e) => Some(e),
+                _ => None,
+            })),
+            Body::Deb822 { entries, .. } => Box::new(entries.iter()),
+        };
+        ret
+    }
+
+    /// Iterate mutably over the entries in the watch file.
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut Entry> + '_ {
+        let ret: Box<dyn Iterator<Item = &mut Entry>> = match &mut self.body {
+            Body::Lines(lines) => Box::new(lines.iter_mut().filter_map(|l| match l {
+                Line::Entry(e) => Some(e),
+                _ => None,
+            })),
+            Body::Deb822 { entries, .. } => Box::new(entries.iter_mut()),
+        };
+        ret
+    }
+
+    /// Check whether the watch file has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries().next().is_none()
+    }
+
+    /// Add a new entry to the watch file.
+    ///
+    /// # Arguments
+    /// * `url` - URL to check for new upstream releases
+    /// * `matching_pattern` - Pattern to match upstream files against
+    /// * `options` - Options for the entry; flags have a value of None
+    ///
+    /// # Returns
+    /// the new entry
+    pub fn add_entry(
+        &mut self,
+        url: &str,
+        matching_pattern: Option<&str>,
+        options: &[(&str, Option<&str>)],
+    ) -> &mut Entry {
+        let mut entry = match &mut self.body {
+            Body::Lines(_) => {
+                let mut text = url.to_string();
+                if let Some(pattern) = matching_pattern {
+                    text.push(' ');
+                    text.push_str(pattern);
+                }
+                text.push('\n');
+                Entry::Line(LineEntry::parse(&text))
+            }
+            Body::Deb822 { doc, .. } => {
+                let mut paragraph = doc.add_paragraph();
+                paragraph.set(SOURCE_FIELD, url);
+                if let Some(pattern) = matching_pattern {
+                    paragraph.set(MATCHING_PATTERN_FIELD, pattern);
+                }
+                Entry::Paragraph(paragraph)
+            }
+        };
+        for (name, value) in options {
+            entry.set_option(name, *value);
+        }
+        match &mut self.body {
+            Body::Lines(lines) => {
+                if let Some(Line::Other(last) | Line::Invalid(last, _)) = lines.last_mut() {
+                    if !last.ends_with('\n') {
+                        last.push('\n');
+                    }
+                } else if let Some(Line::Entry(Entry::Line(last))) = lines.last_mut() {
+                    if !last.trailing.ends_with('\n') {
+                        last.trailing.push('\n');
+                    }
+                }
+                lines.push(Line::Entry(entry));
+                match lines.last_mut() {
+                    Some(Line::Entry(e)) => e,
+                    _ => unreachable!(),
+                }
+            }
+            Body::Deb822 { entries, .. } => {
+                entries.push(entry);
+                entries.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Remove the entry at the given index.
+    ///
+    /// # Returns
+    /// whether an entry was removed
+    pub fn remove_entry(&mut self, index: usize) -> bool {
+        match &mut self.body {
+            Body::Lines(lines) => {
+                let pos = lines
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, l)| matches!(l, Line::Entry(_)))
+                    .nth(index)
+                    .map(|(i, _)| i);
+                if let Some(pos) = pos {
+                    lines.remove(pos);
+                    true
+                } else {
+                    false
+                }
+            }
+            Body::Deb822 { doc, entries } => {
+                if index >= entries.len() {
+                    return false;
+                }
+                entries.remove(index);
+                // The first paragraph is the header
+                doc.remove_paragraph(index + 1);
+                true
+            }
+        }
+    }
+
+    /// Add an entry that tracks version tags in a git repository.
+    ///
+    /// # Arguments
+    /// * `repository` - URL of the git repository
+    /// * `tag_pattern` - Pattern for tags; defaults to [`GIT_TAG_PATTERN`]
+    pub fn add_git_tags_entry(
+        &mut self,
+        repository: &str,
+        tag_pattern: Option<&str>,
+    ) -> &mut Entry {
+        self.add_entry(
+            repository,
+            Some(tag_pattern.unwrap_or(GIT_TAG_PATTERN)),
+            &[("mode", Some("git"))],
+        )
+    }
+
+    /// Add an entry that tracks the head of a git branch.
+    ///
+    /// # Arguments
+    /// * `repository` - URL of the git repository
+    /// * `pretty` - Format for the snapshot version; defaults to [`DEFAULT_GIT_PRETTY`]
+    pub fn add_git_head_entry(&mut self, repository: &str, pretty: Option<&str>) -> &mut Entry {
+        self.add_entry(
+            repository,
+            Some("HEAD"),
+            &[
+                ("mode", Some("git")),
+                ("pretty", Some(pretty.unwrap_or(DEFAULT_GIT_PRETTY))),
+            ],
+        )
+    }
+
+    /// Add an entry that tracks release tags in a subversion repository.
+    ///
+    /// # Arguments
+    /// * `tags_url` - URL of the tags directory in the subversion repository
+    pub fn add_svn_tags_entry(&mut self, tags_url: &str) -> &mut Entry {
+        self.add_entry(tags_url, Some(r"v?(\d[\d.]*)/"), &[("mode", Some("svn"))])
+    }
+
+    /// Add an entry that tracks the head of a subversion branch.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the branch in the subversion repository
+    /// * `pretty` - Format for the snapshot version; defaults to [`DEFAULT_SVN_PRETTY`]
+    pub fn add_svn_head_entry(&mut self, url: &str, pretty: Option<&str>) -> &mut Entry {
+        self.add_entry(
+            url,
+            Some("HEAD"),
+            &[
+                ("mode", Some("svn")),
+                ("pretty", Some(pretty.unwrap_or(DEFAULT_SVN_PRETTY))),
+            ],
+        )
+    }
+
+    /// Upgrade the watch file to a newer format version.
+    ///
+    /// Upgrading from version 3 to 4 only changes the version line. Upgrading
+    /// to version 5 converts every entry to a deb822 paragraph, splitting
+    /// combined URL and matching pattern fields and translating option names
+    /// to field names. Comments are kept; blank lines between entries are not.
+    ///
+    /// # Arguments
+    /// * `target_version` - Format version to upgrade to
+    ///
+    /// # Returns
+    /// whether the watch file was changed
+    pub fn upgrade_format(&mut self, target_version: u32) -> Result<bool, UpgradeError> {
+        if let Some(e) = self.parse_errors().into_iter().next() {
+            return Err(UpgradeError::Unparsed(e));
+        }
+        if target_version == self.version {
+            return Ok(false);
+        }
+        if target_version < self.version {
+            return Err(UpgradeError::Downgrade {
+                from: self.version,
+                to: target_version,
+            });
+        }
+        if self.version < 3 {
+            return Err(UpgradeError::UnsupportedVersion(self.version));
+        }
+        if target_version > 5 {
+            return Err(UpgradeError::UnsupportedVersion(target_version));
+        }
+        let lines = match &mut self.body {
+            Body::Lines(lines) => lines,
+            Body::Deb822 { .. } => unreachable!(),
+        };
+        if target_version == 4 {
+            for line in lines.iter_mut() {
+                if let Line::Version(text) = line {
+                    *text = text.replacen(&self.version.to_string(), "4", 1);
+                }
+            }
+            self.version = 4;
+            return Ok(true);
+        }
+
+        let mut text = String::new();
+        let mut pending = String::new();
+        let mut seen_version = false;
+        for line in lines.iter() {
+            match line {
+                Line::Other(other) => {
+                    if other.trim().starts_with('#') {
+                        pending.push_str(other);
+                    }
+                }
+                Line::Version(_) => {
+                    text.push_str(&std::mem::take(&mut pending));
+                    text.push_str(&format!("Version: {}\n", target_version));
+                    seen_version = true;
+                }
+                Line::Entry(Entry::Line(e)) => {
+                    if !seen_version {
+                        text.push_str(&format!("Version: {}\n", target_version));
+                        seen_version = true;
+                    }
+                    text.push('\n');
+                    text.push_str(&std::mem::take(&mut pending));
+                    let fields = [
+                        (SOURCE_FIELD, e.field(0)),
+                        (MATCHING_PATTERN_FIELD, e.field(1)),
+                        (VERSION_POLICY_FIELD, e.field(2)),
+                        (SCRIPT_FIELD, e.field(3)),
+                    ];
+                    for (name, value) in fields {
+                        if let Some(value) = value {
+                            text.push_str(&format!("{}: {}\n", name, value));
+                        }
+                    }
+                    for (name, value) in e.opts().map(|o| o.options()).unwrap_or_default() {
+                        text.push_str(&format!(
+                            "{}: {}\n",
+                            option_field_name(&name),
+                            value.as_deref().unwrap_or("yes")
+                        ));
+                    }
+                }
+                Line::Entry(Entry::Paragraph(_)) | Line::Invalid(..) => unreachable!(),
+            }
+        }
+        if !pending.is_empty() {
+            text.push('\n');
+            text.push_str(&pending);
+        }
+        // The generated text is always a well-formed deb822 document
+        *self = Self::parse_deb822(&text).unwrap();
+        Ok(true)
+    }
+
+    fn parse_lines(text: &str) -> Self {
+        let mut lines = vec![];
+        let mut version = None;
+        let mut physical = text.split_inclusive('\n').enumerate().peekable();
+        while let Some((lineno, line)) = physical.next() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                lines.push(Line::Other(line.to_string()));
+                continue;
+            }
+            let mut logical = line.to_string();
+            while logical.trim_end_matches(['\n', '\r']).ends_with('\\') {
+                match physical.next() {
+                    Some((_, next)) => logical.push_str(next),
+                    None => break,
+                }
+            }
+            if version.is_none() && lines.iter().all(|l| matches!(l, Line::Other(_))) {
+                if let Some(v) = trimmed.strip_prefix("version=") {
+                    match v.trim().parse::<u32>() {
+                        Ok(v) => {
+                            version = Some(v);
+                            lines.push(Line::Version(logical));
+                        }
+                        Err(_) => lines.push(Line::Invalid(
+                            logical,
+                            ParseError::InvalidVersion(trimmed.to_string()),
+                        )),
+                    }
+                    continue;
+                }
+            }
+            let entry = LineEntry::parse(&logical);
+            if entry.field(0).is_none() {
+                lines.push(Line::Invalid(logical, ParseError::MissingUrl(lineno + 1)));
+                continue;
+            }
+            lines.push(Line::Entry(Entry::Line(entry)));
+        }
+        WatchFile {
+            // Files without a version line are in the (obsolete) version 1 format
+            version: version.unwrap_or(1),
+            body: Body::Lines(lines),
+        }
+    }
+
+    /// Parse a watch file, keeping the parts that can not be parsed.
+    ///
+    /// Lines that can not be parsed, or the whole file if it is a deb822
+    /// watch file that can not be parsed, are preserved as they are but are
+    /// not available as entries. This allows editing and repairing a broken
+    /// watch file.
+    ///
+    /// # Returns
+    /// the watch file and the problems that were found
+    pub fn from_str_relaxed(text: &str) -> (Self, Vec<ParseError>) {
+        let ret = if Self::is_deb822(text) {
+            match Self::parse_deb822(text).and_then(|ret| {
+                if ret.version < 5 {
+                    Err(ParseError::UnsupportedVersion(ret.version))
+                } else {
+                    Ok(ret)
+                }
+            }) {
+                Ok(ret) => ret,
+                Err(e) => WatchFile {
+                    version: match e {
+                        ParseError::UnsupportedVersion(version) => version,
+                        _ => 1,
+                    },
+                    body: Body::Lines(vec![Line::Invalid(text.to_string(), e)]),
+                },
+            }
+        } else {
+            Self::parse_lines(text)
+        };
+        let errors = ret.parse_errors();
+        (ret, errors)
+    }
+
+    /// Return the problems found while parsing the watch file.
+    fn parse_errors(&self) -> Vec<ParseError> {
+        match &self.body {
+            Body::Lines(lines) => lines
+                .iter()
+                .filter_map(|l| match l {
+                    Line::Invalid(_, e) => Some(e.clone()),
+                    _ => None,
+                })
+                .collect(),
+            Body::Deb822 { .. } => vec![],
+        }
+    }
+
+    fn is_deb822(text: &str) -> bool {
+        text.lines()
+            .map(|l| l.trim())
+            .find(|l| !l.is_empty() && !l.starts_with('#'))
+            .is_some_and(|line| normalize_name(line).starts_with("version:"))
+    }
+
+    fn parse_deb822(text: &str) -> Result<Self, ParseError> {
+        let doc = Deb822::from_str_relaxed(text).0;
+        let mut paragraphs = doc.paragraphs();
+        let header = paragraphs
+            .next()
+            .ok_or_else(|| ParseError::InvalidDeb822("missing header paragraph".to_string()))?;
+        let version = header
+            .get("Version")
+            .ok_or_else(|| ParseError::InvalidDeb822("missing Version field".to_string()))?;
+        let version = version
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| ParseError::InvalidVersion(version.to_string()))?;
+        let entries = paragraphs.map(Entry::Paragraph).collect();
+        Ok(WatchFile {
+            version,
+            body: Body::Deb822 { doc, entries },
+        })
+    }
+}
+
+impl std::str::FromStr for WatchFile {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (ret, errors) = Self::from_str_relaxed(text);
+        match errors.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(ret),
+        }
+    }
+}
+
+impl std::fmt::Display for WatchFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.body {
+            Body::Lines(lines) => {
+                for line in lines {
+                    match line {
+                        Line::Other(text) | Line::Version(text) | Line::Invalid(text, _) => {
+                            f.write_str(text)?
+                        }
+                        Line::Entry(Entry::Line(e)) => f.write_str(&e.render())?,
+                        Line::Entry(Entry::Paragraph(_)) => unreachable!(),
+                    }
+                }
+                Ok(())
+            }
+            Body::Deb822 { doc, .. } => write!(f, "{}", doc),
+        }
+    }
+}
+
+impl crate::editor::Marshallable for WatchFile {
+    fn from_bytes(content: &[u8]) -> Self {
+        // Broken watch files are opened as well, so that they can be repaired
+        WatchFile::from_str_relaxed(&String::from_utf8_lossy(content)).0
+    }
+
+    fn empty() -> Self {
+        WatchFile::default()
+    }
+
+    fn to_bytes(&self) -> Option<Vec<u8>> {
+        if self.is_empty() && self.parse_errors().is_empty() {
+            None
+        } else {
+            Some(self.to_string().into_bytes())
+        }
+    }
+}
+
+/// Convert an entry that scrapes an HTTP index page to one that tracks git tags.
+///
+/// This is useful when the upstream repository is known, e.g. from the
+/// `Repository` field in the DEP-12 upstream metadata. Version mangling
+/// options are kept; options that only apply to HTTP downloads are dropped.
+///
+/// # Arguments
+/// * `entry` - Entry to convert
+/// * `repository` - URL of the upstream git repository
+///
+/// # Returns
+/// whether the entry was changed; entries that already use `mode=git` are
+/// left alone
+pub fn convert_to_git_tags(entry: &mut Entry, repository: &str) -> bool {
+    if entry.mode() == "git" {
+        return false;
+    }
+    for option in HTTP_ONLY_OPTIONS {
+        entry.remove_option(option);
+    }
+    if entry.option("pgpmode").as_deref() != Some("none") {
+        entry.remove_option("pgpmode");
+    }
+    entry.set_option("mode", Some("git"));
+    entry.set_url(repository);
+    entry.set_matching_pattern(GIT_TAG_PATTERN);
+    true
+}
+
+/// Paths, relative to the packaging root, of the upstream signing key.
+pub const SIGNING_KEY_PATHS: &[&str] = &[
+    "debian/upstream/signing-key.asc",
+    "debian/upstream/signing-key.pgp",
+    "debian/upstream-signing-key.pgp",
+];
+
+/// Check whether the package ships an upstream signing key.
+///
+/// # Arguments
+/// * `base` - Root of the source package
+pub fn has_signing_key(base: &std::path::Path) -> bool {
+    SIGNING_KEY_PATHS.iter().any(|p| base.join(p).exists())
+}
+
+/// Enable verification of upstream signatures for an entry.
+///
+/// This should only be used if the package ships an upstream signing key,
+/// see [`has_signing_key`].
+///
+/// # Arguments
+/// * `entry` - Entry to modify
+/// * `pgpsigurlmangle` - Mangling rule to find the signature URL; if None,
+///   `pgpmode=auto` is used to find signatures next to the tarball
+///
+/// # Returns
+/// whether the entry was changed; entries that already configure signature
+/// verification or that do not download tarballs over HTTP are left alone
+pub fn add_signature_verification(entry: &mut Entry, pgpsigurlmangle: Option<&str>) -> bool {
+    if entry.mode() != "lwp" || entry.has_option("pgpmode") || entry.has_option("pgpsigurlmangle") {
+        return false;
+    }
+    match pgpsigurlmangle {
+        Some(mangle) => entry.set_option("pgpsigurlmangle", Some(mangle)),
+        None => entry.set_option("pgpmode", Some("auto")),
+    }
+    true
+}
+
+/// Switch plain HTTP URLs in an entry to HTTPS.
+///
+/// # Returns
+/// whether the entry was changed
+pub fn use_https(entry: &mut Entry) -> bool {
+    let mut changed = false;
+    if let Some(url) = entry.url() {
+        if let Some(rest) = url.strip_prefix("http://") {
+            entry.set_url(&format!("https://{}", rest));
+            changed = true;
+        }
+    }
+    if let Some(pattern) = entry.matching_pattern() {
+        if let Some(rest) = pattern.strip_prefix("http://") {
+            entry.set_matching_pattern(&format!("https://{}", rest));
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Return the last path component of a pattern.
+fn pattern_basename(pattern: &str) -> &str {
+    pattern.rsplit('/').next().unwrap_or(pattern)
+}
+
+/// Point an entry at the tags page of a GitHub repository.
+fn set_github_tags(entry: &mut Entry, owner: &str, repo: &str) {
+    let filenamemangle = format!(r"s%.*/v?(\d\S+)\.tar\.gz%{}-$1.tar.gz%", repo);
+    entry.set_url(&format!("https://github.com/{}/{}/tags", owner, repo));
+    entry.set_matching_pattern(r".*/v?(\d\S+)\.tar\.gz");
+    if !entry.has_option("filenamemangle") {
+        entry.set_option("filenamemangle", Some(filenamemangle.as_str()));
+    }
+}
+
+/// Point an entry at the downloads of a project in the Google Code archive.
+fn set_google_code_archive(entry: &mut Entry, project: &str) -> bool {
+    let pattern = match entry.matching_pattern() {
+        Some(pattern) => pattern_basename(&pattern).to_string(),
+        None => return false,
+    };
+    entry.set_url(&format!(
+        "https://storage.googleapis.com/google-code-archive-downloads/?prefix=v2/code.google.com/{}/",
+        project
+    ));
+    entry.set_matching_pattern(&format!("v2/code.google.com/{}/{}", project, pattern));
+    entry.set_option("searchmode", Some("plain"));
+    true
+}
+
+/// Replace URLs of redirectors and hosting sites that no longer exist.
+///
+/// The following are handled:
+/// * `githubredir.debian.net`, replaced by the GitHub tags page
+/// * `qa.debian.org/watch/sf.php`, replaced by `sf.net`
+/// * `code.google.com` and `googlecode.debian.net`, replaced by the
+///   Google Code archive
+///
+/// # Returns
+/// whether the entry was changed
+pub fn fix_dead_redirectors(entry: &mut Entry) -> bool {
+    let url = match entry.url() {
+        Some(url) => url,
+        None => return false,
+    };
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.trim_end_matches('/'),
+        None => return false,
+    };
+
+    if let Some(path) = rest.strip_prefix("githubredir.debian.net/github/") {
+        let mut parts = path.split('/');
+        let (owner, repo) = match (parts.next(), parts.next()) {
+            (Some(owner), Some(repo)) if !owner.is_empty() && !repo.is_empty() => (owner, repo),
+            _ => return false,
+        };
+        set_github_tags(entry, owner, repo);
+        return true;
+    }
+
+    if let Some(path) = rest.strip_prefix("qa.debian.org/watch/sf.php/") {
+        entry.set_url(&format!("https://sf.net/{}", path));
+        return true;
+    }
+
+    let project = rest
+        .strip_prefix("code.google.com/p/")
+        .or_else(|| rest.strip_prefix("googlecode.debian.net/p/"))
+        .and_then(|p| p.split('/').next())
+        .filter(|p| !p.is_empty());
+    if let Some(project) = project {
+        return set_google_code_archive(entry, project);
+    }
+
+    false
+}
+
+/// Determine the SourceForge project for a download mirror URL.
+fn sourceforge_project(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    let project = if host == "prdownloads.sourceforge.net"
+        || host == "download.sourceforge.net"
+        || host == "downloads.sf.net"
+        || host.ends_with(".dl.sourceforge.net")
+        || host == "downloads.sourceforge.net"
+    {
+        match segments.next()? {
+            "project" | "sourceforge" => segments.next()?,
+            project => project,
+        }
+    } else if host == "sourceforge.net" || host == "www.sourceforge.net" {
+        if segments.next()? != "projects" {
+            return None;
+        }
+        segments.next()?
+    } else {
+        return None;
+    };
+    Some(project.to_string())
+}
+
+/// Rewrite download URLs on SourceForge mirrors to the `sf.net` redirector.
+///
+/// uscan has special support for `https://sf.net/<project>/`, which keeps
+/// working when individual mirrors disappear.
+///
+/// # Returns
+/// whether the entry was changed
+pub fn rewrite_sourceforge(entry: &mut Entry) -> bool {
+    let url = match entry.url() {
+        Some(url) => url,
+        None => return false,
+    };
+    let pattern = match entry.matching_pattern() {
+        Some(pattern) => pattern,
+        None => return false,
+    };
+    let project = match sourceforge_project(&url) {
+        Some(project) => project,
+        None => {
+            // The URL may already point at sf.net, with a pattern that
+            // refers to a mirror.
+            if sourceforge_project(&pattern).is_some() {
+                entry.set_matching_pattern(pattern_basename(&pattern));
+                return true;
+            }
+            return false;
+        }
+    };
+    entry.set_url(&format!("https://sf.net/{}/", project));
+    entry.set_matching_pattern(pattern_basename(&pattern));
+    true
+}
+
+/// Rewrite GitHub URLs that no longer list upstream releases.
+///
+/// The following are handled:
+/// * the `/downloads` page, which no longer exists, is replaced by the
+///   tags page
+/// * the `/releases` page, which no longer lists release tarballs in its
+///   HTML, is replaced by the tags page for source tarballs and by the
+///   releases API for uploaded release assets
+///
+/// # Returns
+/// whether the entry was changed
+pub fn rewrite_github(entry: &mut Entry) -> bool {
+    let url = match entry.url().and_then(|u| url::Url::parse(&u).ok()) {
+        Some(url) => url,
+        None => return false,
+    };
+    if url.host_str() != Some("github.com") {
+        return false;
+    }
+    let segments: Vec<&str> = match url.path_segments() {
+        Some(segments) => segments.filter(|s| !s.is_empty()).collect(),
+        None => return false,
+    };
+    let (owner, repo, page) = match segments.as_slice() {
+        [owner, repo, page, ..] => (*owner, *repo, *page),
+        _ => return false,
+    };
+    match page {
+        "downloads" => {
+            set_github_tags(entry, owner, repo);
+            true
+        }
+        "releases" => {
+            let pattern = entry.matching_pattern().unwrap_or_default();
+            if pattern.contains("/releases/download/") {
+                entry.set_url(&format!(
+                    "https://api.github.com/repos/{}/{}/releases?per_page=50",
+                    owner, repo
+                ));
+                entry.set_matching_pattern(&format!(
+                    "https://github.com/{}/{}/releases/download/[^/]+/{}",
+                    owner,
+                    repo,
+                    pattern_basename(&pattern)
+                ));
+                entry.set_option("searchmode", Some("plain"));
+            } else {
+                set_github_tags(entry, owner, repo);
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Rewrite URLs on deprecated or dead hosting sites to their modern equivalents.
+///
+/// This combines [`rewrite_sourceforge`], [`rewrite_github`] and
+/// [`fix_dead_redirectors`], and also handles `<project>.googlecode.com`
+/// download URLs.
+///
+/// # Returns
+/// whether the entry was changed
+pub fn rewrite_forge_urls(entry: &mut Entry) -> bool {
+    let googlecode_project = entry
+        .url()
+        .and_then(|u| url::Url::parse(&u).ok())
+        .and_then(|u| {
+            u.host_str()
+                .and_then(|h| h.strip_suffix(".googlecode.com"))
+                .map(|p| p.to_string())
+        });
+    if let Some(project) = googlecode_project {
+        return set_google_code_archive(entry, &project);
+    }
+    let mut changed = fix_dead_redirectors(entry);
+    changed |= rewrite_sourceforge(entry);
+    changed |= rewrite_github(entry);
+    changed
+}
+
+/// Request that upstream tarballs are repacked.
+///
+/// # Arguments
+/// * `entry` - Entry to modify
+/// * `compression` - Compression to use for the repacked tarball, e.g. "xz"
+///
+/// # Returns
+/// whether the entry was changed
+pub fn add_repack(entry: &mut Entry, compression: Option<&str>) -> bool {
+    let mut changed = false;
+    if !entry.has_option("repack") {
+        entry.set_option("repack", None);
+        changed = true;
+    }
+    if let Some(compression) = compression {
+        if entry.option("compression").as_deref() != Some(compression) {
+            entry.set_option("compression", Some(compression));
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Reason why a watch entry does not currently find any upstream versions.
+pub enum VerificationFailure {
+    /// The upstream URL does not exist
+    NotFound(String),
+
+    /// The upstream page was retrieved, but nothing matched the pattern
+    NoMatches,
+
+    /// The TLS certificate of the upstream site could not be verified
+    CertificateError(String),
+
+    /// The entry uses a mode that can not be verified
+    Unsupported(String),
+
+    /// Any other failure
+    Other(String),
+}
+
+impl std::fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VerificationFailure::NotFound(url) => write!(f, "Not found: {}", url),
+            VerificationFailure::NoMatches => write!(f, "No matching upstream files"),
+            VerificationFailure::CertificateError(e) => write!(f, "Certificate error: {}", e),
+            VerificationFailure::Unsupported(mode) => {
+                write!(f, "Unable to verify entries with mode {}", mode)
+            }
+            VerificationFailure::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl VerificationFailure {
+    /// Classify an error message, e.g. as reported by uscan.
+    fn from_message(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if lower.contains("404") || lower.contains("not found") {
+            VerificationFailure::NotFound(message.to_string())
+        } else if lower.contains("certificate") || lower.contains("ssl") {
+            VerificationFailure::CertificateError(message.to_string())
+        } else if lower.contains("no matching") {
+            VerificationFailure::NoMatches
+        } else {
+            VerificationFailure::Other(message.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Result of checking a watch entry against the live upstream site.
+pub struct EntryVerification {
+    /// Upstream versions that were found
+    pub versions: Vec<String>,
+
+    /// Newest upstream version that was found
+    pub newest: Option<String>,
+
+    /// Why no versions were found, if applicable
+    pub failure: Option<VerificationFailure>,
+}
+
+impl EntryVerification {
+    fn from_versions(versions: Vec<String>) -> Self {
+        if versions.is_empty() {
+            return EntryVerification {
+                failure: Some(VerificationFailure::NoMatches),
+                ..Default::default()
+            };
+        }
+        let newest = versions
+            .iter()
+            .filter_map(|v| {
+                v.parse::<debversion::Version>()
+                    .ok()
+                    .map(|parsed| (parsed, v))
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, v)| v.clone());
+        EntryVerification {
+            versions,
+            newest,
+            failure: None,
+        }
+    }
+
+    fn from_failure(failure: VerificationFailure) -> Self {
+        EntryVerification {
+            failure: Some(failure),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the entry currently matches any upstream versions.
+    pub fn matches(&self) -> bool {
+        !self.versions.is_empty()
+    }
+}
+
+/// Find the upstream versions that an entry matches in a downloaded page.
+///
+/// Mangling rules are not applied.
+fn find_versions(entry: &Entry, page: &str) -> Vec<String> {
+    let pattern = match entry.effective_matching_pattern(None) {
+        Some(pattern) => pattern,
+        None => return vec![],
+    };
+    let re = match regex::Regex::new(&format!("^(?:{})$", pattern)) {
+        Ok(re) => re,
+        Err(_) => return vec![],
+    };
+    let candidates: Vec<&str> = if entry.option("searchmode").as_deref() == Some("plain") {
+        let unanchored = match regex::Regex::new(&pattern) {
+            Ok(re) => re,
+            Err(_) => return vec![],
+        };
+        unanchored.find_iter(page).map(|m| m.as_str()).collect()
+    } else {
+        lazy_regex::regex!(r#"(?i)href\s*=\s*["']([^"']+)["']"#)
+            .captures_iter(page)
+            .map(|c| c.get(1).unwrap().as_str())
+            .collect()
+    };
+    let mut versions = vec![];
+    for candidate in candidates {
+        let basename = candidate.rsplit('/').next().unwrap_or(candidate);
+        let captures = re.captures(candidate).or_else(|| re.captures(basename));
+        if let Some(captures) = captures {
+            let version = captures
+                .iter()
+                .skip(1)
+                .flatten()
+                .map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join(".");
+            if !version.is_empty() && !versions.contains(&version) {
+                versions.push(version);
+            }
+        }
+    }
+    versions
+}
+
+/// Check a watch entry against the live upstream site.
+///
+/// Only entries that scrape HTTP pages (`mode=lwp`) are supported, and
+/// mangling rules are not applied; use [`verify_with_uscan`] for a complete
+/// evaluation.
+///
+/// # Arguments
+/// * `entry` - Entry to check
+pub fn verify_entry(entry: &Entry) -> EntryVerification {
+    let url = match verification_url(entry) {
+        Ok(url) => url,
+        Err(failure) => return EntryVerification::from_failure(failure),
+    };
+    let span = tracing::info_span!("http_request", url = %url);
+    let response = match span.in_scope(|| reqwest::blocking::get(&url)) {
+        Ok(response) => response,
+        Err(e) => return EntryVerification::from_failure(request_failure(&e)),
+    };
+    if let Err(failure) = check_status(&url, response.status()) {
+        return EntryVerification::from_failure(failure);
+    }
+    match response.text() {
+        Ok(page) => EntryVerification::from_versions(find_versions(entry, &page)),
+        Err(e) => EntryVerification::from_failure(VerificationFailure::Other(e.to_string())),
+    }
+}
+
+/// Check a watch entry against the live upstream site, without blocking.
+///
+/// This is the asynchronous equivalent of [`verify_entry`].
+///
+/// # Arguments
+/// * `client` - HTTP client to use
+/// * `entry` - Entry to check
+#[cfg(feature = "async")]
+pub async fn verify_entry_async(client: &reqwest::Client, entry: &Entry) -> EntryVerification {
+    use tracing::Instrument;
+    let url = match verification_url(entry) {
+        Ok(url) => url,
+        Err(failure) => return EntryVerification::from_failure(failure),
+    };
+    let span = tracing::info_span!("http_request", url = %url);
+    let response = match client.get(&url).send().instrument(span).await {
+        Ok(response) => response,
+        Err(e) => return EntryVerification::from_failure(request_failure(&e)),
+    };
+    if let Err(failure) = check_status(&url, response.status()) {
+        return EntryVerification::from_failure(failure);
+    }
+    match response.text().await {
+        Ok(page) => EntryVerification::from_versions(find_versions(entry, &page)),
+        Err(e) => EntryVerification::from_failure(VerificationFailure::Other(e.to_string())),
+    }
+}
+
+/// The URL to fetch to verify an entry.
+fn verification_url(entry: &Entry) -> Result<String, VerificationFailure> {
+    let mode = entry.mode();
+    if mode != "lwp" {
+        return Err(VerificationFailure::Unsupported(mode));
+    }
+    entry.url().ok_or(VerificationFailure::NoMatches)
+}
+
+fn request_failure(e: &reqwest::Error) -> VerificationFailure {
+    VerificationFailure::from_message(&format!("{:?}", e))
+}
+
+fn check_status(url: &str, status: reqwest::StatusCode) -> Result<(), VerificationFailure> {
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(VerificationFailure::NotFound(url.to_string()));
+    }
+    if !status.is_success() {
+        return Err(VerificationFailure::Other(format!(
+            "Unexpected HTTP status {} for {}",
+            status, url
+        )));
+    }
+    Ok(())
+}
+
+/// Parse the DEHS report printed by `uscan --dehs`.
+fn parse_dehs(xml: &str) -> EntryVerification {
+    let tags = |name: &str| -> Vec<String> {
+        regex::Regex::new(&format!(r"(?s)<{0}>(.*?)</{0}>", name))
+            .unwrap()
+            .captures_iter(xml)
+            .map(|c| c[1].trim().to_string())
+            .collect()
+    };
+    if let Some(error) = tags("errors").into_iter().next() {
+        return EntryVerification::from_failure(VerificationFailure::from_message(&error));
+    }
+    let versions = tags("upstream-version");
+    if versions.is_empty() {
+        if let Some(warning) = tags("warnings").into_iter().next() {
+            return EntryVerification::from_failure(VerificationFailure::from_message(&warning));
+        }
+    }
+    EntryVerification::from_versions(versions)
+}
+
+/// Check each entry of a watch file against the live upstream site using uscan.
+///
+/// Each entry is evaluated separately by running
+/// `uscan --no-download --report --dehs`.
+///
+/// # Arguments
+/// * `wf` - Watch file to check
+/// * `package` - Name of the source package
+///
+/// # Returns
+/// the verification result for each entry, in order
+pub fn verify_with_uscan(
+    wf: &WatchFile,
+    package: &str,
+) -> Result<Vec<EntryVerification>, std::io::Error> {
+    let count = wf.entries().count();
+    let td = tempfile::tempdir()?;
+    let mut ret = vec![];
+    for index in 0..count {
+        // Reparse rather than clone, since the deb822 tree is shared between clones
+        let mut single: WatchFile = wf.to_string().parse().unwrap();
+        for other in (0..count).rev().filter(|i| *i != index) {
+            single.remove_entry(other);
+        }
+        let path = td.path().join("watch");
+        std::fs::write(&path, single.to_string())?;
+        let mut cmd = std::process::Command::new("uscan");
+        cmd.arg("--no-download")
+            .arg("--report")
+            .arg("--dehs")
+            .arg("--watchfile")
+            .arg(&path)
+            .arg("--package")
+            .arg(package)
+            .arg("--upstream-version")
+            .arg("0")
+            .current_dir(td.path());
+        let span = tracing::info_span!("command", command = ?cmd, entry = index);
+        let output = span.in_scope(|| cmd.output())?;
+        ret.push(parse_dehs(&String::from_utf8_lossy(&output.stdout)));
+    }
+    Ok(ret)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An orig tarball that uscan is expected to produce.
+pub struct OrigTarball {
+    /// Name of the component, or None for the main tarball
+    pub component: Option<String>,
+
+    /// File name up to and including `.tar`, e.g. `foo_1.0.orig.tar`
+    pub stem: String,
+
+    /// Compression extension, e.g. "xz"; None if this depends on what
+    /// upstream ships
+    pub extension: Option<String>,
+}
+
+impl OrigTarball {
+    /// Return the full file name, if the compression is known.
+    pub fn filename(&self) -> Option<String> {
+        self.extension
+            .as_ref()
+            .map(|ext| format!("{}.{}", self.stem, ext))
+    }
+
+    /// Check whether a file name matches this tarball.
+    pub fn matches(&self, filename: &str) -> bool {
+        match &self.extension {
+            Some(ext) => filename == format!("{}.{}", self.stem, ext),
+            None => filename
+                .strip_prefix(&self.stem)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .map(|ext| compression_extension(ext).is_some())
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Return the file extension for a uscan compression method.
+fn compression_extension(method: &str) -> Option<&'static str> {
+    match method {
+        "gzip" | "gz" => Some("gz"),
+        "bzip2" | "bz2" => Some("bz2"),
+        "xz" => Some("xz"),
+        "lzma" => Some("lzma"),
+        "zstd" | "zst" => Some("zst"),
+        _ => None,
+    }
+}
+
+/// Compute the orig tarballs that uscan is expected to produce.
+///
+/// Entries with a `component=` option produce component tarballs; the
+/// others produce the main tarball. The `repacksuffix=` option is appended
+/// to the version if it is not already present. The compression is known
+/// if it is set explicitly, or if the tarball is repacked or exported from
+/// a version control system, in which case uscan defaults to xz.
+///
+/// # Arguments
+/// * `wf` - Watch file
+/// * `package` - Name of the source package
+/// * `version` - Upstream version
+///
+/// # Returns
+/// the expected tarballs, main tarball first
+pub fn expected_orig_tarballs(wf: &WatchFile, package: &str, version: &str) -> Vec<OrigTarball> {
+    let mut ret: Vec<OrigTarball> = vec![];
+    for entry in wf.entries() {
+        let mut version = version.to_string();
+        if let Some(suffix) = entry.option("repacksuffix") {
+            if entry.has_option("repack") && !version.ends_with(&suffix) {
+                version.push_str(&suffix);
+            }
+        }
+        let component = entry.option("component");
+        let stem = match &component {
+            Some(component) => format!("{}_{}.orig-{}.tar", package, version, component),
+            None => format!("{}_{}.orig.tar", package, version),
+        };
+        let repacked = entry.has_option("repack") || entry.mode() != "lwp";
+        let extension = match entry.option("compression") {
+            Some(method) if method != "default" => compression_extension(&method),
+            _ if repacked => Some("xz"),
+            _ => None,
+        };
+        if ret.iter().any(|t| t.component == component) {
+            continue;
+        }
+        let tarball = OrigTarball {
+            component,
+            stem,
+            extension: extension.map(|e| e.to_string()),
+        };
+        if tarball.component.is_none() {
+            ret.insert(0, tarball);
+        } else {
+            ret.push(tarball);
+        }
+    }
+    ret
+}
+
+/// Version mangling that sorts common pre-release suffixes before the final release.
+pub const PRERELEASE_UVERSIONMANGLE: &str =
+    r"s/(\d)[_\.\-\+]?((RC|rc|pre|dev|beta|alpha|a|b)\.?\d*)$/$1~$2/";
+
+/// A well-known upstream source that a watch file can be generated for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpstreamSource {
+    /// A repository hosted on GitHub.
+    GitHub {
+        /// Owner of the repository
+        owner: String,
+        /// Name of the repository
+        repo: String,
+    },
+    /// A repository hosted on a GitLab instance.
+    GitLab {
+        /// Hostname of the GitLab instance
+        host: String,
+        /// Full path of the project, including its namespace
+        path: String,
+    },
+    /// A repository hosted on Codeberg.
+    Codeberg {
+        /// Owner of the repository
+        owner: String,
+        /// Name of the repository
+        repo: String,
+    },
+    /// A project on the Python Package Index.
+    PyPI(String),
+    /// A crate on crates.io.
+    Crate(String),
+    /// A package on the npm registry.
+    Npm(String),
+    /// A gem on rubygems.org.
+    RubyGem(String),
+}
+
+impl UpstreamSource {
+    /// Determine the upstream source from a repository URL.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the upstream repository
+    ///
+    /// # Returns
+    /// the upstream source, or None if the URL is not hosted on a known forge
+    pub fn from_repository_url(url: &str) -> Option<Self> {
+        let url = url::Url::parse(url).ok()?;
+        let host = url.host_str()?;
+        let path = url
+            .path()
+            .trim_matches('/')
+            .trim_end_matches(".git")
+            .trim_end_matches('/');
+        if host == "github.com" || host == "codeberg.org" {
+            let mut parts = path.split('/');
+            let owner = parts.next().filter(|s| !s.is_empty())?.to_string();
+            let repo = parts.next().filter(|s| !s.is_empty())?.to_string();
+            if host == "github.com" {
+                Some(UpstreamSource::GitHub { owner, repo })
+            } else {
+                Some(UpstreamSource::Codeberg { owner, repo })
+            }
+        } else if crate::vcs::is_gitlab_site(host, Some(false)) {
+            let path = path.split("/-/").next().unwrap();
+            if !path.contains('/') {
+                return None;
+            }
+            Some(UpstreamSource::GitLab {
+                host: host.to_string(),
+                path: path.to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Name of the upstream project, as used in tarball names.
+    pub fn project_name(&self) -> &str {
+        match self {
+            UpstreamSource::GitHub { repo, .. } | UpstreamSource::Codeberg { repo, .. } => repo,
+            UpstreamSource::GitLab { path, .. } => path.rsplit('/').next().unwrap(),
+            UpstreamSource::PyPI(name)
+            | UpstreamSource::Crate(name)
+            | UpstreamSource::RubyGem(name) => name,
+            UpstreamSource::Npm(name) => name.rsplit('/').next().unwrap(),
+        }
+    }
+
+    /// URL of the web view of the repository, for sources hosted on a forge.
+    pub fn web_url(&self) -> Option<String> {
+        match self {
+            UpstreamSource::GitHub { owner, repo } => {
+                Some(format!("https://github.com/{}/{}", owner, repo))
+            }
+            UpstreamSource::GitLab { host, path } => Some(format!("https://{}/{}", host, path)),
+            UpstreamSource::Codeberg { owner, repo } => {
+                Some(format!("https://codeberg.org/{}/{}", owner, repo))
+            }
+            UpstreamSource::PyPI(_)
+            | UpstreamSource::Crate(_)
+            | UpstreamSource::Npm(_)
+            | UpstreamSource::RubyGem(_) => None,
+        }
+    }
+
+    /// Add a watch entry for this upstream source to a watch file.
+    ///
+    /// # Arguments
+    /// * `wf` - Watch file to add the entry to
+    ///
+    /// # Returns
+    /// the new entry
+    pub fn add_to<'a>(&self, wf: &'a mut WatchFile) -> &'a mut Entry {
+        let name = self.project_name();
+        match self {
+            UpstreamSource::GitHub { owner, repo } => {
+                let filenamemangle = format!(r"s%.*/v?(\d\S+)\.tar\.gz%{}-$1.tar.gz%", name);
+                wf.add_entry(
+                    &format!("https://github.com/{}/{}/tags", owner, repo),
+                    Some(r".*/v?(\d\S+)\.tar\.gz"),
+                    &[
+                        ("filenamemangle", Some(filenamemangle.as_str())),
+                        ("uversionmangle", Some(PRERELEASE_UVERSIONMANGLE)),
+                    ],
+                )
+            }
+            UpstreamSource::GitLab { host, path } => {
+                let filenamemangle = format!(r"s%.*/v?(\d\S+)\.tar\.gz%{}-$1.tar.gz%", name);
+                wf.add_entry(
+                    &format!("https://{}/{}/tags?sort=updated_desc", host, path),
+                    Some(&format!(r".*/archive/v?(\d\S+)/{}-.*\.tar\.gz", name)),
+                    &[
+                        ("filenamemangle", Some(filenamemangle.as_str())),
+                        ("uversionmangle", Some(PRERELEASE_UVERSIONMANGLE)),
+                    ],
+                )
+            }
+            UpstreamSource::Codeberg { owner, repo } => {
+                let filenamemangle = format!(r"s%.*/v?(\d\S+)\.tar\.gz%{}-$1.tar.gz%", name);
+                wf.add_entry(
+                    &format!("https://codeberg.org/{}/{}/tags", owner, repo),
+                    Some(r".*/archive/v?(\d\S+)\.tar\.gz"),
+                    &[
+                        ("filenamemangle", Some(filenamemangle.as_str())),
+                        ("uversionmangle", Some(PRERELEASE_UVERSIONMANGLE)),
+                    ],
+                )
+            }
+            UpstreamSource::PyPI(project) => wf.add_entry(
+                &format!("https://pypi.debian.net/{}/", project),
+                Some(&format!(
+                    r"{}-(.+)\.(?:zip|tgz|tbz|txz|(?:tar\.(?:gz|bz2|xz)))",
+                    regex::escape(project)
+                )),
+                &[("uversionmangle", Some(PRERELEASE_UVERSIONMANGLE))],
+            ),
+            UpstreamSource::Crate(krate) => {
+                let filenamemangle = format!(r"s%.*/(\d[^/]*)/download%{}-$1.tar.gz%", krate);
+                wf.add_entry(
+                    &format!("https://crates.io/api/v1/crates/{}/versions", krate),
+                    Some(&format!(r"/api/v1/crates/{}/(\d[^/]*)/download", krate)),
+                    &[
+                        ("searchmode", Some("plain")),
+                        ("filenamemangle", Some(filenamemangle.as_str())),
+                        ("uversionmangle", Some(PRERELEASE_UVERSIONMANGLE)),
+                    ],
+                )
+            }
+            UpstreamSource::Npm(package) => wf.add_entry(
+                &format!("https://registry.npmjs.org/{}", package),
+                Some(&format!(
+                    r"https://registry.npmjs.org/{}/-/{}-(\d[\d.]*)\.tgz",
+                    package,
+                    regex::escape(name)
+                )),
+                &[
+                    ("searchmode", Some("plain")),
+                    ("uversionmangle", Some(PRERELEASE_UVERSIONMANGLE)),
+                ],
+            ),
+            UpstreamSource::RubyGem(gem) => wf.add_entry(
+                &format!("https://gemwatch.debian.net/{}", gem),
+                Some(&format!(r".*/{}-(.*)\.tar\.gz", regex::escape(gem))),
+                &[("uversionmangle", Some(PRERELEASE_UVERSIONMANGLE))],
+            ),
+        }
+    }
+}
+
+/// Generate a watch file for a well-known upstream source.
+///
+/// This is useful for packages that do not have a watch file at all.
+///
+/// # Arguments
+/// * `source` - Upstream source to track
+///
+/// # Returns
+/// a new watch file with a single entry
+pub fn watch_file_for_upstream(source: &UpstreamSource) -> WatchFile {
+    let mut wf = WatchFile::new(DEFAULT_VERSION);
+    source.add_to(&mut wf);
+    wf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_v4() {
+        let text = r#"# Watch file for foo
+version=4
+opts="pgpmode=auto, \
+      uversionmangle=s/-rc/~rc/" \
+  https://example.com/releases/ foo-(\d\S+)\.tar\.gz debian uupdate
+"#;
+        let wf: WatchFile = text.parse().unwrap();
+        assert_eq!(wf.version(), 4);
+        assert_eq!(wf.to_string(), text);
+        let entries = wf.entries().collect::<Vec<_>>();
+        assert_eq!(entries.len(), 1);
+        let entry = entries[0];
+        assert_eq!(
+            entry.url().as_deref(),
+            Some("https://example.com/releases/")
+        );
+        assert_eq!(
+            entry.matching_pattern().as_deref(),
+            Some(r"foo-(\d\S+)\.tar\.gz")
+        );
+        assert_eq!(entry.version_policy().as_deref(), Some("debian"));
+        assert_eq!(entry.script().as_deref(), Some("uupdate"));
+        assert_eq!(
+            entry.options(),
+            vec![
+                ("pgpmode".to_string(), Some("auto".to_string())),
+                ("uversionmangle".to_string(), Some("s/-rc/~rc/".to_string()))
+            ]
+        );
+        assert_eq!(entry.option("pgpmode").as_deref(), Some("auto"));
+    }
+
+    #[test]
+    fn test_parse_v3_combined() {
+        let text = "version=3\nhttp://example.com/dir/foo-(.*)\\.tar\\.gz\n";
+        let wf: WatchFile = text.parse().unwrap();
+        assert_eq!(wf.version(), 3);
+        let entry = wf.entries().next().unwrap();
+        assert_eq!(entry.url().as_deref(), Some("http://example.com/dir"));
+        assert_eq!(
+            entry.matching_pattern().as_deref(),
+            Some("foo-(.*)\\.tar\\.gz")
+        );
+        assert_eq!(entry.version_policy(), None);
+        assert_eq!(wf.to_string(), text);
+    }
+
+    #[test]
+    fn test_modify_preserves_continuations() {
+        let text = r#"version=4
+# Check releases
+opts="pgpmode=auto, \
+      uversionmangle=s/-rc/~rc/" \
+  https://example.com/releases/ foo-(\d\S+)\.tar\.gz
+"#;
+        let mut wf: WatchFile = text.parse().unwrap();
+        let entry = wf.entries_mut().next().unwrap();
+        entry.set_option("pgpmode", Some("none"));
+        entry.set_matching_pattern(r"foo-v?(\d\S+)\.tar\.xz");
+        assert_eq!(
+            wf.to_string(),
+            r#"version=4
+# Check releases
+opts="pgpmode=none, \
+      uversionmangle=s/-rc/~rc/" \
+  https://example.com/releases/ foo-v?(\d\S+)\.tar\.xz
+"#
+        );
+    }
+
+    #[test]
+    fn test_add_and_remove_options() {
+        let mut wf: WatchFile = "version=4\nhttps://example.com/ foo-(.*).tar.gz\n"
+            .parse()
+            .unwrap();
+        let entry = wf.entries_mut().next().unwrap();
+        entry.set_option("repack", None);
+        entry.set_option("compression", Some("xz"));
+        assert_eq!(
+            wf.to_string(),
+            "version=4\nopts=repack,compression=xz https://example.com/ foo-(.*).tar.gz\n"
+        );
+        let entry = wf.entries_mut().next().unwrap();
+        assert!(entry.has_option("repack"));
+        assert!(entry.remove_option("repack"));
+        assert!(!entry.remove_option("repack"));
+        assert_eq!(
+            wf.to_string(),
+            "version=4\nopts=compression=xz https://example.com/ foo-(.*).tar.gz\n"
+        );
+        let entry = wf.entries_mut().next().unwrap();
+        assert!(entry.remove_option("compression"));
+        assert_eq!(
+            wf.to_string(),
+            "version=4\nhttps://example.com/ foo-(.*).tar.gz\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_v5() {
+        let text = r#"Version: 5
+
+Source: https://example.com/releases/
+Matching-Pattern: foo-(\d\S+)\.tar\.gz
+Pgp-Mode: auto
+"#;
+        let mut wf: WatchFile = text.parse().unwrap();
+        assert_eq!(wf.version(), 5);
+        assert_eq!(wf.to_string(), text);
+        let entry = wf.entries_mut().next().unwrap();
+        assert_eq!(
+            entry.url().as_deref(),
+            Some("https://example.com/releases/")
+        );
+        assert_eq!(entry.option("pgpmode").as_deref(), Some("auto"));
+        entry.set_option("uversionmangle", Some("s/-rc/~rc/"));
+        entry.set_option("repack", None);
+        assert!(entry.remove_option("pgpmode"));
+        assert_eq!(
+            wf.to_string(),
+            r#"Version: 5
+
+Source: https://example.com/releases/
+Matching-Pattern: foo-(\d\S+)\.tar\.gz
+Uversion-Mangle: s/-rc/~rc/
+Repack: yes
+"#
+        );
+    }
+
+    #[test]
+    fn test_new_and_add_entry() {
+        let mut wf = WatchFile::new(4);
+        wf.add_entry(
+            "https://example.com/",
+            Some(r"foo-(\d\S+)\.tar\.gz"),
+            &[("pgpmode", Some("auto"))],
+        );
+        assert_eq!(
+            wf.to_string(),
+            "version=4\nopts=pgpmode=auto https://example.com/ foo-(\\d\\S+)\\.tar\\.gz\n"
+        );
+        assert!(wf.remove_entry(0));
+        assert!(wf.is_empty());
+
+        let mut wf = WatchFile::new(5);
+        wf.add_entry("https://example.com/", Some("foo-(.*).tar.gz"), &[]);
+        assert_eq!(
+            wf.to_string(),
+            "Version: 5\n\nSource: https://example.com/\nMatching-Pattern: foo-(.*).tar.gz\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_version() {
+        assert_eq!(
+            "version=foo\n".parse::<WatchFile>().unwrap_err(),
+            ParseError::InvalidVersion("version=foo".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_str_relaxed() {
+        let text = "version=foo\nopts=pgpmode=auto\nhttp://example.com/ foo-(.*).tar.gz\n";
+        let (wf, errors) = WatchFile::from_str_relaxed(text);
+        assert_eq!(
+            errors,
+            vec![
+                ParseError::InvalidVersion("version=foo".to_string()),
+                ParseError::MissingUrl(2)
+            ]
+        );
+        assert_eq!(wf.entries().count(), 1);
+        assert_eq!(wf.to_string(), text);
+
+        let text = "Version: foo\n\nSource: https://example.com/\n";
+        let (wf, errors) = WatchFile::from_str_relaxed(text);
+        assert_eq!(errors, vec![ParseError::InvalidVersion("foo".to_string())]);
+        assert!(wf.is_empty());
+        assert_eq!(wf.to_string(), text);
+        let mut wf = wf;
+        assert!(matches!(
+            wf.upgrade_format(5),
+            Err(UpgradeError::Unparsed(_))
+        ));
+    }
+
+    #[test]
+    fn test_edit_broken() {
+        use crate::editor::Editor;
+        let td = tempfile::tempdir().unwrap();
+        let path = td.path().join("watch");
+        std::fs::write(
+            &path,
+            "version=4\nopts=pgpmode=auto\nhttp://example.com/ foo-(.*).tar.gz\n",
+        )
+        .unwrap();
+        let mut editor =
+            crate::editor::FsEditor::<WatchFile>::new(&path, false, false, false).unwrap();
+        assert!(use_https(editor.entries_mut().next().unwrap()));
+        editor.commit().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "version=4\nopts=pgpmode=auto\nhttps://example.com/ foo-(.*).tar.gz\n"
+        );
+
+        // A file without entries is not removed
+        std::fs::write(&path, "Version: foo\n").unwrap();
+        let editor = crate::editor::FsEditor::<WatchFile>::new(&path, false, false, false).unwrap();
+        assert!(editor.is_empty());
+        assert!(editor.commit().unwrap().is_empty());
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_add_git_entries() {
+        let mut wf = WatchFile::new(4);
+        wf.add_git_tags_entry("https://github.com/foo/bar.git", None);
+        wf.add_git_head_entry("https://github.com/foo/bar.git", None);
+        assert_eq!(
+            wf.to_string(),
+            r#"version=4
+opts=mode=git https://github.com/foo/bar.git refs/tags/v?(\d[\-+\.:\~\da-zA-Z]*)
+opts=mode=git,pretty=0.0~git%cd.%h https://github.com/foo/bar.git HEAD
+"#
+        );
+        assert_eq!(wf.entries().next().unwrap().mode(), "git");
+    }
+
+    #[test]
+    fn test_add_svn_entries() {
+        let mut wf = WatchFile::new(4);
+        wf.add_svn_tags_entry("svn://svn.example.com/foo/tags/");
+        wf.add_svn_head_entry("svn://svn.example.com/foo/trunk", None);
+        assert_eq!(
+            wf.to_string(),
+            r#"version=4
+opts=mode=svn svn://svn.example.com/foo/tags/ v?(\d[\d.]*)/
+opts=mode=svn,pretty=0.0~svn%s svn://svn.example.com/foo/trunk HEAD
+"#
+        );
+    }
+
+    #[test]
+    fn test_convert_to_git_tags() {
+        let mut wf: WatchFile = r#"version=4
+opts="filenamemangle=s%.*/v?(\d\S+)\.tar\.gz%bar-$1.tar.gz%,uversionmangle=s/-rc/~rc/" \
+  https://github.com/foo/bar/tags .*/v?(\d\S+)\.tar\.gz
+"#
+        .parse()
+        .unwrap();
+        let entry = wf.entries_mut().next().unwrap();
+        assert_eq!(entry.mode(), "lwp");
+        assert!(convert_to_git_tags(entry, "https://github.com/foo/bar.git"));
+        assert!(!convert_to_git_tags(
+            entry,
+            "https://github.com/foo/bar.git"
+        ));
+        assert_eq!(
+            wf.to_string(),
+            r#"version=4
+opts="uversionmangle=s/-rc/~rc/,mode=git" \
+  https://github.com/foo/bar.git refs/tags/v?(\d[\-+\.:\~\da-zA-Z]*)
+"#
+        );
+    }
+
+    #[test]
+    fn test_upstream_source_from_repository_url() {
+        assert_eq!(
+            UpstreamSource::from_repository_url("https://github.com/foo/bar.git"),
+            Some(UpstreamSource::GitHub {
+                owner: "foo".to_string(),
+                repo: "bar".to_string()
+            })
+        );
+        assert_eq!(
+            UpstreamSource::from_repository_url("https://salsa.debian.org/foo/sub/bar/-/tree/main"),
+            Some(UpstreamSource::GitLab {
+                host: "salsa.debian.org".to_string(),
+                path: "foo/sub/bar".to_string()
+            })
+        );
+        assert_eq!(
+            UpstreamSource::from_repository_url("https://codeberg.org/foo/bar"),
+            Some(UpstreamSource::Codeberg {
+                owner: "foo".to_string(),
+                repo: "bar".to_string()
+            })
+        );
+        assert_eq!(
+            UpstreamSource::from_repository_url("https://github.com/foo"),
+            None
+        );
+        assert_eq!(
+            UpstreamSource::from_repository_url("https://example.com/foo/bar"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_watch_file_for_upstream() {
+        let wf = watch_file_for_upstream(&UpstreamSource::GitHub {
+            owner: "foo".to_string(),
+            repo: "bar".to_string(),
+        });
+        let entry = wf.entries().next().unwrap();
+        assert_eq!(
+            entry.url().as_deref(),
+            Some("https://github.com/foo/bar/tags")
+        );
+        assert_eq!(
+            entry.option("filenamemangle").as_deref(),
+            Some(r"s%.*/v?(\d\S+)\.tar\.gz%bar-$1.tar.gz%")
+        );
+        let reparsed: WatchFile = wf.to_string().parse().unwrap();
+        assert_eq!(reparsed.to_string(), wf.to_string());
+
+        let wf = watch_file_for_upstream(&UpstreamSource::PyPI("python-foo".to_string()));
+        let entry = wf.entries().next().unwrap();
+        assert_eq!(
+            entry.url().as_deref(),
+            Some("https://pypi.debian.net/python-foo/")
+        );
+        assert_eq!(
+            entry.option("uversionmangle").as_deref(),
+            Some(PRERELEASE_UVERSIONMANGLE)
+        );
+
+        let wf = watch_file_for_upstream(&UpstreamSource::Npm("@foo/bar".to_string()));
+        let entry = wf.entries().next().unwrap();
+        assert_eq!(
+            entry.matching_pattern().as_deref(),
+            Some(r"https://registry.npmjs.org/@foo/bar/-/bar-(\d[\d.]*)\.tgz")
+        );
+        assert_eq!(entry.option("searchmode").as_deref(), Some("plain"));
+
+        let wf = watch_file_for_upstream(&UpstreamSource::Crate("serde".to_string()));
+        let entry = wf.entries().next().unwrap();
+        assert_eq!(
+            entry.url().as_deref(),
+            Some("https://crates.io/api/v1/crates/serde/versions")
+        );
+
+        let wf = watch_file_for_upstream(&UpstreamSource::RubyGem("rake".to_string()));
+        let entry = wf.entries().next().unwrap();
+        assert_eq!(
+            entry.url().as_deref(),
+            Some("https://gemwatch.debian.net/rake")
+        );
+    }
+
+    #[test]
+    fn test_add_signature_verification() {
+        let mut wf: WatchFile = "version=4\nhttps://example.com/ foo-(\\d\\S+)\\.tar\\.gz\n"
+            .parse()
+            .unwrap();
+        let entry = wf.entries_mut().next().unwrap();
+        assert!(add_signature_verification(entry, None));
+        assert!(!add_signature_verification(entry, Some("s/$/.asc/")));
+        assert_eq!(
+            wf.to_string(),
+            "version=4\nopts=pgpmode=auto https://example.com/ foo-(\\d\\S+)\\.tar\\.gz\n"
+        );
+
+        let mut wf = WatchFile::new(4);
+        let entry = wf.add_git_tags_entry("https://example.com/foo.git", None);
+        assert!(!add_signature_verification(entry, None));
+    }
+
+    #[test]
+    fn test_has_signing_key() {
+        let td = tempfile::tempdir().unwrap();
+        assert!(!has_signing_key(td.path()));
+        std::fs::create_dir_all(td.path().join("debian/upstream")).unwrap();
+        std::fs::write(td.path().join("debian/upstream/signing-key.asc"), "").unwrap();
+        assert!(has_signing_key(td.path()));
+    }
+
+    #[test]
+    fn test_use_https() {
+        let mut wf: WatchFile =
+            "version=4\nhttp://example.com/ http://example.com/foo-(\\d\\S+)\\.tar\\.gz\n"
+                .parse()
+                .unwrap();
+        let entry = wf.entries_mut().next().unwrap();
+        assert!(use_https(entry));
+        assert!(!use_https(entry));
+        assert_eq!(
+            wf.to_string(),
+            "version=4\nhttps://example.com/ https://example.com/foo-(\\d\\S+)\\.tar\\.gz\n"
+        );
+    }
+
+    #[test]
+    fn test_fix_dead_redirectors() {
+        let mut wf: WatchFile = "version=3\nhttp://githubredir.debian.net/github/foo/bar .*/v?(\\d\\S+)\\.tar\\.gz\nhttp://qa.debian.org/watch/sf.php/foo foo-(\\d\\S+)\\.tar\\.gz\nhttp://code.google.com/p/foo/downloads/list .*/foo-(\\d\\S+)\\.tar\\.gz\nhttps://example.com/ foo-(\\d\\S+)\\.tar\\.gz\n"
+            .parse()
+            .unwrap();
+        let changed: Vec<bool> = wf.entries_mut().map(fix_dead_redirectors).collect();
+        assert_eq!(changed, vec![true, true, true, false]);
+        assert_eq!(
+            wf.to_string(),
+            r#"version=3
+opts=filenamemangle=s%.*/v?(\d\S+)\.tar\.gz%bar-$1.tar.gz% https://github.com/foo/bar/tags .*/v?(\d\S+)\.tar\.gz
+https://sf.net/foo foo-(\d\S+)\.tar\.gz
+opts=searchmode=plain https://storage.googleapis.com/google-code-archive-downloads/?prefix=v2/code.google.com/foo/ v2/code.google.com/foo/foo-(\d\S+)\.tar\.gz
+https://example.com/ foo-(\d\S+)\.tar\.gz
+"#
+        );
+    }
+
+    #[test]
+    fn test_add_repack() {
+        let mut wf: WatchFile = "version=4\nhttps://example.com/ foo-(\\d\\S+)\\.tar\\.gz\n"
+            .parse()
+            .unwrap();
+        let entry = wf.entries_mut().next().unwrap();
+        assert!(add_repack(entry, Some("xz")));
+        assert!(!add_repack(entry, Some("xz")));
+        assert_eq!(
+            wf.to_string(),
+            "version=4\nopts=repack,compression=xz https://example.com/ foo-(\\d\\S+)\\.tar\\.gz\n"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_format_3_to_4() {
+        let mut wf: WatchFile =
+            "# comment\nversion=3\nhttps://example.com/foo-(\\d\\S+)\\.tar\\.gz\n"
+                .parse()
+                .unwrap();
+        assert_eq!(wf.upgrade_format(4), Ok(true));
+        assert_eq!(wf.upgrade_format(4), Ok(false));
+        assert_eq!(wf.version(), 4);
+        assert_eq!(
+            wf.to_string(),
+            "# comment\nversion=4\nhttps://example.com/foo-(\\d\\S+)\\.tar\\.gz\n"
+        );
+    }
+
+    #[test]
+    fn test_upgrade_format_to_5() {
+        let mut wf: WatchFile = r#"version=3
+# Releases
+opts="pgpmode=auto, repack" https://example.com/releases/foo-(\d\S+)\.tar\.gz debian uupdate
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(wf.upgrade_format(5), Ok(true));
+        assert_eq!(wf.version(), 5);
+        assert_eq!(
+            wf.to_string(),
+            r#"Version: 5
+
+# Releases
+Source: https://example.com/releases
+Matching-Pattern: foo-(\d\S+)\.tar\.gz
+Version-Policy: debian
+Script: uupdate
+Pgp-Mode: auto
+Repack: yes
+"#
+        );
+        let entry = wf.entries().next().unwrap();
+        assert_eq!(entry.option("pgpmode").as_deref(), Some("auto"));
+        assert_eq!(
+            entry.matching_pattern().as_deref(),
+            Some(r"foo-(\d\S+)\.tar\.gz")
+        );
+    }
+
+    #[test]
+    fn test_upgrade_format_errors() {
+        let mut wf = WatchFile::new(4);
+        assert_eq!(
+            wf.upgrade_format(3),
+            Err(UpgradeError::Downgrade { from: 4, to: 3 })
+        );
+        assert_eq!(
+            wf.upgrade_format(6),
+            Err(UpgradeError::UnsupportedVersion(6))
+        );
+    }
+
+    #[test]
+    fn test_find_versions() {
+        let wf: WatchFile =
+            "version=4\nhttps://example.com/releases/ foo-(\\d[\\d.]*)\\.tar\\.gz\n"
+                .parse()
+                .unwrap();
+        let entry = wf.entries().next().unwrap();
+        let page = r#"<a href="foo-1.0.tar.gz">1.0</a>
+<a href='/releases/foo-1.10.tar.gz'>1.10</a>
+<a href="foo-1.2.tar.gz.asc">sig</a>
+<a href="foo-1.2.tar.gz">1.2</a>"#;
+        let versions = find_versions(entry, page);
+        assert_eq!(versions, vec!["1.0", "1.10", "1.2"]);
+        let verification = EntryVerification::from_versions(versions);
+        assert!(verification.matches());
+        assert_eq!(verification.newest.as_deref(), Some("1.10"));
+
+        let verification = EntryVerification::from_versions(find_versions(entry, "<html/>"));
+        assert!(!verification.matches());
+        assert_eq!(verification.failure, Some(VerificationFailure::NoMatches));
+    }
+
+    #[test]
+    fn test_parse_dehs() {
+        let verification = parse_dehs(
+            r#"<dehs>
+<package>foo</package>
+<debian-uversion>0</debian-uversion>
+<upstream-version>1.2</upstream-version>
+<upstream-url>https://example.com/foo-1.2.tar.gz</upstream-url>
+<status>newer package available</status>
+</dehs>"#,
+        );
+        assert_eq!(verification.newest.as_deref(), Some("1.2"));
+        assert_eq!(verification.failure, None);
+
+        let verification = parse_dehs(
+            "<dehs>\n<errors>uscan warn: In watchfile debian/watch, reading webpage\n  https://example.com/ failed: 404 Not Found</errors>\n</dehs>",
+        );
+        assert!(matches!(
+            verification.failure,
+            Some(VerificationFailure::NotFound(_))
+        ));
+
+        let verification = parse_dehs(
+            "<dehs>\n<warnings>In debian/watch no matching files for watch line\nhttps://example.com/ foo-(.*).tar.gz</warnings>\n</dehs>",
+        );
+        assert_eq!(verification.failure, Some(VerificationFailure::NoMatches));
+
+        let verification = parse_dehs(
+            "<dehs>\n<errors>SSL connect attempt failed error:certificate verify failed</errors>\n</dehs>",
+        );
+        assert!(matches!(
+            verification.failure,
+            Some(VerificationFailure::CertificateError(_))
+        ));
+    }
+
+    #[test]
+    fn test_expand_macros() {
+        assert_eq!(
+            expand_macros("@PACKAGE@@ANY_VERSION@@ARCHIVE_EXT@", Some("foo+bar")),
+            format!(r"foo\+bar{}{}", ANY_VERSION, ARCHIVE_EXT)
+        );
+        assert_eq!(expand_macros("@PACKAGE@-(.*)", None), "@PACKAGE@-(.*)");
+
+        let wf: WatchFile = "version=4\nhttps://example.com/ foo@ANY_VERSION@@ARCHIVE_EXT@\n"
+            .parse()
+            .unwrap();
+        let entry = wf.entries().next().unwrap();
+        let re = regex::Regex::new(&format!(
+            "^{}$",
+            entry.effective_matching_pattern(Some("foo")).unwrap()
+        ))
+        .unwrap();
+        assert_eq!(&re.captures("foo-v1.2.TAR.GZ").unwrap()[1], "1.2");
+        assert!(!re.is_match("foo-1.2.tar.gz.asc"));
+        assert_eq!(
+            find_versions(entry, r#"<a href="foo_1.3.tar.xz">"#),
+            vec!["1.3"]
+        );
+
+        let re = regex::Regex::new(&expand_macros(r"1\.0\+dfsg@DEB_EXT@", None)).unwrap();
+        assert!(re.is_match("1.0+dfsg+ds1"));
+    }
+
+    #[test]
+    fn test_rewrite_sourceforge() {
+        let mut wf: WatchFile = r#"version=4
+http://downloads.sourceforge.net/project/foo/foo-(\d\S+)\.tar\.gz
+https://sf.net/bar/ http://prdownloads.sourceforge.net/bar/bar-(\d\S+)\.tar\.gz
+https://sourceforge.net/projects/baz/files/ baz-(\d\S+)\.tar\.gz
+https://sf.net/qux/ qux-(\d\S+)\.tar\.gz
+"#
+        .parse()
+        .unwrap();
+        let changed: Vec<bool> = wf.entries_mut().map(rewrite_sourceforge).collect();
+        assert_eq!(changed, vec![true, true, true, false]);
+        assert_eq!(
+            wf.to_string(),
+            r#"version=4
+https://sf.net/foo/foo-(\d\S+)\.tar\.gz
+https://sf.net/bar/ bar-(\d\S+)\.tar\.gz
+https://sf.net/baz/ baz-(\d\S+)\.tar\.gz
+https://sf.net/qux/ qux-(\d\S+)\.tar\.gz
+"#
+        );
+    }
+
+    #[test]
+    fn test_rewrite_github() {
+        let mut wf: WatchFile = r#"version=4
+https://github.com/foo/bar/downloads .*/bar-(\d\S+)\.tar\.gz
+https://github.com/foo/bar/releases .*/archive/v?(\d\S+)\.tar\.gz
+https://github.com/foo/bar/releases .*/releases/download/.*/bar-(\d\S+)\.tar\.xz
+https://github.com/foo/bar/tags .*/v?(\d\S+)\.tar\.gz
+"#
+        .parse()
+        .unwrap();
+        let changed: Vec<bool> = wf.entries_mut().map(rewrite_github).collect();
+        assert_eq!(changed, vec![true, true, true, false]);
+        assert_eq!(
+            wf.to_string(),
+            r#"version=4
+opts=filenamemangle=s%.*/v?(\d\S+)\.tar\.gz%bar-$1.tar.gz% https://github.com/foo/bar/tags .*/v?(\d\S+)\.tar\.gz
+opts=filenamemangle=s%.*/v?(\d\S+)\.tar\.gz%bar-$1.tar.gz% https://github.com/foo/bar/tags .*/v?(\d\S+)\.tar\.gz
+opts=searchmode=plain https://api.github.com/repos/foo/bar/releases?per_page=50 https://github.com/foo/bar/releases/download/[^/]+/bar-(\d\S+)\.tar\.xz
+https://github.com/foo/bar/tags .*/v?(\d\S+)\.tar\.gz
+"#
+        );
+    }
+
+    #[test]
+    fn test_rewrite_forge_urls() {
+        let mut wf: WatchFile = r#"version=4
+http://foo.googlecode.com/files/ foo-(\d\S+)\.tar\.gz
+http://githubredir.debian.net/github/foo/bar .*/v?(\d\S+)\.tar\.gz
+https://example.com/ foo-(\d\S+)\.tar\.gz
+"#
+        .parse()
+        .unwrap();
+        let changed: Vec<bool> = wf.entries_mut().map(rewrite_forge_urls).collect();
+        assert_eq!(changed, vec![true, true, false]);
+        let urls: Vec<String> = wf.entries().map(|e| e.url().unwrap()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://storage.googleapis.com/google-code-archive-downloads/?prefix=v2/code.google.com/foo/",
+                "https://github.com/foo/bar/tags",
+                "https://example.com/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expected_orig_tarballs() {
+        let wf: WatchFile = r#"version=4
+opts="repack,repacksuffix=+ds,compression=bzip2" https://example.com/ foo-(\d\S+)\.tar\.gz
+opts="component=bar" https://example.com/ bar-(\d\S+)\.tar\.gz
+opts="component=baz,repack" https://example.com/ baz-(\d\S+)\.zip
+"#
+        .parse()
+        .unwrap();
+        let tarballs = expected_orig_tarballs(&wf, "foo", "1.0");
+        assert_eq!(
+            tarballs.iter().map(|t| t.filename()).collect::<Vec<_>>(),
+            vec![
+                Some("foo_1.0+ds.orig.tar.bz2".to_string()),
+                None,
+                Some("foo_1.0.orig-baz.tar.xz".to_string()),
+            ]
+        );
+        assert!(tarballs[1].matches("foo_1.0.orig-bar.tar.gz"));
+        assert!(!tarballs[1].matches("foo_1.0.orig-bar.tar.gz.asc"));
+        assert!(!tarballs[0].matches("foo_1.0+ds.orig.tar.gz"));
+        assert_eq!(
+            expected_orig_tarballs(&wf, "foo", "1.0+ds")[0].filename(),
+            Some("foo_1.0+ds.orig.tar.bz2".to_string())
+        );
+
+        let mut wf = WatchFile::new(4);
+        wf.add_git_tags_entry("https://example.com/foo.git", None);
+        assert_eq!(
+            expected_orig_tarballs(&wf, "foo", "1.0")[0].filename(),
+            Some("foo_1.0.orig.tar.xz".to_string())
+        );
+    }
+}
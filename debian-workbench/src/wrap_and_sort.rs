@@ -0,0 +1,458 @@
+//! Reimplementation of wrap-and-sort(1) from devscripts.
+//!
+//! Relationship fields in control files are sorted and wrapped, and the
+//! lines of debhelper list files such as `debian/*.install` are sorted.
+use crate::editor::{Editor, EditorError, FsEditor};
+use std::path::{Path, PathBuf};
+
+/// Fields in control files that hold comma-separated lists to sort.
+pub const CONTROL_LIST_FIELDS: &[&str] = &[
+    "Breaks",
+    "Build-Conflicts",
+    "Build-Conflicts-Arch",
+    "Build-Conflicts-Indep",
+    "Build-Depends",
+    "Build-Depends-Arch",
+    "Build-Depends-Indep",
+    "Built-Using",
+    "Conflicts",
+    "Depends",
+    "Enhances",
+    "Pre-Depends",
+    "Provides",
+    "Recommends",
+    "Replaces",
+    "Suggests",
+    "Xb-Npp-MimeType",
+];
+
+/// Control files that are formatted, relative to the root of the source tree.
+pub const CONTROL_FILES: &[&str] = &[
+    "debian/control",
+    "debian/control.in",
+    "debian/tests/control",
+];
+
+/// Names of debhelper files whose lines are sorted, either on their own or
+/// as the suffix of a per-package file (e.g. `debian/foo.install`).
+pub const LIST_FILE_NAMES: &[&str] = &[
+    "clean", "dirs", "docs", "examples", "info", "install", "links", "manpages",
+];
+
+/// Default maximum line length before a list is wrapped.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 79;
+
+/// Formatting options, matching the flags of wrap-and-sort.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Wrap lists even if they fit on a single line (`-a`)
+    pub wrap_always: bool,
+    /// Start wrapped lists on the line after the field name, with an indentation of one space (`-s`)
+    pub short_indent: bool,
+    /// Add a trailing comma to wrapped lists (`-t`)
+    pub trailing_comma: bool,
+    /// Keep the first item of each list in place when sorting (`-k`)
+    pub keep_first: bool,
+    /// Maximum line length before a list is wrapped
+    pub max_line_length: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            wrap_always: false,
+            short_indent: false,
+            trailing_comma: false,
+            keep_first: false,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+        }
+    }
+}
+
+/// Sort items, dropping duplicates.
+///
+/// Items starting with a lowercase letter or digit come first; other items,
+/// such as substitution variables, are sorted after them.
+fn sort_list(items: Vec<String>, keep_first: bool) -> Vec<String> {
+    let mut ret: Vec<String> = vec![];
+    let mut rest = items.into_iter();
+    if keep_first {
+        ret.extend(rest.next());
+    }
+    let mut sorted: Vec<String> = vec![];
+    for item in rest {
+        if !ret.contains(&item) && !sorted.contains(&item) {
+            sorted.push(item);
+        }
+    }
+    sorted.sort_by_key(|item| {
+        let regular = item
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+        (!regular, item.clone())
+    });
+    ret.extend(sorted);
+    ret
+}
+
+/// Split a comma-separated field value into its items, normalizing whitespace.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Format a field holding a comma-separated list.
+///
+/// # Arguments
+/// * `name` - Name of the field
+/// * `items` - Items of the list
+/// * `options` - Formatting options
+///
+/// # Returns
+/// The text of the field, including its trailing newline
+pub fn format_list_field(name: &str, items: &[String], options: &Options) -> String {
+    let one_line = items.join(", ");
+    if !options.wrap_always && name.len() + 2 + one_line.len() <= options.max_line_length {
+        return format!("{}: {}\n", name, one_line);
+    }
+    let (separator, indent) = if options.short_indent {
+        ("\n ", " ".to_string())
+    } else {
+        (" ", " ".repeat(name.len() + 2))
+    };
+    let mut ret = format!(
+        "{}:{}{}",
+        name,
+        separator,
+        items.join(&format!(",\n{}", indent))
+    );
+    if options.trailing_comma {
+        ret.push(',');
+    }
+    ret.push('\n');
+    ret
+}
+
+/// Compute the new text of a field, if it is one that wrap-and-sort formats.
+fn format_field(name: &str, value: &str, options: &Options) -> Option<String> {
+    if CONTROL_LIST_FIELDS
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(name))
+    {
+        let items = sort_list(split_list(value), options.keep_first);
+        (!items.is_empty()).then(|| format_list_field(name, &items, options))
+    } else if name.eq_ignore_ascii_case("Uploaders") {
+        let items = split_list(value);
+        (!items.is_empty()).then(|| format_list_field(name, &items, options))
+    } else if name.eq_ignore_ascii_case("Architecture") {
+        let items = sort_list(
+            value.split_whitespace().map(|a| a.to_string()).collect(),
+            false,
+        );
+        (!items.is_empty()).then(|| format!("{}: {}\n", name, items.join(" ")))
+    } else {
+        None
+    }
+}
+
+/// Wrap and sort the fields of a deb822 file.
+///
+/// Only the fields that wrap-and-sort formats are touched; fields
+/// containing comments are left as they are.
+///
+/// # Arguments
+/// * `deb822` - The file to format
+/// * `options` - Formatting options
+pub fn wrap_and_sort_deb822(
+    deb822: &deb822_lossless::Deb822,
+    options: &Options,
+) -> deb822_lossless::Deb822 {
+    let mut text = deb822.to_string();
+    let mut replacements = vec![];
+    for paragraph in deb822.paragraphs() {
+        for entry in paragraph.entries() {
+            let Some(name) = entry.key() else {
+                continue;
+            };
+            let orig = entry.to_string();
+            if orig.lines().any(|l| l.starts_with('#')) {
+                continue;
+            }
+            let Some(formatted) = format_field(&name, &entry.value(), options) else {
+                continue;
+            };
+            if formatted != orig {
+                replacements.push((entry.text_range(), formatted));
+            }
+        }
+    }
+    for (range, formatted) in replacements.into_iter().rev() {
+        text.replace_range(
+            usize::from(range.start())..usize::from(range.end()),
+            &formatted,
+        );
+    }
+    deb822_lossless::Deb822::from_str_relaxed(&text).0
+}
+
+/// Sort the lines of a debhelper list file.
+///
+/// A leading `#!` line (e.g. for dh-exec) is kept in place; duplicate and
+/// empty lines are dropped.
+///
+/// # Returns
+/// The sorted text, or `None` if the file contains comments and can not be
+/// sorted without losing their context
+pub fn wrap_and_sort_list(text: &str) -> Option<String> {
+    let mut lines = text.lines().peekable();
+    let shebang = lines.next_if(|l| l.starts_with("#!"));
+    let mut items = vec![];
+    for line in lines {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+        if !line.is_empty() {
+            items.push(line.to_string());
+        }
+    }
+    let mut ret = String::new();
+    for line in shebang
+        .into_iter()
+        .map(|l| l.to_string())
+        .chain(sort_list(items, false))
+    {
+        ret.push_str(&line);
+        ret.push('\n');
+    }
+    Some(ret)
+}
+
+/// Find the files in a source tree that wrap-and-sort formats.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+///
+/// # Returns
+/// Paths relative to `base`, sorted
+pub fn find_files(base: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut ret: Vec<PathBuf> = CONTROL_FILES
+        .iter()
+        .map(PathBuf::from)
+        .filter(|p| base.join(p).is_file())
+        .collect();
+    let debian = base.join("debian");
+    if !debian.is_dir() {
+        return Ok(ret);
+    }
+    for entry in std::fs::read_dir(&debian)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let kind = name.rsplit('.').next().unwrap_or(&name);
+        if LIST_FILE_NAMES.contains(&kind) {
+            ret.push(Path::new("debian").join(name));
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Error wrapping and sorting the files in a source tree.
+#[derive(Debug)]
+pub enum WrapAndSortError {
+    /// A control file could not be edited
+    Editor(Box<EditorError>),
+    /// I/O error
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for WrapAndSortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WrapAndSortError::Editor(e) => write!(f, "{}", e),
+            WrapAndSortError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for WrapAndSortError {}
+
+impl From<EditorError> for WrapAndSortError {
+    fn from(e: EditorError) -> Self {
+        WrapAndSortError::Editor(Box::new(e))
+    }
+}
+
+impl From<std::io::Error> for WrapAndSortError {
+    fn from(e: std::io::Error) -> Self {
+        WrapAndSortError::Io(e)
+    }
+}
+
+/// Wrap and sort the control and list files in a source tree.
+///
+/// # Arguments
+/// * `base` - Root of the source tree
+/// * `options` - Formatting options
+/// * `dry_run` - Only report the files that would be changed
+///
+/// # Returns
+/// The files that were (or would be) changed, relative to `base`
+pub fn wrap_and_sort_tree(
+    base: &Path,
+    options: &Options,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>, WrapAndSortError> {
+    let mut changed = vec![];
+    for path in find_files(base)? {
+        if CONTROL_FILES.iter().any(|p| Path::new(p) == path) {
+            let mut editor =
+                FsEditor::<deb822_lossless::Deb822>::new(&base.join(&path), true, true, false)?;
+            let formatted = wrap_and_sort_deb822(&editor, options);
+            *editor = formatted;
+            if !editor.has_changed() {
+                continue;
+            }
+            if !dry_run {
+                editor.commit()?;
+            }
+        } else {
+            let text = std::fs::read_to_string(base.join(&path))?;
+            match wrap_and_sort_list(&text) {
+                Some(sorted) if sorted != text => {
+                    if !dry_run {
+                        std::fs::write(base.join(&path), sorted)?;
+                    }
+                }
+                _ => continue,
+            }
+        }
+        changed.push(path);
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_and_sort_deb822() {
+        let deb822: deb822_lossless::Deb822 = "Source: foo
+# Build dependencies
+Build-Depends: debhelper-compat (= 13), ${misc:Depends}, dh-python,  python3-all
+Uploaders: B <b@example.com>, A <a@example.com>
+
+Package: foo
+Architecture: i386 amd64
+Depends: ${misc:Depends}, libfoo1, libbar1
+Description: foo
+ Longer description.
+"
+        .parse()
+        .unwrap();
+        assert_eq!(
+            "Source: foo
+# Build dependencies
+Build-Depends: debhelper-compat (= 13), dh-python, python3-all, ${misc:Depends}
+Uploaders: B <b@example.com>, A <a@example.com>
+
+Package: foo
+Architecture: amd64 i386
+Depends: libbar1, libfoo1, ${misc:Depends}
+Description: foo
+ Longer description.
+",
+            wrap_and_sort_deb822(&deb822, &Options::default()).to_string()
+        );
+        let options = Options {
+            wrap_always: true,
+            trailing_comma: true,
+            keep_first: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            "Source: foo
+# Build dependencies
+Build-Depends: debhelper-compat (= 13),
+               dh-python,
+               python3-all,
+               ${misc:Depends},
+Uploaders: B <b@example.com>,
+           A <a@example.com>,
+
+Package: foo
+Architecture: amd64 i386
+Depends: ${misc:Depends},
+         libbar1,
+         libfoo1,
+Description: foo
+ Longer description.
+",
+            wrap_and_sort_deb822(&deb822, &options).to_string()
+        );
+        let options = Options {
+            wrap_always: true,
+            short_indent: true,
+            ..Default::default()
+        };
+        let formatted = wrap_and_sort_deb822(&deb822, &options).to_string();
+        assert!(formatted.contains("\nDepends:\n libbar1,\n libfoo1,\n ${misc:Depends}\n"));
+    }
+
+    #[test]
+    fn test_wrap_and_sort_list() {
+        assert_eq!(
+            Some("#!/usr/bin/dh-exec\nusr/bin\nusr/lib/${DEB_HOST_MULTIARCH}\n".to_string()),
+            wrap_and_sort_list(
+                "#!/usr/bin/dh-exec\nusr/lib/${DEB_HOST_MULTIARCH}\n\nusr/bin  \nusr/bin\n"
+            )
+        );
+        assert_eq!(None, wrap_and_sort_list("# Docs\nREADME\nNEWS\n"));
+    }
+
+    #[test]
+    fn test_wrap_and_sort_tree() {
+        let td = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(td.path().join("debian")).unwrap();
+        std::fs::write(
+            td.path().join("debian/control"),
+            "Source: foo\nBuild-Depends: foo, bar\n\nPackage: foo\nArchitecture: any\n",
+        )
+        .unwrap();
+        std::fs::write(td.path().join("debian/foo.docs"), "README\nNEWS\n").unwrap();
+        std::fs::write(td.path().join("debian/foo.links"), "a b\n").unwrap();
+        let options = Options::default();
+        assert_eq!(
+            vec![
+                PathBuf::from("debian/control"),
+                PathBuf::from("debian/foo.docs")
+            ],
+            wrap_and_sort_tree(td.path(), &options, true).unwrap()
+        );
+        assert_eq!(
+            "README\nNEWS\n",
+            std::fs::read_to_string(td.path().join("debian/foo.docs")).unwrap()
+        );
+        assert_eq!(
+            2,
+            wrap_and_sort_tree(td.path(), &options, false)
+                .unwrap()
+                .len()
+        );
+        assert_eq!(
+            "Source: foo\nBuild-Depends: bar, foo\n\nPackage: foo\nArchitecture: any\n",
+            std::fs::read_to_string(td.path().join("debian/control")).unwrap()
+        );
+        assert!(wrap_and_sort_tree(td.path(), &options, false)
+            .unwrap()
+            .is_empty());
+    }
+}